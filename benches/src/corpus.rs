@@ -0,0 +1,100 @@
+// Synthetic corpora for the benchmarks.
+// Made from a fixed seed, so every run benchmarks the same input for a given size.
+
+/// Words for the text, with some repeats so grep and uniq have something to find
+const WORDS: &[&str] = &[
+    "the", "quick", "brown", "fox", "jumps", "over", "lazy", "dog", "lorem", "ipsum", "dolor", "sit", "amet",
+    "consectetur", "adipiscing", "elit", "café", "naïve", "the", "a", "of", "and", "to", "in",
+];
+
+/// A xorshift generator, which is plenty random for making up text
+struct Rng(u64);
+
+impl Rng {
+    fn new() -> Rng {
+        Rng(0x2545_F491_4F6C_DD1D)
+    }
+
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    /// A number below `n`
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+}
+
+/// Lines of words, about `size` bytes of them, with runs of repeated lines
+pub fn text(size: usize) -> String {
+    let mut rng = Rng::new();
+    let mut text = String::with_capacity(size + 100);
+    while text.len() < size {
+        let mut line = String::new();
+        for i in 0..1 + rng.below(12) {
+            if i > 0 {
+                line.push(' ');
+            }
+            line.push_str(WORDS[rng.below(WORDS.len())]);
+        }
+        line.push('\n');
+        for _ in 0..1 + rng.below(2) {
+            text.push_str(&line);
+        }
+    }
+    text
+}
+
+/// `text` with about one line in fifty changed, for diffing against it
+pub fn edited(text: &str) -> String {
+    let mut rng = Rng::new();
+    let mut edited = String::with_capacity(text.len());
+    for line in text.lines() {
+        match rng.below(50) {
+            0 => {}, // Deleted
+            1 => {
+                edited.push_str("an added line\n");
+                edited.push_str(line);
+                edited.push('\n');
+            },
+            _ => {
+                edited.push_str(line);
+                edited.push('\n');
+            },
+        }
+    }
+    edited
+}
+
+/// A JSON document of about `size` bytes, an array of records with some nesting to them
+pub fn json(size: usize) -> String {
+    let mut rng = Rng::new();
+    let mut json = String::with_capacity(size + 200);
+    json.push_str("[\n");
+    let mut id = 0;
+    while json.len() < size {
+        if id > 0 {
+            json.push_str(",\n");
+        }
+        let name = WORDS[rng.below(WORDS.len())];
+        let tags: Vec<String> = (0..rng.below(4)).map(|_| format!("\"{}\"", WORDS[rng.below(WORDS.len())])).collect();
+        json.push_str(&format!(
+            "  {{\"id\": {}, \"name\": \"{} \\\"{}\\\"\", \"score\": {}.{}e-2, \"active\": {}, \"parent\": null, \"tags\": [{}], \"position\": {{\"x\": -{}, \"y\": {}}}}}",
+            id,
+            name,
+            WORDS[rng.below(WORDS.len())],
+            rng.below(10000),
+            rng.below(100),
+            rng.below(2) == 0,
+            tags.join(", "),
+            rng.below(1000),
+            rng.below(1000),
+        ));
+        id += 1;
+    }
+    json.push_str("\n]\n");
+    json
+}
@@ -0,0 +1,176 @@
+// Benchmarks of the tools against the system's own.
+// Makes up corpora of the size asked for, times each tool and its system counterpart on them,
+// and prints the results as a markdown table, so how the rewrites compare can be checked by
+// anyone with the same machine. Run it in release mode, which is what it builds the tools with:
+//
+//   cargo run --release -p cc-benches -- --size 64 > results.md
+mod corpus;
+
+use anyhow::{Context, Result};
+use clap::Parser;
+use std::ffi::OsString;
+use std::fs;
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, ExitCode, Stdio};
+use std::time::{Duration, Instant};
+
+/// A tool and the system command it stands in for, run on the same input. In their arguments
+/// TEXT, EDITED, and JSON are the paths of the corpora.
+struct Bench {
+    name: &'static str,
+    /// One of the corpora, for the table
+    input: &'static str,
+    ours: &'static [&'static str],
+    system: &'static [&'static str],
+}
+
+const BENCHES: &[Bench] = &[
+    Bench { name: "wc", input: "TEXT", ours: &["ccwc", "TEXT"], system: &["wc", "TEXT"] },
+    Bench { name: "cat", input: "TEXT", ours: &["cccat", "TEXT"], system: &["cat", "TEXT"] },
+    Bench { name: "cut", input: "TEXT", ours: &["cccut", "-d", " ", "-f", "2", "TEXT"], system: &["cut", "-d", " ", "-f", "2", "TEXT"] },
+    Bench { name: "grep", input: "TEXT", ours: &["ccgrep", "fox|dog", "TEXT"], system: &["grep", "-E", "fox|dog", "TEXT"] },
+    Bench { name: "sort", input: "TEXT", ours: &["ccsort", "TEXT"], system: &["sort", "TEXT"] },
+    Bench { name: "uniq", input: "TEXT", ours: &["ccuniq", "TEXT"], system: &["uniq", "TEXT"] },
+    Bench { name: "base64", input: "TEXT", ours: &["ccbase64", "TEXT"], system: &["base64", "TEXT"] },
+    Bench { name: "xxd", input: "TEXT", ours: &["ccxxd", "TEXT"], system: &["xxd", "TEXT"] },
+    Bench { name: "diff", input: "TEXT", ours: &["ccdiff", "TEXT", "EDITED"], system: &["diff", "TEXT", "EDITED"] },
+    Bench { name: "jsonparser", input: "JSON", ours: &["cc2jsonparser", "JSON"], system: &["jq", "empty", "JSON"] },
+];
+
+/// cc-benches - time the tools against the system's own
+#[derive(Parser)]
+struct Cli {
+    /// How big to make each corpus, in megabytes
+    #[arg(short, long, value_name = "MEGABYTES", default_value_t = 16)]
+    size: usize,
+
+    /// How many times to run each command, giving the median
+    #[arg(short, long, default_value_t = 5)]
+    runs: usize,
+
+    /// Where to write the corpora, instead of a temporary directory
+    #[arg(long, value_name = "DIR")]
+    dir: Option<PathBuf>,
+
+    /// The benchmarks to run, by the name of the tool, or all of them if there are none
+    names: Vec<String>,
+}
+
+fn main() -> ExitCode {
+    let args = Cli::parse_from(std::env::args_os());
+
+    match run(args) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("Error: {:?}", e);
+            ExitCode::FAILURE
+        },
+    }
+}
+
+fn run(args: Cli) -> Result<()> {
+    if args.runs == 0 {
+        anyhow::bail!("there has to be at least one run");
+    }
+    if let Some(unknown) = args.names.iter().find(|name| !BENCHES.iter().any(|b| &b.name == name)) {
+        anyhow::bail!("there's no benchmark for {}", unknown);
+    }
+    let benches: Vec<&Bench> = BENCHES.iter().filter(|b| args.names.is_empty() || args.names.iter().any(|n| n == b.name)).collect();
+
+    let tools = build_tools(&benches)?;
+    let keep_corpora = args.dir.is_some();
+    let dir = args.dir.unwrap_or_else(|| std::env::temp_dir().join(format!("cc-benches-{}", std::process::id())));
+    fs::create_dir_all(&dir).with_context(|| format!("could not create {}", dir.display()))?;
+    eprintln!("Writing {} MB corpora to {}", args.size, dir.display());
+    let size = args.size * 1024 * 1024;
+    let text = corpus::text(size);
+    let corpora = [
+        ("TEXT", dir.join("text.txt"), text.clone()),
+        ("EDITED", dir.join("edited.txt"), corpus::edited(&text)),
+        ("JSON", dir.join("data.json"), corpus::json(size)),
+    ];
+    for (_, path, contents) in &corpora {
+        fs::write(path, contents).with_context(|| format!("could not write {}", path.display()))?;
+    }
+    let arguments = |words: &[&str]| -> Vec<OsString> {
+        words.iter().map(|&word| match corpora.iter().find(|(name, ..)| *name == word) {
+            Some((_, path, _)) => path.clone().into_os_string(),
+            None => word.into(),
+        }).collect()
+    };
+
+    let mut out = std::io::stdout().lock();
+    writeln!(out, "| Tool | Input | Ours | System | Ours / system |")?;
+    writeln!(out, "|------|-------|-----:|-------:|--------------:|")?;
+    for bench in benches {
+        eprintln!("Running {}", bench.name);
+        let input = corpora.iter().find(|(name, ..)| *name == bench.input).unwrap();
+        let ours = median(&tools.join(format!("{}{}", bench.ours[0], std::env::consts::EXE_SUFFIX)), &arguments(&bench.ours[1..]), args.runs)?
+            .with_context(|| format!("could not run {}", bench.ours[0]))?;
+        let system = median(Path::new(bench.system[0]), &arguments(&bench.system[1..]), args.runs)?;
+        let (system_cell, ratio_cell) = match system {
+            Some(system) => (format_duration(system), format!("{:.2}x", ours.as_secs_f64() / system.as_secs_f64())),
+            None => (format!("no {}", bench.system[0]), "-".to_string()),
+        };
+        writeln!(
+            out,
+            "| {} | {:.1} MB {} | {} | {} | {} |",
+            bench.name,
+            input.2.len() as f64 / (1024.0 * 1024.0),
+            input.1.file_name().unwrap().to_string_lossy(),
+            format_duration(ours),
+            system_cell,
+            ratio_cell,
+        )?;
+    }
+
+    if !keep_corpora {
+        let _ = fs::remove_dir_all(&dir);
+    }
+    Ok(())
+}
+
+/// Build the tools the benchmarks run, in the same profile as this, giving the directory they're in
+fn build_tools(benches: &[&Bench]) -> Result<PathBuf> {
+    let mut cargo = Command::new(env!("CARGO"));
+    cargo.args(["build", "--quiet"]);
+    for bench in benches {
+        cargo.args(["--package", bench.ours[0]]);
+    }
+    if !cfg!(debug_assertions) {
+        cargo.arg("--release");
+    }
+    if !cargo.status().context("could not run cargo")?.success() {
+        anyhow::bail!("could not build the tools");
+    }
+    // Cargo puts them alongside this
+    let exe = std::env::current_exe()?;
+    Ok(exe.parent().context("could not find the tools")?.to_path_buf())
+}
+
+/// The median time of running a command, after a run to warm the caches, or None if the command
+/// isn't there to run
+fn median(program: &Path, args: &[OsString], runs: usize) -> Result<Option<Duration>> {
+    let mut times = Vec::with_capacity(runs);
+    for _ in 0..=runs {
+        let start = Instant::now();
+        let status = Command::new(program).args(args).stdin(Stdio::null()).stdout(Stdio::null()).stderr(Stdio::null()).status();
+        match status {
+            // Exit codes don't tell much here, with grep and diff giving 1 for an answer
+            Ok(_) => times.push(start.elapsed()),
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e).with_context(|| format!("could not run {}", program.display())),
+        }
+    }
+    times.remove(0); // The warm up
+    times.sort();
+    Ok(times.get(times.len() / 2).copied())
+}
+
+fn format_duration(duration: Duration) -> String {
+    match duration.as_secs_f64() {
+        secs if secs >= 1.0 => format!("{:.2} s", secs),
+        secs => format!("{:.1} ms", secs * 1000.0),
+    }
+}
@@ -0,0 +1,17 @@
+// Record which commit the tools were built from, for --version
+use std::process::Command;
+
+fn git(args: &[&str]) -> Option<String> {
+    let output = Command::new("git").args(args).output().ok()?;
+    let text = String::from_utf8(output.stdout).ok()?;
+    Some(text.trim().to_string()).filter(|text| output.status.success() && !text.is_empty())
+}
+
+fn main() {
+    let commit = git(&["rev-parse", "--short", "HEAD"]).unwrap_or_else(|| "unknown commit".to_string());
+    let date = git(&["log", "-1", "--format=%cs"]).map(|date| format!(" {}", date)).unwrap_or_default();
+    println!("cargo:rustc-env=CC_BUILD_COMMIT={}{}", commit, date);
+    // Moving HEAD, or committing to the branch it's on
+    println!("cargo:rerun-if-changed=../.git/HEAD");
+    println!("cargo:rerun-if-changed=../.git/refs/heads");
+}
@@ -0,0 +1,285 @@
+// Shell completions and man pages, made from the tool's clap command.
+// Only the options and their values are completed, with file names for everything else, which
+// covers what the tools take.
+use clap::{Arg, ArgAction, Command};
+use std::fmt::Write;
+
+/// The shells completions can be printed for
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq)]
+pub enum Shell {
+    /// A script to source from bash
+    Bash,
+    /// A script for fish's completions directory
+    Fish,
+    /// A script for a directory on zsh's fpath, or to source
+    Zsh,
+}
+
+/// What the completions and man subcommands print
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Generated {
+    Completions(Shell),
+    /// A man page, in roff
+    Man,
+}
+
+/// Generate completions or a man page for the command
+pub fn generate(what: Generated, command: &Command) -> String {
+    // Building it fills in what clap works out for itself, like --help and how many values
+    // each argument takes
+    let mut command = command.clone();
+    command.build();
+    match what {
+        Generated::Completions(Shell::Bash) => bash(&command),
+        Generated::Completions(Shell::Fish) => fish(&command),
+        Generated::Completions(Shell::Zsh) => zsh(&command),
+        Generated::Man => man(&command),
+    }
+}
+
+/// The options a user would type, leaving out the hidden ones
+fn options(command: &Command) -> impl Iterator<Item = &Arg> {
+    command.get_arguments().filter(|arg| !arg.is_positional() && !arg.is_hide_set())
+}
+
+/// The first line of an argument's help
+fn summary(arg: &Arg) -> String {
+    arg.get_help().map(|help| help.to_string().lines().next().unwrap_or("").to_string()).unwrap_or_default()
+}
+
+fn takes_value(arg: &Arg) -> bool {
+    arg.get_num_args().is_some_and(|n| n.takes_values())
+}
+
+fn bash(command: &Command) -> String {
+    let name = command.get_name();
+    let mut words = Vec::new();
+    let mut values = String::new();
+    for arg in options(command) {
+        let flags: Vec<String> = arg.get_short().map(|s| format!("-{}", s)).into_iter().chain(arg.get_long().map(|l| format!("--{}", l))).collect();
+        let possible: Vec<String> = arg.get_possible_values().iter().map(|v| v.get_name().to_string()).collect();
+        if takes_value(arg) && !possible.is_empty() {
+            let _ = writeln!(values, "        {})\n            COMPREPLY=($(compgen -W \"{}\" -- \"$cur\"))\n            return\n            ;;", flags.join("|"), possible.join(" "));
+        }
+        words.extend(flags);
+    }
+
+    let mut script = String::new();
+    let function = format!("_{}", name.replace('-', "_"));
+    let _ = writeln!(script, "{}() {{", function);
+    let _ = writeln!(script, "    local cur=\"${{COMP_WORDS[COMP_CWORD]}}\" prev=\"${{COMP_WORDS[COMP_CWORD-1]}}\"");
+    let _ = writeln!(script, "    case \"$prev\" in");
+    script.push_str(&values);
+    let _ = writeln!(script, "    esac");
+    let _ = writeln!(script, "    if [[ \"$cur\" == -* ]]; then");
+    let _ = writeln!(script, "        COMPREPLY=($(compgen -W \"{}\" -- \"$cur\"))", words.join(" "));
+    let _ = writeln!(script, "    else");
+    let _ = writeln!(script, "        COMPREPLY=($(compgen -f -- \"$cur\"))");
+    let _ = writeln!(script, "    fi");
+    let _ = writeln!(script, "}}");
+    let _ = writeln!(script, "complete -o filenames -F {} {}", function, name);
+    script
+}
+
+/// Quote for fish, where only `\` and `'` mean anything inside single quotes
+fn fish_quote(text: &str) -> String {
+    format!("'{}'", text.replace('\\', "\\\\").replace('\'', "\\'"))
+}
+
+fn fish(command: &Command) -> String {
+    let name = command.get_name();
+    let mut script = String::new();
+    for arg in options(command) {
+        let _ = write!(script, "complete -c {}", name);
+        if let Some(short) = arg.get_short() {
+            let _ = write!(script, " -s {}", short);
+        }
+        if let Some(long) = arg.get_long() {
+            let _ = write!(script, " -l {}", long);
+        }
+        if takes_value(arg) {
+            script.push_str(" -r");
+            let possible: Vec<String> = arg.get_possible_values().iter().map(|v| v.get_name().to_string()).collect();
+            if !possible.is_empty() {
+                let _ = write!(script, " -f -a {}", fish_quote(&possible.join(" ")));
+            }
+        }
+        let help = summary(arg);
+        if !help.is_empty() {
+            let _ = write!(script, " -d {}", fish_quote(&help));
+        }
+        script.push('\n');
+    }
+    script
+}
+
+/// Quote for zsh, ending the quotes around each `'` to put it in with a backslash
+fn zsh_quote(text: &str) -> String {
+    format!("'{}'", text.replace('\'', "'\\''"))
+}
+
+/// Escape what _arguments would take for the end of a description, or of a field of a value
+fn zsh_escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace(']', "\\]").replace(':', "\\:")
+}
+
+fn zsh(command: &Command) -> String {
+    let name = command.get_name();
+    let function = format!("_{}", name.replace('-', "_"));
+    let mut script = String::new();
+    let _ = writeln!(script, "#compdef {}\n", name);
+    let _ = writeln!(script, "{}() {{", function);
+    script.push_str("    _arguments -s");
+    for arg in options(command) {
+        let flags: Vec<String> = arg.get_short().map(|s| format!("-{}", s)).into_iter().chain(arg.get_long().map(|l| format!("--{}", l))).collect();
+        // Those that can be given more than once are starred, the rest can't be given again under
+        // either name
+        let repeats = matches!(arg.get_action(), ArgAction::Append | ArgAction::Count);
+        let exclusive = match repeats {
+            true => "*".to_string(),
+            false => format!("({})", flags.join(" ")),
+        };
+        let mut value = String::new();
+        if takes_value(arg) {
+            let name = arg.get_value_names().and_then(|names| names.first()).map_or(arg.get_id().to_string(), |name| name.to_string());
+            let possible: Vec<String> = arg.get_possible_values().iter().map(|v| v.get_name().to_string()).collect();
+            let action = match possible.is_empty() {
+                true => "_files".to_string(),
+                false => format!("({})", possible.join(" ")),
+            };
+            value = format!(":{}:{}", zsh_escape(&name), action);
+        }
+        for flag in &flags {
+            let spec = format!("{}{}[{}]{}", exclusive, flag, zsh_escape(&summary(arg)), value);
+            let _ = write!(script, " \\\n        {}", zsh_quote(&spec));
+        }
+    }
+    let _ = writeln!(script, " \\\n        '*:file:_files'");
+    let _ = writeln!(script, "}}\n");
+    // Sourced, rather than autoloaded from the fpath, it has to be registered
+    let _ = writeln!(script, "if [ \"$funcstack[1]\" = \"{}\" ]; then\n    {} \"$@\"\nelse\n    compdef {} {}\nfi", function, function, function, name);
+    script
+}
+
+/// Escape text for roff, where a leading `.` or `'` would start a request
+fn roff(text: &str) -> String {
+    let escaped = text.replace('\\', "\\e").replace('-', "\\-");
+    match escaped.chars().next() {
+        Some('.' | '\'') => format!("\\&{}", escaped),
+        _ => escaped,
+    }
+}
+
+fn man(command: &Command) -> String {
+    let name = command.get_name();
+    let mut command = command.clone();
+    let usage = command.render_usage().to_string();
+    let usage = usage.strip_prefix("Usage: ").unwrap_or(&usage).to_string();
+    let about = command.get_about().map(|about| about.to_string()).unwrap_or_default();
+    // The tools' about lines start with the name of what they stand in for
+    let about = about.split_once(" - ").map_or(about.as_str(), |(_, about)| about);
+
+    let mut page = String::new();
+    let _ = writeln!(page, ".TH {} 1 \"\" \"{} {}\"", name.to_uppercase(), name, command.get_version().unwrap_or(""));
+    let _ = writeln!(page, ".SH NAME\n{} \\- {}", name, roff(about));
+    let _ = writeln!(page, ".SH SYNOPSIS\n{}", roff(&usage));
+
+    let positionals: Vec<&Arg> = command.get_positionals().filter(|arg| !arg.is_hide_set()).collect();
+    if !positionals.is_empty() {
+        page.push_str(".SH ARGUMENTS\n");
+        for arg in positionals {
+            let value = arg.get_value_names().and_then(|names| names.first()).map_or(arg.get_id().to_string(), |name| name.to_string());
+            let _ = writeln!(page, ".TP\n\\fI{}\\fR\n{}", roff(&value), roff(&help(arg)));
+        }
+    }
+
+    page.push_str(".SH OPTIONS\n");
+    for arg in options(&command) {
+        let mut flags: Vec<String> = Vec::new();
+        if let Some(short) = arg.get_short() {
+            flags.push(format!("\\fB\\-{}\\fR", short));
+        }
+        if let Some(long) = arg.get_long() {
+            flags.push(format!("\\fB\\-\\-{}\\fR", roff(long)));
+        }
+        let mut line = flags.join(", ");
+        if takes_value(arg) {
+            let value = arg.get_value_names().and_then(|names| names.first()).map_or(arg.get_id().to_string(), |name| name.to_string());
+            let _ = write!(line, " \\fI{}\\fR", roff(&value));
+        }
+        let _ = writeln!(page, ".TP\n{}\n{}", line, roff(&help(arg)));
+    }
+    page
+}
+
+/// All of an argument's help, on one line for roff to fill
+fn help(arg: &Arg) -> String {
+    let help = arg.get_long_help().or(arg.get_help()).map(|help| help.to_string()).unwrap_or_default();
+    help.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+
+    /// tool - a made up tool
+    #[derive(Parser)]
+    #[command(name = "tool")]
+    struct Cli {
+        /// How loud to be
+        #[arg(short, long, value_name = "LEVEL", value_parser = ["low", "high"])]
+        volume: Option<String>,
+
+        /// Say it twice. Or more
+        #[arg(long)]
+        repeat: bool,
+
+        /// The file to read
+        file: String,
+    }
+
+    fn command() -> Command {
+        crate::tool_command::<Cli>("1.2.3")
+    }
+
+    #[test]
+    fn check_bash() {
+        let script = generate(Generated::Completions(Shell::Bash), &command());
+        assert!(script.starts_with("_tool() {\n"), "{}", script);
+        assert!(script.contains("        -v|--volume)\n            COMPREPLY=($(compgen -W \"low high\" -- \"$cur\"))"), "{}", script);
+        assert!(script.contains("compgen -W \"-v --volume --repeat -h --help -V --version\""), "{}", script);
+        assert!(!script.contains("completions"), "{}", script);
+        assert!(script.ends_with("complete -o filenames -F _tool tool\n"), "{}", script);
+    }
+
+    #[test]
+    fn check_fish() {
+        assert_eq!(generate(Generated::Completions(Shell::Fish), &command()), concat!(
+            "complete -c tool -s v -l volume -r -f -a 'low high' -d 'How loud to be'\n",
+            "complete -c tool -l repeat -d 'Say it twice. Or more'\n",
+            "complete -c tool -s h -l help -d 'Print help'\n",
+            "complete -c tool -s V -l version -d 'Print version'\n",
+        ));
+    }
+
+    #[test]
+    fn check_zsh() {
+        let script = generate(Generated::Completions(Shell::Zsh), &command());
+        assert!(script.starts_with("#compdef tool\n\n_tool() {\n    _arguments -s \\\n"), "{}", script);
+        assert!(script.contains("        '(-v --volume)-v[How loud to be]:LEVEL:(low high)' \\\n        '(-v --volume)--volume[How loud to be]:LEVEL:(low high)' \\\n"), "{}", script);
+        assert!(script.contains("        '(--repeat)--repeat[Say it twice. Or more]' \\\n"), "{}", script);
+        assert!(script.contains("        '*:file:_files'\n}\n"), "{}", script);
+        assert!(script.ends_with("    compdef _tool tool\nfi\n"), "{}", script);
+    }
+
+    #[test]
+    fn check_man() {
+        let page = generate(Generated::Man, &command());
+        assert!(page.starts_with(&format!(".TH TOOL 1 \"\" \"tool 1.2.3 ({})\"\n", crate::BUILD_COMMIT)), "{}", page);
+        assert!(page.contains(".SH NAME\ntool \\- a made up tool\n"), "{}", page);
+        assert!(page.contains(".SH SYNOPSIS\ntool [OPTIONS] <FILE>\n"), "{}", page);
+        assert!(page.contains(".TP\n\\fIFILE\\fR\nThe file to read\n"), "{}", page);
+        assert!(page.contains(".TP\n\\fB\\-v\\fR, \\fB\\-\\-volume\\fR \\fILEVEL\\fR\nHow loud to be\n"), "{}", page);
+    }
+}
@@ -0,0 +1,159 @@
+// Command line conventions shared by the workspace binaries.
+// Every tool parses its arguments through parse_from, which gives it a --version saying which
+// commit it was built from, and `completions <SHELL>` and `man` subcommands printing completions
+// for bash, fish, or zsh and a man page. A file named like one of them is still read after an
+// option, or as `./man`. Tools with output worth coloring or silencing flatten OutputArgs into
+// their own for the usual --color and --quiet. Tools whose results a script might want flatten
+// FormatArgs for --format json or sarif, and write a Report of the run, their results deriving
+// Serialize from the serde re-exported here if they've no need of serde otherwise.
+use cc_diagnostics::ColorChoice;
+use clap::{Arg, Command, Parser};
+use std::ffi::OsString;
+use std::io::{ErrorKind, Write};
+
+mod generate;
+mod report;
+
+pub use generate::{generate, Generated, Shell};
+pub use report::{Format, FormatArgs, Report, ReportError};
+pub use serde;
+
+/// Exit status for a write to a closed pipe, as if the process had been killed by SIGPIPE
+pub const BROKEN_PIPE_EXIT_CODE: u8 = 128 + 13;
+
+/// The commit the tools were built from, and its date when there's a repository to tell
+pub const BUILD_COMMIT: &str = env!("CC_BUILD_COMMIT");
+
+const COMPLETIONS: &str = "completions";
+const MAN: &str = "man";
+
+/// The output options a tool can flatten into its own with `#[command(flatten)]`
+#[derive(clap::Args, Clone, Debug, PartialEq)]
+pub struct OutputArgs {
+    /// When to color the output: auto, always, or never
+    #[arg(long, value_name = "WHEN", default_value = "auto")]
+    pub color: ColorChoice,
+
+    /// Print nothing but errors
+    #[arg(short, long)]
+    pub quiet: bool,
+}
+
+/// The tool's command with the shared conventions added, `version` being its package's version
+pub fn command<C: Parser>(version: &str) -> Command {
+    let command = tool_command::<C>(version);
+    let own = command.has_subcommands();
+    let command = command
+        .subcommand(
+            Command::new(COMPLETIONS)
+                .about("Print a script completing the tool's options for bash, fish, or zsh")
+                .arg(Arg::new("shell").value_name("SHELL").required(true).value_parser(clap::value_parser!(Shell))),
+        )
+        .subcommand(Command::new(MAN).about("Print the tool's man page, in roff"));
+    if own {
+        // It has them settled how it wants already
+        return command;
+    }
+    // Neither needs the arguments the tool needs to run, nor is read as one of them, and there's
+    // no help subcommand from clap to be another word its files couldn't be named
+    command.subcommand_negates_reqs(true).args_conflicts_with_subcommands(true).disable_help_subcommand(true)
+}
+
+/// The tool's own command with its version, which is what completions and man pages are made of
+fn tool_command<C: Parser>(version: &str) -> Command {
+    // Clap keeps the version for as long as the command lives, which is the whole run
+    let version: &'static str = Box::leak(format!("{} ({})", version, BUILD_COMMIT).into_boxed_str());
+    C::command().version(version)
+}
+
+/// Parse the command line the way `C::parse_from` does, `version` being the tool's package's
+/// version. Help, the version, and what the completions and man subcommands print are printed
+/// and the process ends there, like clap does for help.
+pub fn parse_from<C, I, T>(args: I, version: &str) -> C
+where
+    C: Parser,
+    I: IntoIterator<Item = T>,
+    T: Into<OsString> + Clone,
+{
+    let mut command = command::<C>(version);
+    let matches = command.clone().get_matches_from(args);
+    let generated = match matches.subcommand() {
+        Some((COMPLETIONS, matches)) => Some(Generated::Completions(*matches.get_one::<Shell>("shell").expect("clap checked the shell"))),
+        Some((MAN, _)) => Some(Generated::Man),
+        _ => None,
+    };
+    if let Some(what) = generated {
+        // Like help, there's no telling anyone if whatever it's piped to goes away
+        let _ = std::io::stdout().write_all(generate(what, &tool_command::<C>(version)).as_bytes());
+        std::process::exit(0);
+    }
+    C::from_arg_matches(&matches).unwrap_or_else(|e| e.format(&mut command).exit())
+}
+
+/// Whether writing failed because the reader on the other end went away, e.g. `ccwc | head`.
+/// That isn't worth reporting, the reader has all the output it wanted.
+pub fn is_broken_pipe(err: &anyhow::Error) -> bool {
+    err.chain()
+        .filter_map(|e| e.downcast_ref::<std::io::Error>())
+        .any(|e| e.kind() == ErrorKind::BrokenPipe)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Error;
+
+    /// tool - a made up tool
+    #[derive(Parser, Debug)]
+    #[command(name = "tool")]
+    struct Cli {
+        #[command(flatten)]
+        output: OutputArgs,
+
+        /// The file to read
+        file: String,
+    }
+
+    #[test]
+    fn check_parse_from() {
+        let args: Cli = parse_from(["tool", "-q", "--color", "never", "in.txt"], "1.2.3");
+        assert_eq!(args.output, OutputArgs { color: ColorChoice::Never, quiet: true });
+        assert_eq!(args.file, "in.txt");
+
+        let args: Cli = parse_from(["tool", "in.txt"], "1.2.3");
+        assert_eq!(args.output, OutputArgs { color: ColorChoice::Auto, quiet: false });
+    }
+
+    #[test]
+    fn check_command() {
+        let command = command::<Cli>("1.2.3");
+        assert_eq!(command.get_version(), Some(format!("1.2.3 ({})", BUILD_COMMIT).as_str()));
+        assert!(command.clone().try_get_matches_from(["tool", "in.txt", "man"]).is_err());
+        // After an option, it's the file
+        let matches = command.clone().try_get_matches_from(["tool", "-q", "man"]).unwrap();
+        assert_eq!((matches.subcommand_name(), matches.get_one::<String>("file").map(String::as_str)), (None, Some("man")));
+        assert!(command.clone().try_get_matches_from(["tool", "completions", "tcsh"]).is_err());
+        // Generating needs nothing else, not even the arguments the tool requires
+        let matches = command.clone().try_get_matches_from(["tool", "completions", "zsh"]).unwrap();
+        assert_eq!(matches.subcommand().map(|(name, matches)| (name, matches.get_one::<Shell>("shell"))), Some(("completions", Some(&Shell::Zsh))));
+        assert_eq!(command.try_get_matches_from(["tool", "man"]).unwrap().subcommand_name(), Some("man"));
+    }
+
+    #[test]
+    fn check_is_broken_pipe() {
+        let err = anyhow::Error::new(Error::from(ErrorKind::BrokenPipe));
+        assert!(is_broken_pipe(&err));
+    }
+
+    #[test]
+    fn check_is_broken_pipe_with_context() {
+        let err = anyhow::Error::new(Error::from(ErrorKind::BrokenPipe)).context("could not write");
+        assert!(is_broken_pipe(&err));
+    }
+
+    #[test]
+    fn check_other_errors_are_not_broken_pipe() {
+        let err = anyhow::Error::new(Error::from(ErrorKind::NotFound));
+        assert!(!is_broken_pipe(&err));
+    }
+}
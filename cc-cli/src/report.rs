@@ -0,0 +1,306 @@
+// Structured output shared by the workspace binaries.
+// With --format json a tool writes one report of its whole run instead of its usual text, in the
+// same shape whichever tool it is, so a script can run any of them the same way:
+//
+//   {"tool":"ccwc","version":"0.1.0","inputs":["a.txt"],"results":[{"input":"a.txt",...}],
+//    "errors":[{"input":"b.txt","message":"could not read `b.txt`: ..."}],"timing":{"elapsed_ms":0.42}}
+//
+// What's in each result is up to the tool, but every result and error says which input it's
+// about, with errors also saying where in it when the tool knows. With --format sarif the errors
+// are written as a SARIF log instead, for code scanning to show them where they are.
+// Reference:  https://docs.oasis-open.org/sarif/sarif/v2.1.0/sarif-v2.1.0.html
+use cc_diagnostics::{LineCol, Span};
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::io::{self, Write};
+use std::time::Instant;
+
+/// How a tool writes its results
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Format {
+    /// The tool's own text
+    #[default]
+    Text,
+    /// A Report
+    Json,
+    /// A Report's errors as a SARIF log
+    Sarif,
+}
+
+impl std::str::FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Format, String> {
+        match s {
+            "text" => Ok(Format::Text),
+            "json" => Ok(Format::Json),
+            "sarif" => Ok(Format::Sarif),
+            _ => Err(format!("unknown format `{}`, expected text, json, or sarif", s)),
+        }
+    }
+}
+
+/// The output format option a tool can flatten into its own with `#[command(flatten)]`
+#[derive(clap::Args, Clone, Debug, PartialEq)]
+pub struct FormatArgs {
+    /// How to write the results: text, json for a report in the same shape from every tool, or
+    /// sarif for its errors as a SARIF log
+    #[arg(long, value_name = "FORMAT", default_value = "text")]
+    pub format: Format,
+}
+
+impl FormatArgs {
+    pub fn is_json(&self) -> bool {
+        self.format == Format::Json
+    }
+
+    /// Whether to write a Report, as JSON or SARIF
+    pub fn is_report(&self) -> bool {
+        self.format != Format::Text
+    }
+}
+
+/// Everything a tool did in one run, `R` being what it found for each input
+#[derive(Debug, Serialize)]
+pub struct Report<R> {
+    pub tool: String,
+    pub version: String,
+    pub inputs: Vec<String>,
+    pub results: Vec<R>,
+    pub errors: Vec<ReportError>,
+    timing: Timing,
+    #[serde(skip)]
+    started: Instant,
+}
+
+#[derive(Debug, Default, Serialize)]
+struct Timing {
+    elapsed_ms: f64,
+}
+
+/// Something that went wrong, and the input it's about if there is one
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct ReportError {
+    pub input: Option<String>,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub column: Option<usize>,
+    /// The bytes of the input it's about, for SARIF
+    #[serde(skip)]
+    pub span: Option<Span>,
+    /// What kind of error it is, as a SARIF rule, e.g. `syntax`
+    #[serde(skip)]
+    pub rule: Option<String>,
+}
+
+impl ReportError {
+    pub fn new(input: Option<&str>, message: impl Into<String>) -> ReportError {
+        ReportError { input: input.map(str::to_string), message: message.into(), line: None, column: None, span: None, rule: None }
+    }
+
+    /// The error and what caused it
+    pub fn from_error(input: Option<&str>, err: &anyhow::Error) -> ReportError {
+        let mut message = err.to_string();
+        for cause in err.chain().skip(1).map(ToString::to_string) {
+            // Some errors already say what caused them
+            if !message.ends_with(&cause) {
+                message = format!("{}: {}", message, cause);
+            }
+        }
+        ReportError::new(input, message)
+    }
+
+    /// The error, pointing at `position` in the input
+    pub fn at(mut self, position: LineCol) -> ReportError {
+        self.line = Some(position.line);
+        self.column = Some(position.column);
+        self
+    }
+
+    /// The error, covering `span` of the input
+    pub fn with_span(mut self, span: Span) -> ReportError {
+        self.span = Some(span);
+        self
+    }
+
+    /// The error, as one of the `rule` kind
+    pub fn with_rule(mut self, rule: &str) -> ReportError {
+        self.rule = Some(rule.to_string());
+        self
+    }
+
+    /// The error as a SARIF result
+    fn to_sarif(&self) -> Value {
+        let mut result = json!({
+            "ruleId": self.rule.as_deref().unwrap_or(DEFAULT_RULE),
+            "level": "error",
+            "message": {"text": self.message},
+        });
+        // Labels like `<stdin>` aren't files, so there's nowhere to point
+        let Some(input) = self.input.as_deref().filter(|input| !input.starts_with('<')) else {
+            return result;
+        };
+        let mut region = serde_json::Map::new();
+        if let (Some(line), Some(column)) = (self.line, self.column) {
+            region.insert("startLine".to_string(), line.into());
+            region.insert("startColumn".to_string(), column.into());
+        }
+        if let Some(span) = self.span {
+            region.insert("byteOffset".to_string(), span.start.into());
+            region.insert("byteLength".to_string(), (span.end - span.start).into());
+        }
+        let mut location = json!({"artifactLocation": {"uri": to_uri(input)}});
+        if !region.is_empty() {
+            location["region"] = Value::Object(region);
+        }
+        result["locations"] = json!([{"physicalLocation": location}]);
+        result
+    }
+}
+
+/// The SARIF rule for errors that don't say what kind they are
+const DEFAULT_RULE: &str = "error";
+
+/// A path as a relative or file URI, escaping what would otherwise end or change it
+fn to_uri(path: &str) -> String {
+    let path = path.replace('\\', "/");
+    let mut uri = String::new();
+    if path.starts_with('/') {
+        uri.push_str("file://");
+    }
+    for byte in path.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'/' | b'-' | b'_' | b'.' | b'~' => uri.push(byte as char),
+            _ => uri.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    uri
+}
+
+impl<R: Serialize> Report<R> {
+    /// Start timing the run of `tool`, `version` being its package's version
+    pub fn start(tool: &str, version: &str) -> Report<R> {
+        Report {
+            tool: tool.to_string(),
+            version: version.to_string(),
+            inputs: Vec::new(),
+            results: Vec::new(),
+            errors: Vec::new(),
+            timing: Timing::default(),
+            started: Instant::now(),
+        }
+    }
+
+    /// Write the report as one line of JSON, timing the run up to now
+    pub fn write(mut self, out: &mut impl Write) -> io::Result<()> {
+        self.timing.elapsed_ms = self.started.elapsed().as_secs_f64() * 1000.0;
+        serde_json::to_writer(&mut *out, &self)?;
+        out.write_all(b"\n")
+    }
+
+    /// Write the report in `format`, as JSON unless it's SARIF
+    pub fn write_as(self, format: Format, out: &mut impl Write) -> io::Result<()> {
+        match format {
+            Format::Sarif => self.write_sarif(out),
+            _ => self.write(out),
+        }
+    }
+
+    /// Write the report's errors as a SARIF log of one run, each error a result against the rule
+    /// it says it breaks. Columns are counted in characters, the way LineCol's are.
+    pub fn write_sarif(self, out: &mut impl Write) -> io::Result<()> {
+        let mut rules: Vec<&str> = self.errors.iter().map(|e| e.rule.as_deref().unwrap_or(DEFAULT_RULE)).collect();
+        rules.sort_unstable();
+        rules.dedup();
+        let log = json!({
+            "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+            "version": "2.1.0",
+            "runs": [{
+                "tool": {"driver": {
+                    "name": self.tool,
+                    "version": self.version,
+                    "rules": rules.iter().map(|rule| json!({"id": rule})).collect::<Vec<_>>(),
+                }},
+                "columnKind": "unicodeCodePoints",
+                "results": self.errors.iter().map(ReportError::to_sarif).collect::<Vec<_>>(),
+            }],
+        });
+        serde_json::to_writer(&mut *out, &log)?;
+        out.write_all(b"\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Serialize)]
+    struct Checked {
+        input: String,
+        ok: bool,
+    }
+
+    #[test]
+    fn check_write_report() {
+        let mut report = Report::start("tool", "1.2.3");
+        report.inputs = vec!["a.txt".to_string(), "b.txt".to_string()];
+        report.results.push(Checked { input: "a.txt".to_string(), ok: true });
+        report.errors.push(ReportError::new(Some("b.txt"), "no good").at(LineCol { line: 2, column: 5 }));
+        report.errors.push(ReportError::new(None, "gave up"));
+
+        let mut out = Vec::new();
+        report.write(&mut out).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        let (written, timing) = out.split_once(",\"timing\":").unwrap();
+        assert_eq!(written, concat!(
+            r#"{"tool":"tool","version":"1.2.3","inputs":["a.txt","b.txt"],"results":[{"input":"a.txt","ok":true}],"#,
+            r#""errors":[{"input":"b.txt","message":"no good","line":2,"column":5},{"input":null,"message":"gave up"}]"#,
+        ));
+        assert!(timing.starts_with("{\"elapsed_ms\":") && timing.ends_with("}}\n"));
+    }
+
+    #[test]
+    fn check_write_sarif() {
+        let mut report: Report<Checked> = Report::start("tool", "1.2.3");
+        let error = ReportError::new(Some("dir/b c.json"), "no good").at(LineCol { line: 2, column: 5 }).with_span(Span::new(9, 11));
+        report.errors.push(error.with_rule("syntax"));
+        report.errors.push(ReportError::new(Some("<stdin>"), "gave up"));
+
+        let mut out = Vec::new();
+        report.write_sarif(&mut out).unwrap();
+        let log: Value = serde_json::from_slice(&out).unwrap();
+        assert_eq!(log["version"], "2.1.0");
+        assert_eq!(log["runs"][0]["tool"]["driver"], json!({"name": "tool", "version": "1.2.3", "rules": [{"id": "error"}, {"id": "syntax"}]}));
+        assert_eq!(log["runs"][0]["results"], json!([
+            {
+                "ruleId": "syntax",
+                "level": "error",
+                "message": {"text": "no good"},
+                "locations": [{"physicalLocation": {
+                    "artifactLocation": {"uri": "dir/b%20c.json"},
+                    "region": {"startLine": 2, "startColumn": 5, "byteOffset": 9, "byteLength": 2},
+                }}],
+            },
+            // Input that isn't a file has nowhere to point
+            {"ruleId": "error", "level": "error", "message": {"text": "gave up"}},
+        ]));
+        assert_eq!(to_uri("/tmp/a#1.json"), "file:///tmp/a%231.json");
+    }
+
+    #[test]
+    fn check_error_from_error() {
+        let err = anyhow::Error::new(io::Error::other("disk on fire")).context("could not read `a.txt`");
+        assert_eq!(ReportError::from_error(None, &err).message, "could not read `a.txt`: disk on fire");
+        let err = anyhow::anyhow!("could not read `a.txt`: disk on fire").context("counting failed");
+        assert_eq!(ReportError::from_error(None, &err).message, "counting failed: could not read `a.txt`: disk on fire");
+    }
+
+    #[test]
+    fn check_format_from_str() {
+        assert_eq!("json".parse(), Ok(Format::Json));
+        assert_eq!("sarif".parse(), Ok(Format::Sarif));
+        assert_eq!("xml".parse::<Format>(), Err("unknown format `xml`, expected text, json, or sarif".to_string()));
+    }
+}
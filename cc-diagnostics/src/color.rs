@@ -0,0 +1,93 @@
+// ANSI terminal colors, shared so every tool highlights output the same way
+use std::io::IsTerminal;
+
+/// When to color output, as chosen with `--color`
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum ColorChoice {
+    /// Color only when writing to a terminal, and NO_COLOR isn't set
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+impl ColorChoice {
+    /// Whether to color output written to `stream`
+    pub fn should_color(self, stream: &impl IsTerminal) -> bool {
+        match self {
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+            ColorChoice::Auto => stream.is_terminal() && std::env::var_os("NO_COLOR").is_none(),
+        }
+    }
+}
+
+impl std::str::FromStr for ColorChoice {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<ColorChoice, String> {
+        match s {
+            "auto" => Ok(ColorChoice::Auto),
+            "always" => Ok(ColorChoice::Always),
+            "never" => Ok(ColorChoice::Never),
+            _ => Err(format!("invalid color choice `{}`, expected auto, always, or never", s)),
+        }
+    }
+}
+
+/// A foreground color plus boldness
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Style {
+    /// ANSI color number, 30-37 or 90-97
+    pub color: Option<u8>,
+    pub bold: bool,
+}
+
+impl Style {
+    pub const PLAIN: Style = Style { color: None, bold: false };
+    pub const BOLD: Style = Style { color: None, bold: true };
+    pub const RED: Style = Style { color: Some(31), bold: true };
+    pub const GREEN: Style = Style { color: Some(32), bold: false };
+    pub const YELLOW: Style = Style { color: Some(33), bold: true };
+    pub const BLUE: Style = Style { color: Some(34), bold: true };
+    pub const MAGENTA: Style = Style { color: Some(35), bold: false };
+    pub const CYAN: Style = Style { color: Some(36), bold: false };
+
+    /// Wrap `text` in the escape codes for this style, or return it as-is when `enabled` is false
+    pub fn paint(self, text: &str, enabled: bool) -> String {
+        if !enabled || self == Style::PLAIN {
+            return text.to_string();
+        }
+
+        let mut codes = Vec::new();
+        if self.bold {
+            codes.push("1".to_string());
+        }
+        if let Some(color) = self.color {
+            codes.push(color.to_string());
+        }
+        format!("\x1b[{}m{}\x1b[0m", codes.join(";"), text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_paint() {
+        assert_eq!(Style::RED.paint("error", true), "\x1b[1;31merror\x1b[0m");
+        assert_eq!(Style::GREEN.paint("ok", true), "\x1b[32mok\x1b[0m");
+    }
+
+    #[test]
+    fn check_paint_disabled() {
+        assert_eq!(Style::RED.paint("error", false), "error");
+    }
+
+    #[test]
+    fn check_color_choice_from_str() {
+        assert_eq!("always".parse(), Ok(ColorChoice::Always));
+        assert!("sometimes".parse::<ColorChoice>().is_err());
+    }
+}
@@ -0,0 +1,314 @@
+// Diagnostics shared by the workspace binaries.
+// Errors point at a span of the input and render with the offending line quoted and a caret
+// underneath, e.g.
+//
+//   error[E0001]: unexpected character `'`
+//    --> config.json:7:12
+//     |
+//   7 |   "key-l": ['list value']
+//     |            ^ expected a value
+use std::fmt;
+use unicode_width::UnicodeWidthChar;
+
+pub mod color;
+
+pub use color::{ColorChoice, Style};
+
+/// A range of byte offsets into the input, `start` inclusive and `end` exclusive
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Span {
+    pub fn new(start: usize, end: usize) -> Span {
+        Span { start, end }
+    }
+
+    /// An empty span pointing between two bytes, e.g. at the end of the input
+    pub fn point(offset: usize) -> Span {
+        Span { start: offset, end: offset }
+    }
+
+    /// The smallest span covering both spans
+    pub fn to(self, other: Span) -> Span {
+        Span {
+            start: self.start.min(other.start),
+            end: self.end.max(other.end),
+        }
+    }
+}
+
+/// A 1-based line and column, with the column counted in characters
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct LineCol {
+    pub line: usize,
+    pub column: usize,
+}
+
+impl fmt::Display for LineCol {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}", self.line, self.column)
+    }
+}
+
+/// Find the line and column of a byte offset in `source`
+pub fn line_col(source: &str, offset: usize) -> LineCol {
+    let offset = floor_char_boundary(source, offset);
+    let before = &source[..offset];
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+
+    LineCol {
+        line: before.matches('\n').count() + 1,
+        column: before[line_start..].chars().count() + 1,
+    }
+}
+
+fn floor_char_boundary(source: &str, offset: usize) -> usize {
+    let mut offset = offset.min(source.len());
+    while !source.is_char_boundary(offset) {
+        offset -= 1;
+    }
+    offset
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Severity {
+    Error,
+    Warning,
+    Note,
+}
+
+impl Severity {
+    fn style(self) -> Style {
+        match self {
+            Severity::Error => Style::RED,
+            Severity::Warning => Style::YELLOW,
+            Severity::Note => Style::BOLD,
+        }
+    }
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+            Severity::Note => "note",
+        })
+    }
+}
+
+/// A problem found in the input
+#[derive(Clone, Debug, PartialEq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    /// Stable identifier for the kind of problem, e.g. `E0001`, so it can be looked up or
+    /// filtered on
+    pub code: Option<String>,
+    pub message: String,
+    pub span: Option<Span>,
+    /// Printed beside the caret
+    pub label: Option<String>,
+    /// Extra lines of explanation printed after the snippet
+    pub notes: Vec<String>,
+}
+
+impl Diagnostic {
+    pub fn new(severity: Severity, message: impl Into<String>) -> Diagnostic {
+        Diagnostic {
+            severity,
+            code: None,
+            message: message.into(),
+            span: None,
+            label: None,
+            notes: Vec::new(),
+        }
+    }
+
+    pub fn error(message: impl Into<String>) -> Diagnostic {
+        Diagnostic::new(Severity::Error, message)
+    }
+
+    pub fn warning(message: impl Into<String>) -> Diagnostic {
+        Diagnostic::new(Severity::Warning, message)
+    }
+
+    pub fn with_code(mut self, code: impl Into<String>) -> Diagnostic {
+        self.code = Some(code.into());
+        self
+    }
+
+    pub fn with_span(mut self, span: Span) -> Diagnostic {
+        self.span = Some(span);
+        self
+    }
+
+    pub fn with_label(mut self, label: impl Into<String>) -> Diagnostic {
+        self.label = Some(label.into());
+        self
+    }
+
+    pub fn with_note(mut self, note: impl Into<String>) -> Diagnostic {
+        self.notes.push(note.into());
+        self
+    }
+
+    /// Render for a terminal. `name` labels the input, and `source` is its text, used to quote
+    /// the line the span points at.
+    pub fn render(&self, name: &str, source: &str, color: bool) -> String {
+        let mut out = String::new();
+
+        let title = match &self.code {
+            Some(code) => format!("{}[{}]", self.severity, code),
+            None => self.severity.to_string(),
+        };
+        out.push_str(&self.severity.style().paint(&title, color));
+        out.push_str(&Style::BOLD.paint(&format!(": {}", self.message), color));
+        out.push('\n');
+
+        let gutter_style = Style::BLUE;
+        match self.span {
+            Some(span) => {
+                let position = line_col(source, span.start);
+                let line_number = position.line.to_string();
+                let gutter = " ".repeat(line_number.len());
+                let line = source.lines().nth(position.line - 1).unwrap_or("");
+
+                out.push_str(&format!("{}{} {}:{}\n", gutter, gutter_style.paint("-->", color), name, position));
+                out.push_str(&format!("{} {}\n", gutter, gutter_style.paint("|", color)));
+                out.push_str(&format!("{} {} {}\n", gutter_style.paint(&line_number, color), gutter_style.paint("|", color), line));
+
+                let (padding, marker) = self.marker(line, position.column, span);
+                out.push_str(&format!(
+                    "{} {} {}{}\n",
+                    gutter,
+                    gutter_style.paint("|", color),
+                    padding,
+                    self.severity.style().paint(&marker, color),
+                ));
+            },
+            None => out.push_str(&format!(" {} {}\n", gutter_style.paint("-->", color), name)),
+        }
+
+        for note in &self.notes {
+            out.push_str(&format!("  = {}\n", note));
+        }
+
+        out
+    }
+
+    /// The line under the quoted source, split into the padding up to the span and the carets
+    /// under it followed by the label
+    fn marker(&self, line: &str, column: usize, span: Span) -> (String, String) {
+        let mut padding = String::new();
+        let mut chars = line.chars();
+
+        // Copy tabs from the source line so the caret lines up however wide tabs are
+        for ch in chars.by_ref().take(column - 1) {
+            match ch {
+                '\t' => padding.push('\t'),
+                _ => padding.push_str(&" ".repeat(ch.width().unwrap_or(0))),
+            }
+        }
+
+        let rest = chars.as_str();
+        let spanned = &rest[..floor_char_boundary(rest, span.end - span.start)];
+        let width: usize = spanned.chars().map(|c| c.width().unwrap_or(1)).sum();
+        let mut marker = "^".repeat(width.max(1));
+
+        if let Some(label) = &self.label {
+            marker.push(' ');
+            marker.push_str(label);
+        }
+        (padding, marker)
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    /// A one-line summary, for when the source isn't at hand
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.severity)?;
+        if let Some(code) = &self.code {
+            write!(f, "[{}]", code)?;
+        }
+        write!(f, ": {}", self.message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_line_col() {
+        let source = "{\n  \"key\": 1\n}";
+        assert_eq!(line_col(source, 0), LineCol { line: 1, column: 1 });
+        assert_eq!(line_col(source, 2), LineCol { line: 2, column: 1 });
+        assert_eq!(line_col(source, 4), LineCol { line: 2, column: 3 });
+        assert_eq!(line_col(source, source.len()), LineCol { line: 3, column: 2 });
+    }
+
+    #[test]
+    fn check_line_col_counts_characters() {
+        let source = "\"caf\u{e9}\" x";
+        assert_eq!(line_col(source, 8), LineCol { line: 1, column: 8 });
+    }
+
+    #[test]
+    fn check_render() {
+        let source = "{\n  \"key\": 'value'\n}\n";
+        let diagnostic = Diagnostic::error("unexpected character `'`")
+            .with_code("E0001")
+            .with_span(Span::new(11, 12))
+            .with_label("expected a value");
+        assert_eq!(diagnostic.render("a.json", source, false), concat!(
+            "error[E0001]: unexpected character `'`\n",
+            " --> a.json:2:10\n",
+            "  |\n",
+            "2 |   \"key\": 'value'\n",
+            "  |          ^ expected a value\n",
+        ));
+    }
+
+    #[test]
+    fn check_render_span_width() {
+        let source = "[tru]";
+        let diagnostic = Diagnostic::error("invalid literal").with_span(Span::new(1, 4));
+        let rendered = diagnostic.render("a.json", source, false);
+        assert!(rendered.ends_with("  |  ^^^\n"), "{}", rendered);
+    }
+
+    #[test]
+    fn check_render_keeps_tabs() {
+        let source = "\t\tx";
+        let diagnostic = Diagnostic::error("bad").with_span(Span::new(2, 3));
+        let rendered = diagnostic.render("a", source, false);
+        assert!(rendered.ends_with("  | \t\t^\n"), "{:?}", rendered);
+    }
+
+    #[test]
+    fn check_render_without_span() {
+        let diagnostic = Diagnostic::warning("file is empty").with_note("nothing to parse");
+        assert_eq!(diagnostic.render("a.json", "", false), concat!(
+            "warning: file is empty\n",
+            " --> a.json\n",
+            "  = nothing to parse\n",
+        ));
+    }
+
+    #[test]
+    fn check_render_colored_caret() {
+        let source = "x";
+        let diagnostic = Diagnostic::error("bad").with_span(Span::new(0, 1));
+        let rendered = diagnostic.render("a", source, true);
+        assert!(rendered.contains("\x1b[1;31m^\x1b[0m"), "{:?}", rendered);
+    }
+
+    #[test]
+    fn check_display() {
+        let diagnostic = Diagnostic::error("bad").with_code("E0002");
+        assert_eq!(diagnostic.to_string(), "error[E0002]: bad");
+    }
+}
@@ -0,0 +1,293 @@
+// Diff core shared by the workspace binaries.
+// Finds the shortest edit script between two sequences with Myers' algorithm, groups it into
+// hunks with some context around each change, and writes hunks in unified format, e.g.
+//
+//   @@ -1,3 +1,3 @@
+//    first
+//   -second
+//   +2nd
+//    third
+//
+// Reference:  http://www.xmailserver.org/diff2.pdf
+use std::io::{self, Write};
+
+/// One step of an edit script, with the positions of the items involved
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Edit {
+    /// `old[old]` and `new[new]` are the same
+    Equal { old: usize, new: usize },
+    /// `old[old]` is removed
+    Delete { old: usize },
+    /// `new[new]` is added
+    Insert { new: usize },
+}
+
+impl Edit {
+    pub fn is_change(&self) -> bool {
+        !matches!(self, Edit::Equal { .. })
+    }
+}
+
+/// The shortest edit script turning `old` into `new`, in order
+pub fn diff<T: PartialEq>(old: &[T], new: &[T]) -> Vec<Edit> {
+    // Common lines at either end are never part of the shortest script, so leave them out of
+    // the search, which is quadratic in the number of differences
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..].iter().rev().zip(new[prefix..].iter().rev()).take_while(|(a, b)| a == b).count();
+
+    let mut edits: Vec<Edit> = (0..prefix).map(|i| Edit::Equal { old: i, new: i }).collect();
+    let middle = myers(&old[prefix..old.len() - suffix], &new[prefix..new.len() - suffix]);
+    edits.extend(middle.into_iter().map(|edit| match edit {
+        Edit::Equal { old, new } => Edit::Equal { old: old + prefix, new: new + prefix },
+        Edit::Delete { old } => Edit::Delete { old: old + prefix },
+        Edit::Insert { new } => Edit::Insert { new: new + prefix },
+    }));
+    edits.extend((0..suffix).map(|i| Edit::Equal {
+        old: old.len() - suffix + i,
+        new: new.len() - suffix + i,
+    }));
+    edits
+}
+
+fn myers<T: PartialEq>(old: &[T], new: &[T]) -> Vec<Edit> {
+    let (n, m) = (old.len() as isize, new.len() as isize);
+    let max = n + m;
+    let offset = max + 1;
+
+    // v[k] is the furthest x reached on diagonal k = x - y. The trace keeps the diagonals around
+    // -d..=d of v as they were before each round d, to walk the path back afterwards.
+    let mut v = vec![0isize; 2 * max as usize + 3];
+    let mut trace: Vec<Vec<isize>> = Vec::new();
+    'search: for d in 0..=max {
+        trace.push(v[(offset - d - 1) as usize..=(offset + d + 1) as usize].to_vec());
+        for k in (-d..=d).step_by(2) {
+            let down = k == -d || (k != d && v[(offset + k - 1) as usize] < v[(offset + k + 1) as usize]);
+            let mut x = match down {
+                true => v[(offset + k + 1) as usize],
+                false => v[(offset + k - 1) as usize] + 1,
+            };
+            let mut y = x - k;
+            while x < n && y < m && old[x as usize] == new[y as usize] {
+                x += 1;
+                y += 1;
+            }
+            v[(offset + k) as usize] = x;
+            if x >= n && y >= m {
+                break 'search;
+            }
+        }
+    }
+
+    let mut edits = Vec::new();
+    let (mut x, mut y) = (n, m);
+    for (d, v) in trace.iter().enumerate().rev() {
+        let d = d as isize;
+        let at = |k: isize| v[(k + d + 1) as usize];
+        let k = x - y;
+        let previous_k = match k == -d || (k != d && at(k - 1) < at(k + 1)) {
+            true => k + 1,
+            false => k - 1,
+        };
+        let previous_x = at(previous_k);
+        let previous_y = previous_x - previous_k;
+
+        while x > previous_x && y > previous_y {
+            x -= 1;
+            y -= 1;
+            edits.push(Edit::Equal { old: x as usize, new: y as usize });
+        }
+        if d > 0 {
+            match x == previous_x {
+                true => edits.push(Edit::Insert { new: previous_y as usize }),
+                false => edits.push(Edit::Delete { old: previous_x as usize }),
+            }
+        }
+        (x, y) = (previous_x, previous_y);
+    }
+
+    edits.reverse();
+    edits
+}
+
+/// A run of changes with the unchanged items around them. Starts are 0-based.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Hunk {
+    pub old_start: usize,
+    pub old_len: usize,
+    pub new_start: usize,
+    pub new_len: usize,
+    pub edits: Vec<Edit>,
+}
+
+/// Group an edit script into hunks, keeping up to `context` unchanged items either side of each
+/// change. Changes closer together than that share a hunk.
+pub fn hunks(edits: &[Edit], context: usize) -> Vec<Hunk> {
+    // Where in each sequence every edit starts
+    let mut positions = Vec::with_capacity(edits.len());
+    let (mut old, mut new) = (0, 0);
+    for edit in edits {
+        positions.push((old, new));
+        match edit {
+            Edit::Equal { .. } => (old, new) = (old + 1, new + 1),
+            Edit::Delete { .. } => old += 1,
+            Edit::Insert { .. } => new += 1,
+        }
+    }
+
+    let changes: Vec<usize> = (0..edits.len()).filter(|&i| edits[i].is_change()).collect();
+    let mut hunks = Vec::new();
+    let mut i = 0;
+    while i < changes.len() {
+        let first = changes[i];
+        let mut last = first;
+        while i + 1 < changes.len() && changes[i + 1] - last <= 2 * context + 1 {
+            i += 1;
+            last = changes[i];
+        }
+        i += 1;
+
+        let start = first.saturating_sub(context);
+        let end = (last + context + 1).min(edits.len());
+        let edits = edits[start..end].to_vec();
+        hunks.push(Hunk {
+            old_start: positions[start].0,
+            old_len: edits.iter().filter(|e| !matches!(e, Edit::Insert { .. })).count(),
+            new_start: positions[start].1,
+            new_len: edits.iter().filter(|e| !matches!(e, Edit::Delete { .. })).count(),
+            edits,
+        });
+    }
+    hunks
+}
+
+/// Split text into lines, each keeping its newline. Only the last line can lack one.
+pub fn split_lines(text: &[u8]) -> Vec<&[u8]> {
+    text.split_inclusive(|&b| b == b'\n').collect()
+}
+
+/// Write hunks of a diff between two texts split with `split_lines`, in unified format. The
+/// `---` and `+++` header lines naming the texts are left to the caller.
+pub fn write_unified(out: &mut impl Write, hunks: &[Hunk], old: &[&[u8]], new: &[&[u8]]) -> io::Result<()> {
+    for hunk in hunks {
+        writeln!(
+            out,
+            "@@ -{} +{} @@",
+            unified_range(hunk.old_start, hunk.old_len),
+            unified_range(hunk.new_start, hunk.new_len),
+        )?;
+        for edit in &hunk.edits {
+            let (marker, line) = match *edit {
+                Edit::Equal { old: i, .. } => (b' ', old[i]),
+                Edit::Delete { old: i } => (b'-', old[i]),
+                Edit::Insert { new: i } => (b'+', new[i]),
+            };
+            out.write_all(&[marker])?;
+            write_line(out, line)?;
+        }
+    }
+    Ok(())
+}
+
+/// Write a line, marking it if the text ended without a newline, as diff does
+pub fn write_line(out: &mut impl Write, line: &[u8]) -> io::Result<()> {
+    out.write_all(line)?;
+    if !line.ends_with(b"\n") {
+        out.write_all(b"\n\\ No newline at end of file\n")?;
+    }
+    Ok(())
+}
+
+/// A hunk range as unified format writes it: 1-based, with the length left off when it's one,
+/// and an empty range given as the line before it
+fn unified_range(start: usize, len: usize) -> String {
+    match len {
+        0 => format!("{},0", start),
+        1 => format!("{}", start + 1),
+        _ => format!("{},{}", start + 1, len),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chars(s: &str) -> Vec<char> {
+        s.chars().collect()
+    }
+
+    /// Apply an edit script to `old`, which should give `new`
+    fn apply(edits: &[Edit], old: &[char], new: &[char]) -> Vec<char> {
+        edits
+            .iter()
+            .filter_map(|edit| match *edit {
+                Edit::Equal { old: i, .. } => Some(old[i]),
+                Edit::Delete { .. } => None,
+                Edit::Insert { new: i } => Some(new[i]),
+            })
+            .collect()
+    }
+
+    fn unified(old: &str, new: &str, context: usize) -> String {
+        let (old, new) = (split_lines(old.as_bytes()), split_lines(new.as_bytes()));
+        let mut out = Vec::new();
+        write_unified(&mut out, &hunks(&diff(&old, &new), context), &old, &new).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn check_diff_is_shortest() {
+        // The example from Myers' paper, which needs five edits
+        let (old, new) = (chars("ABCABBA"), chars("CBABAC"));
+        let edits = diff(&old, &new);
+        assert_eq!(apply(&edits, &old, &new), new);
+        assert_eq!(edits.iter().filter(|e| e.is_change()).count(), 5);
+    }
+
+    #[test]
+    fn check_diff_edge_cases() {
+        assert!(diff::<char>(&[], &[]).is_empty());
+        assert_eq!(diff(&[], &['a']), vec![Edit::Insert { new: 0 }]);
+        assert_eq!(diff(&['a'], &[]), vec![Edit::Delete { old: 0 }]);
+        assert_eq!(diff(&['a'], &['a']), vec![Edit::Equal { old: 0, new: 0 }]);
+    }
+
+    #[test]
+    fn check_diff_with_common_ends() {
+        let (old, new) = (chars("xxABCyy"), chars("xxACByy"));
+        let edits = diff(&old, &new);
+        assert_eq!(apply(&edits, &old, &new), new);
+        assert_eq!(edits.iter().filter(|e| e.is_change()).count(), 2);
+    }
+
+    #[test]
+    fn check_hunks_split_on_distant_changes() {
+        let old = chars("abcdefghijklmnop");
+        let mut new = old.clone();
+        new[1] = 'B';
+        new[14] = 'O';
+        let edits = diff(&old, &new);
+
+        assert_eq!(hunks(&edits, 3).len(), 2);
+        assert_eq!(hunks(&edits, 6).len(), 1);
+        let hunk = &hunks(&edits, 3)[0];
+        assert_eq!((hunk.old_start, hunk.old_len, hunk.new_start, hunk.new_len), (0, 5, 0, 5));
+    }
+
+    #[test]
+    fn check_unified() {
+        assert_eq!(unified("a\nb\nc\n", "a\nB\nc\n", 1), "@@ -1,3 +1,3 @@\n a\n-b\n+B\n c\n");
+    }
+
+    #[test]
+    fn check_unified_into_empty() {
+        assert_eq!(unified("a\n", "", 3), "@@ -1 +0,0 @@\n-a\n");
+    }
+
+    #[test]
+    fn check_unified_missing_newline() {
+        assert_eq!(
+            unified("a\nb", "a\nb\n", 3),
+            "@@ -1,2 +1,2 @@\n a\n-b\n\\ No newline at end of file\n+b\n",
+        );
+    }
+}
@@ -0,0 +1,170 @@
+// Input handling shared by the workspace binaries.
+// Every tool reads either a file or stdin, with `-` or a missing path meaning stdin, and labels the
+// input the same way in its diagnostics.
+use std::{
+    fmt,
+    fs::File,
+    io::{stdin, BufRead, BufReader, IsTerminal as _, Read, Seek, SeekFrom},
+    path::{Path, PathBuf},
+};
+
+/// Label used in diagnostics for input read from stdin
+pub const STDIN_LABEL: &str = "<stdin>";
+
+/// Where a tool's input comes from
+#[derive(Clone, Debug, PartialEq)]
+pub enum Source {
+    Stdin,
+    File(PathBuf),
+}
+
+impl Source {
+    /// The source named by a path argument, where `-` or no path at all means stdin
+    pub fn from_arg(path: Option<&Path>) -> Source {
+        match path {
+            Some(p) if p != Path::new("-") => Source::File(p.to_path_buf()),
+            _ => Source::Stdin,
+        }
+    }
+
+    pub fn is_stdin(&self) -> bool {
+        matches!(self, Source::Stdin)
+    }
+
+    /// Whether reading this source would wait on someone typing at a terminal.
+    /// Tools that can't sensibly take typed input use this to print their help instead.
+    pub fn is_interactive(&self) -> bool {
+        self.is_stdin() && stdin().is_terminal()
+    }
+
+    /// How to refer to the source in diagnostics
+    pub fn label(&self) -> String {
+        match self {
+            Source::Stdin => STDIN_LABEL.to_string(),
+            Source::File(path) => path.display().to_string(),
+        }
+    }
+}
+
+impl From<PathBuf> for Source {
+    fn from(path: PathBuf) -> Source {
+        Source::from_arg(Some(&path))
+    }
+}
+
+/// An opened source, ready to read
+pub struct Input {
+    pub source: Source,
+    pub reader: Box<dyn BufRead>,
+}
+
+impl Input {
+    pub fn label(&self) -> String {
+        self.source.label()
+    }
+}
+
+/// Failure to open a source, naming the source
+#[derive(Debug)]
+pub struct OpenError {
+    pub label: String,
+    pub source: std::io::Error,
+}
+
+impl fmt::Display for OpenError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "could not read `{}`: {}", self.label, self.source)
+    }
+}
+
+impl std::error::Error for OpenError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.source)
+    }
+}
+
+/// Open a source for buffered reading
+pub fn open(source: &Source) -> Result<Input, OpenError> {
+    open_at(source, 0)
+}
+
+/// Open a source for buffered reading, starting `offset` bytes in.
+/// Files seek straight there, while stdin has to read through the skipped bytes since pipes can't
+/// seek.
+pub fn open_at(source: &Source, offset: u64) -> Result<Input, OpenError> {
+    let error = |e| OpenError { label: source.label(), source: e };
+
+    let reader: Box<dyn BufRead> = match source {
+        Source::Stdin => {
+            let mut stdin = stdin().lock();
+            std::io::copy(&mut (&mut stdin).take(offset), &mut std::io::sink()).map_err(error)?;
+            Box::new(stdin)
+        },
+        Source::File(path) => {
+            let mut file = File::open(path).map_err(error)?;
+            if offset > 0 {
+                file.seek(SeekFrom::Start(offset)).map_err(error)?;
+            }
+            Box::new(BufReader::new(file))
+        },
+    };
+
+    Ok(Input { source: source.clone(), reader })
+}
+
+/// Whether the input looks like a binary file rather than text, judging by NUL bytes in the first
+/// buffer of it. Nothing is consumed from the reader.
+pub fn is_binary(reader: &mut impl BufRead) -> std::io::Result<bool> {
+    Ok(reader.fill_buf()?.contains(&0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn manifest() -> PathBuf {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("Cargo.toml")
+    }
+
+    #[test]
+    fn check_from_arg() {
+        assert_eq!(Source::from_arg(None), Source::Stdin);
+        assert_eq!(Source::from_arg(Some(Path::new("-"))), Source::Stdin);
+        assert_eq!(Source::from_arg(Some(Path::new("a.json"))), Source::File(PathBuf::from("a.json")));
+    }
+
+    #[test]
+    fn check_label() {
+        assert_eq!(Source::Stdin.label(), "<stdin>");
+        assert_eq!(Source::File(PathBuf::from("dir/a.json")).label(), "dir/a.json");
+    }
+
+    #[test]
+    fn check_open_file() {
+        let mut input = open(&Source::File(manifest())).unwrap();
+        let mut first_line = String::new();
+        input.reader.read_line(&mut first_line).unwrap();
+        assert_eq!(first_line, "[package]\n");
+    }
+
+    #[test]
+    fn check_open_at_offset() {
+        let mut input = open_at(&Source::File(manifest()), 1).unwrap();
+        let mut first_line = String::new();
+        input.reader.read_line(&mut first_line).unwrap();
+        assert_eq!(first_line, "package]\n");
+    }
+
+    #[test]
+    fn check_is_binary() {
+        assert!(!is_binary(&mut &b"lorem ipsum\n"[..]).unwrap());
+        assert!(is_binary(&mut &b"\x7fELF\x02\x01\x00\x00"[..]).unwrap());
+    }
+
+    #[test]
+    fn check_open_missing_file_names_it() {
+        let err = open(&Source::File(PathBuf::from("does/not/exist.json"))).err().unwrap();
+        assert_eq!(err.label, "does/not/exist.json");
+        assert!(err.to_string().starts_with("could not read `does/not/exist.json`"));
+    }
+}
@@ -0,0 +1,96 @@
+// .gitignore files.
+// Each line is a glob for paths to leave out, with `#` starting a comment, `!` bringing back
+// something an earlier line left out, a trailing `/` matching only directories, and a `/` at the
+// start or in the middle tying the glob to the directory the .gitignore is in. The last line to
+// match a path decides it.
+// Reference:  https://git-scm.com/docs/gitignore
+use crate::Glob;
+use std::path::{Path, PathBuf};
+
+#[derive(Clone, Debug)]
+struct Rule {
+    glob: Glob,
+    /// Whether the line started with `!`
+    negated: bool,
+    dir_only: bool,
+    /// Whether the glob is matched against the path from the .gitignore's directory rather than
+    /// the file name alone
+    anchored: bool,
+}
+
+/// The rules of one .gitignore file
+#[derive(Clone, Debug)]
+pub struct Gitignore {
+    dir: PathBuf,
+    rules: Vec<Rule>,
+}
+
+impl Gitignore {
+    /// The rules in `text`, a .gitignore in `dir`. Like git, lines that aren't valid globs are
+    /// skipped.
+    pub fn parse(dir: &Path, text: &str) -> Gitignore {
+        let rules = text.lines().filter_map(parse_rule).collect();
+        Gitignore { dir: dir.to_path_buf(), rules }
+    }
+
+    /// Whether the file says to leave `path` out, if it says anything about it at all
+    pub fn is_ignored(&self, path: &Path, is_dir: bool) -> Option<bool> {
+        let relative = path.strip_prefix(&self.dir).ok()?;
+        let relative: Vec<_> = relative.components().map(|c| c.as_os_str().to_string_lossy()).collect();
+        let relative = relative.join("/");
+        let name = path.file_name()?.to_string_lossy();
+
+        self.rules.iter().rev().find_map(|rule| {
+            let text = if rule.anchored { &relative } else { &*name };
+            ((is_dir || !rule.dir_only) && rule.glob.matches(text)).then_some(!rule.negated)
+        })
+    }
+}
+
+fn parse_rule(line: &str) -> Option<Rule> {
+    // Trailing spaces don't count unless they're escaped
+    let mut line = line.trim_end_matches('\r');
+    while line.ends_with(' ') && !line.ends_with("\\ ") {
+        line = &line[..line.len() - 1];
+    }
+    if line.is_empty() || line.starts_with('#') {
+        return None;
+    }
+
+    let (negated, line) = match line.strip_prefix('!') {
+        Some(rest) => (true, rest),
+        None => (false, line.strip_prefix('\\').filter(|rest| rest.starts_with(['#', '!'])).unwrap_or(line)),
+    };
+    let (dir_only, line) = match line.strip_suffix('/') {
+        Some(rest) => (true, rest),
+        None => (false, line),
+    };
+    let anchored = line.contains('/');
+    let glob = Glob::new(line.strip_prefix('/').unwrap_or(line)).ok()?;
+    Some(Rule { glob, negated, dir_only, anchored })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_is_ignored() {
+        let gitignore = Gitignore::parse(
+            Path::new("repo"),
+            "# build output\n/target\n*.log\n!keep.log\nnode_modules/\ndocs/*.html\n\\#notes\n",
+        );
+        let ignored = |path: &str, is_dir| gitignore.is_ignored(Path::new(path), is_dir);
+
+        assert_eq!(ignored("repo/target", true), Some(true));
+        assert_eq!(ignored("repo/src/target", true), None);
+        assert_eq!(ignored("repo/src/debug.log", false), Some(true));
+        assert_eq!(ignored("repo/src/keep.log", false), Some(false));
+        assert_eq!(ignored("repo/web/node_modules", true), Some(true));
+        assert_eq!(ignored("repo/web/node_modules", false), None);
+        assert_eq!(ignored("repo/docs/index.html", false), Some(true));
+        assert_eq!(ignored("repo/docs/api/index.html", false), None);
+        assert_eq!(ignored("repo/#notes", false), Some(true));
+        assert_eq!(ignored("elsewhere/debug.log", false), None);
+    }
+}
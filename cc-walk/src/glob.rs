@@ -0,0 +1,230 @@
+// Shell-style glob patterns, as used by --include, --exclude, and .gitignore files.
+// `*` matches any run of characters within a path component, `?` any one character, `[a-z]` or
+// `[!a-z]` one character in or out of the set, and `**` as a whole component any number of
+// components, e.g. `src/**/*.rs` matches `src/lib.rs` and `src/cli/output.rs`. A backslash
+// matches the character after it literally.
+use std::{fmt, path::Path, str::FromStr};
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Char(char),
+    /// `?`
+    AnyChar,
+    /// `*`
+    Star,
+    /// `**` as a whole component, along with the `/` after it
+    Globstar,
+    Class { negated: bool, ranges: Vec<(char, char)> },
+}
+
+/// A compiled glob pattern
+#[derive(Clone, Debug, PartialEq)]
+pub struct Glob {
+    pattern: String,
+    tokens: Vec<Token>,
+}
+
+/// A pattern that couldn't be compiled
+#[derive(Clone, Debug, PartialEq)]
+pub struct GlobError {
+    pub pattern: String,
+    pub message: String,
+}
+
+impl fmt::Display for GlobError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid glob `{}`: {}", self.pattern, self.message)
+    }
+}
+
+impl std::error::Error for GlobError {}
+
+impl Glob {
+    pub fn new(pattern: &str) -> Result<Glob, GlobError> {
+        let error = |message: &str| GlobError { pattern: pattern.to_string(), message: message.to_string() };
+        let chars: Vec<char> = pattern.chars().collect();
+        let mut tokens = Vec::new();
+        let mut i = 0;
+
+        while i < chars.len() {
+            match chars[i] {
+                '\\' => {
+                    i += 1;
+                    tokens.push(Token::Char(*chars.get(i).ok_or_else(|| error("nothing to escape after `\\`"))?));
+                },
+                '?' => tokens.push(Token::AnyChar),
+                '*' if chars.get(i + 1) == Some(&'*') => {
+                    let whole_component =
+                        (i == 0 || chars[i - 1] == '/') && matches!(chars.get(i + 2), None | Some('/'));
+                    i += 1;
+                    if whole_component {
+                        tokens.push(Token::Globstar);
+                        i += 1;
+                    } else {
+                        tokens.push(Token::Star);
+                    }
+                },
+                '*' => tokens.push(Token::Star),
+                '[' => {
+                    let (class, end) = read_class(&chars, i + 1).ok_or_else(|| error("unclosed `[`"))?;
+                    tokens.push(class);
+                    i = end;
+                },
+                c => tokens.push(Token::Char(c)),
+            }
+            i += 1;
+        }
+
+        Ok(Glob { pattern: pattern.to_string(), tokens })
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.pattern
+    }
+
+    /// Whether the whole of `text` matches the pattern
+    pub fn matches(&self, text: &str) -> bool {
+        let text: Vec<char> = text.chars().collect();
+        matches_at(&self.tokens, &text)
+    }
+
+    /// Whether a path matches the pattern. A pattern with no `/` in it is matched against the
+    /// file name alone, the way a .gitignore's are, so `*.json` matches `a/b.json` too.
+    pub fn matches_path(&self, path: &Path) -> bool {
+        if !self.pattern.contains('/') {
+            return path.file_name().is_some_and(|name| self.matches(&name.to_string_lossy()));
+        }
+        let components: Vec<_> = path.components().map(|c| c.as_os_str().to_string_lossy()).collect();
+        self.matches(&components.join("/"))
+    }
+}
+
+impl FromStr for Glob {
+    type Err = GlobError;
+
+    fn from_str(s: &str) -> Result<Glob, GlobError> {
+        Glob::new(s)
+    }
+}
+
+impl fmt::Display for Glob {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.pattern)
+    }
+}
+
+/// The character class starting after its `[` at `start`, and where its `]` is
+fn read_class(chars: &[char], start: usize) -> Option<(Token, usize)> {
+    let mut i = start;
+    let negated = matches!(chars.get(i), Some('!' | '^'));
+    if negated {
+        i += 1;
+    }
+    let mut ranges = Vec::new();
+    // A `]` straight after the `[` is part of the set rather than the end of it
+    let first = i;
+    loop {
+        let c = match *chars.get(i)? {
+            ']' if i > first => return Some((Token::Class { negated, ranges }, i)),
+            '\\' => {
+                i += 1;
+                *chars.get(i)?
+            },
+            c => c,
+        };
+        match (chars.get(i + 1), chars.get(i + 2)) {
+            (Some('-'), Some(&end)) if end != ']' => {
+                ranges.push((c, end));
+                i += 3;
+            },
+            _ => {
+                ranges.push((c, c));
+                i += 1;
+            },
+        }
+    }
+}
+
+fn matches_at(tokens: &[Token], text: &[char]) -> bool {
+    let Some((token, rest)) = tokens.split_first() else {
+        return text.is_empty();
+    };
+    match token {
+        Token::Star => (0..=text.len())
+            .take_while(|&n| n == 0 || text[n - 1] != '/')
+            .any(|n| matches_at(rest, &text[n..])),
+        // Nothing, or whole components up to and including a `/`
+        Token::Globstar if rest.is_empty() => true,
+        Token::Globstar => {
+            matches_at(rest, text)
+                || (1..=text.len()).any(|n| text[n - 1] == '/' && matches_at(rest, &text[n..]))
+        },
+        _ => match text.split_first() {
+            Some((&c, text)) if matches_char(token, c) => matches_at(rest, text),
+            _ => false,
+        },
+    }
+}
+
+fn matches_char(token: &Token, c: char) -> bool {
+    match token {
+        Token::Char(expected) => c == *expected,
+        Token::AnyChar => c != '/',
+        Token::Class { negated, ranges } => {
+            c != '/' && ranges.iter().any(|&(low, high)| (low..=high).contains(&c)) != *negated
+        },
+        Token::Star | Token::Globstar => unreachable!("handled by matches_at"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matches(pattern: &str, text: &str) -> bool {
+        Glob::new(pattern).unwrap().matches(text)
+    }
+
+    #[test]
+    fn check_matches() {
+        assert!(matches("*.json", "a.json"));
+        assert!(matches("*.json", ".json"));
+        assert!(!matches("*.json", "a.json5"));
+        assert!(!matches("*.json", "dir/a.json"));
+        assert!(matches("file?.txt", "file1.txt"));
+        assert!(!matches("file?.txt", "file.txt"));
+        assert!(matches("[a-c]x[!0-9]", "bxy"));
+        assert!(!matches("[a-c]x[!0-9]", "bx1"));
+        assert!(matches("[]]", "]"));
+        assert!(matches("\\*", "*"));
+        assert!(!matches("\\*", "a"));
+    }
+
+    #[test]
+    fn check_globstar() {
+        assert!(matches("src/**/*.rs", "src/lib.rs"));
+        assert!(matches("src/**/*.rs", "src/cli/output.rs"));
+        assert!(!matches("src/**/*.rs", "tests/cli.rs"));
+        assert!(matches("**/target", "target"));
+        assert!(matches("**/target", "a/b/target"));
+        assert!(matches("target/**", "target/debug/build"));
+        // Not a whole component, so just a `*`
+        assert!(matches("a**b", "axxb"));
+        assert!(!matches("a**b", "a/b"));
+    }
+
+    #[test]
+    fn check_matches_path() {
+        let glob = Glob::new("*.json").unwrap();
+        assert!(glob.matches_path(Path::new("configs/app.json")));
+        let glob = Glob::new("configs/*.json").unwrap();
+        assert!(glob.matches_path(Path::new("configs/app.json")));
+        assert!(!glob.matches_path(Path::new("other/configs/app.json")));
+    }
+
+    #[test]
+    fn check_glob_errors() {
+        assert_eq!(Glob::new("[abc").unwrap_err().to_string(), "invalid glob `[abc`: unclosed `[`");
+        assert_eq!(Glob::new("a\\").unwrap_err().message, "nothing to escape after `\\`");
+    }
+}
@@ -0,0 +1,412 @@
+// Directory walking shared by the workspace binaries.
+// The tools with a recursive mode replace each directory they're given with the files beneath
+// it, optionally narrowed by globs and .gitignore files, with the directories read on several
+// threads at once and the files found listed in sorted order however the reads interleave.
+// Tools can also expand glob patterns they're given themselves, for shells that don't.
+use cc_io::OpenError;
+use std::{
+    path::{Path, PathBuf},
+    sync::{Arc, Condvar, Mutex},
+    thread,
+};
+
+mod gitignore;
+pub mod glob;
+
+use gitignore::Gitignore;
+pub use glob::{Glob, GlobError};
+
+/// What to do with a symlink found in a directory. Symlinks given as roots are always followed.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Symlinks {
+    /// Leave them all out
+    Skip,
+    /// Take links to files, but don't go into links to directories
+    #[default]
+    Files,
+    /// Go into links to directories too, except ones leading back to a directory already being
+    /// walked, so a link cycle can't make the walk run forever
+    Follow,
+}
+
+/// How to walk the directories
+#[derive(Clone, Debug, Default)]
+pub struct WalkOptions {
+    /// Only take files matching one of these, or every file if there are none
+    pub include: Vec<Glob>,
+    /// Leave out the files and directories matching any of these
+    pub exclude: Vec<Glob>,
+    /// Leave out whatever the .gitignore files in the directories walked say to, and .git itself
+    pub gitignore: bool,
+    pub symlinks: Symlinks,
+    /// How many directories to read at once, 0 for one per CPU
+    pub threads: usize,
+}
+
+/// The walking options a tool can flatten into its own with `#[command(flatten)]`
+#[derive(clap::Args, Clone, Debug, Default, PartialEq)]
+pub struct WalkArgs {
+    /// In recursive mode, only take files whose name matches GLOB. Can be given more than once
+    #[arg(long, value_name = "GLOB")]
+    pub include: Vec<Glob>,
+
+    /// In recursive mode, leave out files and directories whose name matches GLOB. Can be given
+    /// more than once
+    #[arg(long, value_name = "GLOB")]
+    pub exclude: Vec<Glob>,
+
+    /// In recursive mode, leave out what .gitignore files say to
+    #[arg(long)]
+    pub gitignore: bool,
+
+    /// In recursive mode, go into symlinks to directories
+    #[arg(long)]
+    pub follow_links: bool,
+}
+
+impl WalkArgs {
+    pub fn options(&self) -> WalkOptions {
+        WalkOptions {
+            include: self.include.clone(),
+            exclude: self.exclude.clone(),
+            gitignore: self.gitignore,
+            symlinks: if self.follow_links { Symlinks::Follow } else { Symlinks::Files },
+            threads: 0,
+        }
+    }
+}
+
+/// A directory still to be read
+struct Dir {
+    /// Which of the roots it's under
+    root: usize,
+    path: PathBuf,
+    /// The .gitignore files from the root down to here
+    gitignores: Arc<Vec<Gitignore>>,
+    /// Where it and the directories above it really are, when following links
+    ancestors: Arc<Vec<PathBuf>>,
+}
+
+struct Queue {
+    dirs: Vec<Dir>,
+    /// How many directories are being read, each of which might add more to the queue
+    busy: usize,
+}
+
+/// What one thread found, each path along with the root it's under
+#[derive(Default)]
+struct Found {
+    files: Vec<(usize, PathBuf)>,
+    errors: Vec<(usize, PathBuf, OpenError)>,
+}
+
+/// The files under each of `roots`, or the root itself if it's a file. The files are in sorted
+/// order under each root, the roots in the order given. The first directory that can't be read
+/// fails the walk.
+pub fn walk(roots: &[PathBuf], options: &WalkOptions) -> Result<Vec<PathBuf>, OpenError> {
+    let mut found = Found::default();
+    let mut dirs = Vec::new();
+    for (root, path) in roots.iter().enumerate() {
+        let error = |e| OpenError { label: path.display().to_string(), source: e };
+        let metadata = std::fs::metadata(path).map_err(error)?;
+        if metadata.is_dir() {
+            let ancestors = match options.symlinks {
+                Symlinks::Follow => vec![path.canonicalize().map_err(error)?],
+                _ => Vec::new(),
+            };
+            dirs.push(Dir { root, path: path.clone(), gitignores: Arc::default(), ancestors: Arc::new(ancestors) });
+        } else if metadata.is_file() {
+            found.files.push((root, path.clone()));
+        }
+    }
+
+    let threads = match options.threads {
+        0 => thread::available_parallelism().map_or(1, |n| n.get()),
+        n => n,
+    };
+    let queue = Mutex::new(Queue { dirs, busy: 0 });
+    let ready = Condvar::new();
+    thread::scope(|scope| {
+        let workers: Vec<_> =
+            (0..threads).map(|_| scope.spawn(|| work(&queue, &ready, roots, options))).collect();
+        for worker in workers {
+            let mut more = worker.join().expect("walker thread panicked");
+            found.files.append(&mut more.files);
+            found.errors.append(&mut more.errors);
+        }
+    });
+
+    if let Some((_, _, e)) = found.errors.into_iter().min_by(|a, b| (a.0, &a.1).cmp(&(b.0, &b.1))) {
+        return Err(e);
+    }
+    found.files.sort();
+    Ok(found.files.into_iter().map(|(_, path)| path).collect())
+}
+
+/// Read directories off the queue until there are none left and none being read
+fn work(queue: &Mutex<Queue>, ready: &Condvar, roots: &[PathBuf], options: &WalkOptions) -> Found {
+    let mut found = Found::default();
+    loop {
+        let dir = {
+            let mut queue = queue.lock().unwrap();
+            loop {
+                if let Some(dir) = queue.dirs.pop() {
+                    queue.busy += 1;
+                    break dir;
+                }
+                if queue.busy == 0 {
+                    return found;
+                }
+                queue = ready.wait(queue).unwrap();
+            }
+        };
+
+        let subdirs = match read_dir(&dir, &roots[dir.root], options, &mut found.files) {
+            Ok(subdirs) => subdirs,
+            Err(e) => {
+                found.errors.push((dir.root, dir.path, e));
+                Vec::new()
+            },
+        };
+        let mut queue = queue.lock().unwrap();
+        queue.dirs.extend(subdirs);
+        queue.busy -= 1;
+        ready.notify_all();
+    }
+}
+
+/// Add the files in `dir` that the options take to `files`, and return the directories in it to
+/// walk next
+fn read_dir(dir: &Dir, root: &Path, options: &WalkOptions, files: &mut Vec<(usize, PathBuf)>) -> Result<Vec<Dir>, OpenError> {
+    let error = |e| OpenError { label: dir.path.display().to_string(), source: e };
+    let entries = std::fs::read_dir(&dir.path)
+        .and_then(|entries| entries.map(|entry| entry.map(|e| e.path())).collect::<std::io::Result<Vec<_>>>())
+        .map_err(error)?;
+
+    let mut gitignores = dir.gitignores.clone();
+    if options.gitignore {
+        match std::fs::read_to_string(dir.path.join(".gitignore")) {
+            Ok(text) => Arc::make_mut(&mut gitignores).push(Gitignore::parse(&dir.path, &text)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {},
+            Err(e) => return Err(OpenError { label: dir.path.join(".gitignore").display().to_string(), source: e }),
+        }
+    }
+
+    let mut subdirs = Vec::new();
+    for path in entries {
+        let Some(is_dir) = entry_is_dir(&path, dir, options) else {
+            continue;
+        };
+        if options.gitignore && (is_dir && path.ends_with(".git") || is_ignored(&gitignores, &path, is_dir)) {
+            continue;
+        }
+        let relative = path.strip_prefix(root).unwrap_or(&path);
+        if options.exclude.iter().any(|glob| glob.matches_path(relative)) {
+            continue;
+        }
+
+        if is_dir {
+            let mut ancestors = dir.ancestors.clone();
+            if options.symlinks == Symlinks::Follow {
+                Arc::make_mut(&mut ancestors).push(path.canonicalize().map_err(error)?);
+            }
+            subdirs.push(Dir { root: dir.root, path, gitignores: gitignores.clone(), ancestors });
+        } else if options.include.is_empty() || options.include.iter().any(|glob| glob.matches_path(relative)) {
+            files.push((dir.root, path));
+        }
+    }
+    Ok(subdirs)
+}
+
+/// Whether the entry at `path` is a directory to walk or a file to take, or None to leave it out.
+/// Links that go nowhere are left out, like anything that's neither a file nor a directory.
+fn entry_is_dir(path: &Path, dir: &Dir, options: &WalkOptions) -> Option<bool> {
+    let metadata = std::fs::symlink_metadata(path).ok()?;
+    if !metadata.file_type().is_symlink() {
+        return match metadata.is_dir() {
+            true => Some(true),
+            false => metadata.is_file().then_some(false),
+        };
+    }
+
+    let target = std::fs::metadata(path).ok()?;
+    match options.symlinks {
+        Symlinks::Skip => None,
+        _ if target.is_file() => Some(false),
+        Symlinks::Follow if target.is_dir() => {
+            let target = path.canonicalize().ok()?;
+            (!dir.ancestors.contains(&target)).then_some(true)
+        },
+        _ => None,
+    }
+}
+
+/// Whether the deepest .gitignore with something to say about `path` says to leave it out
+fn is_ignored(gitignores: &[Gitignore], path: &Path, is_dir: bool) -> bool {
+    gitignores.iter().rev().find_map(|gitignore| gitignore.is_ignored(path, is_dir)).unwrap_or(false)
+}
+
+/// The files whose paths match `pattern`, in sorted order, the way a shell would expand it. The
+/// directories before its first component with a wildcard are where to look, and the rest is
+/// matched against the paths from there, so `*.json` only takes files in the current directory
+/// while `configs/**/*.json` takes them anywhere under `configs`. Directories that can't be read
+/// are passed over, like a shell does.
+pub fn expand(pattern: &str) -> Result<Vec<PathBuf>, GlobError> {
+    let components: Vec<&str> = pattern.split('/').collect();
+    let literal = components.iter().take_while(|component| !component.contains(['*', '?', '['])).count();
+    if literal == components.len() {
+        return Ok(vec![PathBuf::from(pattern)]);
+    }
+    let glob = Glob::new(&components[literal..].join("/"))?;
+    // Without a `**` nothing deeper than the pattern can match
+    let depth = match components[literal..].contains(&"**") {
+        true => usize::MAX,
+        false => components.len() - literal,
+    };
+    let root = match components[..literal].join("/") {
+        dir if dir.is_empty() && literal > 0 => PathBuf::from("/"),
+        dir => PathBuf::from(dir),
+    };
+
+    let mut files = Vec::new();
+    let dir = if literal == 0 { Path::new(".") } else { root.as_path() };
+    find_matches(dir, "", depth, &glob, &mut files);
+    files.sort();
+    Ok(files.into_iter().map(|relative| root.join(relative)).collect())
+}
+
+/// Add the files in `dir` and the directories under it, down to `depth` components, whose paths
+/// from where the searching started match `glob`. `prefix` is the path to `dir` from there.
+fn find_matches(dir: &Path, prefix: &str, depth: usize, glob: &Glob, files: &mut Vec<String>) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let relative = format!("{}{}", prefix, entry.file_name().to_string_lossy());
+        match std::fs::metadata(&path) {
+            Ok(metadata) if metadata.is_dir() && depth > 1 => find_matches(&path, &format!("{}/", relative), depth - 1, glob, files),
+            Ok(metadata) if metadata.is_file() && glob.matches(&relative) => files.push(relative),
+            _ => {},
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    /// A directory of files for a test to walk, removed when dropped
+    struct Tree(PathBuf);
+
+    impl Tree {
+        fn new(name: &str, files: &[&str]) -> Tree {
+            let root = std::env::temp_dir().join(format!("cc-walk-{}-{}", name, std::process::id()));
+            let _ = fs::remove_dir_all(&root);
+            for file in files {
+                let path = root.join(file);
+                fs::create_dir_all(path.parent().unwrap()).unwrap();
+                fs::write(path, file.as_bytes()).unwrap();
+            }
+            Tree(root)
+        }
+
+        fn walk(&self, options: &WalkOptions) -> Vec<String> {
+            let files = walk(std::slice::from_ref(&self.0), options).unwrap();
+            files.iter().map(|file| file.strip_prefix(&self.0).unwrap().display().to_string()).collect()
+        }
+    }
+
+    impl Drop for Tree {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn check_walk() {
+        let root = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        let files = walk(&[root.clone(), root.join("Cargo.toml")], &WalkOptions::default()).unwrap();
+        assert!(files.contains(&root.join("src/lib.rs")));
+        assert_eq!(files.last(), Some(&root.join("Cargo.toml")));
+        // Sorted under the first root, the second coming after them
+        assert!(files[..files.len() - 1].windows(2).all(|pair| pair[0] < pair[1]));
+    }
+
+    #[test]
+    fn check_walk_in_parallel() {
+        let names: Vec<String> = (0..40).map(|n| format!("d{}/e{}/f{}.txt", n % 5, n % 3, n)).collect();
+        let tree = Tree::new("parallel", &names.iter().map(String::as_str).collect::<Vec<_>>());
+        let one = tree.walk(&WalkOptions { threads: 1, ..WalkOptions::default() });
+        let many = tree.walk(&WalkOptions { threads: 8, ..WalkOptions::default() });
+        assert_eq!(one.len(), 40);
+        assert_eq!(one, many);
+    }
+
+    #[test]
+    fn check_walk_globs() {
+        let tree = Tree::new("globs", &["a.json", "b.txt", "sub/c.json", "target/d.json", "sub/target/e.json"]);
+        let options = WalkOptions {
+            include: vec![Glob::new("*.json").unwrap()],
+            exclude: vec![Glob::new("target").unwrap()],
+            ..WalkOptions::default()
+        };
+        assert_eq!(tree.walk(&options), ["a.json", "sub/c.json"]);
+
+        let options = WalkOptions { exclude: vec![Glob::new("sub/*.json").unwrap()], ..WalkOptions::default() };
+        assert_eq!(tree.walk(&options), ["a.json", "b.txt", "sub/target/e.json", "target/d.json"]);
+    }
+
+    #[test]
+    fn check_expand() {
+        let tree = Tree::new("expand", &["a.json", "b.txt", "sub/c.json", "sub/deeper/d.json", "other/e.json"]);
+        let root = tree.0.display().to_string();
+        let expand = |pattern: &str| -> Vec<String> {
+            let files = expand(&format!("{}/{}", root, pattern)).unwrap();
+            files.iter().map(|file| file.strip_prefix(&tree.0).unwrap().display().to_string()).collect()
+        };
+        assert_eq!(expand("*.json"), ["a.json"]);
+        assert_eq!(expand("*/*.json"), ["other/e.json", "sub/c.json"]);
+        assert_eq!(expand("sub/**/*.json"), ["sub/c.json", "sub/deeper/d.json"]);
+        assert_eq!(expand("**/*.json"), ["a.json", "other/e.json", "sub/c.json", "sub/deeper/d.json"]);
+        assert_eq!(expand("[ab].*"), ["a.json", "b.txt"]);
+        assert!(expand("*.yaml").is_empty());
+        assert!(expand("missing/*.json").is_empty());
+        // Paths without wildcards are left as they are, whether they exist or not
+        assert_eq!(super::expand("missing.json").unwrap(), [PathBuf::from("missing.json")]);
+        assert!(super::expand("[a").is_err());
+    }
+
+    #[test]
+    fn check_walk_gitignore() {
+        let tree = Tree::new("gitignore", &[".git/HEAD", "a.log", "keep.log", "src/main.rs", "src/out/x", "src/b.log"]);
+        fs::write(tree.0.join(".gitignore"), "*.log\n!keep.log\n").unwrap();
+        fs::write(tree.0.join("src/.gitignore"), "out/\n!b.log\n").unwrap();
+
+        let options = WalkOptions { gitignore: true, ..WalkOptions::default() };
+        assert_eq!(tree.walk(&options), [".gitignore", "keep.log", "src/.gitignore", "src/b.log", "src/main.rs"]);
+        assert_eq!(tree.walk(&WalkOptions::default()).len(), 8);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn check_walk_symlinks() {
+        let tree = Tree::new("symlinks", &["dir/a.txt", "b.txt"]);
+        std::os::unix::fs::symlink(tree.0.join("dir"), tree.0.join("dir/loop")).unwrap();
+        std::os::unix::fs::symlink(tree.0.join("dir"), tree.0.join("linked")).unwrap();
+        std::os::unix::fs::symlink(tree.0.join("b.txt"), tree.0.join("link.txt")).unwrap();
+        std::os::unix::fs::symlink(tree.0.join("missing"), tree.0.join("dangling")).unwrap();
+
+        let walk = |symlinks| tree.walk(&WalkOptions { symlinks, ..WalkOptions::default() });
+        assert_eq!(walk(Symlinks::Skip), ["b.txt", "dir/a.txt"]);
+        assert_eq!(walk(Symlinks::Files), ["b.txt", "dir/a.txt", "link.txt"]);
+        assert_eq!(walk(Symlinks::Follow), ["b.txt", "dir/a.txt", "link.txt", "linked/a.txt"]);
+    }
+
+    #[test]
+    fn check_walk_missing_root_names_it() {
+        let err = walk(&[PathBuf::from("does/not/exist")], &WalkOptions::default()).unwrap_err();
+        assert!(err.to_string().starts_with("could not read `does/not/exist`"));
+    }
+}
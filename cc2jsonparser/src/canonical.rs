@@ -0,0 +1,245 @@
+// Canonical JSON.
+// The JSON Canonicalization Scheme writes a document one way only, so that the same data hashes
+// and signs the same wherever it was written. There's no whitespace, the members of each object
+// are in order of their keys compared as UTF-16 code units, strings are escaped only as much as
+// JSON needs, and numbers are written as JavaScript writes the doubles they are, so 1E30 is 1e+30,
+// 4.50 is 4.5, and an integer past 2^53 is the nearest double. A key given more than once, or a
+// number beyond what a double can hold, has no canonical form, so those are errors saying where
+// they are.
+// Reference:  https://www.rfc-editor.org/rfc/rfc8785
+use crate::pointer::{escape, location};
+use crate::write::write_string;
+use crate::JsonValue;
+use std::cmp::Ordering;
+use std::iter::Enumerate;
+use std::{fmt, slice, vec};
+
+/// What there's no writing in canonical form, and where it is in the document
+#[derive(Clone, Debug, PartialEq)]
+pub struct CanonicalError {
+    pub message: String,
+}
+
+impl fmt::Display for CanonicalError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for CanonicalError {}
+
+/// The value in canonical form, without a line break at the end
+pub fn to_string_canonical(value: &JsonValue) -> Result<String, CanonicalError> {
+    let mut out = Vec::new();
+    write_canonical(&mut out, value)?;
+    Ok(String::from_utf8(out).expect("JSON written from strings is UTF-8"))
+}
+
+/// An object with its members in order or an array being written, with those still to write and
+/// the key or index of the one being written. The ones it's in are kept on a stack rather than the
+/// call stack, so however deep a document nests it can be written.
+enum Open<'a> {
+    Object(Enumerate<vec::IntoIter<&'a (String, JsonValue)>>, &'a str),
+    Array(Enumerate<slice::Iter<'a, JsonValue>>, usize),
+}
+
+/// The JSON Pointer to the value being written in the innermost of `open`, for errors
+fn pointer(open: &[Open]) -> String {
+    open.iter().map(|open| match open {
+        Open::Object(_, key) => format!("/{}", escape(key)),
+        Open::Array(_, i) => format!("/{}", i),
+    }).collect()
+}
+
+/// Write the value in canonical form
+fn write_canonical(out: &mut Vec<u8>, value: &JsonValue) -> Result<(), CanonicalError> {
+    let mut open: Vec<Open> = Vec::new();
+    let mut next = Some(value);
+    loop {
+        match next.take() {
+            Some(JsonValue::Object(members)) => {
+                let mut members: Vec<_> = members.iter().collect();
+                members.sort_by(|(a, _), (b, _)| utf16_order(a, b));
+                if let Some([(key, _), _]) = members.windows(2).find(|pair| pair[0].0 == pair[1].0) {
+                    return Err(CanonicalError { message: format!("{} has the key `{}` more than once, which canonical JSON doesn't allow", location(&pointer(&open)), key) });
+                }
+                out.push(b'{');
+                open.push(Open::Object(members.into_iter().enumerate(), ""));
+            },
+            Some(JsonValue::Array(elements)) => {
+                out.push(b'[');
+                open.push(Open::Array(elements.iter().enumerate(), 0));
+            },
+            Some(JsonValue::String(string)) => write_string(out, string).expect("writing to a Vec can't fail"),
+            Some(JsonValue::Number(number)) => {
+                let double = number.parse::<f64>().expect("a JSON number is a float");
+                if !double.is_finite() {
+                    return Err(CanonicalError { message: format!("{} is {}, which is more than a double can hold, as canonical JSON numbers are", location(&pointer(&open)), number) });
+                }
+                out.extend(canonical_number(double).as_bytes());
+            },
+            Some(JsonValue::Bool(bool)) => out.extend(bool.to_string().as_bytes()),
+            Some(JsonValue::Null) => out.extend(b"null"),
+            None => {},
+        }
+
+        // Go on to the next member or element of the innermost open object or array, or close it
+        let member = match open.last_mut() {
+            None => return Ok(()),
+            Some(Open::Object(members, at)) => members.next().map(|(i, (key, value))| {
+                *at = key;
+                (i, Some(key), value)
+            }),
+            Some(Open::Array(elements, at)) => elements.next().map(|(i, element)| {
+                *at = i;
+                (i, None, element)
+            }),
+        };
+        let Some((i, key, value)) = member else {
+            match open.pop() {
+                Some(Open::Object(..)) => out.push(b'}'),
+                _ => out.push(b']'),
+            }
+            continue;
+        };
+        if i > 0 {
+            out.push(b',');
+        }
+        if let Some(key) = key {
+            write_string(out, key).expect("writing to a Vec can't fail");
+            out.push(b':');
+        }
+        next = Some(value);
+    }
+}
+
+/// How two keys compare as the UTF-16 code units JavaScript strings are made of, which puts
+/// characters beyond the Basic Multilingual Plane before those from U+E000 up
+fn utf16_order(a: &str, b: &str) -> Ordering {
+    a.encode_utf16().cmp(b.encode_utf16())
+}
+
+/// A finite double written as JavaScript's Number.prototype.toString writes it: the fewest digits
+/// that read back as the same double, in plain notation from 1e-6 up to 1e21 and in exponential
+/// notation with a sign on the exponent beyond them
+fn canonical_number(double: f64) -> String {
+    if double == 0.0 {
+        // Negative zero too
+        return "0".to_string();
+    }
+    let (digits, exponent) = shortest_digits(double.abs());
+    // Where the decimal point goes, after however many of the digits
+    let point = exponent + 1;
+    let count = digits.len() as i32;
+    let written = match point {
+        _ if count <= point && point <= 21 => format!("{}{}", digits, "0".repeat((point - count) as usize)),
+        1..=21 => format!("{}.{}", &digits[..point as usize], &digits[point as usize..]),
+        -5..=0 => format!("0.{}{}", "0".repeat(-point as usize), digits),
+        _ => {
+            let sign = if point > 0 { '+' } else { '-' };
+            match digits.split_at(1) {
+                (first, "") => format!("{}e{}{}", first, sign, (point - 1).abs()),
+                (first, rest) => format!("{}.{}e{}{}", first, rest, sign, (point - 1).abs()),
+            }
+        },
+    };
+    match double.is_sign_negative() {
+        true => format!("-{}", written),
+        false => written,
+    }
+}
+
+/// The fewest significant digits that read back as the double, and the power of ten the first of
+/// them is in. Of two as close to the double as each other, the one ending in an even digit.
+fn shortest_digits(double: f64) -> (String, i32) {
+    let split = |written: &str| {
+        let (mantissa, exponent) = written.split_once('e').expect("{:e} writes an exponent");
+        (mantissa.replace('.', ""), exponent.parse::<i32>().expect("{:e} writes an integer exponent"))
+    };
+    // Rust writes the fewest digits too, as d.ddde±n, but rounds up from halfway
+    let (digits, exponent) = split(&format!("{:e}", double));
+    // Every digit of the double's exact value, of which there are never more than 767
+    let (exact, exact_exponent) = split(&format!("{:.800e}", double));
+    let exact = exact.trim_end_matches('0');
+    let halfway = exact_exponent == exponent && exact.len() == digits.len() + 1 && exact.ends_with('5');
+    let last = digits.as_bytes()[digits.len() - 1];
+    if !halfway || last % 2 == 0 {
+        return (digits, exponent);
+    }
+    // The other one as close, which is even as this one is odd
+    let other = match digits == exact[..digits.len()] {
+        true if last < b'9' => last + 1,
+        true => return (digits, exponent),
+        false => last - 1,
+    };
+    let other = format!("{}{}", &digits[..digits.len() - 1], other as char);
+    match format!("0.{}e{}", other, exponent + 1).parse::<f64>() == Ok(double) {
+        true => (other, exponent),
+        false => (digits, exponent),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_str;
+
+    fn canonical(input: &str) -> Result<String, String> {
+        to_string_canonical(&parse_str(input).unwrap()).map_err(|e| e.message)
+    }
+
+    #[test]
+    fn check_to_string_canonical() {
+        // RFC 8785's examples
+        let input = r#"{
+            "numbers": [333333333.33333329, 1E30, 4.50, 2e-3, 0.000000000000000000000000001],
+            "string": "\u20ac$\u000F\u000aA'\u0042\u0022\u005c\\\"\/",
+            "literals": [null, true, false]
+        }"#;
+        assert_eq!(canonical(input).unwrap(), r#"{"literals":[null,true,false],"numbers":[333333333.3333333,1e+30,4.5,0.002,1e-27],"string":"€$\u000f\nA'B\"\\\\\"/"}"#);
+        let input = r#"{"\u20ac": 1, "\r": 2, "\ufb33": 3, "1": 4, "\ud83d\ude00": 5, "\u0080": 6, "\u00f6": 7}"#;
+        assert_eq!(canonical(input).unwrap(), "{\"\\r\":2,\"1\":4,\"\u{80}\":6,\"ö\":7,\"€\":1,\"😀\":5,\"\u{fb33}\":3}");
+        assert_eq!(canonical(r#"[{"b": [], "a": {}}, -0, 9007199254740993]"#).unwrap(), r#"[{"a":{},"b":[]},0,9007199254740992]"#);
+    }
+
+    #[test]
+    fn check_canonical_number() {
+        // RFC 8785's Appendix B
+        let numbers = [
+            (0x0000000000000000, "0"),
+            (0x8000000000000000, "0"),
+            (0x0000000000000001, "5e-324"),
+            (0x8000000000000001, "-5e-324"),
+            (0x7fefffffffffffff, "1.7976931348623157e+308"),
+            (0xffefffffffffffff, "-1.7976931348623157e+308"),
+            (0x4340000000000000, "9007199254740992"),
+            (0xc340000000000000, "-9007199254740992"),
+            (0x4430000000000000, "295147905179352830000"),
+            (0x44b52d02c7e14af5, "9.999999999999997e+22"),
+            (0x44b52d02c7e14af6, "1e+23"),
+            (0x44b52d02c7e14af7, "1.0000000000000001e+23"),
+            (0x444b1ae4d6e2ef4e, "999999999999999700000"),
+            (0x444b1ae4d6e2ef4f, "999999999999999900000"),
+            (0x444b1ae4d6e2ef50, "1e+21"),
+            (0x3eb0c6f7a0b5ed8c, "9.999999999999997e-7"),
+            (0x3eb0c6f7a0b5ed8d, "0.000001"),
+            (0x41b3de4355555553, "333333333.3333332"),
+            (0x41b3de4355555554, "333333333.33333325"),
+            (0x41b3de4355555555, "333333333.3333333"),
+            (0x41b3de4355555556, "333333333.3333334"),
+            (0x41b3de4355555557, "333333333.33333343"),
+            (0xbecbf647612f3696, "-0.0000033333333333333333"),
+            (0x43143ff3c1cb0959, "1424953923781206.2"),
+        ];
+        for (bits, written) in numbers {
+            assert_eq!(canonical_number(f64::from_bits(bits)), written, "{:016x}", bits);
+        }
+    }
+
+    #[test]
+    fn check_to_string_canonical_fails_for_what_has_no_canonical_form() {
+        assert_eq!(canonical(r#"{"a": 1, "b": 2, "a": 3}"#), Err("the document has the key `a` more than once, which canonical JSON doesn't allow".to_string()));
+        assert_eq!(canonical(r#"{"x": [{"k/y": {"b": 1, "b": 1}}]}"#), Err("`/x/0/k~1y` has the key `b` more than once, which canonical JSON doesn't allow".to_string()));
+        assert_eq!(canonical(r#"[1, -1e400]"#), Err("`/1` is -1e400, which is more than a double can hold, as canonical JSON numbers are".to_string()));
+    }
+}
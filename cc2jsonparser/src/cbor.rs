@@ -0,0 +1,440 @@
+// CBOR.
+// The Concise Binary Object Representation, a binary format like MessagePack that COSE and a lot
+// of IoT protocols are built on. A JsonValue is encoded with the shortest head for each integer
+// and length, integers as integers if 64 bits hold them and floats as 32 bits if that holds them
+// exactly or else 64. Decoding turns CBOR into JSON the way the RFC suggests: byte strings are
+// base64url without padding, bignums are numbers, other tags are passed over for the value they
+// tag, undefined and the other simple values are null, as are NaN and the infinities, and a map
+// key that isn't a text string is written as a string, so COSE's integer keys are "1", "4", and
+// so on. Indefinite-length strings, arrays, and maps are read like any others.
+// Reference:  https://www.rfc-editor.org/rfc/rfc8949
+use crate::{to_string_compact, JsonValue};
+use ccbase64::{Alphabet, Encoder};
+use std::fmt;
+use std::io::Write;
+
+const UNSIGNED: u8 = 0;
+const NEGATIVE: u8 = 1;
+const BYTES: u8 = 2;
+const TEXT: u8 = 3;
+const ARRAY: u8 = 4;
+const MAP: u8 = 5;
+const TAG: u8 = 6;
+const SIMPLE: u8 = 7;
+
+/// The initial byte ending an indefinite-length string, array, or map
+const BREAK: u8 = 0xff;
+
+/// Why bytes aren't a CBOR value, and where in them
+#[derive(Clone, Debug, PartialEq)]
+pub struct CborError {
+    pub message: String,
+    /// How far into the bytes the value that's wrong starts
+    pub offset: usize,
+}
+
+impl fmt::Display for CborError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} at byte {}", self.message, self.offset)
+    }
+}
+
+impl std::error::Error for CborError {}
+
+/// The value encoded as CBOR
+pub fn to_cbor(value: &JsonValue) -> Vec<u8> {
+    let mut out = Vec::new();
+    encode(&mut out, value);
+    out
+}
+
+/// What's still to encode: a value, or the key of the member whose value comes after it
+enum Item<'a> {
+    Value(&'a JsonValue),
+    Key(&'a str),
+}
+
+/// Encode the value, keeping what's left of the objects and arrays it's in on a stack rather than
+/// the call stack, so however deep a document nests it can be encoded
+fn encode(out: &mut Vec<u8>, value: &JsonValue) {
+    let mut waiting = vec![Item::Value(value)];
+    while let Some(item) = waiting.pop() {
+        match item {
+            Item::Value(JsonValue::Object(members)) => {
+                write_head(out, MAP, members.len() as u64);
+                waiting.extend(members.iter().rev().flat_map(|(key, value)| [Item::Value(value), Item::Key(key)]));
+            },
+            Item::Value(JsonValue::Array(elements)) => {
+                write_head(out, ARRAY, elements.len() as u64);
+                waiting.extend(elements.iter().rev().map(Item::Value));
+            },
+            Item::Value(JsonValue::String(string)) => {
+                write_head(out, TEXT, string.len() as u64);
+                out.extend(string.as_bytes());
+            },
+            Item::Value(JsonValue::Number(number)) => write_number(out, number),
+            Item::Value(JsonValue::Bool(false)) => out.push(0xf4),
+            Item::Value(JsonValue::Bool(true)) => out.push(0xf5),
+            Item::Value(JsonValue::Null) => out.push(0xf6),
+            Item::Key(key) => {
+                write_head(out, TEXT, key.len() as u64);
+                out.extend(key.as_bytes());
+            },
+        }
+    }
+}
+
+/// Write the head of a data item of the `major` type, with its argument in the fewest bytes
+fn write_head(out: &mut Vec<u8>, major: u8, argument: u64) {
+    let major = major << 5;
+    match argument {
+        0..=23 => out.push(major | argument as u8),
+        24..=0xff => out.extend([major | 24, argument as u8]),
+        0x100..=0xffff => {
+            out.push(major | 25);
+            out.extend((argument as u16).to_be_bytes());
+        },
+        0x1_0000..=0xffff_ffff => {
+            out.push(major | 26);
+            out.extend((argument as u32).to_be_bytes());
+        },
+        _ => {
+            out.push(major | 27);
+            out.extend(argument.to_be_bytes());
+        },
+    }
+}
+
+/// Write a number as an integer if 64 bits hold it, or else as a float
+fn write_number(out: &mut Vec<u8>, number: &str) {
+    let integer = number.parse::<i128>().ok().filter(|_| !number.contains(['.', 'e', 'E']));
+    match integer {
+        Some(n @ 0..=0xffff_ffff_ffff_ffff) => write_head(out, UNSIGNED, n as u64),
+        Some(n @ -0x1_0000_0000_0000_0000..=-1) => write_head(out, NEGATIVE, (-1 - n) as u64),
+        _ => {
+            let float = number.parse::<f64>().expect("a JSON number is a float");
+            match f64::from(float as f32) == float {
+                true => {
+                    out.push(0xfa);
+                    out.extend((float as f32).to_be_bytes());
+                },
+                false => {
+                    out.push(0xfb);
+                    out.extend(float.to_be_bytes());
+                },
+            }
+        },
+    }
+}
+
+/// Decode the one CBOR value `bytes` hold as JSON, nested no more than `max_depth` deep if there's
+/// a limit, tags counting as nesting too
+pub fn from_cbor(bytes: &[u8], max_depth: Option<usize>) -> Result<JsonValue, CborError> {
+    if bytes.is_empty() {
+        return Err(CborError { message: "did not find anything to decode".to_string(), offset: 0 });
+    }
+    let mut decoder = Decoder { bytes, offset: 0, max_depth, depth: 0 };
+    let value = decoder.decode()?;
+    if decoder.offset < bytes.len() {
+        return Err(decoder.error(decoder.offset, "expected the end of the input after the value"));
+    }
+    Ok(value)
+}
+
+struct Decoder<'a> {
+    bytes: &'a [u8],
+    /// How far into the bytes decoding has got
+    offset: usize,
+    max_depth: Option<usize>,
+    /// How many arrays, maps, and tags are open
+    depth: usize,
+}
+
+impl<'a> Decoder<'a> {
+    fn error(&self, offset: usize, message: impl Into<String>) -> CborError {
+        CborError { message: message.into(), offset }
+    }
+
+    /// The next `length` bytes, of what starts at `start`
+    fn take(&mut self, length: usize, start: usize) -> Result<&'a [u8], CborError> {
+        let bytes = self.bytes.get(self.offset..).and_then(|rest| rest.get(..length));
+        let bytes = bytes.ok_or_else(|| self.error(start, "the input ends partway through a value"))?;
+        self.offset += length;
+        Ok(bytes)
+    }
+
+    /// Whether the next byte is the break ending an indefinite-length item, reading past it if so
+    fn at_break(&mut self, start: usize) -> Result<bool, CborError> {
+        match self.bytes.get(self.offset) {
+            Some(&BREAK) => {
+                self.offset += 1;
+                Ok(true)
+            },
+            Some(_) => Ok(false),
+            None => Err(self.error(start, "the input ends partway through a value")),
+        }
+    }
+
+    /// The major type and argument of the data item starting here, the argument being None for
+    /// an indefinite length, or for the break with major type 7
+    fn read_head(&mut self) -> Result<(u8, Option<u64>), CborError> {
+        let start = self.offset;
+        let initial = self.take(1, start)?[0];
+        let (major, info) = (initial >> 5, initial & 0x1f);
+        let argument = match info {
+            0..=23 => Some(u64::from(info)),
+            24..=27 => Some(self.take(1 << (info - 24), start)?.iter().fold(0, |n, &byte| n << 8 | u64::from(byte))),
+            28..=30 => return Err(self.error(start, format!("additional information {} is reserved", info))),
+            _ => None,
+        };
+        if argument.is_none() && matches!(major, UNSIGNED | NEGATIVE | TAG) {
+            return Err(self.error(start, "only strings, arrays, and maps can have an indefinite length"));
+        }
+        Ok((major, argument))
+    }
+
+    fn decode(&mut self) -> Result<JsonValue, CborError> {
+        let start = self.offset;
+        let (major, argument) = self.read_head()?;
+        let value = match (major, argument) {
+            (UNSIGNED, Some(n)) => JsonValue::Number(n.to_string()),
+            (NEGATIVE, Some(n)) => JsonValue::Number((-1 - i128::from(n)).to_string()),
+            (BYTES, _) => JsonValue::String(base64url(&self.read_string(BYTES, argument, start)?)),
+            (TEXT, _) => {
+                let bytes = self.read_string(TEXT, argument, start)?;
+                JsonValue::String(String::from_utf8(bytes).map_err(|_| self.error(start, "invalid UTF-8 in a text string"))?)
+            },
+            (ARRAY, _) => {
+                self.open(start)?;
+                let mut elements = Vec::new();
+                while self.more(argument, elements.len(), start)? {
+                    elements.push(self.decode()?);
+                }
+                self.depth -= 1;
+                JsonValue::Array(elements)
+            },
+            (MAP, _) => {
+                self.open(start)?;
+                let mut members = Vec::new();
+                while self.more(argument, members.len(), start)? {
+                    let key = match &self.decode()? {
+                        JsonValue::String(key) => key.clone(),
+                        JsonValue::Number(number) => number.clone(),
+                        other => to_string_compact(other),
+                    };
+                    members.push((key, self.decode()?));
+                }
+                self.depth -= 1;
+                JsonValue::Object(members)
+            },
+            (TAG, Some(tag)) => {
+                self.open(start)?;
+                let value = match tag {
+                    // Bignums, unsigned and negative
+                    2 | 3 if self.bytes.get(self.offset).is_some_and(|&initial| initial >> 5 == BYTES) => {
+                        let (_, argument) = self.read_head()?;
+                        let magnitude = self.read_string(BYTES, argument, start)?;
+                        let number = bignum(&magnitude, tag == 3);
+                        JsonValue::Number(if tag == 3 { format!("-{}", number) } else { number })
+                    },
+                    _ => self.decode()?,
+                };
+                self.depth -= 1;
+                value
+            },
+            (SIMPLE, Some(_)) => self.decode_simple(start)?,
+            (SIMPLE, None) => return Err(self.error(start, "a break with nothing to end")),
+            _ => unreachable!("a major type is 3 bits, and only strings, arrays, and maps have indefinite lengths"),
+        };
+        Ok(value)
+    }
+
+    /// Whether there's another element or member to read of an array or map of `length`, or of
+    /// an indefinite length if that's None, after the `read` ones so far
+    fn more(&mut self, length: Option<u64>, read: usize, start: usize) -> Result<bool, CborError> {
+        match length {
+            Some(length) => Ok((read as u64) < length),
+            None => Ok(!self.at_break(start)?),
+        }
+    }
+
+    /// The bytes of a byte or text string, the `major` type, of `length` or else of an indefinite
+    /// length made up of chunks
+    fn read_string(&mut self, major: u8, length: Option<u64>, start: usize) -> Result<Vec<u8>, CborError> {
+        if let Some(length) = length {
+            let length = usize::try_from(length).map_err(|_| self.error(start, "the input ends partway through a value"))?;
+            return Ok(self.take(length, start)?.to_vec());
+        }
+        let mut bytes = Vec::new();
+        while !self.at_break(start)? {
+            let chunk = self.offset;
+            match self.read_head()? {
+                (chunk_major, Some(length)) if chunk_major == major => bytes.extend(self.read_string(major, Some(length), chunk)?),
+                _ => return Err(self.error(chunk, "each chunk of an indefinite-length string has to be a string of the same type")),
+            }
+        }
+        Ok(bytes)
+    }
+
+    /// A float or simple value, whose head was just read as starting at `start`
+    fn decode_simple(&mut self, start: usize) -> Result<JsonValue, CborError> {
+        let bytes = &self.bytes[start + 1..self.offset];
+        let value = match (self.bytes[start] & 0x1f, bytes) {
+            (20, _) => JsonValue::Bool(false),
+            (21, _) => JsonValue::Bool(true),
+            (25, &[high, low]) => {
+                let float = f16_to_f32(u16::from_be_bytes([high, low]));
+                float_value(float.into(), || format!("{:?}", float))
+            },
+            (26, bytes) => {
+                let float = f32::from_be_bytes(bytes.try_into().expect("a 32-bit float's head has 4 bytes"));
+                float_value(float.into(), || format!("{:?}", float))
+            },
+            (27, bytes) => {
+                let float = f64::from_be_bytes(bytes.try_into().expect("a 64-bit float's head has 8 bytes"));
+                float_value(float, || format!("{:?}", float))
+            },
+            // Null, undefined, and the simple values with no meaning given
+            _ => JsonValue::Null,
+        };
+        Ok(value)
+    }
+
+    fn open(&mut self, start: usize) -> Result<(), CborError> {
+        if let Some(max_depth) = self.max_depth.filter(|&max| self.depth == max) {
+            return Err(self.error(start, format!("nested more than {} deep", max_depth)));
+        }
+        self.depth += 1;
+        Ok(())
+    }
+}
+
+/// The bytes in base64url without padding, as RFC 8949 turns byte strings into JSON
+fn base64url(bytes: &[u8]) -> String {
+    let mut encoder = Encoder::new(Vec::new(), Alphabet::UrlSafe, 0);
+    encoder.write_all(bytes).expect("writing to a Vec can't fail");
+    let encoded = encoder.finish().expect("writing to a Vec can't fail");
+    String::from_utf8(encoded).expect("base64 is ASCII").trim_end_matches('=').to_string()
+}
+
+/// The bignum with the big-endian `magnitude` in decimal, one more than it if `plus_one`, as a
+/// negative bignum is -1 minus its magnitude
+fn bignum(magnitude: &[u8], plus_one: bool) -> String {
+    // Least significant digit first
+    let mut decimal: Vec<u32> = vec![0];
+    let add = |decimal: &mut Vec<u32>, multiplier: u32, mut carry: u32| {
+        for place in decimal.iter_mut() {
+            let value = *place * multiplier + carry;
+            *place = value % 10;
+            carry = value / 10;
+        }
+        while carry > 0 {
+            decimal.push(carry % 10);
+            carry /= 10;
+        }
+    };
+    for &byte in magnitude {
+        add(&mut decimal, 256, u32::from(byte));
+    }
+    if plus_one {
+        add(&mut decimal, 1, 1);
+    }
+    while decimal.len() > 1 && decimal.last() == Some(&0) {
+        decimal.pop();
+    }
+    decimal.iter().rev().map(|&digit| char::from_digit(digit, 10).expect("each place is a digit")).collect()
+}
+
+/// A half-precision float as a single-precision one, which holds every half exactly
+fn f16_to_f32(bits: u16) -> f32 {
+    let exponent = i32::from((bits >> 10) & 0x1f);
+    let mantissa = f32::from(bits & 0x3ff);
+    let magnitude = match exponent {
+        0 => mantissa * 2f32.powi(-24),
+        31 if mantissa == 0.0 => f32::INFINITY,
+        31 => f32::NAN,
+        _ => (mantissa + 1024.0) * 2f32.powi(exponent - 25),
+    };
+    if bits & 0x8000 != 0 {
+        -magnitude
+    } else {
+        magnitude
+    }
+}
+
+/// The JSON value of a float, null if JSON has no way to write it
+fn float_value(float: f64, written: impl FnOnce() -> String) -> JsonValue {
+    match float.is_finite() {
+        true => JsonValue::Number(written()),
+        false => JsonValue::Null,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_str;
+
+    fn number(n: &str) -> JsonValue {
+        JsonValue::Number(n.to_string())
+    }
+
+    #[test]
+    fn check_to_cbor() {
+        // Examples from RFC 8949's Appendix A
+        let encoded = |input: &str| to_cbor(&parse_str(input).unwrap());
+        assert_eq!(encoded("[0, 23, 24, 100, 1000, 1000000, -1, -100, -1000]"), [
+            0x89, 0x00, 0x17, 0x18, 0x18, 0x18, 0x64, 0x19, 0x03, 0xe8, 0x1a, 0x00, 0x0f, 0x42, 0x40, 0x20, 0x38, 0x63, 0x39, 0x03, 0xe7,
+        ]);
+        assert_eq!(encoded("1000000000000"), [0x1b, 0x00, 0x00, 0x00, 0xe8, 0xd4, 0xa5, 0x10, 0x00]);
+        assert_eq!(encoded("18446744073709551615"), [0x1b, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff]);
+        assert_eq!(encoded("-18446744073709551616"), [0x3b, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff]);
+        assert_eq!(encoded("1.1"), [0xfb, 0x3f, 0xf1, 0x99, 0x99, 0x99, 0x99, 0x99, 0x9a]);
+        assert_eq!(encoded("100000.0"), [0xfa, 0x47, 0xc3, 0x50, 0x00]);
+        assert_eq!(encoded("[false, true, null]"), [0x83, 0xf4, 0xf5, 0xf6]);
+        assert_eq!(encoded(r#"{"a": 1, "b": [2, "IETF"]}"#), [0xa2, 0x61, b'a', 0x01, 0x61, b'b', 0x82, 0x02, 0x64, b'I', b'E', b'T', b'F']);
+    }
+
+    #[test]
+    fn check_from_cbor() {
+        let value = parse_str(r#"{"name": "café", "n": [0, 23, -24, -25, 65536, -4294967297, 18446744073709551615, 0.5, 1.1, false, null], "m": {}}"#).unwrap();
+        assert_eq!(from_cbor(&to_cbor(&value), None), Ok(value));
+        // Half-precision floats, and JSON's null for what it can't write
+        assert_eq!(from_cbor(&[0xf9, 0xc4, 0x00], None), Ok(number("-4.0")));
+        assert_eq!(from_cbor(&[0xf9, 0x00, 0x01], None), Ok(number("5.9604645e-8")));
+        assert_eq!(from_cbor(&[0xf9, 0x7c, 0x00], None), Ok(JsonValue::Null));
+        assert_eq!(from_cbor(&[0xf7], None), Ok(JsonValue::Null));
+        // Bignums, and the value any other tag tags
+        assert_eq!(from_cbor(&[0xc2, 0x49, 0x01, 0, 0, 0, 0, 0, 0, 0, 0], None), Ok(number("18446744073709551616")));
+        assert_eq!(from_cbor(&[0xc3, 0x49, 0x01, 0, 0, 0, 0, 0, 0, 0, 0], None), Ok(number("-18446744073709551617")));
+        assert_eq!(from_cbor(&[0xc1, 0x1a, 0x51, 0x4b, 0x67, 0xb0], None), Ok(number("1363896240")));
+        // Byte strings as base64url, and keys that aren't text strings as strings
+        assert_eq!(from_cbor(&[0x44, 0xfb, 0xff, 0x01, 0x02], None), Ok(JsonValue::String("-_8BAg".to_string())));
+        assert_eq!(from_cbor(&[0xa2, 0x01, 0x26, 0xf5, 0x80], None), Ok(parse_str(r#"{"1": -7, "true": []}"#).unwrap()));
+    }
+
+    #[test]
+    fn check_from_cbor_reads_indefinite_lengths() {
+        assert_eq!(from_cbor(&[0x9f, 0xff], None), Ok(JsonValue::Array(Vec::new())));
+        assert_eq!(from_cbor(&[0x9f, 0x01, 0x82, 0x02, 0x03, 0x9f, 0x04, 0x05, 0xff, 0xff], None), Ok(parse_str("[1, [2, 3], [4, 5]]").unwrap()));
+        assert_eq!(from_cbor(&[0xbf, 0x61, b'a', 0x01, 0x61, b'b', 0x9f, 0x02, 0x03, 0xff, 0xff], None), Ok(parse_str(r#"{"a": 1, "b": [2, 3]}"#).unwrap()));
+        assert_eq!(from_cbor(b"\x7f\x65strea\x65mingx\xff", None), Ok(JsonValue::String("streamingx".to_string())));
+        assert_eq!(from_cbor(&[0x5f, 0x42, 0x01, 0x02, 0x43, 0x03, 0x04, 0x05, 0xff], None), Ok(JsonValue::String("AQIDBAU".to_string())));
+    }
+
+    #[test]
+    fn check_from_cbor_fails_for_what_is_not_cbor() {
+        let error = |bytes: &[u8]| from_cbor(bytes, Some(2)).unwrap_err().to_string();
+        assert_eq!(error(&[]), "did not find anything to decode at byte 0");
+        assert_eq!(error(&[0x82, 0x01]), "the input ends partway through a value at byte 2");
+        assert_eq!(error(&[0x63, b'a']), "the input ends partway through a value at byte 0");
+        assert_eq!(error(&[0x9f, 0x01]), "the input ends partway through a value at byte 0");
+        assert_eq!(error(&[0x81, 0x1c]), "additional information 28 is reserved at byte 1");
+        assert_eq!(error(&[0x1f]), "only strings, arrays, and maps can have an indefinite length at byte 0");
+        assert_eq!(error(&[0x81, 0xff]), "a break with nothing to end at byte 1");
+        assert_eq!(error(&[0x7f, 0x41, b'a', 0xff]), "each chunk of an indefinite-length string has to be a string of the same type at byte 1");
+        assert_eq!(error(&[0x61, 0xff]), "invalid UTF-8 in a text string at byte 0");
+        assert_eq!(error(&[0xf6, 0xf6]), "expected the end of the input after the value at byte 1");
+        assert_eq!(error(&[0x81, 0xc1, 0x80]), "nested more than 2 deep at byte 2");
+    }
+}
@@ -0,0 +1,1193 @@
+use crate::{
+    apply_patch, check_ndjson_reader, diff, merge_patch, check_reader_with, decode_reader, parse_reader, parse_reader_with, parse_str_with, read_stats, repair_str, repl, validate_reader, from_cbor, from_msgpack, to_cbor, to_msgpack, to_string_canonical, to_string_compact, to_string_csv, to_string_toml, to_json_patch, write_commented, write_flat, write_json, write_yaml, Change, DuplicateKey, Encoding, Filter, JsonError, JsonPath,
+    JsonPointer, JsonValue, LineCol, Loss, Number, NumberWarning, ParseOptions, Schema, TrailingComma, Violation, Warnings, WriteOptions, DEFAULT_MAX_DEPTH,
+};
+use cc_cli::{FormatArgs, OutputArgs, Report, ReportError, BROKEN_PIPE_EXIT_CODE};
+use cc_diagnostics::{ColorChoice, Diagnostic, Severity, Style};
+use cc_io::{OpenError, Source};
+use cc_walk::{Glob, WalkArgs, WalkOptions};
+use clap::{ArgGroup, CommandFactory, Parser, Subcommand};
+use std::{
+    collections::HashMap,
+    ffi::OsString,
+    fs,
+    hash::{DefaultHasher, Hasher},
+    io::{self, BufRead, BufWriter, Cursor, IsTerminal, Read, Write},
+    path::{Path, PathBuf},
+    process::ExitCode,
+    slice,
+    str::FromStr,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc,
+    },
+    thread,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+/// The exit status when --pointer doesn't point to anything, told apart from the document not
+/// parsing
+const POINTER_NOT_FOUND_EXIT_CODE: u8 = 3;
+
+/// How often --watch looks at the files for changes
+const WATCH_INTERVAL: Duration = Duration::from_millis(250);
+
+/// What --to writes the document as, rather than JSON
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Target {
+    Yaml,
+    Toml,
+    Csv,
+    Msgpack,
+    Cbor,
+}
+
+impl FromStr for Target {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Target, String> {
+        match s {
+            "yaml" => Ok(Target::Yaml),
+            "toml" => Ok(Target::Toml),
+            "csv" => Ok(Target::Csv),
+            "msgpack" => Ok(Target::Msgpack),
+            "cbor" => Ok(Target::Cbor),
+            _ => Err(format!("unknown format `{}`, expected yaml, toml, csv, msgpack, or cbor", s)),
+        }
+    }
+}
+
+/// What --from reads the input as, rather than JSON
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Binary {
+    Msgpack,
+    Cbor,
+}
+
+impl FromStr for Binary {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Binary, String> {
+        match s {
+            "msgpack" => Ok(Binary::Msgpack),
+            "cbor" => Ok(Binary::Cbor),
+            _ => Err(format!("unknown format `{}`, expected msgpack or cbor", s)),
+        }
+    }
+}
+
+/// jsonparser - check that files are JSON, and format or query them
+#[derive(Parser)]
+// --format is --output too, as editors and CI setups written for other JSON linters ask for it
+#[command(
+    group(ArgGroup::new("writes").args(["pretty", "compact", "filter", "path", "pointer", "to", "canonicalize", "flatten", "repair"]).multiple(true).conflicts_with("quiet")),
+    mut_arg("format", |arg| arg.visible_alias("output")),
+    args_conflicts_with_subcommands = true
+)]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// The paths to the files to read, or stdin if there are none (it must not be a tty), which -
+    /// stands for too. Glob patterns like `configs/**/*.json` are expanded. With more than one
+    /// file, each gets an OK or error line saying how it went
+    files: Vec<PathBuf>,
+
+    /// Check every .json file under DIR too, ending with how many were valid and which weren't.
+    /// Can be given more than once
+    #[arg(short, long, value_name = "DIR", conflicts_with = "writes")]
+    recursive: Vec<PathBuf>,
+
+    #[command(flatten)]
+    walk: WalkArgs,
+
+    /// Check this many files at once when given more than one, or 0 for one at a time per CPU
+    #[arg(short, long, value_name = "N", default_value_t = 0)]
+    jobs: usize,
+
+    /// Report every error in the file rather than stopping at the first
+    #[arg(long, conflicts_with = "writes")]
+    all_errors: bool,
+
+    /// Read each line as a JSON document of its own, as in NDJSON or JSON Lines, reporting every
+    /// line that isn't one
+    #[arg(long, conflicts_with_all = ["writes", "schema", "all_errors"])]
+    ndjson: bool,
+
+    /// Fail when an object has the same key more than once, rather than only warning about it
+    #[arg(long)]
+    deny_duplicate_keys: bool,
+
+    /// Fail on objects and arrays nested more than this deep, or 0 for no limit
+    #[arg(long, value_name = "N", default_value_t = DEFAULT_MAX_DEPTH)]
+    max_depth: usize,
+
+    /// Fail on a byte order mark at the start of the input, rather than skipping it with a
+    /// warning
+    #[arg(long)]
+    strict: bool,
+
+    /// Skip `//` and `/* */` comments like whitespace, as in JSONC files like tsconfig.json and
+    /// VS Code's settings.json
+    #[arg(long)]
+    allow_comments: bool,
+
+    /// Accept a comma after the last member of an object or element of an array, as in
+    /// `[1, 2,]`, warning about each rather than failing
+    #[arg(long, visible_alias = "lenient")]
+    allow_trailing_commas: bool,
+
+    /// Read the input as JSON5, with comments, trailing commas, single-quoted and multi-line
+    /// strings, unquoted keys, and hex numbers, `Infinity`, and `NaN`. It's written back out as
+    /// JSON, Infinity and NaN as null.
+    #[arg(long, conflicts_with_all = ["ndjson", "keep_comments"])]
+    json5: bool,
+
+    /// Read integers too big for 64 bits exactly, as 128-bit IDs need, rather than warning that a
+    /// program reading them as floats rounds them. Numbers with more digits than a float keeps are
+    /// warned about either way.
+    #[arg(long)]
+    bignum: bool,
+
+    /// Read the input as msgpack (MessagePack) or cbor (CBOR) instead, checking or writing the JSON
+    /// it stands for. MessagePack's binary data, extension types, and keys that aren't strings have
+    /// none and fail, while CBOR's byte strings are read as base64url and its tags are passed over
+    #[arg(long, value_name = "FORMAT", conflicts_with_all = ["ndjson", "json5", "allow_comments", "allow_trailing_commas", "strict", "encoding"])]
+    from: Option<Binary>,
+
+    /// Read the input as utf-8, utf-16le, utf-16be, utf-32le, or utf-32be, rather than telling
+    /// which from its first bytes
+    #[arg(long, value_name = "ENCODING")]
+    encoding: Option<Encoding>,
+
+    /// Write the document to stdout indented, instead of saying it parsed
+    #[arg(long)]
+    pretty: bool,
+
+    /// Write the document to stdout on one line without any whitespace, instead of saying it
+    /// parsed
+    #[arg(long, conflicts_with = "pretty")]
+    compact: bool,
+
+    /// Write the values this jq filter picks out of the document instead, e.g. `.items[0].name`
+    /// or `.[] | .id`. They're indented unless --compact is given
+    #[arg(long, value_name = "EXPR")]
+    filter: Option<Filter>,
+
+    /// Write the values this JSONPath query selects from the document instead, one per line,
+    /// e.g. `$.store.book[*].author`. They're written compactly unless --pretty is given
+    #[arg(long, value_name = "QUERY", conflicts_with = "filter")]
+    path: Option<JsonPath>,
+
+    /// Write the one value this JSON Pointer points to instead, e.g. `/items/0/name`, exiting
+    /// with 3 if there's nothing there. It's indented unless --compact is given
+    #[arg(long, value_name = "POINTER", conflicts_with_all = ["filter", "path"])]
+    pointer: Option<JsonPointer>,
+
+    /// Write the document to stdout as yaml, toml, csv, msgpack (MessagePack), or cbor instead, for
+    /// scripts that need it in both. Each value --filter, --path, or --pointer picks out is a YAML
+    /// document of its own. It fails on what TOML has no way to write, like null or a document that
+    /// isn't an object, and CSV is only for an array of objects with nothing nested in them, a row
+    /// for each
+    #[arg(long, value_name = "FORMAT", conflicts_with_all = ["compact", "indent_tab", "keep_comments"])]
+    to: Option<Target>,
+
+    /// Write the document to stdout in the canonical form of RFC 8785 (JCS) instead, with no
+    /// whitespace, keys in order, and numbers as JavaScript writes them, so the same data hashes
+    /// and signs the same. There's no line break at the end, only between the values --filter,
+    /// --path, or --pointer picks out. A key given more than once has no canonical form and fails
+    #[arg(long, conflicts_with_all = ["pretty", "compact", "indent", "indent_tab", "to", "keep_comments"])]
+    canonicalize: bool,
+
+    /// Write a `path = value` line to stdout for each string, number, boolean, null, and empty
+    /// object or array in the document instead, like `user.addresses[0].city = "Oslo"`, for
+    /// grepping or comparing with environment-variable style settings
+    #[arg(long, conflicts_with_all = ["pretty", "compact", "indent", "indent_tab", "to", "canonicalize", "keep_comments"])]
+    flatten: bool,
+
+    /// Write the document back out as JSON, indented unless --compact is given, repairing what's
+    /// commonly wrong with JSON written by hand first: trailing commas, strings in single quotes,
+    /// keys without quotes, comments, and objects and arrays left open at the end. Each repair is
+    /// noted on stderr, and anything else wrong still fails
+    #[arg(long, conflicts_with_all = ["json5", "from", "keep_comments"])]
+    repair: bool,
+
+    /// Indent each level of nesting by this many spaces
+    #[arg(long, value_name = "N", default_value_t = 2, requires = "writes", conflicts_with = "compact")]
+    indent: usize,
+
+    /// Indent each level of nesting by a tab instead
+    #[arg(long, requires = "writes", conflicts_with_all = ["indent", "compact"])]
+    indent_tab: bool,
+
+    /// Write the members of each object in order of their keys, so documents written from the
+    /// same data come out the same
+    #[arg(long, requires = "writes")]
+    sort_keys: bool,
+
+    /// Write the comments allowed by --allow-comments back out with --pretty, rather than
+    /// leaving them out
+    #[arg(long, requires_all = ["allow_comments", "pretty"], conflicts_with_all = ["filter", "path", "pointer", "sort_keys"])]
+    keep_comments: bool,
+
+    /// Count the objects, arrays, strings, numbers, booleans, nulls, and keys in the document as
+    /// it's read instead, with how deep it nests and where its longest string and array are
+    #[arg(long, conflicts_with_all = ["writes", "all_errors", "ndjson", "schema", "quiet", "format"])]
+    stats: bool,
+
+    /// Check the files again each time one of them changes, printing a line for each check with the
+    /// time it was made, in UTC, until interrupted. The files are polled rather than waited on for
+    /// the system to say they've changed: a few times a second each file's modification time and
+    /// size are looked at, and what's in it hashed, to tell whether it has
+    #[arg(long, conflicts_with_all = ["writes", "recursive", "stats", "format"])]
+    watch: bool,
+
+    /// Read JSON snippets typed or pasted in one after another instead, writing each back out or
+    /// showing what's wrong with it, for trying out the grammar. Commands starting with `:` write
+    /// snippets compactly or as their tokens, or query them; :help lists them. At a terminal, lines
+    /// can be edited as they're typed, and the up and down arrows bring back those entered before
+    #[arg(long, conflicts_with_all = ["files", "writes", "recursive", "watch", "stats", "format", "all_errors", "ndjson", "schema", "from", "keep_comments"])]
+    repl: bool,
+
+    /// Check the document against this JSON Schema too, reporting every way it doesn't match.
+    /// A schema that can't be read or used exits with 2
+    #[arg(long, value_name = "FILE", conflicts_with_all = ["writes", "all_errors"])]
+    schema: Option<PathBuf>,
+
+    #[command(flatten)]
+    output: OutputArgs,
+
+    #[command(flatten)]
+    format: FormatArgs,
+}
+
+/// What to do with documents other than check and write them, each subcommand reading its own
+#[derive(Subcommand)]
+enum Command {
+    /// Compare two documents by the values they parse to, rather than how they're written
+    ///
+    /// Each difference is printed at the JSON Pointer to it: + for what's added, - for what's
+    /// removed, and ~ for what's changed. Exits with 1 if they differ and 2 if either can't be read
+    Diff {
+        /// The document to compare from, or - for stdin
+        from: PathBuf,
+
+        /// The document to compare it to, or - for stdin
+        to: PathBuf,
+
+        /// Write the differences as a JSON Patch that turns the first document into the second
+        /// instead, for the patch command to apply
+        #[arg(long)]
+        json_patch: bool,
+
+        #[command(flatten)]
+        output: OutputArgs,
+    },
+
+    /// Apply an RFC 7386 merge patch to a document, writing the patched document
+    ///
+    /// Each member of the patch replaces the document's member with its key, an object being merged
+    /// into the object it replaces in turn, and null removes the member. Any patch but an object
+    /// replaces the whole document. Exits with 2 if either can't be read
+    MergePatch {
+        /// The document to patch, or - for stdin
+        target: PathBuf,
+
+        /// The merge patch to apply to it, or - for stdin
+        patch: PathBuf,
+
+        /// Write the patched document on one line without any whitespace, rather than indented
+        #[arg(long)]
+        compact: bool,
+
+        /// When to color the output: auto, always, or never
+        #[arg(long, value_name = "WHEN", default_value = "auto")]
+        color: ColorChoice,
+    },
+
+    /// Apply an RFC 6902 JSON Patch to a document, writing the patched document
+    ///
+    /// The patch is an array of add, remove, replace, move, copy, and test operations, applied in
+    /// turn. `diff --json-patch` writes one from two documents. Exits with 1 if an operation can't
+    /// be applied or a test fails, writing nothing, and 2 if either document can't be read
+    Patch {
+        /// The document to patch, or - for stdin
+        target: PathBuf,
+
+        /// The JSON Patch to apply to it, or - for stdin
+        patch: PathBuf,
+
+        /// Write the patched document on one line without any whitespace, rather than indented
+        #[arg(long)]
+        compact: bool,
+
+        /// When to color the output: auto, always, or never
+        #[arg(long, value_name = "WHEN", default_value = "auto")]
+        color: ColorChoice,
+    },
+}
+
+impl Cli {
+    /// What to write for each level of nesting, or None to write the document compactly
+    fn indent(&self) -> Option<String> {
+        let compact = self.compact || (self.path.is_some() && !self.pretty);
+        match (compact, self.indent_tab) {
+            (true, _) => None,
+            (false, true) => Some("\t".to_string()),
+            (false, false) => Some(" ".repeat(self.indent)),
+        }
+    }
+
+    /// Print how checking went to stdout, unless --quiet says not to
+    fn say(&self, message: &str) {
+        if !self.output.quiet {
+            println!("{}", message);
+        }
+    }
+
+    fn parse_options(&self) -> ParseOptions {
+        ParseOptions {
+            max_depth: Some(self.max_depth).filter(|&max| max != 0),
+            allow_bom: !self.strict,
+            allow_comments: self.allow_comments,
+            allow_trailing_commas: self.allow_trailing_commas,
+            json5: self.json5,
+            bignum: self.bignum,
+        }
+    }
+
+    /// Whether to write the document back out
+    fn writes_document(&self) -> bool {
+        self.pretty || self.compact || self.filter.is_some() || self.path.is_some() || self.pointer.is_some() || self.to.is_some() || self.canonicalize || self.flatten || self.repair
+    }
+}
+
+/// Run the validator with the given command line, `args[0]` being the program name
+pub fn main<I, T>(args: I) -> ExitCode
+where
+    I: IntoIterator<Item = T>,
+    T: Into<OsString> + Clone,
+{
+    let args: Cli = cc_cli::parse_from(args, env!("CARGO_PKG_VERSION"));
+    if let Some(command) = &args.command {
+        return run_command(command);
+    }
+    if args.repl {
+        let stdin = io::stdin();
+        let interactive = stdin.is_terminal();
+        let color = args.output.color.should_color(&io::stdout());
+        return match repl::run(stdin.lock(), &mut io::stdout().lock(), &args.parse_options(), interactive, color) {
+            Ok(()) => ExitCode::from(0),
+            Err(e) if e.kind() == io::ErrorKind::BrokenPipe => ExitCode::from(BROKEN_PIPE_EXIT_CODE),
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                ExitCode::from(1)
+            },
+        };
+    }
+    let sources: Vec<Source> = match input_paths(&args) {
+        Ok(paths) => paths.into_iter().map(Source::from).collect(),
+        Err(e) => {
+            eprintln!("{}", e);
+            return ExitCode::from(1);
+        },
+    };
+
+    // There's nothing to read from a terminal, so show how to give it something instead
+    if sources.iter().any(Source::is_interactive) {
+        Cli::command().print_help().unwrap();
+        return ExitCode::from(2);
+    }
+    let schema = match args.schema.as_deref().map(read_schema).transpose() {
+        Ok(schema) => schema,
+        Err((name, message)) => {
+            report(&Diagnostic::error(message), &name, args.output.color);
+            return ExitCode::from(2);
+        },
+    };
+    if args.watch {
+        return watch_files(&args, &sources, schema.as_ref());
+    }
+    if args.format.is_report() {
+        if args.writes_document() {
+            let message = "--format json and sarif report whether the document parsed, they cannot be used with --pretty, --compact, --filter, --path, --pointer, --to, --canonicalize, --flatten, or --repair";
+            Cli::command().error(clap::error::ErrorKind::ArgumentConflict, message).exit();
+        }
+        return print_report(&args, &sources, schema.as_ref());
+    }
+    let source = match sources.as_slice() {
+        [source] if args.recursive.is_empty() => source,
+        _ => {
+            if args.stats {
+                let message = "--stats counts what's in one document, it cannot be used with more than one file";
+                Cli::command().error(clap::error::ErrorKind::ArgumentConflict, message).exit();
+            }
+            if args.writes_document() {
+                let message = "--pretty, --compact, --filter, --path, --pointer, --to, --canonicalize, --flatten, and --repair write one document, they cannot be used with more than one file";
+                Cli::command().error(clap::error::ErrorKind::ArgumentConflict, message).exit();
+            }
+            return check_files(&args, &sources, schema.as_ref());
+        },
+    };
+    let (input_name, mut reader) = match open_document(source, &args) {
+        Ok(input) => input,
+        Err(e) => {
+            eprintln!("{}", e);
+            return ExitCode::from(1);
+        },
+    };
+
+    if let Some(warning) = bom_warning(&args, &mut reader) {
+        report(&warning, &input_name, args.output.color);
+    }
+    if args.stats {
+        return match read_stats(reader, &args.parse_options()) {
+            Ok(stats) => match io::stdout().lock().write_all(stats.to_string().as_bytes()) {
+                Err(e) if e.kind() == io::ErrorKind::BrokenPipe => ExitCode::from(BROKEN_PIPE_EXIT_CODE),
+                _ => ExitCode::from(0),
+            },
+            Err(e) => {
+                if let JsonError::Parse(_) = e {
+                    args.say("Parse failed");
+                }
+                report_error(&e, &input_name, &reread(source, Some(&args), slice::from_ref(&e)), args.output.color);
+                ExitCode::from(1)
+            },
+        };
+    }
+    if args.all_errors || args.ndjson {
+        let Checked { errors, warnings, .. } = check(&args, reader, None);
+        let denied = args.deny_duplicate_keys && !warnings.duplicates.is_empty();
+        if errors.is_empty() && !denied {
+            args.say("Parse successful");
+            report_warnings(&args, &warnings, &input_name);
+            return ExitCode::from(0);
+        }
+        if denied || errors.iter().any(|e| matches!(e, JsonError::Parse(_))) {
+            args.say("Parse failed");
+        }
+        let input = reread(source, Some(&args), &errors);
+        for e in &errors {
+            report_error(e, &input_name, &input, args.output.color);
+        }
+        report_warnings(&args, &warnings, &input_name);
+        return ExitCode::from(1);
+    }
+
+    // The comments are written from the input as it is, and repairs are made to it as it is, so all
+    // of it is kept for them
+    let whole = match args.keep_comments || args.repair {
+        true => match io::read_to_string(&mut reader) {
+            Ok(input) => Some(input),
+            Err(e) => {
+                eprintln!("could not read `{}`: {}", input_name, e);
+                return ExitCode::from(1);
+            },
+        },
+        false => None,
+    };
+    // Nothing needs the document kept when only checking its syntax, so it's checked as it's read
+    let keep = args.writes_document() || schema.is_some();
+    let parsed = match (&whole, keep) {
+        (Some(input), _) if args.repair => repair_str(input, &args.parse_options()).map(|repaired| {
+            for repair in &repaired.repairs {
+                let name = format!("{}:{}", input_name, repair.position);
+                report(&Diagnostic::new(Severity::Note, &repair.message), &name, args.output.color);
+            }
+            (Some(repaired.value), repaired.warnings)
+        }),
+        (Some(input), _) => parse_str_with(input, &args.parse_options()).map(|(value, warnings)| (Some(value), warnings)),
+        (None, true) => parse_reader_with(reader, &args.parse_options()).map(|(value, warnings)| (Some(value), warnings)),
+        (None, false) => validate_reader(reader, &args.parse_options()).map(|warnings| (None, warnings)),
+    };
+    let (value, warnings) = match parsed {
+        Ok(parsed) => parsed,
+        Err(e) => {
+            if let JsonError::Parse(_) = e {
+                args.say("Parse failed");
+            }
+            let reread_input;
+            let input = match &whole {
+                Some(input) => input,
+                None => {
+                    reread_input = reread(source, Some(&args), slice::from_ref(&e));
+                    &reread_input
+                },
+            };
+            report_error(&e, &input_name, input, args.output.color);
+            return ExitCode::from(1);
+        },
+    };
+    if args.deny_duplicate_keys && !warnings.duplicates.is_empty() {
+        if !args.writes_document() {
+            args.say("Parse failed");
+        }
+        report_warnings(&args, &warnings, &input_name);
+        return ExitCode::from(1);
+    }
+    report_warnings(&args, &warnings, &input_name);
+
+    if !args.writes_document() {
+        args.say("Parse successful");
+        let (Some(schema), Some(value)) = (schema, value) else {
+            return ExitCode::from(0);
+        };
+        let violations = schema.validate(&value);
+        if violations.is_empty() {
+            args.say("Matches the schema");
+            return ExitCode::from(0);
+        }
+        args.say("Does not match the schema");
+        for violation in violations {
+            let [instance, schema] = violation.locations();
+            report(&Diagnostic::error(violation.message).with_note(instance).with_note(schema), &input_name, args.output.color);
+        }
+        return ExitCode::from(1);
+    }
+
+    let mut value = value.expect("the document is kept when it's written");
+    if args.sort_keys {
+        value.sort_keys();
+    }
+    let values = match &args.filter {
+        Some(filter) => match filter.apply(&value) {
+            Ok(values) => values,
+            Err(e) => {
+                report(&Diagnostic::error(e.message), &input_name, args.output.color);
+                return ExitCode::from(1);
+            },
+        },
+        None => match (&args.path, &args.pointer) {
+            (Some(path), _) => path.query(&value),
+            (None, Some(pointer)) => match pointer.resolve(&value) {
+                Some(value) => vec![value],
+                None => {
+                    let message = format!("nothing at pointer `{}`", pointer);
+                    report(&Diagnostic::error(message), &input_name, args.output.color);
+                    return ExitCode::from(POINTER_NOT_FOUND_EXIT_CODE);
+                },
+            },
+            (None, None) => vec![&value],
+        },
+    };
+    let indent = args.indent();
+    let options = WriteOptions { indent: indent.as_deref(), color: args.output.color.should_color(&io::stdout()) };
+    let commented = whole.as_deref().filter(|_| args.keep_comments);
+    let printed = match (commented, args.to) {
+        (Some(input), _) => print_commented(input, options),
+        (None, Some(Target::Yaml)) => print_yaml(&values),
+        (None, None) if args.flatten => print_flat(&values),
+        (None, None) if !args.canonicalize => print_values(&values, options),
+        (None, to) => match to.map_or_else(|| canonicalize(&values), |target| convert(&values, target)) {
+            Ok(converted) => print_bytes(&converted),
+            Err(message) => {
+                report(&Diagnostic::error(message), &input_name, args.output.color);
+                return ExitCode::from(1);
+            },
+        },
+    };
+    match printed {
+        Ok(()) => ExitCode::from(0),
+        Err(e) if e.kind() == io::ErrorKind::BrokenPipe => ExitCode::from(BROKEN_PIPE_EXIT_CODE),
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            ExitCode::from(1)
+        },
+    }
+}
+
+/// Run a subcommand
+fn run_command(command: &Command) -> ExitCode {
+    let printed = match command {
+        Command::Diff { from, to, json_patch, output } => {
+            let (from, to) = match (read_document(from, output.color), read_document(to, output.color)) {
+                (Ok(from), Ok(to)) => (from, to),
+                _ => return ExitCode::from(2),
+            };
+            let changes = diff(&from, &to);
+            let printed = match (*json_patch, output.quiet) {
+                (_, true) => Ok(()),
+                (true, false) => {
+                    let options = WriteOptions { indent: Some("  "), color: output.color.should_color(&io::stdout()) };
+                    print_values(&[&to_json_patch(&changes)], options)
+                },
+                (false, false) => print_diff(&changes, output.color.should_color(&io::stdout())),
+            };
+            printed.map(|()| if changes.is_empty() { 0 } else { 1 })
+        },
+        Command::MergePatch { target, patch, compact, color } => {
+            let (mut target, patch) = match (read_document(target, *color), read_document(patch, *color)) {
+                (Ok(target), Ok(patch)) => (target, patch),
+                _ => return ExitCode::from(2),
+            };
+            merge_patch(&mut target, &patch);
+            let options = WriteOptions { indent: if *compact { None } else { Some("  ") }, color: color.should_color(&io::stdout()) };
+            print_values(&[&target], options).map(|()| 0)
+        },
+        Command::Patch { target, patch: patch_path, compact, color } => {
+            let (mut target, patch) = match (read_document(target, *color), read_document(patch_path, *color)) {
+                (Ok(target), Ok(patch)) => (target, patch),
+                _ => return ExitCode::from(2),
+            };
+            if let Err(e) = apply_patch(&mut target, &patch) {
+                report(&Diagnostic::error(e.to_string()), &Source::from(patch_path.clone()).label(), *color);
+                return ExitCode::from(1);
+            }
+            let options = WriteOptions { indent: if *compact { None } else { Some("  ") }, color: color.should_color(&io::stdout()) };
+            print_values(&[&target], options).map(|()| 0)
+        },
+    };
+    match printed {
+        Ok(code) => ExitCode::from(code),
+        Err(e) if e.kind() == io::ErrorKind::BrokenPipe => ExitCode::from(BROKEN_PIPE_EXIT_CODE),
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            ExitCode::from(2)
+        },
+    }
+}
+
+/// Read a document a subcommand was given, saying why to stderr if it can't be
+fn read_document(path: &Path, color: ColorChoice) -> Result<JsonValue, ()> {
+    let source = Source::from(path.to_path_buf());
+    let (name, reader) = open_input(&source, None).map_err(|e| eprintln!("{}", e))?;
+    parse_reader(reader).map_err(|e| report_error(&e, &name, &reread(&source, None, slice::from_ref(&e)), color))
+}
+
+/// Write each difference to stdout on a line of its own, with its value compactly or what it
+/// changed from and to
+fn print_diff(changes: &[Change], color: bool) -> io::Result<()> {
+    let mut out = BufWriter::new(io::stdout().lock());
+    for change in changes {
+        let (sign, style, values) = match change {
+            Change::Added { value, .. } => ('+', Style::GREEN, to_string_compact(value)),
+            Change::Removed { value, .. } => ('-', Style::RED, to_string_compact(value)),
+            Change::Changed { from, to, .. } => ('~', Style::YELLOW, format!("{} -> {}", to_string_compact(from), to_string_compact(to))),
+        };
+        // The document itself has no path to put before it
+        let line = match change.path() {
+            "" => format!("{} {}", sign, values),
+            path => format!("{} {}: {}", sign, path, values),
+        };
+        writeln!(out, "{}", style.paint(&line, color))?;
+    }
+    out.flush()
+}
+
+/// Write each value to stdout, with a line break after each
+fn print_values(values: &[&JsonValue], options: WriteOptions) -> io::Result<()> {
+    let mut out = BufWriter::new(io::stdout().lock());
+    for value in values {
+        write_json(&mut out, value, options)?;
+        out.write_all(b"\n")?;
+    }
+    out.flush()
+}
+
+/// Write each value to stdout as a YAML document, with `---` between them
+fn print_yaml(values: &[&JsonValue]) -> io::Result<()> {
+    let mut out = BufWriter::new(io::stdout().lock());
+    for (i, value) in values.iter().enumerate() {
+        if i > 0 {
+            out.write_all(b"---\n")?;
+        }
+        write_yaml(&mut out, value)?;
+        out.write_all(b"\n")?;
+    }
+    out.flush()
+}
+
+/// The one value there is written as TOML, CSV, MessagePack, or CBOR, which have no way to put more than
+/// one document in a file
+fn convert(values: &[&JsonValue], target: Target) -> Result<Vec<u8>, String> {
+    let name = match target {
+        Target::Yaml => unreachable!("YAML documents are written one after another"),
+        Target::Toml => "TOML",
+        Target::Csv => "CSV",
+        Target::Msgpack => "MessagePack",
+        Target::Cbor => "CBOR",
+    };
+    let [value] = values else {
+        return Err(format!("a {} file has one document in it, but {} values were picked out", name, values.len()));
+    };
+    match target {
+        Target::Toml => to_string_toml(value).map(String::into_bytes).map_err(|e| e.message),
+        Target::Csv => to_string_csv(value).map(String::into_bytes).map_err(|e| e.message),
+        Target::Msgpack => Ok(to_msgpack(value)),
+        _ => Ok(to_cbor(value)),
+    }
+}
+
+/// Write each value to stdout flattened into `path = value` lines
+fn print_flat(values: &[&JsonValue]) -> io::Result<()> {
+    let mut out = BufWriter::new(io::stdout().lock());
+    for value in values {
+        write_flat(&mut out, value)?;
+    }
+    out.flush()
+}
+
+/// Each value in canonical form, with a line break between them and none after the last, so one
+/// document is written as exactly the bytes to hash or sign
+fn canonicalize(values: &[&JsonValue]) -> Result<Vec<u8>, String> {
+    let canonical = values.iter().map(|value| to_string_canonical(value).map_err(|e| e.message));
+    Ok(canonical.collect::<Result<Vec<_>, String>>()?.join("\n").into_bytes())
+}
+
+/// Write what's already been laid out to stdout as it is
+fn print_bytes(bytes: &[u8]) -> io::Result<()> {
+    let mut out = io::stdout().lock();
+    out.write_all(bytes)?;
+    out.flush()
+}
+
+/// Write the document `input` to stdout with its comments, and a line break after it
+fn print_commented(input: &str, options: WriteOptions) -> io::Result<()> {
+    let mut out = BufWriter::new(io::stdout().lock());
+    write_commented(&mut out, input, options.indent.unwrap_or_default(), options.color)?;
+    out.write_all(b"\n")?;
+    out.flush()
+}
+
+/// The files to check, stdin if none are given, with those under the --recursive directories
+/// after the ones given. Glob
+/// patterns are expanded here for shells that don't, and left as they are if they match nothing,
+/// so they fail to be read like any other missing file.
+fn input_paths(args: &Cli) -> Result<Vec<PathBuf>, OpenError> {
+    if args.files.is_empty() && args.recursive.is_empty() {
+        return Ok(vec![PathBuf::from("-")]);
+    }
+    let mut paths = Vec::new();
+    for path in &args.files {
+        match path.to_str().filter(|_| !path.exists()).map(cc_walk::expand) {
+            Some(Ok(matches)) if !matches.is_empty() => paths.extend(matches),
+            _ => paths.push(path.clone()),
+        }
+    }
+    if !args.recursive.is_empty() {
+        let mut options = WalkOptions { threads: args.jobs, ..args.walk.options() };
+        if options.include.is_empty() {
+            options.include.push(Glob::new("*.json").expect("the pattern is valid"));
+        }
+        paths.extend(cc_walk::walk(&args.recursive, &options)?);
+    }
+    Ok(paths)
+}
+
+/// Check each of several files, printing a line to stdout for each saying whether it's OK or
+/// what's wrong with it, and failing if any of them aren't. In recursive mode only what's wrong
+/// is printed, followed by a summary. The files are checked on several threads at once, and
+/// printed about in the order given however their checks interleave.
+fn check_files(args: &Cli, sources: &[Source], schema: Option<&Schema>) -> ExitCode {
+    let summarize = !args.recursive.is_empty();
+    let mut invalid = Vec::new();
+    let next = AtomicUsize::new(0);
+    let (sender, receiver) = mpsc::channel();
+    thread::scope(|scope| {
+        for _ in 0..jobs(args.jobs).min(sources.len()) {
+            let (next, sender) = (&next, sender.clone());
+            scope.spawn(move || loop {
+                let index = next.fetch_add(1, Ordering::Relaxed);
+                let Some(source) = sources.get(index) else { break };
+                if sender.send((index, check_file(args, source, schema))).is_err() {
+                    break;
+                }
+            });
+        }
+        drop(sender);
+
+        // Hold on to the files checked early until the ones before them are printed
+        let mut checked = HashMap::new();
+        let mut printed = 0;
+        for (index, check) in receiver {
+            checked.insert(index, check);
+            while let Some(check) = checked.remove(&printed) {
+                let name = sources[printed].label();
+                for (warning, input_name) in &check.warnings {
+                    report(warning, input_name, args.output.color);
+                }
+                if check.errors.is_empty() && !summarize && !args.output.quiet {
+                    println!("{}: OK", name);
+                }
+                for e in &check.errors {
+                    println!("{}: error{}", name, e);
+                }
+                if !check.errors.is_empty() {
+                    invalid.push(name);
+                }
+                printed += 1;
+            }
+        }
+    });
+
+    if summarize && !args.output.quiet {
+        println!("{} valid, {} invalid", sources.len() - invalid.len(), invalid.len());
+        if !invalid.is_empty() {
+            println!("Invalid:");
+            for name in &invalid {
+                println!("  {}", name);
+            }
+        }
+    }
+    ExitCode::from(u8::from(!invalid.is_empty()))
+}
+
+/// Check each file, and then check it again whenever it changes, printing a line to stdout for each
+/// check saying when it was made and whether the file is OK or what's wrong with it. It only
+/// returns if stdout is closed.
+fn watch_files(args: &Cli, sources: &[Source], schema: Option<&Schema>) -> ExitCode {
+    if sources.iter().any(Source::is_stdin) {
+        eprintln!("--watch checks files again when they change, it cannot watch stdin");
+        return ExitCode::from(2);
+    }
+    // How each file was when it was last checked, if it has been
+    let mut checked = vec![None; sources.len()];
+    loop {
+        for (source, checked) in sources.iter().zip(&mut checked) {
+            let Source::File(path) = source else { unreachable!("stdin isn't watched") };
+            let stamp = stamp(path);
+            if *checked == Some(stamp) {
+                continue;
+            }
+            *checked = Some(stamp);
+            let check = check_file(args, source, schema);
+            for (warning, input_name) in &check.warnings {
+                report(warning, input_name, args.output.color);
+            }
+            let (time, name) = (clock(SystemTime::now()), source.label());
+            let mut lines: Vec<String> = check.errors.iter().map(|e| format!("[{}] {}: error{}", time, name, e)).collect();
+            if lines.is_empty() && !args.output.quiet {
+                lines.push(format!("[{}] {}: OK", time, name));
+            }
+            let mut out = io::stdout().lock();
+            match lines.iter().try_for_each(|line| writeln!(out, "{}", line)) {
+                Ok(()) => {},
+                Err(e) if e.kind() == io::ErrorKind::BrokenPipe => return ExitCode::from(BROKEN_PIPE_EXIT_CODE),
+                Err(e) => {
+                    eprintln!("Error: {}", e);
+                    return ExitCode::from(1);
+                },
+            }
+        }
+        thread::sleep(WATCH_INTERVAL);
+    }
+}
+
+/// What --watch looks at to tell whether a file has changed: its modification time and size, and a
+/// hash of what's in it for a change that leaves those the same, as rewriting it within the
+/// resolution of its modification time can. It's None if the file can't be read.
+fn stamp(path: &Path) -> Option<(Option<SystemTime>, u64, u64)> {
+    let metadata = fs::metadata(path).ok()?;
+    let mut file = fs::File::open(path).ok()?;
+    let mut hasher = DefaultHasher::new();
+    let mut buffer = [0; 64 * 1024];
+    loop {
+        match file.read(&mut buffer).ok()? {
+            0 => break,
+            read => hasher.write(&buffer[..read]),
+        }
+    }
+    Some((metadata.modified().ok(), metadata.len(), hasher.finish()))
+}
+
+/// The time of day a --watch check is made, e.g. `09:15:02`, in UTC
+fn clock(time: SystemTime) -> String {
+    let seconds = time.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs() % 86400;
+    format!("{:02}:{:02}:{:02}", seconds / 3600, seconds / 60 % 60, seconds % 60)
+}
+
+/// How many threads to check files on for --jobs, 0 meaning one per CPU
+fn jobs(jobs: usize) -> usize {
+    match jobs {
+        0 => thread::available_parallelism().map_or(1, |n| n.get()),
+        jobs => jobs,
+    }
+}
+
+/// What checking one of several files found, to print once the files before it are printed about
+struct FileCheck {
+    /// Diagnostics for stderr, each with the input name to give it
+    warnings: Vec<(Diagnostic, String)>,
+    /// Everything wrong with the file, as described by `Checked::descriptions`
+    errors: Vec<String>,
+}
+
+fn check_file(args: &Cli, source: &Source, schema: Option<&Schema>) -> FileCheck {
+    let (input_name, mut reader) = match open_document(source, args) {
+        Ok(input) => input,
+        Err(e) => return FileCheck { warnings: Vec::new(), errors: vec![format!(": {}", e)] },
+    };
+    let mut warnings: Vec<_> = bom_warning(args, &mut reader).map(|warning| (warning, input_name.clone())).into_iter().collect();
+    let checked = check(args, reader, schema);
+    if !args.output.quiet {
+        if !args.deny_duplicate_keys {
+            warnings.extend(checked.warnings.duplicates.iter().map(|duplicate| duplicate_diagnostic(duplicate, &input_name, false)));
+        }
+        warnings.extend(checked.warnings.trailing_commas.iter().map(|trailing| trailing_comma_diagnostic(trailing, &input_name)));
+        warnings.extend(checked.warnings.numbers.iter().map(|number| number_diagnostic(number, &input_name)));
+    }
+    FileCheck { warnings, errors: checked.descriptions(args.deny_duplicate_keys) }
+}
+
+/// The warning that a byte order mark at the start of the input is skipped, unless it isn't
+/// because of --strict or --quiet leaves warnings out
+fn bom_warning(args: &Cli, reader: &mut dyn BufRead) -> Option<Diagnostic> {
+    let skipped = !args.strict && !args.output.quiet && starts_with_bom(reader);
+    skipped.then(|| {
+        Diagnostic::warning("skipped the byte order mark at the start of the input")
+            .with_note("JSON text shouldn't start with one, and --strict fails on it")
+    })
+}
+
+/// Whether the input starts with a UTF-8 byte order mark, looking without reading past it
+fn starts_with_bom(reader: &mut dyn BufRead) -> bool {
+    reader.fill_buf().is_ok_and(|buf| buf.starts_with("\u{feff}".as_bytes()))
+}
+
+/// What's wrong with one input
+struct Checked {
+    errors: Vec<JsonError>,
+    warnings: Warnings,
+    violations: Vec<Violation>,
+}
+
+impl Checked {
+    /// Each thing wrong, as `: message` or ` at line:column: message`, counting repeated keys only
+    /// if they're denied
+    fn descriptions(&self, deny_duplicate_keys: bool) -> Vec<String> {
+        let at = |position: Option<LineCol>, message: String| match position {
+            Some(position) => format!(" at {}: {}", position, message),
+            None => format!(": {}", message),
+        };
+        let duplicates = self.warnings.duplicates.iter().filter(|_| deny_duplicate_keys);
+        self.errors.iter().map(|e| at(e.position(), e.message()))
+            .chain(duplicates.map(|d| at(Some(d.position), d.message())))
+            .chain(self.violations.iter().map(|v| at(None, v.to_string())))
+            .collect()
+    }
+}
+
+/// Check the input, against the schema if there is one, finding every error if --all-errors is
+/// given or else only the first, or the first in each line with --ndjson
+fn check(args: &Cli, reader: Box<dyn BufRead>, schema: Option<&Schema>) -> Checked {
+    let options = args.parse_options();
+    if args.ndjson {
+        let (errors, warnings) = check_ndjson_reader(reader, &options);
+        return Checked { errors, warnings, violations: Vec::new() };
+    }
+    if args.all_errors {
+        let (errors, warnings) = check_reader_with(reader, &options);
+        return Checked { errors, warnings, violations: Vec::new() };
+    }
+    let checked = match schema {
+        Some(schema) => parse_reader_with(reader, &options).map(|(value, warnings)| (warnings, schema.validate(&value))),
+        None => validate_reader(reader, &options).map(|warnings| (warnings, Vec::new())),
+    };
+    match checked {
+        Ok((warnings, violations)) => Checked { errors: Vec::new(), warnings, violations },
+        Err(e) => Checked { errors: vec![e], warnings: Warnings::default(), violations: Vec::new() },
+    }
+}
+
+/// Whether one input parsed, in a --format json or sarif report. Serde is only a dependency of the
+/// library with its serde feature, so this derives from cc-cli's.
+#[derive(cc_cli::serde::Serialize)]
+#[serde(crate = "cc_cli::serde")]
+struct Validity {
+    input: String,
+    valid: bool,
+}
+
+/// Open the input, decoding it to UTF-8 if it's in another encoding, along with what to call it
+fn open_input(source: &Source, encoding: Option<Encoding>) -> Result<(String, Box<dyn BufRead>), String> {
+    let input = cc_io::open(source).map_err(|e| e.to_string())?;
+    let name = input.label();
+    match decode_reader(input.reader, encoding) {
+        Ok((_, reader)) => Ok((name, reader)),
+        Err(e) => Err(format!("could not read `{}`: {}", name, e)),
+    }
+}
+
+/// Open one of the inputs named on the command line, as JSON or else decoded from the format --from
+/// gives and read as the JSON it stands for, along with what to call it
+fn open_document(source: &Source, args: &Cli) -> Result<(String, Box<dyn BufRead>), String> {
+    let Some(from) = args.from else {
+        return open_input(source, args.encoding);
+    };
+    let mut input = cc_io::open(source).map_err(|e| e.to_string())?;
+    let name = input.label();
+    let mut bytes = Vec::new();
+    input.reader.read_to_end(&mut bytes).map_err(|e| format!("could not read `{}`: {}", name, e))?;
+    let max_depth = args.parse_options().max_depth;
+    let value = match from {
+        Binary::Msgpack => from_msgpack(&bytes, max_depth).map_err(|e| e.to_string()),
+        Binary::Cbor => from_cbor(&bytes, max_depth).map_err(|e| e.to_string()),
+    };
+    let value = value.map_err(|e| format!("could not decode `{}`: {}", name, e))?;
+    Ok((name, Box::new(Cursor::new(to_string_compact(&value).into_bytes()))))
+}
+
+/// Read the schema at `path`, or say what's wrong with it and what to call it
+fn read_schema(path: &Path) -> Result<Schema, (String, String)> {
+    let source = Source::from(path.to_path_buf());
+    let (name, reader) = open_input(&source, None).map_err(|e| (source.label(), e))?;
+    let schema = parse_reader(reader).map_err(|e| (name.clone(), format!("could not parse the schema: {}", e)))?;
+    Schema::new(&schema).map_err(|e| (name, format!("not a schema this can check with: {}", e)))
+}
+
+/// Check each input, against the schema if there is one, and write a --format json or sarif report of
+/// them to stdout
+fn print_report(args: &Cli, sources: &[Source], schema: Option<&Schema>) -> ExitCode {
+    let mut report = Report::start("cc2jsonparser", env!("CARGO_PKG_VERSION"));
+    for source in sources {
+        let input_name = source.label();
+        report.inputs.push(input_name.clone());
+        let (_, reader) = match open_document(source, args) {
+            Ok(input) => input,
+            Err(e) => {
+                report.errors.push(ReportError::new(Some(&input_name), e).with_rule("read"));
+                continue;
+            },
+        };
+        let Checked { errors, warnings, violations } = check(args, reader, schema);
+        // Repeated keys are only warned about otherwise, and a report has no warnings
+        let duplicates = if args.deny_duplicate_keys { warnings.duplicates } else { Vec::new() };
+        let valid = errors.is_empty() && duplicates.is_empty() && violations.is_empty();
+        report.results.push(Validity { input: input_name.clone(), valid });
+        for e in errors {
+            let mut error = ReportError::new(Some(&input_name), e.message()).with_rule("syntax");
+            if let Some(position) = e.position() {
+                error = error.at(position);
+            }
+            if let Some(span) = e.span() {
+                error = error.with_span(span);
+            }
+            report.errors.push(error);
+        }
+        report.errors.extend(duplicates.iter().map(|d| {
+            ReportError::new(Some(&input_name), d.message()).at(d.position).with_span(d.span).with_rule("duplicate-key")
+        }));
+        report.errors.extend(violations.iter().map(|v| ReportError::new(Some(&input_name), v.to_string()).with_rule("schema")));
+    }
+
+    let code = if report.errors.is_empty() { ExitCode::from(0) } else { ExitCode::from(1) };
+    match report.write_as(args.format.format, &mut io::stdout().lock()) {
+        Ok(()) => code,
+        Err(e) if e.kind() == io::ErrorKind::BrokenPipe => ExitCode::from(BROKEN_PIPE_EXIT_CODE),
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            ExitCode::from(1)
+        },
+    }
+}
+
+/// Print why the input isn't JSON to stderr, quoting where it goes wrong from `input` if that much
+/// of it is at hand
+fn report_error(e: &JsonError, input_name: &str, input: &str, color: ColorChoice) {
+    if let JsonError::Empty = e {
+        report(&Diagnostic::error("Did not find anything to parse"), input_name, color);
+        return;
+    }
+    match e.span() {
+        Some(span) if !input.is_empty() && span.start <= input.len() => {
+            let color = color.should_color(&io::stderr());
+            eprint!("{}", Diagnostic::error(e.message()).with_span(span).render(input_name, input, color));
+        },
+        // Without the source to quote, point at the line and column the way compilers do
+        _ => {
+            let name = match e.position() {
+                Some(position) => format!("{}:{}", input_name, position),
+                None => input_name.to_string(),
+            };
+            report(&Diagnostic::error(e.message()), &name, color);
+        },
+    }
+}
+
+/// The input up to the end of the line the last of the errors is in, read again for them to quote,
+/// or nothing if it's stdin, which can't be read twice. The input is checked as it's read rather
+/// than kept, so only when it's wrong is this much of it held at once. `args` opens it as they
+/// say, or as plain JSON if there are none.
+fn reread(source: &Source, args: Option<&Cli>, errors: &[JsonError]) -> String {
+    let Some(last) = errors.iter().filter_map(JsonError::span).map(|span| span.start).max() else {
+        return String::new();
+    };
+    if source.is_stdin() {
+        return String::new();
+    }
+    let opened = match args {
+        Some(args) => open_document(source, args),
+        None => open_input(source, None),
+    };
+    let Ok((_, mut reader)) = opened else {
+        return String::new();
+    };
+    let mut input = Vec::new();
+    while input.len() <= last {
+        match reader.read_until(b'\n', &mut input) {
+            Ok(0) | Err(_) => break,
+            Ok(_) => {},
+        }
+    }
+    // Invalid UTF-8 is only ever at or after where the errors start
+    String::from_utf8_lossy(&input).into_owned()
+}
+
+/// Print the warnings about the input to stderr, unless --quiet leaves them out, with repeated keys
+/// errors if they're denied
+fn report_warnings(args: &Cli, warnings: &Warnings, input_name: &str) {
+    if args.deny_duplicate_keys || !args.output.quiet {
+        report_duplicates(&warnings.duplicates, input_name, args.deny_duplicate_keys, args.output.color);
+    }
+    if !args.output.quiet {
+        for trailing in &warnings.trailing_commas {
+            let (diagnostic, name) = trailing_comma_diagnostic(trailing, input_name);
+            report(&diagnostic, &name, args.output.color);
+        }
+        for number in &warnings.numbers {
+            let (diagnostic, name) = number_diagnostic(number, input_name);
+            report(&diagnostic, &name, args.output.color);
+        }
+    }
+}
+
+/// Print a warning about each key repeated in an object to stderr, or an error if they're denied
+fn report_duplicates(duplicates: &[DuplicateKey], input_name: &str, deny: bool, color: ColorChoice) {
+    for duplicate in duplicates {
+        let (diagnostic, name) = duplicate_diagnostic(duplicate, input_name, deny);
+        report(&diagnostic, &name, color);
+    }
+}
+
+/// The warning about a repeated key, or error if they're denied, with the name to give it
+fn duplicate_diagnostic(duplicate: &DuplicateKey, input_name: &str, deny: bool) -> (Diagnostic, String) {
+    let diagnostic = match deny {
+        true => Diagnostic::error(duplicate.message()),
+        false => Diagnostic::warning(duplicate.message()),
+    };
+    (diagnostic.with_note(duplicate.note()), format!("{}:{}", input_name, duplicate.position))
+}
+
+/// The warning about a trailing comma let through by --allow-trailing-commas, with the name to
+/// give it
+fn trailing_comma_diagnostic(trailing: &TrailingComma, input_name: &str) -> (Diagnostic, String) {
+    let diagnostic = Diagnostic::warning(trailing.message()).with_note("JSON doesn't allow one, a parser without --allow-trailing-commas fails on it");
+    (diagnostic, format!("{}:{}", input_name, trailing.position))
+}
+
+/// The warning about a number read as something other than what's written, with the name to give
+/// it
+fn number_diagnostic(number: &NumberWarning, input_name: &str) -> (Diagnostic, String) {
+    let diagnostic = Diagnostic::warning(number.message());
+    let diagnostic = match (number.loss, &number.read_as) {
+        (Loss::Overflow, Number::Float(float)) if float.is_finite() => diagnostic.with_note("--bignum reads integers of any size exactly"),
+        _ => diagnostic,
+    };
+    (diagnostic, format!("{}:{}", input_name, number.position))
+}
+
+/// Print a diagnostic about the input to stderr
+fn report(diagnostic: &Diagnostic, input_name: &str, color: ColorChoice) {
+    let color = color.should_color(&io::stderr());
+    eprint!("{}", diagnostic.render(input_name, "", color));
+}
@@ -0,0 +1,107 @@
+// Concatenated JSON, documents written one after another.
+// jq and other tools reading a stream of documents take them with nothing but whitespace between
+// them, or none at all where one ends with a bracket or quote, so `{"a": 1} [2]3 4` is four of
+// them. Each document's tokens are gathered until its brackets are all closed and then parsed on
+// their own, so only one document at a time is in memory. After a document that isn't JSON there's
+// no telling where the next starts, so nothing comes after it.
+// Reference:  https://en.wikipedia.org/wiki/JSON_streaming#Concatenated_JSON
+use crate::token::{StreamError, TokenStream};
+use crate::{parse_document, JsonError, JsonParser, JsonValue, ParseOptions, Token, Warnings};
+use std::io::BufRead;
+
+/// The documents read one after another from `reader` within the limits of `options`
+pub fn read_concatenated<R: BufRead>(reader: R, options: &ParseOptions) -> ConcatenatedReader<R> {
+    ConcatenatedReader { stream: TokenStream::with_options(reader, options), options: options.clone(), done: false }
+}
+
+/// Each document and what it has in it to warn about, or the error in the first that isn't one
+#[derive(Debug)]
+pub struct ConcatenatedReader<R> {
+    stream: TokenStream<R>,
+    options: ParseOptions,
+    /// Whether the input has ended, or gone wrong
+    done: bool,
+}
+
+impl<R: BufRead> Iterator for ConcatenatedReader<R> {
+    type Item = Result<(JsonValue, Warnings), JsonError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let mut tokens = Vec::new();
+        // How many objects and arrays are open
+        let mut depth = 0usize;
+        loop {
+            let token = match self.stream.next_token() {
+                Ok(token) => token,
+                Err(StreamError::Token(e, _) | StreamError::Skipped(e) | StreamError::Read(e)) => {
+                    self.done = true;
+                    return Some(Err(JsonError::Tokenize(e)));
+                },
+            };
+            match token.value {
+                Token::Eof if tokens.is_empty() => {
+                    self.done = true;
+                    return None;
+                },
+                // The parser says what's missing
+                Token::Eof => {
+                    tokens.push(token);
+                    break;
+                },
+                Token::LeftBrace | Token::LeftBracket => depth += 1,
+                Token::RightBrace | Token::RightBracket => depth = depth.saturating_sub(1),
+                _ => {},
+            }
+            tokens.push(token);
+            // A value, or a closing bracket, with nothing left open; anything else at the top,
+            // like a stray comma, is a document of its own for the parser to turn down
+            if depth == 0 {
+                break;
+            }
+        }
+
+        let mut parser = JsonParser::new(&tokens, &self.options);
+        Some(match parse_document(&mut parser) {
+            Ok(value) => Ok((value, std::mem::take(&mut parser.warnings))),
+            Err(e) => {
+                self.done = true;
+                Err(JsonError::Parse(e))
+            },
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::to_string_compact;
+
+    fn read(input: &str) -> Vec<String> {
+        read_concatenated(input.as_bytes(), &ParseOptions::default())
+            .map(|document| match document {
+                Ok((value, _)) => to_string_compact(&value),
+                Err(e) => format!("{}: {}", e.position().unwrap(), e.message()),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn check_read_concatenated() {
+        assert_eq!(read("{\"a\": [1, {}]} [2]3\n\n\"four\"{}null"), ["{\"a\":[1,{}]}", "[2]", "3", "\"four\"", "{}", "null"]);
+        assert!(read(" \n ").is_empty());
+        // A document can go over lines
+        assert_eq!(read("[1,\n2]\n"), ["[1,2]"]);
+    }
+
+    #[test]
+    fn check_read_concatenated_errors() {
+        // Nothing after the one that isn't JSON
+        assert_eq!(read("[1] [1 2] [3]"), ["[1]", "1:8: expected `,` or `]` after the array element, found number `2`"]);
+        assert_eq!(read("[1] , [3]"), ["[1]", "1:5: expected a value, found `,`"]);
+        assert_eq!(read("{\"a\": 1"), ["1:8: expected `,` or `}` after the object member, found end of input"]);
+        assert_eq!(read("1 tru"), ["1", "1:3: expected `true`, found `tru`"]);
+    }
+}
@@ -0,0 +1,110 @@
+// Writing JSON as CSV.
+// An array of objects like an API's list of records is a table: each object a row, each key a
+// column. The header row has every key any of the objects has, in the order they're first seen,
+// and an object without one of them has an empty field there, as null is. Only strings, numbers,
+// and booleans fit in a field, so anything nested is an error saying where it is. Fields are
+// quoted when they have a comma, quote, or line break in them, and rows end with CRLF.
+// Reference:  https://www.rfc-editor.org/rfc/rfc4180
+use crate::JsonValue;
+use std::fmt;
+
+/// What there's no writing as CSV, and where it is in the document
+#[derive(Clone, Debug, PartialEq)]
+pub struct CsvError {
+    pub message: String,
+}
+
+impl fmt::Display for CsvError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for CsvError {}
+
+/// The value as CSV, if it's an array of objects whose values are all strings, numbers, booleans,
+/// or null. With no keys in any of them there are no columns, so nothing, not even a header row.
+pub fn to_string_csv(value: &JsonValue) -> Result<String, CsvError> {
+    let JsonValue::Array(rows) = value else {
+        let message = format!("CSV is a table of rows, so only an array of objects can be written as it, not {}", value.type_name());
+        return Err(CsvError { message });
+    };
+    let mut columns: Vec<&str> = Vec::new();
+    for (i, row) in rows.iter().enumerate() {
+        let JsonValue::Object(members) = row else {
+            return Err(CsvError { message: format!("`[{}]` is {}, but each row has to be an object", i, row.type_name()) });
+        };
+        for (key, value) in members {
+            if matches!(value, JsonValue::Object(_) | JsonValue::Array(_)) {
+                let message = format!("`[{}].{}` is {}, but a field can only be a string, number, boolean, or null", i, key, value.type_name());
+                return Err(CsvError { message });
+            }
+            if !columns.contains(&key.as_str()) {
+                columns.push(key);
+            }
+        }
+    }
+
+    let mut out = String::new();
+    if columns.is_empty() {
+        return Ok(out);
+    }
+    write_row(&mut out, columns.iter().copied());
+    for row in rows {
+        let JsonValue::Object(members) = row else { unreachable!("every row was checked to be an object") };
+        // A key given more than once has the last value given for it, as JavaScript has it
+        let field = |column: &str| match members.iter().rev().find(|(key, _)| key == column).map(|(_, value)| value) {
+            Some(JsonValue::String(string)) => string.as_str(),
+            Some(JsonValue::Number(number)) => number.as_str(),
+            Some(JsonValue::Bool(true)) => "true",
+            Some(JsonValue::Bool(false)) => "false",
+            _ => "",
+        };
+        write_row(&mut out, columns.iter().map(|column| field(column)));
+    }
+    Ok(out)
+}
+
+/// Write the fields as a row, quoting those that need it
+fn write_row<'a>(out: &mut String, fields: impl Iterator<Item = &'a str>) {
+    for (i, field) in fields.enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+        match field.contains([',', '"', '\r', '\n']) {
+            true => {
+                out.push('"');
+                out.push_str(&field.replace('"', "\"\""));
+                out.push('"');
+            },
+            false => out.push_str(field),
+        }
+    }
+    out.push_str("\r\n");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_str;
+
+    fn csv(input: &str) -> Result<String, String> {
+        to_string_csv(&parse_str(input).unwrap()).map_err(|e| e.message)
+    }
+
+    #[test]
+    fn check_to_string_csv() {
+        let input = r#"[{"id": 1, "name": "Ann", "admin": true}, {"id": 2, "email": "b@example.com", "name": null}, {"name": "C, \"Jr\"", "id": 3}]"#;
+        assert_eq!(csv(input).unwrap(), "id,name,admin,email\r\n1,Ann,true,\r\n2,,,b@example.com\r\n3,\"C, \"\"Jr\"\"\",,\r\n");
+        assert_eq!(csv(r#"[{"note": "two\nlines", "a": 1, "a": 2}]"#).unwrap(), "note,a\r\n\"two\nlines\",2\r\n");
+        assert_eq!(csv("[]").unwrap(), "");
+        assert_eq!(csv("[{}, {}]").unwrap(), "");
+    }
+
+    #[test]
+    fn check_to_string_csv_fails_for_what_is_not_a_table() {
+        assert_eq!(csv(r#"{"id": 1}"#), Err("CSV is a table of rows, so only an array of objects can be written as it, not an object".to_string()));
+        assert_eq!(csv(r#"[{"id": 1}, 2]"#), Err("`[1]` is a number, but each row has to be an object".to_string()));
+        assert_eq!(csv(r#"[{"id": 1, "tags": ["a"]}]"#), Err("`[0].tags` is an array, but a field can only be a string, number, boolean, or null".to_string()));
+    }
+}
@@ -0,0 +1,189 @@
+// Comparing two documents.
+// A textual diff of JSON is all noise once a file's keys are reordered or it's laid out again, so
+// this compares the values the documents parse to instead: objects by their keys whatever order
+// they're in, arrays element by element, and numbers by the value they're written as, so 1.0 and
+// 1e0 are the same. Each difference is at a JSON Pointer, and the differences can be written as a
+// JSON Patch that turns the first document into the second.
+// Reference:  https://www.rfc-editor.org/rfc/rfc6902
+use crate::pointer::escape;
+use crate::JsonValue;
+use std::collections::HashMap;
+
+/// One way the second document differs from the first, at the JSON Pointer `path`
+#[derive(Clone, Debug, PartialEq)]
+pub enum Change<'a> {
+    /// The second document has a value where the first has none
+    Added { path: String, value: &'a JsonValue },
+    /// The first document has a value where the second has none
+    Removed { path: String, value: &'a JsonValue },
+    /// The documents have different values there, other than objects or arrays in both
+    Changed { path: String, from: &'a JsonValue, to: &'a JsonValue },
+}
+
+impl Change<'_> {
+    /// The JSON Pointer to where the documents differ
+    pub fn path(&self) -> &str {
+        match self {
+            Change::Added { path, .. } | Change::Removed { path, .. } | Change::Changed { path, .. } => path,
+        }
+    }
+}
+
+/// How `to` differs from `from`, in an order they can be made in one after another: the members of
+/// an object in the order of its keys in `from` and then those only `to` has, and elements past the
+/// end of the shorter array added first to last, or removed last to first. A key given more than
+/// once has the last value given for it, as JavaScript has it.
+pub fn diff<'a>(from: &'a JsonValue, to: &'a JsonValue) -> Vec<Change<'a>> {
+    let mut changes = Vec::new();
+    diff_at(&mut changes, from, to, "");
+    changes
+}
+
+fn diff_at<'a>(changes: &mut Vec<Change<'a>>, from: &'a JsonValue, to: &'a JsonValue, at: &str) {
+    match (from, to) {
+        (JsonValue::Object(from_members), JsonValue::Object(to_members)) => {
+            let (from_values, to_values) = (last_values(from_members), last_values(to_members));
+            for (key, value) in from_members.iter().filter(|(key, value)| std::ptr::eq(from_values[key.as_str()], value)) {
+                let path = format!("{}/{}", at, escape(key));
+                match to_values.get(key.as_str()) {
+                    Some(to) => diff_at(changes, value, to, &path),
+                    None => changes.push(Change::Removed { path, value }),
+                }
+            }
+            for (key, value) in to_members.iter().filter(|(key, value)| std::ptr::eq(to_values[key.as_str()], value)) {
+                if !from_values.contains_key(key.as_str()) {
+                    changes.push(Change::Added { path: format!("{}/{}", at, escape(key)), value });
+                }
+            }
+        },
+        (JsonValue::Array(from_elements), JsonValue::Array(to_elements)) => {
+            for (i, (from, to)) in from_elements.iter().zip(to_elements).enumerate() {
+                diff_at(changes, from, to, &format!("{}/{}", at, i));
+            }
+            let added = to_elements.iter().enumerate().skip(from_elements.len());
+            changes.extend(added.map(|(i, value)| Change::Added { path: format!("{}/{}", at, i), value }));
+            let removed = from_elements.iter().enumerate().skip(to_elements.len()).rev();
+            changes.extend(removed.map(|(i, value)| Change::Removed { path: format!("{}/{}", at, i), value }));
+        },
+        (JsonValue::Number(a), JsonValue::Number(b)) if same_number(a, b) => {},
+        _ if from == to => {},
+        _ => changes.push(Change::Changed { path: at.to_string(), from, to }),
+    }
+}
+
+/// The value of each key in an object, the last one given for it if there's more than one
+fn last_values(members: &[(String, JsonValue)]) -> HashMap<&str, &JsonValue> {
+    members.iter().map(|(key, value)| (key.as_str(), value)).collect()
+}
+
+/// Whether two JSON numbers are written for the same value, however many digits they're written
+/// with or where the decimal point is
+fn same_number(a: &str, b: &str) -> bool {
+    match (decimal(a), decimal(b)) {
+        (Some(a), Some(b)) => a == b,
+        _ => a == b,
+    }
+}
+
+/// A JSON number as whether it's negative, its significant digits, and the power of ten the first
+/// of them is in, with zero having no digits at all. None for an exponent too big to use.
+fn decimal(number: &str) -> Option<(bool, String, i64)> {
+    let (negative, unsigned) = match number.strip_prefix('-') {
+        Some(unsigned) => (true, unsigned),
+        None => (false, number),
+    };
+    let (mantissa, exponent) = match unsigned.split_once(['e', 'E']) {
+        Some((mantissa, exponent)) => (mantissa, exponent.parse::<i64>().ok()?),
+        None => (unsigned, 0),
+    };
+    let (integer, fraction) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+    let (digits, exponent) = match integer.trim_start_matches('0') {
+        // The zeros leading the fraction only move the first digit further down
+        "" => {
+            let significant = fraction.trim_start_matches('0');
+            (significant.to_string(), exponent.checked_sub((fraction.len() - significant.len()) as i64 + 1)?)
+        },
+        integer => (format!("{}{}", integer, fraction), exponent.checked_add(integer.len() as i64 - 1)?),
+    };
+    match digits.trim_end_matches('0') {
+        "" => Some((false, String::new(), 0)),
+        digits => Some((negative, digits.to_string(), exponent)),
+    }
+}
+
+/// The changes as a JSON Patch, its operations in the order the changes are in
+pub fn to_json_patch(changes: &[Change]) -> JsonValue {
+    let operation = |op: &str, path: &str, value: Option<&JsonValue>| {
+        let mut members = vec![("op".to_string(), JsonValue::String(op.to_string())), ("path".to_string(), JsonValue::String(path.to_string()))];
+        members.extend(value.map(|value| ("value".to_string(), value.clone())));
+        JsonValue::Object(members)
+    };
+    JsonValue::Array(
+        changes
+            .iter()
+            .map(|change| match change {
+                Change::Added { path, value } => operation("add", path, Some(value)),
+                Change::Removed { path, .. } => operation("remove", path, None),
+                Change::Changed { path, to, .. } => operation("replace", path, Some(to)),
+            })
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse_str, to_string_compact};
+
+    fn changes(from: &str, to: &str) -> Vec<String> {
+        let (from, to) = (parse_str(from).unwrap(), parse_str(to).unwrap());
+        let changes = diff(&from, &to);
+        let changes = changes.iter().map(|change| match change {
+            Change::Added { path, value } => format!("+ {} {}", path, to_string_compact(value)),
+            Change::Removed { path, value } => format!("- {} {}", path, to_string_compact(value)),
+            Change::Changed { path, from, to } => format!("~ {} {} {}", path, to_string_compact(from), to_string_compact(to)),
+        });
+        changes.collect()
+    }
+
+    #[test]
+    fn check_diff() {
+        let from = r#"{"name": "app", "port": 80, "tags": ["a", "b", "c"], "env": {"DEBUG": true, "a/b": 1}, "gone": null}"#;
+        let to = r#"{"env": {"a/b": 2, "DEBUG": true, "LEVEL": "info"}, "port": "80", "tags": ["a", "x"], "name": "app", "new~": []}"#;
+        assert_eq!(changes(from, to), [
+            "~ /port 80 \"80\"",
+            "~ /tags/1 \"b\" \"x\"",
+            "- /tags/2 \"c\"",
+            "~ /env/a~1b 1 2",
+            "+ /env/LEVEL \"info\"",
+            "- /gone null",
+            "+ /new~0 []",
+        ]);
+        assert_eq!(changes("[1]", "[1, {}, 3]"), ["+ /1 {}", "+ /2 3"]);
+        assert_eq!(changes("[1, 2, 3]", "[]"), ["- /2 3", "- /1 2", "- /0 1"]);
+        assert_eq!(changes("[1]", r#"{"0": 1}"#), ["~  [1] {\"0\":1}"]);
+        // The last value given for a key is the one compared
+        assert_eq!(changes(r#"{"a": 1, "a": 2}"#, r#"{"a": 2}"#), Vec::<String>::new());
+        assert_eq!(changes(r#"{"a": 1, "a": 2}"#, r#"{}"#), ["- /a 2"]);
+    }
+
+    #[test]
+    fn check_diff_compares_numbers_by_value() {
+        let same = ["1", "1.0", "1e0", "10e-1", "0.1E1", "1.000"];
+        for (a, b) in same.iter().zip(same.iter().skip(1)) {
+            assert!(changes(a, b).is_empty(), "{} and {}", a, b);
+        }
+        assert!(changes("[0, -0, 0.0e5, 120, 0.0012, -5e-3]", "[-0.0, 0, 0, 1.2e2, 12e-4, -0.005]").is_empty());
+        assert_eq!(changes("[1, 1e400, 0.1]", "[-1, 1e401, 0.10000000000000001]"), ["~ /0 1 -1", "~ /1 1e400 1e401", "~ /2 0.1 0.10000000000000001"]);
+    }
+
+    #[test]
+    fn check_to_json_patch() {
+        let (from, to) = (parse_str(r#"{"a": 1, "b": [true]}"#).unwrap(), parse_str(r#"{"a": 2, "c": null, "b": []}"#).unwrap());
+        assert_eq!(
+            to_string_compact(&to_json_patch(&diff(&from, &to))),
+            r#"[{"op":"replace","path":"/a","value":2},{"op":"remove","path":"/b/0"},{"op":"add","path":"/c","value":null}]"#,
+        );
+        assert_eq!(to_string_compact(&to_json_patch(&diff(&from, &from))), "[]");
+    }
+}
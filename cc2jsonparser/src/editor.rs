@@ -0,0 +1,302 @@
+// A line editor for the REPL's prompt.
+// A terminal left to itself only lets the line being typed be rubbed out from the end. With it in
+// raw mode while a line is read, each key comes in as it's pressed instead, so the cursor can move
+// along the line, and the lines entered before can be brought back with the up and down arrows.
+// The keys are those of readline's emacs mode that most people's fingers know: Ctrl-A and Ctrl-E
+// for the start and end of the line, Ctrl-K and Ctrl-U to cut to them, Ctrl-C to start the line
+// again, and Ctrl-D at the start of an empty line to end the input. Raw mode is set with stty, so
+// where there's no stty, or no terminal, lines are read as the terminal gives them.
+// Reference:  https://vt100.net/docs/vt100-ug/chapter3.html
+use std::fs::File;
+use std::io::{self, BufRead, Read, Write};
+use std::process::{Command, Stdio};
+
+/// Reads lines typed at a terminal, keeping every one entered to bring back
+#[derive(Debug, Default)]
+pub(crate) struct Editor {
+    history: Vec<String>,
+}
+
+/// What a key does to the line being edited
+#[derive(Clone, Debug, PartialEq)]
+enum Key {
+    Insert(char),
+    Enter,
+    Backspace,
+    Delete,
+    Left,
+    Right,
+    Home,
+    End,
+    Up,
+    Down,
+    /// Ctrl-K
+    CutToEnd,
+    /// Ctrl-U
+    CutToStart,
+    /// Ctrl-C
+    Cancel,
+    /// Ctrl-D, which ends the input on an empty line and deletes otherwise
+    EndOfInput,
+    Ignored,
+}
+
+impl Editor {
+    /// Write the prompt and read a line from `input`, without its line break, or None once the
+    /// input ends. The line is edited as it's typed if the terminal can be put in raw mode.
+    pub(crate) fn read_line(&mut self, prompt: &str, input: &mut impl BufRead, out: &mut impl Write) -> io::Result<Option<String>> {
+        let Some(_raw) = RawMode::enable() else {
+            out.write_all(prompt.as_bytes())?;
+            out.flush()?;
+            return read_line(input);
+        };
+        self.edit(prompt, input, out)
+    }
+
+    /// Read a line a key at a time, redrawing it after each
+    fn edit(&mut self, prompt: &str, input: &mut impl Read, out: &mut impl Write) -> io::Result<Option<String>> {
+        let mut line: Vec<char> = Vec::new();
+        let mut cursor = 0;
+        // Which line of the history is shown, with the one being typed kept while it isn't
+        let mut shown = self.history.len();
+        let mut typed = Vec::new();
+        redraw(out, prompt, &line, cursor)?;
+        loop {
+            match read_key(input)? {
+                None => return Ok(None),
+                Some(Key::EndOfInput) if line.is_empty() => {
+                    out.write_all(b"\r\n")?;
+                    return Ok(None);
+                },
+                Some(Key::Enter) => {
+                    out.write_all(b"\r\n")?;
+                    out.flush()?;
+                    let line: String = line.into_iter().collect();
+                    if !line.trim().is_empty() && self.history.last() != Some(&line) {
+                        self.history.push(line.clone());
+                    }
+                    return Ok(Some(line));
+                },
+                Some(Key::Insert(c)) => {
+                    line.insert(cursor, c);
+                    cursor += 1;
+                },
+                Some(Key::Backspace) if cursor > 0 => {
+                    cursor -= 1;
+                    line.remove(cursor);
+                },
+                Some(Key::Delete | Key::EndOfInput) if cursor < line.len() => {
+                    line.remove(cursor);
+                },
+                Some(Key::Left) => cursor = cursor.saturating_sub(1),
+                Some(Key::Right) => cursor = (cursor + 1).min(line.len()),
+                Some(Key::Home) => cursor = 0,
+                Some(Key::End) => cursor = line.len(),
+                Some(Key::CutToEnd) => line.truncate(cursor),
+                Some(Key::CutToStart) => {
+                    line.drain(..cursor);
+                    cursor = 0;
+                },
+                Some(Key::Cancel) => {
+                    out.write_all(b"^C\r\n")?;
+                    line.clear();
+                    cursor = 0;
+                    shown = self.history.len();
+                },
+                Some(Key::Up) if shown > 0 => {
+                    if shown == self.history.len() {
+                        typed = line;
+                    }
+                    shown -= 1;
+                    line = self.history[shown].chars().collect();
+                    cursor = line.len();
+                },
+                Some(Key::Down) if shown < self.history.len() => {
+                    shown += 1;
+                    line = match self.history.get(shown) {
+                        Some(entered) => entered.chars().collect(),
+                        None => std::mem::take(&mut typed),
+                    };
+                    cursor = line.len();
+                },
+                Some(_) => continue,
+            }
+            redraw(out, prompt, &line, cursor)?;
+        }
+    }
+}
+
+/// Write the prompt and line over what's on the terminal's line, with the cursor where it is in
+/// the line, a column to each character
+fn redraw(out: &mut impl Write, prompt: &str, line: &[char], cursor: usize) -> io::Result<()> {
+    let line: String = line.iter().collect();
+    write!(out, "\r{}{}\x1b[K\r", prompt, line)?;
+    let column = prompt.chars().count() + cursor;
+    if column > 0 {
+        write!(out, "\x1b[{}C", column)?;
+    }
+    out.flush()
+}
+
+/// The next key pressed, or None once the input ends
+fn read_key(input: &mut impl Read) -> io::Result<Option<Key>> {
+    let Some(byte) = read_byte(input)? else {
+        return Ok(None);
+    };
+    let key = match byte {
+        b'\r' | b'\n' => Key::Enter,
+        0x7f | 0x08 => Key::Backspace,
+        0x01 => Key::Home,
+        0x02 => Key::Left,
+        0x03 => Key::Cancel,
+        0x04 => Key::EndOfInput,
+        0x05 => Key::End,
+        0x06 => Key::Right,
+        0x0b => Key::CutToEnd,
+        0x0e => Key::Down,
+        0x10 => Key::Up,
+        0x15 => Key::CutToStart,
+        0x1b => escape(input)?,
+        byte if byte < b' ' => Key::Ignored,
+        byte => character(input, byte)?,
+    };
+    Ok(Some(key))
+}
+
+/// The key an escape sequence stands for, read after its ESC
+fn escape(input: &mut impl Read) -> io::Result<Key> {
+    if !matches!(read_byte(input)?, Some(b'[' | b'O')) {
+        return Ok(Key::Ignored);
+    }
+    let key = match read_byte(input)? {
+        Some(b'A') => Key::Up,
+        Some(b'B') => Key::Down,
+        Some(b'C') => Key::Right,
+        Some(b'D') => Key::Left,
+        Some(b'H') => Key::Home,
+        Some(b'F') => Key::End,
+        // Those with a number, as `ESC [ 3 ~` is Delete, go on to a `~`
+        Some(digit @ b'0'..=b'9') => {
+            let mut number = vec![digit];
+            loop {
+                match read_byte(input)? {
+                    Some(b'~') | None => break,
+                    Some(byte) => number.push(byte),
+                }
+            }
+            match number.as_slice() {
+                b"1" | b"7" => Key::Home,
+                b"3" => Key::Delete,
+                b"4" | b"8" => Key::End,
+                _ => Key::Ignored,
+            }
+        },
+        _ => Key::Ignored,
+    };
+    Ok(key)
+}
+
+/// The character a UTF-8 sequence starting with `first` stands for
+fn character(input: &mut impl Read, first: u8) -> io::Result<Key> {
+    let length = match first {
+        0xc0..=0xdf => 2,
+        0xe0..=0xef => 3,
+        0xf0..=0xf7 => 4,
+        _ => 1,
+    };
+    let mut bytes = vec![first];
+    for _ in 1..length {
+        match read_byte(input)? {
+            Some(byte) => bytes.push(byte),
+            None => break,
+        }
+    }
+    Ok(match std::str::from_utf8(&bytes).ok().and_then(|s| s.chars().next()) {
+        Some(c) => Key::Insert(c),
+        None => Key::Ignored,
+    })
+}
+
+fn read_byte(input: &mut impl Read) -> io::Result<Option<u8>> {
+    let mut byte = [0];
+    match input.read(&mut byte)? {
+        0 => Ok(None),
+        _ => Ok(Some(byte[0])),
+    }
+}
+
+/// Read a line as the terminal gives it, without its line break
+pub(crate) fn read_line(input: &mut impl BufRead) -> io::Result<Option<String>> {
+    let mut line = String::new();
+    if input.read_line(&mut line)? == 0 {
+        return Ok(None);
+    }
+    let without_break = line.strip_suffix('\n').unwrap_or(&line);
+    Ok(Some(without_break.strip_suffix('\r').unwrap_or(without_break).to_string()))
+}
+
+/// The terminal in raw mode, until this is dropped and it's put back how it was
+struct RawMode {
+    /// The settings it had, as `stty -g` gives them
+    saved: String,
+}
+
+impl RawMode {
+    /// Put the terminal in raw mode without echoing, or None if it can't be
+    fn enable() -> Option<RawMode> {
+        let saved = stty(&["-g"])?;
+        stty(&["raw", "-echo"])?;
+        Some(RawMode { saved: saved.trim().to_string() })
+    }
+}
+
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        stty(&[&self.saved]);
+    }
+}
+
+/// Run stty on the terminal, giving what it prints if it succeeds
+fn stty(args: &[&str]) -> Option<String> {
+    let terminal = File::open("/dev/tty").ok()?;
+    let output = Command::new("stty").args(args).stdin(terminal).stderr(Stdio::null()).output().ok()?;
+    output.status.success().then(|| String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The lines the editor reads from the keys, pressed after the lines given before them
+    fn edit(editor: &mut Editor, keys: &str) -> Option<String> {
+        editor.edit("> ", &mut keys.as_bytes(), &mut Vec::new()).unwrap()
+    }
+
+    #[test]
+    fn check_edit() {
+        let mut editor = Editor::default();
+        assert_eq!(edit(&mut editor, "[1, 2]\r"), Some("[1, 2]".to_string()));
+        // Left twice, Backspace, then Home and Ctrl-K
+        assert_eq!(edit(&mut editor, "abcd\x1b[D\x1b[D\x7fX\r"), Some("aXcd".to_string()));
+        assert_eq!(edit(&mut editor, "abc\x01\x0bxy\x1b[Dé\x05!\r"), Some("xéy!".to_string()));
+        assert_eq!(edit(&mut editor, "abc\x1b[D\x15\x1b[3~\r"), Some("".to_string()));
+        // Ctrl-C starts again, Ctrl-D ends the input only on an empty line
+        assert_eq!(edit(&mut editor, "abc\x03de\x01\x04\r"), Some("e".to_string()));
+        assert_eq!(edit(&mut editor, "\x04"), None);
+        assert_eq!(edit(&mut editor, "abc"), None);
+    }
+
+    #[test]
+    fn check_edit_history() {
+        let mut editor = Editor::default();
+        edit(&mut editor, "first\r");
+        edit(&mut editor, "second\r");
+        edit(&mut editor, "second\r");
+        edit(&mut editor, "  \r");
+        assert_eq!(editor.history, ["first", "second"]);
+        assert_eq!(edit(&mut editor, "\x1b[A\x1b[A\r"), Some("first".to_string()));
+        // Down past the newest brings back what was being typed
+        assert_eq!(edit(&mut editor, "typed\x1b[A\x1b[A\x1b[B\x1b[B\x1b[B\r"), Some("typed".to_string()));
+        assert_eq!(edit(&mut editor, "\x10!\r"), Some("typed!".to_string()));
+    }
+}
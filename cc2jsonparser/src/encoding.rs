@@ -0,0 +1,303 @@
+// Text encodings.
+// JSON is UTF-8 nowadays, but some programs, Windows ones especially, write it as UTF-16 or even
+// UTF-32. Which one input is in shows in its first bytes: there's either a byte order mark, or
+// the first character is ASCII since JSON starts with one, so the zero bytes around it give the
+// width and order of its code units. Anything other than UTF-8 is decoded to UTF-8 as it's read,
+// so the tokenizer only ever sees UTF-8, and the lines and columns of errors are the same as in an
+// editor. Byte offsets are into the decoded UTF-8 though, not the original.
+// Reference:  https://www.rfc-editor.org/rfc/rfc4627#section-3
+use std::{
+    fmt,
+    io::{self, BufRead, Cursor, Read},
+    str::FromStr,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Encoding {
+    Utf8,
+    Utf16Le,
+    Utf16Be,
+    Utf32Le,
+    Utf32Be,
+}
+
+/// An encoding name that isn't one of these
+#[derive(Clone, Debug, PartialEq)]
+pub struct EncodingError {
+    pub message: String,
+}
+
+impl fmt::Display for EncodingError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for EncodingError {}
+
+impl Encoding {
+    const ALL: [Encoding; 5] = [Encoding::Utf8, Encoding::Utf16Le, Encoding::Utf16Be, Encoding::Utf32Le, Encoding::Utf32Be];
+
+    /// The encoding that input starting with `start` is in, from its first four bytes if it has
+    /// that many
+    pub fn sniff(start: &[u8]) -> Encoding {
+        match start {
+            // Byte order marks, UTF-32 first since its little endian one starts like UTF-16's
+            [0xEF, 0xBB, 0xBF, ..] => Encoding::Utf8,
+            [0xFF, 0xFE, 0, 0, ..] => Encoding::Utf32Le,
+            [0, 0, 0xFE, 0xFF, ..] => Encoding::Utf32Be,
+            [0xFF, 0xFE, ..] => Encoding::Utf16Le,
+            [0xFE, 0xFF, ..] => Encoding::Utf16Be,
+            // Zero bytes around an ASCII character
+            [0, 0, 0, _, ..] => Encoding::Utf32Be,
+            [_, 0, 0, 0, ..] => Encoding::Utf32Le,
+            [0, _, ..] => Encoding::Utf16Be,
+            [_, 0, ..] => Encoding::Utf16Le,
+            _ => Encoding::Utf8,
+        }
+    }
+
+    /// How many bytes each code unit takes
+    fn unit_length(self) -> usize {
+        match self {
+            Encoding::Utf8 => 1,
+            Encoding::Utf16Le | Encoding::Utf16Be => 2,
+            Encoding::Utf32Le | Encoding::Utf32Be => 4,
+        }
+    }
+
+    fn unit(self, bytes: &[u8]) -> u32 {
+        match self {
+            Encoding::Utf8 => bytes[0].into(),
+            Encoding::Utf16Le => u16::from_le_bytes([bytes[0], bytes[1]]).into(),
+            Encoding::Utf16Be => u16::from_be_bytes([bytes[0], bytes[1]]).into(),
+            Encoding::Utf32Le => u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]),
+            Encoding::Utf32Be => u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]),
+        }
+    }
+
+    /// Decode the character `bytes` starts with, along with how many bytes it takes, or None if
+    /// they stop partway through it. UTF-8 is never decoded, it's read as it is.
+    fn decode_char(self, bytes: &[u8]) -> io::Result<Option<(char, usize)>> {
+        let length = self.unit_length();
+        if bytes.len() < length {
+            return Ok(None);
+        }
+        let unit = self.unit(bytes);
+        let invalid = |message: String| io::Error::new(io::ErrorKind::InvalidData, message);
+        let (code_point, length) = match (self, unit) {
+            (Encoding::Utf8, _) => unreachable!("UTF-8 is read without a decoder"),
+            (Encoding::Utf16Le | Encoding::Utf16Be, 0xD800..=0xDBFF) => {
+                if bytes.len() < 2 * length {
+                    return Ok(None);
+                }
+                match self.unit(&bytes[length..]) {
+                    low @ 0xDC00..=0xDFFF => (0x10000 + ((unit - 0xD800) << 10) + (low - 0xDC00), 2 * length),
+                    _ => return Err(invalid(format!("invalid UTF-16, unpaired surrogate 0x{:04X}", unit))),
+                }
+            },
+            (Encoding::Utf16Le | Encoding::Utf16Be, 0xDC00..=0xDFFF) => {
+                return Err(invalid(format!("invalid UTF-16, unpaired surrogate 0x{:04X}", unit)));
+            },
+            (_, unit) => (unit, length),
+        };
+        let ch = char::from_u32(code_point).ok_or_else(|| invalid(format!("invalid {}, 0x{:X} isn't a character", self, code_point)))?;
+        Ok(Some((ch, length)))
+    }
+}
+
+impl fmt::Display for Encoding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Encoding::Utf8 => "UTF-8",
+            Encoding::Utf16Le => "UTF-16LE",
+            Encoding::Utf16Be => "UTF-16BE",
+            Encoding::Utf32Le => "UTF-32LE",
+            Encoding::Utf32Be => "UTF-32BE",
+        })
+    }
+}
+
+impl FromStr for Encoding {
+    type Err = EncodingError;
+
+    /// Parse a name like `utf-16le`, however it's capitalized and with or without the `-`
+    fn from_str(s: &str) -> Result<Encoding, EncodingError> {
+        let name = s.to_ascii_uppercase().replace("UTF", "UTF-").replace("--", "-");
+        Encoding::ALL.into_iter().find(|encoding| encoding.to_string() == name).ok_or_else(|| EncodingError {
+            message: format!("unknown encoding `{}`, expected utf-8, utf-16le, utf-16be, utf-32le, or utf-32be", s),
+        })
+    }
+}
+
+/// Read `reader` as UTF-8 however it's encoded, as `encoding` if given or else as the encoding its
+/// first bytes show, giving which encoding that is. A byte order mark is decoded like any other
+/// character, to be skipped or not by the tokenizer.
+pub fn decode_reader<'a>(mut reader: impl BufRead + 'a, encoding: Option<Encoding>) -> io::Result<(Encoding, Box<dyn BufRead + 'a>)> {
+    // The bytes looked at are put back in front of the rest
+    let mut start = Vec::with_capacity(4);
+    (&mut reader).take(4).read_to_end(&mut start)?;
+    let encoding = encoding.unwrap_or_else(|| Encoding::sniff(&start));
+    let reader = Cursor::new(start).chain(reader);
+    match encoding {
+        Encoding::Utf8 => Ok((encoding, Box::new(reader))),
+        _ => Ok((encoding, Box::new(Decoder { reader, encoding, pending: Vec::new(), decoded: String::new(), read: 0, done: false }))),
+    }
+}
+
+/// Decodes UTF-16 or UTF-32 from `reader` to UTF-8 a buffer at a time
+struct Decoder<R> {
+    reader: R,
+    encoding: Encoding,
+    /// Bytes read that don't make a whole character yet
+    pending: Vec<u8>,
+    decoded: String,
+    /// How much of `decoded` has been read
+    read: usize,
+    /// Whether `reader` has run out
+    done: bool,
+}
+
+impl<R: BufRead> Decoder<R> {
+    /// Decode the whole characters in what's pending onto `decoded`
+    fn decode_pending(&mut self) -> io::Result<()> {
+        let mut at = 0;
+        while let Some((ch, length)) = self.encoding.decode_char(&self.pending[at..])? {
+            self.decoded.push(ch);
+            at += length;
+        }
+        self.pending.drain(..at);
+        Ok(())
+    }
+}
+
+impl<R: BufRead> Read for Decoder<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let available = self.fill_buf()?;
+        let length = available.len().min(buf.len());
+        buf[..length].copy_from_slice(&available[..length]);
+        self.consume(length);
+        Ok(length)
+    }
+}
+
+impl<R: BufRead> BufRead for Decoder<R> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        while self.read == self.decoded.len() && !self.done {
+            self.decoded.clear();
+            self.read = 0;
+            let bytes = self.reader.fill_buf()?;
+            if bytes.is_empty() {
+                self.done = true;
+                if !self.pending.is_empty() {
+                    let message = format!("the input ends partway through a {} character", self.encoding);
+                    return Err(io::Error::new(io::ErrorKind::UnexpectedEof, message));
+                }
+                break;
+            }
+            self.pending.extend_from_slice(bytes);
+            let length = bytes.len();
+            self.reader.consume(length);
+            self.decode_pending()?;
+        }
+        Ok(&self.decoded.as_bytes()[self.read..])
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.read += amt;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utf16le(text: &str) -> Vec<u8> {
+        text.encode_utf16().flat_map(u16::to_le_bytes).collect()
+    }
+
+    fn utf16be(text: &str) -> Vec<u8> {
+        text.encode_utf16().flat_map(u16::to_be_bytes).collect()
+    }
+
+    fn utf32le(text: &str) -> Vec<u8> {
+        text.chars().flat_map(|c| (c as u32).to_le_bytes()).collect()
+    }
+
+    fn utf32be(text: &str) -> Vec<u8> {
+        text.chars().flat_map(|c| (c as u32).to_be_bytes()).collect()
+    }
+
+    fn decode(bytes: &[u8], encoding: Option<Encoding>) -> io::Result<(Encoding, String)> {
+        let (encoding, mut reader) = decode_reader(bytes, encoding)?;
+        let mut text = String::new();
+        reader.read_to_string(&mut text)?;
+        Ok((encoding, text))
+    }
+
+    #[test]
+    fn check_sniff() {
+        let text = "{\"caf\u{e9}\": \"\u{1f600}\"}";
+        for (bytes, encoding) in [
+            (text.as_bytes().to_vec(), Encoding::Utf8),
+            (utf16le(text), Encoding::Utf16Le),
+            (utf16be(text), Encoding::Utf16Be),
+            (utf32le(text), Encoding::Utf32Le),
+            (utf32be(text), Encoding::Utf32Be),
+            (utf16le("1"), Encoding::Utf16Le),
+        ] {
+            assert_eq!(Encoding::sniff(&bytes), encoding, "{:?}", bytes);
+        }
+        // By the byte order mark
+        for (bytes, encoding) in [
+            (b"\xef\xbb\xbf\x00".as_slice(), Encoding::Utf8),
+            (b"\xff\xfe[\x00", Encoding::Utf16Le),
+            (b"\xfe\xff\x00[", Encoding::Utf16Be),
+            (b"\xff\xfe\x00\x00", Encoding::Utf32Le),
+            (b"\x00\x00\xfe\xff", Encoding::Utf32Be),
+        ] {
+            assert_eq!(Encoding::sniff(bytes), encoding, "{:?}", bytes);
+        }
+        assert_eq!(Encoding::sniff(b""), Encoding::Utf8);
+        assert_eq!(Encoding::sniff(b"1"), Encoding::Utf8);
+    }
+
+    #[test]
+    fn check_decode_reader() {
+        let text = "{\"caf\u{e9}\": \"\u{1f600}\"}\n";
+        for (bytes, encoding) in [
+            (text.as_bytes().to_vec(), Encoding::Utf8),
+            (utf16le(text), Encoding::Utf16Le),
+            (utf16be(text), Encoding::Utf16Be),
+            (utf32le(text), Encoding::Utf32Le),
+            (utf32be(text), Encoding::Utf32Be),
+        ] {
+            assert_eq!(decode(&bytes, None).unwrap(), (encoding, text.to_string()));
+            assert_eq!(decode(&bytes, Some(encoding)).unwrap(), (encoding, text.to_string()));
+        }
+        // The byte order mark is kept
+        let with_bom = utf16le("\u{feff}[]");
+        assert_eq!(decode(&with_bom, None).unwrap(), (Encoding::Utf16Le, "\u{feff}[]".to_string()));
+        // Decoding as something else is up to whoever asks for it
+        assert_eq!(decode(b"[1]", Some(Encoding::Utf16Le)).unwrap_err().to_string(), "the input ends partway through a UTF-16LE character");
+    }
+
+    #[test]
+    fn check_decode_reader_errors() {
+        let error = |bytes: &[u8], encoding| decode(bytes, Some(encoding)).unwrap_err().to_string();
+        assert_eq!(error(b"[\x00\x00\xd8]\x00", Encoding::Utf16Le), "invalid UTF-16, unpaired surrogate 0xD800");
+        assert_eq!(error(b"\x00[\xdc\x00", Encoding::Utf16Be), "invalid UTF-16, unpaired surrogate 0xDC00");
+        assert_eq!(error(b"[\x00\x00\x00\x00\x00\x11\x00", Encoding::Utf32Le), "invalid UTF-32LE, 0x110000 isn't a character");
+        assert_eq!(error(b"\x00\x00\x00[\x00\x00", Encoding::Utf32Be), "the input ends partway through a UTF-32BE character");
+    }
+
+    #[test]
+    fn check_from_str() {
+        for (name, encoding) in [("utf-8", Encoding::Utf8), ("UTF8", Encoding::Utf8), ("utf-16le", Encoding::Utf16Le), ("utf16be", Encoding::Utf16Be),
+                ("UTF-32LE", Encoding::Utf32Le), ("utf-32be", Encoding::Utf32Be)] {
+            assert_eq!(name.parse(), Ok(encoding), "{}", name);
+        }
+        assert_eq!("latin1".parse::<Encoding>().unwrap_err().message,
+            "unknown encoding `latin1`, expected utf-8, utf-16le, utf-16be, utf-32le, or utf-32be");
+    }
+}
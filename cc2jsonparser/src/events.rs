@@ -0,0 +1,389 @@
+// Streaming JSON.
+// Rather than parsing a whole document into a JsonValue, JsonEvents reads it as it goes and gives
+// each part of it as an event, so
+//
+//   {"a": [1, true]}
+//
+// comes out as StartObject, Key("a"), StartArray, Value(1), Value(true), EndArray, EndObject.
+// Only the line being read and the objects and arrays still open are kept, so a document of any
+// size can be checked or picked through in the memory of its longest line.
+use crate::json5;
+use crate::number::number_warning;
+use crate::token::{StreamError, TokenStream};
+use crate::{string_value, DuplicateKey, JsonError, JsonValue, LineCol, NumberWarning, ParseError, ParseOptions, Span, Spanned, Token, TrailingComma, Warnings};
+use std::{collections::HashMap, io::BufRead};
+
+/// One part of a document
+#[derive(Clone, Debug, PartialEq)]
+pub enum JsonEvent {
+    StartObject,
+    /// The key of the object member whose value comes next
+    Key(String),
+    EndObject,
+    StartArray,
+    EndArray,
+    /// A string, number, boolean, or null
+    Value(JsonValue),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Open {
+    Object,
+    Array,
+}
+
+impl Open {
+    fn close(self) -> Token<'static> {
+        match self {
+            Open::Object => Token::RightBrace,
+            Open::Array => Token::RightBracket,
+        }
+    }
+}
+
+/// What's read next
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Expect {
+    Value,
+    /// The first member or element of an object or array just opened, or its close
+    First,
+    /// The key and colon of an object member
+    Key,
+    /// A comma, or the close of the innermost open object or array
+    Separator,
+    /// The end of the input, after the document
+    End,
+    /// Nothing, the document having ended or gone wrong
+    Done,
+}
+
+/// The events of a document read from `R`, ending at the first error
+#[derive(Debug)]
+pub struct JsonEvents<R> {
+    tokens: TokenStream<R>,
+    open: Vec<Open>,
+    expect: Expect,
+    max_depth: Option<usize>,
+    allow_trailing_commas: bool,
+    json5: bool,
+    /// Where the comma just read is, until what comes after it is
+    comma: Option<(LineCol, Span)>,
+    /// Every trailing comma read past so far, if they're allowed
+    trailing_commas: Vec<TrailingComma>,
+    bignum: bool,
+    /// Every number read so far as something other than what's written
+    numbers: Vec<NumberWarning>,
+    /// Where the token of the last event starts
+    position: LineCol,
+    /// The bytes of the token of the last event
+    span: Span,
+}
+
+impl<R: BufRead> JsonEvents<R> {
+    pub fn new(reader: R) -> JsonEvents<R> {
+        JsonEvents::with_options(reader, &ParseOptions::default())
+    }
+
+    /// Read the document within the limits of `options`
+    pub fn with_options(reader: R, options: &ParseOptions) -> JsonEvents<R> {
+        JsonEvents {
+            tokens: TokenStream::with_options(reader, options),
+            open: Vec::new(),
+            expect: Expect::Value,
+            max_depth: options.max_depth,
+            allow_trailing_commas: options.allow_trailing_commas || options.json5,
+            json5: options.json5,
+            comma: None,
+            trailing_commas: Vec::new(),
+            bignum: options.bignum,
+            numbers: Vec::new(),
+            position: LineCol { line: 1, column: 1 },
+            span: Span::point(0),
+        }
+    }
+
+    /// Where the last event was in the input
+    pub fn position(&self) -> LineCol {
+        self.position
+    }
+
+    /// The bytes of the input the last event was read from. For an object or array's start or
+    /// end that's its brace or bracket, and for a key the key itself, quotes and all.
+    pub fn span(&self) -> Span {
+        self.span
+    }
+
+    /// The next token, taken as the event's
+    fn read(&mut self) -> Result<Spanned<Token<'static>>, JsonError> {
+        let token = self.tokens.next_token().map_err(|e| match e {
+            StreamError::Token(e, _) | StreamError::Skipped(e) | StreamError::Read(e) => JsonError::Tokenize(e),
+        })?;
+        self.position = token.position;
+        self.span = token.span;
+        Ok(token)
+    }
+
+    fn next_event(&mut self) -> Result<Option<JsonEvent>, JsonError> {
+        loop {
+            if self.expect == Expect::Done {
+                return Ok(None);
+            }
+            // What's read is never put back, since anything but what's expected is an error
+            let token = self.read()?;
+            let close = self.open.last().map(|open| open.close());
+            // A comma before the close is read past as though it weren't there, if it's allowed
+            let trailing = self.comma.take().filter(|_| self.allow_trailing_commas && close.as_ref() == Some(&token.value));
+            if let Some((position, span)) = trailing {
+                if !self.json5 {
+                    self.trailing_commas.push(TrailingComma { in_object: close == Some(Token::RightBrace), position, span });
+                }
+                self.expect = Expect::Separator;
+            }
+            let event = match (self.expect, &token.value) {
+                (Expect::End, Token::Eof) => {
+                    self.expect = Expect::Done;
+                    return Ok(None);
+                },
+                (Expect::End, _) => return Err(parse_error("the end of input after the value", &token)),
+                (Expect::Value, Token::Eof) if self.open.is_empty() => return Err(JsonError::Empty),
+
+                (Expect::First | Expect::Separator, t) if Some(t) == close.as_ref() => {
+                    let event = match self.open.pop() {
+                        Some(Open::Object) => JsonEvent::EndObject,
+                        _ => JsonEvent::EndArray,
+                    };
+                    self.after_value();
+                    event
+                },
+                (Expect::Separator, Token::Comma) => {
+                    self.comma = Some((token.position, token.span));
+                    self.expect = self.first_expected();
+                    continue;
+                },
+                (Expect::Separator, _) => {
+                    let expected = match self.open.last() {
+                        Some(Open::Object) => "`,` or `}` after the object member",
+                        _ => "`,` or `]` after the array element",
+                    };
+                    return Err(parse_error(expected, &token));
+                },
+
+                (Expect::Key | Expect::First, t) if close == Some(Token::RightBrace) => {
+                    let key = match t {
+                        Token::String(raw) => string_value(raw).ok_or_else(|| parse_error("a valid string", &token))?,
+                        t if self.json5 => match json5::key_name(t) {
+                            Some(name) => name.to_string(),
+                            None => return Err(parse_error("a string or identifier for the object key", &token)),
+                        },
+                        _ => return Err(parse_error("a string for the object key", &token)),
+                    };
+                    let colon = self.read()?;
+                    if colon.value != Token::Colon {
+                        return Err(parse_error("`:` after the object key", &colon));
+                    }
+                    // The key's event is where the key is
+                    self.position = token.position;
+                    self.span = token.span;
+                    self.expect = Expect::Value;
+                    JsonEvent::Key(key)
+                },
+
+                (_, Token::LeftBrace | Token::LeftBracket) => {
+                    if let Some(max_depth) = self.max_depth.filter(|max| self.open.len() == *max) {
+                        return Err(JsonError::Parse(ParseError::too_deep(max_depth, &token)));
+                    }
+                    let (open, event) = match token.value {
+                        Token::LeftBrace => (Open::Object, JsonEvent::StartObject),
+                        _ => (Open::Array, JsonEvent::StartArray),
+                    };
+                    self.open.push(open);
+                    self.expect = Expect::First;
+                    event
+                },
+                (_, Token::String(raw)) => {
+                    let string = string_value(raw).ok_or_else(|| parse_error("a valid string", &token))?;
+                    self.after_value();
+                    JsonEvent::Value(JsonValue::String(string))
+                },
+                (_, Token::Number(_) | Token::True | Token::False | Token::Null) => {
+                    self.numbers.extend(number_warning(&token, self.bignum));
+                    let value = match token.value {
+                        Token::Number(number) => json5::number_value(&number),
+                        Token::True => JsonValue::Bool(true),
+                        Token::False => JsonValue::Bool(false),
+                        _ => JsonValue::Null,
+                    };
+                    self.after_value();
+                    JsonEvent::Value(value)
+                },
+                _ => return Err(parse_error("a value", &token)),
+            };
+            return Ok(Some(event));
+        }
+    }
+
+    /// What comes first in the innermost open object or array, and after each comma
+    fn first_expected(&self) -> Expect {
+        match self.open.last() {
+            Some(Open::Object) => Expect::Key,
+            _ => Expect::Value,
+        }
+    }
+
+    /// Go on from a whole value to what can come after it
+    fn after_value(&mut self) {
+        self.expect = if self.open.is_empty() { Expect::End } else { Expect::Separator };
+    }
+}
+
+fn parse_error(expected: &str, token: &Spanned<Token>) -> JsonError {
+    JsonError::Parse(ParseError::new(expected, token))
+}
+
+impl<R: BufRead> Iterator for JsonEvents<R> {
+    type Item = Result<JsonEvent, JsonError>;
+
+    fn next(&mut self) -> Option<Result<JsonEvent, JsonError>> {
+        match self.next_event() {
+            Ok(event) => event.map(Ok),
+            Err(e) => {
+                self.expect = Expect::Done;
+                Some(Err(e))
+            },
+        }
+    }
+}
+
+/// Check a JSON document read from `reader` within the limits of `options` without keeping it in
+/// memory, giving what it has in it to warn about
+pub fn validate_reader(reader: impl BufRead, options: &ParseOptions) -> Result<Warnings, JsonError> {
+    let mut events = JsonEvents::with_options(reader, options);
+    let mut duplicates = Vec::new();
+    // Where each key of each open object was first written, None for arrays
+    let mut seen: Vec<Option<HashMap<String, LineCol>>> = Vec::new();
+    while let Some(event) = events.next() {
+        match event? {
+            JsonEvent::StartObject => seen.push(Some(HashMap::new())),
+            JsonEvent::StartArray => seen.push(None),
+            JsonEvent::EndObject | JsonEvent::EndArray => {
+                seen.pop();
+            },
+            JsonEvent::Key(key) => {
+                let (position, span) = (events.position(), events.span());
+                if let Some(Some(keys)) = seen.last_mut() {
+                    match keys.get(&key) {
+                        Some(&first) => duplicates.push(DuplicateKey { key, first, position, span }),
+                        None => {
+                            keys.insert(key, position);
+                        },
+                    }
+                }
+            },
+            JsonEvent::Value(_) => {},
+        }
+    }
+    Ok(Warnings { duplicates, trailing_commas: events.trailing_commas, numbers: events.numbers })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_reader_with;
+    use std::io::Cursor;
+
+    fn events(input: &str) -> Result<Vec<JsonEvent>, JsonError> {
+        JsonEvents::new(Cursor::new(input)).collect()
+    }
+
+    #[test]
+    fn check_events() {
+        let number = |n: &str| JsonEvent::Value(JsonValue::Number(n.to_string()));
+        assert_eq!(events("{\"a\": [1, true, {}], \"b\": {\"c\": null}, \"d\": []}"), Ok(vec![
+            JsonEvent::StartObject,
+            JsonEvent::Key("a".to_string()),
+            JsonEvent::StartArray,
+            number("1"),
+            JsonEvent::Value(JsonValue::Bool(true)),
+            JsonEvent::StartObject,
+            JsonEvent::EndObject,
+            JsonEvent::EndArray,
+            JsonEvent::Key("b".to_string()),
+            JsonEvent::StartObject,
+            JsonEvent::Key("c".to_string()),
+            JsonEvent::Value(JsonValue::Null),
+            JsonEvent::EndObject,
+            JsonEvent::Key("d".to_string()),
+            JsonEvent::StartArray,
+            JsonEvent::EndArray,
+            JsonEvent::EndObject,
+        ]));
+        assert_eq!(events("\"a\\u00e9\""), Ok(vec![JsonEvent::Value(JsonValue::String("aé".to_string()))]));
+        assert_eq!(events("\n 12 \n"), Ok(vec![number("12")]));
+    }
+
+    #[test]
+    fn check_events_positions() {
+        let mut events = JsonEvents::new(Cursor::new("{\n  \"a\": [1,\n   2]}"));
+        let mut positions = Vec::new();
+        while let Some(Ok(_)) = events.next() {
+            positions.push(events.position().to_string());
+        }
+        assert_eq!(positions, ["1:1", "2:3", "2:8", "2:9", "3:4", "3:5", "3:6"]);
+
+        let mut events = JsonEvents::new(Cursor::new("{\"key\": \"caf\u{e9}\"}"));
+        let mut spans = Vec::new();
+        while let Some(Ok(_)) = events.next() {
+            spans.push((events.span().start, events.span().end));
+        }
+        assert_eq!(spans, [(0, 1), (1, 6), (8, 15), (15, 16)]);
+    }
+
+    #[test]
+    fn check_events_errors() {
+        // Stopping at the same first error as parsing the whole document
+        for input in ["", "  ", "[1, 2,]", "{\"a\" 1}", "{\"a\": 1,}", "[1 2]", "{\"a\": 1] ", "[1] 2", "{1: 2}", "[tru]", "[\"a\\x\"]", "[1, {"] {
+            let expected = parse_reader_with(Cursor::new(input), &ParseOptions::default()).unwrap_err();
+            let mut events = JsonEvents::new(Cursor::new(input));
+            assert_eq!(events.find_map(Result::err), Some(expected), "{:?}", input);
+            assert_eq!(events.next(), None);
+        }
+        let options = ParseOptions { max_depth: Some(2), ..ParseOptions::default() };
+        let e = JsonEvents::with_options(Cursor::new("[[[1]]]"), &options).find_map(Result::err).unwrap();
+        assert_eq!(e.to_string(), "maximum depth exceeded, `[` nests deeper than 2 levels at line 1, column 3");
+    }
+
+    #[test]
+    fn check_validate_reader() {
+        let deep = format!("{}{}", "[".repeat(1_000_000), "]".repeat(1_000_000));
+        assert_eq!(validate_reader(Cursor::new(deep), &ParseOptions { max_depth: None, ..ParseOptions::default() }), Ok(Warnings::default()));
+
+        let warnings = validate_reader(Cursor::new("{\"a\": 1, \"b\": [{\"a\": 2, \"a\": 3}], \"a\": 4}"), &ParseOptions::default()).unwrap();
+        let duplicates: Vec<String> = warnings.duplicates.iter().map(|d| d.to_string()).collect();
+        assert_eq!(duplicates, [
+            "duplicate key `a` at line 1, column 25, first written at line 1, column 17",
+            "duplicate key `a` at line 1, column 35, first written at line 1, column 2",
+        ]);
+        assert_eq!(validate_reader(Cursor::new("[1,]"), &ParseOptions::default()).unwrap_err().to_string(),
+            "expected a value, found `]` at line 1, column 4");
+    }
+
+    #[test]
+    fn check_validate_reader_trailing_commas() {
+        let options = ParseOptions { allow_trailing_commas: true, ..ParseOptions::default() };
+        let input = "{\"a\": [1, 2,],\n \"b\": {\"c\": [],},\n}";
+        let warnings = validate_reader(Cursor::new(input), &options).unwrap();
+        let trailing: Vec<String> = warnings.trailing_commas.iter().map(|t| t.to_string()).collect();
+        assert_eq!(trailing, [
+            "trailing comma after the last array element at line 1, column 12",
+            "trailing comma after the last object member at line 2, column 15",
+            "trailing comma after the last object member at line 2, column 17",
+        ]);
+        assert_eq!(parse_reader_with(Cursor::new(input), &options).unwrap().1, warnings);
+
+        // Only one, and only after something
+        for input in ["[1,,]", "[,]", "{,}", "{\"a\": 1,,}"] {
+            assert!(validate_reader(Cursor::new(input), &options).is_err(), "{}", input);
+            assert!(parse_reader_with(Cursor::new(input), &options).is_err(), "{}", input);
+        }
+    }
+}
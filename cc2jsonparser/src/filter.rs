@@ -0,0 +1,325 @@
+// jq filters, or the part of them most useful for poking around a document.
+// A filter takes a value and gives any number of values: `.` gives the value itself,
+// `.key` or `.["key"]` a member of an object, `.[2]` an element of an array, counting from the
+// end if negative, and `.[]` every member or element. Steps chain, as in `.items[0].name`, and
+// `a | b` runs b on each value a gives while `a, b` gives a's values and then b's, e.g.
+//
+//   .store.books[] | .title, .author
+//
+// Like jq, a key an object doesn't have or an index past the end of an array gives null, and so
+// does any step on null.
+// Reference:  https://jqlang.github.io/jq/manual/
+use crate::{string_value, JsonValue};
+use std::{fmt, str::FromStr};
+
+static NULL: JsonValue = JsonValue::Null;
+
+#[derive(Clone, Debug, PartialEq)]
+enum Step {
+    Key(String),
+    Index(i64),
+    /// `[]`
+    Each,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Expr {
+    /// `.` and the steps after it
+    Path(Vec<Step>),
+    /// `a, b`
+    Comma(Box<Expr>, Box<Expr>),
+    /// `a | b`
+    Pipe(Box<Expr>, Box<Expr>),
+}
+
+/// A compiled filter
+#[derive(Clone, Debug, PartialEq)]
+pub struct Filter {
+    expr: Expr,
+}
+
+/// A filter that couldn't be compiled, or a value it couldn't be run on
+#[derive(Clone, Debug, PartialEq)]
+pub struct FilterError {
+    pub message: String,
+}
+
+impl FilterError {
+    fn new(message: impl Into<String>) -> FilterError {
+        FilterError { message: message.into() }
+    }
+}
+
+impl fmt::Display for FilterError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for FilterError {}
+
+impl Filter {
+    pub fn new(filter: &str) -> Result<Filter, FilterError> {
+        let mut parser = Parser { chars: filter.chars().collect(), next: 0 };
+        let expr = parser.pipe()?;
+        parser.skip_whitespace();
+        match parser.peek() {
+            None => Ok(Filter { expr }),
+            Some(c) => Err(parser.error(&format!("unexpected `{}`", c))),
+        }
+    }
+
+    /// The values the filter gives for `input`, in order
+    pub fn apply<'a>(&self, input: &'a JsonValue) -> Result<Vec<&'a JsonValue>, FilterError> {
+        let mut values = Vec::new();
+        apply(&self.expr, input, &mut values)?;
+        Ok(values)
+    }
+}
+
+impl FromStr for Filter {
+    type Err = FilterError;
+
+    fn from_str(s: &str) -> Result<Filter, FilterError> {
+        Filter::new(s)
+    }
+}
+
+fn apply<'a>(expr: &Expr, input: &'a JsonValue, values: &mut Vec<&'a JsonValue>) -> Result<(), FilterError> {
+    match expr {
+        Expr::Path(steps) => {
+            let mut current = vec![input];
+            for step in steps {
+                let mut next = Vec::new();
+                for value in current {
+                    apply_step(step, value, &mut next)?;
+                }
+                current = next;
+            }
+            values.extend(current);
+        },
+        Expr::Comma(a, b) => {
+            apply(a, input, values)?;
+            apply(b, input, values)?;
+        },
+        Expr::Pipe(a, b) => {
+            let mut piped = Vec::new();
+            apply(a, input, &mut piped)?;
+            for value in piped {
+                apply(b, value, values)?;
+            }
+        },
+    }
+    Ok(())
+}
+
+fn apply_step<'a>(step: &Step, value: &'a JsonValue, values: &mut Vec<&'a JsonValue>) -> Result<(), FilterError> {
+    match (step, value) {
+        (Step::Key(_) | Step::Index(_), JsonValue::Null) => values.push(&NULL),
+        // With repeated keys the last one counts, as it does in jq
+        (Step::Key(key), JsonValue::Object(members)) => {
+            values.push(members.iter().rev().find(|(k, _)| k == key).map_or(&NULL, |(_, v)| v));
+        },
+        (Step::Index(index), JsonValue::Array(elements)) => {
+            let index = if *index < 0 { elements.len() as i64 + index } else { *index };
+            values.push(usize::try_from(index).ok().and_then(|i| elements.get(i)).unwrap_or(&NULL));
+        },
+        (Step::Each, JsonValue::Object(members)) => values.extend(members.iter().map(|(_, v)| v)),
+        (Step::Each, JsonValue::Array(elements)) => values.extend(elements),
+        (Step::Key(key), value) => {
+            return Err(FilterError::new(format!("cannot index {} with \"{}\"", value.type_name(), key)));
+        },
+        (Step::Index(_), value) => return Err(FilterError::new(format!("cannot index {} with a number", value.type_name()))),
+        (Step::Each, value) => return Err(FilterError::new(format!("cannot iterate over {}", value.type_name()))),
+    }
+    Ok(())
+}
+
+struct Parser {
+    chars: Vec<char>,
+    next: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.next).copied()
+    }
+
+    fn next_if(&mut self, expected: char) -> bool {
+        let matched = self.peek() == Some(expected);
+        if matched {
+            self.next += 1;
+        }
+        matched
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.next += 1;
+        }
+    }
+
+    /// An error at the next character, counting columns from 1
+    fn error(&self, message: &str) -> FilterError {
+        FilterError::new(format!("{} at column {} of the filter", message, self.next + 1))
+    }
+
+    fn pipe(&mut self) -> Result<Expr, FilterError> {
+        let mut expr = self.comma()?;
+        self.skip_whitespace();
+        while self.next_if('|') {
+            expr = Expr::Pipe(Box::new(expr), Box::new(self.comma()?));
+            self.skip_whitespace();
+        }
+        Ok(expr)
+    }
+
+    fn comma(&mut self) -> Result<Expr, FilterError> {
+        let mut expr = self.path()?;
+        self.skip_whitespace();
+        while self.next_if(',') {
+            expr = Expr::Comma(Box::new(expr), Box::new(self.path()?));
+            self.skip_whitespace();
+        }
+        Ok(expr)
+    }
+
+    fn path(&mut self) -> Result<Expr, FilterError> {
+        self.skip_whitespace();
+        if !self.next_if('.') {
+            return Err(match self.peek() {
+                Some(c) => self.error(&format!("expected `.`, found `{}`", c)),
+                None => self.error("expected `.`"),
+            });
+        }
+
+        // The first step can come straight after the `.`, as in `.key` or `.[0]`
+        let mut steps = Vec::new();
+        match self.peek() {
+            Some('[') => {},
+            Some('"') => steps.push(Step::Key(self.string()?)),
+            Some(c) if is_identifier_start(c) => steps.push(Step::Key(self.identifier())),
+            _ => return Ok(Expr::Path(steps)),
+        }
+        loop {
+            if self.next_if('[') {
+                steps.push(self.subscript()?);
+            } else if self.next_if('.') {
+                steps.push(match self.peek() {
+                    Some('[') => {
+                        self.next += 1;
+                        self.subscript()?
+                    },
+                    Some('"') => Step::Key(self.string()?),
+                    Some(c) if is_identifier_start(c) => Step::Key(self.identifier()),
+                    _ => return Err(self.error("expected a key after `.`")),
+                });
+            } else {
+                return Ok(Expr::Path(steps));
+            }
+        }
+    }
+
+    /// What's between `[` and `]`, the `[` having been read
+    fn subscript(&mut self) -> Result<Step, FilterError> {
+        self.skip_whitespace();
+        let step = match self.peek() {
+            Some(']') => Step::Each,
+            Some('"') => Step::Key(self.string()?),
+            Some(c) if c == '-' || c.is_ascii_digit() => {
+                let start = self.next;
+                self.next += 1;
+                while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+                    self.next += 1;
+                }
+                let number: String = self.chars[start..self.next].iter().collect();
+                let index = number.parse().map_err(|_| FilterError::new(format!("invalid index `{}` at column {} of the filter", number, start + 1)))?;
+                Step::Index(index)
+            },
+            _ => return Err(self.error("expected an index, a key, or `]` after `[`")),
+        };
+        self.skip_whitespace();
+        match self.next_if(']') {
+            true => Ok(step),
+            false => Err(self.error("expected `]`")),
+        }
+    }
+
+    fn identifier(&mut self) -> String {
+        let start = self.next;
+        while self.peek().is_some_and(|c| c == '_' || c.is_ascii_alphanumeric()) {
+            self.next += 1;
+        }
+        self.chars[start..self.next].iter().collect()
+    }
+
+    /// A string written as it would be in JSON
+    fn string(&mut self) -> Result<String, FilterError> {
+        let start = self.next;
+        self.next += 1;
+        loop {
+            match self.peek() {
+                None => return Err(FilterError::new(format!("unterminated string at column {} of the filter", start + 1))),
+                Some('"') => break,
+                Some('\\') => self.next += 2,
+                Some(_) => self.next += 1,
+            }
+        }
+        self.next += 1;
+        let raw: String = self.chars[start..self.next].iter().collect();
+        string_value(&raw).ok_or_else(|| FilterError::new(format!("invalid string {} at column {} of the filter", raw, start + 1)))
+    }
+}
+
+fn is_identifier_start(c: char) -> bool {
+    c == '_' || c.is_ascii_alphabetic()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse_str, to_string_compact};
+
+    /// The values the filter gives, written compactly
+    fn run(filter: &str, input: &str) -> Result<Vec<String>, FilterError> {
+        let input = parse_str(input).unwrap();
+        let values = Filter::new(filter)?.apply(&input)?;
+        Ok(values.into_iter().map(to_string_compact).collect())
+    }
+
+    #[test]
+    fn check_apply() {
+        let input = r#"{"a": {"b": [10, 20, 30]}, "key with spaces": true, "n": null, "a": {"b": [1]}}"#;
+        assert_eq!(run(".", "[1]").unwrap(), ["[1]"]);
+        assert_eq!(run(".a.b", input).unwrap(), ["[1]"]);
+        assert_eq!(run(r#"."key with spaces""#, input).unwrap(), ["true"]);
+        assert_eq!(run(r#".["a"].b[0]"#, input).unwrap(), ["1"]);
+        assert_eq!(run(".[1], .[-1], .[5]", "[10, 20, 30]").unwrap(), ["20", "30", "null"]);
+        assert_eq!(run(".missing.deeper[0]", input).unwrap(), ["null"]);
+        assert_eq!(run(".[]", r#"{"x": 1, "y": [2]}"#).unwrap(), ["1", "[2]"]);
+        assert_eq!(run(".items[].name", r#"{"items": [{"name": "a"}, {"name": "b"}]}"#).unwrap(), [r#""a""#, r#""b""#]);
+        assert_eq!(run(r#".[] | .id, .tags[0]"#, r#"[{"id": 1, "tags": ["x"]}, {"id": 2, "tags": []}]"#).unwrap(),
+            ["1", r#""x""#, "2", "null"]);
+        assert_eq!(run(".[] | .[]", "[]").unwrap(), Vec::<String>::new());
+    }
+
+    #[test]
+    fn check_apply_errors() {
+        assert_eq!(run(".a", "[1]").unwrap_err().message, "cannot index an array with \"a\"");
+        assert_eq!(run(".[0]", r#"{"a": 1}"#).unwrap_err().message, "cannot index an object with a number");
+        assert_eq!(run(".[]", "7").unwrap_err().message, "cannot iterate over a number");
+    }
+
+    #[test]
+    fn check_filter_errors() {
+        let error = |filter| Filter::new(filter).unwrap_err().message;
+        assert_eq!(error(""), "expected `.` at column 1 of the filter");
+        assert_eq!(error("a"), "expected `.`, found `a` at column 1 of the filter");
+        assert_eq!(error(".a."), "expected a key after `.` at column 4 of the filter");
+        assert_eq!(error(".[1"), "expected `]` at column 4 of the filter");
+        assert_eq!(error(".[x]"), "expected an index, a key, or `]` after `[` at column 3 of the filter");
+        assert_eq!(error(".a )"), "unexpected `)` at column 4 of the filter");
+        assert_eq!(error(r#"."abc"#), "unterminated string at column 2 of the filter");
+        assert_eq!(error(".[-]"), "invalid index `-` at column 3 of the filter");
+    }
+}
@@ -0,0 +1,124 @@
+// Flattening JSON into a line for each value.
+// Each string, number, boolean, null, and empty object or array in the document is written on a
+// line of its own as `path = value`, so `grep` finds a setting deep in a config along with where
+// it is, and the lines can be compared with environment-variable style settings. The path is the
+// keys and indexes leading to the value, like `user.addresses[0].city`, with keys that aren't
+// identifiers quoted in brackets, like `headers["Content-Type"]`. Values are written as compact
+// JSON.
+use crate::write::{write_compact, write_string};
+use crate::JsonValue;
+use std::io::{self, Write};
+use std::iter::Enumerate;
+use std::slice;
+
+/// Write a `path = value` line for each value in the document that isn't an object or array with
+/// something in it, or just the value when the document is one
+pub fn write_flat(out: &mut impl Write, value: &JsonValue) -> io::Result<()> {
+    write_leaves(out, value)
+}
+
+/// The lines write_flat writes
+pub fn to_string_flat(value: &JsonValue) -> String {
+    let mut out = Vec::new();
+    write_flat(&mut out, value).expect("writing to a Vec can't fail");
+    String::from_utf8(out).expect("JSON written from strings is UTF-8")
+}
+
+/// An object or array with something in it being written, with the members or elements still to
+/// write and how long the path to it is. The ones it's in are kept on a stack rather than the call
+/// stack, so however deep a document nests it can be written.
+enum Open<'a> {
+    Object(slice::Iter<'a, (String, JsonValue)>, usize),
+    Array(Enumerate<slice::Iter<'a, JsonValue>>, usize),
+}
+
+/// Write the lines for the value
+fn write_leaves(out: &mut impl Write, value: &JsonValue) -> io::Result<()> {
+    // How the value being written is reached
+    let mut path = Vec::new();
+    let mut open: Vec<Open> = Vec::new();
+    let mut next = Some(value);
+    loop {
+        match next.take() {
+            Some(JsonValue::Object(members)) if !members.is_empty() => open.push(Open::Object(members.iter(), path.len())),
+            Some(JsonValue::Array(elements)) if !elements.is_empty() => open.push(Open::Array(elements.iter().enumerate(), path.len())),
+            Some(leaf) => {
+                if !path.is_empty() {
+                    out.write_all(&path)?;
+                    out.write_all(b" = ")?;
+                }
+                write_compact(out, leaf)?;
+                out.write_all(b"\n")?;
+            },
+            None => {},
+        }
+
+        // Go on to the next member or element of the innermost open object or array, or leave it
+        match open.last_mut() {
+            None => return Ok(()),
+            Some(Open::Object(members, length)) => {
+                path.truncate(*length);
+                let Some((key, value)) = members.next() else {
+                    open.pop();
+                    continue;
+                };
+                match is_identifier(key) {
+                    true if *length == 0 => path.extend(key.as_bytes()),
+                    true => write!(path, ".{}", key)?,
+                    false => {
+                        path.push(b'[');
+                        write_string(&mut path, key)?;
+                        path.push(b']');
+                    },
+                }
+                next = Some(value);
+            },
+            Some(Open::Array(elements, length)) => {
+                path.truncate(*length);
+                let Some((i, element)) = elements.next() else {
+                    open.pop();
+                    continue;
+                };
+                write!(path, "[{}]", i)?;
+                next = Some(element);
+            },
+        }
+    }
+}
+
+/// Whether a key can go in a path after a `.`, starting with a letter or `_` and going on with
+/// only letters, digits, and `_`
+fn is_identifier(key: &str) -> bool {
+    let mut chars = key.chars();
+    chars.next().is_some_and(|first| first.is_ascii_alphabetic() || first == '_') && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_str;
+
+    fn flat(input: &str) -> String {
+        to_string_flat(&parse_str(input).unwrap())
+    }
+
+    #[test]
+    fn check_write_flat() {
+        let input = r#"{"user": {"name": "Ann", "addresses": [{"city": "Oslo", "zip": null}], "tags": [], "meta": {}},
+            "headers": {"Content-Type": "text/plain", "": 1, "a\"b": [[true], 2.50]}, "_id1": 7}"#;
+        assert_eq!(flat(input), r#"user.name = "Ann"
+user.addresses[0].city = "Oslo"
+user.addresses[0].zip = null
+user.tags = []
+user.meta = {}
+headers["Content-Type"] = "text/plain"
+headers[""] = 1
+headers["a\"b"][0][0] = true
+headers["a\"b"][1] = 2.50
+_id1 = 7
+"#);
+        assert_eq!(flat(r#"[{"1a": "x"}, "y"]"#), "[0][\"1a\"] = \"x\"\n[1] = \"y\"\n");
+        assert_eq!(flat("\"text\""), "\"text\"\n");
+        assert_eq!(flat("{}"), "{}\n");
+    }
+}
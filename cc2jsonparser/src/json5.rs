@@ -0,0 +1,193 @@
+// JSON5.
+// A superset of JSON for config files written by hand, with what ECMAScript 5 allows in object
+// and array literals: strings in single quotes with more escapes and continued over lines with a
+// backslash, object keys that are identifiers, numbers in hex, with a leading `+` or a decimal
+// point at either end, Infinity and NaN, and comments and trailing commas. The tokenizer reads it
+// when asked, keeping tokens as written, and the values made from them are plain JSON, numbers
+// written the way JSON writes them.
+// Reference:  https://spec.json5.org
+use crate::token::{read_digits, LineChars};
+use crate::{JsonValue, Token};
+
+/// Whether `ch` is whitespace JSON5 allows between tokens beyond JSON's: vertical tab, form feed,
+/// the Unicode space separators, the line and paragraph separators, and the byte order mark
+pub(crate) fn is_whitespace(ch: char) -> bool {
+    matches!(ch, '\u{b}' | '\u{c}' | '\u{a0}' | '\u{feff}' | '\u{1680}' | '\u{2000}'..='\u{200a}' | '\u{2028}' | '\u{2029}' | '\u{202f}' | '\u{205f}' | '\u{3000}')
+}
+
+/// Whether an identifier, an object key written without quotes, can start with `ch`
+pub(crate) fn is_identifier_start(ch: char) -> bool {
+    ch.is_alphabetic() || matches!(ch, '$' | '_')
+}
+
+/// Whether `ch` can come after the start of an identifier
+pub(crate) fn is_identifier_part(ch: char) -> bool {
+    ch.is_alphanumeric() || matches!(ch, '$' | '_' | '\u{200c}' | '\u{200d}')
+}
+
+/// The name an object key written without quotes stands for. Identifiers spelled like literals
+/// are names too where a key goes.
+pub(crate) fn key_name<'a>(token: &'a Token) -> Option<&'a str> {
+    match token {
+        Token::Identifier(name) => Some(name),
+        Token::True => Some("true"),
+        Token::False => Some("false"),
+        Token::Null => Some("null"),
+        Token::Number(number) if matches!(number.as_ref(), "Infinity" | "NaN") => Some(number),
+        _ => None,
+    }
+}
+
+/// Check the rest of an escape sequence JSON doesn't have, `escape` being the character after the
+/// backslash. Any character but a digit stands for itself escaped, and `\x` takes two hex digits.
+pub(crate) fn read_escape(escape: char, iter: &mut LineChars) -> Result<(), String> {
+    match escape {
+        'x' => {
+            for _ in 0..2 {
+                if iter.next_if(char::is_ascii_hexdigit).is_none() {
+                    return Err("expected 2 hex digits after `\\x`".to_string());
+                }
+            }
+            Ok(())
+        },
+        '0' if iter.peek().is_some_and(|c| c.is_ascii_digit()) => Err("`\\0` can't have a digit after it".to_string()),
+        '1'..='9' => Err(format!("invalid escape sequence `\\{}`", escape)),
+        _ => Ok(()),
+    }
+}
+
+/// Read the rest of a number starting with `first`, any JSON number or one JSON5 adds, and give
+/// all of it, `from` being the rest of the line from `first` on
+pub(crate) fn read_number<'a>(first: char, from: &'a str, iter: &mut LineChars<'a>) -> Result<&'a str, String> {
+    fn invalid<'a>(from: &'a str, iter: &mut LineChars<'a>, reason: &str) -> String {
+        while iter.next_if(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '+' | '-')).is_some() {}
+        format!("invalid number `{}`: {}", iter.since(from), reason)
+    }
+
+    let leading = match first {
+        '+' | '-' => match iter.next_if(|c| c.is_ascii_digit() || matches!(c, '.' | 'I' | 'N')) {
+            Some(leading) => leading,
+            None => return Err(invalid(from, iter, &format!("expected a digit after `{}`", first))),
+        },
+        leading => leading,
+    };
+    if matches!(leading, 'I' | 'N') {
+        while iter.next_if(char::is_ascii_alphabetic).is_some() {}
+        return match iter.since(from).trim_start_matches(['+', '-']) {
+            "Infinity" | "NaN" => Ok(iter.since(from)),
+            _ => Err(invalid(from, iter, &format!("expected a digit after `{}`", first))),
+        };
+    }
+    if leading == '0' && iter.next_if(|&c| c == 'x' || c == 'X').is_some() {
+        if !iter.peek().is_some_and(|c| c.is_ascii_hexdigit()) {
+            return Err(invalid(from, iter, "expected a hex digit after `0x`"));
+        }
+        while iter.next_if(char::is_ascii_hexdigit).is_some() {}
+        return Ok(iter.since(from));
+    }
+    if leading == '0' && iter.peek().is_some_and(|c| c.is_ascii_digit()) {
+        return Err(invalid(from, iter, "leading zeros aren't allowed"));
+    }
+
+    // A decimal point can come before or after the digits, but not on its own
+    let digits = match leading {
+        '.' => 0,
+        _ => 1 + read_digits(iter),
+    };
+    let fraction = match leading == '.' || iter.next_if(|&c| c == '.').is_some() {
+        true => read_digits(iter),
+        false => 0,
+    };
+    if digits + fraction == 0 {
+        return Err(invalid(from, iter, "expected a digit before or after `.`"));
+    }
+    if iter.next_if(|&c| c == 'e' || c == 'E').is_some() {
+        iter.next_if(|&c| c == '+' || c == '-');
+        if read_digits(iter) == 0 {
+            return Err(invalid(from, iter, "expected a digit in the exponent"));
+        }
+    }
+    Ok(iter.since(from))
+}
+
+/// The value of a number token, written the way JSON writes numbers. JSON has no way to write
+/// Infinity or NaN, so they're null, as JSON.stringify makes them.
+pub(crate) fn number_value(raw: &str) -> JsonValue {
+    let (sign, unsigned) = match raw.strip_prefix('-') {
+        Some(unsigned) => ("-", unsigned),
+        None => ("", raw.strip_prefix('+').unwrap_or(raw)),
+    };
+    let number = match unsigned {
+        "Infinity" | "NaN" => return JsonValue::Null,
+        hex if hex.starts_with("0x") || hex.starts_with("0X") => hex_to_decimal(&hex[2..]),
+        decimal => {
+            let (mantissa, exponent) = decimal.split_at(decimal.find(['e', 'E']).unwrap_or(decimal.len()));
+            let mantissa = mantissa.strip_suffix('.').unwrap_or(mantissa);
+            let zero = if mantissa.starts_with('.') { "0" } else { "" };
+            format!("{}{}{}", zero, mantissa, exponent)
+        },
+    };
+    JsonValue::Number(format!("{}{}", sign, number))
+}
+
+/// Hex digits written out in decimal, however many there are
+fn hex_to_decimal(hex: &str) -> String {
+    // Least significant first
+    let mut decimal: Vec<u32> = vec![0];
+    for digit in hex.chars().filter_map(|c| c.to_digit(16)) {
+        let mut carry = digit;
+        for place in decimal.iter_mut() {
+            let value = *place * 16 + carry;
+            *place = value % 10;
+            carry = value / 10;
+        }
+        while carry > 0 {
+            decimal.push(carry % 10);
+            carry /= 10;
+        }
+    }
+    while decimal.len() > 1 && decimal.last() == Some(&0) {
+        decimal.pop();
+    }
+    decimal.iter().rev().map(|digit| char::from_digit(*digit, 10).unwrap()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read(input: &str) -> Result<&str, String> {
+        let mut iter = LineChars::new(input);
+        let first = iter.next().unwrap();
+        read_number(first, input, &mut iter)
+    }
+
+    #[test]
+    fn check_read_number() {
+        for number in ["1", "-0.5e3", "+1", ".5", "5.", "-.5E-2", "0x1F", "-0XaBc", "Infinity", "+Infinity", "-NaN"] {
+            assert_eq!(read(number), Ok(number));
+        }
+        assert_eq!(read("1.5,"), Ok("1.5"));
+        assert_eq!(read("."), Err("invalid number `.`: expected a digit before or after `.`".to_string()));
+        assert_eq!(read("0x"), Err("invalid number `0x`: expected a hex digit after `0x`".to_string()));
+        assert_eq!(read("0xg"), Err("invalid number `0xg`: expected a hex digit after `0x`".to_string()));
+        assert_eq!(read("-Inf"), Err("invalid number `-Inf`: expected a digit after `-`".to_string()));
+        assert_eq!(read("+x"), Err("invalid number `+x`: expected a digit after `+`".to_string()));
+        assert_eq!(read("012"), Err("invalid number `012`: leading zeros aren't allowed".to_string()));
+        assert_eq!(read("1e+"), Err("invalid number `1e+`: expected a digit in the exponent".to_string()));
+    }
+
+    #[test]
+    fn check_number_value() {
+        let number = |number: &str| JsonValue::Number(number.to_string());
+        assert_eq!(number_value("-1.5e3"), number("-1.5e3"));
+        assert_eq!(number_value("+1"), number("1"));
+        assert_eq!(number_value(".5"), number("0.5"));
+        assert_eq!(number_value("-5.e2"), number("-5e2"));
+        assert_eq!(number_value("0xff"), number("255"));
+        assert_eq!(number_value("-0X0"), number("-0"));
+        assert_eq!(number_value("0x10000000000000000000000000000000"), number("21267647932558653966460912964485513216"));
+        assert_eq!(number_value("Infinity"), JsonValue::Null);
+        assert_eq!(number_value("-NaN"), JsonValue::Null);
+    }
+}
@@ -0,0 +1,377 @@
+// JSONPath queries.
+// A query starts at the document, `$`, and each segment after it selects from what the one
+// before selected: `.name` or `['name']` a member of an object, `[2]` an element of an array,
+// counting from the end if negative, `[1:5:2]` a slice of one, `.*` or `[*]` every member or
+// element, and `[0, 'a']` the union of several. A segment starting `..` selects from everything
+// under the values too, e.g. every author anywhere in a document with
+//
+//   $..book[*].author
+//
+// Selecting something that isn't there just selects nothing. Filter expressions, `[?...]`,
+// aren't supported.
+// Reference:  https://www.rfc-editor.org/rfc/rfc9535
+use crate::{string_value, JsonValue};
+use std::{fmt, str::FromStr};
+
+#[derive(Clone, Debug, PartialEq)]
+enum Selector {
+    Name(String),
+    /// `*`
+    Wildcard,
+    Index(i64),
+    Slice { start: Option<i64>, end: Option<i64>, step: Option<i64> },
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Segment {
+    /// Whether it selects from everything under the values as well as the values themselves
+    descendants: bool,
+    selectors: Vec<Selector>,
+}
+
+/// A compiled JSONPath query
+#[derive(Clone, Debug, PartialEq)]
+pub struct JsonPath {
+    segments: Vec<Segment>,
+}
+
+/// A query that couldn't be compiled
+#[derive(Clone, Debug, PartialEq)]
+pub struct JsonPathError {
+    pub message: String,
+}
+
+impl fmt::Display for JsonPathError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for JsonPathError {}
+
+impl JsonPath {
+    pub fn new(query: &str) -> Result<JsonPath, JsonPathError> {
+        let mut parser = Parser { chars: query.chars().collect(), next: 0 };
+        if !parser.next_if('$') {
+            return Err(parser.error("expected the query to start with `$`"));
+        }
+        let mut segments = Vec::new();
+        loop {
+            parser.skip_whitespace();
+            match parser.peek() {
+                None => return Ok(JsonPath { segments }),
+                Some('.' | '[') => segments.push(parser.segment()?),
+                Some(c) => return Err(parser.error(&format!("expected `.` or `[`, found `{}`", c))),
+            }
+        }
+    }
+
+    /// The values the query selects from `document`, in the order they're in it
+    pub fn query<'a>(&self, document: &'a JsonValue) -> Vec<&'a JsonValue> {
+        let mut nodes = vec![document];
+        for segment in &self.segments {
+            let mut selected = Vec::new();
+            for node in nodes {
+                match segment.descendants {
+                    true => {
+                        let mut under = Vec::new();
+                        descendants(node, &mut under);
+                        for node in under {
+                            select(&segment.selectors, node, &mut selected);
+                        }
+                    },
+                    false => select(&segment.selectors, node, &mut selected),
+                }
+            }
+            nodes = selected;
+        }
+        nodes
+    }
+}
+
+impl FromStr for JsonPath {
+    type Err = JsonPathError;
+
+    fn from_str(s: &str) -> Result<JsonPath, JsonPathError> {
+        JsonPath::new(s)
+    }
+}
+
+/// The value and everything under it, each value before what's inside it
+fn descendants<'a>(value: &'a JsonValue, nodes: &mut Vec<&'a JsonValue>) {
+    // What's still to visit, kept on a stack rather than the call stack so however deep a
+    // document nests it can be queried, in reverse so the first comes off first
+    let mut waiting = vec![value];
+    while let Some(value) = waiting.pop() {
+        nodes.push(value);
+        match value {
+            JsonValue::Object(members) => waiting.extend(members.iter().rev().map(|(_, value)| value)),
+            JsonValue::Array(elements) => waiting.extend(elements.iter().rev()),
+            _ => {},
+        }
+    }
+}
+
+fn select<'a>(selectors: &[Selector], node: &'a JsonValue, selected: &mut Vec<&'a JsonValue>) {
+    for selector in selectors {
+        match (selector, node) {
+            // With repeated keys the last one counts
+            (Selector::Name(name), JsonValue::Object(members)) => {
+                selected.extend(members.iter().rev().find(|(key, _)| key == name).map(|(_, value)| value));
+            },
+            (Selector::Wildcard, JsonValue::Object(members)) => selected.extend(members.iter().map(|(_, value)| value)),
+            (Selector::Wildcard, JsonValue::Array(elements)) => selected.extend(elements),
+            (Selector::Index(index), JsonValue::Array(elements)) => {
+                let len = elements.len() as i64;
+                let index = if *index < 0 { len + index } else { *index };
+                selected.extend(usize::try_from(index).ok().and_then(|i| elements.get(i)));
+            },
+            (Selector::Slice { start, end, step }, JsonValue::Array(elements)) => {
+                selected.extend(slice(elements.len() as i64, *start, *end, step.unwrap_or(1)).map(|i| &elements[i]));
+            },
+            _ => {},
+        }
+    }
+}
+
+/// The indices a slice selects from an array of `len` elements, as RFC 9535 works them out
+fn slice(len: i64, start: Option<i64>, end: Option<i64>, step: i64) -> Box<dyn Iterator<Item = usize>> {
+    let normalize = |i: i64| if i >= 0 { i } else { len + i };
+    if step > 0 {
+        let lower = normalize(start.unwrap_or(0)).clamp(0, len);
+        let upper = normalize(end.unwrap_or(len)).clamp(0, len);
+        Box::new((lower..upper).step_by(step as usize).map(|i| i as usize))
+    } else if step < 0 {
+        let upper = normalize(start.unwrap_or(len - 1)).clamp(-1, len - 1);
+        let lower = normalize(end.unwrap_or(-len - 1)).clamp(-1, len - 1);
+        Box::new((lower + 1..=upper).rev().step_by(step.unsigned_abs() as usize).map(|i| i as usize))
+    } else {
+        Box::new(std::iter::empty())
+    }
+}
+
+struct Parser {
+    chars: Vec<char>,
+    next: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.next).copied()
+    }
+
+    fn next_if(&mut self, expected: char) -> bool {
+        let matched = self.peek() == Some(expected);
+        if matched {
+            self.next += 1;
+        }
+        matched
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.next += 1;
+        }
+    }
+
+    /// An error at the next character, counting columns from 1
+    fn error(&self, message: &str) -> JsonPathError {
+        JsonPathError { message: format!("{} at column {} of the query", message, self.next + 1) }
+    }
+
+    fn segment(&mut self) -> Result<Segment, JsonPathError> {
+        if self.next_if('[') {
+            return Ok(Segment { descendants: false, selectors: self.bracketed()? });
+        }
+        self.next += 1;
+        let descendants = self.next_if('.');
+        let selector = match self.peek() {
+            Some('[') if descendants => {
+                self.next += 1;
+                return Ok(Segment { descendants, selectors: self.bracketed()? });
+            },
+            Some('*') => {
+                self.next += 1;
+                Selector::Wildcard
+            },
+            Some(c) if is_name_start(c) => {
+                let start = self.next;
+                while self.peek().is_some_and(|c| is_name_start(c) || c.is_ascii_digit()) {
+                    self.next += 1;
+                }
+                Selector::Name(self.chars[start..self.next].iter().collect())
+            },
+            _ => return Err(self.error("expected a name or `*`")),
+        };
+        Ok(Segment { descendants, selectors: vec![selector] })
+    }
+
+    /// The selectors between `[` and `]`, the `[` having been read
+    fn bracketed(&mut self) -> Result<Vec<Selector>, JsonPathError> {
+        let mut selectors = Vec::new();
+        loop {
+            self.skip_whitespace();
+            selectors.push(self.selector()?);
+            self.skip_whitespace();
+            if self.next_if(']') {
+                return Ok(selectors);
+            }
+            if !self.next_if(',') {
+                return Err(self.error("expected `,` or `]`"));
+            }
+        }
+    }
+
+    fn selector(&mut self) -> Result<Selector, JsonPathError> {
+        match self.peek() {
+            Some('*') => {
+                self.next += 1;
+                Ok(Selector::Wildcard)
+            },
+            Some(quote @ ('\'' | '"')) => self.string(quote).map(Selector::Name),
+            Some(c) if c == ':' || c == '-' || c.is_ascii_digit() => {
+                let start = self.integer()?;
+                self.skip_whitespace();
+                if !self.next_if(':') {
+                    return start.map(Selector::Index).ok_or_else(|| self.error("expected an index"));
+                }
+                self.skip_whitespace();
+                let end = self.integer()?;
+                self.skip_whitespace();
+                let step = match self.next_if(':') {
+                    true => {
+                        self.skip_whitespace();
+                        self.integer()?
+                    },
+                    false => None,
+                };
+                Ok(Selector::Slice { start, end, step })
+            },
+            _ => Err(self.error("expected a name, an index, a slice, or `*`")),
+        }
+    }
+
+    /// An integer if there's one next
+    fn integer(&mut self) -> Result<Option<i64>, JsonPathError> {
+        let start = self.next;
+        self.next_if('-');
+        while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+            self.next += 1;
+        }
+        let number: String = self.chars[start..self.next].iter().collect();
+        if number.is_empty() {
+            return Ok(None);
+        }
+        number.parse().map(Some).map_err(|_| JsonPathError {
+            message: format!("invalid integer `{}` at column {} of the query", number, start + 1),
+        })
+    }
+
+    /// A string in single or double quotes, escaped the way JSON escapes strings
+    fn string(&mut self, quote: char) -> Result<String, JsonPathError> {
+        let start = self.next;
+        self.next += 1;
+        // Rewritten into a JSON string to decode, the quotes it doesn't need escaping escaped
+        let mut json = String::from("\"");
+        loop {
+            match self.peek() {
+                None => {
+                    return Err(JsonPathError { message: format!("unterminated string at column {} of the query", start + 1) });
+                },
+                Some(c) if c == quote => break,
+                Some('\\') if self.chars.get(self.next + 1) == Some(&'\'') => {
+                    json.push('\'');
+                    self.next += 2;
+                },
+                Some('\\') => {
+                    json.push('\\');
+                    json.extend(self.chars.get(self.next + 1));
+                    self.next += 2;
+                },
+                Some('"') => {
+                    json.push_str("\\\"");
+                    self.next += 1;
+                },
+                Some(c) => {
+                    json.push(c);
+                    self.next += 1;
+                },
+            }
+        }
+        self.next += 1;
+        json.push('"');
+        let written: String = self.chars[start..self.next].iter().collect();
+        string_value(&json).ok_or_else(|| JsonPathError {
+            message: format!("invalid string {} at column {} of the query", written, start + 1),
+        })
+    }
+}
+
+fn is_name_start(c: char) -> bool {
+    c == '_' || c.is_ascii_alphabetic() || !c.is_ascii()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse_str, to_string_compact};
+
+    const STORE: &str = r#"{"store": {
+        "book": [
+            {"category": "reference", "author": "Nigel Rees", "title": "Sayings of the Century", "price": 8.95},
+            {"category": "fiction", "author": "Evelyn Waugh", "title": "Sword of Honour", "price": 12.99},
+            {"category": "fiction", "author": "Herman Melville", "title": "Moby Dick", "price": 8.99},
+            {"category": "fiction", "author": "J. R. R. Tolkien", "title": "The Lord of the Rings", "price": 22.99}
+        ],
+        "bicycle": {"color": "red", "price": 399}
+    }}"#;
+
+    /// What the query selects, written compactly
+    fn query(path: &str, document: &str) -> Vec<String> {
+        let document = parse_str(document).unwrap();
+        JsonPath::new(path).unwrap().query(&document).into_iter().map(to_string_compact).collect()
+    }
+
+    #[test]
+    fn check_query() {
+        assert_eq!(query("$.store.book[*].author", STORE),
+            [r#""Nigel Rees""#, r#""Evelyn Waugh""#, r#""Herman Melville""#, r#""J. R. R. Tolkien""#]);
+        assert_eq!(query("$..price", STORE), ["8.95", "12.99", "8.99", "22.99", "399"]);
+        assert_eq!(query("$.store.*", STORE).len(), 2);
+        assert_eq!(query("$['store'][\"bicycle\"]", STORE), [r#"{"color":"red","price":399}"#]);
+        assert_eq!(query("$..book[2].title", STORE), [r#""Moby Dick""#]);
+        assert_eq!(query("$..book[-1].title", STORE), [r#""The Lord of the Rings""#]);
+        assert_eq!(query("$..book[0, 'x', 1].price", STORE), ["8.95", "12.99"]);
+        assert_eq!(query("$.store.book[9]", STORE), Vec::<String>::new());
+        assert_eq!(query("$.store.bicycle.color.name", STORE), Vec::<String>::new());
+        assert_eq!(query("$", "[1]"), ["[1]"]);
+        assert_eq!(query("$['it\\'s', \"a\\\"b\"]", r#"{"it's": 1, "a\"b": 2}"#), ["1", "2"]);
+        assert_eq!(query("$..[0]", "[[1, 2], [3]]"), ["[1,2]", "1", "3"]);
+    }
+
+    #[test]
+    fn check_slices() {
+        let array = "[0, 1, 2, 3, 4, 5]";
+        assert_eq!(query("$[1:3]", array), ["1", "2"]);
+        assert_eq!(query("$[4:]", array), ["4", "5"]);
+        assert_eq!(query("$[:2]", array), ["0", "1"]);
+        assert_eq!(query("$[::2]", array), ["0", "2", "4"]);
+        assert_eq!(query("$[-2:]", array), ["4", "5"]);
+        assert_eq!(query("$[::-1]", array), ["5", "4", "3", "2", "1", "0"]);
+        assert_eq!(query("$[4:1:-2]", array), ["4", "2"]);
+        assert_eq!(query("$[0:6:0]", array), Vec::<String>::new());
+        assert_eq!(query("$[10:20]", array), Vec::<String>::new());
+    }
+
+    #[test]
+    fn check_query_errors() {
+        let error = |path| JsonPath::new(path).unwrap_err().message;
+        assert_eq!(error(".a"), "expected the query to start with `$` at column 1 of the query");
+        assert_eq!(error("$a"), "expected `.` or `[`, found `a` at column 2 of the query");
+        assert_eq!(error("$."), "expected a name or `*` at column 3 of the query");
+        assert_eq!(error("$[1"), "expected `,` or `]` at column 4 of the query");
+        assert_eq!(error("$[?(@.a)]"), "expected a name, an index, a slice, or `*` at column 3 of the query");
+        assert_eq!(error("$['a"), "unterminated string at column 3 of the query");
+        assert_eq!(error("$[-]"), "invalid integer `-` at column 3 of the query");
+    }
+}
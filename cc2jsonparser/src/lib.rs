@@ -0,0 +1,1234 @@
+// JSON parser
+// Parses a document into a JsonValue, with parse_str or parse_reader for programs embedding it,
+// which the command line uses to report whether a file is valid JSON, or to format it.
+// Reference:  https://www.json.org/json-en.html
+// My answer to:  https://codingchallenges.substack.com/p/coding-challenge-2
+use std::{collections::HashMap, fmt, io::BufRead};
+
+mod cli;
+pub mod canonical;
+pub mod cbor;
+pub mod concatenated;
+pub mod csv;
+pub mod diff;
+mod editor;
+pub mod encoding;
+pub mod events;
+pub mod filter;
+pub mod flatten;
+mod json5;
+pub mod jsonpath;
+pub mod lossless;
+pub mod merge;
+pub mod msgpack;
+pub mod ndjson;
+pub mod number;
+pub mod patch;
+pub mod pointer;
+pub mod repair;
+mod repl;
+pub mod schema;
+#[cfg(feature = "serde")]
+pub mod serde;
+pub mod stats;
+pub mod token;
+pub mod toml;
+pub mod write;
+pub mod yaml;
+
+pub use cli::main;
+pub use canonical::{to_string_canonical, CanonicalError};
+pub use cbor::{from_cbor, to_cbor, CborError};
+pub use cc_diagnostics::{LineCol, Span};
+pub use concatenated::{read_concatenated, ConcatenatedReader};
+pub use csv::{to_string_csv, CsvError};
+pub use diff::{diff, to_json_patch, Change};
+pub use encoding::{decode_reader, Encoding, EncodingError};
+pub use events::{validate_reader, JsonEvent, JsonEvents};
+pub use filter::{Filter, FilterError};
+pub use flatten::{to_string_flat, write_flat};
+pub use jsonpath::{JsonPath, JsonPathError};
+pub use lossless::{parse_str_lossless, to_string_lossless, write_lossless, RawString, RawValue};
+pub use merge::merge_patch;
+pub use msgpack::{from_msgpack, to_msgpack, MsgpackError};
+pub use ndjson::{check_ndjson_reader, read_ndjson, NdjsonReader};
+pub use number::{read_number, Loss, Number, NumberWarning};
+pub use patch::{apply_patch, PatchError};
+pub use pointer::{JsonPointer, PointerError};
+pub use repair::{repair_str, Repair, Repaired};
+pub use schema::{Schema, SchemaError, Violation};
+#[cfg(feature = "serde")]
+pub use self::serde::{from_str, from_value, DeserializeError};
+pub use stats::{read_stats, Longest, Stats};
+pub use toml::{to_string_toml, TomlError};
+pub use token::{tokenize, tokenize_str, tokenize_str_with, tokenize_with_positions, tokenize_with_spans, Spanned, Token, TokenizeError};
+pub use write::{to_string_compact, to_string_pretty, write_commented, write_compact, write_json, write_pretty, WriteOptions};
+pub use yaml::{to_string_yaml, write_yaml};
+use number::number_warning;
+use token::{read_unicode_escape, tokenize_recovering, tokenize_with_options, LineChars};
+
+/// A token where JSON doesn't allow it
+#[derive(Clone, Debug, PartialEq)]
+pub struct ParseError {
+    /// What JSON allows there instead
+    pub expected: String,
+    pub found: Token<'static>,
+    /// Where the token found starts
+    pub position: LineCol,
+    /// The bytes of the token found
+    pub span: Span,
+    /// The limit on nesting the token found went past, when that's what's wrong with it
+    pub max_depth: Option<usize>,
+}
+
+impl ParseError {
+    fn new(expected: impl Into<String>, found: &Spanned<Token>) -> ParseError {
+        ParseError { expected: expected.into(), found: found.value.clone().into_owned(), position: found.position, span: found.span, max_depth: None }
+    }
+
+    /// The error for an object or array opened `max_depth` deep already
+    fn too_deep(max_depth: usize, token: &Spanned<Token>) -> ParseError {
+        ParseError { max_depth: Some(max_depth), ..ParseError::new(format!("at most {} levels of nesting", max_depth), token) }
+    }
+
+    /// What went wrong, without where
+    pub fn message(&self) -> String {
+        match self.max_depth {
+            Some(max_depth) => format!("maximum depth exceeded, {} nests deeper than {} levels", self.found, max_depth),
+            None => format!("expected {}, found {}", self.expected, self.found),
+        }
+    }
+}
+
+/// How deep objects and arrays can nest by default, the same as the JSON_checker suite's parser
+pub const DEFAULT_MAX_DEPTH: usize = 512;
+
+/// Limits on what the parser accepts
+#[derive(Clone, Debug, PartialEq)]
+pub struct ParseOptions {
+    /// How many objects and arrays can be open at once, if there's a limit
+    pub max_depth: Option<usize>,
+    /// Whether the input can start with a UTF-8 byte order mark, which is skipped. RFC 8259 says
+    /// JSON text mustn't have one but lets parsers ignore it.
+    pub allow_bom: bool,
+    /// Whether `//` and `/* */` comments are skipped like whitespace, the way JSONC has them,
+    /// rather than being illegal characters
+    pub allow_comments: bool,
+    /// Whether a comma can come after the last member of an object or element of an array, which
+    /// is warned about rather than being an error
+    pub allow_trailing_commas: bool,
+    /// Whether the input is JSON5, which has comments and trailing commas as well as strings in
+    /// single quotes, keys without quotes, and numbers JSON doesn't. Its trailing commas aren't
+    /// warned about.
+    pub json5: bool,
+    /// Whether integers too big for 64 bits are read exactly as arbitrary-precision integers,
+    /// rather than as floats that are warned about
+    pub bignum: bool,
+}
+
+impl Default for ParseOptions {
+    fn default() -> ParseOptions {
+        ParseOptions { max_depth: Some(DEFAULT_MAX_DEPTH), allow_bom: true, allow_comments: false, allow_trailing_commas: false, json5: false, bignum: false }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} at line {}, column {}", self.message(), self.position.line, self.position.column)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// A key written more than once in the same object. JSON allows it, but programs disagree on
+/// which value counts, so it's usually a mistake.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DuplicateKey {
+    pub key: String,
+    /// Where the key is first written in the object
+    pub first: LineCol,
+    /// Where it's written again
+    pub position: LineCol,
+    /// The bytes of the key, quotes and all, where it's written again
+    pub span: Span,
+}
+
+impl DuplicateKey {
+    /// What's wrong, without where
+    pub fn message(&self) -> String {
+        format!("duplicate key `{}`", self.key)
+    }
+
+    /// Where the key was first written, for a note under the message
+    pub fn note(&self) -> String {
+        format!("`{}` is first written at line {}, column {}", self.key, self.first.line, self.first.column)
+    }
+}
+
+impl fmt::Display for DuplicateKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} at line {}, column {}, first written at line {}, column {}",
+            self.message(), self.position.line, self.position.column, self.first.line, self.first.column)
+    }
+}
+
+/// A comma after the last member of an object or element of an array, which JSON doesn't allow
+/// but a parser allowing trailing commas warns about instead
+#[derive(Clone, Debug, PartialEq)]
+pub struct TrailingComma {
+    /// Whether it's in an object rather than an array
+    pub in_object: bool,
+    pub position: LineCol,
+    /// The byte of the comma
+    pub span: Span,
+}
+
+impl TrailingComma {
+    /// What's wrong, without where
+    pub fn message(&self) -> String {
+        match self.in_object {
+            true => "trailing comma after the last object member".to_string(),
+            false => "trailing comma after the last array element".to_string(),
+        }
+    }
+}
+
+impl fmt::Display for TrailingComma {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} at line {}, column {}", self.message(), self.position.line, self.position.column)
+    }
+}
+
+/// What a document has in it that's allowed but looks like a mistake
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Warnings {
+    /// Every key repeated in one of its objects
+    pub duplicates: Vec<DuplicateKey>,
+    /// Every trailing comma, when they're allowed
+    pub trailing_commas: Vec<TrailingComma>,
+    /// Every number read as something other than what's written
+    pub numbers: Vec<NumberWarning>,
+}
+
+impl Warnings {
+    pub fn is_empty(&self) -> bool {
+        self.duplicates.is_empty() && self.trailing_commas.is_empty() && self.numbers.is_empty()
+    }
+
+    fn append(&mut self, other: &mut Warnings) {
+        self.duplicates.append(&mut other.duplicates);
+        self.trailing_commas.append(&mut other.trailing_commas);
+        self.numbers.append(&mut other.numbers);
+    }
+}
+
+/// Why some input isn't a JSON document
+#[derive(Clone, Debug, PartialEq)]
+pub enum JsonError {
+    Tokenize(TokenizeError),
+    /// Nothing but whitespace
+    Empty,
+    Parse(ParseError),
+}
+
+impl JsonError {
+    /// Where in the input it went wrong, if anywhere in particular
+    pub fn position(&self) -> Option<LineCol> {
+        match self {
+            JsonError::Tokenize(e) => Some(e.position),
+            JsonError::Empty => None,
+            JsonError::Parse(e) => Some(e.position),
+        }
+    }
+
+    /// The bytes of the input it went wrong at, if anywhere in particular
+    pub fn span(&self) -> Option<Span> {
+        match self {
+            JsonError::Tokenize(e) => Some(e.span),
+            JsonError::Empty => None,
+            JsonError::Parse(e) => Some(e.span),
+        }
+    }
+
+    /// What went wrong, without where
+    pub fn message(&self) -> String {
+        match self {
+            JsonError::Tokenize(e) => e.message.clone(),
+            JsonError::Empty => "did not find anything to parse".to_string(),
+            JsonError::Parse(e) => e.message(),
+        }
+    }
+}
+
+impl fmt::Display for JsonError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            JsonError::Tokenize(e) => write!(f, "{}", e),
+            JsonError::Empty => f.write_str("did not find anything to parse"),
+            JsonError::Parse(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for JsonError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            JsonError::Tokenize(e) => Some(e),
+            JsonError::Empty => None,
+            JsonError::Parse(e) => Some(e),
+        }
+    }
+}
+
+/// Parse a JSON document
+pub fn parse_str(input: &str) -> Result<JsonValue, JsonError> {
+    // With all of the input there, its tokens can borrow from it
+    let tokens = tokenize_str(input).map_err(JsonError::Tokenize)?;
+    parse_all(&tokens, &ParseOptions::default()).map(|(value, _)| value)
+}
+
+/// Parse a JSON document within the limits of `options`, along with what it has in it to warn
+/// about
+pub fn parse_str_with(input: &str, options: &ParseOptions) -> Result<(JsonValue, Warnings), JsonError> {
+    let tokens = tokenize_str_with(input, options).map_err(JsonError::Tokenize)?;
+    parse_all(&tokens, options)
+}
+
+/// Parse a JSON document read from `reader`
+pub fn parse_reader(reader: impl BufRead) -> Result<JsonValue, JsonError> {
+    parse_reader_with(reader, &ParseOptions::default()).map(|(value, _)| value)
+}
+
+/// Parse a JSON document read from `reader` within the limits of `options`, along with what it
+/// has in it to warn about
+pub fn parse_reader_with(reader: impl BufRead, options: &ParseOptions) -> Result<(JsonValue, Warnings), JsonError> {
+    let tokens = tokenize_with_options(reader, options).map_err(JsonError::Tokenize)?;
+    parse_all(&tokens, options)
+}
+
+/// Parse the tokens of a whole document, ending with Eof
+fn parse_all(tokens: &[Spanned<Token>], options: &ParseOptions) -> Result<(JsonValue, Warnings), JsonError> {
+    // Only the end
+    if tokens.len() == 1 {
+        return Err(JsonError::Empty);
+    }
+    let mut parser = JsonParser::new(tokens, options);
+    let value = parse_document(&mut parser).map_err(JsonError::Parse)?;
+    Ok((value, parser.warnings))
+}
+
+/// Find every error in a JSON document read from `reader`, in the order they come, rather than
+/// stopping at the first
+pub fn check_reader(reader: impl BufRead) -> Vec<JsonError> {
+    check_reader_with(reader, &ParseOptions::default()).0
+}
+
+/// Find every error in a JSON document read from `reader` within the limits of `options`, and
+/// what it has in it to warn about
+pub fn check_reader_with(reader: impl BufRead, options: &ParseOptions) -> (Vec<JsonError>, Warnings) {
+    let recovered = tokenize_recovering(reader, options);
+    if recovered.errors.is_empty() && recovered.tokens.len() == 1 {
+        return (vec![JsonError::Empty], Warnings::default());
+    }
+    let (parse_errors, warnings) = check_tokens_with(&recovered.tokens, options);
+    // What's wrong with a token that couldn't be read has been said already
+    let parse_errors = parse_errors.into_iter().filter(|e| !recovered.stand_ins.contains(&e.position));
+    let mut errors: Vec<JsonError> = recovered.errors.into_iter().map(JsonError::Tokenize).chain(parse_errors.map(JsonError::Parse)).collect();
+    errors.sort_by_key(|e| e.position());
+    (errors, warnings)
+}
+
+/// A parsed JSON document
+#[derive(Clone, Debug, PartialEq)]
+pub enum JsonValue {
+    /// The members in the order they were written, keeping any repeated keys
+    Object(Vec<(String, JsonValue)>),
+    Array(Vec<JsonValue>),
+    String(String),
+    /// The number as written, so nothing is lost to floating point until it's needed
+    Number(String),
+    Bool(bool),
+    Null,
+}
+
+impl JsonValue {
+    /// The value of a number, as near as a float gets to it
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            JsonValue::Number(number) => number.parse().ok(),
+            _ => None,
+        }
+    }
+
+    /// The value of a number as a program has it, an integer too big for 64 bits being a BigInt
+    /// if `bignum` or a float otherwise
+    pub fn to_number(&self, bignum: bool) -> Option<Number> {
+        match self {
+            JsonValue::Number(number) => read_number(number, bignum).map(|(number, _)| number),
+            _ => None,
+        }
+    }
+
+    /// What sort of value it is, the way errors name it, as in `an object` or `null`
+    pub(crate) fn type_name(&self) -> &'static str {
+        match self {
+            JsonValue::Object(_) => "an object",
+            JsonValue::Array(_) => "an array",
+            JsonValue::String(_) => "a string",
+            JsonValue::Number(_) => "a number",
+            JsonValue::Bool(_) => "a boolean",
+            JsonValue::Null => "null",
+        }
+    }
+
+    /// Put the members of this object, and of every object inside it, in order of their keys.
+    /// Members with the same key stay in the order they were written.
+    pub fn sort_keys(&mut self) {
+        // As in Drop, the values still to sort are kept on a stack rather than recursed into
+        let mut waiting = vec![self];
+        while let Some(value) = waiting.pop() {
+            match value {
+                JsonValue::Object(members) => {
+                    members.sort_by(|(a, _), (b, _)| a.cmp(b));
+                    waiting.extend(members.iter_mut().map(|(_, value)| value));
+                },
+                JsonValue::Array(elements) => waiting.extend(elements.iter_mut()),
+                _ => {},
+            }
+        }
+    }
+}
+
+impl Drop for JsonValue {
+    // Dropping the values inside each one in turn would recurse as deep as they nest, so take
+    // them out onto a stack of values waiting to be dropped
+    fn drop(&mut self) {
+        if !self.has_nested() {
+            return;
+        }
+        let mut waiting = Vec::new();
+        self.take_inside(&mut waiting);
+        while let Some(mut value) = waiting.pop() {
+            if value.has_nested() {
+                value.take_inside(&mut waiting);
+            }
+        }
+    }
+}
+
+impl JsonValue {
+    /// Whether there's an object or array inside this one
+    fn has_nested(&self) -> bool {
+        let nested = |value: &JsonValue| matches!(value, JsonValue::Object(_) | JsonValue::Array(_));
+        match self {
+            JsonValue::Object(members) => members.iter().any(|(_, value)| nested(value)),
+            JsonValue::Array(elements) => elements.iter().any(nested),
+            _ => false,
+        }
+    }
+
+    fn take_inside(&mut self, values: &mut Vec<JsonValue>) {
+        match self {
+            JsonValue::Object(members) => values.extend(members.drain(..).map(|(_, value)| value)),
+            JsonValue::Array(elements) => values.append(elements),
+            _ => {},
+        }
+    }
+}
+
+/// Decode a string token, quotes and escapes as written, into the string it stands for. It can be
+/// a JSON5 string, which the tokenizer only reads when asked.
+fn string_value(raw: &str) -> Option<String> {
+    let inner = match raw.strip_prefix('\'') {
+        Some(single) => single.strip_suffix('\''),
+        None => raw.strip_prefix('"')?.strip_suffix('"'),
+    }?;
+    if !inner.contains('\\') {
+        return Some(inner.to_string());
+    }
+    let mut iter = LineChars::new(inner);
+    let mut string = String::new();
+    while let Some(ch) = iter.next() {
+        if ch != '\\' {
+            string.push(ch);
+            continue;
+        }
+        let unescaped = match iter.next()? {
+            'b' => '\u{8}',
+            'f' => '\u{c}',
+            'n' => '\n',
+            'r' => '\r',
+            't' => '\t',
+            'u' => read_unicode_escape(&mut iter).ok()?,
+            // JSON5 escapes
+            'v' => '\u{b}',
+            '0' => '\0',
+            'x' => {
+                let hex = [iter.next()?, iter.next()?].iter().collect::<String>();
+                char::from(u8::from_str_radix(&hex, 16).ok()?)
+            },
+            // A line continuation
+            '\r' => {
+                iter.next_if(|&c| c == '\n');
+                continue;
+            },
+            '\n' | '\u{2028}' | '\u{2029}' => continue,
+            other => other,
+        };
+        string.push(unescaped);
+    }
+    Some(string)
+}
+
+#[derive(Debug)]
+struct JsonParser<'a> {
+    tokens: &'a [Spanned<Token<'a>>],
+    next: usize,
+    /// Read past the last token, where the input ends
+    eof: Spanned<Token<'a>>,
+    /// Every error so far, when carrying on past them rather than stopping at the first
+    errors: Option<Vec<ParseError>>,
+    /// Every key repeated in an object so far, trailing comma if they're allowed, and number
+    /// read as something else
+    warnings: Warnings,
+    /// How many objects and arrays can be open at once, if there's a limit
+    max_depth: Option<usize>,
+    allow_trailing_commas: bool,
+    json5: bool,
+    bignum: bool,
+}
+
+impl<'a> JsonParser<'a> {
+    fn new(tokens: &'a [Spanned<Token<'a>>], options: &ParseOptions) -> JsonParser<'a> {
+        let eof = match tokens.last() {
+            Some(last) => Spanned { value: Token::Eof, position: last.position, span: Span::point(last.span.end) },
+            None => Spanned { value: Token::Eof, position: LineCol { line: 1, column: 1 }, span: Span::point(0) },
+        };
+        JsonParser {
+            tokens,
+            next: 0,
+            eof,
+            errors: None,
+            warnings: Warnings::default(),
+            max_depth: options.max_depth,
+            allow_trailing_commas: options.allow_trailing_commas || options.json5,
+            json5: options.json5,
+            bignum: options.bignum,
+        }
+    }
+
+    fn read(&mut self) -> &Spanned<Token<'a>> {
+        match self.tokens.get(self.next) {
+            Some(t) => {
+                self.next += 1;
+                t
+            },
+            None => &self.eof,
+        }
+    }
+
+    fn peek(&self) -> &Spanned<Token<'a>> {
+        self.tokens.get(self.next).unwrap_or(&self.eof)
+    }
+
+    /// The key and where it's written
+    fn read_object_key(&mut self) -> Result<Spanned<String>, ParseError> {
+        match self.peek() {
+            token @ Spanned { value: Token::String(raw), position, span } => {
+                let key = string_value(raw).ok_or_else(|| ParseError::new("a valid string", token))?;
+                let key = Spanned { value: key, position: *position, span: *span };
+                self.read();
+                Ok(key)
+            },
+            token @ Spanned { value, position, span } if self.json5 => match json5::key_name(value) {
+                Some(name) => {
+                    let key = Spanned { value: name.to_string(), position: *position, span: *span };
+                    self.read();
+                    Ok(key)
+                },
+                None => Err(ParseError::new("a string or identifier for the object key", token)),
+            },
+            other => Err(ParseError::new("a string for the object key", other)),
+        }
+    }
+
+    fn read_colon(&mut self) -> Result<(), ParseError> {
+        match self.peek() {
+            Spanned { value: Token::Colon, .. } => {
+                self.read();
+                Ok(())
+            },
+            other => Err(ParseError::new("`:` after the object key", other)),
+        }
+    }
+
+    /// Note the error and skip to the next comma or `close` in the object or array being read,
+    /// so it can carry on from there, or give the error back when not carrying on past errors
+    /// or there's nothing to carry on with
+    fn recover(&mut self, e: ParseError, close: &Token) -> Result<(), ParseError> {
+        let Some(errors) = self.errors.as_mut() else {
+            return Err(e);
+        };
+        // An error at the end of the input is seen by each object and array it leaves unclosed
+        if errors.last().map(|last| last.position) != Some(e.position) {
+            errors.push(e.clone());
+        }
+
+        let mut depth = 0;
+        loop {
+            match &self.peek().value {
+                Token::Eof => return Err(e),
+                token if depth == 0 && (token == &Token::Comma || token == close) => return Ok(()),
+                Token::LeftBrace | Token::LeftBracket => depth += 1,
+                // A close that doesn't match goes with the rest of what's skipped
+                Token::RightBrace | Token::RightBracket if depth > 0 => depth -= 1,
+                _ => {},
+            }
+            self.read();
+        }
+    }
+}
+
+// Parse JSON value.
+// A value can be any of the following:
+// - object
+// - array
+// - string
+// - number
+// - "true"
+// - "false"
+// - "null"
+//
+// Apparently there is some disparity between the JSON reference and the test suite
+// The test suite says that the payload must be in an object or array,
+// but that's not what I gathered from the spec.
+// https://www.json.org/json-en.html
+// http://www.json.org/JSON_checker/test.zip
+pub fn parse_tokens(tokens: &[Spanned<Token>]) -> Result<JsonValue, ParseError> {
+    let mut parser = JsonParser::new(tokens, &ParseOptions::default());
+    parse_document(&mut parser)
+}
+
+/// Find every error in the tokens, carrying on past each one from the next comma or close of the
+/// object or array it's in
+pub fn check_tokens(tokens: &[Spanned<Token>]) -> Vec<ParseError> {
+    check_tokens_with(tokens, &ParseOptions::default()).0
+}
+
+fn check_tokens_with(tokens: &[Spanned<Token>], options: &ParseOptions) -> (Vec<ParseError>, Warnings) {
+    let mut parser = JsonParser::new(tokens, options);
+    parser.errors = Some(Vec::new());
+    let result = parse_document(&mut parser);
+    let mut errors = parser.errors.unwrap_or_default();
+    if let Err(e) = result {
+        if errors.last().map(|last| last.position) != Some(e.position) {
+            errors.push(e);
+        }
+    }
+    (errors, parser.warnings)
+}
+
+fn parse_document(parser: &mut JsonParser) -> Result<JsonValue, ParseError> {
+    let value = parse_value(parser)?;
+    match parser.read() {
+        Spanned { value: Token::Eof, .. } => Ok(value),
+        other => Err(ParseError::new("the end of input after the value", other)),
+    }
+}
+
+/// An object or array being read. The ones it's in are kept on a stack rather than the call
+/// stack, so however deep a document nests it can't run the parser out of stack.
+#[derive(Debug)]
+enum Open {
+    Object {
+        members: Vec<(String, JsonValue)>,
+        /// Where each key was first written
+        seen: HashMap<String, LineCol>,
+        /// The key of the member whose value is being read, and where it's written
+        key: Option<Spanned<String>>,
+    },
+    Array(Vec<JsonValue>),
+}
+
+impl Open {
+    fn close(&self) -> Token<'static> {
+        match self {
+            Open::Object { .. } => Token::RightBrace,
+            Open::Array(_) => Token::RightBracket,
+        }
+    }
+
+    fn into_value(self) -> JsonValue {
+        match self {
+            Open::Object { members, .. } => JsonValue::Object(members),
+            Open::Array(elements) => JsonValue::Array(elements),
+        }
+    }
+}
+
+/// What's read next
+enum Expect {
+    Value,
+    /// The key and colon of an object member
+    Key,
+    /// A comma, or the close of the innermost open object or array
+    Separator,
+}
+
+/// What reading one part of a value came to
+enum Step {
+    /// A whole value
+    Done(JsonValue),
+    Then(Expect),
+}
+
+fn parse_value(parser: &mut JsonParser) -> Result<JsonValue, ParseError> {
+    let mut open: Vec<Open> = Vec::new();
+    let mut expect = Expect::Value;
+    loop {
+        let step = match expect {
+            Expect::Value => parse_scalar_or_open(parser, &mut open),
+            Expect::Key => parse_member_key(parser, &mut open),
+            Expect::Separator => parse_separator(parser, &mut open),
+        };
+        let value = match step {
+            Ok(Step::Done(value)) => value,
+            Ok(Step::Then(next)) => {
+                expect = next;
+                continue;
+            },
+            Err(e) => {
+                // Carry on from the next comma or close of the innermost object or array, if
+                // there is one and the parser is carrying on past errors
+                let Some(innermost) = open.last_mut() else {
+                    return Err(e);
+                };
+                if let Open::Object { key, .. } = innermost {
+                    *key = None;
+                }
+                let close = innermost.close();
+                parser.recover(e, &close)?;
+                expect = Expect::Separator;
+                continue;
+            },
+        };
+
+        match open.last_mut() {
+            None => return Ok(value),
+            Some(Open::Object { members, seen, key }) => {
+                let Spanned { value: key, position, span } = key.take().expect("a key is read before each member's value");
+                match seen.get(&key) {
+                    Some(&first) => parser.warnings.duplicates.push(DuplicateKey { key: key.clone(), first, position, span }),
+                    None => {
+                        seen.insert(key.clone(), position);
+                    },
+                }
+                members.push((key, value));
+            },
+            Some(Open::Array(elements)) => elements.push(value),
+        }
+        expect = Expect::Separator;
+    }
+}
+
+/// What comes first in the innermost open object or array, and after each comma
+fn first_in(open: &[Open]) -> Expect {
+    match open.last() {
+        Some(Open::Object { .. }) => Expect::Key,
+        _ => Expect::Value,
+    }
+}
+
+/// Read a value that's whole in itself, empty objects and arrays included, or open an object or
+/// array
+fn parse_scalar_or_open(parser: &mut JsonParser, open: &mut Vec<Open>) -> Result<Step, ParseError> {
+    let value = match parser.peek() {
+        token @ Spanned { value: Token::LeftBrace | Token::LeftBracket, .. } => {
+            if let Some(max_depth) = parser.max_depth.filter(|max| open.len() == *max) {
+                return Err(ParseError::too_deep(max_depth, token));
+            }
+            let opened = match token.value {
+                Token::LeftBrace => Open::Object { members: Vec::new(), seen: HashMap::new(), key: None },
+                _ => Open::Array(Vec::new()),
+            };
+            parser.read();
+            if parser.peek().value == opened.close() {
+                parser.read();
+                return Ok(Step::Done(opened.into_value())); // Empty object or array
+            }
+            open.push(opened);
+            return Ok(Step::Then(first_in(open)));
+        },
+        token @ Spanned { value: Token::String(raw), .. } => {
+            string_value(raw).map(JsonValue::String).ok_or_else(|| ParseError::new("a valid string", token))?
+        },
+        Spanned { value: Token::Number(number), .. } => json5::number_value(number),
+        Spanned { value: Token::True, .. } => JsonValue::Bool(true),
+        Spanned { value: Token::False, .. } => JsonValue::Bool(false),
+        Spanned { value: Token::Null, .. } => JsonValue::Null,
+        other => return Err(ParseError::new("a value", other)),
+    };
+    let bignum = parser.bignum;
+    let warning = number_warning(parser.read(), bignum);
+    parser.warnings.numbers.extend(warning);
+    Ok(Step::Done(value))
+}
+
+/// Read the key of a member of the innermost open object, and the colon after it
+fn parse_member_key(parser: &mut JsonParser, open: &mut [Open]) -> Result<Step, ParseError> {
+    let member = parser.read_object_key()?;
+    parser.read_colon()?;
+    if let Some(Open::Object { key, .. }) = open.last_mut() {
+        *key = Some(member);
+    }
+    Ok(Step::Then(Expect::Value))
+}
+
+/// Read the comma after a member or element, or the close of the innermost open object or array
+fn parse_separator(parser: &mut JsonParser, open: &mut Vec<Open>) -> Result<Step, ParseError> {
+    let innermost = open.last().expect("a separator is only read inside an object or array");
+    let close = innermost.close();
+    match parser.peek() {
+        &Spanned { value: Token::Comma, position, span } => {
+            parser.read();
+            if parser.allow_trailing_commas && parser.peek().value == close {
+                if !parser.json5 {
+                    let in_object = matches!(innermost, Open::Object { .. });
+                    parser.warnings.trailing_commas.push(TrailingComma { in_object, position, span });
+                }
+                parser.read();
+                return Ok(Step::Done(open.pop().unwrap().into_value()));
+            }
+            Ok(Step::Then(first_in(open)))
+        },
+        token if token.value == close => {
+            parser.read();
+            Ok(Step::Done(open.pop().unwrap().into_value()))
+        },
+        other => match innermost {
+            Open::Object { .. } => Err(ParseError::new("`,` or `}` after the object member", other)),
+            Open::Array(_) => Err(ParseError::new("`,` or `]` after the array element", other)),
+        },
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+    use super::*;
+
+    /// The token as though it were one byte long at `column` of the first line
+    fn at(token: Token, column: usize) -> Spanned<Token> {
+        Spanned { value: token, position: LineCol { line: 1, column }, span: Span::new(column - 1, column) }
+    }
+
+    /// Parse tokens as though they were all on the one line
+    fn parse(tokens: &[Token]) -> Result<JsonValue, ParseError> {
+        let tokens: Vec<_> = tokens.iter().enumerate().map(|(i, t)| at(t.clone(), i + 1)).collect();
+        parse_tokens(&tokens)
+    }
+
+    #[test]
+    fn check_parse_tokens_string() {
+        let tokens = [
+            Token::String("\"key\"".into()),
+        ];
+        let result = parse(&tokens);
+        assert_eq!(result, Ok(JsonValue::String("key".to_string())))
+    }
+
+    #[test]
+    fn check_parse_tokens_empty_object() {
+        let tokens = [
+            Token::LeftBrace,
+            Token::RightBrace,
+        ];
+        let result = parse(&tokens);
+        assert_eq!(result, Ok(JsonValue::Object(vec![])))
+    }
+
+    #[test]
+    fn check_parse_tokens_object() {
+        let tokens = [
+            Token::LeftBrace,
+            Token::String("\"key\"".into()),
+            Token::Colon,
+            Token::String("\"value\"".into()),
+            Token::RightBrace,
+        ];
+        let result = parse(&tokens);
+        assert_eq!(result, Ok(JsonValue::Object(vec![("key".to_string(), JsonValue::String("value".to_string()))])))
+    }
+
+    #[test]
+    fn check_parse_tokens_object_trailing_comma() {
+        let tokens = [
+            Token::LeftBrace,
+            Token::String("\"key\"".into()),
+            Token::Colon,
+            Token::String("\"value\"".into()),
+            Token::Comma,
+            Token::RightBrace,
+        ];
+        let result = parse(&tokens).unwrap_err();
+        assert_eq!(result, ParseError::new("a string for the object key", &at(Token::RightBrace, 6)))
+    }
+
+    #[test]
+    fn check_parse_tokens_object_multiple_keys() {
+        let tokens = [
+            Token::LeftBrace,
+            Token::String("\"key\"".into()),
+            Token::Colon,
+            Token::String("\"value\"".into()),
+            Token::Comma,
+            Token::String("\"key2\"".into()),
+            Token::Colon,
+            Token::String("\"value\"".into()),
+            Token::RightBrace,
+        ];
+        let result = parse(&tokens);
+        assert_eq!(result, Ok(JsonValue::Object(vec![("key".to_string(), JsonValue::String("value".to_string())), ("key2".to_string(), JsonValue::String("value".to_string()))])))
+    }
+
+    #[test]
+    fn check_parse_tokens_nested_object() {
+        let tokens = [
+            Token::LeftBrace,
+            Token::String("\"key\"".into()),
+            Token::Colon,
+            Token::LeftBrace,
+            Token::String("\"key2\"".into()),
+            Token::Colon,
+            Token::String("\"list value\"".into()),
+            Token::RightBrace,
+            Token::RightBrace,
+        ];
+        let result = parse(&tokens);
+        assert_eq!(result, Ok(JsonValue::Object(vec![("key".to_string(), JsonValue::Object(vec![("key2".to_string(), JsonValue::String("list value".to_string()))]))])))
+    }
+
+    #[test]
+    fn check_parse_tokens_true() {
+        let tokens = [
+            Token::LeftBrace,
+            Token::String("\"key\"".into()),
+            Token::Colon,
+            Token::True,
+            Token::RightBrace,
+        ];
+        let result = parse(&tokens);
+        assert_eq!(result, Ok(JsonValue::Object(vec![("key".to_string(), JsonValue::Bool(true))])))
+    }
+
+    #[test]
+    fn check_parse_tokens_false() {
+        let tokens = [
+            Token::LeftBrace,
+            Token::String("\"key\"".into()),
+            Token::Colon,
+            Token::False,
+            Token::RightBrace,
+        ];
+        let result = parse(&tokens);
+        assert_eq!(result, Ok(JsonValue::Object(vec![("key".to_string(), JsonValue::Bool(false))])))
+    }
+
+    #[test]
+    fn check_parse_tokens_empty_object_as_value() {
+        let tokens = [
+            Token::LeftBrace,
+            Token::String("\"key\"".into()),
+            Token::Colon,
+            Token::LeftBrace,
+            Token::RightBrace,
+            Token::RightBrace,
+        ];
+        let result = parse(&tokens);
+        assert_eq!(result, Ok(JsonValue::Object(vec![("key".to_string(), JsonValue::Object(vec![]))])))
+    }
+
+    #[test]
+    fn check_parse_tokens_inner_array() {
+        let tokens = [
+            Token::LeftBrace,
+            Token::String("\"key\"".into()),
+            Token::Colon,
+            Token::LeftBracket,
+            Token::String("\"list value\"".into()),
+            Token::RightBracket,
+            Token::RightBrace,
+        ];
+        let result = parse(&tokens);
+        assert_eq!(result, Ok(JsonValue::Object(vec![("key".to_string(), JsonValue::Array(vec![JsonValue::String("list value".to_string())]))])))
+    }
+
+    #[test]
+    fn check_parse_tokens_empty_array() {
+        let tokens = [
+            Token::LeftBracket,
+            Token::RightBracket,
+        ];
+        let result = parse(&tokens);
+        assert_eq!(result, Ok(JsonValue::Array(vec![])))
+    }
+
+    #[test]
+    fn check_parse_tokens_array() {
+        let tokens = [
+            Token::LeftBracket,
+            Token::String("\"value\"".into()),
+            Token::Comma,
+            Token::String("\"value 2\"".into()),
+            Token::RightBracket,
+        ];
+        let result = parse(&tokens);
+        assert_eq!(result, Ok(JsonValue::Array(vec![JsonValue::String("value".to_string()), JsonValue::String("value 2".to_string())])))
+    }
+
+    #[test]
+    fn check_parse_tokens_array_trailing_comma() {
+        let tokens = [
+            Token::LeftBracket,
+            Token::String("\"value\"".into()),
+            Token::Comma,
+            Token::String("\"value 2\"".into()),
+            Token::Comma,
+            Token::RightBracket,
+        ];
+        let result = parse(&tokens).unwrap_err();
+        assert_eq!(result, ParseError::new("a value", &at(Token::RightBracket, 6)))
+    }
+
+    #[test]
+    fn check_parse_tokens_errors() {
+        let error = |input: &str| {
+            let tokens = tokenize_with_spans(Cursor::new(input)).unwrap();
+            parse_tokens(&tokens).unwrap_err().to_string()
+        };
+        assert_eq!(error("{\n  \"key\" 1\n}"), "expected `:` after the object key, found number `1` at line 2, column 9");
+        assert_eq!(error("{\"a\": 1, }"), "expected a string for the object key, found `}` at line 1, column 10");
+        assert_eq!(error("{1: 2}"), "expected a string for the object key, found number `1` at line 1, column 2");
+        assert_eq!(error("{\"a\": 1 \"b\": 2}"), "expected `,` or `}` after the object member, found string \"b\" at line 1, column 9");
+        assert_eq!(error("[1, 2"), "expected `,` or `]` after the array element, found end of input at line 1, column 6");
+        assert_eq!(error("[:]"), "expected a value, found `:` at line 1, column 2");
+        assert_eq!(error("[] \"x\""), "expected the end of input after the value, found string \"x\" at line 1, column 4");
+        assert_eq!(error("\n\n   "), "expected a value, found end of input at line 3, column 4");
+    }
+
+    #[test]
+    fn check_parse_tokens_decodes_strings() {
+        let tokens = [
+            Token::LeftBrace,
+            Token::String("\"a\\\"b\"".into()),
+            Token::Colon,
+            Token::String("\"tab\\t, \\u00e9, \\ud83d\\ude00, \\/\"".into()),
+            Token::RightBrace,
+        ];
+        let result = parse(&tokens);
+        assert_eq!(result, Ok(JsonValue::Object(vec![
+            ("a\"b".to_string(), JsonValue::String("tab\t, \u{e9}, \u{1f600}, /".to_string())),
+        ])))
+    }
+
+    #[test]
+    fn check_parse_tokens_numbers() {
+        let tokens = [
+            Token::LeftBracket,
+            Token::Number("-12.5e3".into()),
+            Token::Comma,
+            Token::Null,
+            Token::RightBracket,
+        ];
+        let result = parse(&tokens).unwrap();
+        assert_eq!(result, JsonValue::Array(vec![JsonValue::Number("-12.5e3".to_string()), JsonValue::Null]));
+        if let JsonValue::Array(elements) = &result {
+            assert_eq!(elements[0].as_f64(), Some(-12500.0));
+            assert_eq!(elements[1].as_f64(), None);
+        }
+    }
+
+    #[test]
+    fn check_parse_tokens_keeps_member_order() {
+        let tokens = tokenize_with_spans(Cursor::new(br#"{"b": 1, "a": [2, {}], "b": 3}"#)).unwrap();
+        let number = |n: &str| JsonValue::Number(n.to_string());
+        assert_eq!(parse_tokens(&tokens), Ok(JsonValue::Object(vec![
+            ("b".to_string(), number("1")),
+            ("a".to_string(), JsonValue::Array(vec![number("2"), JsonValue::Object(vec![])])),
+            ("b".to_string(), number("3")),
+        ])))
+    }
+
+    #[test]
+    fn check_sort_keys() {
+        let mut value = parse_str(r#"{"b": 1, "a": [{"z": 2, "é": 3, "Z": 4}], "b": 0, "": null}"#).unwrap();
+        value.sort_keys();
+        assert_eq!(value, parse_str(r#"{"": null, "a": [{"Z": 4, "z": 2, "é": 3}], "b": 1, "b": 0}"#).unwrap());
+    }
+
+    #[test]
+    fn check_check_reader() {
+        let input = concat!(
+            "{\n",
+            "  \"a\": [1, 2,],\n",
+            "  \"b\" 3,\n",
+            "  key: 'x',\n",
+            "  \"c\": {\"d\": tru},\n",
+            "  \"e\": [1 2], \"f\": 4\n",
+            "  \"g\": null\n",
+            "}\n",
+        );
+        let errors: Vec<String> = check_reader(Cursor::new(input)).iter().map(|e| e.to_string()).collect();
+        assert_eq!(errors, [
+            "expected a value, found `]` at line 2, column 14",
+            "expected `:` after the object key, found number `3` at line 3, column 7",
+            "illegal character `k` at line 4, column 3",
+            "illegal character `'` at line 4, column 8",
+            "expected `true`, found `tru` at line 5, column 14",
+            "expected `,` or `]` after the array element, found number `2` at line 6, column 11",
+            "expected `,` or `}` after the object member, found string \"g\" at line 7, column 3",
+        ]);
+
+        assert_eq!(check_reader(Cursor::new("[1, {\"a\": [2")).iter().map(|e| e.to_string()).collect::<Vec<_>>(), [
+            "expected `,` or `]` after the array element, found end of input at line 1, column 13",
+        ]);
+        assert_eq!(check_reader(Cursor::new("  ")), [JsonError::Empty]);
+        assert_eq!(check_reader(Cursor::new("{\"a\": [true]}")), []);
+    }
+
+    #[test]
+    fn check_no_max_depth() {
+        // Deeper than the call stack could go if parsing or dropping recursed
+        let deep = format!("{}1{}", "[{\"a\":".repeat(200_000), "}]".repeat(200_000));
+        let options = ParseOptions { max_depth: None, ..ParseOptions::default() };
+        assert!(parse_reader_with(Cursor::new(&deep), &options).is_ok());
+        let (errors, _) = check_reader_with(Cursor::new(&deep[..deep.len() - 1]), &options);
+        assert_eq!(errors.iter().map(|e| e.message()).collect::<Vec<_>>(), ["expected `,` or `]` after the array element, found end of input"]);
+    }
+
+    #[test]
+    fn check_max_depth() {
+        let deep = format!("{}{}", "[".repeat(100_000), "]".repeat(100_000));
+        let e = parse_str(&deep).unwrap_err();
+        assert_eq!(e.to_string(), "maximum depth exceeded, `[` nests deeper than 512 levels at line 1, column 513");
+        assert_eq!(check_reader(Cursor::new(&deep)), [e]);
+
+        let options = ParseOptions { max_depth: Some(2), ..ParseOptions::default() };
+        assert!(parse_reader_with(Cursor::new("[{\"a\": 1}, []]"), &options).is_ok());
+        let e = parse_reader_with(Cursor::new("[{\"a\": [1]}]"), &options).unwrap_err();
+        assert_eq!(e.to_string(), "maximum depth exceeded, `[` nests deeper than 2 levels at line 1, column 8");
+        let (errors, _) = check_reader_with(Cursor::new("[[[1]], [2], [[3]]]"), &options);
+        let errors: Vec<String> = errors.iter().map(|e| e.to_string()).collect();
+        assert_eq!(errors, [
+            "maximum depth exceeded, `[` nests deeper than 2 levels at line 1, column 3",
+            "maximum depth exceeded, `[` nests deeper than 2 levels at line 1, column 15",
+        ]);
+    }
+
+    #[test]
+    fn check_duplicate_keys() {
+        let input = "{\"a\": 1,\n \"b\": {\"a\": 2, \"c\": 3, \"c\": 4},\n \"a\": 5, \"\\u0061\": 6}";
+        let (value, warnings) = parse_reader_with(Cursor::new(input), &ParseOptions::default()).unwrap();
+        assert_eq!(value, parse_str(input).unwrap());
+        let duplicates: Vec<String> = warnings.duplicates.iter().map(|d| d.to_string()).collect();
+        assert_eq!(duplicates, [
+            "duplicate key `c` at line 2, column 24, first written at line 2, column 16",
+            "duplicate key `a` at line 3, column 2, first written at line 1, column 2",
+            "duplicate key `a` at line 3, column 10, first written at line 1, column 2",
+        ]);
+
+        let (errors, warnings) = check_reader_with(Cursor::new("[{\"a\": 1, \"a\": 2}, {\"b\" 3}]"), &ParseOptions::default());
+        assert_eq!(errors.len(), 1);
+        assert_eq!(warnings.duplicates, [DuplicateKey {
+            key: "a".to_string(),
+            first: LineCol { line: 1, column: 3 },
+            position: LineCol { line: 1, column: 11 },
+            span: Span::new(10, 13),
+        }]);
+    }
+
+    #[test]
+    fn check_trailing_commas() {
+        let lenient = ParseOptions { allow_trailing_commas: true, ..ParseOptions::default() };
+        let (value, warnings) = parse_str_with("{\"a\": [1, 2,],\n}", &lenient).unwrap();
+        assert_eq!(value, parse_str("{\"a\": [1, 2]}").unwrap());
+        assert_eq!(warnings.trailing_commas, [
+            TrailingComma { in_object: false, position: LineCol { line: 1, column: 12 }, span: Span::new(11, 12) },
+            TrailingComma { in_object: true, position: LineCol { line: 1, column: 14 }, span: Span::new(13, 14) },
+        ]);
+        // Still errors otherwise
+        assert!(parse_str_with("[1,]", &ParseOptions::default()).is_err());
+
+        // Read past along with every error
+        let (errors, warnings) = check_reader_with(Cursor::new("[{\"a\": 1,}, {\"b\" 3,},]"), &lenient);
+        let errors: Vec<String> = errors.iter().map(|e| e.to_string()).collect();
+        assert_eq!(errors, ["expected `:` after the object key, found number `3` at line 1, column 18"]);
+        assert_eq!(warnings.trailing_commas.len(), 3);
+    }
+
+    #[test]
+    fn check_json5() {
+        let options = ParseOptions { json5: true, ..ParseOptions::default() };
+        let input = "// config\n{unquoted: 'and \"you\" can\\'t',\n  null: [0xFF, +1, .5, 5., -Infinity, NaN,],\n  'line\\\nbreak': '\\x41\\v\\0',}";
+        let (value, warnings) = parse_str_with(input, &options).unwrap();
+        assert_eq!(value, parse_str(r#"{"unquoted": "and \"you\" can't", "null": [255, 1, 0.5, 5, null, null], "linebreak": "A\u000b\u0000"}"#).unwrap());
+        // Trailing commas are JSON5, not something to warn about
+        assert!(warnings.is_empty());
+        assert_eq!(parse_reader_with(Cursor::new(input), &options).unwrap().0, value);
+        assert_eq!(validate_reader(Cursor::new(input), &options).map(|warnings| warnings.is_empty()), Ok(true));
+
+        let e = parse_str_with("{[1]: 2}", &options).unwrap_err();
+        assert_eq!(e.to_string(), "expected a string or identifier for the object key, found `[` at line 1, column 2");
+        assert!(parse_str("{unquoted: 1}").is_err());
+    }
+
+    #[test]
+    fn check_bom() {
+        let input = "\u{feff}[1,\n2]";
+        let expected = JsonValue::Array(vec![JsonValue::Number("1".to_string()), JsonValue::Number("2".to_string())]);
+        assert_eq!(parse_str(input), Ok(expected.clone()));
+        assert_eq!(parse_reader(Cursor::new(input)), Ok(expected));
+        // Not counted as a column, but still taking up bytes
+        let e = parse_str("\u{feff}[1,]").unwrap_err();
+        assert_eq!((e.position(), e.span()), (Some(LineCol { line: 1, column: 4 }), Some(Span::new(6, 7))));
+        // Only at the very start
+        assert!(parse_str(" \u{feff}[]").is_err());
+
+        let strict = ParseOptions { allow_bom: false, ..ParseOptions::default() };
+        let e = parse_reader_with(Cursor::new(input), &strict).unwrap_err();
+        assert_eq!(e.to_string(), "byte order mark at the start of the input at line 1, column 1");
+        assert_eq!(e.span(), Some(Span::new(0, 3)));
+        let (errors, _) = check_reader_with(Cursor::new("\u{feff}[1,]"), &strict);
+        let errors: Vec<String> = errors.iter().map(|e| e.to_string()).collect();
+        assert_eq!(errors, ["byte order mark at the start of the input at line 1, column 1", "expected a value, found `]` at line 1, column 4"]);
+        assert!(validate_reader(Cursor::new(input), &strict).is_err());
+    }
+
+    #[test]
+    fn check_parse_str() {
+        assert_eq!(parse_str(" [true, \"\\u0041\"] "), Ok(JsonValue::Array(vec![
+            JsonValue::Bool(true),
+            JsonValue::String("A".to_string()),
+        ])));
+        assert_eq!(parse_str("  \n"), Err(JsonError::Empty));
+        assert_eq!(parse_str("[1,]"), Err(JsonError::Parse(ParseError::new("a value", &at(Token::RightBracket, 4)))));
+        let result = parse_str("{key: 1}").unwrap_err();
+        assert_eq!(result.to_string(), "illegal character `k` at line 1, column 2");
+        assert_eq!(result.position(), Some(LineCol { line: 1, column: 2 }));
+        assert_eq!(result.span(), Some(Span::new(1, 4)));
+        assert_eq!(parse_str("{\"a\": 1 \"b\": 2}").unwrap_err().span(), Some(Span::new(8, 11)));
+        assert_eq!(parse_str("[1, 2").unwrap_err().span(), Some(Span::point(5)));
+    }
+
+    #[test]
+    fn check_parse_reader() {
+        let result = parse_reader(Cursor::new(b"{\n  \"key\": null\n}\n"));
+        assert_eq!(result, Ok(JsonValue::Object(vec![("key".to_string(), JsonValue::Null)])));
+
+        let result = parse_reader(Cursor::new(b"[\"\xff\"]")).unwrap_err();
+        assert!(matches!(result, JsonError::Tokenize(_)));
+        assert_eq!(result.to_string(), "invalid UTF-8, found byte 0xFF where a character should start or go on at line 1, column 3");
+        assert_eq!(result.span(), Some(Span::new(2, 3)));
+    }
+}
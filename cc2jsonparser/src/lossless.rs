@@ -0,0 +1,279 @@
+// Writing a document back out the way it was written.
+// A JsonValue keeps numbers as they're written but strings only as what they stand for, so
+// `"\u00e9"` comes back out as `"é"`. A RawValue keeps each string and key as it's written too,
+// escapes and all, along with what it stands for, so writing it back out gives each token as it
+// was in the input, laid out compactly or indented like write_json. Values made rather than
+// parsed are written the way write_json writes them.
+use crate::json5::{key_name, number_value};
+use crate::write::{write_string, write_styled, KEY_STYLE, LITERAL_STYLE, NUMBER_STYLE, STRING_STYLE};
+use crate::{parse_all, string_value, tokenize_str_with, JsonError, JsonValue, ParseOptions, Span, Token, Warnings, WriteOptions};
+use std::io::{self, Write};
+
+/// A parsed value with its strings, keys, and numbers as they're written in the input
+#[derive(Clone, Debug, PartialEq)]
+pub enum RawValue {
+    /// The members in the order they were written, keeping any repeated keys
+    Object(Vec<(RawString, RawValue)>),
+    Array(Vec<RawValue>),
+    String(RawString),
+    /// The number as written
+    Number(String),
+    Bool(bool),
+    Null,
+}
+
+/// A string or key as it stands, and as it's written with its quotes and escapes
+#[derive(Clone, Debug, PartialEq)]
+pub struct RawString {
+    pub value: String,
+    pub raw: String,
+}
+
+impl RawString {
+    /// The string written the way write_json writes it, escaped only as much as JSON needs
+    pub fn new(value: impl Into<String>) -> RawString {
+        let value = value.into();
+        let mut raw = Vec::new();
+        write_string(&mut raw, &value).expect("writing to a Vec can't fail");
+        RawString { value, raw: String::from_utf8(raw).expect("JSON written from strings is UTF-8") }
+    }
+}
+
+impl RawValue {
+    /// The value it stands for, as parse_str gives it
+    pub fn to_value(&self) -> JsonValue {
+        match self {
+            RawValue::Object(members) => JsonValue::Object(members.iter().map(|(key, value)| (key.value.clone(), value.to_value())).collect()),
+            RawValue::Array(elements) => JsonValue::Array(elements.iter().map(RawValue::to_value).collect()),
+            RawValue::String(string) => JsonValue::String(string.value.clone()),
+            RawValue::Number(number) => number_value(number),
+            RawValue::Bool(bool) => JsonValue::Bool(*bool),
+            RawValue::Null => JsonValue::Null,
+        }
+    }
+}
+
+impl From<&JsonValue> for RawValue {
+    fn from(value: &JsonValue) -> RawValue {
+        match value {
+            JsonValue::Object(members) => RawValue::Object(members.iter().map(|(key, value)| (RawString::new(key.as_str()), value.into())).collect()),
+            JsonValue::Array(elements) => RawValue::Array(elements.iter().map(RawValue::from).collect()),
+            JsonValue::String(string) => RawValue::String(RawString::new(string.as_str())),
+            JsonValue::Number(number) => RawValue::Number(number.clone()),
+            JsonValue::Bool(bool) => RawValue::Bool(*bool),
+            JsonValue::Null => RawValue::Null,
+        }
+    }
+}
+
+impl Drop for RawValue {
+    // As for JsonValue, take the values inside out onto a stack rather than dropping them in turn
+    fn drop(&mut self) {
+        if !self.has_nested() {
+            return;
+        }
+        let mut waiting = Vec::new();
+        self.take_inside(&mut waiting);
+        while let Some(mut value) = waiting.pop() {
+            if value.has_nested() {
+                value.take_inside(&mut waiting);
+            }
+        }
+    }
+}
+
+impl RawValue {
+    /// Whether there's an object or array inside this one
+    fn has_nested(&self) -> bool {
+        let nested = |value: &RawValue| matches!(value, RawValue::Object(_) | RawValue::Array(_));
+        match self {
+            RawValue::Object(members) => members.iter().any(|(_, value)| nested(value)),
+            RawValue::Array(elements) => elements.iter().any(nested),
+            _ => false,
+        }
+    }
+
+    fn take_inside(&mut self, values: &mut Vec<RawValue>) {
+        match self {
+            RawValue::Object(members) => values.extend(members.drain(..).map(|(_, value)| value)),
+            RawValue::Array(elements) => values.append(elements),
+            _ => {},
+        }
+    }
+}
+
+/// An object or array still being read
+enum Open {
+    /// The members read so far, and the key of the one being read
+    Object(Vec<(RawString, RawValue)>, Option<RawString>),
+    Array(Vec<RawValue>),
+}
+
+/// Parse a JSON document within the limits of `options`, keeping its strings, keys, and numbers as
+/// they're written, along with what it has in it to warn about
+pub fn parse_str_lossless(input: &str, options: &ParseOptions) -> Result<(RawValue, Warnings), JsonError> {
+    let tokens = tokenize_str_with(input, options).map_err(JsonError::Tokenize)?;
+    let (_, warnings) = parse_all(&tokens, options)?;
+    // The tokens make a document, so each is where the parser found it could go
+    let mut open = Vec::new();
+    let mut document = None;
+    for token in &tokens {
+        let value = match (&token.value, open.last_mut()) {
+            (Token::LeftBrace, _) => {
+                open.push(Open::Object(Vec::new(), None));
+                continue;
+            },
+            (Token::LeftBracket, _) => {
+                open.push(Open::Array(Vec::new()));
+                continue;
+            },
+            (Token::RightBrace | Token::RightBracket, _) => match open.pop() {
+                Some(Open::Object(members, _)) => RawValue::Object(members),
+                Some(Open::Array(elements)) => RawValue::Array(elements),
+                None => unreachable!("a close comes after its open"),
+            },
+            (Token::Colon | Token::Comma | Token::Eof, _) => continue,
+            (value, Some(Open::Object(_, key @ None))) => {
+                let raw = token_text(input, token.span);
+                let value = match value {
+                    Token::String(raw) => string_value(raw).expect("the parser read the key"),
+                    other => key_name(other).expect("the parser read the key").to_string(),
+                };
+                *key = Some(RawString { value, raw: raw.to_string() });
+                continue;
+            },
+            (Token::String(raw), _) => RawValue::String(RawString { value: string_value(raw).expect("the parser read the string"), raw: raw.to_string() }),
+            (Token::Number(number), _) => RawValue::Number(number.to_string()),
+            (Token::True, _) => RawValue::Bool(true),
+            (Token::False, _) => RawValue::Bool(false),
+            (Token::Null, _) => RawValue::Null,
+            (Token::Identifier(_), _) => unreachable!("the parser only reads names as keys"),
+        };
+        match open.last_mut() {
+            Some(Open::Object(members, key)) => members.push((key.take().expect("a member's key comes before its value"), value)),
+            Some(Open::Array(elements)) => elements.push(value),
+            None => document = Some(value),
+        }
+    }
+    Ok((document.expect("the parser read a value"), warnings))
+}
+
+/// The text of a token in the input
+fn token_text(input: &str, span: Span) -> &str {
+    &input[span.start..span.end]
+}
+
+/// Write the value laid out the way the options say, without a line break at the end, each
+/// string, key, and number as it's written
+pub fn write_lossless(out: &mut impl Write, value: &RawValue, options: WriteOptions) -> io::Result<()> {
+    write_value(out, value, &options, 0)
+}
+
+/// The value as write_lossless writes it, indented by `indent` for each level of nesting with a
+/// line break at the end, or compactly without one if it's None
+pub fn to_string_lossless(value: &RawValue, indent: Option<&str>) -> String {
+    let mut out = Vec::new();
+    write_lossless(&mut out, value, WriteOptions { indent, color: false }).expect("writing to a Vec can't fail");
+    if indent.is_some() {
+        out.push(b'\n');
+    }
+    String::from_utf8(out).expect("JSON written from strings is UTF-8")
+}
+
+fn write_value(out: &mut impl Write, value: &RawValue, options: &WriteOptions, depth: usize) -> io::Result<()> {
+    let newline = |out: &mut dyn Write, depth: usize| -> io::Result<()> {
+        let Some(indent) = options.indent else {
+            return Ok(());
+        };
+        out.write_all(b"\n")?;
+        for _ in 0..depth {
+            out.write_all(indent.as_bytes())?;
+        }
+        Ok(())
+    };
+
+    match value {
+        RawValue::Object(members) if members.is_empty() => out.write_all(b"{}"),
+        RawValue::Array(elements) if elements.is_empty() => out.write_all(b"[]"),
+        RawValue::Object(members) => {
+            out.write_all(b"{")?;
+            for (i, (key, value)) in members.iter().enumerate() {
+                if i > 0 {
+                    out.write_all(b",")?;
+                }
+                newline(out, depth + 1)?;
+                write_styled(out, &key.raw, KEY_STYLE, options.color)?;
+                out.write_all(if options.indent.is_some() { b": " } else { b":" })?;
+                write_value(out, value, options, depth + 1)?;
+            }
+            newline(out, depth)?;
+            out.write_all(b"}")
+        },
+        RawValue::Array(elements) => {
+            out.write_all(b"[")?;
+            for (i, element) in elements.iter().enumerate() {
+                if i > 0 {
+                    out.write_all(b",")?;
+                }
+                newline(out, depth + 1)?;
+                write_value(out, element, options, depth + 1)?;
+            }
+            newline(out, depth)?;
+            out.write_all(b"]")
+        },
+        RawValue::String(string) => write_styled(out, &string.raw, STRING_STYLE, options.color),
+        RawValue::Number(number) => write_styled(out, number, NUMBER_STYLE, options.color),
+        RawValue::Bool(true) => write_styled(out, "true", LITERAL_STYLE, options.color),
+        RawValue::Bool(false) => write_styled(out, "false", LITERAL_STYLE, options.color),
+        RawValue::Null => write_styled(out, "null", LITERAL_STYLE, options.color),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse_str, to_string_compact};
+
+    fn lossless(input: &str) -> RawValue {
+        parse_str_lossless(input, &ParseOptions::default()).unwrap().0
+    }
+
+    #[test]
+    fn check_parse_str_lossless() {
+        let input = r#"{"caf\u00e9":["\/path",1.50E+2,-0.0,"tab\tand \ud83d\ude00",true,null],"a\"b":{}}"#;
+        let value = lossless(input);
+        assert_eq!(to_string_lossless(&value, None), input);
+        assert_eq!(to_string_compact(&value.to_value()), "{\"café\":[\"/path\",1.50E+2,-0.0,\"tab\\tand 😀\",true,null],\"a\\\"b\":{}}");
+        assert_eq!(to_string_lossless(&value, Some("  ")), concat!(
+            "{\n",
+            "  \"caf\\u00e9\": [\n",
+            "    \"\\/path\",\n",
+            "    1.50E+2,\n",
+            "    -0.0,\n",
+            "    \"tab\\tand \\ud83d\\ude00\",\n",
+            "    true,\n",
+            "    null\n",
+            "  ],\n",
+            "  \"a\\\"b\": {}\n",
+            "}\n",
+        ));
+        let RawValue::Object(members) = &value else { panic!("the document is an object") };
+        assert_eq!(members[0].0, RawString { value: "café".to_string(), raw: "\"caf\\u00e9\"".to_string() });
+        assert_eq!(to_string_lossless(&lossless("\"\\u0041\""), None), "\"\\u0041\"");
+    }
+
+    #[test]
+    fn check_parse_str_lossless_of_json5() {
+        let options = ParseOptions { json5: true, ..ParseOptions::default() };
+        let (value, _) = parse_str_lossless("{key: 'it\\'s', hex: 0x1F, 'q': [Infinity,],}", &options).unwrap();
+        assert_eq!(to_string_lossless(&value, None), "{key:'it\\'s',hex:0x1F,'q':[Infinity]}");
+        assert_eq!(to_string_compact(&value.to_value()), r#"{"key":"it's","hex":31,"q":[null]}"#);
+    }
+
+    #[test]
+    fn check_raw_value_from_json_value() {
+        let value = parse_str(r#"{"a\u0062": ["\u00e9\n", 1e2]}"#).unwrap();
+        assert_eq!(to_string_lossless(&RawValue::from(&value), None), to_string_compact(&value));
+        assert!(parse_str_lossless("[1,]", &ParseOptions::default()).is_err());
+    }
+}
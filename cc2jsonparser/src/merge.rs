@@ -0,0 +1,78 @@
+// JSON Merge Patch.
+// A merge patch is written like the document it changes: each member of the patch replaces the
+// document's member with that key, objects being merged into the objects they're replacing in
+// turn, and a null removes the member instead. Anything but an object replaces the whole value
+// it's patching, so an array can only be replaced, not changed element by element.
+// Reference:  https://www.rfc-editor.org/rfc/rfc7386
+use crate::JsonValue;
+
+/// Apply the merge patch to the target, changing it in place. A key the target has more than once
+/// is set where it was last given and removed everywhere.
+pub fn merge_patch(target: &mut JsonValue, patch: &JsonValue) {
+    let JsonValue::Object(patch_members) = patch else {
+        *target = patch.clone();
+        return;
+    };
+    if !matches!(target, JsonValue::Object(_)) {
+        *target = JsonValue::Object(Vec::new());
+    }
+    let JsonValue::Object(members) = target else { unreachable!("the target was just made an object") };
+    for (key, value) in patch_members {
+        if let JsonValue::Null = value {
+            members.retain(|(existing, _)| existing != key);
+            continue;
+        }
+        match members.iter_mut().rev().find(|(existing, _)| existing == key) {
+            Some((_, existing)) => merge_patch(existing, value),
+            None => {
+                // What's added is patched into nothing, so the nulls in it are left out
+                let mut added = JsonValue::Null;
+                merge_patch(&mut added, value);
+                members.push((key.clone(), added));
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse_str, to_string_compact};
+
+    fn patched(target: &str, patch: &str) -> String {
+        let mut target = parse_str(target).unwrap();
+        merge_patch(&mut target, &parse_str(patch).unwrap());
+        to_string_compact(&target)
+    }
+
+    #[test]
+    fn check_merge_patch() {
+        // RFC 7386's Appendix A
+        let examples = [
+            (r#"{"a":"b"}"#, r#"{"a":"c"}"#, r#"{"a":"c"}"#),
+            (r#"{"a":"b"}"#, r#"{"b":"c"}"#, r#"{"a":"b","b":"c"}"#),
+            (r#"{"a":"b"}"#, r#"{"a":null}"#, r#"{}"#),
+            (r#"{"a":"b","b":"c"}"#, r#"{"a":null}"#, r#"{"b":"c"}"#),
+            (r#"{"a":["b"]}"#, r#"{"a":"c"}"#, r#"{"a":"c"}"#),
+            (r#"{"a":"c"}"#, r#"{"a":["b"]}"#, r#"{"a":["b"]}"#),
+            (r#"{"a":{"b":"c"}}"#, r#"{"a":{"b":"d","c":null}}"#, r#"{"a":{"b":"d"}}"#),
+            (r#"{"a":[{"b":"c"}]}"#, r#"{"a":[1]}"#, r#"{"a":[1]}"#),
+            (r#"["a","b"]"#, r#"["c","d"]"#, r#"["c","d"]"#),
+            (r#"{"a":"b"}"#, r#"["c"]"#, r#"["c"]"#),
+            (r#"{"a":"foo"}"#, "null", "null"),
+            (r#"{"a":"foo"}"#, r#""bar""#, r#""bar""#),
+            (r#"{"e":null}"#, r#"{"a":1}"#, r#"{"e":null,"a":1}"#),
+            (r#"[1,2]"#, r#"{"a":"b","c":null}"#, r#"{"a":"b"}"#),
+            (r#"{}"#, r#"{"a":{"bb":{"ccc":null}}}"#, r#"{"a":{"bb":{}}}"#),
+        ];
+        for (target, patch, result) in examples {
+            assert_eq!(patched(target, patch), result, "{} patched with {}", target, patch);
+        }
+    }
+
+    #[test]
+    fn check_merge_patch_with_repeated_keys() {
+        assert_eq!(patched(r#"{"a": 1, "b": 2, "a": 3}"#, r#"{"a": {"x": 4}}"#), r#"{"a":1,"b":2,"a":{"x":4}}"#);
+        assert_eq!(patched(r#"{"a": 1, "b": 2, "a": 3}"#, r#"{"a": null}"#), r#"{"b":2}"#);
+    }
+}
@@ -0,0 +1,335 @@
+// MessagePack.
+// A binary format with the same values as JSON, more compact and quicker to read, which APIs and
+// queues use for payloads. A JsonValue is encoded with the smallest form of each value, integers
+// as integers if 64 bits hold them and any other number as a 64-bit float. Decoding gives the
+// JsonValue of a MessagePack value, floats written the shortest way that reads back as the same
+// float and NaN and the infinities as null, as JSON.stringify has them. Binary data, extension
+// types, and map keys that aren't strings have no JSON to be, so they're errors.
+// Reference:  https://github.com/msgpack/msgpack/blob/master/spec.md
+use crate::JsonValue;
+use std::fmt;
+
+/// Why bytes aren't a MessagePack value that can be JSON, and where in them
+#[derive(Clone, Debug, PartialEq)]
+pub struct MsgpackError {
+    pub message: String,
+    /// How far into the bytes the value that's wrong starts
+    pub offset: usize,
+}
+
+impl fmt::Display for MsgpackError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} at byte {}", self.message, self.offset)
+    }
+}
+
+impl std::error::Error for MsgpackError {}
+
+/// The value encoded as MessagePack
+pub fn to_msgpack(value: &JsonValue) -> Vec<u8> {
+    let mut out = Vec::new();
+    encode(&mut out, value);
+    out
+}
+
+/// What's still to encode: a value, or the key of the member whose value comes after it
+enum Item<'a> {
+    Value(&'a JsonValue),
+    Key(&'a str),
+}
+
+/// Encode the value, keeping what's left of the objects and arrays it's in on a stack rather than
+/// the call stack, so however deep a document nests it can be encoded
+fn encode(out: &mut Vec<u8>, value: &JsonValue) {
+    let mut waiting = vec![Item::Value(value)];
+    while let Some(item) = waiting.pop() {
+        match item {
+            Item::Value(JsonValue::Object(members)) => {
+                write_length(out, members.len(), 0x80, [0xde, 0xdf]);
+                waiting.extend(members.iter().rev().flat_map(|(key, value)| [Item::Value(value), Item::Key(key)]));
+            },
+            Item::Value(JsonValue::Array(elements)) => {
+                write_length(out, elements.len(), 0x90, [0xdc, 0xdd]);
+                waiting.extend(elements.iter().rev().map(Item::Value));
+            },
+            Item::Value(JsonValue::String(string)) => write_str(out, string),
+            Item::Value(JsonValue::Number(number)) => write_number(out, number),
+            Item::Value(JsonValue::Bool(false)) => out.push(0xc2),
+            Item::Value(JsonValue::Bool(true)) => out.push(0xc3),
+            Item::Value(JsonValue::Null) => out.push(0xc0),
+            Item::Key(key) => write_str(out, key),
+        }
+    }
+}
+
+/// Write the length of a map or array, in the fix form starting with `fix` if it's under 16 or
+/// else after the first of `markers` for 16 bits or the second for 32
+fn write_length(out: &mut Vec<u8>, length: usize, fix: u8, markers: [u8; 2]) {
+    match length {
+        0..=15 => out.push(fix | length as u8),
+        16..=0xffff => {
+            out.push(markers[0]);
+            out.extend((length as u16).to_be_bytes());
+        },
+        _ => {
+            out.push(markers[1]);
+            out.extend((length as u32).to_be_bytes());
+        },
+    }
+}
+
+fn write_str(out: &mut Vec<u8>, string: &str) {
+    match string.len() {
+        length @ 0..=31 => out.push(0xa0 | length as u8),
+        length @ 32..=0xff => out.extend([0xd9, length as u8]),
+        length @ 0x100..=0xffff => {
+            out.push(0xda);
+            out.extend((length as u16).to_be_bytes());
+        },
+        length => {
+            out.push(0xdb);
+            out.extend((length as u32).to_be_bytes());
+        },
+    }
+    out.extend(string.as_bytes());
+}
+
+/// Write a number as the smallest integer that holds it, or else as a 64-bit float
+fn write_number(out: &mut Vec<u8>, number: &str) {
+    let integer = !number.contains(['.', 'e', 'E']);
+    match (number.parse::<i64>(), number.parse::<u64>()) {
+        (Ok(n), _) if integer => write_int(out, n),
+        (_, Ok(n)) if integer => {
+            out.push(0xcf);
+            out.extend(n.to_be_bytes());
+        },
+        _ => {
+            out.push(0xcb);
+            out.extend(number.parse::<f64>().expect("a JSON number is a float").to_be_bytes());
+        },
+    }
+}
+
+fn write_int(out: &mut Vec<u8>, n: i64) {
+    match n {
+        -32..=0x7f => out.push(n as u8),
+        0x80..=0xff => out.extend([0xcc, n as u8]),
+        0x100..=0xffff => {
+            out.push(0xcd);
+            out.extend((n as u16).to_be_bytes());
+        },
+        0x1_0000..=0xffff_ffff => {
+            out.push(0xce);
+            out.extend((n as u32).to_be_bytes());
+        },
+        -0x80..=-33 => out.extend([0xd0, n as u8]),
+        -0x8000..=-0x81 => {
+            out.push(0xd1);
+            out.extend((n as i16).to_be_bytes());
+        },
+        -0x8000_0000..=-0x8001 => {
+            out.push(0xd2);
+            out.extend((n as i32).to_be_bytes());
+        },
+        _ if n > 0 => {
+            out.push(0xcf);
+            out.extend((n as u64).to_be_bytes());
+        },
+        _ => {
+            out.push(0xd3);
+            out.extend(n.to_be_bytes());
+        },
+    }
+}
+
+/// Decode the one MessagePack value `bytes` hold, nested no more than `max_depth` deep if there's
+/// a limit
+pub fn from_msgpack(bytes: &[u8], max_depth: Option<usize>) -> Result<JsonValue, MsgpackError> {
+    if bytes.is_empty() {
+        return Err(MsgpackError { message: "did not find anything to decode".to_string(), offset: 0 });
+    }
+    let mut decoder = Decoder { bytes, offset: 0, max_depth, depth: 0 };
+    let value = decoder.decode()?;
+    if decoder.offset < bytes.len() {
+        return Err(decoder.error(decoder.offset, "expected the end of the input after the value"));
+    }
+    Ok(value)
+}
+
+struct Decoder<'a> {
+    bytes: &'a [u8],
+    /// How far into the bytes decoding has got
+    offset: usize,
+    max_depth: Option<usize>,
+    /// How many maps and arrays are open
+    depth: usize,
+}
+
+impl<'a> Decoder<'a> {
+    fn error(&self, offset: usize, message: impl Into<String>) -> MsgpackError {
+        MsgpackError { message: message.into(), offset }
+    }
+
+    /// The next `length` bytes, of what starts at `start`
+    fn take(&mut self, length: usize, start: usize, what: &str) -> Result<&'a [u8], MsgpackError> {
+        let bytes = self.bytes.get(self.offset..).and_then(|rest| rest.get(..length));
+        let bytes = bytes.ok_or_else(|| self.error(start, format!("the input ends partway through {}", what)))?;
+        self.offset += length;
+        Ok(bytes)
+    }
+
+    /// The next `N` bytes, of what starts at `start`
+    fn take_array<const N: usize>(&mut self, start: usize, what: &str) -> Result<[u8; N], MsgpackError> {
+        Ok(self.take(N, start, what)?.try_into().expect("took N bytes"))
+    }
+
+    /// A length or integer of `size` bytes, of what starts at `start`
+    fn read_uint(&mut self, size: usize, start: usize, what: &str) -> Result<u64, MsgpackError> {
+        Ok(self.take(size, start, what)?.iter().fold(0, |n, &byte| n << 8 | u64::from(byte)))
+    }
+
+    fn decode(&mut self) -> Result<JsonValue, MsgpackError> {
+        let start = self.offset;
+        let marker = self.take(1, start, "a value")?[0];
+        let value = match marker {
+            0x00..=0x7f => JsonValue::Number(marker.to_string()),
+            0xe0..=0xff => JsonValue::Number((marker as i8).to_string()),
+            0x80..=0x8f => self.decode_map(usize::from(marker & 0x0f), start)?,
+            0x90..=0x9f => self.decode_array(usize::from(marker & 0x0f), start)?,
+            0xa0..=0xbf => self.decode_str(usize::from(marker & 0x1f), start)?,
+            0xc0 => JsonValue::Null,
+            0xc1 => return Err(self.error(start, "0xc1 is never used in MessagePack")),
+            0xc2 => JsonValue::Bool(false),
+            0xc3 => JsonValue::Bool(true),
+            0xc4..=0xc6 => return Err(self.error(start, "binary data has no JSON to be")),
+            0xc7..=0xc9 | 0xd4..=0xd8 => return Err(self.error(start, "extension types have no JSON to be")),
+            0xca => {
+                let float = f32::from_be_bytes(self.take_array(start, "a float")?);
+                float_value(float.into(), || format!("{:?}", float))
+            },
+            0xcb => {
+                let float = f64::from_be_bytes(self.take_array(start, "a float")?);
+                float_value(float, || format!("{:?}", float))
+            },
+            0xcc..=0xcf => {
+                let n = self.read_uint(1 << (marker - 0xcc), start, "an integer")?;
+                JsonValue::Number(n.to_string())
+            },
+            0xd0..=0xd3 => {
+                let size = 1 << (marker - 0xd0);
+                let n = self.read_uint(size, start, "an integer")?;
+                // Sign-extended from however many bytes there were
+                let shift = 64 - 8 * size;
+                JsonValue::Number((((n << shift) as i64) >> shift).to_string())
+            },
+            0xd9..=0xdb => {
+                let length = self.read_uint(1 << (marker - 0xd9), start, "a string")?;
+                self.decode_str(length as usize, start)?
+            },
+            0xdc | 0xdd => {
+                let length = self.read_uint(2 << (marker - 0xdc), start, "an array")?;
+                self.decode_array(length as usize, start)?
+            },
+            0xde | 0xdf => {
+                let length = self.read_uint(2 << (marker - 0xde), start, "a map")?;
+                self.decode_map(length as usize, start)?
+            },
+        };
+        Ok(value)
+    }
+
+    fn decode_str(&mut self, length: usize, start: usize) -> Result<JsonValue, MsgpackError> {
+        let bytes = self.take(length, start, "a string")?;
+        match std::str::from_utf8(bytes) {
+            Ok(string) => Ok(JsonValue::String(string.to_string())),
+            Err(_) => Err(self.error(start, "invalid UTF-8 in a string")),
+        }
+    }
+
+    fn open(&mut self, start: usize) -> Result<(), MsgpackError> {
+        if let Some(max_depth) = self.max_depth.filter(|&max| self.depth == max) {
+            return Err(self.error(start, format!("nested more than {} deep", max_depth)));
+        }
+        self.depth += 1;
+        Ok(())
+    }
+
+    fn decode_array(&mut self, length: usize, start: usize) -> Result<JsonValue, MsgpackError> {
+        self.open(start)?;
+        // Each element takes at least a byte, so a made-up length can't take much memory
+        let mut elements = Vec::with_capacity(length.min(self.bytes.len() - self.offset));
+        for _ in 0..length {
+            elements.push(self.decode()?);
+        }
+        self.depth -= 1;
+        Ok(JsonValue::Array(elements))
+    }
+
+    fn decode_map(&mut self, length: usize, start: usize) -> Result<JsonValue, MsgpackError> {
+        self.open(start)?;
+        let mut members = Vec::with_capacity(length.min(self.bytes.len() - self.offset));
+        for _ in 0..length {
+            let key_start = self.offset;
+            let key = self.decode()?;
+            let JsonValue::String(key) = &key else {
+                return Err(self.error(key_start, format!("JSON keys are strings, but this one is {}", key.type_name())));
+            };
+            members.push((key.clone(), self.decode()?));
+        }
+        self.depth -= 1;
+        Ok(JsonValue::Object(members))
+    }
+}
+
+/// The JSON value of a float, null if JSON has no way to write it
+fn float_value(float: f64, written: impl FnOnce() -> String) -> JsonValue {
+    match float.is_finite() {
+        true => JsonValue::Number(written()),
+        false => JsonValue::Null,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_str;
+
+    #[test]
+    fn check_to_msgpack() {
+        let value = parse_str(r#"{"a": [1, -1, 200, -200, 70000, -5000000000, 18446744073709551615, 1.5, true, null], "": "x"}"#).unwrap();
+        assert_eq!(to_msgpack(&value), [
+            0x82, 0xa1, b'a', 0x9a, 0x01, 0xff, 0xcc, 0xc8, 0xd1, 0xff, 0x38, 0xce, 0x00, 0x01, 0x11, 0x70,
+            0xd3, 0xff, 0xff, 0xff, 0xfe, 0xd5, 0xfa, 0x0e, 0x00, 0xcf, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+            0xcb, 0x3f, 0xf8, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xc3, 0xc0, 0xa0, 0xa1, b'x',
+        ]);
+        // Longer strings and arrays have their length after a marker
+        let long = JsonValue::Array(vec![JsonValue::String("y".repeat(40)); 16]);
+        assert_eq!(to_msgpack(&long)[..5], [0xdc, 0x00, 0x10, 0xd9, 40]);
+    }
+
+    #[test]
+    fn check_from_msgpack() {
+        let value = parse_str(r#"{"name": "caf\u00e9", "n": [0, 127, -32, -33, 65535, -2147483648, 9223372036854775807, 0.25, false], "m": {}}"#).unwrap();
+        assert_eq!(from_msgpack(&to_msgpack(&value), None), Ok(value));
+        // Floats as short as they can be, 32-bit ones too
+        assert_eq!(from_msgpack(&[0xca, 0x3d, 0xcc, 0xcc, 0xcd], None), Ok(JsonValue::Number("0.1".to_string())));
+        assert_eq!(from_msgpack(&[0xcb, 0x7f, 0xf0, 0, 0, 0, 0, 0, 0], None), Ok(JsonValue::Null));
+        assert_eq!(from_msgpack(&[0xd0, 0x80], None), Ok(JsonValue::Number("-128".to_string())));
+    }
+
+    #[test]
+    fn check_from_msgpack_fails_for_what_is_not_json() {
+        let error = |bytes: &[u8]| from_msgpack(bytes, Some(2)).unwrap_err().to_string();
+        assert_eq!(error(&[]), "did not find anything to decode at byte 0");
+        assert_eq!(error(&[0x92, 0x01]), "the input ends partway through a value at byte 2");
+        assert_eq!(error(&[0xa3, b'a']), "the input ends partway through a string at byte 0");
+        assert_eq!(error(&[0xdd, 0xff, 0xff, 0xff, 0xff]), "the input ends partway through a value at byte 5");
+        assert_eq!(error(&[0x91, 0xc4, 0x01, 0x00]), "binary data has no JSON to be at byte 1");
+        assert_eq!(error(&[0xd4, 0x01, 0x00]), "extension types have no JSON to be at byte 0");
+        assert_eq!(error(&[0x81, 0x01, 0x02]), "JSON keys are strings, but this one is a number at byte 1");
+        assert_eq!(error(&[0xa1, 0xff]), "invalid UTF-8 in a string at byte 0");
+        assert_eq!(error(&[0xc1]), "0xc1 is never used in MessagePack at byte 0");
+        assert_eq!(error(&[0xc0, 0xc0]), "expected the end of the input after the value at byte 1");
+        assert_eq!(error(&[0x91, 0x91, 0x91, 0xc0]), "nested more than 2 deep at byte 2");
+    }
+}
@@ -0,0 +1,154 @@
+// Newline-delimited JSON, also known as JSON Lines.
+// Logs and data pipelines write one JSON document per line, which read as a whole is a document
+// followed by trailing garbage. Read this way each line is checked as a document of its own,
+// blank lines being passed over, so every line that isn't one gets an error of its own. No token
+// can span lines, so each line's tokens are parsed where they're read from and the errors point
+// at the line and column in the whole input. read_ndjson gives each line's document as it's read,
+// so a stream of them is never all in memory at once.
+// Reference:  https://jsonlines.org
+use crate::token::{StreamError, TokenStream};
+use crate::{parse_document, JsonError, JsonParser, JsonValue, ParseOptions, Warnings};
+use std::io::BufRead;
+
+/// Check every line read from `reader` as a JSON document within the limits of `options`, giving
+/// the first error in each line that isn't one, and what those that are have in them to warn
+/// about. Nothing after input that can't be read is checked.
+pub fn check_ndjson_reader(reader: impl BufRead, options: &ParseOptions) -> (Vec<JsonError>, Warnings) {
+    let mut stream = TokenStream::with_options(reader, options);
+    let mut errors = Vec::new();
+    let mut warnings = Warnings::default();
+    while let Some(line) = stream.next_line_tokens() {
+        let tokens = match line {
+            Ok(tokens) => tokens,
+            Err(StreamError::Token(e, _) | StreamError::Skipped(e)) => {
+                errors.push(JsonError::Tokenize(e));
+                continue;
+            },
+            Err(StreamError::Read(e)) => {
+                errors.push(JsonError::Tokenize(e));
+                break;
+            },
+        };
+        // Only the end
+        if tokens.len() == 1 {
+            continue;
+        }
+        let mut parser = JsonParser::new(&tokens, options);
+        match parse_document(&mut parser) {
+            Ok(_) => warnings.append(&mut parser.warnings),
+            Err(e) => errors.push(JsonError::Parse(e)),
+        }
+    }
+    (errors, warnings)
+}
+
+/// The documents read from `reader` a line at a time within the limits of `options`, blank lines
+/// being passed over
+pub fn read_ndjson<R: BufRead>(reader: R, options: &ParseOptions) -> NdjsonReader<R> {
+    NdjsonReader { stream: TokenStream::with_options(reader, options), options: options.clone(), failed: false }
+}
+
+/// Each line's document and what it has in it to warn about, or the first error in a line that
+/// isn't one. Nothing comes after input that can't be read.
+#[derive(Debug)]
+pub struct NdjsonReader<R> {
+    stream: TokenStream<R>,
+    options: ParseOptions,
+    /// Whether the input couldn't be read
+    failed: bool,
+}
+
+impl<R: BufRead> Iterator for NdjsonReader<R> {
+    type Item = Result<(JsonValue, Warnings), JsonError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+        loop {
+            let tokens = match self.stream.next_line_tokens()? {
+                Ok(tokens) => tokens,
+                Err(StreamError::Token(e, _) | StreamError::Skipped(e)) => return Some(Err(JsonError::Tokenize(e))),
+                Err(StreamError::Read(e)) => {
+                    self.failed = true;
+                    return Some(Err(JsonError::Tokenize(e)));
+                },
+            };
+            // Only the end
+            if tokens.len() == 1 {
+                continue;
+            }
+            let mut parser = JsonParser::new(&tokens, &self.options);
+            return Some(match parse_document(&mut parser) {
+                Ok(value) => Ok((value, std::mem::take(&mut parser.warnings))),
+                Err(e) => Err(JsonError::Parse(e)),
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{to_string_compact, Span};
+
+    fn check(input: &[u8]) -> Vec<String> {
+        let (errors, _) = check_ndjson_reader(input, &ParseOptions::default());
+        errors.iter().map(|e| format!("{}: {}", e.position().unwrap(), e.message())).collect()
+    }
+
+    #[test]
+    fn check_ndjson() {
+        assert!(check(b"{\"a\": 1}\n[1, 2]\n\"three\"\n4\n").is_empty());
+        // Blank lines aren't records, and the last line needs no line break
+        assert!(check(b"\n{\"a\": 1}\r\n  \n\t[]").is_empty());
+        assert!(check(b"").is_empty());
+        assert_eq!(check(b"{\"a\": 1}\n{\"a\": }\n[1, 2]\n[1 2]\n"), [
+            "2:7: expected a value, found `}`",
+            "4:4: expected `,` or `]` after the array element, found number `2`",
+        ]);
+    }
+
+    #[test]
+    fn check_ndjson_records_end_with_their_line() {
+        // A document can't carry on into the next line
+        assert_eq!(check(b"{\"a\":\n1}\n"), [
+            "1:6: expected a value, found end of input",
+            "2:2: expected the end of input after the value, found `}`",
+        ]);
+        assert_eq!(check(b"[1] [2]\n"), ["1:5: expected the end of input after the value, found `[`"]);
+        // Nor can a string
+        assert_eq!(check(b"[\"a\n\"]\n"), ["1:2: unterminated string", "2:1: unterminated string"]);
+        // The rest of a line with a bad token in it is passed over
+        assert_eq!(check(b"[tru, fals]\n{}\n[nul]\n"), ["1:2: expected `true`, found `tru`", "3:2: expected `null`, found `nul`"]);
+    }
+
+    #[test]
+    fn check_ndjson_errors_point_into_the_whole_input() {
+        let (errors, warnings) = check_ndjson_reader(b"{\"a\": 1}\n{\"b\": 1, \"b\": 2}\n[}\n".as_slice(), &ParseOptions::default());
+        assert_eq!(errors[0].span(), Some(Span::new(27, 28)));
+        let duplicates = warnings.duplicates;
+        assert_eq!((duplicates[0].position.to_string(), duplicates[0].span), ("2:10".to_string(), Span::new(18, 21)));
+
+        let options = ParseOptions { max_depth: Some(1), ..ParseOptions::default() };
+        let (errors, _) = check_ndjson_reader(b"[1]\n[[1]]\n".as_slice(), &options);
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].position().unwrap().to_string(), "2:2");
+
+        // A byte order mark is only skipped at the start of the input
+        assert!(check(b"\xef\xbb\xbf{}\n{}\n").is_empty());
+        assert_eq!(check(b"{}\n\xef\xbb\xbf{}\n"), ["2:1: illegal character `\u{feff}`"]);
+        // Nothing's checked after input that can't be read
+        assert_eq!(check(b"[1]\n[\"caf\xe9\"]\n[2]\n"), ["2:6: invalid UTF-8, found byte 0xE9 where a character should start or go on"]);
+    }
+
+    #[test]
+    fn check_read_ndjson() {
+        let read: Vec<_> = read_ndjson(b"{\"a\": 1}\n\n[1 2]\n\"three\"\n[\"caf\xe9\"]\n4\n".as_slice(), &ParseOptions::default())
+            .map(|line| line.map(|(value, _)| to_string_compact(&value)).map_err(|e| e.position().unwrap().to_string()))
+            .collect();
+        assert_eq!(read, [Ok("{\"a\":1}".to_string()), Err("3:4".to_string()), Ok("\"three\"".to_string()), Err("5:6".to_string())]);
+        let (_, warnings) = read_ndjson(b"{\"b\": 1, \"b\": 2}".as_slice(), &ParseOptions::default()).next().unwrap().unwrap();
+        assert_eq!(warnings.duplicates.len(), 1);
+    }
+}
@@ -0,0 +1,174 @@
+// Typed numbers.
+// A JsonValue keeps each number as it's written, but a program reads it as an i64, a u64, or an
+// f64, and JSON puts no limit on how big or precise a number is. An integer too big for 64 bits
+// is read as a float, rounding it unless it's read as an arbitrary-precision integer instead, and
+// a float only keeps about 17 digits. The parser reads each number as it'd be typed, warning
+// about those that come out as something other than what's written.
+use crate::json5::number_value;
+use crate::{JsonValue, LineCol, Span, Spanned, Token};
+use num_bigint::BigInt;
+use std::fmt;
+
+/// A number as a program has it
+#[derive(Clone, Debug, PartialEq)]
+pub enum Number {
+    /// An integer that fits in an i64
+    Int(i64),
+    /// An integer too big for an i64 that fits in a u64
+    UInt(u64),
+    /// A number with a fraction or exponent, or an integer too big for 64 bits when it isn't read
+    /// as a BigInt
+    Float(f64),
+    /// An integer too big for 64 bits, when they're read exactly
+    Big(BigInt),
+}
+
+impl fmt::Display for Number {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Number::Int(n) => write!(f, "{}", n),
+            Number::UInt(n) => write!(f, "{}", n),
+            Number::Float(n) => write!(f, "{:?}", n),
+            Number::Big(n) => write!(f, "{}", n),
+        }
+    }
+}
+
+/// What reading a number as a Number loses
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Loss {
+    /// It's an integer too big for 64 bits, read as a float, or too big even for a float
+    Overflow,
+    /// It has more digits than a float keeps, so it's rounded
+    Precision,
+}
+
+/// Read a number as JSON writes it, an integer too big for 64 bits being a BigInt if `bignum` or
+/// a float otherwise, with what reading it loses, if anything. It's None if it isn't a number.
+pub fn read_number(number: &str, bignum: bool) -> Option<(Number, Option<Loss>)> {
+    if !number.contains(['.', 'e', 'E']) {
+        if let Ok(n) = number.parse() {
+            return Some((Number::Int(n), None));
+        }
+        if let Ok(n) = number.parse() {
+            return Some((Number::UInt(n), None));
+        }
+        if bignum {
+            return number.parse().ok().map(|n| (Number::Big(n), None));
+        }
+        return number.parse().ok().map(|n| (Number::Float(n), Some(Loss::Overflow)));
+    }
+    let float: f64 = number.parse().ok()?;
+    let loss = match float.is_finite() {
+        false => Some(Loss::Overflow),
+        true => (decimal(number) != decimal(&format!("{:e}", float))).then_some(Loss::Precision),
+    };
+    Some((Number::Float(float), loss))
+}
+
+/// The sign, significant digits, and exponent of a decimal number, which is the digits after a
+/// point times ten to the exponent, so two numbers are the same if these are. Zero has no digits
+/// and no sign. It's None if the exponent can't be read.
+fn decimal(number: &str) -> Option<(bool, String, i64)> {
+    let (negative, unsigned) = match number.strip_prefix('-') {
+        Some(unsigned) => (true, unsigned),
+        None => (false, number),
+    };
+    let (mantissa, exponent) = unsigned.split_once(['e', 'E']).unwrap_or((unsigned, "0"));
+    let (whole, fraction) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+    let digits = format!("{}{}", whole, fraction);
+    let significant = digits.trim_start_matches('0');
+    let leading = digits.len() - significant.len();
+    let significant = significant.trim_end_matches('0');
+    if significant.is_empty() {
+        return Some((false, String::new(), 0));
+    }
+    let exponent: i64 = exponent.parse().ok()?;
+    Some((negative, significant.to_string(), exponent + whole.len() as i64 - leading as i64))
+}
+
+/// A number that's read as something other than what's written
+#[derive(Clone, Debug, PartialEq)]
+pub struct NumberWarning {
+    /// The number as written
+    pub number: String,
+    /// What it's read as
+    pub read_as: Number,
+    pub loss: Loss,
+    pub position: LineCol,
+    /// The bytes of the number
+    pub span: Span,
+}
+
+impl NumberWarning {
+    /// What's wrong, without where
+    pub fn message(&self) -> String {
+        match (self.loss, &self.read_as) {
+            (Loss::Overflow, Number::Float(float)) if float.is_infinite() => format!("number `{}` is too big for a float", self.number),
+            (Loss::Overflow, _) => format!("integer `{}` is too big for 64 bits, so it's read as the float `{}`", self.number, self.read_as),
+            (Loss::Precision, _) => format!("number `{}` has more digits than a float keeps, so it's read as `{}`", self.number, self.read_as),
+        }
+    }
+}
+
+impl fmt::Display for NumberWarning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} at line {}, column {}", self.message(), self.position.line, self.position.column)
+    }
+}
+
+/// The warning about a number token, if reading it as a Number loses anything
+pub(crate) fn number_warning(token: &Spanned<Token>, bignum: bool) -> Option<NumberWarning> {
+    let Token::Number(raw) = &token.value else {
+        return None;
+    };
+    // A JSON5 number is read as the JSON number it's written as
+    let JsonValue::Number(number) = &number_value(raw) else {
+        return None;
+    };
+    let (read_as, loss) = read_number(number, bignum)?;
+    Some(NumberWarning { number: raw.to_string(), read_as, loss: loss?, position: token.position, span: token.span })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse_str_with, validate_reader, ParseOptions};
+    use std::io::Cursor;
+
+    #[test]
+    fn check_read_number() {
+        assert_eq!(read_number("-42", false), Some((Number::Int(-42), None)));
+        assert_eq!(read_number("18446744073709551615", false), Some((Number::UInt(u64::MAX), None)));
+        assert_eq!(read_number("0.1", false), Some((Number::Float(0.1), None)));
+        assert_eq!(read_number("1.50E+2", false), Some((Number::Float(150.0), None)));
+        assert_eq!(read_number("-0.0", false), Some((Number::Float(-0.0), None)));
+        assert_eq!(read_number("123456789012345678901234567890", false), Some((Number::Float(1.2345678901234568e29), Some(Loss::Overflow))));
+        let big: BigInt = "-123456789012345678901234567890".parse().unwrap();
+        assert_eq!(read_number("-123456789012345678901234567890", true), Some((Number::Big(big), None)));
+        assert_eq!(read_number("1e400", true), Some((Number::Float(f64::INFINITY), Some(Loss::Overflow))));
+        assert_eq!(read_number("3.14159265358979323846", false), Some((Number::Float(std::f64::consts::PI), Some(Loss::Precision))));
+        assert_eq!(read_number("1e-400", false), Some((Number::Float(0.0), Some(Loss::Precision))));
+        assert_eq!(read_number("0e99999999999999999999", false), Some((Number::Float(0.0), None)));
+        assert_eq!(read_number("number", false), None);
+    }
+
+    #[test]
+    fn check_number_warnings() {
+        let input = "[12345678901234567890123, 0.30000000000000000001, 1e999, 9007199254740993.0, 2.5]";
+        let (_, warnings) = parse_str_with(input, &ParseOptions::default()).unwrap();
+        let messages: Vec<_> = warnings.numbers.iter().map(NumberWarning::to_string).collect();
+        assert_eq!(messages, [
+            "integer `12345678901234567890123` is too big for 64 bits, so it's read as the float `1.2345678901234568e22` at line 1, column 2",
+            "number `0.30000000000000000001` has more digits than a float keeps, so it's read as `0.3` at line 1, column 27",
+            "number `1e999` is too big for a float at line 1, column 51",
+            "number `9007199254740993.0` has more digits than a float keeps, so it's read as `9007199254740992.0` at line 1, column 58",
+        ]);
+        assert_eq!(validate_reader(Cursor::new(input), &ParseOptions::default()).unwrap().numbers, warnings.numbers);
+        let options = ParseOptions { bignum: true, ..ParseOptions::default() };
+        let (value, warnings) = parse_str_with("[12345678901234567890123]", &options).unwrap();
+        assert!(warnings.is_empty());
+        let JsonValue::Array(elements) = &value else { panic!("the document is an array") };
+        assert_eq!(elements[0].to_number(true), Some(Number::Big("12345678901234567890123".parse().unwrap())));
+    }
+}
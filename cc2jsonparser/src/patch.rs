@@ -0,0 +1,204 @@
+// JSON Patch.
+// A patch is an array of operations applied one after another, each at a JSON Pointer: add,
+// remove, and replace a value, move or copy one from another pointer, and test that one is what
+// it's expected to be. If any of them fails, test included, the whole patch does and the document
+// is left as it was. `diff --json-patch` writes a patch like this from two documents.
+// Reference:  https://www.rfc-editor.org/rfc/rfc6902
+use crate::pointer::{array_index, location};
+use crate::{diff, JsonPointer, JsonValue};
+use std::fmt;
+
+/// Why a patch can't be applied, and which operation it is if it's one of them
+#[derive(Clone, Debug, PartialEq)]
+pub struct PatchError {
+    pub message: String,
+    /// The index of the operation in the patch
+    pub operation: Option<usize>,
+}
+
+impl fmt::Display for PatchError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.operation {
+            Some(i) => write!(f, "{} in operation {}", self.message, i),
+            None => f.write_str(&self.message),
+        }
+    }
+}
+
+impl std::error::Error for PatchError {}
+
+/// Apply each operation in the patch to the document in turn, or none of them if one fails
+pub fn apply_patch(document: &mut JsonValue, patch: &JsonValue) -> Result<(), PatchError> {
+    let JsonValue::Array(operations) = patch else {
+        return Err(PatchError { message: format!("a patch is an array of operations, not {}", patch.type_name()), operation: None });
+    };
+    let mut patched = document.clone();
+    for (i, operation) in operations.iter().enumerate() {
+        apply(&mut patched, operation).map_err(|message| PatchError { message, operation: Some(i) })?;
+    }
+    *document = patched;
+    Ok(())
+}
+
+/// Apply one operation to the document
+fn apply(document: &mut JsonValue, operation: &JsonValue) -> Result<(), String> {
+    let JsonValue::Object(members) = operation else {
+        return Err(format!("an operation is an object, not {}", operation.type_name()));
+    };
+    // Members it doesn't use are left alone, and with repeated keys the last one counts
+    let member = |key: &str| members.iter().rev().find(|(existing, _)| existing == key).map(|(_, value)| value);
+    let string = |key: &str| match member(key) {
+        Some(JsonValue::String(string)) => Ok(string.as_str()),
+        Some(other) => Err(format!("`{}` is {}, not a string", key, other.type_name())),
+        None => Err(format!("the operation has no `{}`", key)),
+    };
+    let pointer = |key: &str| JsonPointer::new(string(key)?).map_err(|e| e.message);
+    let value = || member("value").ok_or_else(|| "the operation has no `value`".to_string());
+    let path = pointer("path")?;
+    match string("op")? {
+        "add" => add(document, &path, value()?.clone()),
+        "remove" => remove(document, &path).map(drop),
+        "replace" => {
+            let replaced = path.resolve_mut(document).ok_or_else(|| format!("there's nothing at {} to replace", location(&path.to_string())))?;
+            *replaced = value()?.clone();
+            Ok(())
+        },
+        "move" => {
+            let from = pointer("from")?;
+            if from.contains(&path) {
+                return Err(format!("{} can't be moved into itself", location(&from.to_string())));
+            }
+            let moved = remove(document, &from)?;
+            add(document, &path, moved)
+        },
+        "copy" => {
+            let from = pointer("from")?;
+            let copied = from.resolve(document).ok_or_else(|| format!("there's nothing at {} to copy", location(&from.to_string())))?;
+            add(document, &path, copied.clone())
+        },
+        "test" => match path.resolve(document) {
+            Some(found) if diff(found, value()?).is_empty() => Ok(()),
+            Some(_) => Err(format!("the test failed, {} isn't the value it expects", location(&path.to_string()))),
+            None => Err(format!("the test failed, there's nothing at {}", location(&path.to_string()))),
+        },
+        other => Err(format!("unknown operation `{}`, expected add, remove, replace, move, copy, or test", other)),
+    }
+}
+
+/// Add the value at the path: the whole document for the empty path, setting a member of an
+/// object, or putting an element into an array before the index, `-` being after the last element
+fn add(document: &mut JsonValue, path: &JsonPointer, value: JsonValue) -> Result<(), String> {
+    let Some((parent, token)) = path.split_last() else {
+        *document = value;
+        return Ok(());
+    };
+    match parent.resolve_mut(document) {
+        Some(JsonValue::Object(members)) => match members.iter_mut().rev().find(|(key, _)| key == token) {
+            Some((_, existing)) => *existing = value,
+            None => members.push((token.to_string(), value)),
+        },
+        Some(JsonValue::Array(elements)) => {
+            let index = match token {
+                "-" => elements.len(),
+                _ => array_index(token)
+                    .filter(|&i| i <= elements.len())
+                    .ok_or_else(|| format!("{} has no index {} to add at", location(&parent.to_string()), token))?,
+            };
+            elements.insert(index, value);
+        },
+        Some(other) => return Err(format!("{} is {}, not an object or array to add to", location(&parent.to_string()), other.type_name())),
+        None => return Err(format!("there's nothing at {} to add to", location(&parent.to_string()))),
+    }
+    Ok(())
+}
+
+/// Take the value at the path out of the document, every member with its key if it's repeated
+fn remove(document: &mut JsonValue, path: &JsonPointer) -> Result<JsonValue, String> {
+    let nothing = || format!("there's nothing at {} to remove", location(&path.to_string()));
+    let Some((parent, token)) = path.split_last() else {
+        return Err("the whole document can't be removed, only replaced".to_string());
+    };
+    match parent.resolve_mut(document) {
+        Some(JsonValue::Object(members)) => {
+            let i = members.iter().rposition(|(key, _)| key == token).ok_or_else(nothing)?;
+            let (_, removed) = members.remove(i);
+            members.retain(|(key, _)| key != token);
+            Ok(removed)
+        },
+        Some(JsonValue::Array(elements)) => {
+            let i = array_index(token).filter(|&i| i < elements.len()).ok_or_else(nothing)?;
+            Ok(elements.remove(i))
+        },
+        _ => Err(nothing()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse_str, to_string_compact};
+
+    fn patched(document: &str, patch: &str) -> Result<String, String> {
+        let mut document = parse_str(document).unwrap();
+        apply_patch(&mut document, &parse_str(patch).unwrap()).map_err(|e| e.to_string())?;
+        Ok(to_string_compact(&document))
+    }
+
+    #[test]
+    fn check_apply_patch() {
+        // RFC 6902's Appendix A
+        let examples = [
+            (r#"{"foo":"bar"}"#, r#"[{"op":"add","path":"/baz","value":"qux"}]"#, r#"{"foo":"bar","baz":"qux"}"#),
+            (r#"{"foo":["bar","baz"]}"#, r#"[{"op":"add","path":"/foo/1","value":"qux"}]"#, r#"{"foo":["bar","qux","baz"]}"#),
+            (r#"{"baz":"qux","foo":"bar"}"#, r#"[{"op":"remove","path":"/baz"}]"#, r#"{"foo":"bar"}"#),
+            (r#"{"foo":["bar","qux","baz"]}"#, r#"[{"op":"remove","path":"/foo/1"}]"#, r#"{"foo":["bar","baz"]}"#),
+            (r#"{"baz":"qux","foo":"bar"}"#, r#"[{"op":"replace","path":"/baz","value":"boo"}]"#, r#"{"baz":"boo","foo":"bar"}"#),
+            (
+                r#"{"foo":{"bar":"baz","waldo":"fred"},"qux":{"corge":"grault"}}"#,
+                r#"[{"op":"move","from":"/foo/waldo","path":"/qux/thud"}]"#,
+                r#"{"foo":{"bar":"baz"},"qux":{"corge":"grault","thud":"fred"}}"#,
+            ),
+            (r#"{"foo":["all","grass","cows","eat"]}"#, r#"[{"op":"move","from":"/foo/1","path":"/foo/3"}]"#, r#"{"foo":["all","cows","eat","grass"]}"#),
+            (r#"{"baz":"qux","foo":["a",2,"c"]}"#, r#"[{"op":"test","path":"/baz","value":"qux"},{"op":"test","path":"/foo/1","value":2}]"#, r#"{"baz":"qux","foo":["a",2,"c"]}"#),
+            (r#"{"foo":"bar"}"#, r#"[{"op":"add","path":"/child","value":{"grandchild":{}}}]"#, r#"{"foo":"bar","child":{"grandchild":{}}}"#),
+            (r#"{"foo":"bar"}"#, r#"[{"op":"add","path":"/baz","value":"qux","xyz":123}]"#, r#"{"foo":"bar","baz":"qux"}"#),
+            (r#"{"/":9,"~1":10}"#, r#"[{"op":"test","path":"/~01","value":10}]"#, r#"{"/":9,"~1":10}"#),
+            (r#"{"foo":["bar"]}"#, r#"[{"op":"add","path":"/foo/-","value":["abc","def"]}]"#, r#"{"foo":["bar",["abc","def"]]}"#),
+        ];
+        for (document, patch, result) in examples {
+            assert_eq!(patched(document, patch).as_deref(), Ok(result), "{} patched with {}", document, patch);
+        }
+        assert_eq!(patched(r#"{"a": [1]}"#, r#"[{"op": "copy", "from": "/a", "path": "/b"}, {"op": "add", "path": "", "value": [{"c": 1.0}]}, {"op": "test", "path": "", "value": [{"c": 1}]}]"#).as_deref(), Ok(r#"[{"c":1.0}]"#));
+        assert_eq!(patched("[]", "[]").as_deref(), Ok("[]"));
+    }
+
+    #[test]
+    fn check_apply_patch_fails_for_operations_that_cannot_be_applied() {
+        let examples = [
+            (r#"{"baz":"qux"}"#, r#"[{"op":"test","path":"/baz","value":"bar"}]"#, "the test failed, `/baz` isn't the value it expects in operation 0"),
+            (r#"{"foo":"bar"}"#, r#"[{"op":"add","path":"/baz/bat","value":"qux"}]"#, "there's nothing at `/baz` to add to in operation 0"),
+            (r#"{"/":9,"~1":10}"#, r#"[{"op":"test","path":"/~01","value":"10"}]"#, "the test failed, `/~01` isn't the value it expects in operation 0"),
+            ("[1]", r#"[{"op":"add","path":"/2","value":3}]"#, "the document has no index 2 to add at in operation 0"),
+            ("[1]", r#"[{"op":"add","path":"/0/x","value":3}]"#, "`/0` is a number, not an object or array to add to in operation 0"),
+            (r#"{"a":{}}"#, r#"[{"op":"move","from":"/a","path":"/a/b"}]"#, "`/a` can't be moved into itself in operation 0"),
+            ("{}", r#"[{"op":"remove","path":""}]"#, "the whole document can't be removed, only replaced in operation 0"),
+            ("{}", r#"[{"op":"replace","path":"/a","value":1}]"#, "there's nothing at `/a` to replace in operation 0"),
+            ("{}", r#"[{"op":"test","path":"/a","value":1}]"#, "the test failed, there's nothing at `/a` in operation 0"),
+            ("{}", r#"[{"op":"add","path":"/a","value":1}, {"op":"copy","from":"/b","path":"/c"}]"#, "there's nothing at `/b` to copy in operation 1"),
+            ("{}", r#"[{"op":"add","path":"/a"}]"#, "the operation has no `value` in operation 0"),
+            ("{}", r#"[{"op":"delete","path":"/a"}]"#, "unknown operation `delete`, expected add, remove, replace, move, copy, or test in operation 0"),
+            ("{}", r#"[{"path":"/a"}]"#, "the operation has no `op` in operation 0"),
+            ("{}", r#"[{"op":"remove","path":"a"}]"#, "pointer `a` must be empty or start with `/` in operation 0"),
+            ("{}", r#"[{"op":1,"path":""}]"#, "`op` is a number, not a string in operation 0"),
+            ("{}", "[[]]", "an operation is an object, not an array in operation 0"),
+            ("{}", r#"{"op":"remove","path":"/a"}"#, "a patch is an array of operations, not an object"),
+        ];
+        for (document, patch, message) in examples {
+            assert_eq!(patched(document, patch), Err(message.to_string()), "{} patched with {}", document, patch);
+        }
+        // Nothing is changed when an operation fails
+        let mut document = parse_str(r#"{"a": 1}"#).unwrap();
+        assert!(apply_patch(&mut document, &parse_str(r#"[{"op": "remove", "path": "/a"}, {"op": "remove", "path": "/a"}]"#).unwrap()).is_err());
+        assert_eq!(to_string_compact(&document), r#"{"a":1}"#);
+    }
+}
@@ -0,0 +1,193 @@
+// JSON Pointers.
+// A pointer picks out one value in a document by the keys and indexes leading to it, each after a
+// `/`, so `/items/0/name` is the name of the first item and the empty pointer is the whole
+// document. `~1` stands for a `/` in a key and `~0` for a `~`.
+// Reference:  https://www.rfc-editor.org/rfc/rfc6901
+use crate::JsonValue;
+use std::{fmt, str::FromStr};
+
+/// A parsed JSON Pointer
+#[derive(Clone, Debug, PartialEq)]
+pub struct JsonPointer {
+    /// The keys and indexes, unescaped
+    tokens: Vec<String>,
+}
+
+/// A pointer that isn't valid
+#[derive(Clone, Debug, PartialEq)]
+pub struct PointerError {
+    pub message: String,
+}
+
+impl fmt::Display for PointerError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for PointerError {}
+
+impl JsonPointer {
+    pub fn new(pointer: &str) -> Result<JsonPointer, PointerError> {
+        if pointer.is_empty() {
+            return Ok(JsonPointer { tokens: Vec::new() });
+        }
+        let Some(rest) = pointer.strip_prefix('/') else {
+            return Err(PointerError { message: format!("pointer `{}` must be empty or start with `/`", pointer) });
+        };
+        let tokens = rest.split('/').map(unescape).collect::<Option<_>>().ok_or_else(|| PointerError {
+            message: format!("pointer `{}` has a `~` not followed by 0 or 1", pointer),
+        })?;
+        Ok(JsonPointer { tokens })
+    }
+
+    /// The value the pointer points to in `document`, if there is one
+    pub fn resolve<'a>(&self, document: &'a JsonValue) -> Option<&'a JsonValue> {
+        self.tokens.iter().try_fold(document, |value, token| match value {
+            // With repeated keys the last one counts
+            JsonValue::Object(members) => members.iter().rev().find(|(key, _)| key == token).map(|(_, value)| value),
+            JsonValue::Array(elements) => array_index(token).and_then(|i| elements.get(i)),
+            _ => None,
+        })
+    }
+
+    /// The value the pointer points to in `document` to change, if there is one
+    pub fn resolve_mut<'a>(&self, document: &'a mut JsonValue) -> Option<&'a mut JsonValue> {
+        let mut value = document;
+        for token in &self.tokens {
+            value = match value {
+                JsonValue::Object(members) => &mut members.iter_mut().rev().find(|(key, _)| key == token)?.1,
+                JsonValue::Array(elements) => elements.get_mut(array_index(token)?)?,
+                _ => return None,
+            };
+        }
+        Some(value)
+    }
+
+    /// The pointer to the object or array holding what this points to, and the key or index it's
+    /// at in it, or None for the whole document
+    pub fn split_last(&self) -> Option<(JsonPointer, &str)> {
+        let (last, parent) = self.tokens.split_last()?;
+        Some((JsonPointer { tokens: parent.to_vec() }, last))
+    }
+
+    /// Whether what `other` points to is inside what this points to
+    pub fn contains(&self, other: &JsonPointer) -> bool {
+        other.tokens.len() > self.tokens.len() && other.tokens.starts_with(&self.tokens)
+    }
+}
+
+impl FromStr for JsonPointer {
+    type Err = PointerError;
+
+    fn from_str(s: &str) -> Result<JsonPointer, PointerError> {
+        JsonPointer::new(s)
+    }
+}
+
+impl fmt::Display for JsonPointer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for token in &self.tokens {
+            write!(f, "/{}", escape(token))?;
+        }
+        Ok(())
+    }
+}
+
+/// The token as written in a pointer, with `~` and `/` escaped
+pub(crate) fn escape(token: &str) -> String {
+    token.replace('~', "~0").replace('/', "~1")
+}
+
+/// Where the pointer points, for a person to read
+pub(crate) fn location(pointer: &str) -> String {
+    match pointer {
+        "" => "the document".to_string(),
+        _ => format!("`{}`", pointer),
+    }
+}
+
+/// The token with `~1` and `~0` unescaped, or None if it has some other `~`
+fn unescape(token: &str) -> Option<String> {
+    let mut unescaped = String::with_capacity(token.len());
+    let mut chars = token.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '~' => match chars.next() {
+                Some('0') => unescaped.push('~'),
+                Some('1') => unescaped.push('/'),
+                _ => return None,
+            },
+            c => unescaped.push(c),
+        }
+    }
+    Some(unescaped)
+}
+
+/// The index a token stands for in an array. `-`, the element after the last, is never there to
+/// point to, and indexes can't have leading zeros.
+pub(crate) fn array_index(token: &str) -> Option<usize> {
+    let digits = token.bytes().all(|b| b.is_ascii_digit());
+    if !digits || token.is_empty() || (token.len() > 1 && token.starts_with('0')) {
+        return None;
+    }
+    token.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse_str, to_string_compact};
+
+    #[test]
+    fn check_resolve() {
+        // The examples from the RFC
+        let document = parse_str(
+            r#"{"foo": ["bar", "baz"], "": 0, "a/b": 1, "c%d": 2, "e^f": 3, "g|h": 4, "i\\j": 5, "k\"l": 6, " ": 7, "m~n": 8}"#,
+        ).unwrap();
+        let resolve = |pointer| JsonPointer::new(pointer).unwrap().resolve(&document).map(to_string_compact);
+
+        assert_eq!(resolve(""), Some(to_string_compact(&document)));
+        assert_eq!(resolve("/foo").as_deref(), Some(r#"["bar","baz"]"#));
+        assert_eq!(resolve("/foo/0").as_deref(), Some(r#""bar""#));
+        for (pointer, expected) in [("/", "0"), ("/a~1b", "1"), ("/c%d", "2"), ("/e^f", "3"), ("/g|h", "4"), ("/i\\j", "5"),
+                ("/k\"l", "6"), ("/ ", "7"), ("/m~0n", "8")] {
+            assert_eq!(resolve(pointer).as_deref(), Some(expected), "{}", pointer);
+        }
+
+        assert_eq!(resolve("/foo/2"), None);
+        assert_eq!(resolve("/foo/-"), None);
+        assert_eq!(resolve("/foo/01"), None);
+        assert_eq!(resolve("/foo/+1"), None);
+        assert_eq!(resolve("/foo/0/x"), None);
+        assert_eq!(resolve("/bar"), None);
+    }
+
+    #[test]
+    fn check_resolve_mut() {
+        let mut document = parse_str(r#"{"a": [1, {"b": 2}], "a": [3]}"#).unwrap();
+        *JsonPointer::new("/a/0").unwrap().resolve_mut(&mut document).unwrap() = JsonValue::Null;
+        assert_eq!(to_string_compact(&document), r#"{"a":[1,{"b":2}],"a":[null]}"#);
+        assert!(JsonPointer::new("/a/1").unwrap().resolve_mut(&mut document).is_none());
+
+        let pointer = JsonPointer::new("/a/0").unwrap();
+        let (parent, last) = pointer.split_last().unwrap();
+        assert_eq!((parent.to_string().as_str(), last), ("/a", "0"));
+        assert!(parent.contains(&pointer) && !pointer.contains(&parent) && !pointer.contains(&pointer));
+        assert_eq!(JsonPointer::new("").unwrap().split_last(), None);
+    }
+
+    #[test]
+    fn check_display() {
+        for pointer in ["", "/", "/foo/0", "/a~1b/m~0n", "/~01"] {
+            assert_eq!(JsonPointer::new(pointer).unwrap().to_string(), pointer);
+        }
+    }
+
+    #[test]
+    fn check_pointer_errors() {
+        assert_eq!(JsonPointer::new("foo").unwrap_err().message, "pointer `foo` must be empty or start with `/`");
+        assert_eq!(JsonPointer::new("/a~2").unwrap_err().message, "pointer `/a~2` has a `~` not followed by 0 or 1");
+        assert_eq!(JsonPointer::new("/a~").unwrap_err().message, "pointer `/a~` has a `~` not followed by 0 or 1");
+    }
+}
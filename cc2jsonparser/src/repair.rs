@@ -0,0 +1,202 @@
+// Repairing almost-JSON.
+// JSON written by hand or cut from JavaScript often has trailing commas, strings in single quotes,
+// keys without quotes, and comments in it, or ends before the objects and arrays in it are closed.
+// The input is read as JSON5, which has all of those but the last, and each of them is noted
+// where it is as a repair; objects and arrays left open at the end are closed, a key left without
+// a value there getting null. The value it parses to is plain JSON, to be written back out by the
+// serializer.
+use crate::json5::{key_name, number_value};
+use crate::{parse_all, tokenize_str, tokenize_str_with, JsonError, JsonValue, LineCol, ParseOptions, Spanned, Token, Warnings};
+use cc_diagnostics::line_col;
+use std::fmt;
+
+/// One thing about the input that isn't JSON and how it was made so, at the line and column of the
+/// input it's about
+#[derive(Clone, Debug, PartialEq)]
+pub struct Repair {
+    pub message: String,
+    pub position: LineCol,
+}
+
+impl fmt::Display for Repair {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} at line {}, column {}", self.message, self.position.line, self.position.column)
+    }
+}
+
+/// What the input was repaired into, in order of where in the input each repair is
+#[derive(Clone, Debug, PartialEq)]
+pub struct Repaired {
+    pub value: JsonValue,
+    pub repairs: Vec<Repair>,
+    pub warnings: Warnings,
+}
+
+/// Parse input that's meant to be JSON within the limits of `options`, repairing what's commonly
+/// wrong with it. Anything else wrong with it, like a missing comma or a bracket closing the wrong
+/// thing, is an error as it is for JSON5.
+pub fn repair_str(input: &str, options: &ParseOptions) -> Result<Repaired, JsonError> {
+    let options = ParseOptions { json5: true, ..options.clone() };
+    let tokens = tokenize_str_with(input, &options).map_err(JsonError::Tokenize)?;
+    let mut repairs = Vec::new();
+    let mut repaired: Vec<Spanned<Token>> = Vec::with_capacity(tokens.len());
+    // The objects and arrays still open, whether each is an object, and where it's opened
+    let mut open: Vec<(bool, LineCol)> = Vec::new();
+    let mut last_was_key = false;
+    // A byte order mark is skipped before any token, when it's allowed
+    let mut end = if input.starts_with('\u{feff}') { '\u{feff}'.len_utf8() } else { 0 };
+    for token in &tokens {
+        skipped(input, end, token.span.start, &mut repairs);
+        end = token.span.end;
+        let mut repair = |message: String| repairs.push(Repair { message, position: token.position });
+        let expects_key = match repaired.last().map(|token| &token.value) {
+            Some(Token::LeftBrace) => true,
+            Some(Token::Comma) => open.last().is_some_and(|&(object, _)| object),
+            _ => false,
+        };
+        match &token.value {
+            Token::LeftBrace => open.push((true, token.position)),
+            Token::LeftBracket => open.push((false, token.position)),
+            Token::RightBrace | Token::RightBracket => {
+                if let Some(comma) = repaired.pop_if(|token| token.value == Token::Comma) {
+                    repairs.push(trailing_comma(&comma));
+                }
+                open.pop();
+            },
+            Token::Eof if !open.is_empty() => {
+                let at = |value| Spanned { value, position: token.position, span: token.span };
+                match repaired.last().map(|token| &token.value) {
+                    Some(Token::Comma) => {
+                        let comma = repaired.pop().expect("there's a comma at the end");
+                        repairs.push(trailing_comma(&comma));
+                    },
+                    Some(Token::Colon) => {
+                        repaired.push(at(Token::Null));
+                        repair("gave the key at the end null, as it has no value".to_string());
+                    },
+                    Some(_) if last_was_key => {
+                        repaired.extend([at(Token::Colon), at(Token::Null)]);
+                        repair("gave the key at the end null, as it has no value".to_string());
+                    },
+                    _ => {},
+                }
+                for &(object, position) in open.iter().rev() {
+                    let (close, what) = match object {
+                        true => (Token::RightBrace, "object"),
+                        false => (Token::RightBracket, "array"),
+                    };
+                    repaired.push(at(close));
+                    repairs.push(Repair { message: format!("closed the {} left open at the end", what), position });
+                }
+            },
+            value if expects_key && !matches!(value, Token::String(_)) => {
+                if let Some(name) = key_name(value) {
+                    repair(format!("put quotes around the key `{}`", name));
+                }
+            },
+            Token::String(raw) if raw.starts_with('\'') => repair("put the string in double quotes rather than single".to_string()),
+            Token::String(raw) if !is_json_string(raw) => repair("wrote the string with escapes JSON has instead".to_string()),
+            Token::Number(raw) => match &number_value(raw) {
+                JsonValue::Number(number) if number == raw.as_ref() => {},
+                JsonValue::Number(number) => repair(format!("wrote `{}` as `{}`", raw, number)),
+                _ => repair(format!("wrote `{}` as null, JSON having no way to write it", raw)),
+            },
+            _ => {},
+        }
+        repaired.push(token.clone());
+        last_was_key = expects_key;
+    }
+    let (value, warnings) = parse_all(&repaired, &options)?;
+    repairs.sort_by_key(|repair| repair.position);
+    Ok(Repaired { value, repairs, warnings })
+}
+
+fn trailing_comma(comma: &Spanned<Token>) -> Repair {
+    Repair { message: "removed the trailing comma".to_string(), position: comma.position }
+}
+
+/// Whether a string token is one JSON has, quotes and escapes included
+fn is_json_string(raw: &str) -> bool {
+    matches!(tokenize_str(raw).as_deref(), Ok([Spanned { value: Token::String(_), .. }, _]))
+}
+
+/// Note the comments between the token ending at byte `start` and the one starting at `end`, and
+/// the whitespace there JSON doesn't have
+fn skipped(input: &str, start: usize, end: usize, repairs: &mut Vec<Repair>) {
+    let mut chars = input[start..end].char_indices().peekable();
+    while let Some((i, ch)) = chars.next() {
+        let message = match ch {
+            ' ' | '\t' | '\n' | '\r' => continue,
+            '/' => {
+                match chars.next() {
+                    Some((_, '*')) => {
+                        let mut star = false;
+                        for (_, ch) in chars.by_ref() {
+                            if star && ch == '/' {
+                                break;
+                            }
+                            star = ch == '*';
+                        }
+                    },
+                    _ => while chars.next_if(|&(_, ch)| ch != '\n').is_some() {},
+                }
+                "removed the comment"
+            },
+            _ => {
+                while chars.next_if(|&(_, ch)| !matches!(ch, ' ' | '\t' | '\n' | '\r' | '/')).is_some() {}
+                "removed whitespace JSON doesn't have"
+            },
+        };
+        repairs.push(Repair { message: message.to_string(), position: line_col(input, start + i) });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::to_string_compact;
+
+    fn repaired(input: &str) -> (String, Vec<String>) {
+        let repaired = repair_str(input, &ParseOptions::default()).unwrap();
+        (to_string_compact(&repaired.value), repaired.repairs.iter().map(Repair::to_string).collect())
+    }
+
+    #[test]
+    fn check_repair_str() {
+        let input = "{\n  name: 'app', // the name\n  \"tags\": ['a', \"b\",],\n  size: 0x10,\n  /* more */ \"deps\": {\"x\": 1,\n";
+        assert_eq!(repaired(input), (r#"{"name":"app","tags":["a","b"],"size":16,"deps":{"x":1}}"#.to_string(), vec![
+            "closed the object left open at the end at line 1, column 1".to_string(),
+            "put quotes around the key `name` at line 2, column 3".to_string(),
+            "put the string in double quotes rather than single at line 2, column 9".to_string(),
+            "removed the comment at line 2, column 16".to_string(),
+            "put the string in double quotes rather than single at line 3, column 12".to_string(),
+            "removed the trailing comma at line 3, column 20".to_string(),
+            "put quotes around the key `size` at line 4, column 3".to_string(),
+            "wrote `0x10` as `16` at line 4, column 9".to_string(),
+            "removed the comment at line 5, column 3".to_string(),
+            "closed the object left open at the end at line 5, column 22".to_string(),
+            "removed the trailing comma at line 5, column 29".to_string(),
+        ]));
+        assert_eq!(repaired(r#"{"a": [1, 2]}"#), (r#"{"a":[1,2]}"#.to_string(), Vec::new()));
+    }
+
+    #[test]
+    fn check_repair_str_at_the_end() {
+        assert_eq!(repaired("[1, [2").0, "[1,[2]]");
+        assert_eq!(repaired("[").0, "[]");
+        assert_eq!(repaired(r#"{"a": 1, "b":"#), (r#"{"a":1,"b":null}"#.to_string(), vec![
+            "closed the object left open at the end at line 1, column 1".to_string(),
+            "gave the key at the end null, as it has no value at line 1, column 14".to_string(),
+        ]));
+        assert_eq!(repaired("{true").0, r#"{"true":null}"#);
+        assert_eq!(repaired("['it\\'s', NaN]").0, r#"["it's",null]"#);
+    }
+
+    #[test]
+    fn check_repair_str_fails_for_what_it_cannot_repair() {
+        assert!(repair_str("[1 2]", &ParseOptions::default()).is_err());
+        assert!(repair_str("{\"a\": 1]", &ParseOptions::default()).is_err());
+        assert!(repair_str("[\"open", &ParseOptions::default()).is_err());
+        assert_eq!(repair_str("", &ParseOptions::default()), Err(JsonError::Empty));
+    }
+}
@@ -0,0 +1,232 @@
+// An interactive prompt for trying out JSON.
+// Each snippet typed or pasted in is parsed as soon as it's whole, going on over as many lines as
+// it takes, and either written back out or shown with what's wrong with it, the line quoted with a
+// caret under where it goes wrong, for seeing how the grammar works one snippet at a time. Lines
+// starting with `:` are commands choosing how the snippets are shown, or setting a query to pick
+// values out of them. At a terminal the lines are read with the editor in editor.rs, so they can
+// be edited as they're typed and those entered before brought back with the arrow keys.
+use crate::editor::{read_line, Editor};
+use crate::{parse_str_with, tokenize_str_with, write_json, Filter, JsonError, JsonPath, JsonPointer, JsonValue, ParseOptions, Token, WriteOptions};
+use cc_diagnostics::Diagnostic;
+use std::io::{self, BufRead, Write};
+
+const PROMPT: &str = "json> ";
+
+/// The prompt for the rest of a snippet that isn't whole yet
+const CONTINUED: &str = "  ... ";
+
+const HELP: &str = "\
+Type or paste a JSON snippet to check it, over as many lines as it takes. A blank line ends one
+that isn't whole. Commands:
+  :pretty           write snippets indented, as at the start
+  :compact          write snippets on one line
+  :tokens           list the tokens of snippets instead
+  :filter EXPR      write what this jq filter picks out of snippets
+  :path QUERY       write what this JSONPath query selects from snippets
+  :pointer POINTER  write what this JSON Pointer points to in snippets
+  :all              write the whole of snippets again, after a query
+  :help             show this
+  :quit             stop, as the end of input does
+";
+
+/// How a snippet that parses is shown
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Show {
+    Pretty,
+    Compact,
+    Tokens,
+}
+
+/// What's picked out of each snippet to show
+enum Query {
+    Filter(Filter),
+    Path(JsonPath),
+    Pointer(JsonPointer),
+}
+
+struct Repl<'a> {
+    options: &'a ParseOptions,
+    color: bool,
+    show: Show,
+    query: Option<Query>,
+    /// The lines of a snippet read so far that isn't whole yet
+    pending: String,
+}
+
+/// Read snippets and commands from `input` until it ends or `:quit`, writing what's made of each to
+/// `out`, with prompts if `interactive`
+pub(crate) fn run(mut input: impl BufRead, out: &mut impl Write, options: &ParseOptions, interactive: bool, color: bool) -> io::Result<()> {
+    let mut repl = Repl { options, color, show: Show::Pretty, query: None, pending: String::new() };
+    let mut editor = interactive.then(Editor::default);
+    loop {
+        let line = match &mut editor {
+            Some(editor) => editor.read_line(if repl.pending.is_empty() { PROMPT } else { CONTINUED }, &mut input, out)?,
+            None => read_line(&mut input)?,
+        };
+        let Some(line) = line else { break };
+        if !repl.line(&line, out)? {
+            return Ok(());
+        }
+    }
+    // Whatever's left of a snippet can't be whole now
+    if !repl.pending.is_empty() {
+        repl.snippet(out, true)?;
+    }
+    // Leave the prompt line tidy when the user ends input with Ctrl-D
+    if interactive {
+        writeln!(out)?;
+    }
+    Ok(())
+}
+
+impl Repl<'_> {
+    /// Take in one line, giving whether to go on to the next
+    fn line(&mut self, line: &str, out: &mut impl Write) -> io::Result<bool> {
+        if self.pending.is_empty() {
+            if line.trim().is_empty() {
+                return Ok(true);
+            }
+            if let Some(command) = line.trim_start().strip_prefix(':') {
+                return self.command(command.trim(), out);
+            }
+        }
+        let ended = line.trim().is_empty();
+        self.pending.push_str(line);
+        self.pending.push('\n');
+        self.snippet(out, ended)?;
+        Ok(true)
+    }
+
+    /// Show the snippet read so far if it's whole, or what's wrong with it, unless it only needs
+    /// more lines and it hasn't `ended`
+    fn snippet(&mut self, out: &mut impl Write, ended: bool) -> io::Result<()> {
+        let value = match parse_str_with(&self.pending, self.options) {
+            Ok((value, _)) => value,
+            Err(JsonError::Parse(e)) if e.found == Token::Eof && e.max_depth.is_none() && !ended => return Ok(()),
+            Err(e) => {
+                let diagnostic = match e.span() {
+                    Some(span) => Diagnostic::error(e.message()).with_span(span),
+                    None => Diagnostic::error(e.message()),
+                };
+                let source = std::mem::take(&mut self.pending);
+                return out.write_all(diagnostic.render("snippet", &source, self.color).as_bytes());
+            },
+        };
+        let source = std::mem::take(&mut self.pending);
+        if self.show == Show::Tokens {
+            let tokens = tokenize_str_with(&source, self.options).expect("the snippet parsed, so its tokens can be read");
+            for token in tokens.iter().filter(|token| token.value != Token::Eof) {
+                writeln!(out, "{}  {}", token.position, token.value)?;
+            }
+            return Ok(());
+        }
+        let values = match &self.query {
+            Some(Query::Filter(filter)) => match filter.apply(&value) {
+                Ok(values) => values,
+                Err(e) => return out.write_all(Diagnostic::error(e.message).render("filter", "", self.color).as_bytes()),
+            },
+            Some(Query::Path(path)) => path.query(&value),
+            Some(Query::Pointer(pointer)) => match pointer.resolve(&value) {
+                Some(value) => vec![value],
+                None => return writeln!(out, "nothing at pointer `{}`", pointer),
+            },
+            None => vec![&value],
+        };
+        let indent = match self.show {
+            Show::Compact => None,
+            _ => Some("  "),
+        };
+        for value in values {
+            write_value(out, value, WriteOptions { indent, color: self.color })?;
+        }
+        Ok(())
+    }
+
+    /// Run a command, the line after its `:`, giving whether to go on
+    fn command(&mut self, command: &str, out: &mut impl Write) -> io::Result<bool> {
+        let (name, argument) = command.split_once(char::is_whitespace).map_or((command, ""), |(name, argument)| (name, argument.trim()));
+        let query = match (name, argument) {
+            ("pretty", "") => {
+                self.show = Show::Pretty;
+                return Ok(true);
+            },
+            ("compact", "") => {
+                self.show = Show::Compact;
+                return Ok(true);
+            },
+            ("tokens", "") => {
+                self.show = Show::Tokens;
+                return Ok(true);
+            },
+            ("all", "") => {
+                self.query = None;
+                return Ok(true);
+            },
+            ("help", "") => {
+                out.write_all(HELP.as_bytes())?;
+                return Ok(true);
+            },
+            ("quit" | "q", "") => return Ok(false),
+            ("filter" | "path" | "pointer", "") => Err(format!(":{} needs something to query with", name)),
+            ("filter", filter) => Filter::new(filter).map(Query::Filter).map_err(|e| e.message),
+            ("path", path) => JsonPath::new(path).map(Query::Path).map_err(|e| e.message),
+            ("pointer", pointer) => JsonPointer::new(pointer).map(Query::Pointer).map_err(|e| e.message),
+            ("pretty" | "compact" | "tokens" | "all" | "help" | "quit" | "q", _) => Err(format!(":{} doesn't take anything after it", name)),
+            _ => Err(format!("unknown command `:{}`, :help lists them", name)),
+        };
+        match query {
+            Ok(query) => self.query = Some(query),
+            Err(message) => out.write_all(Diagnostic::error(message).render("command", "", self.color).as_bytes())?,
+        }
+        Ok(true)
+    }
+}
+
+/// Write a value and the line break after it
+fn write_value(out: &mut impl Write, value: &JsonValue, options: WriteOptions) -> io::Result<()> {
+    write_json(out, value, options)?;
+    writeln!(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn session(input: &str) -> String {
+        let mut out = Vec::new();
+        run(Cursor::new(input), &mut out, &ParseOptions::default(), false, false).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn check_run() {
+        assert_eq!(session("{\"a\": [1,\n2]}\n:compact\n[true, null]\n\n  \n\"text\"\n"), "{\n  \"a\": [\n    1,\n    2\n  ]\n}\n[true,null]\n\"text\"\n");
+        assert_eq!(session("[1,\n\n:compact\n"), "error: expected a value, found end of input\n --> snippet:2:1\n  |\n2 | \n  | ^\n");
+        assert_eq!(session("{\"a\" 1}\n"), "\
+error: expected `:` after the object key, found number `1`
+ --> snippet:1:6
+  |
+1 | {\"a\" 1}
+  |      ^
+");
+        // A snippet left open at the end of the input isn't waited on
+        assert!(session("[1, 2").starts_with("error: expected `,` or `]` after the array element, found end of input\n"));
+        assert_eq!(session(":quit\n[1]\n"), "");
+    }
+
+    #[test]
+    fn check_run_commands() {
+        let input = ":compact\n:filter .items[]\n{\"items\": [1, {\"b\": 2}]}\n:pointer /items/1/b\n{\"items\": [1, {\"b\": 2}]}\n[]\n:all\n:tokens\n{\"a\": -1}\n";
+        assert_eq!(session(input), "1\n{\"b\":2}\n2\nnothing at pointer `/items/1/b`\n1:1  `{`\n1:2  string \"a\"\n1:5  `:`\n1:7  number `-1`\n1:9  `}`\n");
+        assert_eq!(session(":path\n:nope\n:pretty now\n"), "\
+error: :path needs something to query with
+ --> command
+error: unknown command `:nope`, :help lists them
+ --> command
+error: :pretty doesn't take anything after it
+ --> command
+");
+        assert!(session(":help\n").starts_with("Type or paste a JSON snippet"));
+    }
+}
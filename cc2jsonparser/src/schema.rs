@@ -0,0 +1,473 @@
+// JSON Schema validation.
+// A schema is a JSON document describing what other documents, instances, should look like:
+//
+//   {"type": "object", "required": ["name"], "properties": {"age": {"type": "integer", "minimum": 0}}}
+//
+// This supports the keywords of draft 2020-12 that check a value by itself: type, enum, const,
+// minimum, maximum, exclusiveMinimum, exclusiveMaximum, multipleOf, minLength, maxLength,
+// minItems, maxItems, uniqueItems, minProperties, maxProperties, required, properties,
+// additionalProperties, and items. A schema using any keyword that combines or refers to other
+// schemas, such as $ref or anyOf, is rejected rather than checked only partly, and other keywords,
+// like title, are ignored as the spec says.
+// Every way an instance doesn't match is found, each with the JSON Pointer to the value at fault
+// and to the schema keyword it broke.
+// Reference:  https://json-schema.org/draft/2020-12/json-schema-validation
+use crate::{pointer, to_string_compact, JsonValue};
+use std::fmt;
+
+/// Keywords that would need more than this supports to check
+const UNSUPPORTED: &[&str] = &[
+    "$ref", "$dynamicRef", "allOf", "anyOf", "oneOf", "not", "if", "then", "else", "dependentSchemas", "dependentRequired",
+    "prefixItems", "contains", "minContains", "maxContains", "patternProperties", "propertyNames", "pattern",
+    "unevaluatedItems", "unevaluatedProperties",
+];
+
+/// A compiled schema
+#[derive(Clone, Debug, PartialEq)]
+pub struct Schema {
+    root: Node,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Node {
+    /// `true` allows anything, `false` nothing
+    Bool(bool, String),
+    Keywords(Box<Keywords>),
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+struct Keywords {
+    /// Where this schema is in the schema document
+    path: String,
+    types: Option<Vec<Type>>,
+    enum_values: Option<Vec<JsonValue>>,
+    constant: Option<JsonValue>,
+    minimum: Option<f64>,
+    maximum: Option<f64>,
+    exclusive_minimum: Option<f64>,
+    exclusive_maximum: Option<f64>,
+    multiple_of: Option<f64>,
+    min_length: Option<usize>,
+    max_length: Option<usize>,
+    min_items: Option<usize>,
+    max_items: Option<usize>,
+    unique_items: bool,
+    min_properties: Option<usize>,
+    max_properties: Option<usize>,
+    required: Vec<String>,
+    properties: Vec<(String, Node)>,
+    additional_properties: Option<Node>,
+    items: Option<Node>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Type {
+    Null,
+    Boolean,
+    Object,
+    Array,
+    Number,
+    Integer,
+    String,
+}
+
+impl Type {
+    const ALL: [(Type, &'static str); 7] = [
+        (Type::Null, "null"),
+        (Type::Boolean, "boolean"),
+        (Type::Object, "object"),
+        (Type::Array, "array"),
+        (Type::Number, "number"),
+        (Type::Integer, "integer"),
+        (Type::String, "string"),
+    ];
+
+    fn name(self) -> &'static str {
+        Type::ALL.iter().find(|(t, _)| *t == self).map(|(_, name)| *name).unwrap()
+    }
+
+    /// The type of the value, a number never being called an integer since `matches` checks for those
+    fn of(value: &JsonValue) -> Type {
+        match value {
+            JsonValue::Null => Type::Null,
+            JsonValue::Bool(_) => Type::Boolean,
+            JsonValue::Object(_) => Type::Object,
+            JsonValue::Array(_) => Type::Array,
+            JsonValue::Number(_) => Type::Number,
+            JsonValue::String(_) => Type::String,
+        }
+    }
+
+    fn matches(self, value: &JsonValue) -> bool {
+        match self {
+            // 1.0 counts as an integer
+            Type::Integer => value.as_f64().is_some_and(|n| n.fract() == 0.0),
+            _ => Type::of(value) == self,
+        }
+    }
+}
+
+/// A schema document that isn't a schema this can check with
+#[derive(Clone, Debug, PartialEq)]
+pub struct SchemaError {
+    /// The JSON Pointer to where it's wrong
+    pub schema_path: String,
+    pub message: String,
+}
+
+impl fmt::Display for SchemaError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {}", self.message, location(&self.schema_path, "schema"))
+    }
+}
+
+impl std::error::Error for SchemaError {}
+
+/// One way an instance doesn't match a schema
+#[derive(Clone, Debug, PartialEq)]
+pub struct Violation {
+    /// The JSON Pointer to the value that doesn't match
+    pub instance_path: String,
+    /// The JSON Pointer to the keyword it doesn't match
+    pub schema_path: String,
+    pub message: String,
+}
+
+impl Violation {
+    /// Where the value is in the instance, and the keyword in the schema, for a person to read
+    pub fn locations(&self) -> [String; 2] {
+        [location(&self.instance_path, "document"), location(&self.schema_path, "schema")]
+    }
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let [instance, schema] = self.locations();
+        write!(f, "{} {}, {}", self.message, instance, schema)
+    }
+}
+
+/// Where a JSON pointer points in `document`
+fn location(pointer: &str, document: &str) -> String {
+    match pointer {
+        "" => format!("at the root of the {}", document),
+        _ => format!("at `{}` in the {}", pointer, document),
+    }
+}
+
+impl Schema {
+    pub fn new(schema: &JsonValue) -> Result<Schema, SchemaError> {
+        Ok(Schema { root: compile(schema, String::new())? })
+    }
+
+    /// Every way `instance` doesn't match the schema, in the order they're found
+    pub fn validate(&self, instance: &JsonValue) -> Vec<Violation> {
+        let mut violations = Vec::new();
+        check(&self.root, instance, "", &mut violations);
+        violations
+    }
+}
+
+fn compile(schema: &JsonValue, path: String) -> Result<Node, SchemaError> {
+    let members = match schema {
+        JsonValue::Bool(allowed) => return Ok(Node::Bool(*allowed, path)),
+        JsonValue::Object(members) => members,
+        _ => return Err(SchemaError { schema_path: path, message: "expected a schema, an object or a boolean".to_string() }),
+    };
+    let mut keywords = Keywords { path: path.clone(), ..Keywords::default() };
+    for (keyword, value) in members {
+        let path = format!("{}/{}", path, pointer::escape(keyword));
+        let error = |expected: &str| SchemaError { schema_path: path.clone(), message: format!("expected {}", expected) };
+        let number = || value.as_f64().ok_or_else(|| error("a number"));
+        let count = || {
+            value.as_f64().filter(|n| *n >= 0.0 && n.fract() == 0.0).map(|n| n as usize).ok_or_else(|| error("a non-negative integer"))
+        };
+
+        match keyword.as_str() {
+            "type" => {
+                let names = match value {
+                    JsonValue::Array(names) => names.iter().collect(),
+                    name => vec![name],
+                };
+                let types = names.into_iter().map(|name| match name {
+                    JsonValue::String(name) => Type::ALL.iter().find(|(_, n)| n == name).map(|(t, _)| *t),
+                    _ => None,
+                });
+                keywords.types = Some(types.collect::<Option<_>>().ok_or_else(|| error("a type name or an array of them"))?);
+            },
+            "enum" => match value {
+                JsonValue::Array(values) => keywords.enum_values = Some(values.clone()),
+                _ => return Err(error("an array")),
+            },
+            "const" => keywords.constant = Some(value.clone()),
+            "minimum" => keywords.minimum = Some(number()?),
+            "maximum" => keywords.maximum = Some(number()?),
+            "exclusiveMinimum" => keywords.exclusive_minimum = Some(number()?),
+            "exclusiveMaximum" => keywords.exclusive_maximum = Some(number()?),
+            "multipleOf" => {
+                keywords.multiple_of = Some(number().and_then(|n| if n > 0.0 { Ok(n) } else { Err(error("a number more than 0")) })?);
+            },
+            "minLength" => keywords.min_length = Some(count()?),
+            "maxLength" => keywords.max_length = Some(count()?),
+            "minItems" => keywords.min_items = Some(count()?),
+            "maxItems" => keywords.max_items = Some(count()?),
+            "minProperties" => keywords.min_properties = Some(count()?),
+            "maxProperties" => keywords.max_properties = Some(count()?),
+            "uniqueItems" => match value {
+                JsonValue::Bool(unique) => keywords.unique_items = *unique,
+                _ => return Err(error("a boolean")),
+            },
+            "required" => {
+                let names = match value {
+                    JsonValue::Array(names) => names.iter().map(|name| match name {
+                        JsonValue::String(name) => Some(name.clone()),
+                        _ => None,
+                    }).collect(),
+                    _ => None,
+                };
+                keywords.required = names.ok_or_else(|| error("an array of property names"))?;
+            },
+            "properties" => match value {
+                JsonValue::Object(properties) => {
+                    keywords.properties = properties
+                        .iter()
+                        .map(|(name, schema)| Ok((name.clone(), compile(schema, format!("{}/{}", path, pointer::escape(name)))?)))
+                        .collect::<Result<_, _>>()?;
+                },
+                _ => return Err(error("an object")),
+            },
+            "additionalProperties" => keywords.additional_properties = Some(compile(value, path)?),
+            "items" => keywords.items = Some(compile(value, path)?),
+            keyword if UNSUPPORTED.contains(&keyword) => {
+                return Err(SchemaError { schema_path: path, message: format!("`{}` isn't supported", keyword) });
+            },
+            _ => {},
+        }
+    }
+    Ok(Node::Keywords(Box::new(keywords)))
+}
+
+fn check(node: &Node, value: &JsonValue, instance_path: &str, violations: &mut Vec<Violation>) {
+    let keywords = match node {
+        Node::Bool(true, _) => return,
+        Node::Bool(false, path) => {
+            let message = "the schema doesn't allow any value here".to_string();
+            violations.push(Violation { instance_path: instance_path.to_string(), schema_path: path.clone(), message });
+            return;
+        },
+        Node::Keywords(keywords) => keywords,
+    };
+    let mut violation = |keyword: &str, message: String| {
+        let schema_path = format!("{}/{}", keywords.path, keyword);
+        violations.push(Violation { instance_path: instance_path.to_string(), schema_path, message });
+    };
+
+    if let Some(types) = &keywords.types {
+        if !types.iter().any(|t| t.matches(value)) {
+            let expected: Vec<_> = types.iter().map(|t| t.name()).collect();
+            violation("type", format!("expected {}, found {}", expected.join(" or "), Type::of(value).name()));
+        }
+    }
+    if let Some(values) = &keywords.enum_values {
+        if !values.iter().any(|v| equal(v, value)) {
+            let allowed: Vec<_> = values.iter().map(to_string_compact).collect();
+            violation("enum", format!("expected one of {}, found {}", allowed.join(", "), to_string_compact(value)));
+        }
+    }
+    if let Some(constant) = &keywords.constant {
+        if !equal(constant, value) {
+            violation("const", format!("expected {}, found {}", to_string_compact(constant), to_string_compact(value)));
+        }
+    }
+
+    match value {
+        JsonValue::Number(written) => {
+            let n = value.as_f64().unwrap_or(f64::NAN);
+            let bounds = [
+                ("minimum", keywords.minimum, "at least", n >= keywords.minimum.unwrap_or(f64::NEG_INFINITY)),
+                ("maximum", keywords.maximum, "at most", n <= keywords.maximum.unwrap_or(f64::INFINITY)),
+                ("exclusiveMinimum", keywords.exclusive_minimum, "more than", n > keywords.exclusive_minimum.unwrap_or(f64::NEG_INFINITY)),
+                ("exclusiveMaximum", keywords.exclusive_maximum, "less than", n < keywords.exclusive_maximum.unwrap_or(f64::INFINITY)),
+            ];
+            for (keyword, bound, relation, within) in bounds {
+                if let (Some(bound), false) = (bound, within) {
+                    violation(keyword, format!("expected {} {}, found {}", relation, bound, written));
+                }
+            }
+            if let Some(divisor) = keywords.multiple_of {
+                if (n / divisor).fract() != 0.0 {
+                    violation("multipleOf", format!("expected a multiple of {}, found {}", divisor, written));
+                }
+            }
+        },
+        JsonValue::String(string) => {
+            // Length in characters, not bytes
+            let length = string.chars().count();
+            check_count(&mut violation, ("minLength", "maxLength"), keywords.min_length, keywords.max_length, length, ("character", "characters"));
+        },
+        JsonValue::Array(elements) => {
+            check_count(&mut violation, ("minItems", "maxItems"), keywords.min_items, keywords.max_items, elements.len(), ("item", "items"));
+            if keywords.unique_items {
+                let repeated = (1..elements.len()).find(|&i| elements[..i].iter().any(|e| equal(e, &elements[i])));
+                if let Some(i) = repeated {
+                    violation("uniqueItems", format!("expected unique items, found {} again at index {}", to_string_compact(&elements[i]), i));
+                }
+            }
+        },
+        JsonValue::Object(members) => {
+            let count = distinct_keys(members).len();
+            let limits = (keywords.min_properties, keywords.max_properties);
+            check_count(&mut violation, ("minProperties", "maxProperties"), limits.0, limits.1, count, ("property", "properties"));
+            for name in &keywords.required {
+                if !members.iter().any(|(key, _)| key == name) {
+                    violation("required", format!("missing required property `{}`", name));
+                }
+            }
+        },
+        _ => {},
+    }
+
+    match value {
+        JsonValue::Object(members) => {
+            for (key, member) in distinct_keys(members) {
+                let path = format!("{}/{}", instance_path, pointer::escape(key));
+                match keywords.properties.iter().find(|(name, _)| name == key) {
+                    Some((_, schema)) => check(schema, member, &path, violations),
+                    None => {
+                        if let Some(schema) = &keywords.additional_properties {
+                            check(schema, member, &path, violations);
+                        }
+                    },
+                }
+            }
+        },
+        JsonValue::Array(elements) => {
+            if let Some(schema) = &keywords.items {
+                for (i, element) in elements.iter().enumerate() {
+                    check(schema, element, &format!("{}/{}", instance_path, i), violations);
+                }
+            }
+        },
+        _ => {},
+    }
+}
+
+/// Check a count against the minimum and maximum keywords for it
+fn check_count(
+    violation: &mut impl FnMut(&str, String),
+    (min_keyword, max_keyword): (&str, &str),
+    min: Option<usize>,
+    max: Option<usize>,
+    count: usize,
+    (thing, things): (&str, &str),
+) {
+    let things = |n| if n == 1 { thing } else { things };
+    if let Some(min) = min.filter(|min| count < *min) {
+        violation(min_keyword, format!("expected at least {} {}, found {}", min, things(min), count));
+    }
+    if let Some(max) = max.filter(|max| count > *max) {
+        violation(max_keyword, format!("expected at most {} {}, found {}", max, things(max), count));
+    }
+}
+
+/// The members of an object with each key once, the last of any repeated ones counting, in the
+/// order they were first written
+fn distinct_keys(members: &[(String, JsonValue)]) -> Vec<(&str, &JsonValue)> {
+    let mut distinct: Vec<(&str, &JsonValue)> = Vec::with_capacity(members.len());
+    for (key, value) in members {
+        match distinct.iter_mut().find(|(k, _)| k == key) {
+            Some(member) => member.1 = value,
+            None => distinct.push((key, value)),
+        }
+    }
+    distinct
+}
+
+/// Whether two values are the same as far as JSON Schema is concerned, with numbers compared by
+/// value and objects regardless of the order of their members
+fn equal(a: &JsonValue, b: &JsonValue) -> bool {
+    match (a, b) {
+        (JsonValue::Number(_), JsonValue::Number(_)) => a.as_f64() == b.as_f64(),
+        (JsonValue::Array(a), JsonValue::Array(b)) => a.len() == b.len() && a.iter().zip(b).all(|(a, b)| equal(a, b)),
+        (JsonValue::Object(a), JsonValue::Object(b)) => {
+            let (a, b) = (distinct_keys(a), distinct_keys(b));
+            a.len() == b.len() && a.iter().all(|(key, a)| b.iter().any(|(k, b)| k == key && equal(a, b)))
+        },
+        _ => a == b,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_str;
+
+    /// Each violation, as the message and the instance and schema paths
+    fn validate(schema: &str, instance: &str) -> Vec<(String, String, String)> {
+        let schema = Schema::new(&parse_str(schema).unwrap()).unwrap();
+        let violations = schema.validate(&parse_str(instance).unwrap());
+        violations.into_iter().map(|v| (v.message, v.instance_path, v.schema_path)).collect()
+    }
+
+    fn violation(message: &str, instance_path: &str, schema_path: &str) -> (String, String, String) {
+        (message.to_string(), instance_path.to_string(), schema_path.to_string())
+    }
+
+    const PERSON: &str = r#"{
+        "type": "object",
+        "required": ["name", "age"],
+        "properties": {
+            "name": {"type": "string", "minLength": 1},
+            "age": {"type": "integer", "minimum": 0, "maximum": 150},
+            "role": {"enum": ["admin", "user"]},
+            "tags": {"type": "array", "items": {"type": "string"}, "maxItems": 2, "uniqueItems": true},
+            "a/b": {"const": 1}
+        },
+        "additionalProperties": false
+    }"#;
+
+    #[test]
+    fn check_validate() {
+        assert_eq!(validate(PERSON, r#"{"name": "Ann", "age": 30, "role": "user", "tags": ["x"], "a/b": 1.0}"#), []);
+        assert_eq!(validate(PERSON, r#"{"name": "", "age": 30.5, "role": "root"}"#), [
+            violation("expected at least 1 character, found 0", "/name", "/properties/name/minLength"),
+            violation("expected integer, found number", "/age", "/properties/age/type"),
+            violation(r#"expected one of "admin", "user", found "root""#, "/role", "/properties/role/enum"),
+        ]);
+        assert_eq!(validate(PERSON, r#"{"age": -1, "tags": ["x", 2, "x"], "a/b": 2, "extra": null}"#), [
+            violation("missing required property `name`", "", "/required"),
+            violation("expected at least 0, found -1", "/age", "/properties/age/minimum"),
+            violation("expected at most 2 items, found 3", "/tags", "/properties/tags/maxItems"),
+            violation(r#"expected unique items, found "x" again at index 2"#, "/tags", "/properties/tags/uniqueItems"),
+            violation("expected string, found number", "/tags/1", "/properties/tags/items/type"),
+            violation("expected 1, found 2", "/a~1b", "/properties/a~1b/const"),
+            violation("the schema doesn't allow any value here", "/extra", "/additionalProperties"),
+        ]);
+        assert_eq!(validate(PERSON, "[]"), [
+            violation("expected object, found array", "", "/type"),
+        ]);
+    }
+
+    #[test]
+    fn check_validate_numbers() {
+        let schema = r#"{"type": ["number", "null"], "exclusiveMinimum": 0, "exclusiveMaximum": 10, "multipleOf": 0.5}"#;
+        assert_eq!(validate(schema, "2.5"), []);
+        assert_eq!(validate(schema, "null"), []);
+        assert_eq!(validate(schema, "10"), [violation("expected less than 10, found 10", "", "/exclusiveMaximum")]);
+        assert_eq!(validate(schema, "0.3"), [violation("expected a multiple of 0.5, found 0.3", "", "/multipleOf")]);
+        assert_eq!(validate(schema, "\"5\""), [violation("expected number or null, found string", "", "/type")]);
+        assert_eq!(validate("true", "[1]"), []);
+        assert_eq!(validate("false", "[1]"), [violation("the schema doesn't allow any value here", "", "")]);
+        assert_eq!(validate(r#"{"const": {"a": [1, {"b": 2}], "c": 3}}"#, r#"{"c": 3.0, "a": [1, {"b": 2}]}"#), []);
+    }
+
+    #[test]
+    fn check_schema_errors() {
+        let error = |schema| Schema::new(&parse_str(schema).unwrap()).unwrap_err().to_string();
+        assert_eq!(error("3"), "expected a schema, an object or a boolean at the root of the schema");
+        assert_eq!(error(r#"{"type": "thing"}"#), "expected a type name or an array of them at `/type` in the schema");
+        assert_eq!(error(r#"{"properties": {"a": {"minLength": -1}}}"#),
+            "expected a non-negative integer at `/properties/a/minLength` in the schema");
+        assert_eq!(error(r##"{"items": {"$ref": "#"}}"##), "`$ref` isn't supported at `/items/$ref` in the schema");
+    }
+}
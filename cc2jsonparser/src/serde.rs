@@ -0,0 +1,477 @@
+// Serde support, with the `serde` feature.
+// A JsonValue is a serde Deserializer, so what this crate parses can be deserialized into a
+// program's own types without serde_json, and it's Serialize and Deserialize itself, so it can be
+// written or read in any other format serde has. Numbers are kept as written until they're
+// deserialized into the type asked for, an integer too big for 64 bits going to an i128 or u128 if
+// that's what's asked for. A key given more than once is given to the visitor each time, which
+// for a struct is a duplicate field error.
+use crate::{parse_str, JsonValue};
+use serde::de::{self, DeserializeSeed, Deserializer, EnumAccess, IntoDeserializer, MapAccess, SeqAccess, VariantAccess, Visitor};
+use serde::ser::{Serialize, SerializeMap, SerializeSeq, Serializer};
+use serde::{forward_to_deserialize_any, Deserialize};
+use std::fmt;
+
+/// Why a value can't be deserialized into a type
+#[derive(Clone, Debug, PartialEq)]
+pub struct DeserializeError {
+    pub message: String,
+}
+
+impl fmt::Display for DeserializeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for DeserializeError {}
+
+impl de::Error for DeserializeError {
+    fn custom<T: fmt::Display>(message: T) -> DeserializeError {
+        DeserializeError { message: message.to_string() }
+    }
+}
+
+/// Deserialize a `T` from the value, borrowing its strings if `T` does
+pub fn from_value<'de, T: Deserialize<'de>>(value: &'de JsonValue) -> Result<T, DeserializeError> {
+    T::deserialize(value)
+}
+
+/// Parse a JSON document and deserialize a `T` from it
+pub fn from_str<T: de::DeserializeOwned>(input: &str) -> Result<T, DeserializeError> {
+    let value = parse_str(input).map_err(|e| DeserializeError { message: e.to_string() })?;
+    T::deserialize(&value)
+}
+
+/// A JSON number as the kind of number serde has that it fits in best
+enum Number {
+    Unsigned(u64),
+    Signed(i64),
+    Float(f64),
+}
+
+/// Read a number, failing for one too big for a float, rather than having it be infinity, as well
+/// as for one that isn't a JSON number
+fn number(number: &str) -> Result<Number, DeserializeError> {
+    let invalid = || DeserializeError { message: format!("invalid number `{}`", number) };
+    if !number.contains(['.', 'e', 'E']) {
+        if let Ok(unsigned) = number.parse() {
+            return Ok(Number::Unsigned(unsigned));
+        }
+        if let Ok(signed) = number.parse() {
+            return Ok(Number::Signed(signed));
+        }
+    }
+    match number.parse::<f64>() {
+        Ok(float) if float.is_finite() => Ok(Number::Float(float)),
+        Ok(_) => Err(DeserializeError { message: format!("number `{}` is too big for a float", number) }),
+        Err(_) => Err(invalid()),
+    }
+}
+
+impl<'de> Deserializer<'de> for &'de JsonValue {
+    type Error = DeserializeError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeserializeError> {
+        match self {
+            JsonValue::Null => visitor.visit_unit(),
+            JsonValue::Bool(bool) => visitor.visit_bool(*bool),
+            JsonValue::Number(raw) => match number(raw)? {
+                Number::Unsigned(unsigned) => visitor.visit_u64(unsigned),
+                Number::Signed(signed) => visitor.visit_i64(signed),
+                Number::Float(float) => visitor.visit_f64(float),
+            },
+            JsonValue::String(string) => visitor.visit_borrowed_str(string),
+            JsonValue::Array(elements) => visitor.visit_seq(Elements(elements.iter())),
+            JsonValue::Object(members) => visitor.visit_map(Members { members: members.iter(), value: None }),
+        }
+    }
+
+    fn deserialize_i128<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeserializeError> {
+        match self {
+            JsonValue::Number(raw) => match raw.parse() {
+                Ok(signed) => visitor.visit_i128(signed),
+                Err(_) => self.deserialize_any(visitor),
+            },
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_u128<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeserializeError> {
+        match self {
+            JsonValue::Number(raw) => match raw.parse() {
+                Ok(unsigned) => visitor.visit_u128(unsigned),
+                Err(_) => self.deserialize_any(visitor),
+            },
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    /// Null is None, and anything else is Some
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeserializeError> {
+        match self {
+            JsonValue::Null => visitor.visit_none(),
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(self, _name: &'static str, visitor: V) -> Result<V::Value, DeserializeError> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    /// A unit variant is its name as a string, and any other variant an object with its name as the
+    /// only key, as serde_json has them
+    fn deserialize_enum<V: Visitor<'de>>(self, _name: &'static str, _variants: &'static [&'static str], visitor: V) -> Result<V::Value, DeserializeError> {
+        match self {
+            JsonValue::String(variant) => visitor.visit_enum(variant.as_str().into_deserializer()),
+            JsonValue::Object(members) => match members.as_slice() {
+                [(variant, value)] => visitor.visit_enum(Variant { variant, value }),
+                _ => Err(de::Error::invalid_value(de::Unexpected::Map, &"an object with only the variant's name as a key")),
+            },
+            other => Err(de::Error::invalid_type(unexpected(other), &"a string or an object for an enum")),
+        }
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 u8 u16 u32 u64 f32 f64 char str string bytes byte_buf unit unit_struct seq tuple tuple_struct map
+        struct identifier ignored_any
+    }
+}
+
+impl<'de> IntoDeserializer<'de, DeserializeError> for &'de JsonValue {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+/// How a value is described in a deserialize error
+fn unexpected(value: &JsonValue) -> de::Unexpected<'_> {
+    match value {
+        JsonValue::Null => de::Unexpected::Unit,
+        JsonValue::Bool(bool) => de::Unexpected::Bool(*bool),
+        JsonValue::Number(raw) => de::Unexpected::Other(raw),
+        JsonValue::String(string) => de::Unexpected::Str(string),
+        JsonValue::Array(_) => de::Unexpected::Seq,
+        JsonValue::Object(_) => de::Unexpected::Map,
+    }
+}
+
+/// The elements of an array, to visit as a sequence
+struct Elements<'de>(std::slice::Iter<'de, JsonValue>);
+
+impl<'de> SeqAccess<'de> for Elements<'de> {
+    type Error = DeserializeError;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(&mut self, seed: T) -> Result<Option<T::Value>, DeserializeError> {
+        self.0.next().map(|element| seed.deserialize(element)).transpose()
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.0.len())
+    }
+}
+
+/// The members of an object, to visit as a map, with the value of the key just visited
+struct Members<'de> {
+    members: std::slice::Iter<'de, (String, JsonValue)>,
+    value: Option<&'de JsonValue>,
+}
+
+impl<'de> MapAccess<'de> for Members<'de> {
+    type Error = DeserializeError;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(&mut self, seed: K) -> Result<Option<K::Value>, DeserializeError> {
+        let Some((key, value)) = self.members.next() else { return Ok(None) };
+        self.value = Some(value);
+        seed.deserialize(Key(key)).map(Some)
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, DeserializeError> {
+        let value = self.value.take().expect("a value is only asked for after its key");
+        seed.deserialize(value)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.members.len())
+    }
+}
+
+/// An object key, which is a string but can be deserialized as the number it's written as too,
+/// for maps with numbers as keys
+struct Key<'de>(&'de str);
+
+macro_rules! deserialize_number_key {
+    ($($method:ident => $visit:ident,)*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeserializeError> {
+                match self.0.parse() {
+                    Ok(number) => visitor.$visit(number),
+                    Err(_) => Err(de::Error::invalid_value(de::Unexpected::Str(self.0), &"a key written as a number")),
+                }
+            }
+        )*
+    };
+}
+
+impl<'de> Deserializer<'de> for Key<'de> {
+    type Error = DeserializeError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeserializeError> {
+        visitor.visit_borrowed_str(self.0)
+    }
+
+    deserialize_number_key! {
+        deserialize_i8 => visit_i8,
+        deserialize_i16 => visit_i16,
+        deserialize_i32 => visit_i32,
+        deserialize_i64 => visit_i64,
+        deserialize_i128 => visit_i128,
+        deserialize_u8 => visit_u8,
+        deserialize_u16 => visit_u16,
+        deserialize_u32 => visit_u32,
+        deserialize_u64 => visit_u64,
+        deserialize_u128 => visit_u128,
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(self, _name: &'static str, visitor: V) -> Result<V::Value, DeserializeError> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(self, _name: &'static str, _variants: &'static [&'static str], visitor: V) -> Result<V::Value, DeserializeError> {
+        visitor.visit_enum(self.0.into_deserializer())
+    }
+
+    forward_to_deserialize_any! {
+        bool f32 f64 char str string bytes byte_buf option unit unit_struct seq tuple tuple_struct map struct identifier
+        ignored_any
+    }
+}
+
+/// An enum variant written as an object with its name as the only key
+struct Variant<'de> {
+    variant: &'de str,
+    value: &'de JsonValue,
+}
+
+impl<'de> EnumAccess<'de> for Variant<'de> {
+    type Error = DeserializeError;
+    type Variant = &'de JsonValue;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(self, seed: V) -> Result<(V::Value, &'de JsonValue), DeserializeError> {
+        Ok((seed.deserialize(Key(self.variant))?, self.value))
+    }
+}
+
+impl<'de> VariantAccess<'de> for &'de JsonValue {
+    type Error = DeserializeError;
+
+    fn unit_variant(self) -> Result<(), DeserializeError> {
+        Deserialize::deserialize(self)
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(self, seed: T) -> Result<T::Value, DeserializeError> {
+        seed.deserialize(self)
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value, DeserializeError> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn struct_variant<V: Visitor<'de>>(self, _fields: &'static [&'static str], visitor: V) -> Result<V::Value, DeserializeError> {
+        self.deserialize_map(visitor)
+    }
+}
+
+/// Numbers are written as the 64-bit integer or float they fit best, so one with more digits
+/// than those have loses some, and one too big for a float fails
+impl Serialize for JsonValue {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            JsonValue::Null => serializer.serialize_unit(),
+            JsonValue::Bool(bool) => serializer.serialize_bool(*bool),
+            JsonValue::Number(raw) => match number(raw).map_err(serde::ser::Error::custom)? {
+                Number::Unsigned(unsigned) => serializer.serialize_u64(unsigned),
+                Number::Signed(signed) => serializer.serialize_i64(signed),
+                Number::Float(float) => serializer.serialize_f64(float),
+            },
+            JsonValue::String(string) => serializer.serialize_str(string),
+            JsonValue::Array(elements) => {
+                let mut seq = serializer.serialize_seq(Some(elements.len()))?;
+                for element in elements {
+                    seq.serialize_element(element)?;
+                }
+                seq.end()
+            },
+            JsonValue::Object(members) => {
+                let mut map = serializer.serialize_map(Some(members.len()))?;
+                for (key, value) in members {
+                    map.serialize_entry(key, value)?;
+                }
+                map.end()
+            },
+        }
+    }
+}
+
+/// Anything serde can give, with maps' keys strings. Floats are written the shortest way that
+/// reads back the same, and those JSON has no way to write, infinity and NaN, are null.
+impl<'de> Deserialize<'de> for JsonValue {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<JsonValue, D::Error> {
+        deserializer.deserialize_any(ValueVisitor)
+    }
+}
+
+struct ValueVisitor;
+
+impl<'de> Visitor<'de> for ValueVisitor {
+    type Value = JsonValue;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a value JSON can write")
+    }
+
+    fn visit_bool<E: de::Error>(self, bool: bool) -> Result<JsonValue, E> {
+        Ok(JsonValue::Bool(bool))
+    }
+
+    fn visit_i64<E: de::Error>(self, signed: i64) -> Result<JsonValue, E> {
+        Ok(JsonValue::Number(signed.to_string()))
+    }
+
+    fn visit_i128<E: de::Error>(self, signed: i128) -> Result<JsonValue, E> {
+        Ok(JsonValue::Number(signed.to_string()))
+    }
+
+    fn visit_u64<E: de::Error>(self, unsigned: u64) -> Result<JsonValue, E> {
+        Ok(JsonValue::Number(unsigned.to_string()))
+    }
+
+    fn visit_u128<E: de::Error>(self, unsigned: u128) -> Result<JsonValue, E> {
+        Ok(JsonValue::Number(unsigned.to_string()))
+    }
+
+    fn visit_f64<E: de::Error>(self, float: f64) -> Result<JsonValue, E> {
+        match float.is_finite() {
+            true => Ok(JsonValue::Number(format!("{:?}", float))),
+            false => Ok(JsonValue::Null),
+        }
+    }
+
+    fn visit_str<E: de::Error>(self, string: &str) -> Result<JsonValue, E> {
+        Ok(JsonValue::String(string.to_string()))
+    }
+
+    fn visit_string<E: de::Error>(self, string: String) -> Result<JsonValue, E> {
+        Ok(JsonValue::String(string))
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<JsonValue, E> {
+        Ok(JsonValue::Null)
+    }
+
+    fn visit_none<E: de::Error>(self) -> Result<JsonValue, E> {
+        Ok(JsonValue::Null)
+    }
+
+    fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<JsonValue, D::Error> {
+        Deserialize::deserialize(deserializer)
+    }
+
+    fn visit_newtype_struct<D: Deserializer<'de>>(self, deserializer: D) -> Result<JsonValue, D::Error> {
+        Deserialize::deserialize(deserializer)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<JsonValue, A::Error> {
+        let mut elements = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+        while let Some(element) = seq.next_element()? {
+            elements.push(element);
+        }
+        Ok(JsonValue::Array(elements))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<JsonValue, A::Error> {
+        let mut members = Vec::with_capacity(map.size_hint().unwrap_or(0));
+        while let Some(member) = map.next_entry()? {
+            members.push(member);
+        }
+        Ok(JsonValue::Object(members))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::to_string_compact;
+    use std::collections::HashMap;
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Config<'a> {
+        name: &'a str,
+        port: u16,
+        ratio: f64,
+        tags: Vec<String>,
+        owner: Option<String>,
+        limits: HashMap<u32, i64>,
+        level: Level,
+        shapes: Vec<Shape>,
+        #[serde(default)]
+        missing: bool,
+    }
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    enum Level {
+        Debug,
+        Info,
+    }
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    enum Shape {
+        Circle(f32),
+        Rect { w: u8, h: u8 },
+        Point(i8, i8),
+    }
+
+    #[test]
+    fn check_from_value() {
+        let value = parse_str(r#"{
+            "name": "app", "port": 8080, "ratio": 1e-1, "tags": ["a", "b"], "owner": null, "limits": {"1": -5, "20": 9007199254740993},
+            "level": "Info", "shapes": [{"Circle": 1.5}, {"Rect": {"w": 2, "h": 3}}, {"Point": [-1, 1]}], "extra": [{}]
+        }"#).unwrap();
+        assert_eq!(from_value::<Config>(&value), Ok(Config {
+            name: "app",
+            port: 8080,
+            ratio: 0.1,
+            tags: vec!["a".to_string(), "b".to_string()],
+            owner: None,
+            limits: HashMap::from([(1, -5), (20, 9007199254740993)]),
+            level: Level::Info,
+            shapes: vec![Shape::Circle(1.5), Shape::Rect { w: 2, h: 3 }, Shape::Point(-1, 1)],
+            missing: false,
+        }));
+        assert_eq!(from_str::<(u128, i128, Level)>("[340282366920938463463374607431768211455, -1, \"Debug\"]"), Ok((u128::MAX, -1, Level::Debug)));
+        assert_eq!(from_str::<Option<Vec<u8>>>("[1, 2]"), Ok(Some(vec![1, 2])));
+    }
+
+    #[test]
+    fn check_from_value_fails_for_values_of_the_wrong_type() {
+        let error = |input: &str| from_str::<Vec<u8>>(input).unwrap_err().message;
+        assert_eq!(error("[256]"), "invalid value: integer `256`, expected u8");
+        assert_eq!(error("[1.5]"), "invalid type: floating point `1.5`, expected u8");
+        assert_eq!(error(r#"{"a": 1}"#), "invalid type: map, expected a sequence");
+        assert_eq!(error("[1,"), "expected a value, found end of input at line 1, column 4");
+        assert_eq!(from_str::<Level>(r#""Warn""#).unwrap_err().message, "unknown variant `Warn`, expected `Debug` or `Info`");
+        assert_eq!(from_str::<Level>(r#"{"Info": null, "Debug": null}"#).unwrap_err().message, "invalid value: map, expected an object with only the variant's name as a key");
+    }
+
+    #[test]
+    fn check_serialize_and_deserialize_value() {
+        let value = parse_str(r#"{"a": [1, -2, 2.5, true, null, "x"], "b": {}, "a": 18446744073709551615}"#).unwrap();
+        let copied = JsonValue::deserialize(&value).unwrap();
+        assert_eq!(to_string_compact(&copied), r#"{"a":[1,-2,2.5,true,null,"x"],"b":{},"a":18446744073709551615}"#);
+        // serde's own value deserializers stand in for another format
+        let float: JsonValue = JsonValue::deserialize(de::value::F64Deserializer::<DeserializeError>::new(f64::NAN)).unwrap();
+        assert_eq!(float, JsonValue::Null);
+        assert_eq!(from_str::<JsonValue>("[1e400]").unwrap_err().message, "number `1e400` is too big for a float");
+    }
+}
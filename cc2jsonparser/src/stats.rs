@@ -0,0 +1,190 @@
+// Document statistics.
+// How many of each kind of value a document has, how many keys, how deep it nests, and where its
+// longest string and array are, for sizing a payload before it goes somewhere with limits, like a
+// queue. They're counted from the events of the document as it's read, so like validate_reader
+// this only keeps the objects and arrays still open, however big the document is.
+use crate::pointer::{escape, location};
+use crate::{JsonError, JsonEvent, JsonEvents, JsonValue, ParseOptions};
+use std::{fmt, io::BufRead};
+
+/// What a document has in it
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Stats {
+    pub objects: usize,
+    pub arrays: usize,
+    pub strings: usize,
+    pub numbers: usize,
+    pub booleans: usize,
+    pub nulls: usize,
+    /// The keys of every object, counting a repeated key each time
+    pub keys: usize,
+    /// How many objects and arrays the deepest value is in, or is one of at the deepest
+    pub max_depth: usize,
+    /// The most characters in a string value, the first with that many if more than one has
+    pub longest_string: Option<Longest>,
+    /// The most elements in an array, the first with that many if more than one has
+    pub longest_array: Option<Longest>,
+}
+
+/// How long the longest of a kind of value is, and the JSON Pointer to it
+#[derive(Clone, Debug, PartialEq)]
+pub struct Longest {
+    pub length: usize,
+    pub path: String,
+}
+
+impl fmt::Display for Stats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "objects: {}", self.objects)?;
+        writeln!(f, "arrays: {}", self.arrays)?;
+        writeln!(f, "strings: {}", self.strings)?;
+        writeln!(f, "numbers: {}", self.numbers)?;
+        writeln!(f, "booleans: {}", self.booleans)?;
+        writeln!(f, "nulls: {}", self.nulls)?;
+        writeln!(f, "keys: {}", self.keys)?;
+        writeln!(f, "max depth: {}", self.max_depth)?;
+        let longest = |longest: &Option<Longest>, unit: &str| match longest {
+            Some(longest) => format!("{} {}, {}", longest.length, unit, location(&longest.path)),
+            None => "none".to_string(),
+        };
+        writeln!(f, "longest string: {}", longest(&self.longest_string, "characters"))?;
+        writeln!(f, "longest array: {}", longest(&self.longest_array, "elements"))
+    }
+}
+
+/// An object or array still open, with the key or the number of elements read in it so far
+enum Open {
+    Object { key: String },
+    Array { length: usize },
+}
+
+/// Read a JSON document from `reader` within the limits of `options`, counting what's in it
+pub fn read_stats(reader: impl BufRead, options: &ParseOptions) -> Result<Stats, JsonError> {
+    let events = JsonEvents::with_options(reader, options);
+    let mut stats = Stats::default();
+    // Each open object and array only keeps where in it the value being read is, so the path to
+    // a value is only put together when it's the longest so far
+    let mut open: Vec<Open> = Vec::new();
+    for event in events {
+        let event = event?;
+        // A value starting here is the next element of the array it's in
+        if let (JsonEvent::StartObject | JsonEvent::StartArray | JsonEvent::Value(_), Some(Open::Array { length })) = (&event, open.last_mut()) {
+            *length += 1;
+        }
+        match event {
+            JsonEvent::StartObject => {
+                stats.objects += 1;
+                open.push(Open::Object { key: String::new() });
+                stats.max_depth = stats.max_depth.max(open.len());
+            },
+            JsonEvent::StartArray => {
+                stats.arrays += 1;
+                open.push(Open::Array { length: 0 });
+                stats.max_depth = stats.max_depth.max(open.len());
+            },
+            JsonEvent::Key(key) => {
+                stats.keys += 1;
+                if let Some(Open::Object { key: open_key }) = open.last_mut() {
+                    *open_key = key;
+                }
+            },
+            JsonEvent::EndObject => {
+                open.pop();
+            },
+            JsonEvent::EndArray => {
+                if let Some(Open::Array { length }) = open.pop() {
+                    longer(&mut stats.longest_array, length, &open);
+                }
+            },
+            JsonEvent::Value(value) => match &value {
+                JsonValue::String(string) => {
+                    stats.strings += 1;
+                    longer(&mut stats.longest_string, string.chars().count(), &open);
+                },
+                JsonValue::Number(_) => stats.numbers += 1,
+                JsonValue::Bool(_) => stats.booleans += 1,
+                JsonValue::Null => stats.nulls += 1,
+                JsonValue::Object(_) | JsonValue::Array(_) => unreachable!("objects and arrays come as their start and end"),
+            },
+        }
+    }
+    Ok(stats)
+}
+
+/// Keep the value in the innermost of `open` as the longest if it's longer than the longest so far
+fn longer(longest: &mut Option<Longest>, length: usize, open: &[Open]) {
+    if longest.as_ref().is_none_or(|longest| length > longest.length) {
+        *longest = Some(Longest { length, path: path(open) });
+    }
+}
+
+/// The JSON Pointer to the value being read in the innermost of `open`
+fn path(open: &[Open]) -> String {
+    let mut path = String::new();
+    for open in open {
+        path.push('/');
+        match open {
+            Open::Object { key } => path.push_str(&escape(key)),
+            Open::Array { length } => path.push_str(&(length - 1).to_string()),
+        }
+    }
+    path
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn stats(input: &str) -> Stats {
+        read_stats(Cursor::new(input), &ParseOptions::default()).unwrap()
+    }
+
+    #[test]
+    fn check_read_stats() {
+        let stats = stats(r#"{"name": "app", "tags": ["a", "bé", "cc"], "deps": [{"name": "longer", "a/b": [1, 2.5, [true, null]]}], "name": "x"}"#);
+        assert_eq!(stats, Stats {
+            objects: 2,
+            arrays: 4,
+            strings: 6,
+            numbers: 2,
+            booleans: 1,
+            nulls: 1,
+            keys: 6,
+            max_depth: 5,
+            longest_string: Some(Longest { length: 6, path: "/deps/0/name".to_string() }),
+            longest_array: Some(Longest { length: 3, path: "/tags".to_string() }),
+        });
+        assert_eq!(stats.to_string(), "\
+objects: 2
+arrays: 4
+strings: 6
+numbers: 2
+booleans: 1
+nulls: 1
+keys: 6
+max depth: 5
+longest string: 6 characters, `/deps/0/name`
+longest array: 3 elements, `/tags`
+");
+    }
+
+    #[test]
+    fn check_read_stats_of_documents_without_nesting() {
+        let text = stats("\"text\"");
+        assert_eq!((text.strings, text.max_depth), (1, 0));
+        assert_eq!(text.longest_string, Some(Longest { length: 4, path: String::new() }));
+        assert!(text.to_string().ends_with("longest string: 4 characters, the document\nlongest array: none\n"));
+        assert_eq!(stats("[]").longest_array, Some(Longest { length: 0, path: String::new() }));
+        assert!(read_stats(Cursor::new("[1, 2"), &ParseOptions::default()).is_err());
+    }
+
+    #[test]
+    fn check_read_stats_of_deep_documents() {
+        let depth = 100_000;
+        let input = format!("{}{}", "[".repeat(depth), "]".repeat(depth));
+        let stats = read_stats(Cursor::new(input), &ParseOptions { max_depth: None, ..ParseOptions::default() }).unwrap();
+        assert_eq!((stats.arrays, stats.max_depth), (depth, depth));
+        assert_eq!(stats.longest_array.map(|longest| longest.path.len()), Some(2 * (depth - 2)));
+    }
+}
@@ -0,0 +1,1207 @@
+// JSON tokens.
+// The input is split into the punctuation, literals, strings, and numbers that JSON is made of,
+// checking each is well formed on the way, so the parser only has to check their order. Strings
+// and numbers are kept as written in the input, as slices of it when the whole input is there to
+// borrow from, so lexing a document allocates next to nothing. When they're allowed, `//` and
+// `/* */` comments are passed over like whitespace, the way JSONC has them, and JSON5 is read with
+// what it adds to JSON when that's asked for.
+// Reference:  https://code.visualstudio.com/docs/languages/json#_json-with-comments
+use crate::{json5, ParseOptions};
+use cc_diagnostics::{LineCol, Span};
+use std::{borrow::Cow, fmt, io::BufRead};
+
+/// The byte order mark some programs start UTF-8 text with, though JSON text shouldn't have one
+const BOM: char = '\u{feff}';
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Token<'a> {
+    LeftBrace,
+    RightBrace,
+    Colon,
+    Comma,
+    String(Cow<'a, str>),
+    True,
+    False,
+    Null,
+    Number(Cow<'a, str>),
+    /// An object key written without quotes, only in JSON5
+    Identifier(Cow<'a, str>),
+    LeftBracket,
+    RightBracket,
+    /// Past the last token, which the parser reads rather than running off the end
+    Eof,
+}
+
+impl Token<'_> {
+    /// The token with a string or number copied out of the input it's borrowed from
+    pub fn into_owned(self) -> Token<'static> {
+        match self {
+            Token::LeftBrace => Token::LeftBrace,
+            Token::RightBrace => Token::RightBrace,
+            Token::Colon => Token::Colon,
+            Token::Comma => Token::Comma,
+            Token::String(raw) => Token::String(Cow::Owned(raw.into_owned())),
+            Token::True => Token::True,
+            Token::False => Token::False,
+            Token::Null => Token::Null,
+            Token::Number(number) => Token::Number(Cow::Owned(number.into_owned())),
+            Token::Identifier(name) => Token::Identifier(Cow::Owned(name.into_owned())),
+            Token::LeftBracket => Token::LeftBracket,
+            Token::RightBracket => Token::RightBracket,
+            Token::Eof => Token::Eof,
+        }
+    }
+}
+
+/// Something read from the input along with where it is: the line and column it starts at, and
+/// the bytes it takes up
+#[derive(Clone, Debug, PartialEq)]
+pub struct Spanned<T> {
+    pub value: T,
+    pub position: LineCol,
+    pub span: Span,
+}
+
+/// What's wrong with the input, and where
+#[derive(Clone, Debug, PartialEq)]
+pub struct TokenizeError {
+    pub message: String,
+    pub position: LineCol,
+    /// The bytes of the token that isn't well formed
+    pub span: Span,
+}
+
+impl TokenizeError {
+    fn new(message: impl Into<String>, line: usize, column: usize) -> TokenizeError {
+        TokenizeError { message: message.into(), position: LineCol { line, column }, span: Span::default() }
+    }
+
+    fn at(self, span: Span) -> TokenizeError {
+        TokenizeError { span, ..self }
+    }
+}
+
+impl fmt::Display for TokenizeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} at line {}, column {}", self.message, self.position.line, self.position.column)
+    }
+}
+
+impl std::error::Error for TokenizeError {}
+
+impl fmt::Display for Token<'_> {
+    /// How the token is described in errors
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Token::LeftBrace => f.write_str("`{`"),
+            Token::RightBrace => f.write_str("`}`"),
+            Token::Colon => f.write_str("`:`"),
+            Token::Comma => f.write_str("`,`"),
+            Token::String(raw) => write!(f, "string {}", raw),
+            Token::True => f.write_str("`true`"),
+            Token::False => f.write_str("`false`"),
+            Token::Null => f.write_str("`null`"),
+            Token::Number(number) => write!(f, "number `{}`", number),
+            Token::Identifier(name) => write!(f, "identifier `{}`", name),
+            Token::LeftBracket => f.write_str("`[`"),
+            Token::RightBracket => f.write_str("`]`"),
+            Token::Eof => f.write_str("end of input"),
+        }
+    }
+}
+
+/// The characters of one line, counting columns as they're read
+#[derive(Clone, Debug)]
+pub(crate) struct LineChars<'a> {
+    /// What's left of the line
+    rest: &'a str,
+    /// The column of the character just read, counting from 1
+    column: usize,
+}
+
+impl<'a> LineChars<'a> {
+    pub(crate) fn new(line: &'a str) -> LineChars<'a> {
+        LineChars { rest: line, column: 0 }
+    }
+
+    pub(crate) fn peek(&self) -> Option<char> {
+        self.rest.chars().next()
+    }
+
+    pub(crate) fn next_if(&mut self, func: impl FnOnce(&char) -> bool) -> Option<char> {
+        self.peek().filter(func)?;
+        self.next()
+    }
+
+    /// Whether what's left is the line break at the end of the line
+    fn at_line_break(&self) -> bool {
+        matches!(self.rest, "\n" | "\r\n")
+    }
+
+    /// The next character, unless it's the start of the line break
+    fn next_before_line_break(&mut self) -> Option<char> {
+        match self.at_line_break() {
+            true => None,
+            false => self.next(),
+        }
+    }
+
+    /// What's been read since `from`, which is what was left of the line at some point before
+    pub(crate) fn since(&self, from: &'a str) -> &'a str {
+        &from[..from.len() - self.rest.len()]
+    }
+}
+
+impl Iterator for LineChars<'_> {
+    type Item = char;
+
+    fn next(&mut self) -> Option<char> {
+        let mut chars = self.rest.chars();
+        let ch = chars.next()?;
+        self.rest = chars.as_str();
+        self.column += 1;
+        Some(ch)
+    }
+}
+
+/// Split the input into its tokens, line by line
+pub fn tokenize(buf_reader: impl BufRead) -> Result<Vec<Token<'static>>, TokenizeError> {
+    let mut tokens = tokenize_with_spans(buf_reader)?;
+    tokens.pop(); // The end
+    Ok(tokens.into_iter().map(|token| token.value).collect())
+}
+
+/// Split the input into its tokens along with where each one starts, ending with an Eof token
+/// for where the input ends
+pub fn tokenize_with_positions(buf_reader: impl BufRead) -> Result<Vec<(Token<'static>, LineCol)>, TokenizeError> {
+    let tokens = tokenize_with_spans(buf_reader)?;
+    Ok(tokens.into_iter().map(|token| (token.value, token.position)).collect())
+}
+
+/// Split the input into its tokens along with where each one starts and the bytes it takes up,
+/// ending with an empty Eof token at the end of the last line. A byte order mark at the start of
+/// the input is skipped, the way RFC 8259 lets parsers.
+pub fn tokenize_with_spans(buf_reader: impl BufRead) -> Result<Vec<Spanned<Token<'static>>>, TokenizeError> {
+    tokenize_with_options(buf_reader, &ParseOptions::default())
+}
+
+/// Split the input into its tokens like tokenize_with_spans, with a byte order mark at the start
+/// an error unless `options` allow it, and comments skipped if they're allowed
+pub(crate) fn tokenize_with_options(buf_reader: impl BufRead, options: &ParseOptions) -> Result<Vec<Spanned<Token<'static>>>, TokenizeError> {
+    let mut tokens = Vec::new();
+    let end = tokenize_lines(TokenStream::with_options(buf_reader, options), &mut tokens, &mut |e, _| Err(e))?;
+    tokens.push(end);
+    Ok(tokens)
+}
+
+/// Split the whole of `input` into its tokens like tokenize_with_spans, with each string and
+/// number a slice of `input` rather than a copy
+pub fn tokenize_str(input: &str) -> Result<Vec<Spanned<Token<'_>>>, TokenizeError> {
+    tokenize_str_with(input, &ParseOptions::default())
+}
+
+/// Split the whole of `input` into its tokens like tokenize_str, with a byte order mark,
+/// comments, and JSON5 allowed or not by `options`
+pub fn tokenize_str_with<'a>(input: &'a str, options: &ParseOptions) -> Result<Vec<Spanned<Token<'a>>>, TokenizeError> {
+    let mut tokens = Vec::new();
+    let mut state = LineState::new(options);
+    let mut line = "";
+    for next in input.split_inclusive('\n') {
+        line = next;
+        state = state.next_line(line.len());
+        state.skip_bom(line, options.allow_bom)?;
+        while let Some(token) = state.next_token(line) {
+            let token = token.map_err(|(e, _)| e)?;
+            // A string carried on from the lines before is all there in the input
+            let token = match token.value {
+                Token::String(_) if token.span.start < state.line_start => {
+                    Spanned { value: Token::String(Cow::Borrowed(&input[token.span.start..token.span.end])), ..token }
+                },
+                _ => token,
+            };
+            tokens.push(token);
+        }
+    }
+    let end = state.end(line);
+    if let Some((e, _)) = state.unterminated(end.span.start) {
+        return Err(e);
+    }
+    tokens.push(end);
+    Ok(tokens)
+}
+
+/// The tokens of some input read past any errors in it
+#[derive(Debug)]
+pub(crate) struct Recovered {
+    /// Ending with Eof, with a null standing in for each token that couldn't be read
+    pub(crate) tokens: Vec<Spanned<Token<'static>>>,
+    pub(crate) errors: Vec<TokenizeError>,
+    /// Where the stand-ins are
+    pub(crate) stand_ins: Vec<LineCol>,
+}
+
+/// Split the input into its tokens like tokenize_with_options, but carry on past any token that
+/// isn't well formed, skipping the rest of it, to find all the errors
+pub(crate) fn tokenize_recovering(buf_reader: impl BufRead, options: &ParseOptions) -> Recovered {
+    let mut tokens = Vec::new();
+    let mut errors = Vec::new();
+    let mut stand_ins = Vec::new();
+    let result = tokenize_lines(TokenStream::with_options(buf_reader, options), &mut tokens, &mut |e, position| {
+        errors.push(e);
+        // Nothing stands in for what isn't a token
+        Ok(position.map(|position| {
+            stand_ins.push(position);
+            Token::Null
+        }))
+    });
+    // There's no carrying on after the input can't be read
+    let end = result.unwrap_or_else(|e| {
+        let end = Spanned { value: Token::Eof, position: e.position, span: e.span };
+        errors.push(e);
+        end
+    });
+    tokens.push(end);
+    Recovered { tokens, errors, stand_ins }
+}
+
+/// Push the tokens of each line onto `tokens`, giving the Eof token for where the input ends. A
+/// token that isn't well formed goes to `on_error`, with where it starts, to give the error or a
+/// token for it, and so does anything else wrong that can be read past, without a position.
+fn tokenize_lines(
+    mut stream: TokenStream<impl BufRead>,
+    tokens: &mut Vec<Spanned<Token<'static>>>,
+    on_error: &mut dyn FnMut(TokenizeError, Option<LineCol>) -> Result<Option<Token<'static>>, TokenizeError>,
+) -> Result<Spanned<Token<'static>>, TokenizeError> {
+    loop {
+        match stream.next_token() {
+            Ok(token) if token.value == Token::Eof => return Ok(token),
+            Ok(token) => tokens.push(token),
+            Err(StreamError::Token(e, position)) => {
+                let span = e.span;
+                if let Some(value) = on_error(e, Some(position))? {
+                    tokens.push(Spanned { value, position, span });
+                }
+            },
+            Err(StreamError::Skipped(e)) => {
+                on_error(e, None)?;
+            },
+            Err(StreamError::Read(e)) => return Err(e),
+        }
+    }
+}
+
+/// Why the next token couldn't be read
+#[derive(Debug)]
+pub(crate) enum StreamError {
+    /// It isn't well formed, with where it starts. The rest of it has been skipped, so reading
+    /// can carry on after it.
+    Token(TokenizeError, LineCol),
+    /// Something that isn't part of any token is there, a byte order mark that isn't allowed,
+    /// and has been skipped
+    Skipped(TokenizeError),
+    /// The input couldn't be read
+    Read(TokenizeError),
+}
+
+/// A string carried on past the end of a line by a backslash before the line break, as JSON5
+/// allows
+#[derive(Clone, Copy, Debug)]
+struct OpenString {
+    position: LineCol,
+    /// How far into the input it starts, in bytes
+    start: usize,
+    quote: char,
+}
+
+/// How far reading the tokens of a line has got
+#[derive(Clone, Copy, Debug, Default)]
+struct LineState {
+    /// Counting from 1, or 0 before the first line
+    line_number: usize,
+    /// How far into the input the line starts, in bytes
+    line_start: usize,
+    /// How long the line is in bytes, its line break included
+    line_length: usize,
+    /// How much of the line has been read, in bytes
+    offset: usize,
+    /// The column of the last character read in the line
+    column: usize,
+    /// Whether comments are skipped like whitespace rather than being illegal characters
+    allow_comments: bool,
+    /// Whether the input is JSON5 rather than JSON
+    json5: bool,
+    /// Where the block comment being read starts, and how far into the input, if it's carried on
+    /// past the end of a line
+    comment: Option<(LineCol, usize)>,
+    /// The string being read, if it's carried on past the end of a line
+    string: Option<OpenString>,
+}
+
+impl LineState {
+    /// Ready to read the first line of input with what `options` allow
+    fn new(options: &ParseOptions) -> LineState {
+        LineState { allow_comments: options.allow_comments || options.json5, json5: options.json5, ..LineState::default() }
+    }
+
+    /// The next token in what's left of `line`, or None once it's all been read, line break and
+    /// all. A token that isn't well formed comes with where it starts.
+    fn next_token<'a>(&mut self, line: &'a str) -> Option<Result<Spanned<Token<'a>>, (TokenizeError, LineCol)>> {
+        loop {
+            let from = &line[self.offset..];
+            let mut iter = LineChars { rest: from, column: self.column };
+            if self.comment.is_some() {
+                if from.is_empty() {
+                    return None;
+                }
+                self.read_block_comment(&mut iter);
+                self.offset = line.len() - iter.rest.len();
+                self.column = iter.column;
+                continue;
+            }
+            let start = self.line_start + self.offset;
+            let (position, start, token) = match self.string.take() {
+                // Carrying on with a string from the line before
+                Some(open) => {
+                    if from.is_empty() {
+                        self.string = Some(open);
+                        return None;
+                    }
+                    (open.position, open.start, self.string_token(open, from, &mut iter))
+                },
+                None => {
+                    let ch = iter.next()?;
+                    let position = LineCol { line: self.line_number, column: iter.column };
+                    let comment = match ch {
+                        '/' if self.allow_comments => iter.next_if(|&c| c == '/' || c == '*'),
+                        _ => None,
+                    };
+                    let token = match comment {
+                        Some('/') => {
+                            while iter.next_before_line_break().is_some() {}
+                            Ok(None)
+                        },
+                        Some(_) => {
+                            self.comment = Some((position, start));
+                            self.read_block_comment(&mut iter);
+                            Ok(None)
+                        },
+                        None if ch == '"' || (ch == '\'' && self.json5) => {
+                            self.string_token(OpenString { position, start, quote: ch }, from, &mut iter)
+                        },
+                        None => read_token(ch, from, &mut iter, self.line_number, self.json5),
+                    };
+                    (position, start, token)
+                },
+            };
+            self.offset = line.len() - iter.rest.len();
+            self.column = iter.column;
+            let span = Span::new(start, self.line_start + self.offset);
+            match token {
+                Ok(Some(value)) => return Some(Ok(Spanned { value, position, span })),
+                Ok(None) => {},
+                Err(e) => return Some(Err((e.at(span), position))),
+            }
+        }
+    }
+
+    /// Read the rest of the string `open`, up to its closing quote or to the end of the line if
+    /// it's carried on past it, `from` being what's left of the line. An error is at the string's
+    /// start if not anywhere in particular in it.
+    fn string_token<'a>(&mut self, open: OpenString, from: &'a str, iter: &mut LineChars<'a>) -> Result<Option<Token<'a>>, TokenizeError> {
+        match read_string(iter, open.quote, self.json5) {
+            Ok(StringEnd::Closed) => Ok(Some(Token::String(Cow::Borrowed(iter.since(from))))),
+            Ok(StringEnd::Continued) => {
+                self.string = Some(open);
+                Ok(None)
+            },
+            Err((message, column)) => {
+                skip_string(iter, open.quote);
+                Err(match column {
+                    Some(column) => TokenizeError::new(message, self.line_number, column),
+                    None => TokenizeError::new(message, open.position.line, open.position.column),
+                })
+            },
+        }
+    }
+
+    /// Read up to the end of the block comment being read, or to the end of the line if it
+    /// carries on past it
+    fn read_block_comment(&mut self, iter: &mut LineChars) {
+        while let Some(c) = iter.next() {
+            if c == '*' && iter.next_if(|&c| c == '/').is_some() {
+                self.comment = None;
+                return;
+            }
+        }
+    }
+
+    /// Ready to read the line after this one, `length` bytes long with its line break. A block
+    /// comment or string not ended in this line carries on into it.
+    fn next_line(&self, length: usize) -> LineState {
+        LineState {
+            line_number: self.line_number + 1,
+            line_start: self.line_start + self.line_length,
+            line_length: length,
+            allow_comments: self.allow_comments,
+            json5: self.json5,
+            comment: self.comment,
+            string: self.string,
+            ..LineState::default()
+        }
+    }
+
+    /// The error for a block comment or string still open where the input ends, `end` bytes into
+    /// it, with where it starts
+    fn unterminated(&self, end: usize) -> Option<(TokenizeError, LineCol)> {
+        let (message, position, start) = match (self.comment, self.string) {
+            (Some((position, start)), _) => ("unterminated comment", position, start),
+            (None, Some(open)) => ("unterminated string", open.position, open.start),
+            (None, None) => return None,
+        };
+        let e = TokenizeError::new(message, position.line, position.column);
+        Some((e.at(Span::new(start, end)), position))
+    }
+
+    /// Skip the byte order mark `line` starts with, if it's the first line and does. It isn't
+    /// counted as a column, since editors don't show it, but does take up bytes. Unless `allow`,
+    /// it's an error too.
+    fn skip_bom(&mut self, line: &str, allow: bool) -> Result<(), TokenizeError> {
+        if self.line_number != 1 || self.offset != 0 || !line.starts_with(BOM) {
+            return Ok(());
+        }
+        self.offset = BOM.len_utf8();
+        if allow {
+            return Ok(());
+        }
+        Err(TokenizeError::new("byte order mark at the start of the input", 1, 1).at(Span::new(0, self.offset)))
+    }
+
+    /// The Eof token for the input ending with `line`, once it's all been read. The input ends
+    /// just after the last line's last character, before any line break.
+    fn end(&self, line: &str) -> Spanned<Token<'static>> {
+        let without_break = line.strip_suffix('\n').unwrap_or(line);
+        let without_break = without_break.strip_suffix('\r').unwrap_or(without_break);
+        let line_break = line.len() - without_break.len();
+        Spanned {
+            value: Token::Eof,
+            position: match self.line_number {
+                0 => LineCol { line: 1, column: 1 },
+                line => LineCol { line, column: self.column - line_break + 1 },
+            },
+            span: Span::point(self.line_start + without_break.len()),
+        }
+    }
+}
+
+/// The tokens of some input read as they're asked for, so only the line they're in is kept in
+/// memory. Each line's bytes are checked to be UTF-8 as they're read.
+#[derive(Debug)]
+pub(crate) struct TokenStream<R> {
+    reader: R,
+    /// With its line break, if it has one
+    line: String,
+    state: LineState,
+    /// What's been read of a string carried on past the end of a line, from the lines before
+    continued: String,
+    /// Whether a byte order mark at the start is skipped rather than an error
+    allow_bom: bool,
+    /// The Eof token, once the input has ended
+    end: Option<Spanned<Token<'static>>>,
+}
+
+impl<R: BufRead> TokenStream<R> {
+    pub(crate) fn new(reader: R) -> TokenStream<R> {
+        TokenStream { reader, line: String::new(), state: LineState::default(), continued: String::new(), allow_bom: true, end: None }
+    }
+
+    /// A stream with a byte order mark at the start, comments, and JSON5 allowed or not by
+    /// `options`
+    pub(crate) fn with_options(reader: R, options: &ParseOptions) -> TokenStream<R> {
+        TokenStream { state: LineState::new(options), allow_bom: options.allow_bom, ..TokenStream::new(reader) }
+    }
+
+    /// The next token and where it is, Eof once the input ends. The line it's read from is
+    /// reused for the next, so the token has its own copy of any string or number.
+    pub(crate) fn next_token(&mut self) -> Result<Spanned<Token<'static>>, StreamError> {
+        loop {
+            if let Some(end) = &self.end {
+                return Ok(end.clone());
+            }
+            match self.state.next_token(&self.line) {
+                Some(Ok(token)) => return Ok(owned(token, &mut self.continued, self.state.line_start)),
+                Some(Err((e, position))) => return Err(StreamError::Token(e, position)),
+                None => self.next_line()?,
+            }
+        }
+    }
+
+    /// The tokens of the next line on their own, ending with an Eof token for where the line
+    /// ends, or None once the input has. After an error the rest of the line is skipped, so the
+    /// next call reads the line after it.
+    pub(crate) fn next_line_tokens(&mut self) -> Option<Result<Vec<Spanned<Token<'static>>>, StreamError>> {
+        if let Err(e) = self.next_line() {
+            return Some(Err(e));
+        }
+        if self.end.is_some() {
+            return None;
+        }
+        let mut tokens = Vec::new();
+        while let Some(token) = self.state.next_token(&self.line) {
+            match token {
+                Ok(token) => tokens.push(owned(token, &mut self.continued, self.state.line_start)),
+                Err((e, position)) => return Some(Err(StreamError::Token(e, position))),
+            }
+        }
+        tokens.push(self.state.end(&self.line));
+        Some(Ok(tokens))
+    }
+
+    /// Move on to the next line, or to the end of the input if there isn't one
+    fn next_line(&mut self) -> Result<(), StreamError> {
+        if let Some(open) = self.state.string {
+            self.continued.push_str(&self.line[open.start.saturating_sub(self.state.line_start)..]);
+        }
+        // Where the input ends if this was the last line
+        let end = self.state.end(&self.line);
+        let next = self.state.next_line(0);
+        // Reusing the line's buffer for the bytes of the next
+        let mut bytes = std::mem::take(&mut self.line).into_bytes();
+        bytes.clear();
+        let read = self.reader.read_until(b'\n', &mut bytes).map_err(|e| {
+            let e = TokenizeError::new(format!("could not read the input: {}", e), next.line_number, 1);
+            StreamError::Read(e.at(Span::point(next.line_start)))
+        })?;
+        if read == 0 {
+            // Given only the once
+            let unterminated = self.state.unterminated(end.span.start);
+            self.state.comment = None;
+            self.state.string = None;
+            self.end = Some(end);
+            return match unterminated {
+                Some((e, position)) => Err(StreamError::Token(e, position)),
+                None => Ok(()),
+            };
+        }
+        self.line = String::from_utf8(bytes).map_err(|e| StreamError::Read(invalid_utf8(e, next)))?;
+        self.state = self.state.next_line(read);
+        self.state.skip_bom(&self.line, self.allow_bom).map_err(StreamError::Skipped)
+    }
+}
+
+/// The token with its own copy of any string or number, joining a string carried on from the
+/// lines before, which started before `line_start`, onto what of it they had
+fn owned(token: Spanned<Token>, continued: &mut String, line_start: usize) -> Spanned<Token<'static>> {
+    let value = match token.value {
+        Token::String(raw) if token.span.start < line_start => {
+            let mut string = std::mem::take(continued);
+            string.push_str(&raw);
+            Token::String(Cow::Owned(string))
+        },
+        value => value.into_owned(),
+    };
+    Spanned { value, ..token }
+}
+
+/// The error for a line that isn't UTF-8, at the first byte that isn't part of a character
+fn invalid_utf8(e: std::string::FromUtf8Error, line: LineState) -> TokenizeError {
+    let valid = e.utf8_error().valid_up_to();
+    let bytes = e.as_bytes();
+    let invalid = e.utf8_error().error_len().unwrap_or(bytes.len() - valid);
+    // What comes before it is UTF-8, so its characters can be counted for the column
+    let column = String::from_utf8_lossy(&bytes[..valid]).chars().count() + 1;
+    let message = format!("invalid UTF-8, found byte 0x{:02X} where a character should start or go on", bytes[valid]);
+    let start = line.line_start + valid;
+    TokenizeError::new(message, line.line_number, column).at(Span::new(start, start + invalid))
+}
+
+/// Read the token starting with `ch`, anything but a string, or None for whitespace, `from` being
+/// the rest of the line from `ch` on, and JSON5 if `json5`. After an error the rest of the token
+/// has been skipped, as far as there's telling where it ends.
+fn read_token<'a>(ch: char, from: &'a str, iter: &mut LineChars<'a>, line_number: usize, json5: bool) -> Result<Option<Token<'a>>, TokenizeError> {
+    let start = iter.column;
+    let error = |message: String| TokenizeError::new(message, line_number, start);
+    let token_value = match ch {
+        '{' => Some(Token::LeftBrace),
+        '}' => Some(Token::RightBrace),
+        '[' => Some(Token::LeftBracket),
+        ']' => Some(Token::RightBracket),
+        ':' => Some(Token::Colon),
+        ',' => Some(Token::Comma),
+        first @ ('-' | '+' | '.' | '0'..='9') if json5 => Some(Token::Number(Cow::Borrowed(json5::read_number(first, from, iter).map_err(error)?))),
+        // A number can't start with `+` or `.`, but take them here to say so
+        first @ ('-' | '+' | '.' | '0'..='9') => Some(Token::Number(Cow::Borrowed(read_number(first, from, iter).map_err(error)?))),
+        ' ' | '\t' | '\n' | '\r' => None, // Ignore whitespace
+        space if json5 && json5::is_whitespace(space) => None,
+        first if json5 && json5::is_identifier_start(first) => {
+            while iter.next_if(|&c| json5::is_identifier_part(c)).is_some() {}
+            Some(match iter.since(from) {
+                "true" => Token::True,
+                "false" => Token::False,
+                "null" => Token::Null,
+                number @ ("Infinity" | "NaN") => Token::Number(Cow::Borrowed(number)),
+                name => Token::Identifier(Cow::Borrowed(name)),
+            })
+        },
+        first if first.is_ascii_alphabetic() => {
+            while iter.next_if(char::is_ascii_alphanumeric).is_some() {}
+            let word = iter.since(from);
+            match (first, word) {
+                (_, "true") => Some(Token::True),
+                (_, "false") => Some(Token::False),
+                (_, "null") => Some(Token::Null),
+                ('t', _) => return Err(error(format!("expected `true`, found `{}`", word))),
+                ('f', _) => return Err(error(format!("expected `false`, found `{}`", word))),
+                ('n', _) => return Err(error(format!("expected `null`, found `{}`", word))),
+                _ => return Err(error(format!("illegal character `{}`", first))),
+            }
+        },
+        // Any other character is not valid in this context
+        _ => {
+            // Along with whatever comes after it up to something JSON would allow
+            while iter.next_if(|c| !matches!(c, ' ' | '\t' | '\n' | '\r' | '{' | '}' | '[' | ']' | ':' | ',' | '"')).is_some() {}
+            return Err(error(format!("illegal character `{}`", ch)));
+        },
+    };
+    Ok(token_value)
+}
+
+/// Where a string's line ends
+enum StringEnd {
+    /// With its closing quote
+    Closed,
+    /// With a backslash before the line break, the string going on into the next line
+    Continued,
+}
+
+/// Read the rest of a string after its opening `quote`, or the rest of the line it's carried on
+/// to, checking its escape sequences so an escaped quote doesn't end the string. JSON5 strings
+/// can have more escapes and control characters in them. An error gives the column it's at, if
+/// not the start of the string.
+fn read_string(iter: &mut LineChars, quote: char, json5: bool) -> Result<StringEnd, (String, Option<usize>)> {
+    // Consume line until we reach the terminal quotation mark. Strings can't span lines, a line
+    // break inside one has to be escaped.
+    while let Some(i) = iter.next_before_line_break() {
+        match i {
+            end if end == quote => return Ok(StringEnd::Closed),
+            '\\' => {
+                let backslash = Some(iter.column);
+                if iter.at_line_break() {
+                    if json5 {
+                        while iter.next().is_some() {}
+                        return Ok(StringEnd::Continued);
+                    }
+                    return Err(("expected an escape sequence after `\\`".to_string(), backslash));
+                }
+                match iter.next() {
+                    Some('"' | '\\' | '/' | 'b' | 'f' | 'n' | 'r' | 't') => {},
+                    Some('u') => {
+                        read_unicode_escape(iter).map_err(|message| (message, backslash))?;
+                    },
+                    Some(e) if json5 => json5::read_escape(e, iter).map_err(|message| (message, backslash))?,
+                    Some(e) => return Err((format!("invalid escape sequence `\\{}`", e), backslash)),
+                    None => return Err(("expected an escape sequence after `\\`".to_string(), backslash)),
+                }
+            },
+            // RFC 8259 needs U+0000 to U+001F escaped
+            control if control < ' ' && !json5 => {
+                let message = format!("control character U+{:04X} must be escaped in a string", control as u32);
+                return Err((message, Some(iter.column)));
+            },
+            _ => {},
+        }
+    }
+    Err(("unterminated string".to_string(), None))
+}
+
+/// Skip to the end of a string that went wrong partway through
+fn skip_string(iter: &mut LineChars, quote: char) {
+    while let Some(c) = iter.next_before_line_break() {
+        match c {
+            end if end == quote => return,
+            '\\' => {
+                iter.next();
+            },
+            _ => {},
+        }
+    }
+}
+
+/// Read the rest of a number starting with `first`, which takes the form
+/// -?(0|[1-9][0-9]*)(.[0-9]+)?([eE][+-]?[0-9]+)?
+/// and give all of it, `from` being the rest of the line from `first` on
+fn read_number<'a>(first: char, from: &'a str, iter: &mut LineChars<'a>) -> Result<&'a str, String> {
+    fn invalid<'a>(from: &'a str, iter: &mut LineChars<'a>, reason: &str) -> String {
+        // Take the rest of whatever looks like part of the number, to show all of it
+        while iter.next_if(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '+' | '-')).is_some() {}
+        format!("invalid number `{}`: {}", iter.since(from), reason)
+    }
+
+    let leading_digit = match first {
+        '+' => return Err(invalid(from, iter, "a number can't start with `+`")),
+        '.' => return Err(invalid(from, iter, "expected a digit before `.`")),
+        '-' => match iter.next_if(char::is_ascii_digit) {
+            Some(digit) => digit,
+            None => return Err(invalid(from, iter, "expected a digit after `-`")),
+        },
+        digit => digit,
+    };
+    if leading_digit == '0' && iter.peek().is_some_and(|c| c.is_ascii_digit()) {
+        return Err(invalid(from, iter, "leading zeros aren't allowed"));
+    }
+    read_digits(iter);
+
+    if iter.next_if(|&c| c == '.').is_some() && read_digits(iter) == 0 {
+        return Err(invalid(from, iter, "expected a digit after `.`"));
+    }
+    if iter.next_if(|&c| c == 'e' || c == 'E').is_some() {
+        iter.next_if(|&c| c == '+' || c == '-');
+        if read_digits(iter) == 0 {
+            return Err(invalid(from, iter, "expected a digit in the exponent"));
+        }
+    }
+    Ok(iter.since(from))
+}
+
+/// Read past any ASCII digits that come next, giving how many there were
+pub(crate) fn read_digits(iter: &mut LineChars) -> usize {
+    let mut count = 0;
+    // We only go forward if we're still in a number
+    while iter.next_if(char::is_ascii_digit).is_some() {
+        count += 1;
+    }
+    count
+}
+
+/// Read the `XXXX` of a `\uXXXX` escape, along with the low surrogate's escape when it's the
+/// high half of a UTF-16 surrogate pair, and decode the character
+pub(crate) fn read_unicode_escape(iter: &mut LineChars) -> Result<char, String> {
+    fn read_hex(iter: &mut LineChars) -> Result<u32, String> {
+        let mut unit = 0;
+        for _ in 0..4 {
+            let expected = || "expected 4 hex digits after `\\u`".to_string();
+            let digit = iter.next().ok_or_else(expected)?;
+            unit = unit * 16 + digit.to_digit(16).ok_or_else(expected)?;
+        }
+        Ok(unit)
+    }
+
+    let unit = read_hex(iter)?;
+    let unpaired = |unit: u32| format!("unpaired surrogate `\\u{:04X}`", unit);
+    let code_point = match unit {
+        0xD800..=0xDBFF => {
+            // Left for what comes after the string to be read from, if it isn't the low surrogate
+            if !iter.rest.starts_with("\\u") {
+                return Err(unpaired(unit));
+            }
+            iter.nth(1);
+            match read_hex(iter)? {
+                low @ 0xDC00..=0xDFFF => 0x10000 + ((unit - 0xD800) << 10) + (low - 0xDC00),
+                _ => return Err(unpaired(unit)),
+            }
+        },
+        // A low surrogate without the high one before it
+        0xDC00..=0xDFFF => return Err(unpaired(unit)),
+        _ => unit,
+    };
+    // Every code point outside the surrogates is a char
+    Ok(char::from_u32(code_point).unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+    use super::*;
+
+    #[test]
+    fn check_tokenize_empty_string() {
+        let result = tokenize(Cursor::new(b"")).unwrap();
+        assert_eq!(result, []);
+    }
+
+    #[test]
+    fn check_tokenize_empty_object() {
+        let result = tokenize(Cursor::new(b"{}")).unwrap();
+        assert_eq!(result, [
+            Token::LeftBrace,
+            Token::RightBrace,
+        ]);
+    }
+
+    #[test]
+    fn check_tokenize_object() {
+        let result = tokenize(Cursor::new(b"{\"key\": \"value\"}")).unwrap();
+        assert_eq!(result, [
+            Token::LeftBrace,
+            Token::String("\"key\"".into()),
+            Token::Colon,
+            Token::String("\"value\"".into()),
+            Token::RightBrace,
+        ])
+    }
+
+    #[test]
+    fn check_tokenize_object_multiline() {
+        let result = tokenize(Cursor::new(b"{\n  \"key\": \"value\",\n  \"key2\": \"value\"\n}")).unwrap();
+        assert_eq!(result, [
+            Token::LeftBrace,
+            Token::String("\"key\"".into()),
+            Token::Colon,
+            Token::String("\"value\"".into()),
+            Token::Comma,
+            Token::String("\"key2\"".into()),
+            Token::Colon,
+            Token::String("\"value\"".into()),
+            Token::RightBrace,
+        ])
+    }
+
+    #[test]
+    fn check_tokenize_fails_for_unquoted_key() {
+        let result = tokenize(Cursor::new(b"{key: \"value\"}")).unwrap_err();
+        assert_eq!(result, TokenizeError::new("illegal character `k`", 1, 2).at(Span::new(1, 4)))
+    }
+
+    #[test]
+    fn check_tokenize_with_positions() {
+        let result = tokenize_with_positions(Cursor::new("{\n  \"caf\u{e9}\": true\n}")).unwrap();
+        let at = |line, column| LineCol { line, column };
+        assert_eq!(result, [
+            (Token::LeftBrace, at(1, 1)),
+            (Token::String("\"caf\u{e9}\"".into()), at(2, 3)),
+            (Token::Colon, at(2, 9)),
+            (Token::True, at(2, 11)),
+            (Token::RightBrace, at(3, 1)),
+            (Token::Eof, at(3, 2)),
+        ])
+    }
+
+    #[test]
+    fn check_tokenize_with_spans() {
+        let result = tokenize_with_spans(Cursor::new("{\n  \"caf\u{e9}\": true\r\n}\n")).unwrap();
+        let spans: Vec<_> = result.iter().map(|token| (token.span.start, token.span.end)).collect();
+        assert_eq!(spans, [(0, 1), (4, 11), (11, 12), (13, 17), (19, 20), (20, 20)]);
+
+        assert_eq!(tokenize_with_spans(Cursor::new("")).unwrap()[0].span, Span::point(0));
+        let e = tokenize_with_spans(Cursor::new("[1,\n \"a\\x\", 2]")).unwrap_err();
+        assert_eq!((e.position, e.span), (LineCol { line: 2, column: 4 }, Span::new(5, 10)));
+    }
+
+    #[test]
+    fn check_tokenize_whitespace() {
+        let result = tokenize_with_positions(Cursor::new("[1,\t2,\r\n\t3\r]\r\n")).unwrap();
+        let at = |line, column| LineCol { line, column };
+        assert_eq!(result, [
+            (Token::LeftBracket, at(1, 1)),
+            (Token::Number("1".into()), at(1, 2)),
+            (Token::Comma, at(1, 3)),
+            (Token::Number("2".into()), at(1, 5)),
+            (Token::Comma, at(1, 6)),
+            (Token::Number("3".into()), at(2, 2)),
+            (Token::RightBracket, at(2, 4)),
+            (Token::Eof, at(2, 5)),
+        ]);
+    }
+
+    #[test]
+    fn check_tokenize_fails_for_invalid_utf8() {
+        let message = |byte| format!("invalid UTF-8, found byte 0x{} where a character should start or go on", byte);
+        let result = tokenize(Cursor::new(b"[\n \"caf\xe9\"]")).unwrap_err();
+        assert_eq!(result, TokenizeError::new(message("E9"), 2, 6).at(Span::new(7, 8)));
+        // Cut off partway through a character
+        let result = tokenize(Cursor::new(b"\"caf\xc3")).unwrap_err();
+        assert_eq!(result, TokenizeError::new(message("C3"), 1, 5).at(Span::new(4, 5)));
+    }
+
+    #[test]
+    fn check_tokenize_str() {
+        let input = "{\n  \"caf\u{e9}\": [1.5e3, true]\r\n}\n";
+        let tokens = tokenize_str(input).unwrap();
+        assert_eq!(tokens, tokenize_with_spans(Cursor::new(input)).unwrap());
+        // Borrowed from the input rather than copied
+        assert!(matches!(&tokens[1].value, Token::String(Cow::Borrowed(raw)) if *raw == "\"caf\u{e9}\""));
+        assert!(matches!(&tokens[4].value, Token::Number(Cow::Borrowed("1.5e3"))));
+
+        for input in ["", "  \n\n", "\t1\r\n", "[\"a\r\n\"]", "[1, tru]", "[\"a\\x\"]", "{\"a\": 1,\n\"b\":"] {
+            assert_eq!(tokenize_str(input), tokenize_with_spans(Cursor::new(input)), "{:?}", input);
+        }
+    }
+
+    #[test]
+    fn check_tokenize_comments() {
+        let options = ParseOptions { allow_comments: true, ..ParseOptions::default() };
+        let input = "// settings\n{\"a\": /* one */ 1, // more\n /* b\n  */ \"b\": \"//\"}/**/";
+        let tokens = tokenize_with_options(Cursor::new(input), &options).unwrap();
+        let values: Vec<_> = tokens.iter().map(|token| token.value.clone()).collect();
+        assert_eq!(values, [
+            Token::LeftBrace,
+            Token::String("\"a\"".into()),
+            Token::Colon,
+            Token::Number("1".into()),
+            Token::Comma,
+            Token::String("\"b\"".into()),
+            Token::Colon,
+            Token::String("\"//\"".into()),
+            Token::RightBrace,
+            Token::Eof,
+        ]);
+        // Columns carry on after a block comment ending on a later line
+        assert_eq!((tokens[5].position, tokens[5].span), (LineCol { line: 4, column: 6 }, Span::new(50, 53)));
+        assert_eq!(tokenize_str_with(input, &options), Ok(tokens));
+
+        // Only when they're allowed
+        assert_eq!(tokenize(Cursor::new(b"[1] // one")).unwrap_err(), TokenizeError::new("illegal character `/`", 1, 5).at(Span::new(4, 6)));
+        assert_eq!(tokenize_str_with("[1 / 2]", &options).unwrap_err(), TokenizeError::new("illegal character `/`", 1, 4).at(Span::new(3, 4)));
+    }
+
+    #[test]
+    fn check_tokenize_json5() {
+        let options = ParseOptions { json5: true, ..ParseOptions::default() };
+        let input = "{key: 'it\\'s', $b: +0x1F,\u{a0}c: [.5, -Infinity, NaN], 'd':\n'one \\\ntwo'}";
+        let tokens = tokenize_with_options(Cursor::new(input), &options).unwrap();
+        let values: Vec<_> = tokens.iter().map(|token| token.value.clone()).collect();
+        assert_eq!(values, [
+            Token::LeftBrace,
+            Token::Identifier("key".into()),
+            Token::Colon,
+            Token::String("'it\\'s'".into()),
+            Token::Comma,
+            Token::Identifier("$b".into()),
+            Token::Colon,
+            Token::Number("+0x1F".into()),
+            Token::Comma,
+            Token::Identifier("c".into()),
+            Token::Colon,
+            Token::LeftBracket,
+            Token::Number(".5".into()),
+            Token::Comma,
+            Token::Number("-Infinity".into()),
+            Token::Comma,
+            Token::Number("NaN".into()),
+            Token::RightBracket,
+            Token::Comma,
+            Token::String("'d'".into()),
+            Token::Colon,
+            Token::String("'one \\\ntwo'".into()),
+            Token::RightBrace,
+            Token::Eof,
+        ]);
+        // A string carried on into the next line is where it starts
+        assert_eq!((tokens[21].position, tokens[21].span), (LineCol { line: 2, column: 1 }, Span::new(57, 68)));
+        assert_eq!(tokenize_str_with(input, &options), Ok(tokens));
+
+        assert_eq!(tokenize_str_with("['a\\1']", &options).unwrap_err().message, "invalid escape sequence `\\1`");
+        assert_eq!(tokenize_str_with("['a\\x4']", &options).unwrap_err().message, "expected 2 hex digits after `\\x`");
+        let expected = TokenizeError::new("unterminated string", 1, 2).at(Span::new(1, 7));
+        assert_eq!(tokenize_with_options(Cursor::new("['one \\\n"), &options).unwrap_err(), expected);
+        assert_eq!(tokenize_str_with("['one \\\n", &options).unwrap_err(), expected);
+        // None of it otherwise
+        assert!(tokenize_str("{key: 1}").is_err());
+        assert!(tokenize_str("['a']").is_err());
+        assert!(tokenize_str("[\"one \\\ntwo\"]").is_err());
+    }
+
+    #[test]
+    fn check_tokenize_fails_for_unterminated_comment() {
+        let options = ParseOptions { allow_comments: true, ..ParseOptions::default() };
+        let expected = TokenizeError::new("unterminated comment", 2, 3).at(Span::new(6, 16));
+        assert_eq!(tokenize_with_options(Cursor::new("[1,\n2 /* three\n]\n"), &options).unwrap_err(), expected);
+        assert_eq!(tokenize_str_with("[1,\n2 /* three\n]\n", &options).unwrap_err(), expected);
+        // `*/` has to come after `/*` rather than overlapping it
+        assert!(tokenize_with_options(Cursor::new("1 /*/"), &options).is_err());
+
+        let recovered = tokenize_recovering(Cursor::new("[1 /*"), &options);
+        assert_eq!(recovered.errors, [TokenizeError::new("unterminated comment", 1, 4).at(Span::new(3, 5))]);
+    }
+
+    #[test]
+    fn check_tokenize_fails_for_misspelled_literals() {
+        assert_eq!(tokenize(Cursor::new(b"[tru]")).unwrap_err(), TokenizeError::new("expected `true`, found `tru`", 1, 2).at(Span::new(1, 4)));
+        // Even when the input ends partway through
+        assert_eq!(tokenize(Cursor::new(b"[fa")).unwrap_err(), TokenizeError::new("expected `false`, found `fa`", 1, 2).at(Span::new(1, 3)));
+        assert_eq!(tokenize(Cursor::new(b"n")).unwrap_err(), TokenizeError::new("expected `null`, found `n`", 1, 1).at(Span::new(0, 1)));
+    }
+
+    #[test]
+    fn check_tokenize_string_escapes() {
+        let result = tokenize(Cursor::new(br#"["he said \"hi\"", "\\\/\b\f\n\r\t"]"#)).unwrap();
+        assert_eq!(result, [
+            Token::LeftBracket,
+            Token::String(r#""he said \"hi\"""#.into()),
+            Token::Comma,
+            Token::String(r#""\\\/\b\f\n\r\t""#.into()),
+            Token::RightBracket,
+        ])
+    }
+
+    #[test]
+    fn check_tokenize_fails_for_invalid_escape() {
+        let result = tokenize(Cursor::new(br#"{"key": "\x41"}"#)).unwrap_err();
+        assert_eq!(result, TokenizeError::new("invalid escape sequence `\\x`", 1, 10).at(Span::new(8, 14)))
+    }
+
+    #[test]
+    fn check_tokenize_fails_for_control_characters() {
+        let result = tokenize(Cursor::new(b"{\n  \"key\": \"a\tb\"}")).unwrap_err();
+        assert_eq!(result, TokenizeError::new("control character U+0009 must be escaped in a string", 2, 12).at(Span::new(11, 16)));
+        let result = tokenize(Cursor::new(b"[\"\x00\"]")).unwrap_err();
+        assert_eq!(result, TokenizeError::new("control character U+0000 must be escaped in a string", 1, 3).at(Span::new(1, 4)));
+        // Escaped, they're fine
+        assert!(tokenize(Cursor::new(br#"["a\tb\u0000"]"#)).is_ok());
+    }
+
+    #[test]
+    fn check_tokenize_fails_for_unterminated_string() {
+        let result = tokenize(Cursor::new(b"{\n  \"key\": \"value,\n  \"key2\": 1\n}")).unwrap_err();
+        assert_eq!(result, TokenizeError::new("unterminated string", 2, 10).at(Span::new(11, 18)));
+        // An escaped quote doesn't end it either
+        let result = tokenize(Cursor::new(br#"["abc\""#)).unwrap_err();
+        assert_eq!(result, TokenizeError::new("unterminated string", 1, 2).at(Span::new(1, 7)));
+    }
+
+    #[test]
+    fn check_unicode_escape() {
+        // Giving what's left after the escape along with the character
+        let decode = |digits| {
+            let mut iter = LineChars::new(digits);
+            read_unicode_escape(&mut iter).map(|c| (c, iter.rest))
+        };
+        assert_eq!(decode("00e9\""), Ok(('\u{e9}', "\"")));
+        assert_eq!(decode("D83D\\uDE00x"), Ok(('\u{1f600}', "x")));
+        let expected_hex = "expected 4 hex digits after `\\u`".to_string();
+        assert_eq!(decode("00g0"), Err(expected_hex.clone()));
+        assert_eq!(decode("12"), Err(expected_hex));
+    }
+
+    #[test]
+    fn check_tokenize_unicode_escapes() {
+        let result = tokenize(Cursor::new(br#"["caf\u00e9 \uD83D\uDE00"]"#)).unwrap();
+        assert_eq!(result, [
+            Token::LeftBracket,
+            Token::String(r#""caf\u00e9 \uD83D\uDE00""#.into()),
+            Token::RightBracket,
+        ])
+    }
+
+    #[test]
+    fn check_tokenize_fails_for_unpaired_surrogates() {
+        for (input, unit) in [
+            (br#"["\uD83D"]"#.as_slice(), "D83D"),
+            (br#"["\uD83D\u0041"]"#, "D83D"),
+            (br#"["\uDE00\uD83D"]"#, "DE00"),
+            (br#"["\ud83dx"]"#, "D83D"),
+        ] {
+            let expected = TokenizeError::new(format!("unpaired surrogate `\\u{}`", unit), 1, 3).at(Span::new(1, input.len() - 1));
+            assert_eq!(tokenize(Cursor::new(input)).unwrap_err(), expected);
+        }
+    }
+
+    #[test]
+    fn check_tokenize_true() {
+        let result = tokenize(Cursor::new(b"{\"key\": true}")).unwrap();
+        assert_eq!(result, [
+            Token::LeftBrace,
+            Token::String("\"key\"".into()),
+            Token::Colon,
+            Token::True,
+            Token::RightBrace,
+        ])
+    }
+
+    #[test]
+    fn check_tokenize_false() {
+        let result = tokenize(Cursor::new(b"{\"key\": false}")).unwrap();
+        assert_eq!(result, [
+            Token::LeftBrace,
+            Token::String("\"key\"".into()),
+            Token::Colon,
+            Token::False,
+            Token::RightBrace,
+        ])
+    }
+
+    #[test]
+    fn check_tokenize_null() {
+        let result = tokenize(Cursor::new(b"{\"key\": null}")).unwrap();
+        assert_eq!(result, [
+            Token::LeftBrace,
+            Token::String("\"key\"".into()),
+            Token::Colon,
+            Token::Null,
+            Token::RightBrace,
+        ])
+    }
+
+    #[test]
+    fn check_tokenize_number() {
+        let result = tokenize(Cursor::new(b"{\"key\": 101}")).unwrap();
+        assert_eq!(result, [
+            Token::LeftBrace,
+            Token::String("\"key\"".into()),
+            Token::Colon,
+            Token::Number("101".into()),
+            Token::RightBrace,
+        ])
+    }
+
+    #[test]
+    fn check_tokenize_number_grammar() {
+        let result = tokenize(Cursor::new(b"[-12.5e+3, 0, -0.25, 6E23, 1e-7]")).unwrap();
+        assert_eq!(result, [
+            Token::LeftBracket,
+            Token::Number("-12.5e+3".into()),
+            Token::Comma,
+            Token::Number("0".into()),
+            Token::Comma,
+            Token::Number("-0.25".into()),
+            Token::Comma,
+            Token::Number("6E23".into()),
+            Token::Comma,
+            Token::Number("1e-7".into()),
+            Token::RightBracket,
+        ])
+    }
+
+    #[test]
+    fn check_tokenize_fails_for_invalid_number() {
+        for (input, message) in [
+            (b"[0123]".as_slice(), "invalid number `0123`: leading zeros aren't allowed"),
+            (b"[-00]", "invalid number `-00`: leading zeros aren't allowed"),
+            (b"[+1]", "invalid number `+1`: a number can't start with `+`"),
+            (b"[.5]", "invalid number `.5`: expected a digit before `.`"),
+            (b"[1.]", "invalid number `1.`: expected a digit after `.`"),
+            (b"[1.e5]", "invalid number `1.e5`: expected a digit after `.`"),
+            (b"[-]", "invalid number `-`: expected a digit after `-`"),
+            (b"[-x]", "invalid number `-x`: expected a digit after `-`"),
+            (b"[2e+]", "invalid number `2e+`: expected a digit in the exponent"),
+        ] {
+            assert_eq!(tokenize(Cursor::new(input)).unwrap_err(), TokenizeError::new(message, 1, 2).at(Span::new(1, input.len() - 1)));
+        }
+    }
+
+    #[test]
+    fn check_tokenize_empty_array() {
+        let result = tokenize(Cursor::new(b"{\"key\": []}")).unwrap();
+        assert_eq!(result, [
+            Token::LeftBrace,
+            Token::String("\"key\"".into()),
+            Token::Colon,
+            Token::LeftBracket,
+            Token::RightBracket,
+            Token::RightBrace,
+        ])
+    }
+
+    #[test]
+    fn check_tokenize_array() {
+        let result = tokenize(Cursor::new(b"{\"key\": [\"list value\"]}")).unwrap();
+        assert_eq!(result, [
+            Token::LeftBrace,
+            Token::String("\"key\"".into()),
+            Token::Colon,
+            Token::LeftBracket,
+            Token::String("\"list value\"".into()),
+            Token::RightBracket,
+            Token::RightBrace,
+        ])
+    }
+}
@@ -0,0 +1,229 @@
+// Writing JSON as TOML.
+// A TOML document is a table, so only an object can be written as one, and TOML has no null and
+// no integers beyond 64 bits, so those are errors saying where they are rather than being written
+// as something else. Each table's keys with plain values come first, then its objects as tables
+// and its arrays of objects as arrays of tables under headers of their own, the way TOML is
+// written by hand. Anything else nested is written inline. A header carries every key above it, so
+// a document nesting more than DEFAULT_MAX_DEPTH deep isn't written.
+// Reference:  https://toml.io/en/v1.0.0
+use crate::{JsonValue, DEFAULT_MAX_DEPTH};
+use std::fmt::{self, Write};
+
+/// What there's no writing as TOML, and where it is in the document
+#[derive(Clone, Debug, PartialEq)]
+pub struct TomlError {
+    pub message: String,
+}
+
+impl fmt::Display for TomlError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for TomlError {}
+
+/// The value as a TOML document, if it's an object TOML can express all of
+pub fn to_string_toml(value: &JsonValue) -> Result<String, TomlError> {
+    let JsonValue::Object(members) = value else {
+        let message = format!("a TOML document is a table, so only an object can be written as one, not {}", value.type_name());
+        return Err(TomlError { message });
+    };
+    if nests_deeper(value, DEFAULT_MAX_DEPTH) {
+        let message = format!("the document nests objects and arrays more than {} deep, too deep to write as TOML", DEFAULT_MAX_DEPTH);
+        return Err(TomlError { message });
+    }
+    let mut out = String::new();
+    write_table(&mut out, members, "", "")?;
+    Ok(out)
+}
+
+/// Whether objects and arrays nest more than `max` deep in the value
+fn nests_deeper(value: &JsonValue, max: usize) -> bool {
+    let mut waiting = vec![(value, 0)];
+    while let Some((value, depth)) = waiting.pop() {
+        match value {
+            JsonValue::Object(_) | JsonValue::Array(_) if depth == max => return true,
+            JsonValue::Object(members) => waiting.extend(members.iter().map(|(_, value)| (value, depth + 1))),
+            JsonValue::Array(elements) => waiting.extend(elements.iter().map(|element| (element, depth + 1))),
+            _ => {},
+        }
+    }
+    false
+}
+
+/// Write the members of a table, those with plain values first and then those that are tables
+/// or arrays of tables, each under its header. `header` is the table's dotted key, and `at` where
+/// it is in the document for errors, with the index of each array element it's in.
+fn write_table(out: &mut String, members: &[(String, JsonValue)], header: &str, at: &str) -> Result<(), TomlError> {
+    let mut tables = Vec::new();
+    for (key, value) in members {
+        match value {
+            JsonValue::Object(members) if !members.is_empty() => tables.push((key, value)),
+            JsonValue::Array(elements) if is_array_of_tables(elements) => tables.push((key, value)),
+            _ => {
+                let value = inline(value, &dotted(at, key))?;
+                writeln!(out, "{} = {}", toml_key(key), value).expect("writing to a String can't fail");
+            },
+        }
+    }
+    for (key, value) in tables {
+        let (header, at) = (dotted(header, key), dotted(at, key));
+        match value {
+            JsonValue::Object(members) => {
+                write_header(out, &format!("[{}]", header));
+                write_table(out, members, &header, &at)?;
+            },
+            JsonValue::Array(elements) => {
+                for (i, element) in elements.iter().enumerate() {
+                    let JsonValue::Object(members) = element else { unreachable!("only arrays of objects are arrays of tables") };
+                    write_header(out, &format!("[[{}]]", header));
+                    write_table(out, members, &header, &format!("{}[{}]", at, i))?;
+                }
+            },
+            _ => unreachable!("only objects and arrays are tables"),
+        }
+    }
+    Ok(())
+}
+
+/// Write a table's header on a line of its own, after a blank line unless it's the first thing
+fn write_header(out: &mut String, header: &str) {
+    if !out.is_empty() {
+        out.push('\n');
+    }
+    out.push_str(header);
+    out.push('\n');
+}
+
+/// Whether the elements are all objects, which are written as an array of tables
+fn is_array_of_tables(elements: &[JsonValue]) -> bool {
+    !elements.is_empty() && elements.iter().all(|element| matches!(element, JsonValue::Object(_)))
+}
+
+/// The value written on one line, `at` being where it is for errors
+fn inline(value: &JsonValue, at: &str) -> Result<String, TomlError> {
+    match value {
+        JsonValue::Object(members) if members.is_empty() => Ok("{}".to_string()),
+        JsonValue::Object(members) => {
+            let members = members.iter().map(|(key, value)| Ok(format!("{} = {}", toml_key(key), inline(value, &dotted(at, key))?)));
+            Ok(format!("{{ {} }}", members.collect::<Result<Vec<_>, TomlError>>()?.join(", ")))
+        },
+        JsonValue::Array(elements) => {
+            let elements = elements.iter().enumerate().map(|(i, element)| inline(element, &format!("{}[{}]", at, i)));
+            Ok(format!("[{}]", elements.collect::<Result<Vec<_>, TomlError>>()?.join(", ")))
+        },
+        JsonValue::String(string) => Ok(toml_string(string)),
+        JsonValue::Number(number) => {
+            let integer = !number.contains(['.', 'e', 'E']);
+            if integer && number.parse::<i64>().is_err() {
+                return Err(TomlError { message: format!("`{}` is {}, which is too big for a TOML integer", at, number) });
+            }
+            Ok(number.clone())
+        },
+        JsonValue::Bool(bool) => Ok(bool.to_string()),
+        JsonValue::Null => Err(TomlError { message: format!("`{}` is null, which TOML has no way to write", at) }),
+    }
+}
+
+/// The key after the dotted keys before it, if there are any
+fn dotted(before: &str, key: &str) -> String {
+    match before {
+        "" => toml_key(key),
+        _ => format!("{}.{}", before, toml_key(key)),
+    }
+}
+
+/// A key bare if it can be, or else quoted
+fn toml_key(key: &str) -> String {
+    match !key.is_empty() && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-') {
+        true => key.to_string(),
+        false => toml_string(key),
+    }
+}
+
+/// A string quoted, escaping what TOML doesn't allow in a basic string as it is
+fn toml_string(string: &str) -> String {
+    let mut quoted = String::from("\"");
+    for ch in string.chars() {
+        match ch {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            '\u{8}' => quoted.push_str("\\b"),
+            '\u{c}' => quoted.push_str("\\f"),
+            control if control < ' ' || control == '\u{7f}' => write!(quoted, "\\u{:04X}", control as u32).expect("writing to a String can't fail"),
+            _ => quoted.push(ch),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_str;
+
+    fn toml(input: &str) -> Result<String, String> {
+        to_string_toml(&parse_str(input).unwrap()).map_err(|e| e.message)
+    }
+
+    #[test]
+    fn check_to_string_toml() {
+        let input = r#"{
+            "title": "app", "port": 8080, "ratio": 0.5, "debug": false, "tags": ["a", 1, [true]], "empty": {},
+            "owner": {"name": "Ann \"A\"", "dob": {"year": 1979}, "a.b": {"x": 1}},
+            "servers": [{"host": "alpha", "ip": {"v4": "10.0.0.1"}}, {"host": "beta", "pairs": [{"x": 1}]}]
+        }"#;
+        assert_eq!(toml(input).unwrap(), r#"title = "app"
+port = 8080
+ratio = 0.5
+debug = false
+tags = ["a", 1, [true]]
+empty = {}
+
+[owner]
+name = "Ann \"A\""
+
+[owner.dob]
+year = 1979
+
+[owner."a.b"]
+x = 1
+
+[[servers]]
+host = "alpha"
+
+[servers.ip]
+v4 = "10.0.0.1"
+
+[[servers]]
+host = "beta"
+
+[[servers.pairs]]
+x = 1
+"#);
+        assert_eq!(toml(r#"{"points": [{"x": 1}, 2], "tab\tkey": "line\nbreak"}"#).unwrap(), "points = [{ x = 1 }, 2]\n\"tab\\tkey\" = \"line\\nbreak\"\n");
+        assert_eq!(toml("{}").unwrap(), "");
+    }
+
+    #[test]
+    fn check_to_string_toml_fails_for_what_toml_cannot_express() {
+        assert_eq!(toml("[1, \"two\"]"), Err("a TOML document is a table, so only an object can be written as one, not an array".to_string()));
+        assert_eq!(toml("\"text\""), Err("a TOML document is a table, so only an object can be written as one, not a string".to_string()));
+        assert_eq!(toml(r#"{"a": {"b": [1, null]}}"#), Err("`a.b[1]` is null, which TOML has no way to write".to_string()));
+        assert_eq!(toml(r#"{"list": [{"my key": {"n": null}}]}"#), Err("`list[0].\"my key\".n` is null, which TOML has no way to write".to_string()));
+        assert_eq!(toml(r#"{"n": 9223372036854775808}"#), Err("`n` is 9223372036854775808, which is too big for a TOML integer".to_string()));
+        assert!(toml(r#"{"n": -9223372036854775808, "f": 1e400}"#).is_ok());
+        // Arrays nested `depth` deep in an object
+        let nested = |depth| {
+            let arrays = (1..depth).fold(JsonValue::Array(Vec::new()), |inside, _| JsonValue::Array(vec![inside]));
+            to_string_toml(&JsonValue::Object(vec![("a".to_string(), arrays)])).map_err(|e| e.message)
+        };
+        assert!(nested(DEFAULT_MAX_DEPTH - 1).is_ok());
+        assert_eq!(nested(DEFAULT_MAX_DEPTH), Err("the document nests objects and arrays more than 512 deep, too deep to write as TOML".to_string()));
+    }
+}
@@ -0,0 +1,391 @@
+// Writing JSON back out.
+// Strings are escaped only as much as JSON needs, so anything outside ASCII is written as it is,
+// and numbers are written as they were in the input. A document with comments in it can be laid
+// out from its tokens instead, so the comments between them are kept.
+use crate::{string_value, tokenize_str_with, JsonValue, ParseOptions, Token};
+use cc_diagnostics::Style;
+use std::io::{self, Write};
+use std::iter::Enumerate;
+use std::slice;
+
+pub(crate) const KEY_STYLE: Style = Style::BLUE;
+pub(crate) const STRING_STYLE: Style = Style::GREEN;
+pub(crate) const NUMBER_STYLE: Style = Style::CYAN;
+pub(crate) const LITERAL_STYLE: Style = Style::YELLOW;
+const COMMENT_STYLE: Style = Style::MAGENTA;
+
+/// How write_json lays out a value
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct WriteOptions<'a> {
+    /// What to indent each level of nesting by, with each member and element on its own line, or
+    /// None to write the value on one line without any whitespace
+    pub indent: Option<&'a str>,
+    /// Whether to color keys, strings, numbers, and literals with ANSI escape codes, for a terminal
+    pub color: bool,
+}
+
+/// Write the value the way the options say, without a line break at the end
+pub fn write_json(out: &mut impl Write, value: &JsonValue, options: WriteOptions) -> io::Result<()> {
+    write_value(out, value, &options)
+}
+
+/// Write the value with each member and element on its own line, indented by `indent` for each
+/// level of nesting, and a line break at the end
+pub fn write_pretty(out: &mut impl Write, value: &JsonValue, indent: &str) -> io::Result<()> {
+    write_json(out, value, WriteOptions { indent: Some(indent), color: false })?;
+    out.write_all(b"\n")
+}
+
+/// Write the value without any whitespace between tokens, not even a line break at the end
+pub fn write_compact(out: &mut impl Write, value: &JsonValue) -> io::Result<()> {
+    write_json(out, value, WriteOptions::default())
+}
+
+/// The value as write_pretty writes it
+pub fn to_string_pretty(value: &JsonValue, indent: &str) -> String {
+    let mut out = Vec::new();
+    write_pretty(&mut out, value, indent).expect("writing to a Vec can't fail");
+    String::from_utf8(out).expect("JSON written from strings is UTF-8")
+}
+
+/// The value as write_compact writes it
+pub fn to_string_compact(value: &JsonValue) -> String {
+    let mut out = Vec::new();
+    write_compact(&mut out, value).expect("writing to a Vec can't fail");
+    String::from_utf8(out).expect("JSON written from strings is UTF-8")
+}
+
+/// What write_commented puts between a token and the one before it
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Separator {
+    Nothing,
+    Space,
+    /// A line break, and the indent for this many levels
+    Line(usize),
+}
+
+/// Write `input`, a JSON document that can have `//` and `/* */` comments in it, laid out like
+/// write_json with `indent`, keeping its comments. A comment after something on the same line
+/// stays after it, and one on a line of its own gets a line of its own, indented like what comes
+/// after it. Trailing commas are left out. Input that isn't JSON with comments gives an
+/// InvalidData error.
+pub fn write_commented(out: &mut impl Write, input: &str, indent: &str, color: bool) -> io::Result<()> {
+    let options = ParseOptions { max_depth: None, allow_comments: true, ..ParseOptions::default() };
+    let tokens = tokenize_str_with(input, &options).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    let newline = |out: &mut dyn Write, depth: usize| -> io::Result<()> {
+        out.write_all(b"\n")?;
+        for _ in 0..depth {
+            out.write_all(indent.as_bytes())?;
+        }
+        Ok(())
+    };
+
+    let mut depth: usize = 0;
+    // How far into the input what's been written goes, if it's anything yet
+    let mut end = 0;
+    let mut written = false;
+    for (i, token) in tokens.iter().enumerate() {
+        let closes = matches!(token.value, Token::RightBrace | Token::RightBracket);
+        // A trailing comma is left out, with the comments around it kept for what comes next
+        if token.value == Token::Comma && tokens.get(i + 1).is_some_and(|next| matches!(next.value, Token::RightBrace | Token::RightBracket)) {
+            continue;
+        }
+        if closes {
+            depth = depth.saturating_sub(1);
+        }
+        let previous = i.checked_sub(1).map(|i| &tokens[i].value);
+        let mut separator = match (previous, &token.value) {
+            (None, _) | (_, Token::Eof) => Separator::Nothing,
+            // Nothing inside to lay out
+            (Some(Token::LeftBrace), Token::RightBrace) | (Some(Token::LeftBracket), Token::RightBracket) => Separator::Nothing,
+            (Some(Token::LeftBrace | Token::LeftBracket | Token::Comma), _) | (_, Token::RightBrace | Token::RightBracket) => Separator::Line(depth),
+            (Some(Token::Colon), _) => Separator::Space,
+            _ => Separator::Nothing,
+        };
+        // A comment on a line of its own goes with what's inside an object or array, even before
+        // the end of it
+        let comment_depth = match (&token.value, separator) {
+            _ if previous.is_none() || token.value == Token::Eof => 0,
+            (_, Separator::Line(depth)) if !closes => depth,
+            _ => depth + 1,
+        };
+        for (own_line, comment) in comments_in(&input[end..token.span.start]) {
+            match (own_line, written) {
+                (_, false) => {},
+                (true, true) => newline(out, comment_depth)?,
+                (false, true) => out.write_all(b" ")?,
+            }
+            write_styled(out, comment, COMMENT_STYLE, color)?;
+            written = true;
+            // What comes after a line comment, or one on a line of its own, can't carry on the line
+            if (own_line || comment.starts_with("//")) && token.value != Token::Eof && !matches!(separator, Separator::Line(_)) {
+                separator = Separator::Line(if closes { depth } else { comment_depth });
+            }
+        }
+        match separator {
+            Separator::Nothing => {},
+            Separator::Space => out.write_all(b" ")?,
+            Separator::Line(depth) => newline(out, depth)?,
+        }
+        match &token.value {
+            Token::LeftBrace => out.write_all(b"{")?,
+            Token::RightBrace => out.write_all(b"}")?,
+            Token::LeftBracket => out.write_all(b"[")?,
+            Token::RightBracket => out.write_all(b"]")?,
+            Token::Colon => out.write_all(b":")?,
+            Token::Comma => out.write_all(b",")?,
+            Token::String(raw) => {
+                let key = tokens.get(i + 1).is_some_and(|next| next.value == Token::Colon);
+                let string = string_value(raw).expect("the tokenizer checked the string");
+                write_styled_string(out, &string, if key { KEY_STYLE } else { STRING_STYLE }, color)?;
+            },
+            Token::Number(number) => write_styled(out, number, NUMBER_STYLE, color)?,
+            Token::Identifier(name) => write_styled_string(out, name, KEY_STYLE, color)?,
+            Token::True => write_styled(out, "true", LITERAL_STYLE, color)?,
+            Token::False => write_styled(out, "false", LITERAL_STYLE, color)?,
+            Token::Null => write_styled(out, "null", LITERAL_STYLE, color)?,
+            Token::Eof => {},
+        }
+        if matches!(token.value, Token::LeftBrace | Token::LeftBracket) {
+            depth += 1;
+        }
+        end = token.span.end;
+        written = true;
+    }
+    Ok(())
+}
+
+/// The comments in what's between two tokens, each with whether it's on a line of its own. The
+/// rest is whitespace, or the byte order mark before the first token.
+fn comments_in(between: &str) -> Vec<(bool, &str)> {
+    let mut comments = Vec::new();
+    let mut rest = between;
+    while let Some(start) = rest.find('/') {
+        let own_line = rest[..start].contains('\n');
+        let comment = &rest[start..];
+        let length = match comment.starts_with("//") {
+            true => comment.find('\n').unwrap_or(comment.len()),
+            false => comment[2..].find("*/").map_or(comment.len(), |end| end + 4),
+        };
+        comments.push((own_line, comment[..length].trim_end()));
+        rest = &comment[length..];
+    }
+    comments
+}
+
+/// An object or array being written, with the members or elements still to write. The ones it's
+/// in are kept on a stack rather than the call stack, so however deep a document nests it can be
+/// written, as it can be parsed.
+enum Open<'a> {
+    Object(Enumerate<slice::Iter<'a, (String, JsonValue)>>),
+    Array(Enumerate<slice::Iter<'a, JsonValue>>),
+}
+
+fn write_value(out: &mut impl Write, value: &JsonValue, options: &WriteOptions) -> io::Result<()> {
+    let newline = |out: &mut dyn Write, depth: usize| -> io::Result<()> {
+        let Some(indent) = options.indent else {
+            return Ok(());
+        };
+        out.write_all(b"\n")?;
+        for _ in 0..depth {
+            out.write_all(indent.as_bytes())?;
+        }
+        Ok(())
+    };
+
+    let mut open: Vec<Open> = Vec::new();
+    let mut next = Some(value);
+    loop {
+        match next.take() {
+            // Nothing inside to lay out
+            Some(JsonValue::Object(members)) if members.is_empty() => out.write_all(b"{}")?,
+            Some(JsonValue::Array(elements)) if elements.is_empty() => out.write_all(b"[]")?,
+            Some(JsonValue::Object(members)) => {
+                out.write_all(b"{")?;
+                open.push(Open::Object(members.iter().enumerate()));
+            },
+            Some(JsonValue::Array(elements)) => {
+                out.write_all(b"[")?;
+                open.push(Open::Array(elements.iter().enumerate()));
+            },
+            Some(JsonValue::String(string)) => write_styled_string(out, string, STRING_STYLE, options.color)?,
+            Some(JsonValue::Number(number)) => write_styled(out, number, NUMBER_STYLE, options.color)?,
+            Some(JsonValue::Bool(true)) => write_styled(out, "true", LITERAL_STYLE, options.color)?,
+            Some(JsonValue::Bool(false)) => write_styled(out, "false", LITERAL_STYLE, options.color)?,
+            Some(JsonValue::Null) => write_styled(out, "null", LITERAL_STYLE, options.color)?,
+            None => {},
+        }
+
+        // Go on to the next member or element of the innermost open object or array, or close it
+        let depth = open.len();
+        let member = match open.last_mut() {
+            None => return Ok(()),
+            Some(Open::Object(members)) => members.next().map(|(i, (key, value))| (i, Some(key), value)),
+            Some(Open::Array(elements)) => elements.next().map(|(i, element)| (i, None, element)),
+        };
+        let Some((i, key, value)) = member else {
+            let close = match open.pop() {
+                Some(Open::Object(_)) => b"}",
+                _ => b"]",
+            };
+            newline(out, depth - 1)?;
+            out.write_all(close)?;
+            continue;
+        };
+        if i > 0 {
+            out.write_all(b",")?;
+        }
+        newline(out, depth)?;
+        if let Some(key) = key {
+            write_styled_string(out, key, KEY_STYLE, options.color)?;
+            out.write_all(if options.indent.is_some() { b": " } else { b":" })?;
+        }
+        next = Some(value);
+    }
+}
+
+/// Write a token, in its style if coloring
+pub(crate) fn write_styled(out: &mut impl Write, text: &str, style: Style, color: bool) -> io::Result<()> {
+    match color {
+        true => out.write_all(style.paint(text, true).as_bytes()),
+        false => out.write_all(text.as_bytes()),
+    }
+}
+
+/// Write a string quoted and escaped, in its style if coloring
+fn write_styled_string(out: &mut impl Write, string: &str, style: Style, color: bool) -> io::Result<()> {
+    if !color {
+        return write_string(out, string);
+    }
+    let mut quoted = Vec::new();
+    write_string(&mut quoted, string)?;
+    write_styled(out, &String::from_utf8(quoted).expect("JSON written from strings is UTF-8"), style, true)
+}
+
+/// Write a string quoted, escaping what JSON doesn't allow as it is
+pub(crate) fn write_string(out: &mut impl Write, string: &str) -> io::Result<()> {
+    out.write_all(b"\"")?;
+    let mut written = 0;
+    for (i, ch) in string.char_indices() {
+        let escape = match ch {
+            '"' => Some("\\\""),
+            '\\' => Some("\\\\"),
+            '\n' => Some("\\n"),
+            '\r' => Some("\\r"),
+            '\t' => Some("\\t"),
+            '\u{8}' => Some("\\b"),
+            '\u{c}' => Some("\\f"),
+            // The rest of the control characters only have the long form
+            control if control < ' ' => None,
+            _ => continue,
+        };
+        out.write_all(&string.as_bytes()[written..i])?;
+        match escape {
+            Some(escape) => out.write_all(escape.as_bytes())?,
+            None => write!(out, "\\u{:04x}", ch as u32)?,
+        }
+        written = i + ch.len_utf8();
+    }
+    out.write_all(&string.as_bytes()[written..])?;
+    out.write_all(b"\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_str;
+
+    #[test]
+    fn check_to_string_pretty() {
+        let value = parse_str(r#"{"a": [1, -2.5e3, {"b": null}], "c": {}, "d": [], "e": true}"#).unwrap();
+        assert_eq!(to_string_pretty(&value, "  "), concat!(
+            "{\n",
+            "  \"a\": [\n",
+            "    1,\n",
+            "    -2.5e3,\n",
+            "    {\n",
+            "      \"b\": null\n",
+            "    }\n",
+            "  ],\n",
+            "  \"c\": {},\n",
+            "  \"d\": [],\n",
+            "  \"e\": true\n",
+            "}\n",
+        ));
+        assert_eq!(to_string_pretty(&value, "\t").lines().nth(2), Some("\t\t1,"));
+        assert_eq!(to_string_pretty(&JsonValue::Null, "  "), "null\n");
+    }
+
+    #[test]
+    fn check_to_string_compact() {
+        let value = parse_str("{ \"a\" : [ 1 , -2.5e3 , { \"b\" : null } ] ,\n \"c\" : { } , \"d\" : \" x y \" }").unwrap();
+        assert_eq!(to_string_compact(&value), r#"{"a":[1,-2.5e3,{"b":null}],"c":{},"d":" x y "}"#);
+        assert_eq!(to_string_compact(&JsonValue::Array(Vec::new())), "[]");
+    }
+
+    #[test]
+    fn check_write_colored() {
+        let value = parse_str(r#"{"a": ["x", 1, true, null], "b": {}}"#).unwrap();
+        let mut out = Vec::new();
+        write_json(&mut out, &value, WriteOptions { indent: None, color: true }).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), concat!(
+            "{\x1b[1;34m\"a\"\x1b[0m:[\x1b[32m\"x\"\x1b[0m,\x1b[36m1\x1b[0m,\x1b[1;33mtrue\x1b[0m,\x1b[1;33mnull\x1b[0m],",
+            "\x1b[1;34m\"b\"\x1b[0m:{}}",
+        ));
+    }
+
+    #[test]
+    fn check_write_commented() {
+        let input = concat!(
+            "// settings\n",
+            "{\"a\": 1, // one\n",
+            "  /* two */ \"b\": [true, // yes\n",
+            "    // still to come\n",
+            "  ], \"c\": {\n",
+            "    // nothing yet\n",
+            "  }, \"d\": \"\\/\" /* escaped */\n",
+            "}\n",
+        );
+        let mut out = Vec::new();
+        write_commented(&mut out, input, "  ", false).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), concat!(
+            "// settings\n",
+            "{\n",
+            "  \"a\": 1, // one\n",
+            "  /* two */\n",
+            "  \"b\": [\n",
+            "    true // yes\n",
+            "    // still to come\n",
+            "  ],\n",
+            "  \"c\": {\n",
+            "    // nothing yet\n",
+            "  },\n",
+            "  \"d\": \"/\" /* escaped */\n",
+            "}",
+        ));
+
+        // Without comments it's laid out the way write_json lays it out
+        let input = r#"{"a": [1, -2.5e3, {"b": null}], "c": {}, "d": []}"#;
+        let mut out = Vec::new();
+        write_commented(&mut out, input, "\t", true).unwrap();
+        let mut expected = Vec::new();
+        write_json(&mut expected, &parse_str(input).unwrap(), WriteOptions { indent: Some("\t"), color: true }).unwrap();
+        assert_eq!(out, expected);
+
+        let mut out = Vec::new();
+        write_commented(&mut out, "[1, // one\n 2, // two\n]", "  ", false).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "[\n  1, // one\n  2 // two\n]");
+
+        let e = write_commented(&mut Vec::new(), "[1 /* two", "  ", false).unwrap_err();
+        assert_eq!(e.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn check_strings_round_trip() {
+        let input = r#"["quote \" backslash \\ slash \/ tab \t nul \u0000 bell \u0007 café 😀 é"]"#;
+        let value = parse_str(input).unwrap();
+        let written = to_string_pretty(&value, "");
+        assert_eq!(written, "[\n\"quote \\\" backslash \\\\ slash / tab \\t nul \\u0000 bell \\u0007 café 😀 é\"\n]\n");
+        assert_eq!(parse_str(&written), Ok(value));
+    }
+}
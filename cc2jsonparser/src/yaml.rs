@@ -0,0 +1,204 @@
+// Writing JSON as YAML.
+// Every JSON document is a YAML 1.2 one already, but --to yaml writes the block style people
+// write by hand: a member or element on each line, nested ones indented under them. Strings are
+// left unquoted when YAML would read them back as the same string, whether as 1.2 or as the 1.1
+// that many parsers still are, and written double-quoted otherwise, with JSON's escapes, which
+// YAML has too. Numbers are written as they were in the input, which YAML reads as numbers.
+// Reference:  https://yaml.org/spec/1.2.2/
+use crate::JsonValue;
+use std::io::{self, Write};
+use std::iter::Enumerate;
+use std::slice;
+
+/// How many spaces each level of nesting is indented by
+const INDENT: usize = 2;
+
+/// Write the value as a YAML document, without a line break at the end
+pub fn write_yaml(out: &mut impl Write, value: &JsonValue) -> io::Result<()> {
+    match value {
+        JsonValue::Object(members) if !members.is_empty() => write_block(out, value, 0),
+        JsonValue::Array(elements) if !elements.is_empty() => write_block(out, value, 0),
+        _ => write_scalar(out, value),
+    }
+}
+
+/// The value as write_yaml writes it, with a line break at the end
+pub fn to_string_yaml(value: &JsonValue) -> String {
+    let mut out = Vec::new();
+    write_yaml(&mut out, value).expect("writing to a Vec can't fail");
+    out.push(b'\n');
+    String::from_utf8(out).expect("YAML written from strings is UTF-8")
+}
+
+/// An object or array being written, with the members or elements still to write and how many
+/// spaces they're indented by. The ones it's in are kept on a stack rather than the call stack, so
+/// however deep a document nests it can be written.
+enum Open<'a> {
+    Object(Enumerate<slice::Iter<'a, (String, JsonValue)>>, usize),
+    Array(Enumerate<slice::Iter<'a, JsonValue>>, usize),
+}
+
+impl<'a> Open<'a> {
+    /// An object or array with something in it, to write its members or elements indented by
+    /// `indent` spaces
+    fn new(value: &'a JsonValue, indent: usize) -> Open<'a> {
+        match value {
+            JsonValue::Object(members) => Open::Object(members.iter().enumerate(), indent),
+            JsonValue::Array(elements) => Open::Array(elements.iter().enumerate(), indent),
+            _ => unreachable!("only objects and arrays are written in block style"),
+        }
+    }
+}
+
+/// Write an object or array with something in it one member or element to a line, the first
+/// carrying on from where the line is already and the rest indented by `indent` spaces
+fn write_block(out: &mut impl Write, value: &JsonValue, indent: usize) -> io::Result<()> {
+    let newline = |out: &mut dyn Write, indent: usize| write!(out, "\n{:indent$}", "");
+    let mut open = vec![Open::new(value, indent)];
+    while let Some(innermost) = open.last_mut() {
+        let (value, indent) = match innermost {
+            Open::Object(members, indent) => {
+                let indent = *indent;
+                let Some((i, (key, value))) = members.next() else {
+                    open.pop();
+                    continue;
+                };
+                if i > 0 {
+                    newline(out, indent)?;
+                }
+                write_string(out, key)?;
+                out.write_all(b":")?;
+                if is_block(value) {
+                    newline(out, indent + INDENT)?;
+                } else {
+                    out.write_all(b" ")?;
+                }
+                (value, indent)
+            },
+            Open::Array(elements, indent) => {
+                let indent = *indent;
+                let Some((i, element)) = elements.next() else {
+                    open.pop();
+                    continue;
+                };
+                if i > 0 {
+                    newline(out, indent)?;
+                }
+                // What's nested in an element starts on the same line as its `-`
+                out.write_all(b"- ")?;
+                (element, indent)
+            },
+        };
+        match is_block(value) {
+            true => open.push(Open::new(value, indent + INDENT)),
+            false => write_scalar(out, value)?,
+        }
+    }
+    Ok(())
+}
+
+/// Whether the value is written in block style, over lines of its own, rather than on one line
+fn is_block(value: &JsonValue) -> bool {
+    match value {
+        JsonValue::Object(members) => !members.is_empty(),
+        JsonValue::Array(elements) => !elements.is_empty(),
+        _ => false,
+    }
+}
+
+/// Write a value that goes on one line, an empty object or array being written in flow style
+fn write_scalar(out: &mut impl Write, value: &JsonValue) -> io::Result<()> {
+    match value {
+        JsonValue::Object(_) => out.write_all(b"{}"),
+        JsonValue::Array(_) => out.write_all(b"[]"),
+        JsonValue::String(string) => write_string(out, string),
+        JsonValue::Number(number) => out.write_all(number.as_bytes()),
+        JsonValue::Bool(bool) => write!(out, "{}", bool),
+        JsonValue::Null => out.write_all(b"null"),
+    }
+}
+
+/// Write a string plainly if it can be, or else double-quoted
+fn write_string(out: &mut impl Write, string: &str) -> io::Result<()> {
+    if is_plain(string) {
+        return out.write_all(string.as_bytes());
+    }
+    out.write_all(b"\"")?;
+    for ch in string.chars() {
+        match ch {
+            '"' => out.write_all(b"\\\"")?,
+            '\\' => out.write_all(b"\\\\")?,
+            '\n' => out.write_all(b"\\n")?,
+            '\r' => out.write_all(b"\\r")?,
+            '\t' => out.write_all(b"\\t")?,
+            // YAML doesn't allow control characters, the byte order mark, or the line and paragraph
+            // separators as they are
+            control if control < ' ' || ('\u{7f}'..='\u{9f}').contains(&control) => write!(out, "\\u{:04x}", control as u32)?,
+            '\u{feff}' | '\u{2028}' | '\u{2029}' => write!(out, "\\u{:04x}", ch as u32)?,
+            _ => write!(out, "{}", ch)?,
+        }
+    }
+    out.write_all(b"\"")
+}
+
+/// Whether a string can be written without quotes and read back the same: starting with a letter
+/// or `_` and going on with only letters, digits, spaces, and `_-./`, not ending with a space,
+/// and not a word YAML reads as null or a boolean
+fn is_plain(string: &str) -> bool {
+    let Some(first) = string.chars().next() else {
+        return false;
+    };
+    let reserved = ["null", "true", "false", "yes", "no", "on", "off", "y", "n"];
+    (first.is_alphabetic() || first == '_')
+        && string.chars().all(|c| c.is_alphanumeric() || matches!(c, ' ' | '_' | '-' | '.' | '/'))
+        && !string.ends_with(' ')
+        && !reserved.iter().any(|word| string.eq_ignore_ascii_case(word))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_str;
+
+    fn yaml(input: &str) -> String {
+        to_string_yaml(&parse_str(input).unwrap())
+    }
+
+    #[test]
+    fn check_write_yaml() {
+        let input = r#"{"name": "app", "ports": [80, 443], "env": {"DEBUG": false, "paths": []}, "deps": [{"name": "a", "tags": ["x"]}, [1, [2]]], "none": null, "empty": {}}"#;
+        assert_eq!(yaml(input), "\
+name: app
+ports:
+  - 80
+  - 443
+env:
+  DEBUG: false
+  paths: []
+deps:
+  - name: a
+    tags:
+      - x
+  - - 1
+    - - 2
+none: null
+empty: {}
+");
+        assert_eq!(yaml("1.5e3"), "1.5e3\n");
+        assert_eq!(yaml("[]"), "[]\n");
+    }
+
+    #[test]
+    fn check_write_yaml_quotes_strings_read_as_something_else() {
+        assert_eq!(yaml(r#"["plain text", "v1.2/x_y-z"]"#), "- plain text\n- v1.2/x_y-z\n");
+        assert_eq!(
+            yaml(r##"["", "true", "No", "null", "12", "-1", " lead", "trail ", "a: b", "# c", "- d", "*e", "line\nbreak", "tab\there", "q\"\\", "\u0007"]"##),
+            concat!(
+                "- \"\"\n- \"true\"\n- \"No\"\n- \"null\"\n- \"12\"\n- \"-1\"\n- \" lead\"\n- \"trail \"\n- \"a: b\"\n- \"# c\"\n- \"- d\"\n",
+                "- \"*e\"\n- \"line\\nbreak\"\n- \"tab\\there\"\n- \"q\\\"\\\\\"\n- \"\\u0007\"\n",
+            ),
+        );
+        // Keys too
+        assert_eq!(yaml(r#"{"on": 1, "a b": 2, "": 3}"#), "\"on\": 1\na b: 2\n\"\": 3\n");
+    }
+}
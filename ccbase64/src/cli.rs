@@ -0,0 +1,70 @@
+use crate::{Alphabet, Decoder, Encoder, DEFAULT_WRAP};
+use anyhow::{Context, Result};
+use cc_cli::{is_broken_pipe, BROKEN_PIPE_EXIT_CODE};
+use cc_io::Source;
+use clap::Parser;
+use std::ffi::OsString;
+use std::io::{stdout, BufWriter};
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+/// base64 - encode data as base64, or decode it back
+#[derive(Parser)]
+struct Cli {
+    /// Decode instead of encoding
+    #[arg(short, long)]
+    decode: bool,
+
+    /// Wrap encoded lines after this many characters, 0 for no wrapping
+    #[arg(short, long, value_name = "COLS", default_value_t = DEFAULT_WRAP)]
+    wrap: usize,
+
+    /// Use the URL and filename safe alphabet, with - and _ in place of + and /
+    #[arg(short, long)]
+    url: bool,
+
+    /// When decoding, skip characters outside the alphabet instead of failing on them
+    #[arg(short, long, requires = "decode")]
+    ignore_garbage: bool,
+
+    /// The file to read, or - for stdin
+    input: Option<PathBuf>,
+}
+
+/// Run ccbase64 with the given command line, `args[0]` being the program name
+pub fn main<I, T>(args: I) -> ExitCode
+where
+    I: IntoIterator<Item = T>,
+    T: Into<OsString> + Clone,
+{
+    let args: Cli = cc_cli::parse_from(args, env!("CARGO_PKG_VERSION"));
+
+    match run(args) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) if is_broken_pipe(&e) => ExitCode::from(BROKEN_PIPE_EXIT_CODE),
+        Err(e) => {
+            eprintln!("Error: {:?}", e);
+            ExitCode::FAILURE
+        },
+    }
+}
+
+fn run(args: Cli) -> Result<()> {
+    let source = Source::from_arg(args.input.as_deref());
+    let mut input = cc_io::open(&source)?;
+    let alphabet = if args.url { Alphabet::UrlSafe } else { Alphabet::Standard };
+    let out = BufWriter::new(stdout().lock());
+
+    if args.decode {
+        let mut decoder = Decoder::new(out, alphabet, args.ignore_garbage);
+        std::io::copy(&mut input.reader, &mut decoder)
+            .and_then(|_| decoder.finish())
+            .with_context(|| format!("could not decode `{}`", source.label()))?;
+    } else {
+        let mut encoder = Encoder::new(out, alphabet, args.wrap);
+        std::io::copy(&mut input.reader, &mut encoder)
+            .and_then(|_| encoder.finish())
+            .with_context(|| format!("could not encode `{}`", source.label()))?;
+    }
+    Ok(())
+}
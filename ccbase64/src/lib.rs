@@ -0,0 +1,289 @@
+// Base64 encoding and decoding, as in RFC 4648. Every 3 bytes become 4 characters of 6 bits each,
+// with `=` padding out the last group, e.g.
+//
+//   Many hands  ->  TWFueSBoYW5kcw==
+//
+// Both directions are writers that transform whatever passes through them, so a file of any size
+// only needs a buffer's worth in memory.
+// Reference:  https://datatracker.ietf.org/doc/html/rfc4648
+use std::io::{self, ErrorKind, Write};
+
+mod cli;
+
+pub use cli::main;
+
+/// Characters per line in the output of GNU base64
+pub const DEFAULT_WRAP: usize = 76;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Alphabet {
+    Standard,
+    /// The URL and filename safe alphabet, with `-` and `_` in place of `+` and `/`
+    UrlSafe,
+}
+
+impl Alphabet {
+    fn symbols(self) -> &'static [u8; 64] {
+        match self {
+            Alphabet::Standard => b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/",
+            Alphabet::UrlSafe => b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_",
+        }
+    }
+
+    fn value(self, symbol: u8) -> Option<u8> {
+        match symbol {
+            b'A'..=b'Z' => Some(symbol - b'A'),
+            b'a'..=b'z' => Some(symbol - b'a' + 26),
+            b'0'..=b'9' => Some(symbol - b'0' + 52),
+            b'+' if self == Alphabet::Standard => Some(62),
+            b'/' if self == Alphabet::Standard => Some(63),
+            b'-' if self == Alphabet::UrlSafe => Some(62),
+            b'_' if self == Alphabet::UrlSafe => Some(63),
+            _ => None,
+        }
+    }
+}
+
+/// Writes the base64 of everything written to it to `out`, wrapping lines after `wrap`
+/// characters unless that's 0. Call `finish` at the end to write the last group.
+pub struct Encoder<W: Write> {
+    out: W,
+    alphabet: Alphabet,
+    wrap: usize,
+    column: usize,
+    /// Bytes short of a group of 3, waiting for more
+    pending: Vec<u8>,
+}
+
+impl<W: Write> Encoder<W> {
+    pub fn new(out: W, alphabet: Alphabet, wrap: usize) -> Encoder<W> {
+        Encoder { out, alphabet, wrap, column: 0, pending: Vec::with_capacity(3) }
+    }
+
+    /// Write characters, breaking lines where they're due
+    fn emit(&mut self, symbols: &[u8]) -> io::Result<()> {
+        for &symbol in symbols {
+            if self.wrap > 0 && self.column == self.wrap {
+                self.out.write_all(b"\n")?;
+                self.column = 0;
+            }
+            self.out.write_all(&[symbol])?;
+            self.column += 1;
+        }
+        Ok(())
+    }
+
+    fn encode_group(&mut self, group: &[u8]) -> io::Result<()> {
+        let symbols = self.alphabet.symbols();
+        let bits = u32::from(group[0]) << 16
+            | u32::from(*group.get(1).unwrap_or(&0)) << 8
+            | u32::from(*group.get(2).unwrap_or(&0));
+        let mut encoded = [b'='; 4];
+        for (i, symbol) in encoded.iter_mut().enumerate().take(group.len() + 1) {
+            *symbol = symbols[(bits >> (18 - 6 * i) & 0x3f) as usize];
+        }
+        self.emit(&encoded)
+    }
+
+    /// Write the last group, padded, and the newline ending the last line when wrapping, giving
+    /// back the writer
+    pub fn finish(mut self) -> io::Result<W> {
+        if !self.pending.is_empty() {
+            let pending = std::mem::take(&mut self.pending);
+            self.encode_group(&pending)?;
+        }
+        if self.wrap > 0 && self.column > 0 {
+            self.out.write_all(b"\n")?;
+        }
+        self.out.flush()?;
+        Ok(self.out)
+    }
+}
+
+impl<W: Write> Write for Encoder<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut bytes = buf;
+        if !self.pending.is_empty() {
+            let taken = bytes.len().min(3 - self.pending.len());
+            self.pending.extend_from_slice(&bytes[..taken]);
+            bytes = &bytes[taken..];
+            if self.pending.len() < 3 {
+                return Ok(buf.len());
+            }
+            let pending = std::mem::take(&mut self.pending);
+            self.encode_group(&pending)?;
+        }
+        let mut groups = bytes.chunks_exact(3);
+        for group in &mut groups {
+            self.encode_group(group)?;
+        }
+        self.pending.extend_from_slice(groups.remainder());
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.out.flush()
+    }
+}
+
+/// Writes the bytes that everything written to it encodes to `out`. Line breaks are skipped, and
+/// so is anything else outside the alphabet when ignoring garbage, otherwise it's an error. Call
+/// `finish` at the end to check the input didn't stop partway through a group.
+pub struct Decoder<W: Write> {
+    out: W,
+    alphabet: Alphabet,
+    ignore_garbage: bool,
+    /// The 6 bit values of the group so far
+    group: Vec<u8>,
+    /// How many `=` the group has ended with
+    padding: usize,
+    /// Whether a padded group has ended the input
+    ended: bool,
+    /// How far into the input we are, for errors
+    offset: u64,
+}
+
+fn invalid(message: String) -> io::Error {
+    io::Error::new(ErrorKind::InvalidData, message)
+}
+
+impl<W: Write> Decoder<W> {
+    pub fn new(out: W, alphabet: Alphabet, ignore_garbage: bool) -> Decoder<W> {
+        Decoder { out, alphabet, ignore_garbage, group: Vec::with_capacity(4), padding: 0, ended: false, offset: 0 }
+    }
+
+    fn decode_symbol(&mut self, symbol: u8) -> io::Result<()> {
+        let value = self.alphabet.value(symbol);
+        let at = || format!("at byte {}", self.offset);
+        match (symbol, value) {
+            (b'\n' | b'\r', _) => return Ok(()),
+            (_, Some(_)) | (b'=', _) if self.ended || self.padding > 0 && symbol != b'=' => {
+                return Err(invalid(format!("data after padding {}", at())));
+            },
+            (b'=', _) if self.group.len() < 2 => return Err(invalid(format!("unexpected padding {}", at()))),
+            (b'=', _) => self.padding += 1,
+            (_, Some(value)) => self.group.push(value),
+            _ if self.ignore_garbage => return Ok(()),
+            _ => return Err(invalid(format!("invalid character `{}` {}", symbol.escape_ascii(), at()))),
+        }
+
+        if self.group.len() + self.padding == 4 {
+            let bits = self.group.iter().fold(0u32, |bits, &value| bits << 6 | u32::from(value)) << (6 * self.padding);
+            let bytes = bits.to_be_bytes();
+            self.out.write_all(&bytes[1..self.group.len()])?;
+            self.ended = self.padding > 0;
+            self.group.clear();
+            self.padding = 0;
+        }
+        Ok(())
+    }
+
+    /// Check the input ended with a whole group, giving back the writer
+    pub fn finish(mut self) -> io::Result<W> {
+        if !self.group.is_empty() {
+            return Err(invalid("input ends partway through a group, missing padding?".to_string()));
+        }
+        self.out.flush()?;
+        Ok(self.out)
+    }
+}
+
+impl<W: Write> Write for Decoder<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        for &symbol in buf {
+            self.offset += 1;
+            self.decode_symbol(symbol)?;
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.out.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encode(input: &[u8], alphabet: Alphabet, wrap: usize) -> String {
+        let mut encoder = Encoder::new(Vec::new(), alphabet, wrap);
+        encoder.write_all(input).unwrap();
+        String::from_utf8(encoder.finish().unwrap()).unwrap()
+    }
+
+    fn decode(input: &str, alphabet: Alphabet, ignore_garbage: bool) -> io::Result<Vec<u8>> {
+        let mut decoder = Decoder::new(Vec::new(), alphabet, ignore_garbage);
+        decoder.write_all(input.as_bytes())?;
+        decoder.finish()
+    }
+
+    /// The test vectors from RFC 4648
+    const VECTORS: [(&str, &str); 7] = [
+        ("", ""),
+        ("f", "Zg=="),
+        ("fo", "Zm8="),
+        ("foo", "Zm9v"),
+        ("foob", "Zm9vYg=="),
+        ("fooba", "Zm9vYmE="),
+        ("foobar", "Zm9vYmFy"),
+    ];
+
+    #[test]
+    fn check_encode() {
+        for (input, output) in VECTORS {
+            assert_eq!(encode(input.as_bytes(), Alphabet::Standard, 0), output);
+        }
+        assert_eq!(encode(b"Many hands", Alphabet::Standard, 76), "TWFueSBoYW5kcw==\n");
+        assert_eq!(encode(b"\xfb\xff", Alphabet::Standard, 0), "+/8=");
+        assert_eq!(encode(b"\xfb\xff", Alphabet::UrlSafe, 0), "-_8=");
+    }
+
+    #[test]
+    fn check_encode_wrap() {
+        assert_eq!(encode(b"foobar", Alphabet::Standard, 3), "Zm9\nvYm\nFy\n");
+        assert_eq!(encode(b"foobar", Alphabet::Standard, 4), "Zm9v\nYmFy\n");
+        assert_eq!(encode(b"", Alphabet::Standard, 4), "");
+    }
+
+    #[test]
+    fn check_encode_stream() {
+        // However the input is split up, it comes out the same
+        let input: Vec<u8> = (0..=255).collect();
+        let mut encoder = Encoder::new(Vec::new(), Alphabet::Standard, 76);
+        for chunk in input.chunks(5).chain(input.chunks(1)) {
+            encoder.write_all(chunk).unwrap();
+        }
+        let whole = encode(&[&input[..], &input[..]].concat(), Alphabet::Standard, 76);
+        assert_eq!(String::from_utf8(encoder.finish().unwrap()).unwrap(), whole);
+    }
+
+    #[test]
+    fn check_decode() {
+        for (output, input) in VECTORS {
+            assert_eq!(decode(input, Alphabet::Standard, false).unwrap(), output.as_bytes());
+        }
+        assert_eq!(decode("Zm9v\nYmFy\r\n", Alphabet::Standard, false).unwrap(), b"foobar");
+        assert_eq!(decode("-_8=", Alphabet::UrlSafe, false).unwrap(), b"\xfb\xff");
+
+        let input: Vec<u8> = (0..=255).collect();
+        assert_eq!(decode(&encode(&input, Alphabet::UrlSafe, 10), Alphabet::UrlSafe, false).unwrap(), input);
+    }
+
+    #[test]
+    fn check_decode_garbage() {
+        let err = |input, alphabet| decode(input, alphabet, false).unwrap_err().to_string();
+        assert_eq!(err("Zm9v YmFy", Alphabet::Standard), "invalid character ` ` at byte 5");
+        assert_eq!(err("+/8=", Alphabet::UrlSafe), "invalid character `+` at byte 1");
+        assert_eq!(decode("Zm9v *YmFy!", Alphabet::Standard, true).unwrap(), b"foobar");
+    }
+
+    #[test]
+    fn check_decode_padding() {
+        let err = |input| decode(input, Alphabet::Standard, false).unwrap_err().to_string();
+        assert_eq!(err("Zg==Zg=="), "data after padding at byte 5");
+        assert_eq!(err("Zg=a"), "data after padding at byte 4");
+        assert_eq!(err("Z==="), "unexpected padding at byte 2");
+        assert_eq!(err("Zm9"), "input ends partway through a group, missing padding?");
+    }
+}
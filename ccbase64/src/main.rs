@@ -0,0 +1,3 @@
+fn main() -> std::process::ExitCode {
+    ccbase64::main(std::env::args_os())
+}
@@ -0,0 +1,54 @@
+use assert_cmd::Command;
+
+fn ccbase64() -> Command {
+    let mut cmd = Command::cargo_bin("ccbase64").unwrap();
+    cmd.current_dir(env!("CARGO_MANIFEST_DIR"));
+    cmd
+}
+
+const FOX: &str = "The quick brown fox\njumps over\tthe lazy dog.\n";
+const FOX_BASE64: &str = "VGhlIHF1aWNrIGJyb3duIGZveApqdW1wcyBvdmVyCXRoZSBsYXp5IGRvZy4K\n";
+
+#[test]
+fn run_base64_encode_file() {
+    ccbase64().arg("testinputs/fox.txt").assert().success().stdout(FOX_BASE64);
+}
+
+#[test]
+fn run_base64_wrap() {
+    ccbase64()
+        .args(["-w", "20", "testinputs/fox.txt"])
+        .assert()
+        .success()
+        .stdout("VGhlIHF1aWNrIGJyb3du\nIGZveApqdW1wcyBvdmVy\nCXRoZSBsYXp5IGRvZy4K\n");
+    ccbase64().args(["-w", "0"]).write_stdin("hi").assert().success().stdout("aGk=");
+}
+
+#[test]
+fn run_base64_decode() {
+    ccbase64().arg("-d").write_stdin(FOX_BASE64).assert().success().stdout(FOX);
+    ccbase64().args(["-d", "-u"]).write_stdin("-_8=\n").assert().success().stdout(&b"\xfb\xff"[..]);
+    ccbase64().args(["-u", "-w", "0"]).write_stdin(&b"\xfb\xff"[..]).assert().success().stdout("-_8=");
+}
+
+#[test]
+fn run_base64_garbage() {
+    ccbase64()
+        .arg("-d")
+        .write_stdin("aGk=*")
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains("could not decode `<stdin>`"));
+    ccbase64().args(["-d", "-i"]).write_stdin("a G*k=\n").assert().success().stdout("hi");
+    // Only decoding has garbage to ignore
+    ccbase64().arg("-i").write_stdin("hi").assert().code(2);
+}
+
+#[test]
+fn run_base64_round_trip_large() {
+    let input: Vec<u8> = (0..200_000u32).map(|i| (i * 7 % 251) as u8).collect();
+    let encoded = ccbase64().write_stdin(input.clone()).output().unwrap();
+    assert!(encoded.status.success());
+    assert!(encoded.stdout.split(|&b| b == b'\n').all(|line| line.len() <= 76));
+    ccbase64().arg("--decode").write_stdin(encoded.stdout).assert().success().stdout(input);
+}
@@ -0,0 +1,206 @@
+// Multicall binary bundling every tool in the workspace, like busybox.
+// Run a tool as a subcommand, `ccbox wc file.txt`, or through a link named after it,
+// `ln -s ccbox wc && ./wc file.txt`.
+use std::{ffi::OsString, path::Path, process::ExitCode};
+
+struct Tool {
+    /// The first name is the short one used as a subcommand, the rest are aliases
+    names: &'static [&'static str],
+    about: &'static str,
+    main: fn(Vec<OsString>) -> ExitCode,
+}
+
+const TOOLS: &[Tool] = &[
+    Tool {
+        names: &["wc", "ccwc"],
+        about: "word, line, character, and byte count",
+        main: ccwc::main::<Vec<OsString>, OsString>,
+    },
+    Tool {
+        names: &["jsonparser", "cc2jsonparser"],
+        about: "validate a JSON document",
+        main: cc2jsonparser::main::<Vec<OsString>, OsString>,
+    },
+    Tool {
+        names: &["base64", "ccbase64"],
+        about: "encode data as base64, or decode it back",
+        main: ccbase64::main::<Vec<OsString>, OsString>,
+    },
+    Tool {
+        names: &["calc", "cccalc"],
+        about: "evaluate arithmetic expressions",
+        main: cccalc::main::<Vec<OsString>, OsString>,
+    },
+    Tool {
+        names: &["cat", "cccat"],
+        about: "concatenate files to stdout",
+        main: cccat::main::<Vec<OsString>, OsString>,
+    },
+    Tool {
+        names: &["cut", "cccut"],
+        about: "print selected parts of each line",
+        main: cccut::main::<Vec<OsString>, OsString>,
+    },
+    Tool {
+        names: &["curl", "ccurl"],
+        about: "transfer a URL over HTTP",
+        main: ccurl::main::<Vec<OsString>, OsString>,
+    },
+    Tool {
+        names: &["diff", "ccdiff"],
+        about: "compare two files line by line",
+        main: ccdiff::main::<Vec<OsString>, OsString>,
+    },
+    Tool {
+        names: &["dns", "ccdns"],
+        about: "look up DNS records",
+        main: ccdns::main::<Vec<OsString>, OsString>,
+    },
+    Tool {
+        names: &["grep", "ccgrep"],
+        about: "print lines that match a pattern",
+        main: ccgrep::main::<Vec<OsString>, OsString>,
+    },
+    Tool {
+        names: &["huffman", "cchuffman"],
+        about: "compress and decompress files with Huffman coding",
+        main: cchuffman::main::<Vec<OsString>, OsString>,
+    },
+    Tool {
+        names: &["irc", "ccirc"],
+        about: "chat on an IRC server",
+        main: ccirc::main::<Vec<OsString>, OsString>,
+    },
+    Tool {
+        names: &["jq", "ccjq"],
+        about: "run a filter on JSON and print the values it gives",
+        main: ccjq::main::<Vec<OsString>, OsString>,
+    },
+    Tool {
+        names: &["memcached", "ccmemcached"],
+        about: "run a lightweight memcached server",
+        main: ccmemcached::main::<Vec<OsString>, OsString>,
+    },
+    Tool {
+        names: &["ratelimiter", "ccratelimiter"],
+        about: "an HTTP service that limits how often each client may call it",
+        main: ccratelimiter::main::<Vec<OsString>, OsString>,
+    },
+    Tool {
+        names: &["redis", "ccredis"],
+        about: "run a lightweight Redis server",
+        main: ccredis::main::<Vec<OsString>, OsString>,
+    },
+    Tool {
+        names: &["shell", "ccshell"],
+        about: "run commands with pipes and redirection",
+        main: ccshell::main::<Vec<OsString>, OsString>,
+    },
+    Tool {
+        names: &["sort", "ccsort"],
+        about: "sort lines of text",
+        main: ccsort::main::<Vec<OsString>, OsString>,
+    },
+    Tool {
+        names: &["uniq", "ccuniq"],
+        about: "report or omit repeated lines",
+        main: ccuniq::main::<Vec<OsString>, OsString>,
+    },
+    Tool {
+        names: &["webserver", "ccwebserver"],
+        about: "serve static files over HTTP",
+        main: ccwebserver::main::<Vec<OsString>, OsString>,
+    },
+    Tool {
+        names: &["xxd", "ccxxd"],
+        about: "make a hex dump, or turn one back into binary",
+        main: ccxxd::main::<Vec<OsString>, OsString>,
+    },
+];
+
+fn find_tool(name: &str) -> Option<&'static Tool> {
+    TOOLS.iter().find(|tool| tool.names.contains(&name))
+}
+
+/// The name the binary was run as, without any directory or extension
+fn program_name(arg0: &OsString) -> String {
+    Path::new(arg0)
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+fn usage() -> String {
+    let mut usage = String::from("Usage: ccbox <TOOL> [ARGS]...\n\nTools:\n");
+    for tool in TOOLS {
+        usage.push_str(&format!("  {:<12} {}\n", tool.names[0], tool.about));
+    }
+    usage
+}
+
+fn main() -> ExitCode {
+    let mut args: Vec<OsString> = std::env::args_os().collect();
+
+    // Run as a link named after one of the tools
+    if let Some(tool) = args.first().and_then(|arg0| find_tool(&program_name(arg0))) {
+        return (tool.main)(args);
+    }
+
+    // Otherwise the first argument names the tool, and becomes its program name. An exec can
+    // leave argv empty, so there may not be a program name to drop.
+    if !args.is_empty() {
+        args.remove(0);
+    }
+    match args.first().map(|name| name.to_string_lossy().into_owned()).as_deref() {
+        None => {
+            eprint!("{}", usage());
+            ExitCode::from(2)
+        },
+        Some("-h" | "--help") => {
+            print!("{}", usage());
+            ExitCode::SUCCESS
+        },
+        Some("--list") => {
+            for tool in TOOLS {
+                println!("{}", tool.names.join(" "));
+            }
+            ExitCode::SUCCESS
+        },
+        Some(name) => match find_tool(name) {
+            Some(tool) => (tool.main)(args),
+            None => {
+                eprintln!("ccbox: unknown tool `{}`\n", name);
+                eprint!("{}", usage());
+                ExitCode::from(2)
+            },
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_find_tool_by_alias() {
+        assert_eq!(find_tool("wc").unwrap().names[0], "wc");
+        assert_eq!(find_tool("ccwc").unwrap().names[0], "wc");
+        assert_eq!(find_tool("cc2jsonparser").unwrap().names[0], "jsonparser");
+        assert!(find_tool("ccbox").is_none());
+    }
+
+    #[test]
+    fn check_program_name() {
+        assert_eq!(program_name(&OsString::from("/usr/local/bin/wc")), "wc");
+        assert_eq!(program_name(&OsString::from("ccwc.exe")), "ccwc");
+    }
+
+    #[test]
+    fn check_tool_names_are_unique() {
+        let mut names: Vec<_> = TOOLS.iter().flat_map(|tool| tool.names.iter()).collect();
+        let count = names.len();
+        names.sort();
+        names.dedup();
+        assert_eq!(names.len(), count);
+    }
+}
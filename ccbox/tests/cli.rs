@@ -0,0 +1,43 @@
+use assert_cmd::Command;
+
+#[test]
+fn run_subcommand() {
+    let mut cmd = Command::cargo_bin("ccbox").unwrap();
+    cmd.args(["wc", "-l"]).write_stdin("lorem\nipsum\n").assert().success().stdout("    2 \n");
+}
+
+#[test]
+fn run_subcommand_by_alias() {
+    let mut cmd = Command::cargo_bin("ccbox").unwrap();
+    cmd.args(["cc2jsonparser", "-"]).write_stdin("{}").assert().success();
+}
+
+#[test]
+fn run_via_link() {
+    let dir = std::env::temp_dir().join(format!("ccbox-link-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let link = dir.join(format!("wc{}", std::env::consts::EXE_SUFFIX));
+    let _ = std::fs::remove_file(&link);
+    std::fs::copy(assert_cmd::cargo::cargo_bin("ccbox"), &link).unwrap();
+
+    Command::new(&link).arg("-c").write_stdin("lorem").assert().success().stdout("    5 \n");
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn run_unknown_tool() {
+    let mut cmd = Command::cargo_bin("ccbox").unwrap();
+    cmd.arg("nope").assert().failure().code(2);
+}
+
+#[test]
+fn run_list() {
+    let mut cmd = Command::cargo_bin("ccbox").unwrap();
+    cmd.arg("--list").assert().success().stdout(predicates::str::contains("wc ccwc\n"));
+}
+
+#[test]
+fn run_jq() {
+    let mut cmd = Command::cargo_bin("ccbox").unwrap();
+    cmd.args(["jq", "-c", ".a"]).write_stdin("{\"a\": [1]} {\"a\": 2}").assert().success().stdout("[1]\n2\n");
+}
@@ -0,0 +1,93 @@
+use crate::{calculate, format_number, CalcError};
+use cc_cli::BROKEN_PIPE_EXIT_CODE;
+use cc_diagnostics::{ColorChoice, Diagnostic};
+use clap::Parser;
+use std::ffi::OsString;
+use std::io::{stdin, stdout, BufRead, ErrorKind, IsTerminal, Write};
+use std::process::ExitCode;
+
+/// cccalc - evaluate arithmetic expressions
+#[derive(Parser)]
+struct Cli {
+    /// When to color error messages: auto, always, or never
+    #[arg(long, value_name = "WHEN", default_value = "auto")]
+    color: ColorChoice,
+
+    /// The expression to evaluate, e.g. '2 * (3 + sqrt(16))'. Words are joined with spaces, and
+    /// with none, each line of stdin is evaluated in turn.
+    #[arg(allow_hyphen_values = true, trailing_var_arg = true)]
+    expression: Vec<String>,
+}
+
+/// Run cccalc with the given command line, `args[0]` being the program name
+pub fn main<I, T>(args: I) -> ExitCode
+where
+    I: IntoIterator<Item = T>,
+    T: Into<OsString> + Clone,
+{
+    let args: Cli = cc_cli::parse_from(args, env!("CARGO_PKG_VERSION"));
+    let color = args.color.should_color(&std::io::stderr());
+
+    if !args.expression.is_empty() {
+        let expression = args.expression.join(" ");
+        return match calculate(&expression) {
+            Ok(value) => {
+                println!("{}", format_number(value));
+                ExitCode::SUCCESS
+            },
+            Err(e) => {
+                report(&e, "expression", &expression, color);
+                ExitCode::FAILURE
+            },
+        };
+    }
+
+    // Like bc, keep going after a bad line, and prompt only when someone is typing
+    let interactive = stdin().is_terminal();
+    let mut out = stdout().lock();
+    let mut failed = false;
+    let mut lines = stdin().lock().lines();
+    loop {
+        if interactive {
+            print!("> ");
+            let _ = stdout().flush();
+        }
+        let line = match lines.next() {
+            Some(Ok(line)) => line,
+            Some(Err(e)) => {
+                eprintln!("Error: {}", e);
+                return ExitCode::FAILURE;
+            },
+            None => break,
+        };
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        match calculate(&line) {
+            Ok(value) => match writeln!(out, "{}", format_number(value)) {
+                Err(e) if e.kind() == ErrorKind::BrokenPipe => return ExitCode::from(BROKEN_PIPE_EXIT_CODE),
+                Err(e) => {
+                    eprintln!("Error: {}", e);
+                    return ExitCode::FAILURE;
+                },
+                Ok(()) => {},
+            },
+            Err(e) => {
+                report(&e, "stdin", &line, color);
+                failed = true;
+            },
+        }
+    }
+
+    match failed {
+        true => ExitCode::FAILURE,
+        false => ExitCode::SUCCESS,
+    }
+}
+
+/// Print an error pointing into the expression to stderr
+fn report(error: &CalcError, name: &str, expression: &str, color: bool) {
+    let diagnostic = Diagnostic::error(&error.message).with_span(error.span);
+    eprint!("{}", diagnostic.render(name, expression, color));
+}
@@ -0,0 +1,530 @@
+// Expression calculator
+// The input is split into tokens, parsed into an expression tree by precedence climbing (a Pratt
+// parser), and then evaluated. The parser calls itself for what's inside brackets, so they can
+// only nest so deep, but evaluating and dropping the tree go off a stack of their own.
+// Reference:  https://matklad.github.io/2020/04/13/simple-but-powerful-pratt-parsing.html
+// My answer to:  https://codingchallenges.substack.com/p/coding-challenge-7
+use cc_diagnostics::Span;
+use std::fmt;
+
+mod cli;
+
+pub use cli::main;
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Number(f64),
+    Name(String),
+    Plus,
+    Minus,
+    Star,
+    Slash,
+    Percent,
+    Caret,
+    LeftParen,
+    RightParen,
+    Comma,
+    Eof,
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Token::Number(n) => write!(f, "`{}`", n),
+            Token::Name(name) => write!(f, "`{}`", name),
+            Token::Plus => f.write_str("`+`"),
+            Token::Minus => f.write_str("`-`"),
+            Token::Star => f.write_str("`*`"),
+            Token::Slash => f.write_str("`/`"),
+            Token::Percent => f.write_str("`%`"),
+            Token::Caret => f.write_str("`^`"),
+            Token::LeftParen => f.write_str("`(`"),
+            Token::RightParen => f.write_str("`)`"),
+            Token::Comma => f.write_str("`,`"),
+            Token::Eof => f.write_str("the end of the expression"),
+        }
+    }
+}
+
+/// Why an expression couldn't be calculated, and where in it
+#[derive(Clone, Debug, PartialEq)]
+pub struct CalcError {
+    pub message: String,
+    pub span: Span,
+}
+
+impl CalcError {
+    fn new(message: impl Into<String>, span: Span) -> CalcError {
+        CalcError { message: message.into(), span }
+    }
+}
+
+impl fmt::Display for CalcError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for CalcError {}
+
+fn tokenize(input: &str) -> Result<Vec<(Token, Span)>, CalcError> {
+    let mut tokens = Vec::new();
+    let mut iter = input.char_indices().peekable();
+
+    while let Some((start, ch)) = iter.next() {
+        let token = match ch {
+            '+' => Token::Plus,
+            '-' => Token::Minus,
+            '*' => Token::Star,
+            '/' => Token::Slash,
+            '%' => Token::Percent,
+            '^' => Token::Caret,
+            '(' => Token::LeftParen,
+            ')' => Token::RightParen,
+            ',' => Token::Comma,
+            digit if digit.is_ascii_digit() || digit == '.' => {
+                let mut end = start + 1;
+                let mut previous = ch;
+                // Take digits, a decimal point, and an exponent with its sign, e.g. 1.5e-3
+                while let Some(&(i, c)) = iter.peek() {
+                    let exponent_sign = (c == '-' || c == '+') && (previous == 'e' || previous == 'E');
+                    if !(c.is_ascii_digit() || c == '.' || c == 'e' || c == 'E' || exponent_sign) {
+                        break;
+                    }
+                    iter.next();
+                    end = i + c.len_utf8();
+                    previous = c;
+                }
+                let text = &input[start..end];
+                match text.parse() {
+                    Ok(n) => Token::Number(n),
+                    Err(_) => return Err(CalcError::new(format!("invalid number `{}`", text), Span::new(start, end))),
+                }
+            },
+            letter if letter.is_alphabetic() => {
+                let mut name = letter.to_string();
+                while let Some(&(_, c)) = iter.peek() {
+                    if !c.is_alphanumeric() && c != '_' {
+                        break;
+                    }
+                    name.push(c);
+                    iter.next();
+                }
+                Token::Name(name)
+            },
+            whitespace if whitespace.is_whitespace() => continue,
+            _ => {
+                let span = Span::new(start, start + ch.len_utf8());
+                return Err(CalcError::new(format!("unexpected character `{}`", ch), span));
+            },
+        };
+
+        let end = iter.peek().map_or(input.len(), |&(i, _)| i);
+        tokens.push((token, Span::new(start, end)));
+    }
+
+    tokens.push((Token::Eof, Span::point(input.len())));
+    Ok(tokens)
+}
+
+/// A function that can be called in an expression
+pub struct Function {
+    pub name: &'static str,
+    pub arity: usize,
+    apply: fn(&[f64]) -> f64,
+}
+
+impl fmt::Debug for Function {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name)
+    }
+}
+
+impl PartialEq for Function {
+    fn eq(&self, other: &Function) -> bool {
+        self.name == other.name
+    }
+}
+
+pub const FUNCTIONS: &[Function] = &[
+    Function { name: "sin", arity: 1, apply: |args| args[0].sin() },
+    Function { name: "cos", arity: 1, apply: |args| args[0].cos() },
+    Function { name: "tan", arity: 1, apply: |args| args[0].tan() },
+    Function { name: "sqrt", arity: 1, apply: |args| args[0].sqrt() },
+    Function { name: "abs", arity: 1, apply: |args| args[0].abs() },
+    Function { name: "ln", arity: 1, apply: |args| args[0].ln() },
+    Function { name: "log", arity: 1, apply: |args| args[0].log10() },
+    Function { name: "exp", arity: 1, apply: |args| args[0].exp() },
+    Function { name: "round", arity: 1, apply: |args| args[0].round() },
+    Function { name: "min", arity: 2, apply: |args| args[0].min(args[1]) },
+    Function { name: "max", arity: 2, apply: |args| args[0].max(args[1]) },
+    Function { name: "pow", arity: 2, apply: |args| args[0].powf(args[1]) },
+];
+
+pub const CONSTANTS: &[(&str, f64)] = &[("pi", std::f64::consts::PI), ("e", std::f64::consts::E)];
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BinaryOp {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Remainder,
+    Power,
+}
+
+/// A parsed expression. Operators keep the span of their symbol, to point at it if evaluating
+/// them fails.
+#[derive(Debug, PartialEq)]
+pub enum Expr {
+    Number(f64),
+    Negate(Box<Expr>),
+    Binary { op: BinaryOp, span: Span, lhs: Box<Expr>, rhs: Box<Expr> },
+    Call { function: &'static Function, span: Span, args: Vec<Expr> },
+}
+
+impl Drop for Expr {
+    // Dropping the expressions inside each one in turn would recurse as deep as they nest, and a
+    // long run of `1 + 1 + ...` nests as deep as it's long, so take them out onto a stack of
+    // expressions waiting to be dropped
+    fn drop(&mut self) {
+        let mut waiting = Vec::new();
+        self.take_inside(&mut waiting);
+        while let Some(mut expr) = waiting.pop() {
+            expr.take_inside(&mut waiting);
+        }
+    }
+}
+
+impl Expr {
+    fn take_inside(&mut self, exprs: &mut Vec<Expr>) {
+        let mut take = |expr: &mut Expr| exprs.push(std::mem::replace(expr, Expr::Number(0.0)));
+        match self {
+            Expr::Negate(inner) => take(inner),
+            Expr::Binary { lhs, rhs, .. } => {
+                take(lhs);
+                take(rhs);
+            },
+            Expr::Call { args, .. } => exprs.append(args),
+            Expr::Number(_) => {},
+        }
+    }
+}
+
+/// How deep brackets, prefix operators, and the right of `^` can nest, each being parsed by a call
+/// inside the one for what it's in
+pub const MAX_DEPTH: usize = 256;
+
+/// How tightly a prefix minus binds: tighter than `*`, but looser than `^`, so `-2^2` is -4
+const PREFIX_BINDING_POWER: u8 = 5;
+
+/// The binding power of an infix operator on its left and right. Left associative operators bind
+/// tighter on their right, and `^`, being right associative, the other way round.
+fn infix_binding_power(token: &Token) -> Option<(BinaryOp, u8, u8)> {
+    match token {
+        Token::Plus => Some((BinaryOp::Add, 1, 2)),
+        Token::Minus => Some((BinaryOp::Subtract, 1, 2)),
+        Token::Star => Some((BinaryOp::Multiply, 3, 4)),
+        Token::Slash => Some((BinaryOp::Divide, 3, 4)),
+        Token::Percent => Some((BinaryOp::Remainder, 3, 4)),
+        Token::Caret => Some((BinaryOp::Power, 8, 7)),
+        _ => None,
+    }
+}
+
+#[derive(Debug)]
+struct CalcParser<'a> {
+    iter: core::iter::Peekable<core::slice::Iter<'a, (Token, Span)>>,
+    end: Span,
+    /// How many expressions the one being parsed is inside
+    depth: usize,
+}
+
+impl<'a> CalcParser<'a> {
+    fn new(tokens: &'a [(Token, Span)]) -> CalcParser<'a> {
+        CalcParser {
+            iter: tokens.iter().peekable(),
+            end: tokens.last().map_or(Span::point(0), |(_, span)| *span),
+            depth: 0,
+        }
+    }
+
+    fn read(&mut self) -> (&'a Token, Span) {
+        match self.iter.next() {
+            Some((t, span)) => (t, *span),
+            None => (&Token::Eof, self.end),
+        }
+    }
+
+    fn peek(&mut self) -> &'a Token {
+        match self.iter.peek() {
+            Some((t, _)) => t,
+            None => &Token::Eof,
+        }
+    }
+
+    fn expect(&mut self, expected: Token) -> Result<Span, CalcError> {
+        match self.read() {
+            (t, span) if *t == expected => Ok(span),
+            (t, span) => Err(CalcError::new(format!("expected {}, found {}", expected, t), span)),
+        }
+    }
+
+    fn expression(&mut self, min_binding_power: u8) -> Result<Expr, CalcError> {
+        if self.depth == MAX_DEPTH {
+            let (_, span) = self.read();
+            return Err(CalcError::new(format!("expression nests deeper than {} levels", MAX_DEPTH), span));
+        }
+        self.depth += 1;
+        let expr = self.operand_and_operators(min_binding_power);
+        self.depth -= 1;
+        expr
+    }
+
+    /// An operand and the operators after it that bind tighter than `min_binding_power`
+    fn operand_and_operators(&mut self, min_binding_power: u8) -> Result<Expr, CalcError> {
+        let mut lhs = match self.read() {
+            (Token::Number(n), _) => Expr::Number(*n),
+            (Token::Minus, _) => Expr::Negate(Box::new(self.expression(PREFIX_BINDING_POWER)?)),
+            (Token::Plus, _) => self.expression(PREFIX_BINDING_POWER)?,
+            (Token::LeftParen, _) => {
+                let inner = self.expression(0)?;
+                self.expect(Token::RightParen)?;
+                inner
+            },
+            (Token::Name(name), span) if *self.peek() == Token::LeftParen => self.call(name, span)?,
+            (Token::Name(name), span) => match CONSTANTS.iter().find(|(constant, _)| constant == name) {
+                Some((_, value)) => Expr::Number(*value),
+                None => return Err(CalcError::new(format!("unknown constant `{}`", name), span)),
+            },
+            (t, span) => return Err(CalcError::new(format!("expected a number, found {}", t), span)),
+        };
+
+        while let Some((op, left, right)) = infix_binding_power(self.peek()) {
+            if left < min_binding_power {
+                break;
+            }
+            let (_, span) = self.read();
+            let rhs = self.expression(right)?;
+            lhs = Expr::Binary { op, span, lhs: Box::new(lhs), rhs: Box::new(rhs) };
+        }
+
+        Ok(lhs)
+    }
+
+    fn call(&mut self, name: &str, name_span: Span) -> Result<Expr, CalcError> {
+        let Some(function) = FUNCTIONS.iter().find(|f| f.name == name) else {
+            return Err(CalcError::new(format!("unknown function `{}`", name), name_span));
+        };
+
+        self.expect(Token::LeftParen)?;
+        let mut args = Vec::new();
+        if *self.peek() != Token::RightParen {
+            args.push(self.expression(0)?);
+            while *self.peek() == Token::Comma {
+                self.read();
+                args.push(self.expression(0)?);
+            }
+        }
+        let close = self.expect(Token::RightParen)?;
+
+        let span = name_span.to(close);
+        if args.len() != function.arity {
+            let message = format!("`{}` takes {} argument(s) but was given {}", name, function.arity, args.len());
+            return Err(CalcError::new(message, span));
+        }
+        Ok(Expr::Call { function, span, args })
+    }
+}
+
+/// Parse an expression, without evaluating it
+pub fn parse(input: &str) -> Result<Expr, CalcError> {
+    let tokens = tokenize(input)?;
+    let mut parser = CalcParser::new(&tokens);
+
+    let expr = parser.expression(0)?;
+    match parser.read() {
+        (Token::Eof, _) => Ok(expr),
+        (t, span) => Err(CalcError::new(format!("expected an operator, found {}", t), span)),
+    }
+}
+
+/// What's left to do for an expression being evaluated
+enum Step<'a> {
+    /// Evaluate it, starting with what's inside it
+    Visit(&'a Expr),
+    /// Apply it to the values of what's inside it, which are the last ones worked out
+    Apply(&'a Expr),
+}
+
+/// Evaluate an expression. What's inside each one is evaluated first, left to right, off a stack
+/// rather than by recursing, as a long run of `1 + 1 + ...` nests as deep as it's long.
+pub fn evaluate(expr: &Expr) -> Result<f64, CalcError> {
+    let mut steps = vec![Step::Visit(expr)];
+    let mut values: Vec<f64> = Vec::new();
+    while let Some(step) = steps.pop() {
+        let expr = match step {
+            Step::Visit(Expr::Number(n)) => {
+                values.push(*n);
+                continue;
+            },
+            Step::Visit(expr) => {
+                steps.push(Step::Apply(expr));
+                match expr {
+                    Expr::Negate(inner) => steps.push(Step::Visit(inner)),
+                    Expr::Binary { lhs, rhs, .. } => steps.extend([Step::Visit(rhs), Step::Visit(lhs)]),
+                    Expr::Call { args, .. } => steps.extend(args.iter().rev().map(Step::Visit)),
+                    Expr::Number(_) => unreachable!("numbers are taken as they are"),
+                }
+                continue;
+            },
+            Step::Apply(expr) => expr,
+        };
+        let value = match expr {
+            Expr::Number(n) => *n,
+            Expr::Negate(_) => -values.pop().expect("the operand was evaluated"),
+            Expr::Binary { op, span, .. } => {
+                let rhs = values.pop().expect("the right operand was evaluated");
+                let lhs = values.pop().expect("the left operand was evaluated");
+                match op {
+                    BinaryOp::Add => lhs + rhs,
+                    BinaryOp::Subtract => lhs - rhs,
+                    BinaryOp::Multiply => lhs * rhs,
+                    BinaryOp::Divide | BinaryOp::Remainder if rhs == 0.0 => {
+                        return Err(CalcError::new("division by zero", *span));
+                    },
+                    BinaryOp::Divide => lhs / rhs,
+                    BinaryOp::Remainder => lhs % rhs,
+                    BinaryOp::Power => lhs.powf(rhs),
+                }
+            },
+            Expr::Call { function, span, args } => {
+                let args = values.split_off(values.len() - args.len());
+                let value = (function.apply)(&args);
+                if value.is_nan() {
+                    return Err(CalcError::new(format!("`{}` is undefined for these arguments", function.name), *span));
+                }
+                value
+            },
+        };
+        values.push(value);
+    }
+    Ok(values.pop().expect("the expression was evaluated"))
+}
+
+/// Parse and evaluate an expression
+pub fn calculate(input: &str) -> Result<f64, CalcError> {
+    evaluate(&parse(input)?)
+}
+
+/// Format a result the way a person would write it: whole numbers without a decimal point,
+/// floating point noise like 0.30000000000000004 rounded away, and numbers too big for their
+/// digits to mean anything in exponent notation, like 1e301
+pub fn format_number(value: f64) -> String {
+    if value.is_infinite() {
+        return if value > 0.0 { "inf" } else { "-inf" }.to_string();
+    }
+    if value.abs() >= 1e15 {
+        let rounded = format!("{:.12e}", value);
+        let (mantissa, exponent) = rounded.split_once('e').expect("exponent notation has an e");
+        return format!("{}e{}", mantissa.trim_end_matches('0').trim_end_matches('.'), exponent);
+    }
+    let rounded = format!("{:.12}", value);
+    let trimmed = rounded.trim_end_matches('0').trim_end_matches('.');
+    match trimmed {
+        "-0" => "0".to_string(),
+        _ => trimmed.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_tokenize() {
+        let tokens: Vec<Token> = tokenize("2 * (3.5+x)").unwrap().into_iter().map(|(t, _)| t).collect();
+        assert_eq!(tokens, [
+            Token::Number(2.0),
+            Token::Star,
+            Token::LeftParen,
+            Token::Number(3.5),
+            Token::Plus,
+            Token::Name("x".to_string()),
+            Token::RightParen,
+            Token::Eof,
+        ]);
+    }
+
+    #[test]
+    fn check_tokenize_exponent() {
+        let tokens = tokenize("1.5e-3-1").unwrap();
+        assert_eq!(tokens[0], (Token::Number(0.0015), Span::new(0, 6)));
+        assert_eq!(tokens[1].0, Token::Minus);
+    }
+
+    #[test]
+    fn check_tokenize_illegal_character() {
+        assert_eq!(tokenize("1 & 2").unwrap_err(), CalcError::new("unexpected character `&`", Span::new(2, 3)));
+    }
+
+    #[test]
+    fn check_precedence() {
+        assert_eq!(calculate("1 + 2 * 3").unwrap(), 7.0);
+        assert_eq!(calculate("(1 + 2) * 3").unwrap(), 9.0);
+        assert_eq!(calculate("10 - 4 - 3").unwrap(), 3.0);
+        assert_eq!(calculate("2 ^ 3 ^ 2").unwrap(), 512.0);
+        assert_eq!(calculate("-2 ^ 2").unwrap(), -4.0);
+        assert_eq!(calculate("2 * -3").unwrap(), -6.0);
+        assert_eq!(calculate("7 % 4 * 2").unwrap(), 6.0);
+    }
+
+    #[test]
+    fn check_floats() {
+        assert_eq!(calculate(".5 + 1.25").unwrap(), 1.75);
+        assert_eq!(calculate("1 / 4").unwrap(), 0.25);
+    }
+
+    #[test]
+    fn check_functions_and_constants() {
+        assert_eq!(calculate("sqrt(16) + abs(-2)").unwrap(), 6.0);
+        assert_eq!(calculate("max(1, min(5, 3))").unwrap(), 3.0);
+        assert!((calculate("sin(pi / 2)").unwrap() - 1.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn check_errors() {
+        assert_eq!(calculate("1 / (2 - 2)").unwrap_err(), CalcError::new("division by zero", Span::new(2, 3)));
+        assert_eq!(calculate("(1 + 2").unwrap_err().message, "expected `)`, found the end of the expression");
+        assert_eq!(calculate("1 2").unwrap_err().message, "expected an operator, found `2`");
+        assert_eq!(calculate("1 +").unwrap_err().message, "expected a number, found the end of the expression");
+        assert_eq!(calculate("foo(1)").unwrap_err(), CalcError::new("unknown function `foo`", Span::new(0, 3)));
+        assert_eq!(calculate("sqrt(1, 2)").unwrap_err().span, Span::new(0, 10));
+        assert_eq!(calculate("sqrt(-1)").unwrap_err().message, "`sqrt` is undefined for these arguments");
+        assert!(calculate("1..2").is_err());
+    }
+
+    #[test]
+    fn check_deep_expressions() {
+        let nested = format!("{}1{}", "(".repeat(MAX_DEPTH - 1), ")".repeat(MAX_DEPTH - 1));
+        assert_eq!(calculate(&nested).unwrap(), 1.0);
+        let too_deep = format!("{}1{}", "(".repeat(100_000), ")".repeat(100_000));
+        let message = format!("expression nests deeper than {} levels", MAX_DEPTH);
+        assert_eq!(calculate(&too_deep).unwrap_err(), CalcError::new(message.as_str(), Span::new(MAX_DEPTH, MAX_DEPTH + 1)));
+        assert_eq!(calculate(&"-".repeat(100_000)).unwrap_err().message, message);
+        // A run of operators only nests the expression, not the parsing
+        assert_eq!(calculate(&format!("{}1", "1 + ".repeat(100_000))).unwrap(), 100_001.0);
+    }
+
+    #[test]
+    fn check_format_number() {
+        assert_eq!(format_number(3.0), "3");
+        assert_eq!(format_number(0.1 + 0.2), "0.3");
+        assert_eq!(format_number(-1.5), "-1.5");
+        assert_eq!(format_number(-0.0), "0");
+        assert_eq!(format_number(f64::INFINITY), "inf");
+        assert_eq!(format_number(999_999_999_999_999.0), "999999999999999");
+        assert_eq!(format_number(1e300 * 10.0), "1e301");
+        assert_eq!(format_number(-123_456_789_012_345_678.0), "-1.234567890123e17");
+    }
+}
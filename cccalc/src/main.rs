@@ -0,0 +1,3 @@
+fn main() -> std::process::ExitCode {
+    cccalc::main(std::env::args_os())
+}
@@ -0,0 +1,35 @@
+use assert_cmd::Command;
+
+fn cccalc() -> Command {
+    Command::cargo_bin("cccalc").unwrap()
+}
+
+#[test]
+fn run_calc_expression() {
+    cccalc().args(["2", "*", "(3 + sqrt(16))"]).assert().success().stdout("14\n");
+}
+
+#[test]
+fn run_calc_negative_expression() {
+    cccalc().args(["--color", "never", "-1.5 * 2"]).assert().success().stdout("-3\n");
+}
+
+#[test]
+fn run_calc_stdin_lines() {
+    cccalc().write_stdin("1 + 2\n\n0.1 + 0.2\n").assert().success().stdout("3\n0.3\n");
+}
+
+#[test]
+fn run_calc_error_points_at_problem() {
+    cccalc()
+        .args(["--color", "never", "1 / 0"])
+        .assert()
+        .failure()
+        .stdout("")
+        .stderr("error: division by zero\n --> expression:1:3\n  |\n1 | 1 / 0\n  |   ^\n");
+}
+
+#[test]
+fn run_calc_stdin_continues_after_error() {
+    cccalc().args(["--color", "never"]).write_stdin("1 +\n2 ^ 10\n").assert().failure().stdout("1024\n");
+}
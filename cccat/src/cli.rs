@@ -0,0 +1,85 @@
+use crate::{Cat, CatOptions};
+use anyhow::Context;
+use cc_cli::{is_broken_pipe, BROKEN_PIPE_EXIT_CODE};
+use cc_io::Source;
+use clap::Parser;
+use std::ffi::OsString;
+use std::io::{stdout, BufWriter, ErrorKind, Write};
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+/// cat - concatenate files to stdout
+#[derive(Parser)]
+struct Cli {
+    /// Number every line
+    #[arg(short, long)]
+    number: bool,
+
+    /// Number only lines that aren't blank, overriding -n
+    #[arg(short = 'b', long)]
+    number_nonblank: bool,
+
+    /// Collapse runs of blank lines into one
+    #[arg(short, long)]
+    squeeze_blank: bool,
+
+    /// Show the end of each line as $
+    #[arg(short = 'E', long)]
+    show_ends: bool,
+
+    /// Show tabs as ^I
+    #[arg(short = 'T', long)]
+    show_tabs: bool,
+
+    /// The files to concatenate, with - for stdin, or stdin if there are none
+    files: Vec<PathBuf>,
+}
+
+/// Run cccat with the given command line, `args[0]` being the program name
+pub fn main<I, T>(args: I) -> ExitCode
+where
+    I: IntoIterator<Item = T>,
+    T: Into<OsString> + Clone,
+{
+    let args: Cli = cc_cli::parse_from(args, env!("CARGO_PKG_VERSION"));
+    let mut sources: Vec<Source> = args.files.iter().map(|file| Source::from(file.clone())).collect();
+    if sources.is_empty() {
+        sources.push(Source::Stdin);
+    }
+
+    let mut cat = Cat::new(CatOptions {
+        number: args.number,
+        number_nonblank: args.number_nonblank,
+        squeeze_blank: args.squeeze_blank,
+        show_ends: args.show_ends,
+        show_tabs: args.show_tabs,
+    });
+
+    // Like cat, report files that can't be read and carry on with the rest
+    let mut out = BufWriter::new(stdout().lock());
+    let mut failed = false;
+    for source in &sources {
+        let result = cc_io::open(source).map_err(anyhow::Error::from).and_then(|input| {
+            cat.copy(input.reader, &mut out).with_context(|| format!("could not copy `{}`", source.label()))
+        });
+
+        match result {
+            Ok(()) => {},
+            Err(e) if is_broken_pipe(&e) => return ExitCode::from(BROKEN_PIPE_EXIT_CODE),
+            Err(e) => {
+                eprintln!("Error: {:?}", e);
+                failed = true;
+            },
+        }
+    }
+
+    match out.flush() {
+        Err(e) if e.kind() == ErrorKind::BrokenPipe => ExitCode::from(BROKEN_PIPE_EXIT_CODE),
+        Err(e) => {
+            eprintln!("Error: {:?}", e);
+            ExitCode::FAILURE
+        },
+        Ok(()) if failed => ExitCode::FAILURE,
+        Ok(()) => ExitCode::SUCCESS,
+    }
+}
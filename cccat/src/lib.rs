@@ -0,0 +1,137 @@
+// Concatenation core of cccat, usable without the command line interface.
+// Like cat, line numbering and squeezing carry on from one input to the next, as if they were a
+// single stream.
+use std::io::{self, BufRead, Write};
+
+mod cli;
+
+pub use cli::main;
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct CatOptions {
+    /// Number every line
+    pub number: bool,
+    /// Number only lines that aren't blank, overriding `number`
+    pub number_nonblank: bool,
+    /// Collapse runs of blank lines into one
+    pub squeeze_blank: bool,
+    /// Mark the end of each line with `$`
+    pub show_ends: bool,
+    /// Show tabs as `^I`
+    pub show_tabs: bool,
+}
+
+impl CatOptions {
+    /// Whether the input can be copied through unchanged
+    fn is_plain(&self) -> bool {
+        !(self.number || self.number_nonblank || self.squeeze_blank || self.show_ends || self.show_tabs)
+    }
+}
+
+/// Copies inputs to an output, keeping track of where it is across inputs
+pub struct Cat {
+    options: CatOptions,
+    line_number: usize,
+    /// Whether the last input ended partway through a line
+    mid_line: bool,
+    previous_blank: bool,
+}
+
+impl Cat {
+    pub fn new(options: CatOptions) -> Cat {
+        Cat { options, line_number: 0, mid_line: false, previous_blank: false }
+    }
+
+    /// Copy one input to `out`
+    pub fn copy(&mut self, mut reader: impl BufRead, out: &mut impl Write) -> io::Result<()> {
+        if self.options.is_plain() {
+            io::copy(&mut reader, out)?;
+            return Ok(());
+        }
+
+        let mut line = Vec::new();
+        loop {
+            line.clear();
+            if reader.read_until(b'\n', &mut line)? == 0 {
+                return Ok(());
+            }
+            let ends = line.last() == Some(&b'\n');
+            let content = if ends { &line[..line.len() - 1] } else { &line[..] };
+
+            if !self.mid_line {
+                let blank = content.is_empty() && ends;
+                if blank && self.previous_blank && self.options.squeeze_blank {
+                    continue;
+                }
+                self.previous_blank = blank;
+
+                if self.options.number_nonblank && !blank || self.options.number && !self.options.number_nonblank {
+                    self.line_number += 1;
+                    write!(out, "{:>6}\t", self.line_number)?;
+                }
+            }
+
+            if self.options.show_tabs {
+                for (i, part) in content.split(|&b| b == b'\t').enumerate() {
+                    if i > 0 {
+                        out.write_all(b"^I")?;
+                    }
+                    out.write_all(part)?;
+                }
+            } else {
+                out.write_all(content)?;
+            }
+
+            if ends {
+                if self.options.show_ends {
+                    out.write_all(b"$")?;
+                }
+                out.write_all(b"\n")?;
+            }
+            self.mid_line = !ends;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cat(inputs: &[&str], options: CatOptions) -> String {
+        let mut cat = Cat::new(options);
+        let mut out = Vec::new();
+        for input in inputs {
+            cat.copy(input.as_bytes(), &mut out).unwrap();
+        }
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn check_plain() {
+        assert_eq!(cat(&["one\n", "two"], CatOptions::default()), "one\ntwo");
+    }
+
+    #[test]
+    fn check_number_continues_across_inputs() {
+        let options = CatOptions { number: true, ..CatOptions::default() };
+        assert_eq!(cat(&["one\n\ntwo", "three\n"], options), "     1\tone\n     2\t\n     3\ttwothree\n");
+    }
+
+    #[test]
+    fn check_number_nonblank() {
+        let options = CatOptions { number: true, number_nonblank: true, ..CatOptions::default() };
+        assert_eq!(cat(&["one\n\ntwo\n"], options), "     1\tone\n\n     2\ttwo\n");
+    }
+
+    #[test]
+    fn check_squeeze_blank() {
+        let options = CatOptions { squeeze_blank: true, number: true, ..CatOptions::default() };
+        assert_eq!(cat(&["a\n\n", "\n\nb\n"], options), "     1\ta\n     2\t\n     3\tb\n");
+    }
+
+    #[test]
+    fn check_show_ends_and_tabs() {
+        let options = CatOptions { show_ends: true, show_tabs: true, ..CatOptions::default() };
+        assert_eq!(cat(&["\t- Rumi\n\n"], options), "^I- Rumi$\n$\n");
+    }
+}
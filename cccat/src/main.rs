@@ -0,0 +1,3 @@
+fn main() -> std::process::ExitCode {
+    cccat::main(std::env::args_os())
+}
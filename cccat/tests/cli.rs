@@ -0,0 +1,41 @@
+use assert_cmd::Command;
+
+fn cccat() -> Command {
+    let mut cmd = Command::cargo_bin("cccat").unwrap();
+    cmd.current_dir(env!("CARGO_MANIFEST_DIR"));
+    cmd
+}
+
+#[test]
+fn run_cat_file_and_stdin() {
+    cccat().args(["-", "testinputs/quote.txt"]).write_stdin("first\n").assert().success()
+        .stdout(concat!(
+            "first\n",
+            "\"Your heart is the size of an ocean.\n\n\n\n",
+            "Go find yourself in its hidden depths.\"\n",
+            "\t- Rumi\n",
+        ));
+}
+
+#[test]
+fn run_cat_squeeze_and_number() {
+    cccat().args(["-sn", "testinputs/quote.txt"]).assert().success()
+        .stdout(concat!(
+            "     1\t\"Your heart is the size of an ocean.\n",
+            "     2\t\n",
+            "     3\tGo find yourself in its hidden depths.\"\n",
+            "     4\t\t- Rumi\n",
+        ));
+}
+
+#[test]
+fn run_cat_show_ends_and_tabs() {
+    cccat().args(["-ET", "-"]).write_stdin("\ta\n\n").assert().success().stdout("^Ia$\n$\n");
+}
+
+#[test]
+fn run_cat_missing_file() {
+    cccat().args(["missing.txt", "-"]).write_stdin("still here\n").assert().failure()
+        .stdout("still here\n")
+        .stderr(predicates::str::contains("could not read `missing.txt`"));
+}
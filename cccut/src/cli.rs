@@ -0,0 +1,114 @@
+use crate::{Cutter, List, Unit};
+use anyhow::{bail, Result};
+use cc_cli::{is_broken_pipe, BROKEN_PIPE_EXIT_CODE};
+use cc_io::Source;
+use clap::{ArgGroup, Parser};
+use std::ffi::OsString;
+use std::io::{stdout, BufWriter, ErrorKind, Write};
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+/// cut - print selected parts of each line
+#[derive(Parser)]
+#[command(group(ArgGroup::new("unit").required(true).args(["bytes", "characters", "fields"])))]
+struct Cli {
+    /// Select only these bytes, e.g. 1-3,5
+    #[arg(short, long, value_name = "LIST")]
+    bytes: Option<List>,
+
+    /// Select only these characters
+    #[arg(short, long, value_name = "LIST")]
+    characters: Option<List>,
+
+    /// Select only these fields. Lines with no delimiter are printed whole, unless -s is given
+    #[arg(short, long, value_name = "LIST")]
+    fields: Option<List>,
+
+    /// Fields are separated by this character instead of a tab
+    #[arg(short, long, value_name = "DELIM", requires = "fields", value_parser = parse_delimiter)]
+    delimiter: Option<String>,
+
+    /// Leave out lines with no delimiter in them
+    #[arg(short = 's', long, requires = "fields")]
+    only_delimited: bool,
+
+    /// Select everything except the listed bytes, characters, or fields
+    #[arg(long)]
+    complement: bool,
+
+    /// Join the selections with this string instead of the input delimiter
+    #[arg(long, value_name = "STRING")]
+    output_delimiter: Option<String>,
+
+    /// Ignored, for compatibility with cut
+    #[arg(short = 'n', hide = true)]
+    no_split: bool,
+
+    /// The files to read, or stdin if there are none
+    files: Vec<PathBuf>,
+}
+
+impl Cli {
+    fn cutter(self) -> Cutter {
+        let (list, unit) = match (self.bytes, self.characters, self.fields) {
+            (Some(list), _, _) => (list, Unit::Bytes),
+            (_, Some(list), _) => (list, Unit::Chars),
+            (_, _, Some(list)) => (list, Unit::Fields {
+                delimiter: self.delimiter.unwrap_or_else(|| "\t".to_string()),
+                only_delimited: self.only_delimited,
+            }),
+            _ => unreachable!("clap requires one of -b, -c, or -f"),
+        };
+
+        Cutter { list, unit, complement: self.complement, output_delimiter: self.output_delimiter }
+    }
+}
+
+fn parse_delimiter(value: &str) -> Result<String> {
+    if value.chars().count() != 1 {
+        bail!("the delimiter must be a single character");
+    }
+    Ok(value.to_string())
+}
+
+/// Run cccut with the given command line, `args[0]` being the program name
+pub fn main<I, T>(args: I) -> ExitCode
+where
+    I: IntoIterator<Item = T>,
+    T: Into<OsString> + Clone,
+{
+    let args: Cli = cc_cli::parse_from(args, env!("CARGO_PKG_VERSION"));
+    let mut sources: Vec<Source> = args.files.iter().map(|file| Source::from(file.clone())).collect();
+    if sources.is_empty() {
+        sources.push(Source::Stdin);
+    }
+    let cutter = args.cutter();
+
+    // Like cut, report files that can't be read and carry on with the rest
+    let mut out = BufWriter::new(stdout().lock());
+    let mut failed = false;
+    for source in &sources {
+        let result = cc_io::open(source)
+            .map_err(anyhow::Error::from)
+            .and_then(|input| Ok(cutter.cut(input.reader, &mut out)?));
+
+        match result {
+            Ok(()) => {},
+            Err(e) if is_broken_pipe(&e) => return ExitCode::from(BROKEN_PIPE_EXIT_CODE),
+            Err(e) => {
+                eprintln!("Error: {:?}", e);
+                failed = true;
+            },
+        }
+    }
+
+    match out.flush() {
+        Err(e) if e.kind() == ErrorKind::BrokenPipe => ExitCode::from(BROKEN_PIPE_EXIT_CODE),
+        Err(e) => {
+            eprintln!("Error: {:?}", e);
+            ExitCode::FAILURE
+        },
+        Ok(()) if failed => ExitCode::FAILURE,
+        Ok(()) => ExitCode::SUCCESS,
+    }
+}
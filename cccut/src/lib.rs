@@ -0,0 +1,312 @@
+// Line slicing core of cccut, usable without the command line interface.
+// My answer to:  https://codingchallenges.substack.com/p/coding-challenge-4
+use std::fmt;
+use std::io::{self, BufRead, Write};
+use std::str::FromStr;
+
+mod cli;
+
+pub use cli::main;
+
+/// A range of 1-based positions, inclusive at both ends, with no end meaning to the end of the line
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Range {
+    pub start: usize,
+    pub end: Option<usize>,
+}
+
+impl Range {
+    fn contains(&self, position: usize) -> bool {
+        position >= self.start && self.end.is_none_or(|end| position <= end)
+    }
+}
+
+/// The positions to select, as given to `-b`, `-c`, or `-f`, e.g. `1,3-5,7-`
+#[derive(Clone, Debug, PartialEq)]
+pub struct List {
+    ranges: Vec<Range>,
+}
+
+impl List {
+    pub fn contains(&self, position: usize) -> bool {
+        self.ranges.iter().any(|range| range.contains(position))
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct ListError(String);
+
+impl fmt::Display for ListError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for ListError {}
+
+impl FromStr for List {
+    type Err = ListError;
+
+    /// Ranges are separated by commas or whitespace, like `-f "1 2"`, and each is `N`, `N-`,
+    /// `N-M`, or `-M`
+    fn from_str(s: &str) -> Result<List, ListError> {
+        let position = |number: &str| -> Result<usize, ListError> {
+            match number.parse::<usize>() {
+                Ok(0) => Err(ListError("positions are numbered from 1".to_string())),
+                Ok(n) => Ok(n),
+                Err(_) => Err(ListError(format!("invalid position `{}` in list `{}`", number, s))),
+            }
+        };
+
+        let mut ranges = Vec::new();
+        for part in s.split(|c: char| c == ',' || c.is_whitespace()).filter(|p| !p.is_empty()) {
+            let range = match part.split_once('-') {
+                None => {
+                    let n = position(part)?;
+                    Range { start: n, end: Some(n) }
+                },
+                Some(("", "")) => return Err(ListError("invalid range with no endpoint: -".to_string())),
+                Some(("", end)) => Range { start: 1, end: Some(position(end)?) },
+                Some((start, "")) => Range { start: position(start)?, end: None },
+                Some((start, end)) => {
+                    let (start, end) = (position(start)?, position(end)?);
+                    if end < start {
+                        return Err(ListError(format!("invalid decreasing range `{}`", part)));
+                    }
+                    Range { start, end: Some(end) }
+                },
+            };
+            ranges.push(range);
+        }
+
+        if ranges.is_empty() {
+            return Err(ListError("the list is empty".to_string()));
+        }
+        Ok(List { ranges })
+    }
+}
+
+/// What the positions in the list count
+#[derive(Clone, Debug, PartialEq)]
+pub enum Unit {
+    Bytes,
+    /// Characters, when the line is valid UTF-8. Other lines fall back to bytes.
+    Chars,
+    Fields {
+        delimiter: String,
+        /// Drop lines with no delimiter in them, rather than printing them whole
+        only_delimited: bool,
+    },
+}
+
+/// Everything needed to cut a line
+#[derive(Clone, Debug)]
+pub struct Cutter {
+    pub list: List,
+    pub unit: Unit,
+    /// Select the positions not in the list instead
+    pub complement: bool,
+    /// Joins selected fields, defaulting to the field delimiter. With bytes and characters it
+    /// goes between selections that aren't next to each other, and nothing does by default.
+    pub output_delimiter: Option<String>,
+}
+
+impl Cutter {
+    fn selected(&self, position: usize) -> bool {
+        self.list.contains(position) != self.complement
+    }
+
+    /// Append the selected part of `line`, which has no trailing newline, to `out`.
+    /// Returns false if the line should be left out altogether.
+    pub fn cut_line(&self, line: &[u8], out: &mut Vec<u8>) -> bool {
+        match &self.unit {
+            Unit::Bytes => self.cut_pieces(line.iter().map(std::slice::from_ref), out),
+            Unit::Chars => match std::str::from_utf8(line) {
+                Ok(text) => self.cut_pieces(text.char_indices().map(|(i, c)| &line[i..i + c.len_utf8()]), out),
+                Err(_) => self.cut_pieces(line.iter().map(std::slice::from_ref), out),
+            },
+            Unit::Fields { delimiter, only_delimited } => {
+                let delimiter = delimiter.as_bytes();
+                if memchr::memmem::find(line, delimiter).is_none() {
+                    if *only_delimited {
+                        return false;
+                    }
+                    out.extend_from_slice(line);
+                    return true;
+                }
+
+                let output_delimiter = self.output_delimiter.as_deref().map_or(delimiter, str::as_bytes);
+                let fields = split(line, delimiter);
+                let mut first = true;
+                for (i, field) in fields.enumerate() {
+                    if self.selected(i + 1) {
+                        if !first {
+                            out.extend_from_slice(output_delimiter);
+                        }
+                        out.extend_from_slice(field);
+                        first = false;
+                    }
+                }
+            },
+        }
+        true
+    }
+
+    fn cut_pieces<'a>(&self, pieces: impl Iterator<Item = &'a [u8]>, out: &mut Vec<u8>) {
+        let mut previous = None;
+        for (i, piece) in pieces.enumerate() {
+            if !self.selected(i + 1) {
+                continue;
+            }
+            if let (Some(delimiter), Some(previous)) = (&self.output_delimiter, previous) {
+                if previous + 1 != i {
+                    out.extend_from_slice(delimiter.as_bytes());
+                }
+            }
+            out.extend_from_slice(piece);
+            previous = Some(i);
+        }
+    }
+
+    /// Cut every line of `reader` into `out`. Each line printed ends with a newline, even if the
+    /// last line of the input didn't.
+    pub fn cut(&self, mut reader: impl BufRead, mut out: impl Write) -> io::Result<()> {
+        let mut line = Vec::new();
+        let mut cut = Vec::new();
+        loop {
+            line.clear();
+            if reader.read_until(b'\n', &mut line)? == 0 {
+                return Ok(());
+            }
+            if line.last() == Some(&b'\n') {
+                line.pop();
+            }
+
+            cut.clear();
+            if self.cut_line(&line, &mut cut) {
+                cut.push(b'\n');
+                out.write_all(&cut)?;
+            }
+        }
+    }
+}
+
+/// Split `line` on every occurrence of `delimiter`
+fn split<'a>(line: &'a [u8], delimiter: &'a [u8]) -> impl Iterator<Item = &'a [u8]> {
+    let mut rest = Some(line);
+    std::iter::from_fn(move || {
+        let current = rest?;
+        match memchr::memmem::find(current, delimiter) {
+            Some(i) => {
+                rest = Some(&current[i + delimiter.len()..]);
+                Some(&current[..i])
+            },
+            None => {
+                rest = None;
+                Some(current)
+            },
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fields(list: &str, delimiter: &str) -> Cutter {
+        Cutter {
+            list: list.parse().unwrap(),
+            unit: Unit::Fields { delimiter: delimiter.to_string(), only_delimited: false },
+            complement: false,
+            output_delimiter: None,
+        }
+    }
+
+    fn cut(cutter: &Cutter, input: &str) -> String {
+        let mut out = Vec::new();
+        cutter.cut(input.as_bytes(), &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn check_parse_list() {
+        let list: List = "1,3-4 6-".parse().unwrap();
+        assert_eq!(list.ranges, vec![
+            Range { start: 1, end: Some(1) },
+            Range { start: 3, end: Some(4) },
+            Range { start: 6, end: None },
+        ]);
+        assert_eq!("-2".parse::<List>().unwrap().ranges, vec![Range { start: 1, end: Some(2) }]);
+    }
+
+    #[test]
+    fn check_parse_list_errors() {
+        assert_eq!("0".parse::<List>().unwrap_err().to_string(), "positions are numbered from 1");
+        assert_eq!("3-1".parse::<List>().unwrap_err().to_string(), "invalid decreasing range `3-1`");
+        assert!("a".parse::<List>().is_err());
+        assert!("-".parse::<List>().is_err());
+        assert!("".parse::<List>().is_err());
+    }
+
+    #[test]
+    fn check_cut_fields() {
+        let input = "f0\tf1\tf2\n0\t1\t2\n";
+        assert_eq!(cut(&fields("2", "\t"), input), "f1\n1\n");
+        assert_eq!(cut(&fields("1,3", "\t"), input), "f0\tf2\n0\t2\n");
+    }
+
+    #[test]
+    fn check_cut_fields_keeps_input_order() {
+        assert_eq!(cut(&fields("3,1,1", ","), "a,b,c"), "a,c\n");
+    }
+
+    #[test]
+    fn check_lines_without_delimiter() {
+        let mut cutter = fields("2", ",");
+        assert_eq!(cut(&cutter, "no delimiter\na,b\n"), "no delimiter\nb\n");
+
+        cutter.unit = Unit::Fields { delimiter: ",".to_string(), only_delimited: true };
+        assert_eq!(cut(&cutter, "no delimiter\na,b\n"), "b\n");
+    }
+
+    #[test]
+    fn check_output_delimiter() {
+        let mut cutter = fields("1-2", ",");
+        cutter.output_delimiter = Some(" | ".to_string());
+        assert_eq!(cut(&cutter, "a,b,c"), "a | b\n");
+    }
+
+    #[test]
+    fn check_complement() {
+        let mut cutter = fields("2", ",");
+        cutter.complement = true;
+        assert_eq!(cut(&cutter, "a,b,c"), "a,c\n");
+    }
+
+    #[test]
+    fn check_cut_chars_and_bytes() {
+        let mut cutter = Cutter {
+            list: "2-3".parse().unwrap(),
+            unit: Unit::Chars,
+            complement: false,
+            output_delimiter: None,
+        };
+        assert_eq!(cut(&cutter, "caf\u{e9}s\n"), "af\n");
+        assert_eq!(cut(&cutter, "\u{e9}\u{e9}\u{e9}\u{e9}\n"), "\u{e9}\u{e9}\n");
+
+        cutter.list = "1-2".parse().unwrap();
+        cutter.unit = Unit::Bytes;
+        assert_eq!(cut(&cutter, "\u{e9}x\n"), "\u{e9}\n");
+    }
+
+    #[test]
+    fn check_output_delimiter_between_byte_ranges() {
+        let cutter = Cutter {
+            list: "1-2,4".parse().unwrap(),
+            unit: Unit::Bytes,
+            complement: false,
+            output_delimiter: Some(":".to_string()),
+        };
+        assert_eq!(cut(&cutter, "abcde"), "ab:d\n");
+    }
+}
@@ -0,0 +1,41 @@
+use assert_cmd::Command;
+
+fn cccut() -> Command {
+    let mut cmd = Command::cargo_bin("cccut").unwrap();
+    cmd.current_dir(env!("CARGO_MANIFEST_DIR"));
+    cmd
+}
+
+#[test]
+fn run_field_with_default_tab() {
+    cccut().args(["-f2", "testinputs/sample.tsv"]).assert().success().stdout("f1\n1\n6\n11\n");
+}
+
+#[test]
+fn run_field_with_delimiter() {
+    cccut().args(["-f1", "-d,", "testinputs/fourchords.csv"]).assert().success()
+        .stdout("Song title\n\"10000 Reasons (Bless the Lord)\"\n\"20 Something\"\n");
+}
+
+#[test]
+fn run_field_list_with_spaces() {
+    cccut().args(["-f", "1 2", "testinputs/sample.tsv"]).assert().success()
+        .stdout("f0\tf1\n0\t1\n5\t6\n10\t11\n");
+}
+
+#[test]
+fn run_stdin() {
+    cccut().args(["-c", "1-3", "-"]).write_stdin("abcdef\nxy\n").assert().success().stdout("abc\nxy\n");
+}
+
+#[test]
+fn run_needs_a_list() {
+    cccut().arg("testinputs/sample.tsv").assert().failure().code(2);
+}
+
+#[test]
+fn run_missing_file_still_cuts_the_rest() {
+    cccut().args(["-b1", "missing.txt", "testinputs/sample.tsv"]).assert().failure()
+        .stdout("f\n0\n5\n1\n")
+        .stderr(predicates::str::contains("could not read `missing.txt`"));
+}
@@ -0,0 +1,113 @@
+use crate::{timestamp, write_normal};
+use anyhow::{Context, Result};
+use cc_cli::{is_broken_pipe, BROKEN_PIPE_EXIT_CODE};
+use cc_diff::{diff, hunks, split_lines, write_unified};
+use cc_io::Source;
+use clap::Parser;
+use std::ffi::OsString;
+use std::io::{stdout, BufWriter, Read, Write};
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::time::SystemTime;
+
+/// Like diff, 1 means the files differ and 2 means something went wrong
+const DIFFERENT_EXIT_CODE: u8 = 1;
+const ERROR_EXIT_CODE: u8 = 2;
+
+const DEFAULT_CONTEXT: usize = 3;
+
+/// diff - compare two files line by line
+#[derive(Parser)]
+struct Cli {
+    /// Print a unified diff, with 3 lines of context around each change
+    #[arg(short = 'u')]
+    unified: bool,
+
+    /// Print a unified diff with this many lines of context
+    #[arg(short = 'U', long = "unified", value_name = "NUM")]
+    context: Option<usize>,
+
+    /// Only say whether the files differ
+    #[arg(short = 'q', long)]
+    brief: bool,
+
+    /// The original file, or - for stdin
+    old: PathBuf,
+
+    /// The changed file, or - for stdin
+    new: PathBuf,
+}
+
+struct File {
+    source: Source,
+    contents: Vec<u8>,
+    binary: bool,
+    modified: SystemTime,
+}
+
+fn read(path: PathBuf) -> Result<File> {
+    let source = Source::from(path);
+    let mut input = cc_io::open(&source)?;
+    let binary = cc_io::is_binary(&mut input.reader)?;
+    let mut contents = Vec::new();
+    input.reader.read_to_end(&mut contents).with_context(|| format!("could not read `{}`", source.label()))?;
+
+    let modified = match &source {
+        Source::File(path) => std::fs::metadata(path).and_then(|m| m.modified()).unwrap_or(SystemTime::now()),
+        Source::Stdin => SystemTime::now(),
+    };
+    Ok(File { source, contents, binary, modified })
+}
+
+/// Run ccdiff with the given command line, `args[0]` being the program name
+pub fn main<I, T>(args: I) -> ExitCode
+where
+    I: IntoIterator<Item = T>,
+    T: Into<OsString> + Clone,
+{
+    let args: Cli = cc_cli::parse_from(args, env!("CARGO_PKG_VERSION"));
+    let mut out = BufWriter::new(stdout().lock());
+
+    let result = run(args, &mut out).and_then(|differ| {
+        out.flush()?;
+        Ok(differ)
+    });
+    match result {
+        Ok(false) => ExitCode::SUCCESS,
+        Ok(true) => ExitCode::from(DIFFERENT_EXIT_CODE),
+        Err(e) if is_broken_pipe(&e) => ExitCode::from(BROKEN_PIPE_EXIT_CODE),
+        Err(e) => {
+            eprintln!("Error: {:?}", e);
+            ExitCode::from(ERROR_EXIT_CODE)
+        },
+    }
+}
+
+/// Compare the files, returning whether they differ
+fn run(args: Cli, out: &mut impl Write) -> Result<bool> {
+    let old = read(args.old)?;
+    let new = read(args.new)?;
+    if old.contents == new.contents {
+        return Ok(false);
+    }
+
+    if args.brief || old.binary || new.binary {
+        let kind = if old.binary || new.binary { "Binary files" } else { "Files" };
+        writeln!(out, "{} {} and {} differ", kind, old.source.label(), new.source.label())?;
+        return Ok(true);
+    }
+
+    let old_lines = split_lines(&old.contents);
+    let new_lines = split_lines(&new.contents);
+    let edits = diff(&old_lines, &new_lines);
+
+    match args.context.or(args.unified.then_some(DEFAULT_CONTEXT)) {
+        Some(context) => {
+            writeln!(out, "--- {}\t{}", old.source.label(), timestamp(old.modified))?;
+            writeln!(out, "+++ {}\t{}", new.source.label(), timestamp(new.modified))?;
+            write_unified(out, &hunks(&edits, context), &old_lines, &new_lines)?;
+        },
+        None => write_normal(out, &hunks(&edits, 0), &old_lines, &new_lines)?,
+    }
+    Ok(true)
+}
@@ -0,0 +1,129 @@
+// Line based diff of two files, built on the shared cc-diff core.
+// Prints diff's normal format by default, or unified format with -u.
+use cc_diff::{write_line, Edit, Hunk};
+use std::io::{self, Write};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+mod cli;
+
+pub use cli::main;
+
+/// Write hunks found with no context in diff's normal format, e.g.
+///
+///   3c3
+///   < old line
+///   ---
+///   > new line
+pub fn write_normal(out: &mut impl Write, hunks: &[Hunk], old: &[&[u8]], new: &[&[u8]]) -> io::Result<()> {
+    for hunk in hunks {
+        let deleted: Vec<usize> = hunk.edits.iter().filter_map(|e| match *e {
+            Edit::Delete { old } => Some(old),
+            _ => None,
+        }).collect();
+        let inserted: Vec<usize> = hunk.edits.iter().filter_map(|e| match *e {
+            Edit::Insert { new } => Some(new),
+            _ => None,
+        }).collect();
+
+        // An empty range is given as the line before it
+        let old_range = normal_range(hunk.old_start, hunk.old_len);
+        let new_range = normal_range(hunk.new_start, hunk.new_len);
+        match (deleted.is_empty(), inserted.is_empty()) {
+            (true, false) => writeln!(out, "{}a{}", hunk.old_start, new_range)?,
+            (false, true) => writeln!(out, "{}d{}", old_range, hunk.new_start)?,
+            _ => writeln!(out, "{}c{}", old_range, new_range)?,
+        }
+
+        for &i in &deleted {
+            out.write_all(b"< ")?;
+            write_line(out, old[i])?;
+        }
+        if !deleted.is_empty() && !inserted.is_empty() {
+            out.write_all(b"---\n")?;
+        }
+        for &i in &inserted {
+            out.write_all(b"> ")?;
+            write_line(out, new[i])?;
+        }
+    }
+    Ok(())
+}
+
+fn normal_range(start: usize, len: usize) -> String {
+    match len {
+        0 | 1 => format!("{}", start + 1),
+        _ => format!("{},{}", start + 1, start + len),
+    }
+}
+
+/// A modification time as unified headers show it, e.g. `2023-11-20 09:15:02.123456789 +0000`.
+/// Times are given in UTC.
+pub fn timestamp(time: SystemTime) -> String {
+    let since_epoch = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+    let seconds = since_epoch.as_secs();
+    let (year, month, day) = civil_from_days((seconds / 86400) as i64);
+    let time_of_day = seconds % 86400;
+
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02}.{:09} +0000",
+        year,
+        month,
+        day,
+        time_of_day / 3600,
+        time_of_day / 60 % 60,
+        time_of_day % 60,
+        since_epoch.subsec_nanos(),
+    )
+}
+
+/// The year, month, and day of a count of days since 1970-01-01.
+/// Reference:  https://howardhinnant.github.io/date_algorithms.html#civil_from_days
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days.rem_euclid(146097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * month_index + 2) / 5 + 1) as u32;
+    let month = if month_index < 10 { month_index + 3 } else { month_index - 9 } as u32;
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cc_diff::{diff, hunks, split_lines};
+    use std::time::Duration;
+
+    fn normal(old: &str, new: &str) -> String {
+        let (old, new) = (split_lines(old.as_bytes()), split_lines(new.as_bytes()));
+        let mut out = Vec::new();
+        write_normal(&mut out, &hunks(&diff(&old, &new), 0), &old, &new).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn check_normal_add_delete() {
+        assert_eq!(normal("a\nb\nc\n", "b\nc\nd\n"), "1d0\n< a\n3a3\n> d\n");
+    }
+
+    #[test]
+    fn check_normal_change() {
+        assert_eq!(normal("a\nb\nc\nd\n", "a\nB\nC\nd\n"), "2,3c2,3\n< b\n< c\n---\n> B\n> C\n");
+    }
+
+    #[test]
+    fn check_normal_same() {
+        assert_eq!(normal("a\n", "a\n"), "");
+    }
+
+    #[test]
+    fn check_timestamp() {
+        assert_eq!(timestamp(UNIX_EPOCH), "1970-01-01 00:00:00.000000000 +0000");
+        let time = UNIX_EPOCH + Duration::new(1_700_471_702, 5);
+        assert_eq!(timestamp(time), "2023-11-20 09:15:02.000000005 +0000");
+        assert_eq!(civil_from_days(11016), (2000, 2, 29));
+    }
+}
@@ -0,0 +1,3 @@
+fn main() -> std::process::ExitCode {
+    ccdiff::main(std::env::args_os())
+}
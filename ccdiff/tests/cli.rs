@@ -0,0 +1,44 @@
+use assert_cmd::Command;
+use predicates::prelude::*;
+
+fn ccdiff() -> Command {
+    let mut cmd = Command::cargo_bin("ccdiff").unwrap();
+    cmd.current_dir(env!("CARGO_MANIFEST_DIR"));
+    cmd
+}
+
+#[test]
+fn run_same_files() {
+    ccdiff().args(["testinputs/original.txt", "testinputs/original.txt"]).assert().success().stdout("");
+}
+
+#[test]
+fn run_normal_diff() {
+    ccdiff().args(["testinputs/original.txt", "testinputs/new.txt"]).assert().code(1).stdout(concat!(
+        "3c3\n",
+        "< I’ve used or am using these coding challenges as exercise to learn a new programming language or technology.\n",
+        "---\n",
+        "> These are challenges that I’ve used or am using as exercises to learn a new programming language or technology.\n",
+    ));
+}
+
+#[test]
+fn run_unified_diff() {
+    ccdiff().args(["-U", "1", "testinputs/original.txt", "-"]).write_stdin("changed\n").assert().code(1)
+        .stdout(predicate::str::starts_with("--- testinputs/original.txt\t"))
+        .stdout(predicate::str::contains("\n+++ <stdin>\t"))
+        .stdout(predicate::str::contains("\n@@ -1,4 +1 @@\n-Coding Challenges"))
+        .stdout(predicate::str::ends_with("\n+changed\n"));
+}
+
+#[test]
+fn run_brief() {
+    ccdiff().args(["-q", "testinputs/original.txt", "testinputs/new.txt"]).assert().code(1)
+        .stdout("Files testinputs/original.txt and testinputs/new.txt differ\n");
+}
+
+#[test]
+fn run_missing_file() {
+    ccdiff().args(["testinputs/original.txt", "missing.txt"]).assert().code(2)
+        .stderr(predicate::str::contains("could not read `missing.txt`"));
+}
@@ -0,0 +1,74 @@
+use crate::message::RecordType;
+use crate::{resolve, Iterative, DEFAULT_PORT, DEFAULT_SERVER, ROOT_SERVERS};
+use anyhow::{Context, Result};
+use cc_cli::{is_broken_pipe, BROKEN_PIPE_EXIT_CODE};
+use clap::Parser;
+use std::ffi::OsString;
+use std::io::{stdout, BufWriter, Write};
+use std::net::{IpAddr, SocketAddr};
+use std::process::ExitCode;
+
+/// dns - look up DNS records
+#[derive(Parser)]
+struct Cli {
+    /// The type of record to look up: A, AAAA, CNAME, or NS
+    #[arg(short = 't', long = "type", value_name = "TYPE", default_value = "A")]
+    record_type: RecordType,
+
+    /// The resolver to ask, or with --trace the root server to start from
+    #[arg(short, long, value_name = "ADDRESS")]
+    server: Option<IpAddr>,
+
+    /// The port to send each query to
+    #[arg(short, long, default_value_t = DEFAULT_PORT)]
+    port: u16,
+
+    /// Resolve the name without a resolver, from the root servers down, showing each server asked
+    #[arg(long)]
+    trace: bool,
+
+    /// The name to look up
+    name: String,
+}
+
+/// Run ccdns with the given command line, `args[0]` being the program name
+pub fn main<I, T>(args: I) -> ExitCode
+where
+    I: IntoIterator<Item = T>,
+    T: Into<OsString> + Clone,
+{
+    let args: Cli = cc_cli::parse_from(args, env!("CARGO_PKG_VERSION"));
+
+    match run(args) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) if is_broken_pipe(&e) => ExitCode::from(BROKEN_PIPE_EXIT_CODE),
+        Err(e) => {
+            eprintln!("Error: {:?}", e);
+            ExitCode::FAILURE
+        },
+    }
+}
+
+fn run(args: Cli) -> Result<()> {
+    let mut out = BufWriter::new(stdout().lock());
+    let records = match args.trace {
+        true => {
+            let roots = match args.server {
+                Some(server) => vec![server],
+                None => ROOT_SERVERS.to_vec(),
+            };
+            Iterative::new(&roots, args.port).resolve(&args.name, args.record_type, &mut out)
+        },
+        false => resolve(SocketAddr::new(args.server.unwrap_or(DEFAULT_SERVER), args.port), &args.name, args.record_type),
+    };
+    let records = records.with_context(|| format!("could not look up {} records for {}", args.record_type, args.name))?;
+
+    if records.is_empty() {
+        eprintln!("{} has no {} records", args.name, args.record_type);
+    }
+    for record in records {
+        writeln!(out, "{}", record)?;
+    }
+    out.flush()?;
+    Ok(())
+}
@@ -0,0 +1,309 @@
+// DNS resolver
+// Builds queries by hand and sends them over UDP, either to one resolver that does all the work,
+// or in trace mode to a root server and then to each server it refers us to in turn, the way a
+// resolver works itself. CNAMEs are followed from one name to the next in both.
+use std::collections::hash_map::RandomState;
+use std::fmt;
+use std::hash::{BuildHasher, Hasher};
+use std::io::{self, ErrorKind, Write};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, UdpSocket};
+use std::time::Duration;
+
+mod cli;
+pub mod message;
+
+pub use cli::main;
+use message::{Message, MessageError, Record, RecordData, RecordType};
+
+pub const DEFAULT_PORT: u16 = 53;
+
+/// Google's public resolver, asked when no server is given
+pub const DEFAULT_SERVER: IpAddr = IpAddr::V4(Ipv4Addr::new(8, 8, 8, 8));
+
+/// The root servers a to m, by the addresses every resolver ships with
+pub const ROOT_SERVERS: [IpAddr; 13] = [
+    IpAddr::V4(Ipv4Addr::new(198, 41, 0, 4)),
+    IpAddr::V4(Ipv4Addr::new(170, 247, 170, 2)),
+    IpAddr::V4(Ipv4Addr::new(192, 33, 4, 12)),
+    IpAddr::V4(Ipv4Addr::new(199, 7, 91, 13)),
+    IpAddr::V4(Ipv4Addr::new(192, 203, 230, 10)),
+    IpAddr::V4(Ipv4Addr::new(192, 5, 5, 241)),
+    IpAddr::V4(Ipv4Addr::new(192, 112, 36, 4)),
+    IpAddr::V4(Ipv4Addr::new(198, 97, 190, 53)),
+    IpAddr::V4(Ipv4Addr::new(192, 36, 148, 17)),
+    IpAddr::V4(Ipv4Addr::new(192, 58, 128, 30)),
+    IpAddr::V4(Ipv4Addr::new(193, 0, 14, 129)),
+    IpAddr::V4(Ipv4Addr::new(199, 7, 83, 42)),
+    IpAddr::V4(Ipv4Addr::new(202, 12, 27, 33)),
+];
+
+/// How long to wait for each response
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Most CNAMEs to follow for one lookup
+const MAX_CNAMES: usize = 8;
+
+/// Most queries trace mode makes for one lookup, in case servers refer us round in circles
+const MAX_QUERIES: usize = 64;
+
+#[derive(Debug)]
+pub enum ResolveError {
+    Io(io::Error),
+    Message(MessageError),
+    NoResponse(SocketAddr),
+    /// The response didn't fit in a UDP message, which would take TCP to fetch
+    Truncated,
+    /// The response code of a failed query, e.g. 3 for a name that doesn't exist
+    Failed(u8),
+    /// A referral without an address for any of the servers it names
+    NoServers(String),
+    TooManyCnames,
+    TooManyQueries,
+}
+
+impl From<io::Error> for ResolveError {
+    fn from(e: io::Error) -> ResolveError {
+        ResolveError::Io(e)
+    }
+}
+
+impl From<MessageError> for ResolveError {
+    fn from(e: MessageError) -> ResolveError {
+        ResolveError::Message(e)
+    }
+}
+
+impl fmt::Display for ResolveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ResolveError::Io(e) => write!(f, "{}", e),
+            ResolveError::Message(e) => write!(f, "invalid response: {}", e),
+            ResolveError::NoResponse(server) => write!(f, "no response from {}", server),
+            ResolveError::Truncated => f.write_str("response truncated"),
+            ResolveError::Failed(rcode) => write!(f, "server said {}", rcode_name(*rcode)),
+            ResolveError::NoServers(zone) => write!(f, "no address for any name server of {}.", zone),
+            ResolveError::TooManyCnames => write!(f, "more than {} CNAMEs in a row", MAX_CNAMES),
+            ResolveError::TooManyQueries => write!(f, "gave up after {} queries", MAX_QUERIES),
+        }
+    }
+}
+
+impl std::error::Error for ResolveError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ResolveError::Io(e) => Some(e),
+            ResolveError::Message(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+fn rcode_name(rcode: u8) -> String {
+    match rcode {
+        1 => "FORMERR".to_string(),
+        2 => "SERVFAIL".to_string(),
+        3 => "NXDOMAIN".to_string(),
+        4 => "NOTIMP".to_string(),
+        5 => "REFUSED".to_string(),
+        rcode => format!("RCODE{}", rcode),
+    }
+}
+
+/// A query ID that's hard to guess, so a response can't easily be forged
+fn random_id() -> u16 {
+    RandomState::new().build_hasher().finish() as u16
+}
+
+/// Send one query to `server` and wait for the response to it
+pub fn query(server: SocketAddr, name: &str, record_type: RecordType, recursion_desired: bool) -> Result<Message, ResolveError> {
+    let local: SocketAddr = match server {
+        SocketAddr::V4(_) => (Ipv4Addr::UNSPECIFIED, 0).into(),
+        SocketAddr::V6(_) => (Ipv6Addr::UNSPECIFIED, 0).into(),
+    };
+    let socket = UdpSocket::bind(local)?;
+    socket.connect(server)?;
+    socket.set_read_timeout(Some(TIMEOUT))?;
+
+    let id = random_id();
+    socket.send(&Message::query(id, name, record_type, recursion_desired).encode()?)?;
+
+    let mut buf = [0; message::MAX_UDP_SIZE];
+    loop {
+        let length = match socket.recv(&mut buf) {
+            Err(e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {
+                return Err(ResolveError::NoResponse(server));
+            },
+            result => result?,
+        };
+        let response = Message::parse(&buf[..length])?;
+        // Anything else is a late response to some earlier query
+        if response.id != id || !response.is_response() {
+            continue;
+        }
+        return match response {
+            response if response.is_truncated() => Err(ResolveError::Truncated),
+            response if response.rcode() != 0 => Err(ResolveError::Failed(response.rcode())),
+            response => Ok(response),
+        };
+    }
+}
+
+/// The records of the type asked for, after the CNAMEs followed to get to them. `ask` gives the
+/// answers for one name.
+fn follow_cnames<F>(name: &str, record_type: RecordType, mut ask: F) -> Result<Vec<Record>, ResolveError>
+where
+    F: FnMut(&str) -> Result<Vec<Record>, ResolveError>,
+{
+    let mut records = Vec::new();
+    let mut name = name.strip_suffix('.').unwrap_or(name).to_string();
+    for _ in 0..=MAX_CNAMES {
+        let answers = ask(&name)?;
+        // A resolver usually follows the CNAMEs itself, but a CNAME with nothing for its
+        // target means asking again about the target
+        let done = record_type == RecordType::Cname || answers.iter().any(|r| r.record_type() == record_type);
+        let target = answers.iter().rev().find_map(|r| match &r.data {
+            RecordData::Cname(target) => Some(target.clone()),
+            _ => None,
+        });
+        records.extend(answers);
+        match target {
+            Some(target) if !done => name = target,
+            _ => return Ok(records),
+        }
+    }
+    Err(ResolveError::TooManyCnames)
+}
+
+/// Look up a name by asking a resolver that does the work
+pub fn resolve(server: SocketAddr, name: &str, record_type: RecordType) -> Result<Vec<Record>, ResolveError> {
+    follow_cnames(name, record_type, |name| Ok(query(server, name, record_type, true)?.answers))
+}
+
+/// Look up a name without a resolver, starting at the root servers and asking each server each
+/// one refers us to until one answers, writing the servers asked and the referrals to `trace`
+#[derive(Debug)]
+pub struct Iterative {
+    roots: Vec<IpAddr>,
+    port: u16,
+    queries: usize,
+}
+
+impl Iterative {
+    pub fn new(roots: &[IpAddr], port: u16) -> Iterative {
+        Iterative { roots: roots.to_vec(), port, queries: 0 }
+    }
+
+    pub fn resolve(&mut self, name: &str, record_type: RecordType, trace: &mut dyn Write) -> Result<Vec<Record>, ResolveError> {
+        follow_cnames(name, record_type, |name| self.ask(name, record_type, trace))
+    }
+
+    /// Ask one server after another about one name, going down from the root
+    fn ask(&mut self, name: &str, record_type: RecordType, trace: &mut dyn Write) -> Result<Vec<Record>, ResolveError> {
+        let mut servers = self.roots.clone();
+        loop {
+            let response = self.ask_any(&servers, name, record_type, trace)?;
+            let referral: Vec<&Record> =
+                response.authorities.iter().filter(|r| r.record_type() == RecordType::Ns).collect();
+            // No referral means this server has the answer, even if that's no records at all
+            if !response.answers.is_empty() || referral.is_empty() {
+                return Ok(response.answers);
+            }
+            for record in &referral {
+                writeln!(trace, "{}", record)?;
+            }
+
+            let names: Vec<&str> = referral
+                .iter()
+                .filter_map(|r| match &r.data {
+                    RecordData::Ns(name) => Some(name.as_str()),
+                    _ => None,
+                })
+                .collect();
+            // The glue, the addresses which come with the referral for servers inside the zone
+            // being referred to, which couldn't be looked up without them
+            servers = response
+                .additionals
+                .iter()
+                .filter(|r| names.iter().any(|name| name.eq_ignore_ascii_case(&r.name)))
+                .filter_map(|r| match r.data {
+                    RecordData::A(address) => Some(IpAddr::V4(address)),
+                    _ => None,
+                })
+                .collect();
+            if servers.is_empty() {
+                for name in names {
+                    let found = self.resolve(name, RecordType::A, trace);
+                    servers.extend(found.iter().flatten().filter_map(|r| match r.data {
+                        RecordData::A(address) => Some(IpAddr::V4(address)),
+                        _ => None,
+                    }));
+                    if !servers.is_empty() {
+                        break;
+                    }
+                }
+            }
+            if servers.is_empty() {
+                return Err(ResolveError::NoServers(referral[0].name.clone()));
+            }
+        }
+    }
+
+    /// Ask the first server that responds
+    fn ask_any(&mut self, servers: &[IpAddr], name: &str, record_type: RecordType, trace: &mut dyn Write) -> Result<Message, ResolveError> {
+        let mut error = None;
+        for &server in servers {
+            if self.queries == MAX_QUERIES {
+                return Err(ResolveError::TooManyQueries);
+            }
+            self.queries += 1;
+            let server = SocketAddr::new(server, self.port);
+            writeln!(trace, ";; asking {} about {}. {}", server, name, record_type)?;
+            match query(server, name, record_type, false) {
+                Err(e @ (ResolveError::NoResponse(_) | ResolveError::Io(_))) => {
+                    writeln!(trace, ";; {}", e)?;
+                    error = Some(e);
+                },
+                result => return result,
+            }
+        }
+        Err(error.unwrap_or(ResolveError::NoServers(name.to_string())))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_follow_cnames() {
+        let cname = |from: &str, to: &str| Record::new(from, 60, RecordData::Cname(to.to_string()));
+        let a = |name: &str| Record::new(name, 60, RecordData::A(Ipv4Addr::new(10, 0, 0, 1)));
+        let mut asked = Vec::new();
+        let records = follow_cnames("www.example.com.", RecordType::A, |name| {
+            asked.push(name.to_string());
+            Ok(match name {
+                "www.example.com" => vec![cname("www.example.com", "web.example.com")],
+                _ => vec![cname("web.example.com", "cdn.example.net"), a("cdn.example.net")],
+            })
+        })
+        .unwrap();
+        assert_eq!(asked, ["www.example.com", "web.example.com"]);
+        assert_eq!(records, [
+            cname("www.example.com", "web.example.com"),
+            cname("web.example.com", "cdn.example.net"),
+            a("cdn.example.net"),
+        ]);
+
+        let records = follow_cnames("www.example.com", RecordType::Cname, |name| Ok(vec![cname(name, "x")])).unwrap();
+        assert_eq!(records.len(), 1);
+    }
+
+    #[test]
+    fn check_follow_cnames_loop() {
+        let result = follow_cnames("a", RecordType::A, |name| {
+            let target = if name == "a" { "b" } else { "a" };
+            Ok(vec![Record::new(name, 60, RecordData::Cname(target.to_string()))])
+        });
+        assert!(matches!(result, Err(ResolveError::TooManyCnames)));
+    }
+}
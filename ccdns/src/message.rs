@@ -0,0 +1,443 @@
+// DNS messages.
+// A query or response is a 12 byte header followed by the questions and then the answer,
+// authority, and additional records. Names are written as length-prefixed labels, which a
+// response can shorten by pointing back to the same labels earlier in the packet, e.g. a query
+// for the A records of dns.google with ID 0x0016 and recursion desired is:
+//
+//   0016 0100 0001 0000 0000 0000 03 646e73 06 676f6f676c65 00 0001 0001
+//
+// Reference:  https://datatracker.ietf.org/doc/html/rfc1035#section-4
+use std::fmt;
+use std::net::{Ipv4Addr, Ipv6Addr};
+use std::str::FromStr;
+
+/// Largest message that goes over UDP without extensions
+pub const MAX_UDP_SIZE: usize = 512;
+
+/// The header flags, apart from the opcode and response code
+pub const RESPONSE: u16 = 0x8000;
+pub const AUTHORITATIVE: u16 = 0x0400;
+pub const TRUNCATED: u16 = 0x0200;
+pub const RECURSION_DESIRED: u16 = 0x0100;
+pub const RECURSION_AVAILABLE: u16 = 0x0080;
+
+/// The internet, the only class anyone uses
+const CLASS_IN: u16 = 1;
+
+const MAX_LABEL_LENGTH: usize = 63;
+const MAX_NAME_LENGTH: usize = 255;
+
+#[derive(Debug, PartialEq)]
+pub struct MessageError(String);
+
+impl fmt::Display for MessageError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for MessageError {}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RecordType {
+    A,
+    Ns,
+    Cname,
+    Aaaa,
+    Other(u16),
+}
+
+impl RecordType {
+    pub fn code(self) -> u16 {
+        match self {
+            RecordType::A => 1,
+            RecordType::Ns => 2,
+            RecordType::Cname => 5,
+            RecordType::Aaaa => 28,
+            RecordType::Other(code) => code,
+        }
+    }
+
+    pub fn from_code(code: u16) -> RecordType {
+        match code {
+            1 => RecordType::A,
+            2 => RecordType::Ns,
+            5 => RecordType::Cname,
+            28 => RecordType::Aaaa,
+            code => RecordType::Other(code),
+        }
+    }
+}
+
+impl fmt::Display for RecordType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RecordType::A => f.write_str("A"),
+            RecordType::Ns => f.write_str("NS"),
+            RecordType::Cname => f.write_str("CNAME"),
+            RecordType::Aaaa => f.write_str("AAAA"),
+            // Written the way RFC 3597 gives types without a name
+            RecordType::Other(code) => write!(f, "TYPE{}", code),
+        }
+    }
+}
+
+impl FromStr for RecordType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<RecordType, String> {
+        match s.to_ascii_uppercase().as_str() {
+            "A" => Ok(RecordType::A),
+            "NS" => Ok(RecordType::Ns),
+            "CNAME" => Ok(RecordType::Cname),
+            "AAAA" => Ok(RecordType::Aaaa),
+            _ => Err(format!("unsupported record type `{}`, expected A, AAAA, CNAME, or NS", s)),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum RecordData {
+    A(Ipv4Addr),
+    Aaaa(Ipv6Addr),
+    Cname(String),
+    Ns(String),
+    /// The type and data of any other record, as it came
+    Other(u16, Vec<u8>),
+}
+
+impl fmt::Display for RecordData {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RecordData::A(address) => write!(f, "{}", address),
+            RecordData::Aaaa(address) => write!(f, "{}", address),
+            RecordData::Cname(name) | RecordData::Ns(name) => write!(f, "{}.", name),
+            RecordData::Other(_, bytes) => {
+                write!(f, "\\# {}", bytes.len())?;
+                match bytes.is_empty() {
+                    true => Ok(()),
+                    false => write!(f, " {}", bytes.iter().map(|b| format!("{:02x}", b)).collect::<String>()),
+                }
+            },
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Question {
+    /// The name without the trailing dot, so the root is empty
+    pub name: String,
+    pub record_type: RecordType,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Record {
+    /// The name without the trailing dot, so the root is empty
+    pub name: String,
+    pub ttl: u32,
+    pub data: RecordData,
+}
+
+impl Record {
+    pub fn new(name: &str, ttl: u32, data: RecordData) -> Record {
+        Record { name: name.to_string(), ttl, data }
+    }
+
+    pub fn record_type(&self) -> RecordType {
+        match self.data {
+            RecordData::A(_) => RecordType::A,
+            RecordData::Aaaa(_) => RecordType::Aaaa,
+            RecordData::Cname(_) => RecordType::Cname,
+            RecordData::Ns(_) => RecordType::Ns,
+            RecordData::Other(code, _) => RecordType::from_code(code),
+        }
+    }
+}
+
+/// In the zone file format dig uses, with tabs between the fields
+impl fmt::Display for Record {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}.\t{}\tIN\t{}\t{}", self.name, self.ttl, self.record_type(), self.data)
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Message {
+    pub id: u16,
+    pub flags: u16,
+    pub questions: Vec<Question>,
+    pub answers: Vec<Record>,
+    pub authorities: Vec<Record>,
+    pub additionals: Vec<Record>,
+}
+
+impl Message {
+    /// A standard query asking one question
+    pub fn query(id: u16, name: &str, record_type: RecordType, recursion_desired: bool) -> Message {
+        let name = name.strip_suffix('.').unwrap_or(name).to_string();
+        Message {
+            id,
+            flags: if recursion_desired { RECURSION_DESIRED } else { 0 },
+            questions: vec![Question { name, record_type }],
+            ..Message::default()
+        }
+    }
+
+    pub fn is_response(&self) -> bool {
+        self.flags & RESPONSE != 0
+    }
+
+    pub fn is_truncated(&self) -> bool {
+        self.flags & TRUNCATED != 0
+    }
+
+    /// The response code, 0 for no error
+    pub fn rcode(&self) -> u8 {
+        (self.flags & 0xf) as u8
+    }
+
+    /// Write the message in its wire format, without compressing names
+    pub fn encode(&self) -> Result<Vec<u8>, MessageError> {
+        let mut out = Vec::with_capacity(MAX_UDP_SIZE);
+        out.extend_from_slice(&self.id.to_be_bytes());
+        out.extend_from_slice(&self.flags.to_be_bytes());
+        for count in [self.questions.len(), self.answers.len(), self.authorities.len(), self.additionals.len()] {
+            let count = u16::try_from(count).map_err(|_| MessageError("too many records".to_string()))?;
+            out.extend_from_slice(&count.to_be_bytes());
+        }
+
+        for question in &self.questions {
+            write_name(&mut out, &question.name)?;
+            out.extend_from_slice(&question.record_type.code().to_be_bytes());
+            out.extend_from_slice(&CLASS_IN.to_be_bytes());
+        }
+        for record in self.answers.iter().chain(&self.authorities).chain(&self.additionals) {
+            write_record(&mut out, record)?;
+        }
+        Ok(out)
+    }
+
+    pub fn parse(packet: &[u8]) -> Result<Message, MessageError> {
+        let mut reader = Reader { packet, position: 0 };
+        let id = reader.u16()?;
+        let flags = reader.u16()?;
+        let counts = [reader.u16()?, reader.u16()?, reader.u16()?, reader.u16()?];
+
+        let mut message = Message { id, flags, ..Message::default() };
+        for _ in 0..counts[0] {
+            let name = reader.name()?;
+            let record_type = RecordType::from_code(reader.u16()?);
+            reader.u16()?; // The class
+            message.questions.push(Question { name, record_type });
+        }
+        for (count, records) in counts[1..].iter().zip([&mut message.answers, &mut message.authorities, &mut message.additionals]) {
+            for _ in 0..*count {
+                records.push(reader.record()?);
+            }
+        }
+        Ok(message)
+    }
+}
+
+fn write_name(out: &mut Vec<u8>, name: &str) -> Result<(), MessageError> {
+    let invalid = || MessageError(format!("invalid name `{}`", name));
+    let trimmed = name.strip_suffix('.').unwrap_or(name);
+    // The labels, their lengths, and the root's empty label at the end
+    if trimmed.len() + 2 > MAX_NAME_LENGTH {
+        return Err(invalid());
+    }
+    if !trimmed.is_empty() {
+        for label in trimmed.split('.') {
+            if label.is_empty() || label.len() > MAX_LABEL_LENGTH {
+                return Err(invalid());
+            }
+            out.push(label.len() as u8);
+            out.extend_from_slice(label.as_bytes());
+        }
+    }
+    out.push(0);
+    Ok(())
+}
+
+fn write_record(out: &mut Vec<u8>, record: &Record) -> Result<(), MessageError> {
+    write_name(out, &record.name)?;
+    out.extend_from_slice(&record.record_type().code().to_be_bytes());
+    out.extend_from_slice(&CLASS_IN.to_be_bytes());
+    out.extend_from_slice(&record.ttl.to_be_bytes());
+
+    let mut data = Vec::new();
+    match &record.data {
+        RecordData::A(address) => data.extend_from_slice(&address.octets()),
+        RecordData::Aaaa(address) => data.extend_from_slice(&address.octets()),
+        RecordData::Cname(name) | RecordData::Ns(name) => write_name(&mut data, name)?,
+        RecordData::Other(_, bytes) => data.extend_from_slice(bytes),
+    }
+    let length = u16::try_from(data.len()).map_err(|_| MessageError("record data too long".to_string()))?;
+    out.extend_from_slice(&length.to_be_bytes());
+    out.extend_from_slice(&data);
+    Ok(())
+}
+
+struct Reader<'a> {
+    packet: &'a [u8],
+    position: usize,
+}
+
+fn truncated() -> MessageError {
+    MessageError("message ends too soon".to_string())
+}
+
+impl Reader<'_> {
+    fn bytes(&mut self, length: usize) -> Result<&[u8], MessageError> {
+        let bytes = self.packet.get(self.position..self.position + length).ok_or_else(truncated)?;
+        self.position += length;
+        Ok(bytes)
+    }
+
+    fn u16(&mut self) -> Result<u16, MessageError> {
+        self.bytes(2).map(|b| u16::from_be_bytes([b[0], b[1]]))
+    }
+
+    fn u32(&mut self) -> Result<u32, MessageError> {
+        self.bytes(4).map(|b| u32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+    }
+
+    /// Read a name, following any pointers to labels earlier in the packet
+    fn name(&mut self) -> Result<String, MessageError> {
+        let mut labels = Vec::new();
+        let mut length = 0;
+        let mut position = self.position;
+        // Where to carry on after the name, which is just past the first pointer if there is one
+        let mut end = None;
+
+        loop {
+            let first = *self.packet.get(position).ok_or_else(truncated)?;
+            match first {
+                0 => {
+                    position += 1;
+                    break;
+                },
+                first if first & 0xc0 == 0xc0 => {
+                    let second = *self.packet.get(position + 1).ok_or_else(truncated)?;
+                    let target = usize::from(first & 0x3f) << 8 | usize::from(second);
+                    // Only following pointers that go backwards means a name can't loop
+                    if target >= position {
+                        return Err(MessageError("name points forwards".to_string()));
+                    }
+                    end.get_or_insert(position + 2);
+                    position = target;
+                },
+                first if first & 0xc0 != 0 => return Err(MessageError("invalid label length".to_string())),
+                first => {
+                    let start = position + 1;
+                    let label = self.packet.get(start..start + usize::from(first)).ok_or_else(truncated)?;
+                    length += label.len() + 1;
+                    if length + 1 > MAX_NAME_LENGTH {
+                        return Err(MessageError("name too long".to_string()));
+                    }
+                    labels.push(String::from_utf8_lossy(label));
+                    position = start + label.len();
+                },
+            }
+        }
+
+        self.position = end.unwrap_or(position);
+        Ok(labels.join("."))
+    }
+
+    fn record(&mut self) -> Result<Record, MessageError> {
+        let name = self.name()?;
+        let record_type = RecordType::from_code(self.u16()?);
+        self.u16()?; // The class
+        let ttl = self.u32()?;
+        let length = usize::from(self.u16()?);
+        let start = self.position;
+        let bytes = self.bytes(length)?;
+
+        let data = match (record_type, length) {
+            (RecordType::A, 4) => RecordData::A(Ipv4Addr::from(<[u8; 4]>::try_from(bytes).unwrap())),
+            (RecordType::Aaaa, 16) => RecordData::Aaaa(Ipv6Addr::from(<[u8; 16]>::try_from(bytes).unwrap())),
+            (RecordType::A | RecordType::Aaaa, _) => {
+                return Err(MessageError(format!("{} record with {} bytes of data", record_type, length)));
+            },
+            (RecordType::Cname | RecordType::Ns, _) => {
+                // A name inside the data can still point anywhere earlier in the packet
+                let mut reader = Reader { packet: &self.packet[..start + length], position: start };
+                let name = reader.name()?;
+                match record_type {
+                    RecordType::Cname => RecordData::Cname(name),
+                    _ => RecordData::Ns(name),
+                }
+            },
+            (RecordType::Other(code), _) => RecordData::Other(code, bytes.to_vec()),
+        };
+        Ok(Record { name, ttl, data })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_encode_query() {
+        let query = Message::query(0x0016, "dns.google.", RecordType::A, true);
+        let expected = b"\x00\x16\x01\x00\x00\x01\x00\x00\x00\x00\x00\x00\x03dns\x06google\x00\x00\x01\x00\x01";
+        assert_eq!(query.encode().unwrap(), expected);
+        assert_eq!(Message::query(1, ".", RecordType::Ns, false).encode().unwrap()[12..], [0, 0, 2, 0, 1]);
+    }
+
+    #[test]
+    fn check_encode_invalid_name() {
+        for name in ["a..b", ".a", &"x".repeat(64), &["abc"; 64].join(".")] {
+            let err = Message::query(1, name, RecordType::A, true).encode().unwrap_err();
+            assert!(err.to_string().starts_with("invalid name"), "{}", name);
+        }
+    }
+
+    #[test]
+    fn check_parse_compressed() {
+        // The answers name the question by pointing at offset 12, then the CNAME's target
+        // points at the middle of its own data
+        let mut packet = b"\xbe\xef\x81\x80\x00\x01\x00\x02\x00\x00\x00\x00".to_vec();
+        packet.extend_from_slice(b"\x03www\x07example\x03com\x00\x00\x01\x00\x01");
+        packet.extend_from_slice(b"\xc0\x0c\x00\x05\x00\x01\x00\x00\x0e\x10\x00\x06\x03web\xc0\x10");
+        packet.extend_from_slice(b"\xc0\x2d\x00\x01\x00\x01\x00\x00\x00\x3c\x00\x04\x5d\xb8\xd8\x22");
+
+        let message = Message::parse(&packet).unwrap();
+        assert_eq!(message.id, 0xbeef);
+        assert!(message.is_response() && !message.is_truncated());
+        assert_eq!(message.rcode(), 0);
+        assert_eq!(message.questions, [Question { name: "www.example.com".to_string(), record_type: RecordType::A }]);
+        assert_eq!(message.answers, [
+            Record::new("www.example.com", 3600, RecordData::Cname("web.example.com".to_string())),
+            Record::new("web.example.com", 60, RecordData::A(Ipv4Addr::new(93, 184, 216, 34))),
+        ]);
+        assert_eq!(message.answers[0].to_string(), "www.example.com.\t3600\tIN\tCNAME\tweb.example.com.");
+    }
+
+    #[test]
+    fn check_round_trip() {
+        let message = Message {
+            id: 7,
+            flags: RESPONSE | AUTHORITATIVE,
+            questions: vec![Question { name: "example.com".to_string(), record_type: RecordType::Aaaa }],
+            answers: vec![Record::new("example.com", 1, RecordData::Aaaa("2606:2800:220:1::".parse().unwrap()))],
+            authorities: vec![Record::new("", 2, RecordData::Ns("a.root-servers.net".to_string()))],
+            additionals: vec![Record::new("x", 3, RecordData::Other(16, b"\x02hi".to_vec()))],
+        };
+        assert_eq!(Message::parse(&message.encode().unwrap()).unwrap(), message);
+        assert_eq!(message.authorities[0].to_string(), ".\t2\tIN\tNS\ta.root-servers.net.");
+        assert_eq!(message.additionals[0].to_string(), "x.\t3\tIN\tTYPE16\t\\# 3 026869");
+    }
+
+    #[test]
+    fn check_parse_errors() {
+        let header = b"\x00\x01\x81\x80\x00\x01\x00\x00\x00\x00\x00\x00";
+        let parse = |rest: &[u8]| Message::parse(&[&header[..], rest].concat()).unwrap_err().to_string();
+        assert_eq!(parse(b"\x03www"), "message ends too soon");
+        assert_eq!(parse(b"\xc0\x0c\x00\x01\x00\x01"), "name points forwards");
+        assert_eq!(parse(b"\x80\x00\x01\x00\x01"), "invalid label length");
+        assert_eq!(Message::parse(b"\x00\x01").unwrap_err(), truncated());
+    }
+}
@@ -0,0 +1,123 @@
+use assert_cmd::Command;
+use ccdns::message::{Message, Record, RecordData, RecordType, RESPONSE};
+use predicates::prelude::*;
+use predicates::str::contains;
+use std::net::{Ipv4Addr, UdpSocket};
+use std::thread::{self, JoinHandle};
+
+fn ccdns() -> Command {
+    Command::cargo_bin("ccdns").unwrap()
+}
+
+/// Answer each query with the next of `responses`, returning the port to send them to and a
+/// handle giving the queries that were received
+fn serve(responses: Vec<Message>) -> (u16, JoinHandle<Vec<Message>>) {
+    let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+    let port = socket.local_addr().unwrap().port();
+    let handle = thread::spawn(move || {
+        let mut queries = Vec::new();
+        for mut response in responses {
+            let mut buf = [0; 512];
+            let (length, client) = socket.recv_from(&mut buf).unwrap();
+            let query = Message::parse(&buf[..length]).unwrap();
+            response.id = query.id;
+            response.flags |= RESPONSE;
+            response.questions = query.questions.clone();
+            socket.send_to(&response.encode().unwrap(), client).unwrap();
+            queries.push(query);
+        }
+        queries
+    });
+    (port, handle)
+}
+
+fn a(name: &str, address: [u8; 4]) -> Record {
+    Record::new(name, 300, RecordData::A(Ipv4Addr::from(address)))
+}
+
+fn answer(answers: Vec<Record>) -> Message {
+    Message { answers, ..Message::default() }
+}
+
+/// Refer the query on to the name server for `zone`, at the same address
+fn referral(zone: &str) -> Message {
+    let server = format!("ns.{}", zone);
+    Message {
+        authorities: vec![Record::new(zone, 3600, RecordData::Ns(server.clone()))],
+        additionals: vec![a(&server, [127, 0, 0, 1])],
+        ..Message::default()
+    }
+}
+
+#[test]
+fn run_dns_resolver() {
+    let (port, server) = serve(vec![answer(vec![a("dns.google", [8, 8, 8, 8]), a("dns.google", [8, 8, 4, 4])])]);
+    ccdns()
+        .args(["-s", "127.0.0.1", "-p", &port.to_string(), "dns.google"])
+        .assert()
+        .success()
+        .stdout("dns.google.\t300\tIN\tA\t8.8.8.8\ndns.google.\t300\tIN\tA\t8.8.4.4\n");
+
+    let queries = server.join().unwrap();
+    assert_eq!(queries.len(), 1);
+    assert_eq!(queries[0].flags, ccdns::message::RECURSION_DESIRED);
+    assert_eq!(queries[0].questions[0].name, "dns.google");
+    assert_eq!(queries[0].questions[0].record_type, RecordType::A);
+}
+
+#[test]
+fn run_dns_record_types() {
+    let (port, server) = serve(vec![Message::default()]);
+    ccdns()
+        .args(["-s", "127.0.0.1", "-p", &port.to_string(), "-t", "aaaa", "example.com"])
+        .assert()
+        .success()
+        .stdout("")
+        .stderr("example.com has no AAAA records\n");
+    assert_eq!(server.join().unwrap()[0].questions[0].record_type, RecordType::Aaaa);
+
+    ccdns().args(["-t", "MX", "example.com"]).assert().code(2).stderr(contains("unsupported record type `MX`"));
+}
+
+#[test]
+fn run_dns_trace() {
+    let (port, server) = serve(vec![
+        referral("com"),
+        referral("example.com"),
+        answer(vec![Record::new("www.example.com", 60, RecordData::Cname("web.example.com".to_string()))]),
+        referral("com"),
+        answer(vec![a("web.example.com", [93, 184, 216, 34])]),
+    ]);
+    let address = format!("127.0.0.1:{}", port);
+    ccdns()
+        .args(["--trace", "-s", "127.0.0.1", "-p", &port.to_string(), "www.example.com"])
+        .assert()
+        .success()
+        .stdout(format!(
+            ";; asking {0} about www.example.com. A\n\
+             com.\t3600\tIN\tNS\tns.com.\n\
+             ;; asking {0} about www.example.com. A\n\
+             example.com.\t3600\tIN\tNS\tns.example.com.\n\
+             ;; asking {0} about www.example.com. A\n\
+             ;; asking {0} about web.example.com. A\n\
+             com.\t3600\tIN\tNS\tns.com.\n\
+             ;; asking {0} about web.example.com. A\n\
+             www.example.com.\t60\tIN\tCNAME\tweb.example.com.\n\
+             web.example.com.\t300\tIN\tA\t93.184.216.34\n",
+            address,
+        ));
+    assert!(server.join().unwrap().iter().all(|query| query.flags == 0));
+}
+
+#[test]
+fn run_dns_errors() {
+    let (port, server) = serve(vec![Message { flags: 3, ..Message::default() }]);
+    ccdns()
+        .args(["-s", "127.0.0.1", "-p", &port.to_string(), "nowhere.invalid"])
+        .assert()
+        .failure()
+        .stderr(contains("could not look up A records for nowhere.invalid").and(contains("server said NXDOMAIN")));
+    server.join().unwrap();
+
+    ccdns().arg("a..b").assert().failure().stderr(contains("invalid name `a..b`"));
+}
@@ -0,0 +1,207 @@
+use crate::{search, MatchOptions, Matcher};
+use anyhow::{Context, Result};
+use cc_cli::{is_broken_pipe, BROKEN_PIPE_EXIT_CODE};
+use cc_diagnostics::{ColorChoice, Style};
+use cc_io::Source;
+use cc_walk::WalkArgs;
+use clap::Parser;
+use std::ffi::OsString;
+use std::io::{stdout, BufWriter, Write};
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+/// Like grep, 1 means nothing matched and 2 means something went wrong
+const NO_MATCH_EXIT_CODE: u8 = 1;
+const ERROR_EXIT_CODE: u8 = 2;
+
+const FILE_NAME_STYLE: Style = Style::MAGENTA;
+const LINE_NUMBER_STYLE: Style = Style::GREEN;
+const SEPARATOR_STYLE: Style = Style::CYAN;
+const MATCH_STYLE: Style = Style::RED;
+
+/// grep - print lines that match a pattern
+#[derive(Parser)]
+struct Cli {
+    /// Match the pattern as a literal string rather than a regex
+    #[arg(short = 'F', long)]
+    fixed_strings: bool,
+
+    /// Ignored, patterns are always extended regexes
+    #[arg(short = 'E', long, hide = true)]
+    extended_regexp: bool,
+
+    /// Ignore case distinctions in the pattern and the input
+    #[arg(short, long)]
+    ignore_case: bool,
+
+    /// Select the lines that don't match
+    #[arg(short = 'v', long)]
+    invert_match: bool,
+
+    /// Print the line number before each line
+    #[arg(short = 'n', long)]
+    line_number: bool,
+
+    /// Print only how many lines were selected in each file
+    #[arg(short, long)]
+    count: bool,
+
+    /// Search every file under the given directories, or under the current directory if none are
+    /// given. Binary files are skipped
+    #[arg(short, long)]
+    recursive: bool,
+
+    /// Search files that look binary too
+    #[arg(short = 'a', long)]
+    text: bool,
+
+    #[command(flatten)]
+    walk: WalkArgs,
+
+    /// Highlight matches: auto, always, or never
+    #[arg(long, value_name = "WHEN", default_value = "auto")]
+    color: ColorChoice,
+
+    /// The regex to search for
+    pattern: String,
+
+    /// The files to search, or stdin if there are none
+    paths: Vec<PathBuf>,
+}
+
+/// Run ccgrep with the given command line, `args[0]` being the program name
+pub fn main<I, T>(args: I) -> ExitCode
+where
+    I: IntoIterator<Item = T>,
+    T: Into<OsString> + Clone,
+{
+    let args: Cli = cc_cli::parse_from(args, env!("CARGO_PKG_VERSION"));
+    let mut out = BufWriter::new(stdout().lock());
+
+    let result = run(&args, &mut out).and_then(|summary| {
+        out.flush()?;
+        Ok(summary)
+    });
+    match result {
+        Ok(Summary { failed: true, .. }) => ExitCode::from(ERROR_EXIT_CODE),
+        Ok(Summary { found: true, .. }) => ExitCode::SUCCESS,
+        Ok(Summary { found: false, .. }) => ExitCode::from(NO_MATCH_EXIT_CODE),
+        Err(e) if is_broken_pipe(&e) => ExitCode::from(BROKEN_PIPE_EXIT_CODE),
+        Err(e) => {
+            eprintln!("Error: {:?}", e);
+            ExitCode::from(ERROR_EXIT_CODE)
+        },
+    }
+}
+
+struct Summary {
+    /// Whether any line was selected
+    found: bool,
+    /// Whether any input couldn't be read
+    failed: bool,
+}
+
+fn run(args: &Cli, out: &mut impl Write) -> Result<Summary> {
+    let options = MatchOptions {
+        fixed_strings: args.fixed_strings,
+        ignore_case: args.ignore_case,
+        invert: args.invert_match,
+    };
+    let matcher = Matcher::new(&args.pattern, options).context("invalid pattern")?;
+    let color = args.color.should_color(&stdout());
+
+    let sources = input_sources(args)?;
+    let show_names = sources.len() > 1 || args.recursive;
+    let mut summary = Summary { found: false, failed: false };
+
+    // Like grep, report inputs that can't be read and carry on with the rest
+    for source in &sources {
+        let mut input = match cc_io::open(source) {
+            Ok(input) => input,
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                summary.failed = true;
+                continue;
+            },
+        };
+        if !args.text && !source.is_stdin() {
+            match cc_io::is_binary(&mut input.reader) {
+                Ok(true) => continue,
+                Ok(false) => {},
+                Err(e) => {
+                    eprintln!("Error: could not read `{}`: {}", source.label(), e);
+                    summary.failed = true;
+                    continue;
+                },
+            }
+        }
+
+        let mut name_prefix = String::new();
+        if show_names {
+            name_prefix.push_str(&FILE_NAME_STYLE.paint(&display_name(source), color));
+            name_prefix.push_str(&SEPARATOR_STYLE.paint(":", color));
+        }
+
+        let selected = search(input.reader, &matcher, |number, line| {
+            if args.count {
+                return Ok(());
+            }
+            out.write_all(name_prefix.as_bytes())?;
+            if args.line_number {
+                let number = LINE_NUMBER_STYLE.paint(&number.to_string(), color);
+                write!(out, "{}{}", number, SEPARATOR_STYLE.paint(":", color))?;
+            }
+            if color {
+                write_highlighted(out, line, &matcher.matches(line))?;
+            } else {
+                out.write_all(line)?;
+            }
+            out.write_all(b"\n")
+        })
+        .with_context(|| format!("could not search `{}`", source.label()))?;
+
+        if args.count {
+            writeln!(out, "{}{}", name_prefix, selected)?;
+        }
+        summary.found |= selected > 0;
+    }
+
+    Ok(summary)
+}
+
+/// The inputs to search. In recursive mode, directories are replaced by every file beneath them.
+fn input_sources(args: &Cli) -> Result<Vec<Source>> {
+    if !args.recursive {
+        return Ok(match args.paths.is_empty() {
+            true => vec![Source::Stdin],
+            false => args.paths.iter().map(|path| Source::from(path.clone())).collect(),
+        });
+    }
+
+    let roots = match args.paths.is_empty() {
+        true => vec![PathBuf::from(".")],
+        false => args.paths.clone(),
+    };
+    let files = cc_walk::walk(&roots, &args.walk.options())?;
+    Ok(files.into_iter().map(Source::File).collect())
+}
+
+/// How to name a file before its lines. Files found under the current directory leave off the
+/// leading `./`, like grep.
+fn display_name(source: &Source) -> String {
+    match source {
+        Source::File(path) => path.strip_prefix(".").unwrap_or(path).display().to_string(),
+        Source::Stdin => String::from("(standard input)"),
+    }
+}
+
+fn write_highlighted(out: &mut impl Write, line: &[u8], matches: &[std::ops::Range<usize>]) -> std::io::Result<()> {
+    let mut written = 0;
+    for range in matches {
+        out.write_all(&line[written..range.start])?;
+        let matched = String::from_utf8_lossy(&line[range.clone()]);
+        out.write_all(MATCH_STYLE.paint(&matched, true).as_bytes())?;
+        written = range.end;
+    }
+    out.write_all(&line[written..])
+}
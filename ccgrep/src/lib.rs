@@ -0,0 +1,155 @@
+// Searching core of ccgrep, usable without the command line interface.
+// Lines are matched as bytes, so input that isn't valid UTF-8 can still be searched.
+// My answer to:  https://codingchallenges.substack.com/p/coding-challenge-9
+use regex::bytes::{Regex, RegexBuilder};
+use std::io::{self, BufRead};
+use std::ops::Range;
+
+mod cli;
+
+pub use cli::main;
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct MatchOptions {
+    /// Treat the pattern as a literal string rather than a regex
+    pub fixed_strings: bool,
+    pub ignore_case: bool,
+    /// Select the lines that don't match instead
+    pub invert: bool,
+}
+
+/// Decides which lines are selected, and where in them the pattern matched
+#[derive(Clone, Debug)]
+pub struct Matcher {
+    regex: Regex,
+    invert: bool,
+}
+
+impl Matcher {
+    pub fn new(pattern: &str, options: MatchOptions) -> Result<Matcher, regex::Error> {
+        let pattern = match options.fixed_strings {
+            true => regex::escape(pattern),
+            false => pattern.to_string(),
+        };
+        let regex = RegexBuilder::new(&pattern).case_insensitive(options.ignore_case).build()?;
+
+        Ok(Matcher { regex, invert: options.invert })
+    }
+
+    /// Whether `line`, without its `\n`, is selected. A `\r` ending it is left out of the match,
+    /// so `$` still matches at the end of a CRLF line.
+    pub fn is_selected(&self, line: &[u8]) -> bool {
+        self.regex.is_match(trim_carriage_return(line)) != self.invert
+    }
+
+    /// Where the pattern matches in `line`, for highlighting. Inverted matches have nothing to
+    /// highlight.
+    pub fn matches(&self, line: &[u8]) -> Vec<Range<usize>> {
+        if self.invert {
+            return Vec::new();
+        }
+        let text = trim_carriage_return(line);
+        self.regex.find_iter(text).filter(|m| !m.is_empty()).map(|m| m.range()).collect()
+    }
+}
+
+/// Call `on_line` with the 1-based number and text of each selected line, without its `\n`, and
+/// return how many were selected. Any `\r` before the `\n` is kept, so the line can be written
+/// back out as it was.
+pub fn search(
+    mut reader: impl BufRead,
+    matcher: &Matcher,
+    mut on_line: impl FnMut(usize, &[u8]) -> io::Result<()>,
+) -> io::Result<usize> {
+    let mut line = Vec::new();
+    let mut number = 0;
+    let mut selected = 0;
+    loop {
+        line.clear();
+        if reader.read_until(b'\n', &mut line)? == 0 {
+            return Ok(selected);
+        }
+        number += 1;
+
+        let text = line.strip_suffix(b"\n").unwrap_or(&line);
+        if matcher.is_selected(text) {
+            selected += 1;
+            on_line(number, text)?;
+        }
+    }
+}
+
+fn trim_carriage_return(line: &[u8]) -> &[u8] {
+    line.strip_suffix(b"\r").unwrap_or(line)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn selected(pattern: &str, options: MatchOptions, input: &str) -> Vec<(usize, String)> {
+        let matcher = Matcher::new(pattern, options).unwrap();
+        let mut lines = Vec::new();
+        let count = search(input.as_bytes(), &matcher, |number, line| {
+            lines.push((number, String::from_utf8_lossy(line).into_owned()));
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(count, lines.len());
+        lines
+    }
+
+    #[test]
+    fn check_search_regex() {
+        let input = "apple\nbanana\ncherry\n";
+        assert_eq!(selected("an+a", MatchOptions::default(), input), vec![(2, "banana".to_string())]);
+        assert_eq!(selected("^c|e$", MatchOptions::default(), input).len(), 2);
+    }
+
+    #[test]
+    fn check_search_fixed_strings() {
+        let options = MatchOptions { fixed_strings: true, ..MatchOptions::default() };
+        assert_eq!(selected("a.c", options, "abc\na.c\n"), vec![(2, "a.c".to_string())]);
+    }
+
+    #[test]
+    fn check_search_ignore_case() {
+        let options = MatchOptions { ignore_case: true, ..MatchOptions::default() };
+        assert_eq!(selected("APPLE", options, "Apple pie\n").len(), 1);
+    }
+
+    #[test]
+    fn check_search_invert() {
+        let options = MatchOptions { invert: true, ..MatchOptions::default() };
+        assert_eq!(selected("a", options, "a\nb\r\nc"), vec![(2, "b\r".to_string()), (3, "c".to_string())]);
+    }
+
+    #[test]
+    fn check_search_crlf() {
+        // Matched without the `\r`, handed back with it
+        assert_eq!(selected("b$", MatchOptions::default(), "a\r\nb\r\n"), vec![(2, "b\r".to_string())]);
+        let matcher = Matcher::new("b$", MatchOptions::default()).unwrap();
+        assert_eq!(matcher.matches(b"ab\r"), vec![1..2]);
+    }
+
+    #[test]
+    fn check_search_non_utf8() {
+        let matcher = Matcher::new("caf", MatchOptions::default()).unwrap();
+        assert_eq!(search(&b"caf\xe9\n"[..], &matcher, |_, _| Ok(())).unwrap(), 1);
+    }
+
+    #[test]
+    fn check_matches() {
+        let matcher = Matcher::new("o", MatchOptions::default()).unwrap();
+        assert_eq!(matcher.matches(b"foo"), vec![1..2, 2..3]);
+
+        let inverted = Matcher::new("o", MatchOptions { invert: true, ..MatchOptions::default() }).unwrap();
+        assert!(inverted.matches(b"foo").is_empty());
+    }
+
+    #[test]
+    fn check_invalid_regex() {
+        assert!(Matcher::new("(", MatchOptions::default()).is_err());
+        assert!(Matcher::new("(", MatchOptions { fixed_strings: true, ..MatchOptions::default() }).is_ok());
+    }
+}
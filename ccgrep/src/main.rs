@@ -0,0 +1,3 @@
+fn main() -> std::process::ExitCode {
+    ccgrep::main(std::env::args_os())
+}
@@ -0,0 +1,90 @@
+use assert_cmd::Command;
+
+fn ccgrep() -> Command {
+    let mut cmd = Command::cargo_bin("ccgrep").unwrap();
+    cmd.current_dir(env!("CARGO_MANIFEST_DIR"));
+    cmd
+}
+
+#[test]
+fn run_search_file() {
+    ccgrep().args(["are", "testinputs/dir/poem.txt"]).assert().success()
+        .stdout("Roses are red\nViolets are blue\nAnd so are you\n");
+}
+
+#[test]
+fn run_search_stdin_with_line_numbers() {
+    ccgrep().args(["-n", "b"]).write_stdin("a\nb\nab\n").assert().success().stdout("2:b\n3:ab\n");
+}
+
+#[test]
+fn run_count_inverted_ignoring_case() {
+    ccgrep().args(["-c", "-v", "-i", "ROSES", "testinputs/dir/poem.txt"]).assert().success().stdout("3\n");
+}
+
+#[test]
+fn run_no_match() {
+    ccgrep().args(["zebra", "testinputs/dir/poem.txt"]).assert().code(1).stdout("");
+}
+
+#[test]
+fn run_recursive_skips_binary_files() {
+    ccgrep().args(["-r", "red", "testinputs/dir"]).assert().success()
+        .stdout("testinputs/dir/poem.txt:Roses are red\ntestinputs/dir/sub/fox.txt:the red fox\n");
+}
+
+#[test]
+fn run_recursive_from_current_directory() {
+    let mut cmd = Command::cargo_bin("ccgrep").unwrap();
+    cmd.current_dir(concat!(env!("CARGO_MANIFEST_DIR"), "/testinputs/dir"));
+    cmd.args(["-r", "fox"]).assert().success().stdout("sub/fox.txt:the red fox\n");
+}
+
+#[test]
+fn run_recursive_include_exclude() {
+    ccgrep().args(["-r", "--include", "*.txt", "--exclude", "sub", "red", "testinputs/dir"]).assert().success()
+        .stdout("testinputs/dir/poem.txt:Roses are red\n");
+    ccgrep().args(["-r", "--include", "[", "red", "testinputs/dir"]).assert().code(2);
+}
+
+#[test]
+fn run_color_always() {
+    ccgrep().args(["--color", "always", "o", "-"]).write_stdin("fox\n").assert().success()
+        .stdout("f\x1b[1;31mo\x1b[0mx\n");
+}
+
+#[test]
+fn run_missing_file_still_searches_the_rest() {
+    ccgrep().args(["red", "missing.txt", "testinputs/dir/sub/fox.txt"]).assert().code(2)
+        .stdout("testinputs/dir/sub/fox.txt:the red fox\n");
+}
+
+#[test]
+fn run_invalid_regex() {
+    ccgrep().args(["(", "testinputs/dir/poem.txt"]).assert().code(2);
+}
+
+#[test]
+fn run_man_and_completions() {
+    // Neither needs the pattern the search does
+    let man = ccgrep().arg("man").output().unwrap();
+    assert!(man.status.success());
+    assert!(String::from_utf8(man.stdout).unwrap().contains(".SH NAME\nccgrep \\- print lines that match a pattern\n"));
+    let zsh = ccgrep().args(["completions", "zsh"]).output().unwrap();
+    assert!(String::from_utf8(zsh.stdout).unwrap().starts_with("#compdef ccgrep\n"));
+    // After an option it's the pattern
+    ccgrep().args(["-i", "man"]).write_stdin("a Man\nnot\n").assert().success().stdout("a Man\n");
+}
+
+#[test]
+fn run_crlf_lines_are_written_as_they_were() {
+    ccgrep().args(["a$", "-"]).write_stdin("a\r\nb\r\n").assert().success().stdout("a\r\n");
+}
+
+#[test]
+fn run_unreadable_file_still_searches_the_rest() {
+    // A directory opens but can't be read
+    ccgrep().args(["red", "testinputs/dir", "testinputs/dir/sub/fox.txt"]).assert().code(2)
+        .stdout("testinputs/dir/sub/fox.txt:the red fox\n")
+        .stderr(predicates::str::contains("could not read `testinputs/dir`"));
+}
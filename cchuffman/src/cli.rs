@@ -0,0 +1,104 @@
+use anyhow::{bail, Context, Result};
+use cc_io::{OpenError, Source};
+use clap::{Parser, Subcommand};
+use std::ffi::OsString;
+use std::fs::File;
+use std::io::{stdout, Cursor, IsTerminal, Read, Write};
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+/// huffman - compress and decompress files with Huffman coding
+#[derive(Parser)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Compress a file
+    Compress {
+        /// The file to compress, or - for stdin
+        file: PathBuf,
+
+        /// Write the compressed file here instead of stdout
+        #[arg(short, long, value_name = "OUT")]
+        output: Option<PathBuf>,
+    },
+
+    /// Restore a file compressed with `compress`
+    Decompress {
+        /// The compressed file, or - for stdin
+        file: PathBuf,
+
+        /// Write the restored file here instead of stdout
+        #[arg(short, long, value_name = "OUT")]
+        output: Option<PathBuf>,
+    },
+}
+
+/// Run the tool with the given command line, `args[0]` being the program name
+pub fn main<I, T>(args: I) -> ExitCode
+where
+    I: IntoIterator<Item = T>,
+    T: Into<OsString> + Clone,
+{
+    let args: Cli = cc_cli::parse_from(args, env!("CARGO_PKG_VERSION"));
+
+    match run(args.command) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("Error: {:?}", e);
+            ExitCode::FAILURE
+        },
+    }
+}
+
+fn run(command: Command) -> Result<()> {
+    match command {
+        Command::Compress { file, output } => {
+            let source = Source::from(file);
+            if output.is_none() && stdout().is_terminal() {
+                bail!("refusing to write compressed data to a terminal, use -o to name an output file");
+            }
+            let result = match &source {
+                // Compressing reads the input twice, so stdin is held in memory to read it again
+                Source::Stdin => {
+                    let mut data = Vec::new();
+                    std::io::stdin().lock().read_to_end(&mut data).context("could not read stdin")?;
+                    with_output(output, |out| crate::compress(Cursor::new(data), out))
+                },
+                Source::File(path) => {
+                    let file = File::open(path).map_err(|e| OpenError { label: source.label(), source: e })?;
+                    with_output(output, |out| crate::compress(file, out))
+                },
+            };
+            result.with_context(|| format!("could not compress `{}`", source.label()))
+        },
+        Command::Decompress { file, output } => {
+            let source = Source::from(file);
+            let input = cc_io::open(&source)?;
+            with_output(output, |out| crate::decompress(input.reader, out))
+                .with_context(|| format!("could not decompress `{}`", source.label()))
+        },
+    }
+}
+
+/// Run `write` against the output file, or stdout without one. A file left half written by a
+/// failure is removed, so it can't be mistaken for a good one.
+fn with_output(
+    path: Option<PathBuf>,
+    write: impl FnOnce(&mut dyn Write) -> std::io::Result<()>,
+) -> Result<()> {
+    let Some(path) = path else {
+        return Ok(write(&mut stdout().lock())?);
+    };
+
+    let mut file = File::create(&path).with_context(|| format!("could not create `{}`", path.display()))?;
+    if let Err(e) = write(&mut file) {
+        drop(file);
+        let _ = std::fs::remove_file(&path);
+        return Err(e.into());
+    }
+    Ok(())
+}
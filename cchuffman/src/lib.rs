@@ -0,0 +1,426 @@
+// Huffman coding core of cchuffman, usable without the command line interface.
+// A compressed file is a header holding the original length and the code length of every byte
+// value, followed by the canonical Huffman code of each input byte, packed most significant bit
+// first.
+// My answer to:  https://codingchallenges.substack.com/p/coding-challenge-3
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::io::{self, BufRead, BufReader, BufWriter, ErrorKind, Read, Seek, SeekFrom, Write};
+
+mod cli;
+
+pub use cli::main;
+
+/// The first bytes of every compressed file
+pub const MAGIC: &[u8; 4] = b"CCHF";
+pub const VERSION: u8 = 1;
+
+/// Longest code accepted when decompressing. Inputs shorter than 2^64 bytes can't need codes
+/// anywhere near this long, so anything longer is a corrupt header.
+pub const MAX_CODE_LENGTH: u8 = 100;
+
+const SYMBOLS: usize = 256;
+const BLOCK_SIZE: usize = 64 * 1024;
+
+/// How often each byte value occurs
+pub type Frequencies = [u64; SYMBOLS];
+
+/// A code of `length` bits, held in the low bits of `bits`
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Code {
+    pub bits: u128,
+    pub length: u8,
+}
+
+/// Count how often each byte value occurs in `reader`
+pub fn frequencies(mut reader: impl Read) -> io::Result<Frequencies> {
+    let mut freqs = [0; SYMBOLS];
+    let mut buf = vec![0; BLOCK_SIZE];
+    loop {
+        let n = match reader.read(&mut buf) {
+            Ok(0) => break,
+            Ok(n) => n,
+            Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        for &b in &buf[..n] {
+            freqs[b as usize] += 1;
+        }
+    }
+    Ok(freqs)
+}
+
+/// The length in bits of each byte value's code, 0 for values that never occur
+pub fn code_lengths(freqs: &Frequencies) -> [u8; SYMBOLS] {
+    let mut lengths = [0; SYMBOLS];
+    let mut heap: BinaryHeap<Reverse<(u64, usize)>> = freqs
+        .iter()
+        .enumerate()
+        .filter(|(_, &freq)| freq > 0)
+        .map(|(symbol, &freq)| Reverse((freq, symbol)))
+        .collect();
+
+    // A lone byte value still needs a code one bit long
+    if heap.len() == 1 {
+        let Reverse((_, symbol)) = heap.pop().unwrap();
+        lengths[symbol] = 1;
+        return lengths;
+    }
+
+    // Nodes below SYMBOLS are the leaves, the rest are merged nodes, and each points at its parent
+    let mut parent = vec![usize::MAX; SYMBOLS];
+    while heap.len() > 1 {
+        let Reverse((freq_a, a)) = heap.pop().unwrap();
+        let Reverse((freq_b, b)) = heap.pop().unwrap();
+        let node = parent.len();
+        parent.push(usize::MAX);
+        parent[a] = node;
+        parent[b] = node;
+        heap.push(Reverse((freq_a + freq_b, node)));
+    }
+
+    for symbol in (0..SYMBOLS).filter(|&s| freqs[s] > 0) {
+        let mut node = symbol;
+        while parent[node] != usize::MAX {
+            node = parent[node];
+            lengths[symbol] += 1;
+        }
+    }
+    lengths
+}
+
+/// Assign canonical codes: shorter codes come first, and codes of the same length go in byte
+/// order, so the lengths alone are enough to rebuild the codes when decompressing
+pub fn canonical_codes(lengths: &[u8; SYMBOLS]) -> [Code; SYMBOLS] {
+    let mut symbols: Vec<usize> = (0..SYMBOLS).filter(|&s| lengths[s] > 0).collect();
+    symbols.sort_by_key(|&s| (lengths[s], s));
+
+    let mut codes = [Code::default(); SYMBOLS];
+    let mut next = 0u128;
+    let mut previous_length = 0;
+    for symbol in symbols {
+        let length = lengths[symbol];
+        next <<= length - previous_length;
+        codes[symbol] = Code { bits: next, length };
+        next += 1;
+        previous_length = length;
+    }
+    codes
+}
+
+/// Compress `input` into `output`. The input is read twice, once to count its bytes and once to
+/// encode them, so it has to be seekable.
+pub fn compress<R: Read + Seek>(mut input: R, output: impl Write) -> io::Result<()> {
+    let start = input.stream_position()?;
+    let freqs = frequencies(&mut input)?;
+    input.seek(SeekFrom::Start(start))?;
+
+    let lengths = code_lengths(&freqs);
+    let codes = canonical_codes(&lengths);
+    let total: u64 = freqs.iter().sum();
+
+    let mut output = BufWriter::new(output);
+    output.write_all(MAGIC)?;
+    output.write_all(&[VERSION])?;
+    output.write_all(&total.to_le_bytes())?;
+    output.write_all(&lengths)?;
+
+    let mut bits = BitWriter::new(output);
+    let mut encoded = 0;
+    let mut buf = vec![0; BLOCK_SIZE];
+    loop {
+        let n = match input.read(&mut buf) {
+            Ok(0) => break,
+            Ok(n) => n,
+            Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        for &b in &buf[..n] {
+            let code = codes[b as usize];
+            if code.length == 0 {
+                return Err(changed_while_compressing());
+            }
+            bits.write(code)?;
+        }
+        encoded += n as u64;
+    }
+    if encoded != total {
+        return Err(changed_while_compressing());
+    }
+
+    bits.finish()?.flush()
+}
+
+fn changed_while_compressing() -> io::Error {
+    io::Error::other("input changed while it was being compressed")
+}
+
+/// Decompress `input`, as written by `compress`, into `output`
+pub fn decompress(input: impl Read, output: impl Write) -> io::Result<()> {
+    let mut input = BufReader::new(input);
+
+    let mut magic = [0; 4];
+    read_header(&mut input, &mut magic)?;
+    if &magic != MAGIC {
+        return Err(invalid_data("not a cchuffman file"));
+    }
+    let mut version = [0];
+    read_header(&mut input, &mut version)?;
+    if version[0] != VERSION {
+        return Err(invalid_data(&format!("unsupported version {}", version[0])));
+    }
+    let mut total = [0; 8];
+    read_header(&mut input, &mut total)?;
+    let total = u64::from_le_bytes(total);
+    let mut lengths = [0; SYMBOLS];
+    read_header(&mut input, &mut lengths)?;
+
+    let decoder = Decoder::new(&lengths)?;
+    if total > 0 && decoder.symbols.is_empty() {
+        return Err(invalid_data("header has no codes for a non-empty file"));
+    }
+
+    let mut bits = BitReader::new(input);
+    let mut output = BufWriter::new(output);
+    for _ in 0..total {
+        output.write_all(&[decoder.decode(&mut bits)?])?;
+    }
+    output.flush()
+}
+
+fn read_header(input: &mut impl Read, buf: &mut [u8]) -> io::Result<()> {
+    input.read_exact(buf).map_err(|e| match e.kind() {
+        ErrorKind::UnexpectedEof => invalid_data("header is truncated"),
+        _ => e,
+    })
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(ErrorKind::InvalidData, message.to_string())
+}
+
+/// Rebuilds canonical codes from their lengths, and reads them back into bytes
+struct Decoder {
+    /// Byte values in canonical order
+    symbols: Vec<u8>,
+    /// For each code length, how many codes have it, the first of them, and the index of its
+    /// byte value in `symbols`
+    count: [u128; MAX_CODE_LENGTH as usize + 1],
+    first_code: [u128; MAX_CODE_LENGTH as usize + 1],
+    first_index: [usize; MAX_CODE_LENGTH as usize + 1],
+}
+
+impl Decoder {
+    fn new(lengths: &[u8; SYMBOLS]) -> io::Result<Decoder> {
+        if lengths.iter().any(|&length| length > MAX_CODE_LENGTH) {
+            return Err(invalid_data("header has a code that is too long"));
+        }
+
+        let mut symbols: Vec<u8> = (0..=255u8).filter(|&s| lengths[s as usize] > 0).collect();
+        symbols.sort_by_key(|&s| lengths[s as usize]);
+
+        let mut decoder = Decoder {
+            symbols,
+            count: [0; MAX_CODE_LENGTH as usize + 1],
+            first_code: [0; MAX_CODE_LENGTH as usize + 1],
+            first_index: [0; MAX_CODE_LENGTH as usize + 1],
+        };
+        for &length in lengths.iter().filter(|&&length| length > 0) {
+            decoder.count[length as usize] += 1;
+        }
+
+        let mut code = 0;
+        let mut index = 0;
+        for length in 1..=MAX_CODE_LENGTH as usize {
+            code = (code + decoder.count[length - 1]) << 1;
+            decoder.first_code[length] = code;
+            decoder.first_index[length] = index;
+            index += decoder.count[length] as usize;
+
+            // More codes than fit in this many bits means the lengths can't come from a real tree
+            if code + decoder.count[length] > 1 << length {
+                return Err(invalid_data("header has inconsistent code lengths"));
+            }
+        }
+        Ok(decoder)
+    }
+
+    fn decode(&self, bits: &mut BitReader<impl BufRead>) -> io::Result<u8> {
+        let mut code = 0;
+        for length in 1..=MAX_CODE_LENGTH as usize {
+            code = code << 1 | bits.read()? as u128;
+            let offset = code.wrapping_sub(self.first_code[length]);
+            if code >= self.first_code[length] && offset < self.count[length] {
+                return Ok(self.symbols[self.first_index[length] + offset as usize]);
+            }
+        }
+        Err(invalid_data("compressed data has an unknown code"))
+    }
+}
+
+/// Packs codes into bytes, most significant bit first
+struct BitWriter<W> {
+    inner: W,
+    byte: u8,
+    filled: u8,
+}
+
+impl<W: Write> BitWriter<W> {
+    fn new(inner: W) -> Self {
+        BitWriter { inner, byte: 0, filled: 0 }
+    }
+
+    fn write(&mut self, code: Code) -> io::Result<()> {
+        for i in (0..code.length).rev() {
+            self.byte = self.byte << 1 | (code.bits >> i & 1) as u8;
+            self.filled += 1;
+            if self.filled == 8 {
+                self.inner.write_all(&[self.byte])?;
+                self.byte = 0;
+                self.filled = 0;
+            }
+        }
+        Ok(())
+    }
+
+    /// Pad the last byte with zeros and hand back the writer
+    fn finish(mut self) -> io::Result<W> {
+        if self.filled > 0 {
+            self.inner.write_all(&[self.byte << (8 - self.filled)])?;
+        }
+        Ok(self.inner)
+    }
+}
+
+struct BitReader<R> {
+    inner: R,
+    byte: u8,
+    left: u8,
+}
+
+impl<R: BufRead> BitReader<R> {
+    fn new(inner: R) -> Self {
+        BitReader { inner, byte: 0, left: 0 }
+    }
+
+    fn read(&mut self) -> io::Result<bool> {
+        if self.left == 0 {
+            let mut byte = [0];
+            self.inner.read_exact(&mut byte).map_err(|e| match e.kind() {
+                ErrorKind::UnexpectedEof => invalid_data("compressed data is truncated"),
+                _ => e,
+            })?;
+            self.byte = byte[0];
+            self.left = 8;
+        }
+        self.left -= 1;
+        Ok(self.byte >> self.left & 1 == 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn round_trip(data: &[u8]) -> Vec<u8> {
+        let mut compressed = Vec::new();
+        compress(Cursor::new(data), &mut compressed).unwrap();
+
+        let mut decompressed = Vec::new();
+        decompress(&compressed[..], &mut decompressed).unwrap();
+        assert_eq!(decompressed, data);
+        compressed
+    }
+
+    #[test]
+    fn check_code_lengths() {
+        let mut freqs = [0; 256];
+        freqs[b'a' as usize] = 1;
+        freqs[b'b' as usize] = 1;
+        freqs[b'c' as usize] = 2;
+        let lengths = code_lengths(&freqs);
+        assert_eq!((lengths[b'a' as usize], lengths[b'b' as usize], lengths[b'c' as usize]), (2, 2, 1));
+        assert_eq!(lengths.iter().filter(|&&l| l > 0).count(), 3);
+    }
+
+    #[test]
+    fn check_canonical_codes() {
+        let mut lengths = [0; 256];
+        lengths[b'a' as usize] = 2;
+        lengths[b'b' as usize] = 2;
+        lengths[b'c' as usize] = 1;
+        let codes = canonical_codes(&lengths);
+        assert_eq!(codes[b'c' as usize], Code { bits: 0b0, length: 1 });
+        assert_eq!(codes[b'a' as usize], Code { bits: 0b10, length: 2 });
+        assert_eq!(codes[b'b' as usize], Code { bits: 0b11, length: 2 });
+    }
+
+    #[test]
+    fn check_round_trip_text() {
+        let text = "A MAN, A PLAN, A CANAL: PANAMA. ".repeat(100);
+        let compressed = round_trip(text.as_bytes());
+        assert!(compressed.len() < text.len());
+    }
+
+    #[test]
+    fn check_round_trip_empty() {
+        let compressed = round_trip(b"");
+        assert_eq!(compressed.len(), 4 + 1 + 8 + 256);
+    }
+
+    #[test]
+    fn check_round_trip_single_byte_value() {
+        round_trip(b"zzzzzzzzzzzz");
+        round_trip(b"z");
+    }
+
+    #[test]
+    fn check_round_trip_every_byte_value() {
+        let data: Vec<u8> = (0..=255u8).cycle().take(256 * 3 + 17).collect();
+        round_trip(&data);
+    }
+
+    #[test]
+    fn check_round_trip_skewed_frequencies() {
+        // Fibonacci frequencies give the deepest possible tree for this many byte values
+        let mut data = Vec::new();
+        let (mut a, mut b) = (1, 1);
+        for symbol in 0..20u8 {
+            data.extend(std::iter::repeat_n(symbol, a));
+            (a, b) = (b, a + b);
+        }
+        round_trip(&data);
+    }
+
+    #[test]
+    fn check_decompress_rejects_other_files() {
+        let mut out = Vec::new();
+        let err = decompress(&b"PK\x03\x04 not ours"[..], &mut out).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+        assert_eq!(err.to_string(), "not a cchuffman file");
+    }
+
+    #[test]
+    fn check_decompress_truncated() {
+        let mut compressed = Vec::new();
+        compress(Cursor::new(b"truncate me, please"), &mut compressed).unwrap();
+        compressed.truncate(compressed.len() - 2);
+
+        let mut out = Vec::new();
+        let err = decompress(&compressed[..], &mut out).unwrap_err();
+        assert_eq!(err.to_string(), "compressed data is truncated");
+    }
+
+    #[test]
+    fn check_decompress_rejects_bad_lengths() {
+        let mut compressed = Vec::new();
+        compress(Cursor::new(b"abc"), &mut compressed).unwrap();
+        // Give every byte value a one bit code, far more than one bit can tell apart
+        compressed[13..13 + 256].fill(1);
+
+        let mut out = Vec::new();
+        let err = decompress(&compressed[..], &mut out).unwrap_err();
+        assert_eq!(err.to_string(), "header has inconsistent code lengths");
+    }
+}
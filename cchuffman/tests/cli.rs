@@ -0,0 +1,49 @@
+use assert_cmd::Command;
+use std::path::PathBuf;
+
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("cchuffman-{}-{}", name, std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+#[test]
+fn run_round_trip_through_files() {
+    let dir = temp_dir("files");
+    let original = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("src/lib.rs");
+    let compressed = dir.join("lib.rs.hf");
+    let restored = dir.join("lib.rs");
+
+    Command::cargo_bin("cchuffman").unwrap()
+        .arg("compress").arg(&original).arg("-o").arg(&compressed)
+        .assert().success();
+    Command::cargo_bin("cchuffman").unwrap()
+        .arg("decompress").arg(&compressed).arg("-o").arg(&restored)
+        .assert().success();
+
+    assert_eq!(std::fs::read(&restored).unwrap(), std::fs::read(&original).unwrap());
+    assert!(std::fs::metadata(&compressed).unwrap().len() < std::fs::metadata(&original).unwrap().len());
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn run_round_trip_through_pipes() {
+    let compressed = Command::cargo_bin("cchuffman").unwrap()
+        .args(["compress", "-"]).write_stdin("lorem ipsum dolor sit amet")
+        .output().unwrap().stdout;
+    Command::cargo_bin("cchuffman").unwrap()
+        .args(["decompress", "-"]).write_stdin(compressed)
+        .assert().success().stdout("lorem ipsum dolor sit amet");
+}
+
+#[test]
+fn run_decompress_garbage_leaves_no_output() {
+    let dir = temp_dir("garbage");
+    let restored = dir.join("out");
+
+    Command::cargo_bin("cchuffman").unwrap()
+        .args(["decompress", "-", "-o"]).arg(&restored).write_stdin("not compressed")
+        .assert().failure().stderr(predicates::str::contains("not a cchuffman file"));
+    assert!(!restored.exists());
+    std::fs::remove_dir_all(&dir).unwrap();
+}
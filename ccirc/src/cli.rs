@@ -0,0 +1,155 @@
+use crate::message::Message;
+use crate::{Session, DEFAULT_PORT};
+use anyhow::{bail, Context, Result};
+use cc_cli::{is_broken_pipe, BROKEN_PIPE_EXIT_CODE};
+use clap::Parser;
+use std::ffi::OsString;
+use std::io::{self, stdin, stdout, BufRead, BufReader, ErrorKind, Write};
+use std::net::TcpStream;
+use std::process::ExitCode;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+/// How long the server can stay quiet before we ping it, and then how long it has to answer
+const PING_INTERVAL: Duration = Duration::from_secs(120);
+
+/// irc - chat on an IRC server
+#[derive(Parser)]
+struct Cli {
+    /// The nick to go by
+    #[arg(short, long, default_value = "ccirc")]
+    nick: String,
+
+    /// The user name to register, the nick by default
+    #[arg(short, long)]
+    user: Option<String>,
+
+    /// The real name to register, the nick by default
+    #[arg(short, long, value_name = "NAME")]
+    realname: Option<String>,
+
+    /// Join this channel once the server welcomes us
+    #[arg(short, long, value_name = "CHANNEL")]
+    join: Option<String>,
+
+    /// The port to connect to
+    #[arg(short, long, default_value_t = DEFAULT_PORT)]
+    port: u16,
+
+    /// The server to connect to
+    server: String,
+}
+
+/// Run ccirc with the given command line, `args[0]` being the program name
+pub fn main<I, T>(args: I) -> ExitCode
+where
+    I: IntoIterator<Item = T>,
+    T: Into<OsString> + Clone,
+{
+    let args: Cli = cc_cli::parse_from(args, env!("CARGO_PKG_VERSION"));
+
+    match run(args) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) if is_broken_pipe(&e) => ExitCode::from(BROKEN_PIPE_EXIT_CODE),
+        Err(e) => {
+            eprintln!("Error: {:?}", e);
+            ExitCode::FAILURE
+        },
+    }
+}
+
+/// The session and the connection to send on, shared by the thread reading what's typed and the
+/// one reading from the server, so their messages never interleave
+struct Connection {
+    session: Session,
+    out: TcpStream,
+}
+
+impl Connection {
+    fn send(&mut self, messages: &[Message]) -> io::Result<()> {
+        for message in messages {
+            let line = message.encode().map_err(|e| io::Error::new(ErrorKind::InvalidInput, e))?;
+            self.out.write_all(line.as_bytes())?;
+        }
+        Ok(())
+    }
+}
+
+fn run(args: Cli) -> Result<()> {
+    let stream = TcpStream::connect((args.server.as_str(), args.port))
+        .with_context(|| format!("could not connect to {} port {}", args.server, args.port))?;
+    stream.set_read_timeout(Some(PING_INTERVAL))?;
+
+    let mut session = Session::new(&args.nick);
+    if let Some(channel) = &args.join {
+        session.input(&format!("/join {}", channel))?;
+    }
+    let register = session.register(args.user.as_deref().unwrap_or(&args.nick), args.realname.as_deref().unwrap_or(&args.nick));
+    let connection = Arc::new(Mutex::new(Connection { session, out: stream.try_clone()? }));
+    connection.lock().unwrap().send(&register)?;
+
+    let typing = Arc::clone(&connection);
+    // The connection closing ends the program, and with it this thread
+    thread::spawn(move || read_input(&typing));
+
+    let mut out = stdout().lock();
+    let mut reader = BufReader::new(stream);
+    let mut line = Vec::new();
+    let mut pinged = false;
+    loop {
+        match reader.read_until(b'\n', &mut line) {
+            Ok(0) => break,
+            Ok(_) => pinged = false,
+            Err(e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {
+                if pinged {
+                    bail!("no response from {} in {} seconds", args.server, PING_INTERVAL.as_secs());
+                }
+                connection.lock().unwrap().send(&[Message::new("PING", &[&args.server])])?;
+                pinged = true;
+                continue;
+            },
+            Err(e) => return Err(e).context("could not read from the server"),
+        }
+
+        let text = String::from_utf8_lossy(&line);
+        match Message::parse(&text) {
+            Ok(message) => {
+                let mut connection = connection.lock().unwrap();
+                let mut replies = Vec::new();
+                let shown = connection.session.receive(&message, &mut replies);
+                connection.send(&replies)?;
+                if let Some(shown) = shown {
+                    writeln!(out, "{}", shown)?;
+                    out.flush()?;
+                }
+            },
+            Err(e) => eprintln!("-!- ignoring `{}` from the server: {}", text.trim_end(), e),
+        }
+        line.clear();
+    }
+
+    if !connection.lock().unwrap().session.has_quit() {
+        bail!("the server closed the connection");
+    }
+    Ok(())
+}
+
+/// Send each line typed, and quit when there are no more
+fn read_input(connection: &Mutex<Connection>) -> Result<()> {
+    for line in stdin().lock().lines() {
+        let line = line?;
+        let mut connection = connection.lock().unwrap();
+        match connection.session.input(&line) {
+            Ok(messages) => connection.send(&messages)?,
+            Err(e) => eprintln!("-!- {}", e),
+        }
+        if connection.session.is_quitting() {
+            return Ok(());
+        }
+    }
+    let mut connection = connection.lock().unwrap();
+    let messages = connection.session.input("/quit")?;
+    connection.send(&messages)?;
+    Ok(())
+}
@@ -0,0 +1,273 @@
+// IRC client
+// Registers a nick with the server, then sends on each line typed and shows each message that
+// comes back on a line of its own. Text goes to the channel last joined, and anything else is a
+// command starting with `/`:
+//
+//   /join CHANNEL   /part [CHANNEL]   /msg NICK TEXT   /nick NICK   /quote RAW   /quit [REASON]
+//
+// The server pings every so often to check we're still there, and we ping it when it goes quiet.
+// Reference:  https://datatracker.ietf.org/doc/html/rfc2812
+use std::fmt;
+
+mod cli;
+pub mod message;
+
+pub use cli::main;
+use message::{Message, MessageError};
+
+pub const DEFAULT_PORT: u16 = 6667;
+
+/// A line typed that can't be sent
+#[derive(Debug, PartialEq)]
+pub struct InputError(String);
+
+impl From<MessageError> for InputError {
+    fn from(e: MessageError) -> InputError {
+        InputError(e.to_string())
+    }
+}
+
+impl fmt::Display for InputError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for InputError {}
+
+fn is_channel(target: &str) -> bool {
+    target.starts_with(['#', '&', '+', '!'])
+}
+
+/// What we know of the conversation so far
+#[derive(Debug)]
+pub struct Session {
+    /// Our nick, as the server last confirmed it or as we asked for it before that
+    pub nick: String,
+    /// Where text that isn't a command goes
+    pub channel: Option<String>,
+    registered: bool,
+    quitting: bool,
+    /// Messages typed before the server welcomed us, which it would refuse until then
+    pending: Vec<Message>,
+}
+
+impl Session {
+    pub fn new(nick: &str) -> Session {
+        Session { nick: nick.to_string(), channel: None, registered: false, quitting: false, pending: Vec::new() }
+    }
+
+    /// The messages introducing us to the server, which welcomes us once it's taken the nick
+    pub fn register(&self, user: &str, realname: &str) -> Vec<Message> {
+        vec![Message::new("NICK", &[&self.nick]), Message::new("USER", &[user, "0", "*", realname])]
+    }
+
+    /// Whether we've asked to leave the server, after which there's nothing more to send
+    pub fn is_quitting(&self) -> bool {
+        self.quitting || self.pending.iter().any(|message| message.command == "QUIT")
+    }
+
+    /// Whether we've told the server we're leaving, rather than waiting to be welcomed first
+    pub fn has_quit(&self) -> bool {
+        self.quitting
+    }
+
+    /// Turn a line typed into the messages to send for it, which are held back until the server
+    /// has welcomed us
+    pub fn input(&mut self, line: &str) -> Result<Vec<Message>, InputError> {
+        let messages = self.parse_input(line)?;
+        for message in &messages {
+            message.encode()?;
+        }
+        match self.registered {
+            true => {
+                self.quitting |= messages.iter().any(|message| message.command == "QUIT");
+                Ok(messages)
+            },
+            false => {
+                self.pending.extend(messages);
+                Ok(Vec::new())
+            },
+        }
+    }
+
+    fn parse_input(&mut self, line: &str) -> Result<Vec<Message>, InputError> {
+        let line = line.trim_end_matches(['\r', '\n']);
+        let command = match line.strip_prefix('/') {
+            // `//` is how to say something starting with `/`
+            Some(command) if !command.starts_with('/') => command,
+            _ if line.is_empty() => return Ok(Vec::new()),
+            _ => {
+                let text = line.strip_prefix('/').unwrap_or(line);
+                let channel = self.channel.as_deref().ok_or_else(|| InputError("not in a channel, /join one first".to_string()))?;
+                return Ok(vec![Message::new("PRIVMSG", &[channel, text])]);
+            },
+        };
+
+        let (name, rest) = command.split_once(' ').unwrap_or((command, ""));
+        let rest = rest.trim();
+        let usage = |usage: &str| InputError(format!("usage: /{}", usage));
+        let message = match name.to_ascii_lowercase().as_str() {
+            "join" if !rest.is_empty() && !rest.contains(' ') => {
+                self.channel = rest.split(',').next().map(str::to_string);
+                Message::new("JOIN", &[rest])
+            },
+            "join" => return Err(usage("join CHANNEL")),
+            "part" => {
+                let channel = match rest {
+                    "" => self.channel.clone().ok_or_else(|| InputError("not in a channel".to_string()))?,
+                    channel => channel.to_string(),
+                };
+                if self.channel.as_ref().is_some_and(|current| current.eq_ignore_ascii_case(&channel)) {
+                    self.channel = None;
+                }
+                Message::new("PART", &[&channel])
+            },
+            "msg" => match rest.split_once(' ') {
+                Some((target, text)) => Message::new("PRIVMSG", &[target, text]),
+                None => return Err(usage("msg NICK TEXT")),
+            },
+            "nick" if !rest.is_empty() && !rest.contains(' ') => Message::new("NICK", &[rest]),
+            "nick" => return Err(usage("nick NICK")),
+            "quote" => Message::parse(rest)?,
+            "quit" if rest.is_empty() => Message::new("QUIT", &[]),
+            "quit" => Message::new("QUIT", &[rest]),
+            _ => return Err(InputError(format!("unknown command `/{}`", name))),
+        };
+        Ok(vec![message])
+    }
+
+    /// Take in a message from the server, adding any that need sending back to `replies`, and
+    /// giving the line to show for it if there is one
+    pub fn receive(&mut self, message: &Message, replies: &mut Vec<Message>) -> Option<String> {
+        let nick = message.source_nick().unwrap_or("");
+        let is_us = |nick: &str| nick.eq_ignore_ascii_case(&self.nick);
+        let reason = |index: usize| match message.param(index) {
+            "" => String::new(),
+            reason => format!(" ({})", reason),
+        };
+        let line = match message.command.as_str() {
+            "PING" => {
+                replies.push(Message { prefix: None, command: "PONG".to_string(), params: message.params.clone() });
+                return None;
+            },
+            // The answer to our keepalive
+            "PONG" => return None,
+            // The welcome, which says the nick we ended up with
+            "001" => {
+                self.registered = true;
+                self.nick = message.param(0).to_string();
+                self.quitting = self.is_quitting();
+                replies.append(&mut self.pending);
+                message.params.last().cloned().unwrap_or_default()
+            },
+            // Nick already in use, which before the welcome means trying another to get in at all
+            "433" if !self.registered => {
+                let taken = std::mem::replace(&mut self.nick, format!("{}_", message.param(1)));
+                replies.push(Message::new("NICK", &[&self.nick]));
+                format!("-!- {} is already in use, trying {}", taken, self.nick)
+            },
+            "JOIN" => format!("-!- {} has joined {}", nick, message.param(0)),
+            "PART" => format!("-!- {} has left {}{}", nick, message.param(0), reason(1)),
+            "QUIT" => format!("-!- {} has quit{}", nick, reason(0)),
+            "KICK" => {
+                if is_us(message.param(1)) && self.channel.as_ref().is_some_and(|c| c.eq_ignore_ascii_case(message.param(0))) {
+                    self.channel = None;
+                }
+                format!("-!- {} was kicked from {} by {}{}", message.param(1), message.param(0), nick, reason(2))
+            },
+            "NICK" => {
+                let line = format!("-!- {} is now known as {}", nick, message.param(0));
+                if is_us(nick) {
+                    self.nick = message.param(0).to_string();
+                }
+                line
+            },
+            "PRIVMSG" if is_channel(message.param(0)) => format!("{} <{}> {}", message.param(0), nick, message.param(1)),
+            "PRIVMSG" => format!("*{}* {}", nick, message.param(1)),
+            "NOTICE" => format!("-{}- {}", nick, message.param(1)),
+            "ERROR" => format!("-!- error: {}", message.param(0)),
+            // Other replies are addressed to us, which goes without saying
+            command if command.bytes().all(|b| b.is_ascii_digit()) => message.params[1.min(message.params.len())..].join(" "),
+            _ => message.to_string(),
+        };
+        Some(line)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn receive(session: &mut Session, line: &str) -> (Option<String>, Vec<String>) {
+        let mut replies = Vec::new();
+        let shown = session.receive(&Message::parse(line).unwrap(), &mut replies);
+        (shown, replies.iter().map(|reply| reply.to_string()).collect())
+    }
+
+    #[test]
+    fn check_registration() {
+        let mut session = Session::new("alice");
+        let register: Vec<String> = session.register("alice", "Alice A").iter().map(|m| m.to_string()).collect();
+        assert_eq!(register, ["NICK alice", "USER alice 0 * :Alice A"]);
+
+        // Everything typed waits for the welcome
+        assert_eq!(session.input("/join #cc"), Ok(Vec::new()));
+        assert_eq!(session.input("hello all"), Ok(Vec::new()));
+        assert_eq!(session.input("/quit"), Ok(Vec::new()));
+        assert!(session.is_quitting() && !session.has_quit());
+
+        assert_eq!(
+            receive(&mut session, ":srv 433 * alice :Nickname is already in use"),
+            (Some("-!- alice is already in use, trying alice_".to_string()), vec!["NICK alice_".to_string()]),
+        );
+        assert_eq!(
+            receive(&mut session, ":srv 001 alice_ :Welcome to the network, alice_"),
+            (Some("Welcome to the network, alice_".to_string()), vec!["JOIN #cc".to_string(), "PRIVMSG #cc :hello all".to_string(), "QUIT".to_string()]),
+        );
+        assert_eq!(session.nick, "alice_");
+        assert!(session.has_quit());
+        assert_eq!(receive(&mut session, "PING :srv"), (None, vec!["PONG srv".to_string()]));
+    }
+
+    #[test]
+    fn check_receive() {
+        let mut session = Session::new("alice");
+        session.channel = Some("#cc".to_string());
+        let mut shown = |line| receive(&mut session, line).0.unwrap();
+        assert_eq!(shown(":bob!b@host PRIVMSG #cc :hi there"), "#cc <bob> hi there");
+        assert_eq!(shown(":bob!b@host PRIVMSG alice :psst"), "*bob* psst");
+        assert_eq!(shown(":srv NOTICE alice :*** Looking up your hostname"), "-srv- *** Looking up your hostname");
+        assert_eq!(shown(":bob!b@host JOIN #cc"), "-!- bob has joined #cc");
+        assert_eq!(shown(":bob!b@host PART #cc"), "-!- bob has left #cc");
+        assert_eq!(shown(":bob!b@host QUIT :Ping timeout"), "-!- bob has quit (Ping timeout)");
+        assert_eq!(shown(":srv 353 alice = #cc :alice bob"), "= #cc alice bob");
+        assert_eq!(shown(":alice!a@host NICK alicia"), "-!- alice is now known as alicia");
+        assert_eq!(shown(":op!o@host KICK #cc alicia :bye"), "-!- alicia was kicked from #cc by op (bye)");
+        assert_eq!(shown("ERROR :Closing link"), "-!- error: Closing link");
+        assert_eq!(session.nick, "alicia");
+        assert_eq!(session.channel, None);
+    }
+
+    #[test]
+    fn check_input() {
+        let mut session = Session::new("alice");
+        receive(&mut session, ":srv 001 alice :Welcome");
+        let err = |session: &mut Session, line| session.input(line).unwrap_err().to_string();
+        assert_eq!(err(&mut session, "hello"), "not in a channel, /join one first");
+        assert_eq!(err(&mut session, "/join"), "usage: /join CHANNEL");
+        assert_eq!(err(&mut session, "/msg bob"), "usage: /msg NICK TEXT");
+        assert_eq!(err(&mut session, "/dance"), "unknown command `/dance`");
+
+        let sent = |session: &mut Session, line| -> Vec<String> { session.input(line).unwrap().iter().map(|m| m.to_string()).collect() };
+        assert_eq!(sent(&mut session, "/JOIN #a,#b"), ["JOIN #a,#b"]);
+        assert_eq!(sent(&mut session, "//shrug"), ["PRIVMSG #a /shrug"]);
+        assert_eq!(sent(&mut session, ""), Vec::<String>::new());
+        assert_eq!(sent(&mut session, "/part"), ["PART #a"]);
+        assert_eq!(session.channel, None);
+        assert_eq!(sent(&mut session, "/quote MODE alice +i"), ["MODE alice +i"]);
+        assert!(!session.is_quitting());
+        assert_eq!(sent(&mut session, "/quit see you"), ["QUIT :see you"]);
+        assert!(session.is_quitting() && session.has_quit());
+    }
+}
@@ -0,0 +1,173 @@
+// IRC messages.
+// Each message is one line ending in CRLF: an optional prefix naming the sender, a command or
+// three digit reply number, and up to 15 parameters separated by spaces. The last parameter
+// can take a `:` to let it have spaces of its own, e.g. a message to a channel is:
+//
+//   :alice!alice@example.com PRIVMSG #cc :hello there
+//
+// Reference:  https://datatracker.ietf.org/doc/html/rfc2812#section-2.3
+use std::fmt;
+
+/// Longest message allowed, counting the CRLF
+pub const MAX_LINE_LENGTH: usize = 512;
+
+const MAX_PARAMS: usize = 15;
+
+#[derive(Debug, PartialEq)]
+pub struct MessageError(String);
+
+impl fmt::Display for MessageError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for MessageError {}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Message {
+    /// Who sent it, `nick!user@host` for a user or the name of a server
+    pub prefix: Option<String>,
+    pub command: String,
+    pub params: Vec<String>,
+}
+
+impl Message {
+    pub fn new(command: &str, params: &[&str]) -> Message {
+        Message {
+            prefix: None,
+            command: command.to_string(),
+            params: params.iter().map(|param| param.to_string()).collect(),
+        }
+    }
+
+    /// Parse a line, with or without its CRLF. Any IRCv3 tags in front are skipped.
+    pub fn parse(line: &str) -> Result<Message, MessageError> {
+        let mut rest = line.trim_end_matches(['\r', '\n']);
+        if rest.starts_with('@') {
+            rest = rest.split_once(' ').map_or("", |(_, rest)| rest);
+        }
+        let prefix = match rest.strip_prefix(':') {
+            Some(prefixed) => {
+                let (prefix, after) = prefixed.split_once(' ').unwrap_or((prefixed, ""));
+                rest = after;
+                Some(prefix.to_string())
+            },
+            None => None,
+        };
+
+        rest = rest.trim_start_matches(' ');
+        let (command, mut rest) = rest.split_once(' ').unwrap_or((rest, ""));
+        if command.is_empty() || !command.bytes().all(|b| b.is_ascii_alphanumeric()) {
+            return Err(MessageError(format!("invalid command `{}`", command)));
+        }
+        let mut params = Vec::new();
+        loop {
+            rest = rest.trim_start_matches(' ');
+            if rest.is_empty() {
+                break;
+            }
+            // The 15th parameter takes the rest of the line, even without a `:`
+            if let Some(trailing) = rest.strip_prefix(':').or((params.len() == MAX_PARAMS - 1).then_some(rest)) {
+                params.push(trailing.to_string());
+                break;
+            }
+            let (param, after) = rest.split_once(' ').unwrap_or((rest, ""));
+            params.push(param.to_string());
+            rest = after;
+        }
+        Ok(Message { prefix, command: command.to_ascii_uppercase(), params })
+    }
+
+    /// The nick of the user who sent it, the part of the prefix before any `!user@host`
+    pub fn source_nick(&self) -> Option<&str> {
+        let prefix = self.prefix.as_deref()?;
+        Some(prefix.split(['!', '@']).next().unwrap_or(prefix))
+    }
+
+    /// The parameter at `index`, or an empty string when there are fewer
+    pub fn param(&self, index: usize) -> &str {
+        self.params.get(index).map_or("", String::as_str)
+    }
+
+    /// The line to send, with its CRLF, checking first it's one the server can take
+    pub fn encode(&self) -> Result<String, MessageError> {
+        let line = format!("{}\r\n", self);
+        if line.len() > MAX_LINE_LENGTH {
+            return Err(MessageError(format!("message is {} bytes, more than the {} allowed", line.len(), MAX_LINE_LENGTH)));
+        }
+        if line[..line.len() - 2].contains(['\r', '\n', '\0']) {
+            return Err(MessageError("message contains a line break or NUL".to_string()));
+        }
+        Ok(line)
+    }
+}
+
+impl fmt::Display for Message {
+    /// The wire format, without the CRLF
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(prefix) = &self.prefix {
+            write!(f, ":{} ", prefix)?;
+        }
+        f.write_str(&self.command)?;
+        for (i, param) in self.params.iter().enumerate() {
+            let last = i == self.params.len() - 1;
+            match last && (param.is_empty() || param.contains(' ') || param.starts_with(':')) {
+                true => write!(f, " :{}", param)?,
+                false => write!(f, " {}", param)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_parse() {
+        let message = Message::parse(":alice!alice@example.com PRIVMSG #cc :hello there\r\n").unwrap();
+        assert_eq!(message.prefix.as_deref(), Some("alice!alice@example.com"));
+        assert_eq!(message.source_nick(), Some("alice"));
+        assert_eq!(message.command, "PRIVMSG");
+        assert_eq!(message.params, ["#cc", "hello there"]);
+
+        let message = Message::parse("ping irc.example.com").unwrap();
+        assert_eq!((message.prefix, message.command.as_str()), (None, "PING"));
+        assert_eq!(message.params, ["irc.example.com"]);
+
+        let message = Message::parse("@time=2023-01-01T00:00:00Z :srv 001 alice :Welcome").unwrap();
+        assert_eq!(message.source_nick(), Some("srv"));
+        assert_eq!(message.params, ["alice", "Welcome"]);
+        assert_eq!(message.param(2), "");
+
+        assert_eq!(Message::parse(":srv  353 a = #cc :").unwrap().params, ["a", "=", "#cc", ""]);
+        assert_eq!(Message::parse("A 1 2 3 4 5 6 7 8 9 10 11 12 13 14 15 16").unwrap().param(14), "15 16");
+    }
+
+    #[test]
+    fn check_parse_invalid() {
+        assert_eq!(Message::parse("").unwrap_err().to_string(), "invalid command ``");
+        assert_eq!(Message::parse(":srv").unwrap_err().to_string(), "invalid command ``");
+        assert_eq!(Message::parse("PRIV-MSG x").unwrap_err().to_string(), "invalid command `PRIV-MSG`");
+    }
+
+    #[test]
+    fn check_encode() {
+        assert_eq!(Message::new("NICK", &["alice"]).encode().unwrap(), "NICK alice\r\n");
+        assert_eq!(Message::new("USER", &["alice", "0", "*", "Alice A"]).encode().unwrap(), "USER alice 0 * :Alice A\r\n");
+        assert_eq!(Message::new("PRIVMSG", &["#cc", ":)"]).encode().unwrap(), "PRIVMSG #cc ::)\r\n");
+        assert_eq!(Message::new("QUIT", &[""]).encode().unwrap(), "QUIT :\r\n");
+
+        let message = Message::parse(":bob PRIVMSG alice :hi").unwrap();
+        assert_eq!(Message::parse(&message.encode().unwrap()).unwrap(), message);
+
+        let long = "x".repeat(MAX_LINE_LENGTH);
+        assert_eq!(
+            Message::new("PRIVMSG", &["#cc", &long]).encode().unwrap_err().to_string(),
+            "message is 526 bytes, more than the 512 allowed",
+        );
+        assert!(Message::new("PRIVMSG", &["#cc", "a\nQUIT"]).encode().is_err());
+    }
+}
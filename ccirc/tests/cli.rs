@@ -0,0 +1,107 @@
+use assert_cmd::Command;
+use predicates::prelude::*;
+use predicates::str::contains;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::thread::{self, JoinHandle};
+
+fn ccirc() -> Command {
+    Command::cargo_bin("ccirc").unwrap()
+}
+
+fn read_line(reader: &mut BufReader<&TcpStream>) -> String {
+    let mut line = String::new();
+    reader.read_line(&mut line).unwrap();
+    line
+}
+
+/// Serve one client: ping it and welcome it after it registers, and then pass on whatever is in
+/// `chatter`, collecting what it sends until it quits. Returns the port to connect to and a handle
+/// giving the lines received.
+fn serve(chatter: &'static str) -> (u16, JoinHandle<Vec<String>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let handle = thread::spawn(move || {
+        let (stream, _) = listener.accept().unwrap();
+        let mut reader = BufReader::new(&stream);
+        let mut received = vec![read_line(&mut reader), read_line(&mut reader)];
+        (&stream).write_all(b"PING :irc.test\r\n:irc.test 001 alice :Welcome to the test network, alice\r\n").unwrap();
+        (&stream).write_all(chatter.as_bytes()).unwrap();
+        loop {
+            let line = read_line(&mut reader);
+            if line.is_empty() {
+                break;
+            }
+            received.push(line.clone());
+            if line.starts_with("QUIT") {
+                (&stream).write_all(b"ERROR :Closing link (alice)\r\n").unwrap();
+                break;
+            }
+        }
+        received
+    });
+    (port, handle)
+}
+
+#[test]
+fn run_irc_session() {
+    let (port, server) = serve(":alice!alice@localhost JOIN #cc\r\n:bob!bob@localhost PRIVMSG #cc :hey alice\r\n");
+    ccirc()
+        .args(["-n", "alice", "-r", "Alice A", "-j", "#cc", "-p", &port.to_string(), "127.0.0.1"])
+        .write_stdin("hello\n/msg bob hi\n/quit bye\n")
+        .assert()
+        .success()
+        .stdout(
+            contains("Welcome to the test network, alice\n")
+                .and(contains("-!- alice has joined #cc\n"))
+                .and(contains("#cc <bob> hey alice\n"))
+                .and(contains("-!- error: Closing link (alice)\n")),
+        );
+
+    let received = server.join().unwrap();
+    assert_eq!(received[..2], ["NICK alice\r\n", "USER alice 0 * :Alice A\r\n"]);
+    // Everything typed waits for the welcome, and then goes in order after the channel to send it to
+    assert_eq!(received[2..], ["PONG irc.test\r\n", "JOIN #cc\r\n", "PRIVMSG #cc hello\r\n", "PRIVMSG bob hi\r\n", "QUIT bye\r\n"]);
+}
+
+#[test]
+fn run_irc_quit_at_end_of_input() {
+    let (port, server) = serve("");
+    ccirc()
+        .args(["-p", &port.to_string(), "127.0.0.1"])
+        .write_stdin("hello\n")
+        .assert()
+        .success()
+        .stderr("-!- not in a channel, /join one first\n");
+
+    let received = server.join().unwrap();
+    assert_eq!(received[..2], ["NICK ccirc\r\n", "USER ccirc 0 * ccirc\r\n"]);
+    assert_eq!(received.last().unwrap(), "QUIT\r\n");
+}
+
+#[test]
+fn run_irc_server_closes() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let server = thread::spawn(move || {
+        let (stream, _) = listener.accept().unwrap();
+        let mut reader = BufReader::new(&stream);
+        read_line(&mut reader);
+        read_line(&mut reader);
+        (&stream).write_all(b"ERROR :Server going down\r\n").unwrap();
+    });
+    ccirc()
+        .args(["-p", &port.to_string(), "127.0.0.1"])
+        .write_stdin("")
+        .assert()
+        .failure()
+        .stdout("-!- error: Server going down\n")
+        .stderr(contains("the server closed the connection"));
+    server.join().unwrap();
+
+    ccirc()
+        .args(["-p", &port.to_string(), "127.0.0.1"])
+        .assert()
+        .failure()
+        .stderr(contains(format!("could not connect to 127.0.0.1 port {}", port)));
+}
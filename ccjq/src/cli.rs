@@ -0,0 +1,220 @@
+use crate::Printer;
+use cc2jsonparser::{read_concatenated, read_ndjson, Filter, JsonError, JsonValue, ParseOptions};
+use cc_cli::BROKEN_PIPE_EXIT_CODE;
+use cc_diagnostics::{ColorChoice, Diagnostic};
+use cc_io::Source;
+use clap::Parser;
+use std::ffi::OsString;
+use std::io::{self, stderr, stdout, BufRead, BufWriter, ErrorKind, Write};
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+/// The exit code for input that can't be read or isn't JSON, as jq has it
+const INPUT_ERROR: u8 = 2;
+/// The exit code for a filter that doesn't compile
+const FILTER_ERROR: u8 = 3;
+/// The exit code for a filter that can't be run on a value it's given
+const RUN_ERROR: u8 = 5;
+
+/// jq - run a filter on JSON and print the values it gives
+#[derive(Parser)]
+struct Cli {
+    /// The filter, e.g. `.store.books[] | .title`, with `.` printing each document as it is
+    #[arg(default_value = ".")]
+    filter: String,
+
+    /// The files to read, each with any number of documents one after another, or stdin if there
+    /// are none
+    files: Vec<PathBuf>,
+
+    /// Print each value on one line
+    #[arg(short, long)]
+    compact_output: bool,
+
+    /// Print strings as their text, without quotes or escapes
+    #[arg(short, long)]
+    raw_output: bool,
+
+    /// Indent by a tab rather than spaces
+    #[arg(long, conflicts_with_all = ["compact_output", "indent"])]
+    tab: bool,
+
+    /// Indent by this many spaces
+    #[arg(long, value_name = "N", default_value_t = 2, value_parser = clap::value_parser!(u8).range(0..=7))]
+    indent: u8,
+
+    /// Read each line as a document of its own, filtering each as it's read
+    #[arg(long)]
+    ndjson: bool,
+
+    /// Run the filter once, on an array of every document read
+    #[arg(short, long)]
+    slurp: bool,
+
+    /// Run the filter once, on null, without reading anything
+    #[arg(short, long, conflicts_with_all = ["ndjson", "slurp"])]
+    null_input: bool,
+
+    /// When to color the output: auto, always, or never
+    #[arg(long, value_name = "WHEN", default_value = "auto")]
+    color: ColorChoice,
+}
+
+impl Cli {
+    fn printer(&self) -> Printer {
+        let indent = match (self.compact_output, self.tab, self.indent) {
+            (true, _, _) | (_, _, 0) => None,
+            (_, true, _) => Some("\t".to_string()),
+            (_, _, spaces) => Some(" ".repeat(spaces as usize)),
+        };
+        Printer { indent, raw: self.raw_output, color: self.color.should_color(&stdout()) }
+    }
+}
+
+/// Where the documents read go, and how the run has gone
+struct Run<'a, W> {
+    filter: &'a Filter,
+    /// The filter as it's written, to name it in errors
+    filter_text: &'a str,
+    printer: Printer,
+    out: W,
+    /// Every document read so far, with --slurp
+    slurped: Option<Vec<JsonValue>>,
+    color: ColorChoice,
+    /// The exit code of the worst failure so far
+    code: u8,
+}
+
+impl<W: Write> Run<'_, W> {
+    /// Filter a document and print what it gives, or keep it for later with --slurp
+    fn document(&mut self, value: JsonValue) -> io::Result<()> {
+        if let Some(slurped) = &mut self.slurped {
+            slurped.push(value);
+            return Ok(());
+        }
+        self.filter_and_print(&value)
+    }
+
+    fn filter_and_print(&mut self, value: &JsonValue) -> io::Result<()> {
+        match self.filter.apply(value) {
+            Ok(values) => values.into_iter().try_for_each(|value| self.printer.print(&mut self.out, value)),
+            Err(e) => {
+                self.fail(RUN_ERROR, &Diagnostic::error(e.message), self.filter_text, "");
+                Ok(())
+            },
+        }
+    }
+
+    /// Read the documents in every source in turn, then filter those slurped up if there are any
+    fn read_all(&mut self, sources: &[Source], ndjson: bool) -> io::Result<()> {
+        for source in sources {
+            self.read(source, ndjson)?;
+        }
+        match self.slurped.take() {
+            Some(slurped) => self.filter_and_print(&JsonValue::Array(slurped)),
+            None => Ok(()),
+        }
+    }
+
+    /// Read every document in the source, reporting those that aren't JSON
+    fn read(&mut self, source: &Source, ndjson: bool) -> io::Result<()> {
+        let input = match cc_io::open(source) {
+            Ok(input) => input,
+            Err(e) => {
+                self.fail(INPUT_ERROR, &Diagnostic::error(e.to_string()), &source.label(), "");
+                return Ok(());
+            },
+        };
+        let name = input.label();
+        let options = ParseOptions::default();
+        let documents: Box<dyn Iterator<Item = _>> = match ndjson {
+            true => Box::new(read_ndjson(input.reader, &options)),
+            false => Box::new(read_concatenated(input.reader, &options)),
+        };
+        for document in documents {
+            match document {
+                Ok((value, _)) => self.document(value)?,
+                Err(e) => self.fail_to_parse(&e, source, &name),
+            }
+        }
+        Ok(())
+    }
+
+    /// Report input that isn't JSON. Only the document being read is kept, so a file's read again
+    /// to quote where it goes wrong, and stdin's only pointed at.
+    fn fail_to_parse(&mut self, e: &JsonError, source: &Source, name: &str) {
+        let diagnostic = Diagnostic::error(e.message());
+        let text = match e.span() {
+            Some(span) if !source.is_stdin() => reread(source, span.start),
+            _ => String::new(),
+        };
+        match (e.span(), e.position()) {
+            (Some(span), _) if span.start <= text.len() && !text.is_empty() => {
+                self.fail(INPUT_ERROR, &diagnostic.with_span(span), name, &text)
+            },
+            (_, Some(position)) => self.fail(INPUT_ERROR, &diagnostic, &format!("{}:{}", name, position), ""),
+            _ => self.fail(INPUT_ERROR, &diagnostic, name, ""),
+        }
+    }
+
+    /// Print the diagnostic to stderr and keep the worse exit code
+    fn fail(&mut self, code: u8, diagnostic: &Diagnostic, name: &str, source: &str) {
+        eprint!("{}", diagnostic.render(name, source, self.color.should_color(&stderr())));
+        self.code = self.code.max(code);
+    }
+}
+
+/// The source's lines up to and including the one with `offset` in it, or nothing if it can't be
+/// read again
+fn reread(source: &Source, offset: usize) -> String {
+    let Ok(mut input) = cc_io::open(source) else {
+        return String::new();
+    };
+    let mut text = Vec::new();
+    while text.len() <= offset {
+        match input.reader.read_until(b'\n', &mut text) {
+            Ok(0) | Err(_) => break,
+            Ok(_) => {},
+        }
+    }
+    // Invalid UTF-8 is only ever at or after where the error is
+    String::from_utf8_lossy(&text).into_owned()
+}
+
+/// Run ccjq with the given command line, `args[0]` being the program name
+pub fn main<I, T>(args: I) -> ExitCode
+where
+    I: IntoIterator<Item = T>,
+    T: Into<OsString> + Clone,
+{
+    let args: Cli = cc_cli::parse_from(args, env!("CARGO_PKG_VERSION"));
+    let color = args.color.should_color(&stderr());
+    let filter = match Filter::new(&args.filter) {
+        Ok(filter) => filter,
+        Err(e) => {
+            eprint!("{}", Diagnostic::error(format!("invalid filter: {}", e.message)).render(&args.filter, "", color));
+            return ExitCode::from(FILTER_ERROR);
+        },
+    };
+    let mut sources: Vec<Source> = args.files.iter().map(|file| Source::from(file.clone())).collect();
+    if sources.is_empty() {
+        sources.push(Source::Stdin);
+    }
+
+    let out = BufWriter::new(stdout().lock());
+    let slurped = args.slurp.then(Vec::new);
+    let mut run = Run { filter: &filter, filter_text: &args.filter, printer: args.printer(), out, slurped, color: args.color, code: 0 };
+    let result = match args.null_input {
+        true => run.filter_and_print(&JsonValue::Null),
+        false => run.read_all(&sources, args.ndjson),
+    };
+
+    match result.and_then(|()| run.out.flush()) {
+        Err(e) if e.kind() == ErrorKind::BrokenPipe => ExitCode::from(BROKEN_PIPE_EXIT_CODE),
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            ExitCode::FAILURE
+        },
+        Ok(()) => ExitCode::from(run.code),
+    }
+}
@@ -0,0 +1,71 @@
+// Printing core of ccjq, a jq-like front end to cc2jsonparser, usable without the command line
+// interface.
+// A filter runs on each document read and every value it gives is printed on its own, indented
+// unless asked otherwise. A file holds any number of documents one after another, as jq reads
+// them, or with --ndjson one to each line, and each is read and filtered as it comes so a stream
+// of them is never all in memory at once. The filters are cc2jsonparser's: paths, `,`, and `|`.
+// Reference:  https://jqlang.github.io/jq/manual/
+use cc2jsonparser::{write_json, JsonValue, WriteOptions};
+use std::io::{self, Write};
+
+mod cli;
+
+pub use cli::main;
+
+/// How each value a filter gives is printed
+#[derive(Clone, Debug, PartialEq)]
+pub struct Printer {
+    /// What to indent each level of nesting by, or None to print each value on one line
+    pub indent: Option<String>,
+    /// Print strings as their text, without quotes or escapes
+    pub raw: bool,
+    pub color: bool,
+}
+
+impl Default for Printer {
+    /// Indented by two spaces, as jq prints
+    fn default() -> Printer {
+        Printer { indent: Some("  ".to_string()), raw: false, color: false }
+    }
+}
+
+impl Printer {
+    /// Print the value followed by a line break
+    pub fn print(&self, out: &mut impl Write, value: &JsonValue) -> io::Result<()> {
+        match value {
+            JsonValue::String(string) if self.raw => out.write_all(string.as_bytes())?,
+            _ => write_json(out, value, WriteOptions { indent: self.indent.as_deref(), color: self.color })?,
+        }
+        out.write_all(b"\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cc2jsonparser::parse_str;
+
+    fn print(printer: &Printer, input: &str) -> String {
+        let mut out = Vec::new();
+        printer.print(&mut out, &parse_str(input).unwrap()).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn check_print() {
+        let input = r#"{"a": [1, "x\ty"]}"#;
+        assert_eq!(print(&Printer::default(), input), "{\n  \"a\": [\n    1,\n    \"x\\ty\"\n  ]\n}\n");
+        let compact = Printer { indent: None, ..Printer::default() };
+        assert_eq!(print(&compact, input), "{\"a\":[1,\"x\\ty\"]}\n");
+        let tab = Printer { indent: Some("\t".to_string()), ..Printer::default() };
+        assert_eq!(print(&tab, "[true]"), "[\n\ttrue\n]\n");
+    }
+
+    #[test]
+    fn check_print_raw() {
+        let raw = Printer { raw: true, ..Printer::default() };
+        assert_eq!(print(&raw, r#""x\ty""#), "x\ty\n");
+        // Only strings are raw
+        assert_eq!(print(&raw, r#"["x"]"#), "[\n  \"x\"\n]\n");
+    }
+}
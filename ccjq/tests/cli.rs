@@ -0,0 +1,60 @@
+use assert_cmd::Command;
+use predicates::prelude::*;
+
+fn ccjq() -> Command {
+    let mut cmd = Command::cargo_bin("ccjq").unwrap();
+    cmd.current_dir(env!("CARGO_MANIFEST_DIR"));
+    cmd
+}
+
+#[test]
+fn run_identity_pretty_prints() {
+    ccjq().write_stdin(r#"{"a": [1, {}]}"#).assert().success().stdout("{\n  \"a\": [\n    1,\n    {}\n  ]\n}\n");
+    ccjq().args(["--tab", "."]).write_stdin("[1]").assert().success().stdout("[\n\t1\n]\n");
+    ccjq().args(["--indent", "0", "."]).write_stdin("[1, 2]").assert().success().stdout("[1,2]\n");
+}
+
+#[test]
+fn run_filter() {
+    ccjq().args(["-r", ".store.books[] | .title", "testinputs/store.json"]).assert().success()
+        .stdout("Sayings of the Century\nMoby Dick\n");
+    ccjq().args(["-c", ".store.books[0].price, .store.owner", "testinputs/store.json"]).assert().success().stdout("8.95\nnull\n");
+}
+
+#[test]
+fn run_concatenated_documents() {
+    ccjq().args(["-c", "."]).write_stdin("1 [2]{\"a\": 3}\n\"four\"").assert().success().stdout("1\n[2]\n{\"a\":3}\n\"four\"\n");
+    ccjq().args(["-s", "-c", "."]).write_stdin("1 2\n3").assert().success().stdout("[1,2,3]\n");
+}
+
+#[test]
+fn run_ndjson() {
+    ccjq().args(["--ndjson", "-r", ".message", "testinputs/log.ndjson"]).assert().success().stdout("started\ndisk full\n");
+    // A bad line is reported and the rest are still filtered
+    ccjq().args(["--ndjson", "-c", "."]).write_stdin("[1]\n[1 2]\n[3]\n").assert().failure().code(2)
+        .stdout("[1]\n[3]\n")
+        .stderr(predicate::str::contains("<stdin>:2:4"));
+}
+
+#[test]
+fn run_slurp_and_null_input() {
+    ccjq().args(["-s", "-c", ".", "testinputs/store.json", "-"]).write_stdin("7").assert().success()
+        .stdout(predicate::str::ends_with(",7]\n"));
+    ccjq().args(["--ndjson", "-s", "-r", ".[1].level", "testinputs/log.ndjson"]).assert().success().stdout("error\n");
+    ccjq().args(["-n", "."]).assert().success().stdout("null\n");
+}
+
+#[test]
+fn run_errors() {
+    // A file that isn't JSON is quoted where it goes wrong, after the documents before it
+    ccjq().args(["-c", ".", "testinputs/invalid.json"]).assert().failure().code(2)
+        .stdout("{\"a\":1}\n")
+        .stderr(predicate::str::contains(" --> testinputs/invalid.json:2:10\n  |\n2 | {\"b\": [1,}\n  |          ^"));
+    ccjq().write_stdin("{\"a\": [1,}").assert().failure().code(2)
+        .stderr(predicate::str::contains(" --> <stdin>:1:10\n"));
+    ccjq().args([".a b"]).write_stdin("{}").assert().failure().code(3)
+        .stderr(predicate::str::contains("invalid filter: unexpected `b` at column 4 of the filter"));
+    ccjq().args([".a"]).write_stdin("[1]").assert().failure().code(5);
+    ccjq().args([".", "missing.json"]).assert().failure().code(2)
+        .stderr(predicate::str::contains("could not read `missing.json`"));
+}
@@ -0,0 +1,330 @@
+// Lightweight memcached server
+// Speaks the memcached text protocol for set, add, replace, get, delete, and flush_all, with
+// expiry. The items share a memory limit, and storing one that doesn't fit evicts the least
+// recently used until it does, and as in memcached no value can be over a megabyte. Each
+// connection gets its own thread, and they share the cache behind a mutex.
+use std::collections::{BTreeMap, HashMap};
+use std::io::{self, BufReader, BufWriter, ErrorKind, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+mod cli;
+pub mod protocol;
+
+pub use cli::main;
+use protocol::{Command, Reply, StoreMode, MAX_VALUE_LENGTH};
+
+pub const DEFAULT_PORT: u16 = 11211;
+
+/// The memory limit memcached starts with, in megabytes
+pub const DEFAULT_MEMORY_LIMIT_MB: usize = 64;
+
+/// What each item costs on top of its key and value, roughly what memcached's item header takes
+const ITEM_OVERHEAD: usize = 48;
+
+/// Longest expiry time taken as seconds from now, rather than a Unix time
+const MAX_RELATIVE_EXPTIME: i64 = 60 * 60 * 24 * 30;
+
+#[derive(Debug)]
+struct Item {
+    flags: u32,
+    value: Vec<u8>,
+    expires: Option<Instant>,
+    /// When it was last stored or fetched, as a position in `Cache::recency`
+    used: u64,
+}
+
+impl Item {
+    fn is_live(&self, now: Instant) -> bool {
+        self.expires.is_none_or(|expires| expires > now)
+    }
+}
+
+fn size(key: &[u8], value: &[u8]) -> usize {
+    key.len() + value.len() + ITEM_OVERHEAD
+}
+
+/// The items, kept within a number of bytes
+#[derive(Debug)]
+pub struct Cache {
+    items: HashMap<Vec<u8>, Item>,
+    /// The keys from least to most recently used
+    recency: BTreeMap<u64, Vec<u8>>,
+    next_use: u64,
+    size: usize,
+    limit: usize,
+}
+
+impl Cache {
+    pub fn new(limit: usize) -> Cache {
+        Cache { items: HashMap::new(), recency: BTreeMap::new(), next_use: 0, size: 0, limit }
+    }
+
+    /// How many bytes the items take up
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// How many bytes the items can take up
+    pub fn limit(&self) -> usize {
+        self.limit
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// Mark an item the most recently used
+    fn touch(&mut self, key: &[u8]) {
+        if let Some(item) = self.items.get_mut(key) {
+            let key = self.recency.remove(&item.used).unwrap();
+            item.used = self.next_use;
+            self.recency.insert(self.next_use, key);
+            self.next_use += 1;
+        }
+    }
+
+    fn remove(&mut self, key: &[u8]) -> Option<Item> {
+        let item = self.items.remove(key)?;
+        self.recency.remove(&item.used);
+        self.size -= size(key, &item.value);
+        Some(item)
+    }
+
+    /// The flags and value of an item that hasn't expired
+    pub fn get(&mut self, key: &[u8], now: Instant) -> Option<(u32, &[u8])> {
+        if !self.items.get(key)?.is_live(now) {
+            self.remove(key);
+            return None;
+        }
+        self.touch(key);
+        self.items.get(key).map(|item| (item.flags, &item.value[..]))
+    }
+
+    pub fn contains(&mut self, key: &[u8], now: Instant) -> bool {
+        self.get(key, now).is_some()
+    }
+
+    /// Store an item, evicting the least recently used ones until it fits. Returns false if it
+    /// can never fit.
+    pub fn insert(&mut self, key: Vec<u8>, flags: u32, value: Vec<u8>, expires: Option<Instant>) -> bool {
+        let item_size = size(&key, &value);
+        if item_size > self.limit {
+            return false;
+        }
+        self.remove(&key);
+        while self.size + item_size > self.limit {
+            let (_, oldest) = self.recency.pop_first().unwrap();
+            self.remove(&oldest);
+        }
+        self.recency.insert(self.next_use, key.clone());
+        self.items.insert(key, Item { flags, value, expires, used: self.next_use });
+        self.next_use += 1;
+        self.size += item_size;
+        true
+    }
+
+    /// Remove an item, returning whether it was there
+    pub fn delete(&mut self, key: &[u8], now: Instant) -> bool {
+        self.remove(key).is_some_and(|item| item.is_live(now))
+    }
+
+    pub fn clear(&mut self) {
+        self.items.clear();
+        self.recency.clear();
+        self.size = 0;
+    }
+}
+
+/// When an item stored with `exptime` expires, None for never
+fn expiry(exptime: i64, now: Instant) -> Option<Instant> {
+    match exptime {
+        0 => None,
+        ..=-1 => Some(now),
+        1..=MAX_RELATIVE_EXPTIME => Some(now + Duration::from_secs(exptime as u64)),
+        _ => {
+            let unix_now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs() as i64;
+            Some(now + Duration::from_secs((exptime - unix_now).max(0) as u64))
+        },
+    }
+}
+
+/// Run one command and return the reply
+pub fn execute(cache: &Mutex<Cache>, command: Command) -> Reply {
+    let now = Instant::now();
+    let mut cache = cache.lock().unwrap();
+
+    match command {
+        Command::Store { mode, key, flags, exptime, value, .. } => {
+            let exists = cache.contains(&key, now);
+            match mode {
+                StoreMode::Add if exists => Reply::NotStored,
+                StoreMode::Replace if !exists => Reply::NotStored,
+                _ if cache.insert(key, flags, value, expiry(exptime, now)) => Reply::Stored,
+                _ => Reply::ServerError("object too large for cache".to_string()),
+            }
+        },
+        Command::Get { keys } => Reply::Values(
+            keys.into_iter()
+                .filter_map(|key| {
+                    let (flags, value) = cache.get(&key, now)?;
+                    let value = value.to_vec();
+                    Some((key, flags, value))
+                })
+                .collect(),
+        ),
+        Command::Delete { key, .. } => match cache.delete(&key, now) {
+            true => Reply::Deleted,
+            false => Reply::NotFound,
+        },
+        Command::FlushAll { .. } => {
+            cache.clear();
+            Reply::Ok
+        },
+        Command::Version => Reply::Version(env!("CARGO_PKG_VERSION").to_string()),
+        // The connection handles this itself
+        Command::Quit => Reply::Ok,
+    }
+}
+
+/// Answer commands from one client until it disconnects
+pub fn handle_connection(stream: TcpStream, cache: &Mutex<Cache>) -> io::Result<()> {
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut writer = BufWriter::new(stream);
+    // No value bigger than the whole cache could be stored, so there's no reading one in
+    let max_value = cache.lock().unwrap().limit().min(MAX_VALUE_LENGTH);
+
+    loop {
+        let reply = match protocol::read_command(&mut reader, max_value) {
+            Ok(None) | Ok(Some(Ok(Command::Quit))) => return writer.flush(),
+            Ok(Some(Ok(command))) if command.is_noreply() => {
+                execute(cache, command);
+                None
+            },
+            Ok(Some(Ok(command))) => Some(execute(cache, command)),
+            Ok(Some(Err(reply))) => Some(reply),
+            // Where the next command starts is anyone's guess, so explain and hang up
+            Err(e) if e.kind() == ErrorKind::InvalidData => {
+                Reply::ClientError(e.to_string()).write(&mut writer)?;
+                return writer.flush();
+            },
+            // The client went away partway through a command
+            Err(e) if e.kind() == ErrorKind::UnexpectedEof => return Ok(()),
+            Err(e) => return Err(e),
+        };
+        if let Some(reply) = reply {
+            reply.write(&mut writer)?;
+        }
+
+        // Clients may pipeline several commands, so only flush once the ones sent so far are done
+        if reader.buffer().is_empty() {
+            writer.flush()?;
+        }
+    }
+}
+
+/// Accept connections forever, serving each on its own thread
+pub fn serve(listener: TcpListener, cache: Arc<Mutex<Cache>>) -> io::Result<()> {
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            // The client gave up before we got to it
+            Err(e) if e.kind() == ErrorKind::ConnectionAborted => continue,
+            Err(e) => return Err(e),
+        };
+        let cache = Arc::clone(&cache);
+        thread::spawn(move || {
+            if let Err(e) = handle_connection(stream, &cache) {
+                if e.kind() != ErrorKind::ConnectionReset && e.kind() != ErrorKind::BrokenPipe {
+                    eprintln!("Error: {}", e);
+                }
+            }
+        });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn store(cache: &Mutex<Cache>, mode: StoreMode, key: &str, value: &str, exptime: i64) -> Reply {
+        let (key, value) = (key.as_bytes().to_vec(), value.as_bytes().to_vec());
+        execute(cache, Command::Store { mode, key, flags: 7, exptime, value, noreply: false })
+    }
+
+    fn get(cache: &Mutex<Cache>, key: &str) -> Option<String> {
+        match execute(cache, Command::Get { keys: vec![key.as_bytes().to_vec()] }) {
+            Reply::Values(values) => values.first().map(|(_, _, value)| String::from_utf8(value.clone()).unwrap()),
+            reply => panic!("{:?}", reply),
+        }
+    }
+
+    #[test]
+    fn check_store_get_delete() {
+        let cache = Mutex::new(Cache::new(1024));
+        assert_eq!(store(&cache, StoreMode::Replace, "k", "v", 0), Reply::NotStored);
+        assert_eq!(store(&cache, StoreMode::Add, "k", "v", 0), Reply::Stored);
+        assert_eq!(store(&cache, StoreMode::Add, "k", "w", 0), Reply::NotStored);
+        assert_eq!(store(&cache, StoreMode::Replace, "k", "w", 0), Reply::Stored);
+        assert_eq!(store(&cache, StoreMode::Set, "other", "x", 0), Reply::Stored);
+
+        let reply = execute(&cache, Command::Get { keys: vec![b"k".to_vec(), b"nope".to_vec(), b"other".to_vec()] });
+        assert_eq!(reply, Reply::Values(vec![(b"k".to_vec(), 7, b"w".to_vec()), (b"other".to_vec(), 7, b"x".to_vec())]));
+        assert_eq!(execute(&cache, Command::Delete { key: b"k".to_vec(), noreply: false }), Reply::Deleted);
+        assert_eq!(execute(&cache, Command::Delete { key: b"k".to_vec(), noreply: false }), Reply::NotFound);
+        assert_eq!(execute(&cache, Command::FlushAll { noreply: false }), Reply::Ok);
+        assert_eq!(get(&cache, "other"), None);
+        assert_eq!(cache.lock().unwrap().size(), 0);
+    }
+
+    #[test]
+    fn check_expiry() {
+        let cache = Mutex::new(Cache::new(1024));
+        let unix_now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs() as i64;
+        store(&cache, StoreMode::Set, "gone", "v", -1);
+        store(&cache, StoreMode::Set, "past", "v", unix_now - 10);
+        store(&cache, StoreMode::Set, "hour", "v", 3600);
+        store(&cache, StoreMode::Set, "tomorrow", "v", unix_now + 86400);
+        assert_eq!(get(&cache, "gone"), None);
+        assert_eq!(get(&cache, "past"), None);
+        assert_eq!(get(&cache, "hour").as_deref(), Some("v"));
+        assert_eq!(get(&cache, "tomorrow").as_deref(), Some("v"));
+        // An expired item can be added again
+        assert_eq!(store(&cache, StoreMode::Add, "gone", "back", 0), Reply::Stored);
+
+        let now = Instant::now();
+        assert_eq!(expiry(60, now), Some(now + Duration::from_secs(60)));
+        assert_eq!(expiry(0, now), None);
+    }
+
+    #[test]
+    fn check_lru_eviction() {
+        // Room for three items with one byte keys and ten byte values
+        let cache = Mutex::new(Cache::new(3 * size(b"k", b"0123456789")));
+        for key in ["a", "b", "c"] {
+            store(&cache, StoreMode::Set, key, "0123456789", 0);
+        }
+        // Fetching a makes b the least recently used
+        assert!(get(&cache, "a").is_some());
+        store(&cache, StoreMode::Set, "d", "0123456789", 0);
+        assert_eq!(get(&cache, "b"), None);
+        assert!(get(&cache, "a").is_some() && get(&cache, "c").is_some() && get(&cache, "d").is_some());
+
+        // A bigger item pushes out as many as it takes, here a and then c
+        store(&cache, StoreMode::Set, "e", &"x".repeat(20), 0);
+        assert_eq!(get(&cache, "a"), None);
+        assert_eq!(get(&cache, "c"), None);
+        assert!(cache.lock().unwrap().size() <= 3 * size(b"k", b"0123456789"));
+        assert_eq!(cache.lock().unwrap().len(), 2);
+
+        let reply = store(&cache, StoreMode::Set, "huge", &"x".repeat(200), 0);
+        assert_eq!(reply, Reply::ServerError("object too large for cache".to_string()));
+    }
+}
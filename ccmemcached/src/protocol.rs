@@ -0,0 +1,280 @@
+// The memcached text protocol.
+// Each command is a line of words ending in CRLF, and the storage commands follow theirs with a
+// block of data of the length they give, e.g. storing and then fetching a key:
+//
+//   set greeting 0 60 5\r\nhello\r\n     ->  STORED\r\n
+//   get greeting\r\n                     ->  VALUE greeting 0 5\r\nhello\r\nEND\r\n
+//
+// Reference:  https://github.com/memcached/memcached/blob/master/doc/protocol.txt
+use std::io::{self, BufRead, ErrorKind, Read, Write};
+
+/// Longest key allowed, the same limit as memcached
+pub const MAX_KEY_LENGTH: usize = 250;
+
+/// Longest command line accepted, which is plenty for a storage command or a get of a few keys
+pub const MAX_LINE_LENGTH: usize = 2048;
+
+/// Longest value stored, memcached's default item size limit
+pub const MAX_VALUE_LENGTH: usize = 1024 * 1024;
+
+/// How a storage command treats a key that's already there
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum StoreMode {
+    /// Store it either way
+    Set,
+    /// Only store it if the key isn't there
+    Add,
+    /// Only store it if the key is there
+    Replace,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Command {
+    Store {
+        mode: StoreMode,
+        key: Vec<u8>,
+        /// Opaque to the server, handed back with the value
+        flags: u32,
+        /// 0 for never, seconds from now up to 30 days, a Unix time beyond that, or negative to
+        /// expire straight away
+        exptime: i64,
+        value: Vec<u8>,
+        noreply: bool,
+    },
+    Get {
+        keys: Vec<Vec<u8>>,
+    },
+    Delete {
+        key: Vec<u8>,
+        noreply: bool,
+    },
+    FlushAll {
+        noreply: bool,
+    },
+    Version,
+    Quit,
+}
+
+impl Command {
+    /// Whether the client asked to go without a reply
+    pub fn is_noreply(&self) -> bool {
+        match self {
+            Command::Store { noreply, .. } | Command::Delete { noreply, .. } | Command::FlushAll { noreply } => *noreply,
+            _ => false,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Reply {
+    Stored,
+    NotStored,
+    Deleted,
+    NotFound,
+    /// The keys found by a get, with their flags and values
+    Values(Vec<(Vec<u8>, u32, Vec<u8>)>),
+    Ok,
+    Version(String),
+    /// A command that doesn't exist
+    Error,
+    ClientError(String),
+    ServerError(String),
+}
+
+impl Reply {
+    /// Write the reply in its wire format
+    pub fn write(&self, out: &mut impl Write) -> io::Result<()> {
+        match self {
+            Reply::Stored => out.write_all(b"STORED\r\n"),
+            Reply::NotStored => out.write_all(b"NOT_STORED\r\n"),
+            Reply::Deleted => out.write_all(b"DELETED\r\n"),
+            Reply::NotFound => out.write_all(b"NOT_FOUND\r\n"),
+            Reply::Values(values) => {
+                for (key, flags, value) in values {
+                    out.write_all(b"VALUE ")?;
+                    out.write_all(key)?;
+                    write!(out, " {} {}\r\n", flags, value.len())?;
+                    out.write_all(value)?;
+                    out.write_all(b"\r\n")?;
+                }
+                out.write_all(b"END\r\n")
+            },
+            Reply::Ok => out.write_all(b"OK\r\n"),
+            Reply::Version(version) => write!(out, "VERSION {}\r\n", version),
+            Reply::Error => out.write_all(b"ERROR\r\n"),
+            Reply::ClientError(message) => write!(out, "CLIENT_ERROR {}\r\n", message),
+            Reply::ServerError(message) => write!(out, "SERVER_ERROR {}\r\n", message),
+        }
+    }
+}
+
+fn bad_format() -> Reply {
+    Reply::ClientError("bad command line format".to_string())
+}
+
+fn parse_number<T: std::str::FromStr>(word: &[u8]) -> Result<T, Reply> {
+    std::str::from_utf8(word).ok().and_then(|word| word.parse().ok()).ok_or_else(bad_format)
+}
+
+fn parse_key(word: &[u8]) -> Result<Vec<u8>, Reply> {
+    match word.len() {
+        0..=MAX_KEY_LENGTH => Ok(word.to_vec()),
+        _ => Err(Reply::ClientError("key too long".to_string())),
+    }
+}
+
+/// Whether the optional last word is there and says `noreply`
+fn parse_noreply(rest: &[&[u8]]) -> Result<bool, Reply> {
+    match rest {
+        [] => Ok(false),
+        [b"noreply"] => Ok(true),
+        _ => Err(bad_format()),
+    }
+}
+
+/// The storage command with these words, once its data has been read
+fn parse_store(mode: StoreMode, [key, flags, exptime]: [&[u8]; 3], rest: &[&[u8]], value: Vec<u8>) -> Result<Command, Reply> {
+    Ok(Command::Store {
+        mode,
+        key: parse_key(key)?,
+        flags: parse_number(flags)?,
+        exptime: parse_number(exptime)?,
+        value,
+        noreply: parse_noreply(rest)?,
+    })
+}
+
+/// Read the next command, or None if the input ended before it started. A command the client
+/// got wrong gives the reply explaining why, and anything that leaves the input out of step with
+/// the commands in it is an error of kind InvalidData. A storage command's data longer than
+/// `max_value` is skipped rather than read.
+pub fn read_command(reader: &mut impl BufRead, max_value: usize) -> io::Result<Option<Result<Command, Reply>>> {
+    let mut line = Vec::new();
+    if reader.by_ref().take(MAX_LINE_LENGTH as u64).read_until(b'\n', &mut line)? == 0 {
+        return Ok(None);
+    }
+    if !line.ends_with(b"\n") {
+        return match line.len() {
+            MAX_LINE_LENGTH => Err(io::Error::new(ErrorKind::InvalidData, "line too long")),
+            _ => Ok(None),
+        };
+    }
+    // Like memcached, a bare LF will do
+    let line = line.strip_suffix(b"\r\n").or(line.strip_suffix(b"\n")).unwrap_or(&line);
+    let words: Vec<&[u8]> = line.split(|&b| b == b' ').filter(|word| !word.is_empty()).collect();
+    let Some((&name, args)) = words.split_first() else {
+        return Ok(Some(Err(Reply::Error)));
+    };
+
+    let command = match (name, args) {
+        (b"set" | b"add" | b"replace", [key, flags, exptime, length, rest @ ..]) => {
+            let length: usize = match parse_number(length) {
+                Ok(length) => length,
+                // Without the length the data can't be skipped, so like memcached, the line after
+                // is read as the next command
+                Err(reply) => return Ok(Some(Err(reply))),
+            };
+            if length > max_value {
+                // Like memcached, the data's still read, only to be thrown away
+                let skipped = io::copy(&mut reader.take((length as u64).saturating_add(2)), &mut io::sink())?;
+                if skipped < (length as u64).saturating_add(2) {
+                    return Err(ErrorKind::UnexpectedEof.into());
+                }
+                return Ok(Some(Err(Reply::ServerError("object too large for cache".to_string()))));
+            }
+            let mut value = vec![0; length + 2];
+            reader.read_exact(&mut value)?;
+            if !value.ends_with(b"\r\n") {
+                return Err(io::Error::new(ErrorKind::InvalidData, "bad data chunk"));
+            }
+            value.truncate(length);
+            let mode = match name {
+                b"set" => StoreMode::Set,
+                b"add" => StoreMode::Add,
+                _ => StoreMode::Replace,
+            };
+            parse_store(mode, [key, flags, exptime], rest, value)
+        },
+        (b"get" | b"gets", keys) if !keys.is_empty() => {
+            keys.iter().map(|key| parse_key(key)).collect::<Result<_, _>>().map(|keys| Command::Get { keys })
+        },
+        (b"delete", [key, rest @ ..]) => {
+            parse_key(key).and_then(|key| Ok(Command::Delete { key, noreply: parse_noreply(rest)? }))
+        },
+        (b"flush_all", rest) => parse_noreply(rest).map(|noreply| Command::FlushAll { noreply }),
+        (b"version", []) => Ok(Command::Version),
+        (b"quit", []) => Ok(Command::Quit),
+        (b"set" | b"add" | b"replace" | b"get" | b"gets" | b"delete" | b"version" | b"quit", _) => Err(bad_format()),
+        _ => Err(Reply::Error),
+    };
+    Ok(Some(command))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn read(input: &[u8]) -> io::Result<Option<Result<Command, Reply>>> {
+        read_command(&mut Cursor::new(input), MAX_VALUE_LENGTH)
+    }
+
+    fn reply(reply: Reply) -> String {
+        let mut out = Vec::new();
+        reply.write(&mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn check_read_command() {
+        assert_eq!(read(b"set greeting 5 60 10\r\nhello\r\nyou\r\n").unwrap(), Some(Ok(Command::Store {
+            mode: StoreMode::Set,
+            key: b"greeting".to_vec(),
+            flags: 5,
+            exptime: 60,
+            value: b"hello\r\nyou".to_vec(),
+            noreply: false,
+        })));
+        assert!(read(b"add k 0 -1 0 noreply\r\n\r\n").unwrap().unwrap().unwrap().is_noreply());
+        assert_eq!(read(b"get a  b\n").unwrap(), Some(Ok(Command::Get { keys: vec![b"a".to_vec(), b"b".to_vec()] })));
+        assert_eq!(read(b"delete a noreply\r\n").unwrap(), Some(Ok(Command::Delete { key: b"a".to_vec(), noreply: true })));
+        assert_eq!(read(b"flush_all\r\n").unwrap(), Some(Ok(Command::FlushAll { noreply: false })));
+        assert_eq!(read(b"quit\r\n").unwrap(), Some(Ok(Command::Quit)));
+        assert_eq!(read(b"").unwrap(), None);
+    }
+
+    #[test]
+    fn check_read_command_errors() {
+        assert_eq!(read(b"fly away\r\n").unwrap(), Some(Err(Reply::Error)));
+        assert_eq!(read(b"\r\n").unwrap(), Some(Err(Reply::Error)));
+        assert_eq!(read(b"get\r\n").unwrap(), Some(Err(bad_format())));
+        assert_eq!(read(b"set k x 0 1\r\na\r\n").unwrap(), Some(Err(bad_format())));
+        assert_eq!(read(b"set k 0 0 abc\r\n").unwrap(), Some(Err(bad_format())));
+        // Too long to store, though the data's still skipped to get to the next command
+        let too_large = Some(Err(Reply::ServerError("object too large for cache".to_string())));
+        let mut input = Cursor::new(b"set k 0 0 9\r\n123456789\r\nversion\r\n".as_slice());
+        assert_eq!(read_command(&mut input, 8).unwrap(), too_large);
+        assert_eq!(read_command(&mut input, 8).unwrap(), Some(Ok(Command::Version)));
+        assert_eq!(read(format!("set k 0 0 {}\r\n", usize::MAX).as_bytes()).unwrap_err().kind(), ErrorKind::UnexpectedEof);
+        assert_eq!(read(b"set k 0 0 100000000000000\r\nabc").unwrap_err().kind(), ErrorKind::UnexpectedEof);
+        assert_eq!(read(b"delete k later\r\n").unwrap(), Some(Err(bad_format())));
+        let long_key = format!("get {}\r\n", "k".repeat(MAX_KEY_LENGTH + 1));
+        assert_eq!(read(long_key.as_bytes()).unwrap(), Some(Err(Reply::ClientError("key too long".to_string()))));
+
+        let err = |input: &[u8]| read(input).unwrap_err().to_string();
+        assert_eq!(err(b"set k 0 0 1\r\nab\r\n"), "bad data chunk");
+        assert_eq!(err(b"set k 0 0 5\r\nab"), "failed to fill whole buffer");
+        assert_eq!(err("x".repeat(MAX_LINE_LENGTH + 1).as_bytes()), "line too long");
+    }
+
+    #[test]
+    fn check_write_reply() {
+        assert_eq!(reply(Reply::Stored), "STORED\r\n");
+        assert_eq!(reply(Reply::Values(Vec::new())), "END\r\n");
+        assert_eq!(
+            reply(Reply::Values(vec![(b"a".to_vec(), 3, b"one".to_vec()), (b"b".to_vec(), 0, Vec::new())])),
+            "VALUE a 3 3\r\none\r\nVALUE b 0 0\r\n\r\nEND\r\n",
+        );
+        assert_eq!(reply(Reply::ClientError("bad data chunk".to_string())), "CLIENT_ERROR bad data chunk\r\n");
+    }
+}
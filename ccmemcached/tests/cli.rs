@@ -0,0 +1,161 @@
+use cc_tests::Server;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpStream;
+use std::process::Command;
+use std::thread;
+use std::time::Duration;
+
+/// A ccmemcached process listening on a free port
+fn start(args: &[&str]) -> Server {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_ccmemcached"));
+    command.args(args);
+    Server::start(command)
+}
+
+fn connect(server: &Server) -> Client {
+    let stream = TcpStream::connect(&server.address).unwrap();
+    Client { reader: BufReader::new(stream.try_clone().unwrap()), stream }
+}
+
+struct Client {
+    stream: TcpStream,
+    reader: BufReader<TcpStream>,
+}
+
+impl Client {
+    fn send(&mut self, request: &[u8]) {
+        self.stream.write_all(request).unwrap();
+    }
+
+    /// Read one reply, which for a get is each value found and the END after them
+    fn reply(&mut self) -> String {
+        let mut reply = String::new();
+        loop {
+            let mut line = String::new();
+            self.reader.read_line(&mut line).unwrap();
+            reply.push_str(&line);
+            let Some(header) = line.strip_prefix("VALUE ") else {
+                return reply;
+            };
+            let length: usize = header.split_whitespace().nth(2).unwrap().parse().unwrap();
+            let mut data = vec![0; length + 2];
+            self.reader.read_exact(&mut data).unwrap();
+            reply.push_str(&String::from_utf8(data).unwrap());
+        }
+    }
+
+    fn request(&mut self, request: &[u8]) -> String {
+        self.send(request);
+        self.reply()
+    }
+}
+
+#[test]
+fn run_memcached_set_get_delete() {
+    let server = start(&[]);
+    let mut client = connect(&server);
+
+    assert_eq!(client.request(b"set greeting 42 0 10\r\nhello\r\nyou\r\n"), "STORED\r\n");
+    assert_eq!(client.request(b"get greeting missing\r\n"), "VALUE greeting 42 10\r\nhello\r\nyou\r\nEND\r\n");
+    assert_eq!(client.request(b"add greeting 0 0 1\r\nx\r\n"), "NOT_STORED\r\n");
+    assert_eq!(client.request(b"replace greeting 0 0 2\r\nhi\r\n"), "STORED\r\n");
+    assert_eq!(client.request(b"get greeting\r\n"), "VALUE greeting 0 2\r\nhi\r\nEND\r\n");
+    assert_eq!(client.request(b"delete greeting\r\n"), "DELETED\r\n");
+    assert_eq!(client.request(b"delete greeting\r\n"), "NOT_FOUND\r\n");
+    assert_eq!(client.request(b"get greeting\r\n"), "END\r\n");
+    assert_eq!(client.request(b"version\r\n"), "VERSION 0.1.0\r\n");
+}
+
+#[test]
+fn run_memcached_expiry() {
+    let server = start(&[]);
+    let mut client = connect(&server);
+
+    assert_eq!(client.request(b"set short 0 1 1\r\na\r\n"), "STORED\r\n");
+    assert_eq!(client.request(b"set long 0 100 1\r\nb\r\n"), "STORED\r\n");
+    assert_eq!(client.request(b"set now 0 -1 1\r\nc\r\n"), "STORED\r\n");
+    assert_eq!(client.request(b"get short now\r\n"), "VALUE short 0 1\r\na\r\nEND\r\n");
+    thread::sleep(Duration::from_millis(1100));
+    assert_eq!(client.request(b"get short long\r\n"), "VALUE long 0 1\r\nb\r\nEND\r\n");
+}
+
+#[test]
+fn run_memcached_evicts_least_recently_used() {
+    let server = start(&["--memory-limit", "1"]);
+    let mut client = connect(&server);
+
+    let value = "x".repeat(400 * 1024);
+    let set = |key: &str| format!("set {} 0 0 {}\r\n{}\r\n", key, value.len(), value).into_bytes();
+    assert_eq!(client.request(&set("a")), "STORED\r\n");
+    assert_eq!(client.request(&set("b")), "STORED\r\n");
+    // Fetching a leaves b the one to go when c needs the room
+    assert!(client.request(b"get a\r\n").starts_with("VALUE a 0 409600\r\n"));
+    assert_eq!(client.request(&set("c")), "STORED\r\n");
+    assert_eq!(client.request(b"get b\r\n"), "END\r\n");
+    assert!(client.request(b"get a c\r\n").ends_with(&format!("VALUE c 0 409600\r\n{}\r\nEND\r\n", value)));
+
+    let huge = "x".repeat(2 * 1024 * 1024);
+    let reply = client.request(format!("set huge 0 0 {}\r\n{}\r\n", huge.len(), huge).as_bytes());
+    assert_eq!(reply, "SERVER_ERROR object too large for cache\r\n");
+    assert_eq!(client.request(b"version\r\n"), "VERSION 0.1.0\r\n");
+
+    // A length far too big to hold isn't held, and the server carries on without it
+    client.send(b"set huge 0 0 100000000000000\r\nabc");
+    assert_eq!(connect(&server).request(b"version\r\n"), "VERSION 0.1.0\r\n");
+}
+
+#[test]
+fn run_memcached_noreply_and_pipelining() {
+    let server = start(&[]);
+    let mut client = connect(&server);
+
+    client.send(b"set a 0 0 1 noreply\r\n1\r\nset b 0 0 1 noreply\r\n2\r\ndelete a noreply\r\nget a b\r\n");
+    assert_eq!(client.reply(), "VALUE b 0 1\r\n2\r\nEND\r\n");
+    client.send(b"flush_all noreply\r\nget b\r\nquit\r\n");
+    assert_eq!(client.reply(), "END\r\n");
+    assert_eq!(client.reply(), "");
+}
+
+#[test]
+fn run_memcached_errors() {
+    let server = start(&[]);
+    let mut client = connect(&server);
+
+    assert_eq!(client.request(b"fly away\r\n"), "ERROR\r\n");
+    assert_eq!(client.request(b"get\r\n"), "CLIENT_ERROR bad command line format\r\n");
+    assert_eq!(client.request(b"set k x 0 1\r\na\r\n"), "CLIENT_ERROR bad command line format\r\n");
+    // With no length to go by, the data is taken for a command
+    assert_eq!(client.request(b"set e 0 0 abc\r\n"), "CLIENT_ERROR bad command line format\r\n");
+    assert_eq!(client.request(b"abc\r\n"), "ERROR\r\n");
+    // Still in step with the commands after all that
+    assert_eq!(client.request(b"set k 0 0 1\r\na\r\n"), "STORED\r\n");
+    // Data longer than it said leaves no telling where the next command starts
+    assert_eq!(client.request(b"set k 0 0 1\r\nabc\r\n"), "CLIENT_ERROR bad data chunk\r\n");
+    assert_eq!(client.reply(), "");
+}
+
+#[test]
+fn run_memcached_concurrent_clients() {
+    let server = start(&[]);
+    let clients: Vec<_> = (0..8)
+        .map(|n| {
+            let mut client = connect(&server);
+            thread::spawn(move || {
+                for i in 0..100 {
+                    let (key, value) = (format!("client{}-{}", n, i), format!("{}", n * i));
+                    let set = format!("set {} {} 0 {}\r\n{}\r\n", key, n, value.len(), value);
+                    assert_eq!(client.request(set.as_bytes()), "STORED\r\n");
+                    let expected = format!("VALUE {} {} {}\r\n{}\r\nEND\r\n", key, n, value.len(), value);
+                    assert_eq!(client.request(format!("get {}\r\n", key).as_bytes()), expected);
+                }
+            })
+        })
+        .collect();
+    for client in clients {
+        client.join().unwrap();
+    }
+
+    // Every client's keys ended up in the one cache
+    let mut client = connect(&server);
+    assert_eq!(client.request(b"get client0-5 client7-99\r\n"), "VALUE client0-5 0 1\r\n0\r\nVALUE client7-99 7 3\r\n693\r\nEND\r\n");
+}
@@ -0,0 +1,70 @@
+use crate::{serve, Algorithm, Limit, RateLimiter, DEFAULT_PORT};
+use anyhow::{bail, Context, Result};
+use clap::Parser;
+use std::ffi::OsString;
+use std::net::TcpListener;
+use std::process::ExitCode;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// ratelimiter - an HTTP service that limits how often each client may call it
+#[derive(Parser)]
+struct Cli {
+    /// The address to listen on
+    #[arg(long, default_value = "127.0.0.1")]
+    bind: String,
+
+    /// The port to listen on, or 0 to pick a free one
+    #[arg(short, long, default_value_t = DEFAULT_PORT)]
+    port: u16,
+
+    /// How to count requests: token-bucket or sliding-window
+    #[arg(short, long, default_value = "token-bucket")]
+    algorithm: Algorithm,
+
+    /// Requests each client may make per period
+    #[arg(short, long, default_value_t = 10)]
+    requests: u32,
+
+    /// The period in seconds, over which a token bucket refills or a window slides
+    #[arg(long, value_name = "SECONDS", default_value_t = 1.0)]
+    period: f64,
+}
+
+/// Run ccratelimiter with the given command line, `args[0]` being the program name
+pub fn main<I, T>(args: I) -> ExitCode
+where
+    I: IntoIterator<Item = T>,
+    T: Into<OsString> + Clone,
+{
+    let args: Cli = cc_cli::parse_from(args, env!("CARGO_PKG_VERSION"));
+
+    match run(args) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("Error: {:?}", e);
+            ExitCode::FAILURE
+        },
+    }
+}
+
+fn run(args: Cli) -> Result<()> {
+    if args.requests == 0 {
+        bail!("requests must be at least 1");
+    }
+    let Ok(period) = Duration::try_from_secs_f64(args.period) else {
+        bail!("invalid period {}", args.period);
+    };
+    if period.is_zero() {
+        bail!("the period must be longer than 0 seconds");
+    }
+    let limiter = RateLimiter::new(args.algorithm, Limit { requests: args.requests, period });
+
+    let address = (args.bind.as_str(), args.port);
+    let listener = TcpListener::bind(address).with_context(|| format!("could not listen on {}:{}", args.bind, args.port))?;
+    // Give the real address, which is how to find the port when asking for any free one
+    eprintln!("Limiting requests on http://{}/", listener.local_addr()?);
+
+    serve(listener, Arc::new(limiter))?;
+    Ok(())
+}
@@ -0,0 +1,326 @@
+// Rate limiter
+// An HTTP service with a /limited endpoint that lets each client make so many requests in a
+// period, answering 429 Too Many Requests once they're over, and an /unlimited one to compare it
+// with. Clients are told apart by their X-API-Key header, or else their IP address, and each gets
+// its own token bucket or sliding window log. The HTTP side is ccwebserver's.
+use ccwebserver::{read_request, write_response, Request, RequestError, Status, KEEP_ALIVE_TIMEOUT};
+use std::collections::{HashMap, VecDeque};
+use std::io::{self, BufReader, BufWriter, ErrorKind, Write};
+use std::net::{IpAddr, TcpListener, TcpStream};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+mod cli;
+
+pub use cli::main;
+
+pub const DEFAULT_PORT: u16 = 8080;
+
+pub const TOO_MANY_REQUESTS: Status = Status(429, "Too Many Requests");
+
+/// Clients kept before forgetting those back to their full allowance, which is the same as
+/// never having been seen
+const MAX_CLIENTS: usize = 10_000;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Algorithm {
+    /// Each client has a bucket of `requests` tokens, one taken by each request and refilled
+    /// steadily over `period`, which allows bursts up to the bucket's size
+    TokenBucket,
+    /// Each client may make `requests` in any `period`, going by the times of its last requests
+    SlidingWindow,
+}
+
+impl FromStr for Algorithm {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Algorithm, String> {
+        match s {
+            "token-bucket" => Ok(Algorithm::TokenBucket),
+            "sliding-window" => Ok(Algorithm::SlidingWindow),
+            _ => Err(format!("unknown algorithm `{}`, expected token-bucket or sliding-window", s)),
+        }
+    }
+}
+
+/// How many requests a client may make in how long
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Limit {
+    pub requests: u32,
+    pub period: Duration,
+}
+
+/// Whether a request may go ahead, and what the client has left
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Decision {
+    pub allowed: bool,
+    pub remaining: u32,
+    /// How long until the next request would be allowed, zero if it would be now
+    pub retry_after: Duration,
+}
+
+#[derive(Debug)]
+enum Allowance {
+    TokenBucket { tokens: f64, updated: Instant },
+    SlidingWindow { times: VecDeque<Instant> },
+}
+
+impl Allowance {
+    fn new(algorithm: Algorithm, limit: Limit, now: Instant) -> Allowance {
+        match algorithm {
+            Algorithm::TokenBucket => Allowance::TokenBucket { tokens: f64::from(limit.requests), updated: now },
+            Algorithm::SlidingWindow => Allowance::SlidingWindow { times: VecDeque::new() },
+        }
+    }
+
+    /// Catch up to `now`, refilling the bucket or forgetting requests that left the window
+    fn update(&mut self, limit: Limit, now: Instant) {
+        match self {
+            Allowance::TokenBucket { tokens, updated } => {
+                let refilled = now.saturating_duration_since(*updated).as_secs_f64() / limit.period.as_secs_f64();
+                *tokens = (*tokens + refilled * f64::from(limit.requests)).min(f64::from(limit.requests));
+                *updated = now;
+            },
+            Allowance::SlidingWindow { times } => {
+                while times.front().is_some_and(|&time| now.saturating_duration_since(time) >= limit.period) {
+                    times.pop_front();
+                }
+            },
+        }
+    }
+
+    fn check(&mut self, limit: Limit, now: Instant) -> Decision {
+        self.update(limit, now);
+        match self {
+            Allowance::TokenBucket { tokens, .. } => {
+                let allowed = *tokens >= 1.0;
+                if allowed {
+                    *tokens -= 1.0;
+                }
+                let per_token = limit.period.as_secs_f64() / f64::from(limit.requests);
+                Decision {
+                    allowed,
+                    remaining: *tokens as u32,
+                    retry_after: Duration::from_secs_f64((1.0 - *tokens).max(0.0) * per_token),
+                }
+            },
+            Allowance::SlidingWindow { times } => {
+                let allowed = times.len() < limit.requests as usize;
+                if allowed {
+                    times.push_back(now);
+                }
+                let retry_after = match times.len() < limit.requests as usize {
+                    true => Duration::ZERO,
+                    false => (times[0] + limit.period).saturating_duration_since(now),
+                };
+                Decision { allowed, remaining: limit.requests - times.len() as u32, retry_after }
+            },
+        }
+    }
+
+    fn is_full(&self, limit: Limit) -> bool {
+        match self {
+            Allowance::TokenBucket { tokens, .. } => *tokens >= f64::from(limit.requests),
+            Allowance::SlidingWindow { times } => times.is_empty(),
+        }
+    }
+}
+
+/// The allowances of every client, shared between connections
+#[derive(Debug)]
+pub struct RateLimiter {
+    algorithm: Algorithm,
+    limit: Limit,
+    clients: Mutex<HashMap<String, Allowance>>,
+}
+
+impl RateLimiter {
+    pub fn new(algorithm: Algorithm, limit: Limit) -> RateLimiter {
+        RateLimiter { algorithm, limit, clients: Mutex::new(HashMap::new()) }
+    }
+
+    pub fn limit(&self) -> Limit {
+        self.limit
+    }
+
+    /// Count a request from `client`, if it's allowed one
+    pub fn check(&self, client: &str, now: Instant) -> Decision {
+        let mut clients = self.clients.lock().unwrap();
+        if clients.len() >= MAX_CLIENTS && !clients.contains_key(client) {
+            clients.retain(|_, allowance| {
+                allowance.update(self.limit, now);
+                !allowance.is_full(self.limit)
+            });
+        }
+        clients
+            .entry(client.to_string())
+            .or_insert_with(|| Allowance::new(self.algorithm, self.limit, now))
+            .check(self.limit, now)
+    }
+}
+
+/// The key a client's requests are counted under
+fn client_key(request: &Request, peer: IpAddr) -> String {
+    match request.header("x-api-key") {
+        Some(key) => format!("key {}", key),
+        None => format!("ip {}", peer),
+    }
+}
+
+/// Answer with a short plain text body
+fn write_text(out: &mut impl Write, status: Status, headers: &[(&str, &str)], body: &str, close: bool) -> io::Result<()> {
+    let mut headers = headers.to_vec();
+    headers.insert(0, ("Content-Type", "text/plain; charset=utf-8"));
+    if close {
+        headers.push(("Connection", "close"));
+    }
+    write_response(out, status, &headers, &mut body.as_bytes(), body.len() as u64, false)
+}
+
+/// Answer one request, returning whether the connection can stay open
+fn respond(out: &mut impl Write, limiter: &RateLimiter, request: &Request, peer: IpAddr) -> io::Result<bool> {
+    let keep_alive = request.keep_alive();
+    if request.version != "HTTP/1.1" && request.version != "HTTP/1.0" {
+        write_text(out, Status::VERSION_NOT_SUPPORTED, &[], "HTTP version not supported\n", true)?;
+        return Ok(false);
+    }
+    // Bodies aren't read, so the next request can't be found after one and the connection has to
+    // close
+    let has_body =
+        request.header("content-length").is_some_and(|n| n != "0") || request.header("transfer-encoding").is_some();
+    if request.method != "GET" || has_body {
+        let status = if has_body { Status::BAD_REQUEST } else { Status::METHOD_NOT_ALLOWED };
+        let reuse = keep_alive && !has_body;
+        write_text(out, status, &[("Allow", "GET")], "Only GET requests are allowed\n", !reuse)?;
+        return Ok(reuse);
+    }
+
+    let path = request.target.split('?').next().unwrap_or_default();
+    match path {
+        "/unlimited" => write_text(out, Status::OK, &[], "Unlimited! Let's Go!\n", !keep_alive)?,
+        "/limited" => {
+            let decision = limiter.check(&client_key(request, peer), Instant::now());
+            let limit = limiter.limit().requests.to_string();
+            let remaining = decision.remaining.to_string();
+            let mut headers = vec![("X-RateLimit-Limit", limit.as_str()), ("X-RateLimit-Remaining", remaining.as_str())];
+            match decision.allowed {
+                true => write_text(out, Status::OK, &headers, "Limited, don't over use me!\n", !keep_alive)?,
+                false => {
+                    // Whole seconds, rounded up so trying again then will work
+                    let retry_after = decision.retry_after.as_secs_f64().ceil().to_string();
+                    headers.push(("Retry-After", retry_after.as_str()));
+                    write_text(out, TOO_MANY_REQUESTS, &headers, "Too many requests, slow down\n", !keep_alive)?;
+                },
+            }
+        },
+        _ => write_text(out, Status::NOT_FOUND, &[], "Not found\n", !keep_alive)?,
+    }
+    Ok(keep_alive)
+}
+
+/// Answer requests from one client until it disconnects or wants the connection closed
+pub fn handle_connection(stream: TcpStream, limiter: &RateLimiter) -> io::Result<()> {
+    stream.set_read_timeout(Some(KEEP_ALIVE_TIMEOUT))?;
+    let peer = stream.peer_addr()?.ip();
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut writer = BufWriter::new(stream);
+
+    loop {
+        let request = match read_request(&mut reader) {
+            Ok(Some(request)) => request,
+            Ok(None) => return Ok(()),
+            Err(RequestError::Io(e)) if e.kind() == ErrorKind::WouldBlock || e.kind() == ErrorKind::TimedOut => {
+                return Ok(());
+            },
+            Err(RequestError::Io(e)) => return Err(e),
+            Err(RequestError::Malformed(_)) => {
+                return write_text(&mut writer, Status::BAD_REQUEST, &[], "Malformed request\n", true);
+            },
+            Err(RequestError::TooLarge) => {
+                return write_text(&mut writer, Status::HEADERS_TOO_LARGE, &[], "Request headers too large\n", true);
+            },
+        };
+        if !respond(&mut writer, limiter, &request, peer)? {
+            return Ok(());
+        }
+    }
+}
+
+/// Accept connections forever, each on its own thread, all sharing one limiter
+pub fn serve(listener: TcpListener, limiter: Arc<RateLimiter>) -> io::Result<()> {
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            // The client gave up before we got to it
+            Err(e) if e.kind() == ErrorKind::ConnectionAborted => continue,
+            Err(e) => return Err(e),
+        };
+        let limiter = Arc::clone(&limiter);
+        thread::spawn(move || {
+            if let Err(e) = handle_connection(stream, &limiter) {
+                if e.kind() != ErrorKind::ConnectionReset && e.kind() != ErrorKind::BrokenPipe {
+                    eprintln!("Error: {}", e);
+                }
+            }
+        });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LIMIT: Limit = Limit { requests: 3, period: Duration::from_secs(6) };
+
+    fn allowed(limiter: &RateLimiter, client: &str, now: Instant) -> bool {
+        limiter.check(client, now).allowed
+    }
+
+    #[test]
+    fn check_token_bucket() {
+        let limiter = RateLimiter::new(Algorithm::TokenBucket, LIMIT);
+        let start = Instant::now();
+        // A full bucket allows a burst, then refills a token every 2 seconds
+        for (remaining, retry_after) in [(2, 0), (1, 0), (0, 2)] {
+            let retry_after = Duration::from_secs(retry_after);
+            assert_eq!(limiter.check("a", start), Decision { allowed: true, remaining, retry_after });
+        }
+        assert_eq!(limiter.check("a", start + Duration::from_secs(1)), Decision {
+            allowed: false,
+            remaining: 0,
+            retry_after: Duration::from_secs(1),
+        });
+        assert!(allowed(&limiter, "b", start));
+        assert!(allowed(&limiter, "a", start + Duration::from_secs(2)));
+        assert!(!allowed(&limiter, "a", start + Duration::from_secs(3)));
+        // A long wait only refills as far as the bucket's size
+        let later = start + Duration::from_secs(60);
+        assert_eq!((0..4).filter(|_| allowed(&limiter, "a", later)).count(), 3);
+    }
+
+    #[test]
+    fn check_sliding_window() {
+        let limiter = RateLimiter::new(Algorithm::SlidingWindow, LIMIT);
+        let start = Instant::now();
+        let at = |seconds| start + Duration::from_secs(seconds);
+        assert!(allowed(&limiter, "a", at(0)));
+        assert!(allowed(&limiter, "a", at(2)));
+        assert!(allowed(&limiter, "a", at(4)));
+        assert_eq!(limiter.check("a", at(5)), Decision { allowed: false, remaining: 0, retry_after: Duration::from_secs(1) });
+        // The first request leaves the window 6 seconds after it, making room for one more
+        assert!(allowed(&limiter, "a", at(6)));
+        assert!(!allowed(&limiter, "a", at(7)));
+        assert_eq!(limiter.check("a", at(8)).remaining, 0);
+        assert_eq!(limiter.check("b", at(7)).remaining, 2);
+    }
+
+    #[test]
+    fn check_parse_algorithm() {
+        assert_eq!("token-bucket".parse(), Ok(Algorithm::TokenBucket));
+        assert_eq!("sliding-window".parse(), Ok(Algorithm::SlidingWindow));
+        assert!("leaky-bucket".parse::<Algorithm>().is_err());
+    }
+}
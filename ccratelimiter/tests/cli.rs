@@ -0,0 +1,73 @@
+use cc_tests::Server;
+use std::process::Command;
+
+/// A ccratelimiter process on a free port
+fn start(args: &[&str]) -> Server {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_ccratelimiter"));
+    command.args(args);
+    Server::start(command)
+}
+
+fn get(server: &Server, path: &str, headers: &str) -> String {
+    server.send(&format!("GET {} HTTP/1.1\r\nHost: localhost\r\n{}Connection: close\r\n\r\n", path, headers))
+}
+
+#[test]
+fn run_ratelimiter_token_bucket() {
+    let server = start(&["--requests", "2", "--period", "60"]);
+    let response = get(&server, "/limited", "");
+    assert_eq!(
+        response,
+        "HTTP/1.1 200 OK\r\nContent-Type: text/plain; charset=utf-8\r\nX-RateLimit-Limit: 2\r\n\
+         X-RateLimit-Remaining: 1\r\nConnection: close\r\nContent-Length: 28\r\n\r\nLimited, don't over use me!\n",
+    );
+    assert!(get(&server, "/limited", "").starts_with("HTTP/1.1 200 OK\r\n"));
+
+    // A token comes back every 30 seconds
+    let response = get(&server, "/limited?again", "");
+    assert!(response.starts_with("HTTP/1.1 429 Too Many Requests\r\n"), "{}", response);
+    assert!(response.contains("\r\nX-RateLimit-Remaining: 0\r\nRetry-After: 30\r\n"), "{}", response);
+
+    assert!(get(&server, "/unlimited", "").ends_with("\r\n\r\nUnlimited! Let's Go!\n"));
+}
+
+#[test]
+fn run_ratelimiter_sliding_window_per_key() {
+    let server = start(&["--algorithm", "sliding-window", "--requests", "1", "--period", "60"]);
+    assert!(get(&server, "/limited", "X-API-Key: alice\r\n").starts_with("HTTP/1.1 200 OK\r\n"));
+    assert!(get(&server, "/limited", "X-API-Key: alice\r\n").starts_with("HTTP/1.1 429 Too Many Requests\r\n"));
+    assert!(get(&server, "/limited", "X-API-Key: bob\r\n").starts_with("HTTP/1.1 200 OK\r\n"));
+    // Without a key it's the IP address that counts
+    assert!(get(&server, "/limited", "").starts_with("HTTP/1.1 200 OK\r\n"));
+    assert!(get(&server, "/limited", "").starts_with("HTTP/1.1 429 Too Many Requests\r\n"));
+}
+
+#[test]
+fn run_ratelimiter_keep_alive() {
+    let server = start(&["--requests", "1", "--period", "60"]);
+    let request = "GET /limited HTTP/1.1\r\nHost: localhost\r\n\r\n";
+    let response = server.send(&request.repeat(2));
+    assert_eq!(response.matches("HTTP/1.1 ").count(), 2, "{}", response);
+    assert!(response.starts_with("HTTP/1.1 200 OK\r\n"), "{}", response);
+    assert!(response.contains("HTTP/1.1 429 Too Many Requests\r\n"), "{}", response);
+}
+
+#[test]
+fn run_ratelimiter_bad_requests() {
+    let server = start(&[]);
+    assert!(get(&server, "/nope", "").starts_with("HTTP/1.1 404 Not Found\r\n"));
+    let response = server.send("POST /limited HTTP/1.1\r\nConnection: close\r\n\r\n");
+    assert!(response.starts_with("HTTP/1.1 405 Method Not Allowed\r\n"), "{}", response);
+    assert!(response.contains("\r\nAllow: GET\r\n"), "{}", response);
+    assert!(server.send("nonsense\r\n\r\n").starts_with("HTTP/1.1 400 Bad Request\r\n"));
+}
+
+#[test]
+fn run_ratelimiter_invalid_options() {
+    let output = Command::new(env!("CARGO_BIN_EXE_ccratelimiter")).args(["--requests", "0"]).output().unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("requests must be at least 1"));
+
+    let output = Command::new(env!("CARGO_BIN_EXE_ccratelimiter")).args(["--algorithm", "leaky"]).output().unwrap();
+    assert_eq!(output.status.code(), Some(2));
+}
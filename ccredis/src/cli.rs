@@ -0,0 +1,46 @@
+use crate::{serve, Store, DEFAULT_PORT};
+use anyhow::{Context, Result};
+use clap::Parser;
+use std::ffi::OsString;
+use std::net::TcpListener;
+use std::process::ExitCode;
+use std::sync::{Arc, Mutex};
+
+/// redis - a lightweight Redis server
+#[derive(Parser)]
+struct Cli {
+    /// The address to listen on
+    #[arg(long, default_value = "127.0.0.1")]
+    bind: String,
+
+    /// The port to listen on, or 0 to pick a free one
+    #[arg(short, long, default_value_t = DEFAULT_PORT)]
+    port: u16,
+}
+
+/// Run ccredis with the given command line, `args[0]` being the program name
+pub fn main<I, T>(args: I) -> ExitCode
+where
+    I: IntoIterator<Item = T>,
+    T: Into<OsString> + Clone,
+{
+    let args: Cli = cc_cli::parse_from(args, env!("CARGO_PKG_VERSION"));
+
+    match run(args) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("Error: {:?}", e);
+            ExitCode::FAILURE
+        },
+    }
+}
+
+fn run(args: Cli) -> Result<()> {
+    let address = (args.bind.as_str(), args.port);
+    let listener = TcpListener::bind(address).with_context(|| format!("could not listen on {}:{}", args.bind, args.port))?;
+    // Give the real address, which is how to find the port when asking for any free one
+    eprintln!("Ready to accept connections on {}", listener.local_addr()?);
+
+    serve(listener, Arc::new(Mutex::new(Store::new())))?;
+    Ok(())
+}
@@ -0,0 +1,237 @@
+// Lightweight Redis server
+// Speaks enough of the Redis protocol for PING, ECHO, SET and GET with expiry, DEL, and EXISTS.
+// Each connection gets its own thread, and they share one keyspace behind a mutex. Expired keys
+// are dropped when they're next looked at.
+// My answer to:  https://codingchallenges.substack.com/p/coding-challenge-8
+use std::collections::HashMap;
+use std::io::{self, BufReader, BufWriter, ErrorKind, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+mod cli;
+pub mod resp;
+
+pub use cli::main;
+use resp::Value;
+
+pub const DEFAULT_PORT: u16 = 6379;
+
+#[derive(Debug)]
+struct Entry {
+    value: Vec<u8>,
+    expires: Option<Instant>,
+}
+
+impl Entry {
+    fn is_live(&self, now: Instant) -> bool {
+        self.expires.is_none_or(|expires| expires > now)
+    }
+}
+
+/// The keyspace
+#[derive(Debug, Default)]
+pub struct Store {
+    entries: HashMap<Vec<u8>, Entry>,
+}
+
+impl Store {
+    pub fn new() -> Store {
+        Store::default()
+    }
+
+    pub fn get(&mut self, key: &[u8]) -> Option<&[u8]> {
+        let now = Instant::now();
+        if self.entries.get(key).is_some_and(|entry| !entry.is_live(now)) {
+            self.entries.remove(key);
+        }
+        self.entries.get(key).map(|entry| &entry.value[..])
+    }
+
+    /// Set a key, replacing any value and expiry it had
+    pub fn set(&mut self, key: Vec<u8>, value: Vec<u8>, ttl: Option<Duration>) {
+        let expires = ttl.map(|ttl| Instant::now() + ttl);
+        self.entries.insert(key, Entry { value, expires });
+    }
+
+    /// Remove a key, returning whether it was there
+    pub fn remove(&mut self, key: &[u8]) -> bool {
+        let now = Instant::now();
+        self.entries.remove(key).is_some_and(|entry| entry.is_live(now))
+    }
+
+    pub fn contains(&mut self, key: &[u8]) -> bool {
+        self.get(key).is_some()
+    }
+}
+
+fn error(message: impl std::fmt::Display) -> Value {
+    Value::Error(format!("ERR {}", message))
+}
+
+fn wrong_arguments(command: &str) -> Value {
+    error(format!("wrong number of arguments for '{}' command", command))
+}
+
+/// Parse the options after `SET key value`, giving how long the key should live
+fn set_expiry(options: &[Vec<u8>]) -> Result<Option<Duration>, Value> {
+    let mut ttl = None;
+    let mut options = options.iter();
+    while let Some(option) = options.next() {
+        let to_duration: fn(u64) -> Duration = match &option.to_ascii_uppercase()[..] {
+            b"EX" => Duration::from_secs,
+            b"PX" => Duration::from_millis,
+            _ => return Err(error("syntax error")),
+        };
+        if ttl.is_some() {
+            return Err(error("syntax error"));
+        }
+        let amount = options
+            .next()
+            .and_then(|amount| std::str::from_utf8(amount).ok())
+            .and_then(|amount| amount.parse::<u64>().ok());
+        match amount {
+            Some(amount) if amount > 0 => ttl = Some(to_duration(amount)),
+            _ => return Err(error("invalid expire time in 'set' command")),
+        }
+    }
+    Ok(ttl)
+}
+
+/// Run one command, given as its name followed by its arguments, and return the reply
+pub fn execute(store: &Mutex<Store>, args: &[Vec<u8>]) -> Value {
+    let Some((name, args)) = args.split_first() else {
+        return error("empty command");
+    };
+    let name = String::from_utf8_lossy(name).to_ascii_lowercase();
+    let mut store = store.lock().unwrap();
+
+    match (name.as_str(), args) {
+        ("ping", []) => Value::SimpleString("PONG".to_string()),
+        ("ping", [message]) => Value::bulk(message.clone()),
+        ("echo", [message]) => Value::bulk(message.clone()),
+        ("get", [key]) => Value::BulkString(store.get(key).map(<[u8]>::to_vec)),
+        ("set", [key, value, options @ ..]) => match set_expiry(options) {
+            Ok(ttl) => {
+                store.set(key.clone(), value.clone(), ttl);
+                Value::ok()
+            },
+            Err(reply) => reply,
+        },
+        ("del", keys) if !keys.is_empty() => {
+            Value::Integer(keys.iter().filter(|key| store.remove(key)).count() as i64)
+        },
+        // Like Redis, a key repeated in the arguments is counted each time
+        ("exists", keys) if !keys.is_empty() => {
+            Value::Integer(keys.iter().filter(|key| store.contains(key)).count() as i64)
+        },
+        ("ping" | "echo" | "get" | "set" | "del" | "exists", _) => wrong_arguments(&name),
+        _ => error(format!("unknown command '{}'", name)),
+    }
+}
+
+/// Answer commands from one client until it disconnects
+pub fn handle_connection(stream: TcpStream, store: &Mutex<Store>) -> io::Result<()> {
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut writer = BufWriter::new(stream);
+
+    loop {
+        let reply = match resp::read_command(&mut reader) {
+            Ok(None) => return Ok(()),
+            Ok(Some(args)) if args.is_empty() => continue,
+            Ok(Some(args)) => execute(store, &args),
+            // The stream can't be trusted to line up with the next command after bad input, so
+            // explain and hang up, as Redis does
+            Err(e) if e.kind() == ErrorKind::InvalidData => {
+                error(format!("Protocol error: {}", e)).write(&mut writer)?;
+                return writer.flush();
+            },
+            Err(e) => return Err(e),
+        };
+        reply.write(&mut writer)?;
+
+        // Clients may pipeline several commands, so only flush once the ones sent so far are done
+        if reader.buffer().is_empty() {
+            writer.flush()?;
+        }
+    }
+}
+
+/// Accept connections forever, serving each on its own thread
+pub fn serve(listener: TcpListener, store: Arc<Mutex<Store>>) -> io::Result<()> {
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            // The client gave up before we got to it
+            Err(e) if e.kind() == ErrorKind::ConnectionAborted => continue,
+            Err(e) => return Err(e),
+        };
+        let store = Arc::clone(&store);
+        thread::spawn(move || {
+            if let Err(e) = handle_connection(stream, &store) {
+                if e.kind() != ErrorKind::ConnectionReset && e.kind() != ErrorKind::BrokenPipe {
+                    eprintln!("Error: {}", e);
+                }
+            }
+        });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(store: &Mutex<Store>, command: &[&str]) -> Value {
+        let args: Vec<Vec<u8>> = command.iter().map(|arg| arg.as_bytes().to_vec()).collect();
+        execute(store, &args)
+    }
+
+    #[test]
+    fn check_ping_echo() {
+        let store = Mutex::new(Store::new());
+        assert_eq!(run(&store, &["PING"]), Value::SimpleString("PONG".to_string()));
+        assert_eq!(run(&store, &["ping", "hi"]), Value::bulk("hi"));
+        assert_eq!(run(&store, &["ECHO", "hello world"]), Value::bulk("hello world"));
+        assert_eq!(run(&store, &["echo"]), error("wrong number of arguments for 'echo' command"));
+    }
+
+    #[test]
+    fn check_set_get_del_exists() {
+        let store = Mutex::new(Store::new());
+        assert_eq!(run(&store, &["GET", "k"]), Value::BulkString(None));
+        assert_eq!(run(&store, &["SET", "k", "v"]), Value::ok());
+        assert_eq!(run(&store, &["GET", "k"]), Value::bulk("v"));
+        assert_eq!(run(&store, &["EXISTS", "k", "k", "nope"]), Value::Integer(2));
+        assert_eq!(run(&store, &["DEL", "k", "nope"]), Value::Integer(1));
+        assert_eq!(run(&store, &["EXISTS", "k"]), Value::Integer(0));
+    }
+
+    #[test]
+    fn check_set_expiry() {
+        let store = Mutex::new(Store::new());
+        assert_eq!(run(&store, &["SET", "short", "v", "px", "20"]), Value::ok());
+        assert_eq!(run(&store, &["SET", "long", "v", "EX", "100"]), Value::ok());
+        assert_eq!(run(&store, &["GET", "short"]), Value::bulk("v"));
+        thread::sleep(Duration::from_millis(40));
+        assert_eq!(run(&store, &["GET", "short"]), Value::BulkString(None));
+        assert_eq!(run(&store, &["DEL", "short", "long"]), Value::Integer(1));
+    }
+
+    #[test]
+    fn check_set_invalid_options() {
+        let store = Mutex::new(Store::new());
+        assert_eq!(run(&store, &["SET", "k", "v", "EX"]), error("invalid expire time in 'set' command"));
+        assert_eq!(run(&store, &["SET", "k", "v", "EX", "0"]), error("invalid expire time in 'set' command"));
+        assert_eq!(run(&store, &["SET", "k", "v", "EX", "1", "PX", "1"]), error("syntax error"));
+        assert_eq!(run(&store, &["SET", "k", "v", "KEEP"]), error("syntax error"));
+        assert_eq!(run(&store, &["GET", "k"]), Value::BulkString(None));
+    }
+
+    #[test]
+    fn check_unknown_command() {
+        let store = Mutex::new(Store::new());
+        assert_eq!(run(&store, &["FLY", "away"]), error("unknown command 'fly'"));
+    }
+}
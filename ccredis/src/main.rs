@@ -0,0 +1,3 @@
+fn main() -> std::process::ExitCode {
+    ccredis::main(std::env::args_os())
+}
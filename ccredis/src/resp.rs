@@ -0,0 +1,239 @@
+// Redis serialization protocol, version 2.
+// Every value starts with a byte giving its type and ends its header with CRLF, e.g. a request
+// to get a key is an array of bulk strings:
+//
+//   *2\r\n$3\r\nGET\r\n$3\r\nkey\r\n
+//
+// Reference:  https://redis.io/docs/reference/protocol-spec/
+use std::io::{self, BufRead, ErrorKind, Write};
+
+/// Longest bulk string accepted, the same limit as Redis
+pub const MAX_BULK_LENGTH: usize = 512 * 1024 * 1024;
+
+/// Deepest arrays are read nested, which is far more than any reply has
+pub const MAX_DEPTH: usize = 128;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    SimpleString(String),
+    Error(String),
+    Integer(i64),
+    /// None is the null bulk string, `$-1`
+    BulkString(Option<Vec<u8>>),
+    /// None is the null array, `*-1`
+    Array(Option<Vec<Value>>),
+}
+
+impl Value {
+    pub fn ok() -> Value {
+        Value::SimpleString("OK".to_string())
+    }
+
+    pub fn bulk(bytes: impl Into<Vec<u8>>) -> Value {
+        Value::BulkString(Some(bytes.into()))
+    }
+
+    /// Write the value in its wire format
+    pub fn write(&self, out: &mut impl Write) -> io::Result<()> {
+        match self {
+            Value::SimpleString(s) => write!(out, "+{}\r\n", s),
+            Value::Error(message) => write!(out, "-{}\r\n", message),
+            Value::Integer(n) => write!(out, ":{}\r\n", n),
+            Value::BulkString(None) => out.write_all(b"$-1\r\n"),
+            Value::BulkString(Some(bytes)) => {
+                write!(out, "${}\r\n", bytes.len())?;
+                out.write_all(bytes)?;
+                out.write_all(b"\r\n")
+            },
+            Value::Array(None) => out.write_all(b"*-1\r\n"),
+            Value::Array(Some(values)) => {
+                write!(out, "*{}\r\n", values.len())?;
+                values.iter().try_for_each(|value| value.write(out))
+            },
+        }
+    }
+}
+
+fn invalid(message: impl Into<String>) -> io::Error {
+    io::Error::new(ErrorKind::InvalidData, message.into())
+}
+
+/// Read a header line, without its CRLF, or None if the input ended before it started
+fn read_line(reader: &mut impl BufRead) -> io::Result<Option<Vec<u8>>> {
+    let mut line = Vec::new();
+    if reader.read_until(b'\n', &mut line)? == 0 {
+        return Ok(None);
+    }
+    if !line.ends_with(b"\r\n") {
+        return Err(invalid("line not terminated by CRLF"));
+    }
+    line.truncate(line.len() - 2);
+    Ok(Some(line))
+}
+
+fn parse_integer(bytes: &[u8]) -> io::Result<i64> {
+    std::str::from_utf8(bytes)
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .ok_or_else(|| invalid(format!("invalid integer `{}`", String::from_utf8_lossy(bytes))))
+}
+
+/// The length of a bulk string or array, None for the null value
+fn parse_length(bytes: &[u8]) -> io::Result<Option<usize>> {
+    match parse_integer(bytes)? {
+        -1 => Ok(None),
+        n if n < 0 => Err(invalid(format!("invalid length {}", n))),
+        n => Ok(Some(n as usize)),
+    }
+}
+
+/// Read one value, or None if the input ended cleanly before it
+pub fn read_value(reader: &mut impl BufRead) -> io::Result<Option<Value>> {
+    read_nested(reader, 0)
+}
+
+/// Read a value inside `depth` arrays
+fn read_nested(reader: &mut impl BufRead, depth: usize) -> io::Result<Option<Value>> {
+    let Some(line) = read_line(reader)? else {
+        return Ok(None);
+    };
+    let Some((&kind, rest)) = line.split_first() else {
+        return Err(invalid("empty line"));
+    };
+
+    let value = match kind {
+        b'+' => Value::SimpleString(String::from_utf8_lossy(rest).into_owned()),
+        b'-' => Value::Error(String::from_utf8_lossy(rest).into_owned()),
+        b':' => Value::Integer(parse_integer(rest)?),
+        b'$' => match parse_length(rest)? {
+            None => Value::BulkString(None),
+            Some(len) if len > MAX_BULK_LENGTH => return Err(invalid("bulk string too long")),
+            Some(len) => {
+                let mut bytes = vec![0; len + 2];
+                reader.read_exact(&mut bytes)?;
+                if !bytes.ends_with(b"\r\n") {
+                    return Err(invalid("bulk string not terminated by CRLF"));
+                }
+                bytes.truncate(len);
+                Value::BulkString(Some(bytes))
+            },
+        },
+        b'*' => match parse_length(rest)? {
+            None => Value::Array(None),
+            Some(_) if depth == MAX_DEPTH => return Err(invalid("arrays nested too deeply")),
+            Some(len) => {
+                // Don't trust the length for the allocation, it costs nothing to claim a big one
+                let mut values = Vec::with_capacity(len.min(1024));
+                for _ in 0..len {
+                    match read_nested(reader, depth + 1)? {
+                        Some(value) => values.push(value),
+                        None => return Err(ErrorKind::UnexpectedEof.into()),
+                    }
+                }
+                Value::Array(Some(values))
+            },
+        },
+        _ => return Err(invalid(format!("unknown type byte `{}`", kind.escape_ascii()))),
+    };
+    Ok(Some(value))
+}
+
+/// Read a request, which clients send as an array of bulk strings, or None at the end of the
+/// input. Like Redis, also accept an inline command typed as plain words, e.g. from telnet.
+pub fn read_command(reader: &mut impl BufRead) -> io::Result<Option<Vec<Vec<u8>>>> {
+    let inline = match reader.fill_buf()?.first() {
+        None => return Ok(None),
+        Some(&first) => first != b'*',
+    };
+    if inline {
+        let mut line = Vec::new();
+        reader.read_until(b'\n', &mut line)?;
+        let args = line.split(|b| b.is_ascii_whitespace()).filter(|word| !word.is_empty()).map(<[u8]>::to_vec);
+        return Ok(Some(args.collect()));
+    }
+
+    // Read flat rather than through read_value, as there's no nesting in a command for a client
+    // to send arrays in arrays to
+    let header = read_line(reader)?.ok_or(ErrorKind::UnexpectedEof)?;
+    let Some(len) = parse_length(&header[1..])? else {
+        return Err(invalid("expected an array of bulk strings"));
+    };
+    let mut args = Vec::with_capacity(len.min(1024));
+    for _ in 0..len {
+        match reader.fill_buf()?.first() {
+            Some(b'$') => {},
+            Some(_) => return Err(invalid("expected a bulk string")),
+            None => return Err(ErrorKind::UnexpectedEof.into()),
+        }
+        match read_value(reader)? {
+            Some(Value::BulkString(Some(bytes))) => args.push(bytes),
+            _ => return Err(invalid("expected a bulk string")),
+        }
+    }
+    Ok(Some(args))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encode(value: &Value) -> Vec<u8> {
+        let mut out = Vec::new();
+        value.write(&mut out).unwrap();
+        out
+    }
+
+    #[test]
+    fn check_write() {
+        assert_eq!(encode(&Value::ok()), b"+OK\r\n");
+        assert_eq!(encode(&Value::Error("ERR bad".to_string())), b"-ERR bad\r\n");
+        assert_eq!(encode(&Value::Integer(-3)), b":-3\r\n");
+        assert_eq!(encode(&Value::BulkString(None)), b"$-1\r\n");
+        assert_eq!(encode(&Value::bulk("hi")), b"$2\r\nhi\r\n");
+        assert_eq!(
+            encode(&Value::Array(Some(vec![Value::bulk("a"), Value::Integer(1)]))),
+            b"*2\r\n$1\r\na\r\n:1\r\n",
+        );
+    }
+
+    #[test]
+    fn check_read_round_trip() {
+        let values = [
+            Value::SimpleString("PONG".to_string()),
+            Value::Integer(42),
+            Value::bulk(&b"with\r\ninside"[..]),
+            Value::bulk(""),
+            Value::Array(None),
+            Value::Array(Some(vec![Value::BulkString(None), Value::Array(Some(vec![]))])),
+        ];
+        for value in values {
+            let bytes = encode(&value);
+            assert_eq!(read_value(&mut &bytes[..]).unwrap(), Some(value));
+        }
+    }
+
+    #[test]
+    fn check_read_command() {
+        let mut input = &b"*2\r\n$4\r\nECHO\r\n$2\r\nhi\r\nPING  now\r\n"[..];
+        assert_eq!(read_command(&mut input).unwrap(), Some(vec![b"ECHO".to_vec(), b"hi".to_vec()]));
+        assert_eq!(read_command(&mut input).unwrap(), Some(vec![b"PING".to_vec(), b"now".to_vec()]));
+        assert_eq!(read_command(&mut input).unwrap(), None);
+    }
+
+    #[test]
+    fn check_read_invalid() {
+        assert!(read_value(&mut &b"$3\r\nab\r\n"[..]).is_err());
+        assert!(read_value(&mut &b":x\r\n"[..]).is_err());
+        assert!(read_value(&mut &b"*2\r\n:1\r\n"[..]).is_err());
+        assert!(read_value(&mut &b"+OK\n"[..]).is_err());
+        assert!(read_command(&mut &b"*1\r\n:1\r\n"[..]).is_err());
+        assert!(read_command(&mut &b"*1\r\n*1\r\n$1\r\na\r\n"[..]).is_err());
+        assert!(read_command(&mut &b"*-1\r\n"[..]).is_err());
+        // However deep, a reader doesn't go down with it
+        let deep = b"*1\r\n".repeat(2_000_000);
+        assert_eq!(read_command(&mut &deep[..]).unwrap_err().to_string(), "expected a bulk string");
+        assert_eq!(read_value(&mut &deep[..]).unwrap_err().to_string(), "arrays nested too deeply");
+        let nested = format!("{}:1\r\n", "*1\r\n".repeat(MAX_DEPTH));
+        assert!(read_value(&mut nested.as_bytes()).is_ok());
+    }
+}
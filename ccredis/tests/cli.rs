@@ -0,0 +1,74 @@
+use cc_tests::Server;
+use redis::{Commands, Connection};
+use std::process::Command;
+use std::thread;
+use std::time::Duration;
+
+/// A ccredis process listening on a free port
+fn start() -> Server {
+    Server::start(Command::new(env!("CARGO_BIN_EXE_ccredis")))
+}
+
+fn connect(server: &Server) -> Connection {
+    redis::Client::open(format!("redis://{}/", server.address)).unwrap().get_connection().unwrap()
+}
+
+#[test]
+fn run_redis_ping_echo() {
+    let server = start();
+    let mut con = connect(&server);
+
+    let pong: String = redis::cmd("PING").query(&mut con).unwrap();
+    assert_eq!(pong, "PONG");
+    let echo: String = redis::cmd("ECHO").arg("hello world").query(&mut con).unwrap();
+    assert_eq!(echo, "hello world");
+}
+
+#[test]
+fn run_redis_set_get_del_exists() {
+    let server = start();
+    let mut con = connect(&server);
+
+    let () = con.set("name", "ccredis").unwrap();
+    let name: Option<String> = con.get("name").unwrap();
+    assert_eq!(name.as_deref(), Some("ccredis"));
+    assert_eq!(con.exists::<_, usize>(&["name", "missing"]).unwrap(), 1);
+    assert_eq!(con.del::<_, usize>(&["name", "missing"]).unwrap(), 1);
+    assert_eq!(con.get::<_, Option<String>>("name").unwrap(), None);
+}
+
+#[test]
+fn run_redis_expiry() {
+    let server = start();
+    let mut con = connect(&server);
+
+    let () = redis::cmd("SET").arg("short").arg("v").arg("PX").arg(50).query(&mut con).unwrap();
+    let () = redis::cmd("SET").arg("long").arg("v").arg("EX").arg(100).query(&mut con).unwrap();
+    assert!(con.exists::<_, bool>("short").unwrap());
+    thread::sleep(Duration::from_millis(100));
+    assert!(!con.exists::<_, bool>("short").unwrap());
+    assert!(con.exists::<_, bool>("long").unwrap());
+}
+
+#[test]
+fn run_redis_clients_share_keys() {
+    let server = start();
+    let mut first = connect(&server);
+    let mut second = connect(&server);
+
+    let () = first.set("shared", 7).unwrap();
+    assert_eq!(second.get::<_, i64>("shared").unwrap(), 7);
+}
+
+#[test]
+fn run_redis_pipeline_and_errors() {
+    let server = start();
+    let mut con = connect(&server);
+
+    let (ok, value): (String, String) =
+        redis::pipe().cmd("SET").arg("k").arg("v").cmd("GET").arg("k").query(&mut con).unwrap();
+    assert_eq!((ok.as_str(), value.as_str()), ("OK", "v"));
+
+    let err = redis::cmd("NOSUCHCOMMAND").query::<()>(&mut con).unwrap_err();
+    assert!(err.to_string().contains("unknown command 'nosuchcommand'"), "{}", err);
+}
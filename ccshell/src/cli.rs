@@ -0,0 +1,75 @@
+use crate::Shell;
+use clap::Parser;
+use std::ffi::OsString;
+use std::fs::File;
+use std::io::{stdin, stdout, BufRead, BufReader, IsTerminal, Write};
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+const PROMPT: &str = "ccsh> ";
+
+/// shell - run commands typed in, or from a script
+#[derive(Parser)]
+struct Cli {
+    /// Run this command line and exit
+    #[arg(short = 'c', value_name = "COMMAND", conflicts_with = "script")]
+    command: Option<String>,
+
+    /// Read commands from this file instead of stdin
+    script: Option<PathBuf>,
+}
+
+/// Run ccshell with the given command line, `args[0]` being the program name
+pub fn main<I, T>(args: I) -> ExitCode
+where
+    I: IntoIterator<Item = T>,
+    T: Into<OsString> + Clone,
+{
+    let args: Cli = cc_cli::parse_from(args, env!("CARGO_PKG_VERSION"));
+    let mut shell = Shell::new();
+
+    if let Some(command) = args.command {
+        shell.run_line(&command);
+        return exit_code(shell.exit.unwrap_or(shell.status));
+    }
+
+    let (reader, interactive): (Box<dyn BufRead>, bool) = match &args.script {
+        Some(path) => match File::open(path) {
+            Ok(file) => (Box::new(BufReader::new(file)), false),
+            Err(e) => {
+                eprintln!("ccshell: {}: {}", path.display(), e);
+                return exit_code(crate::NOT_FOUND_STATUS);
+            },
+        },
+        None => (Box::new(stdin().lock()), stdin().is_terminal()),
+    };
+
+    let mut lines = reader.lines();
+    while shell.exit.is_none() {
+        if interactive {
+            print!("{}", PROMPT);
+            let _ = stdout().flush();
+        }
+        match lines.next() {
+            Some(Ok(line)) => {
+                shell.run_line(&line);
+            },
+            Some(Err(e)) => {
+                eprintln!("ccshell: {}", e);
+                return ExitCode::FAILURE;
+            },
+            None => break,
+        }
+    }
+
+    // Leave the prompt line tidy when the user ends input with Ctrl-D
+    if interactive && shell.exit.is_none() {
+        println!();
+    }
+    exit_code(shell.exit.unwrap_or(shell.status))
+}
+
+/// Statuses cover 0 to 255, like the ones a process can exit with
+fn exit_code(status: i32) -> ExitCode {
+    ExitCode::from(status as u8)
+}
@@ -0,0 +1,328 @@
+// Basic Unix shell
+// Runs pipelines of programs with quoting and file redirection, plus a few built-in commands
+// that have to run inside the shell itself, like cd. Parsing lives in `parse`, and executing
+// the parsed pipelines in `Shell`, so built-ins can be added to `BUILTINS` without touching
+// either.
+use std::fs::{File, OpenOptions};
+use std::io::{self, ErrorKind, Write};
+use std::path::PathBuf;
+use std::process::{self, ChildStdout, ExitStatus, Stdio};
+use std::thread;
+
+mod cli;
+pub mod parse;
+
+pub use cli::main;
+pub use parse::{parse, Command, Output, ParseError, Pipeline};
+
+/// Exit status for a line that didn't parse, like bash gives for a syntax error
+pub const SYNTAX_ERROR_STATUS: i32 = 2;
+
+/// Exit statuses for a program that couldn't be run, as POSIX shells give
+pub const NOT_EXECUTABLE_STATUS: i32 = 126;
+pub const NOT_FOUND_STATUS: i32 = 127;
+
+/// A command run inside the shell. It gets the arguments after its name and writes to `out`,
+/// returning its exit status.
+type Builtin = fn(&mut Shell, &[String], &mut dyn Write) -> io::Result<i32>;
+
+const BUILTINS: &[(&str, Builtin)] = &[("cd", cd), ("exit", exit), ("pwd", pwd)];
+
+fn find_builtin(name: &str) -> Option<Builtin> {
+    BUILTINS.iter().find(|(builtin, _)| *builtin == name).map(|(_, run)| *run)
+}
+
+/// The state kept between commands
+#[derive(Debug, Default)]
+pub struct Shell {
+    /// The status of the last pipeline run
+    pub status: i32,
+    /// Set by `exit`, to the status the shell should exit with
+    pub exit: Option<i32>,
+    previous_dir: Option<PathBuf>,
+}
+
+/// Where a command in a pipeline reads from
+enum Input {
+    Inherit,
+    File(File),
+    Pipe(ChildStdout),
+    /// Output already produced by a built-in, fed in from a thread
+    Bytes(Vec<u8>),
+}
+
+/// Where a command in a pipeline writes to
+enum Target {
+    Inherit,
+    File(File),
+    Pipe,
+}
+
+impl Shell {
+    pub fn new() -> Shell {
+        Shell::default()
+    }
+
+    /// Parse and run a line, reporting problems on stderr, and return its status
+    pub fn run_line(&mut self, line: &str) -> i32 {
+        match parse(line) {
+            Ok(pipelines) => {
+                for pipeline in &pipelines {
+                    self.status = self.execute(pipeline);
+                    if self.exit.is_some() {
+                        break;
+                    }
+                }
+            },
+            Err(e) => {
+                eprintln!("ccshell: {}", e);
+                self.status = SYNTAX_ERROR_STATUS;
+            },
+        }
+        self.status
+    }
+
+    /// Run a pipeline, returning the status of its last command
+    pub fn execute(&mut self, pipeline: &Pipeline) -> i32 {
+        let mut input = Input::Inherit;
+        let mut children = Vec::new();
+        let mut feeders = Vec::new();
+        let mut status = 0;
+        // Whether the last command is a program, whose status comes from waiting for it
+        let mut wait_for_status = false;
+
+        for (i, command) in pipeline.commands.iter().enumerate() {
+            let last = i + 1 == pipeline.commands.len();
+            let piped_input = std::mem::replace(&mut input, Input::Bytes(Vec::new()));
+
+            let redirected = open_redirections(command).map(|(file_input, file_output)| {
+                let input = file_input.map_or(piped_input, Input::File);
+                let target = match file_output {
+                    Some(file) => Target::File(file),
+                    None if last => Target::Inherit,
+                    None => Target::Pipe,
+                };
+                (input, target)
+            });
+            let (stage_input, target) = match redirected {
+                Ok(redirected) => redirected,
+                Err(e) => {
+                    eprintln!("ccshell: {}", e);
+                    status = 1;
+                    continue;
+                },
+            };
+
+            let name = &command.args[0];
+            wait_for_status = false;
+            status = match find_builtin(name) {
+                Some(builtin) => {
+                    let (result, output) = match target {
+                        Target::Inherit => (builtin(self, &command.args[1..], &mut io::stdout().lock()), Vec::new()),
+                        Target::File(mut file) => (builtin(self, &command.args[1..], &mut file), Vec::new()),
+                        Target::Pipe => {
+                            let mut output = Vec::new();
+                            (builtin(self, &command.args[1..], &mut output), output)
+                        },
+                    };
+                    input = Input::Bytes(output);
+                    result.unwrap_or_else(|e| {
+                        eprintln!("ccshell: {}: {}", name, e);
+                        1
+                    })
+                },
+                None => match spawn(command, stage_input, target) {
+                    Ok((mut child, feeder)) => {
+                        if let Some(stdout) = child.stdout.take() {
+                            input = Input::Pipe(stdout);
+                        }
+                        feeders.extend(feeder);
+                        children.push(child);
+                        wait_for_status = last;
+                        0
+                    },
+                    Err(e) => {
+                        eprintln!("ccshell: {}: {}", name, describe(&e));
+                        match e.kind() {
+                            ErrorKind::NotFound => NOT_FOUND_STATUS,
+                            _ => NOT_EXECUTABLE_STATUS,
+                        }
+                    },
+                },
+            };
+        }
+
+        let mut waited = None;
+        for mut child in children {
+            waited = Some(child.wait());
+        }
+        if let (true, Some(result)) = (wait_for_status, waited) {
+            status = match result {
+                Ok(exit_status) => exit_code(exit_status),
+                Err(e) => {
+                    eprintln!("ccshell: {}", e);
+                    1
+                },
+            };
+        }
+        for feeder in feeders {
+            let _ = feeder.join();
+        }
+        status
+    }
+}
+
+/// Start a program, with a thread feeding it any input that came from a built-in
+fn spawn(command: &Command, input: Input, target: Target) -> io::Result<(process::Child, Option<thread::JoinHandle<()>>)> {
+    let mut process = process::Command::new(&command.args[0]);
+    process.args(&command.args[1..]);
+
+    let mut bytes = None;
+    match input {
+        Input::Inherit => {},
+        Input::File(file) => {
+            process.stdin(file);
+        },
+        Input::Pipe(stdout) => {
+            process.stdin(stdout);
+        },
+        Input::Bytes(data) => {
+            process.stdin(Stdio::piped());
+            bytes = Some(data);
+        },
+    }
+    match target {
+        Target::Inherit => {},
+        Target::File(file) => {
+            process.stdout(file);
+        },
+        Target::Pipe => {
+            process.stdout(Stdio::piped());
+        },
+    }
+
+    let mut child = process.spawn()?;
+    // Writing from a thread means a program that doesn't read all of it can't leave the shell stuck
+    let feeder = match (bytes, child.stdin.take()) {
+        (Some(data), Some(mut stdin)) => Some(thread::spawn(move || {
+            let _ = stdin.write_all(&data);
+        })),
+        _ => None,
+    };
+    Ok((child, feeder))
+}
+
+/// Open the files a command redirects its input and output to
+fn open_redirections(command: &Command) -> io::Result<(Option<File>, Option<File>)> {
+    let input = match &command.input {
+        Some(path) => Some(File::open(path).map_err(|e| with_path(e, path))?),
+        None => None,
+    };
+    let output = match &command.output {
+        Some(Output { path, append }) => {
+            let mut options = OpenOptions::new();
+            options.create(true);
+            match append {
+                true => options.append(true),
+                false => options.write(true).truncate(true),
+            };
+            Some(options.open(path).map_err(|e| with_path(e, path))?)
+        },
+        None => None,
+    };
+    Ok((input, output))
+}
+
+fn with_path(e: io::Error, path: &std::path::Path) -> io::Error {
+    io::Error::new(e.kind(), format!("{}: {}", path.display(), describe(&e)))
+}
+
+/// An error message without the `(os error 2)` the standard library adds
+fn describe(e: &io::Error) -> String {
+    match e.kind() {
+        ErrorKind::NotFound => "not found".to_string(),
+        ErrorKind::PermissionDenied => "permission denied".to_string(),
+        _ => e.to_string(),
+    }
+}
+
+/// A program's exit status as a shell reports it, with death by a signal as 128 plus the signal
+fn exit_code(status: ExitStatus) -> i32 {
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::ExitStatusExt;
+        if let Some(signal) = status.signal() {
+            return 128 + signal;
+        }
+    }
+    status.code().unwrap_or(1)
+}
+
+/// `cd [DIR]`, going home without a directory, or back to the previous one with `-`
+fn cd(shell: &mut Shell, args: &[String], out: &mut dyn Write) -> io::Result<i32> {
+    let dir = match args {
+        [] => match std::env::var_os("HOME") {
+            Some(home) => PathBuf::from(home),
+            None => return Err(io::Error::other("HOME not set")),
+        },
+        [dir] if dir == "-" => match &shell.previous_dir {
+            Some(previous) => {
+                writeln!(out, "{}", previous.display())?;
+                previous.clone()
+            },
+            None => return Err(io::Error::other("no previous directory")),
+        },
+        [dir] => PathBuf::from(dir),
+        _ => return Err(io::Error::other("too many arguments")),
+    };
+
+    let current = std::env::current_dir().ok();
+    std::env::set_current_dir(&dir).map_err(|e| with_path(e, &dir))?;
+    shell.previous_dir = current;
+    Ok(0)
+}
+
+/// `exit [STATUS]`, with the status of the last command by default
+fn exit(shell: &mut Shell, args: &[String], _: &mut dyn Write) -> io::Result<i32> {
+    let status = match args {
+        [] => shell.status,
+        [status] => status.parse().map_err(|_| io::Error::other(format!("{}: numeric argument required", status)))?,
+        _ => return Err(io::Error::other("too many arguments")),
+    };
+    shell.exit = Some(status);
+    Ok(status)
+}
+
+fn pwd(_: &mut Shell, _: &[String], out: &mut dyn Write) -> io::Result<i32> {
+    writeln!(out, "{}", std::env::current_dir()?.display())?;
+    Ok(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_exit_status() {
+        let mut shell = Shell::new();
+        assert_eq!(shell.run_line("true"), 0);
+        assert_eq!(shell.run_line("false"), 1);
+        assert_eq!(shell.run_line("false | true"), 0);
+        assert_eq!(shell.run_line("true | false"), 1);
+        assert_eq!(shell.run_line("sh -c 'exit 3'"), 3);
+        assert_eq!(shell.run_line("no-such-program-here"), NOT_FOUND_STATUS);
+        assert_eq!(shell.run_line("echo 'oops"), SYNTAX_ERROR_STATUS);
+    }
+
+    #[test]
+    fn check_exit_builtin() {
+        let mut shell = Shell::new();
+        shell.run_line("false; exit; true");
+        assert_eq!(shell.exit, Some(1));
+
+        let mut shell = Shell::new();
+        shell.run_line("exit 7");
+        assert_eq!(shell.exit, Some(7));
+        assert_eq!(shell.run_line("exit abc"), 1);
+    }
+}
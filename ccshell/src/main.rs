@@ -0,0 +1,3 @@
+fn main() -> std::process::ExitCode {
+    ccshell::main(std::env::args_os())
+}
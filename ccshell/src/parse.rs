@@ -0,0 +1,251 @@
+// Command line parser.
+// A line is split into words and operators, honouring quotes and backslashes, then grouped into
+// pipelines of commands, each with its own redirections, e.g.
+//
+//   grep -i "hello world" < notes.txt | sort > sorted.txt; cd ..
+//
+// is a pipeline of grep and sort reading notes.txt and writing sorted.txt, followed by cd.
+use std::fmt;
+use std::path::PathBuf;
+
+#[derive(Debug, PartialEq)]
+pub struct ParseError(String);
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Word(String),
+    Pipe,
+    RedirectIn,
+    RedirectOut,
+    Append,
+    Semicolon,
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Token::Word(word) => write!(f, "`{}`", word),
+            Token::Pipe => f.write_str("`|`"),
+            Token::RedirectIn => f.write_str("`<`"),
+            Token::RedirectOut => f.write_str("`>`"),
+            Token::Append => f.write_str("`>>`"),
+            Token::Semicolon => f.write_str("`;`"),
+        }
+    }
+}
+
+fn tokenize(line: &str) -> Result<Vec<Token>, ParseError> {
+    let mut tokens = Vec::new();
+    let mut iter = line.chars().peekable();
+    // A word can be built from several quoted and unquoted pieces, e.g. `--name="a b"`, and
+    // quotes make a word even when they hold nothing, e.g. `''`
+    let mut word = String::new();
+    let mut in_word = false;
+
+    while let Some(ch) = iter.next() {
+        let operator = match ch {
+            '|' => Some(Token::Pipe),
+            '<' => Some(Token::RedirectIn),
+            '>' if iter.peek() == Some(&'>') => {
+                iter.next();
+                Some(Token::Append)
+            },
+            '>' => Some(Token::RedirectOut),
+            ';' => Some(Token::Semicolon),
+            _ => None,
+        };
+        if operator.is_some() || ch.is_whitespace() || (ch == '#' && !in_word) {
+            if in_word {
+                tokens.push(Token::Word(std::mem::take(&mut word)));
+                in_word = false;
+            }
+            match operator {
+                Some(operator) => tokens.push(operator),
+                None if ch == '#' => break,
+                None => {},
+            }
+            continue;
+        }
+
+        in_word = true;
+        match ch {
+            '\'' => loop {
+                match iter.next() {
+                    Some('\'') => break,
+                    Some(c) => word.push(c),
+                    None => return Err(ParseError("unterminated single quote".to_string())),
+                }
+            },
+            '"' => loop {
+                match iter.next() {
+                    Some('"') => break,
+                    // Inside double quotes a backslash only escapes the characters that would
+                    // otherwise be special there
+                    Some('\\') if matches!(iter.peek(), Some('"' | '\\' | '$' | '`')) => {
+                        word.push(iter.next().unwrap());
+                    },
+                    Some(c) => word.push(c),
+                    None => return Err(ParseError("unterminated double quote".to_string())),
+                }
+            },
+            '\\' => match iter.next() {
+                Some(c) => word.push(c),
+                None => return Err(ParseError("nothing to escape after `\\`".to_string())),
+            },
+            _ => word.push(ch),
+        }
+    }
+
+    if in_word {
+        tokens.push(Token::Word(word));
+    }
+    Ok(tokens)
+}
+
+/// Where a command's output goes instead of its standard output
+#[derive(Clone, Debug, PartialEq)]
+pub struct Output {
+    pub path: PathBuf,
+    /// `>>` rather than `>`
+    pub append: bool,
+}
+
+/// One program and its arguments, with the files replacing its stdin and stdout
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Command {
+    pub args: Vec<String>,
+    pub input: Option<PathBuf>,
+    pub output: Option<Output>,
+}
+
+/// Commands joined by `|`, each reading the output of the one before
+#[derive(Clone, Debug, PartialEq)]
+pub struct Pipeline {
+    pub commands: Vec<Command>,
+}
+
+/// Parse a line into the pipelines separated by `;` in it, which is none for a blank line or a
+/// comment
+pub fn parse(line: &str) -> Result<Vec<Pipeline>, ParseError> {
+    let mut pipelines = Vec::new();
+    let mut commands = Vec::new();
+    let mut command = Command::default();
+    let mut tokens = tokenize(line)?.into_iter().peekable();
+
+    while let Some(token) = tokens.next() {
+        match token {
+            Token::Word(word) => command.args.push(word),
+            Token::RedirectIn | Token::RedirectOut | Token::Append => {
+                let path = match tokens.next() {
+                    Some(Token::Word(path)) => PathBuf::from(path),
+                    Some(other) => {
+                        return Err(ParseError(format!("expected a file name after {}, found {}", token, other)));
+                    },
+                    None => return Err(ParseError(format!("expected a file name after {}", token))),
+                };
+                match token {
+                    Token::RedirectIn => command.input = Some(path),
+                    _ => command.output = Some(Output { path, append: token == Token::Append }),
+                }
+            },
+            Token::Pipe | Token::Semicolon => {
+                if command.args.is_empty() {
+                    return Err(ParseError(format!("expected a command before {}", token)));
+                }
+                commands.push(std::mem::take(&mut command));
+                if token == Token::Semicolon {
+                    pipelines.push(Pipeline { commands: std::mem::take(&mut commands) });
+                } else if tokens.peek().is_none_or(|next| *next == Token::Semicolon) {
+                    return Err(ParseError("expected a command after `|`".to_string()));
+                }
+            },
+        }
+    }
+
+    if !command.args.is_empty() {
+        commands.push(command);
+    } else if command != Command::default() {
+        return Err(ParseError("expected a command to redirect".to_string()));
+    }
+    if !commands.is_empty() {
+        pipelines.push(Pipeline { commands });
+    }
+    Ok(pipelines)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn words(line: &str) -> Vec<String> {
+        let pipelines = parse(line).unwrap();
+        assert_eq!(pipelines.len(), 1);
+        pipelines[0].commands[0].args.clone()
+    }
+
+    #[test]
+    fn check_quotes_and_escapes() {
+        assert_eq!(words("echo  hello   world"), ["echo", "hello", "world"]);
+        assert_eq!(words(r#"echo 'a  "b"' "c 'd' \"e\"" f\ g"#), ["echo", "a  \"b\"", "c 'd' \"e\"", "f g"]);
+        assert_eq!(words(r#"echo --name="a b"'c' '' "\n""#), ["echo", "--name=a bc", "", "\\n"]);
+        assert_eq!(words("echo 'a|b;c' \\>"), ["echo", "a|b;c", ">"]);
+    }
+
+    #[test]
+    fn check_comments() {
+        assert_eq!(words("echo a#b # the rest"), ["echo", "a#b"]);
+        assert_eq!(parse("  # nothing here").unwrap(), []);
+        assert_eq!(parse("").unwrap(), []);
+    }
+
+    #[test]
+    fn check_pipelines_and_redirections() {
+        let pipelines = parse("sort < in.txt | uniq -c >> out.txt; cd /tmp").unwrap();
+        assert_eq!(pipelines, [
+            Pipeline {
+                commands: vec![
+                    Command {
+                        args: vec!["sort".to_string()],
+                        input: Some(PathBuf::from("in.txt")),
+                        output: None,
+                    },
+                    Command {
+                        args: vec!["uniq".to_string(), "-c".to_string()],
+                        input: None,
+                        output: Some(Output { path: PathBuf::from("out.txt"), append: true }),
+                    },
+                ],
+            },
+            Pipeline {
+                commands: vec![Command { args: vec!["cd".to_string(), "/tmp".to_string()], ..Command::default() }],
+            },
+        ]);
+    }
+
+    #[test]
+    fn check_redirection_without_spaces() {
+        let pipelines = parse("ls>out.txt").unwrap();
+        let command = &pipelines[0].commands[0];
+        assert_eq!(command.args, ["ls"]);
+        assert_eq!(command.output, Some(Output { path: PathBuf::from("out.txt"), append: false }));
+    }
+
+    #[test]
+    fn check_errors() {
+        assert_eq!(parse("echo 'oops").unwrap_err(), ParseError("unterminated single quote".to_string()));
+        assert_eq!(parse("echo \"oops").unwrap_err(), ParseError("unterminated double quote".to_string()));
+        assert_eq!(parse("| ls").unwrap_err(), ParseError("expected a command before `|`".to_string()));
+        assert_eq!(parse("ls |").unwrap_err(), ParseError("expected a command after `|`".to_string()));
+        assert_eq!(parse("ls >").unwrap_err(), ParseError("expected a file name after `>`".to_string()));
+        assert_eq!(parse("ls > | x").unwrap_err(), ParseError("expected a file name after `>`, found `|`".to_string()));
+        assert_eq!(parse("< in.txt").unwrap_err(), ParseError("expected a command to redirect".to_string()));
+    }
+}
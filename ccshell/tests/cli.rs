@@ -0,0 +1,64 @@
+use assert_cmd::Command;
+use std::path::PathBuf;
+
+fn ccshell() -> Command {
+    Command::cargo_bin("ccshell").unwrap()
+}
+
+/// A fresh directory for a test to write files in
+fn scratch_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("ccshell-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir(&dir).unwrap();
+    dir
+}
+
+#[test]
+fn run_shell_command_with_quotes() {
+    ccshell().args(["-c", r#"echo "hello   world" 'it''s' a\ b"#]).assert().success().stdout("hello   world its a b\n");
+}
+
+#[test]
+fn run_shell_pipeline() {
+    ccshell().args(["-c", "printf 'b\\na\\nb\\n' | sort | uniq -c | tr -s ' '"]).assert().success().stdout(" 1 a\n 2 b\n");
+}
+
+#[test]
+fn run_shell_redirection() {
+    let dir = scratch_dir("redirect");
+    ccshell()
+        .current_dir(&dir)
+        .args(["-c", "echo one > out.txt; echo two >> out.txt; tr a-z A-Z < out.txt"])
+        .assert()
+        .success()
+        .stdout("ONE\nTWO\n");
+    assert_eq!(std::fs::read_to_string(dir.join("out.txt")).unwrap(), "one\ntwo\n");
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn run_shell_cd_and_builtin_in_pipeline() {
+    let dir = scratch_dir("cd");
+    std::fs::create_dir(dir.join("sub")).unwrap();
+    let sub = dir.join("sub").canonicalize().unwrap();
+    ccshell()
+        .current_dir(&dir)
+        .write_stdin("cd sub\npwd | cat\n")
+        .assert()
+        .success()
+        .stdout(format!("{}\n", sub.display()));
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn run_shell_exit_status() {
+    ccshell().args(["-c", "sh -c 'exit 4'"]).assert().code(4);
+    ccshell().write_stdin("true\nexit 3\necho unreachable\n").assert().code(3).stdout("");
+    ccshell().args(["-c", "no-such-program-here"]).assert().code(127).stderr("ccshell: no-such-program-here: not found\n");
+}
+
+#[test]
+fn run_shell_errors() {
+    ccshell().args(["-c", "echo 'open"]).assert().code(2).stderr("ccshell: unterminated single quote\n");
+    ccshell().args(["-c", "cat < missing.txt"]).assert().code(1).stderr("ccshell: missing.txt: not found\n");
+}
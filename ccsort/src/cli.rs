@@ -0,0 +1,151 @@
+use crate::{sort, Key, SortOptions, DEFAULT_BUFFER_SIZE};
+use anyhow::{bail, Context, Result};
+use cc_cli::{is_broken_pipe, BROKEN_PIPE_EXIT_CODE};
+use cc_io::Source;
+use clap::Parser;
+use std::ffi::OsString;
+use std::fs::File;
+use std::io::{stdout, BufRead};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+/// sort - sort lines of text
+#[derive(Parser)]
+struct Cli {
+    /// Compare by the number at the start of each line or key
+    #[arg(short, long = "numeric-sort")]
+    numeric: bool,
+
+    /// Reverse the order
+    #[arg(short, long)]
+    reverse: bool,
+
+    /// Print only the first of each run of equal lines
+    #[arg(short, long)]
+    unique: bool,
+
+    /// Sort on the fields from START to END, e.g. 2 or 2,3. Either can be followed by n or r, as in
+    /// 2,2nr, to sort that key numerically or in reverse in place of -n and -r. Can be given more
+    /// than once, with later keys breaking ties in earlier ones
+    #[arg(short, long = "key", value_name = "START[,END]")]
+    keys: Vec<Key>,
+
+    /// Fields are separated by this character instead of runs of blanks
+    #[arg(short = 't', long = "field-separator", value_name = "SEP", value_parser = parse_separator)]
+    separator: Option<u8>,
+
+    /// Memory to use before spilling sorted runs to disk, e.g. 500K, 64M, or 1G
+    #[arg(short = 'S', long, value_name = "SIZE", value_parser = parse_size, default_value_t = DEFAULT_BUFFER_SIZE)]
+    buffer_size: usize,
+
+    /// Write spilled runs here instead of the system temporary directory
+    #[arg(short = 'T', long, value_name = "DIR")]
+    temporary_directory: Option<PathBuf>,
+
+    /// Write the result to this file instead of stdout. It can be one of the inputs
+    #[arg(short, long, value_name = "FILE")]
+    output: Option<PathBuf>,
+
+    /// The files to sort, or stdin if there are none
+    files: Vec<PathBuf>,
+}
+
+fn parse_separator(value: &str) -> Result<u8> {
+    match value.as_bytes() {
+        [separator] => Ok(*separator),
+        _ => bail!("the separator must be a single byte"),
+    }
+}
+
+/// Parse a size like `500K`, `64M`, or `1G`. A bare number is taken as bytes.
+fn parse_size(value: &str) -> Result<usize> {
+    let split = value.find(|c: char| !c.is_ascii_digit()).unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+    let number: usize = number.parse().with_context(|| format!("invalid size `{}`", value))?;
+
+    let multiplier: usize = match unit {
+        "" | "b" | "B" => 1,
+        "k" | "K" => 1 << 10,
+        "m" | "M" => 1 << 20,
+        "g" | "G" => 1 << 30,
+        _ => bail!("unknown unit `{}` in size `{}`, expected K, M, or G", unit, value),
+    };
+
+    number.checked_mul(multiplier).with_context(|| format!("size `{}` is too big", value))
+}
+
+/// Run ccsort with the given command line, `args[0]` being the program name
+pub fn main<I, T>(args: I) -> ExitCode
+where
+    I: IntoIterator<Item = T>,
+    T: Into<OsString> + Clone,
+{
+    let args: Cli = cc_cli::parse_from(args, env!("CARGO_PKG_VERSION"));
+
+    match run(args) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) if is_broken_pipe(&e) => ExitCode::from(BROKEN_PIPE_EXIT_CODE),
+        Err(e) => {
+            eprintln!("Error: {:?}", e);
+            ExitCode::FAILURE
+        },
+    }
+}
+
+fn run(args: Cli) -> Result<()> {
+    let options = SortOptions {
+        numeric: args.numeric,
+        reverse: args.reverse,
+        unique: args.unique,
+        keys: args.keys,
+        separator: args.separator,
+        buffer_size: args.buffer_size,
+        temp_dir: args.temporary_directory.unwrap_or_else(std::env::temp_dir),
+    };
+
+    let mut sources: Vec<Source> = args.files.into_iter().map(Source::from).collect();
+    if sources.is_empty() {
+        sources.push(Source::Stdin);
+    }
+    let inputs = sources
+        .iter()
+        .map(|source| Ok(cc_io::open(source)?.reader))
+        .collect::<Result<Vec<Box<dyn BufRead>>>>()?;
+
+    match args.output {
+        None => sort(inputs, &options, stdout().lock()).context("could not sort"),
+        Some(path) => sort_into_file(inputs, &options, &path),
+    }
+}
+
+/// Sort into a temporary file beside `path` and rename it into place, so `path` can also be one
+/// of the inputs
+fn sort_into_file(inputs: Vec<Box<dyn BufRead>>, options: &SortOptions, path: &Path) -> Result<()> {
+    let name = path.file_name().map(|n| n.to_string_lossy()).unwrap_or_default();
+    let temp = path.with_file_name(format!(".{}.{}.tmp", name, std::process::id()));
+
+    let file = File::create(&temp).with_context(|| format!("could not create `{}`", temp.display()))?;
+    let result = sort(inputs, options, file)
+        .context("could not sort")
+        .and_then(|()| {
+            std::fs::rename(&temp, path).with_context(|| format!("could not write `{}`", path.display()))
+        });
+    if result.is_err() {
+        let _ = std::fs::remove_file(&temp);
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_parse_size() {
+        assert_eq!(parse_size("100").unwrap(), 100);
+        assert_eq!(parse_size("2K").unwrap(), 2048);
+        assert_eq!(parse_size("64M").unwrap(), 64 << 20);
+        assert!(parse_size("5T").is_err());
+        assert!(parse_size("M").is_err());
+    }
+}
@@ -0,0 +1,523 @@
+// Sorting core of ccsort, usable without the command line interface.
+// Lines are sorted in memory a chunk at a time. Input too big for one chunk is written out as
+// sorted runs in temporary files, which are then merged, so the whole input never has to fit in
+// memory at once.
+// My answer to:  https://codingchallenges.substack.com/p/coding-challenge-6
+use std::cmp::Ordering;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering as AtomicOrdering};
+
+mod cli;
+
+pub use cli::main;
+
+/// Memory used for lines before a sorted run is spilled to disk
+pub const DEFAULT_BUFFER_SIZE: usize = 64 * 1024 * 1024;
+
+/// Most runs merged at once, to stay well clear of the limit on open files
+const MERGE_WIDTH: usize = 32;
+
+/// Rough cost of keeping a line in memory beyond its bytes
+const LINE_OVERHEAD: usize = std::mem::size_of::<Vec<u8>>();
+
+/// Sort on the fields from `start` to `end`, 1-based and inclusive, as given to `-k`. No end
+/// means to the end of the line.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Key {
+    pub start: usize,
+    pub end: Option<usize>,
+    /// Compare this key by number. Like sort(1), a key with a modifier of its own ignores the
+    /// global `numeric` and `reverse`.
+    pub numeric: bool,
+    pub reverse: bool,
+}
+
+impl Key {
+    fn has_modifiers(&self) -> bool {
+        self.numeric || self.reverse
+    }
+}
+
+impl FromStr for Key {
+    type Err = String;
+
+    /// Parse `START[,END]`, either of which can be followed by the modifiers `n` and `r`
+    fn from_str(s: &str) -> Result<Key, String> {
+        let mut key = Key::default();
+        let mut field = |position: &str| {
+            let digits = position.find(|c: char| !c.is_ascii_digit()).unwrap_or(position.len());
+            let (number, modifiers) = position.split_at(digits);
+            for modifier in modifiers.chars() {
+                match modifier {
+                    'n' => key.numeric = true,
+                    'r' => key.reverse = true,
+                    _ => return Err(format!("invalid key `{}`, unknown modifier `{}`", s, modifier)),
+                }
+            }
+            match number.parse::<usize>() {
+                Ok(0) => Err("fields are numbered from 1".to_string()),
+                Ok(n) => Ok(n),
+                Err(_) => Err(format!("invalid key `{}`, expected START or START,END", s)),
+            }
+        };
+
+        let (start, end) = match s.split_once(',') {
+            Some((start, end)) => (field(start)?, Some(field(end)?)),
+            None => (field(s)?, None),
+        };
+        if end.is_some_and(|end| end < start) {
+            return Err(format!("invalid key `{}`, it ends before it starts", s));
+        }
+        Ok(Key { start, end, ..key })
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct SortOptions {
+    /// Compare by the number at the start of the key rather than byte by byte
+    pub numeric: bool,
+    pub reverse: bool,
+    /// Print only the first of a run of lines that compare equal
+    pub unique: bool,
+    /// Compare on these keys in turn, or the whole line when there are none
+    pub keys: Vec<Key>,
+    /// Separates fields for `keys`. By default fields are separated by runs of blanks.
+    pub separator: Option<u8>,
+    /// How much input to hold in memory before spilling a sorted run to disk
+    pub buffer_size: usize,
+    /// Where spilled runs are written
+    pub temp_dir: PathBuf,
+}
+
+impl Default for SortOptions {
+    fn default() -> Self {
+        SortOptions {
+            numeric: false,
+            reverse: false,
+            unique: false,
+            keys: Vec::new(),
+            separator: None,
+            buffer_size: DEFAULT_BUFFER_SIZE,
+            temp_dir: std::env::temp_dir(),
+        }
+    }
+}
+
+impl SortOptions {
+    /// Order two lines. Lines whose keys are equal are ordered byte by byte as a last resort,
+    /// except with `unique`, where they count as duplicates.
+    pub fn compare(&self, a: &[u8], b: &[u8]) -> Ordering {
+        let mut ordering = if self.keys.is_empty() {
+            self.compare_keys(&Key::default(), a, b)
+        } else {
+            self.keys
+                .iter()
+                .map(|key| {
+                    let ordering = self.compare_keys(key, self.key(key, a), self.key(key, b));
+                    // Global reverse is applied below, to the whole comparison
+                    match key.has_modifiers() && key.reverse != self.reverse {
+                        true => ordering.reverse(),
+                        false => ordering,
+                    }
+                })
+                .find(|ordering| ordering.is_ne())
+                .unwrap_or(Ordering::Equal)
+        };
+
+        if ordering.is_eq() && !self.unique {
+            ordering = a.cmp(b);
+        }
+        if self.reverse {
+            ordering.reverse()
+        } else {
+            ordering
+        }
+    }
+
+    fn compare_keys(&self, key: &Key, a: &[u8], b: &[u8]) -> Ordering {
+        let numeric = match key.has_modifiers() {
+            true => key.numeric,
+            false => self.numeric,
+        };
+        if numeric {
+            numeric_value(a).total_cmp(&numeric_value(b))
+        } else {
+            a.cmp(b)
+        }
+    }
+
+    /// The part of `line` covered by `key`, from the start of its first field to the end of its
+    /// last
+    fn key<'a>(&self, key: &Key, line: &'a [u8]) -> &'a [u8] {
+        let fields = self.fields(line);
+        let Some(&(start, _)) = fields.get(key.start - 1) else {
+            return &[];
+        };
+        let end = match key.end {
+            Some(end) => fields[..end.min(fields.len())].last().map_or(start, |&(_, e)| e),
+            None => line.len(),
+        };
+        &line[start..end]
+    }
+
+    /// The byte range of each field in `line`
+    fn fields(&self, line: &[u8]) -> Vec<(usize, usize)> {
+        let mut fields = Vec::new();
+        match self.separator {
+            Some(separator) => {
+                let mut start = 0;
+                for (i, &b) in line.iter().enumerate() {
+                    if b == separator {
+                        fields.push((start, i));
+                        start = i + 1;
+                    }
+                }
+                fields.push((start, line.len()));
+            },
+            None => {
+                let mut start = None;
+                for (i, b) in line.iter().enumerate() {
+                    match (b.is_ascii_whitespace(), start) {
+                        (false, None) => start = Some(i),
+                        (true, Some(s)) => {
+                            fields.push((s, i));
+                            start = None;
+                        },
+                        _ => {},
+                    }
+                }
+                if let Some(s) = start {
+                    fields.push((s, line.len()));
+                }
+            },
+        }
+        fields
+    }
+}
+
+/// The number at the start of `key`, after any blanks, or 0 if there isn't one
+fn numeric_value(key: &[u8]) -> f64 {
+    let start = key.iter().position(|b| !b.is_ascii_whitespace()).unwrap_or(key.len());
+    let key = &key[start..];
+
+    let mut end = usize::from(key.first() == Some(&b'-'));
+    let mut seen_point = false;
+    while let Some(&b) = key.get(end) {
+        match b {
+            b'0'..=b'9' => {},
+            b'.' if !seen_point => seen_point = true,
+            _ => break,
+        }
+        end += 1;
+    }
+
+    std::str::from_utf8(&key[..end]).ok().and_then(|number| number.parse().ok()).unwrap_or(0.0)
+}
+
+/// Sorted runs spilled to disk, removed again when dropped
+struct Runs {
+    dir: PathBuf,
+    /// In input order, so merging them keeps the sort stable
+    files: Vec<PathBuf>,
+    merged: usize,
+}
+
+impl Runs {
+    fn new(temp_dir: &Path) -> io::Result<Runs> {
+        // Sorts running at the same time, in this process or another, each get their own directory
+        static SORTS: AtomicUsize = AtomicUsize::new(0);
+        let sort = SORTS.fetch_add(1, AtomicOrdering::Relaxed);
+        let dir = temp_dir.join(format!("ccsort-{}-{}", std::process::id(), sort));
+        std::fs::create_dir_all(&dir)?;
+        Ok(Runs { dir, files: Vec::new(), merged: 0 })
+    }
+
+    fn spill(&mut self, lines: &[Vec<u8>]) -> io::Result<()> {
+        let path = self.dir.join(format!("run{}", self.files.len()));
+        let mut file = BufWriter::new(File::create(&path)?);
+        for line in lines {
+            file.write_all(line)?;
+            file.write_all(b"\n")?;
+        }
+        file.flush()?;
+        self.files.push(path);
+        Ok(())
+    }
+
+    /// Merge the earliest runs together until few enough are left to merge in one go
+    fn reduce(&mut self, options: &SortOptions) -> io::Result<()> {
+        while self.files.len() > MERGE_WIDTH {
+            let path = self.dir.join(format!("merged{}", self.merged));
+            self.merged += 1;
+
+            let mut file = BufWriter::new(File::create(&path)?);
+            let mut writer = UniqueWriter { out: &mut file, options, previous: None };
+            merge(&self.files[..MERGE_WIDTH], &mut writer)?;
+            file.flush()?;
+
+            for merged in self.files.splice(..MERGE_WIDTH, [path]) {
+                std::fs::remove_file(merged)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Drop for Runs {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.dir);
+    }
+}
+
+/// Sort the lines of every input together into `out`
+pub fn sort(inputs: Vec<Box<dyn BufRead>>, options: &SortOptions, mut out: impl Write) -> io::Result<()> {
+    let mut runs: Option<Runs> = None;
+    let mut lines = Vec::new();
+    let mut used = 0;
+
+    for mut input in inputs {
+        loop {
+            let mut line = Vec::new();
+            if input.read_until(b'\n', &mut line)? == 0 {
+                break;
+            }
+            if line.last() == Some(&b'\n') {
+                line.pop();
+            }
+            used += line.len() + LINE_OVERHEAD;
+            lines.push(line);
+
+            if used >= options.buffer_size {
+                lines.sort_by(|a, b| options.compare(a, b));
+                match &mut runs {
+                    Some(runs) => runs.spill(&lines)?,
+                    None => runs.insert(Runs::new(&options.temp_dir)?).spill(&lines)?,
+                }
+                lines.clear();
+                used = 0;
+            }
+        }
+    }
+    lines.sort_by(|a, b| options.compare(a, b));
+
+    let mut writer = UniqueWriter { out: &mut out, options, previous: None };
+    match runs {
+        // Everything fit in memory
+        None => {
+            for line in lines {
+                writer.write(line)?;
+            }
+        },
+        Some(mut runs) => {
+            if !lines.is_empty() {
+                runs.spill(&lines)?;
+            }
+            drop(lines);
+            runs.reduce(options)?;
+            merge(&runs.files, &mut writer)?;
+        },
+    }
+    out.flush()
+}
+
+/// Merge sorted runs by repeatedly taking the smallest of their next lines. Ties go to the
+/// earlier run, which keeps the sort stable.
+fn merge(files: &[PathBuf], writer: &mut UniqueWriter<impl Write>) -> io::Result<()> {
+    let mut readers = Vec::new();
+    let mut heads = Vec::new();
+    for path in files {
+        let mut reader = BufReader::new(File::open(path)?);
+        heads.push(next_line(&mut reader)?);
+        readers.push(reader);
+    }
+
+    loop {
+        let mut smallest: Option<usize> = None;
+        for (i, head) in heads.iter().enumerate() {
+            let Some(line) = head else { continue };
+            let is_smaller = match smallest {
+                None => true,
+                Some(s) => writer.options.compare(line, heads[s].as_ref().unwrap()).is_lt(),
+            };
+            if is_smaller {
+                smallest = Some(i);
+            }
+        }
+
+        let Some(i) = smallest else {
+            return Ok(());
+        };
+        let line = std::mem::replace(&mut heads[i], next_line(&mut readers[i])?);
+        writer.write(line.unwrap())?;
+    }
+}
+
+fn next_line(reader: &mut impl BufRead) -> io::Result<Option<Vec<u8>>> {
+    let mut line = Vec::new();
+    if reader.read_until(b'\n', &mut line)? == 0 {
+        return Ok(None);
+    }
+    line.pop();
+    Ok(Some(line))
+}
+
+/// Writes sorted lines, dropping duplicates when sorting with `unique`
+struct UniqueWriter<'a, W> {
+    out: W,
+    options: &'a SortOptions,
+    previous: Option<Vec<u8>>,
+}
+
+impl<W: Write> UniqueWriter<'_, W> {
+    fn write(&mut self, line: Vec<u8>) -> io::Result<()> {
+        if self.options.unique {
+            if let Some(previous) = &self.previous {
+                if self.options.compare(previous, &line).is_eq() {
+                    return Ok(());
+                }
+            }
+        }
+
+        self.out.write_all(&line)?;
+        self.out.write_all(b"\n")?;
+        if self.options.unique {
+            self.previous = Some(line);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sorted(input: &str, options: &SortOptions) -> String {
+        let mut out = Vec::new();
+        let reader: Box<dyn BufRead> = Box::new(io::Cursor::new(input.as_bytes().to_vec()));
+        sort(vec![reader], options, &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn check_sort_lexicographic() {
+        assert_eq!(sorted("pear\napple\nBanana\n10\n9\n", &SortOptions::default()), "10\n9\nBanana\napple\npear\n");
+    }
+
+    #[test]
+    fn check_sort_without_final_newline() {
+        assert_eq!(sorted("b\na", &SortOptions::default()), "a\nb\n");
+    }
+
+    #[test]
+    fn check_sort_numeric() {
+        let options = SortOptions { numeric: true, ..SortOptions::default() };
+        assert_eq!(sorted("10\n9\n-1.5\nx\n 2\n", &options), "-1.5\nx\n 2\n9\n10\n");
+    }
+
+    #[test]
+    fn check_sort_reverse_unique() {
+        let options = SortOptions { reverse: true, unique: true, ..SortOptions::default() };
+        assert_eq!(sorted("a\nc\nb\nc\na\n", &options), "c\nb\na\n");
+    }
+
+    #[test]
+    fn check_unique_numeric_compares_values() {
+        let options = SortOptions { numeric: true, unique: true, ..SortOptions::default() };
+        assert_eq!(sorted("01\n1\n2\n", &options), "01\n2\n");
+    }
+
+    #[test]
+    fn check_sort_by_key() {
+        let options = SortOptions { keys: vec!["2".parse().unwrap()], ..SortOptions::default() };
+        assert_eq!(sorted("x  b\ny a\nz c\n", &options), "y a\nx  b\nz c\n");
+    }
+
+    #[test]
+    fn check_sort_by_key_with_separator() {
+        let options = SortOptions {
+            keys: vec!["2,2".parse().unwrap()],
+            separator: Some(b','),
+            numeric: true,
+            ..SortOptions::default()
+        };
+        assert_eq!(sorted("a,10,z\nb,9,y\nc,,x\n", &options), "c,,x\nb,9,y\na,10,z\n");
+    }
+
+    #[test]
+    fn check_parse_key() {
+        assert_eq!("2".parse(), Ok(Key { start: 2, ..Key::default() }));
+        assert_eq!("1,3".parse(), Ok(Key { start: 1, end: Some(3), ..Key::default() }));
+        assert!("0".parse::<Key>().is_err());
+        assert!("3,1".parse::<Key>().is_err());
+    }
+
+    #[test]
+    fn check_parse_key_modifiers() {
+        assert_eq!("2n".parse(), Ok(Key { start: 2, numeric: true, ..Key::default() }));
+        assert_eq!("2,2n".parse(), Ok(Key { start: 2, end: Some(2), numeric: true, ..Key::default() }));
+        assert_eq!("1r,3n".parse(), Ok(Key { start: 1, end: Some(3), numeric: true, reverse: true }));
+        assert_eq!("2b".parse::<Key>(), Err("invalid key `2b`, unknown modifier `b`".to_string()));
+        assert!("n".parse::<Key>().is_err());
+    }
+
+    #[test]
+    fn check_sort_by_keys_with_modifiers() {
+        // By name, then by number biggest first
+        let options = SortOptions { keys: vec!["1,1".parse().unwrap(), "2nr".parse().unwrap()], ..SortOptions::default() };
+        assert_eq!(sorted("b 9
+a 10
+b 10
+a 9
+", &options), "a 10
+a 9
+b 10
+b 9
+");
+
+        // A key with modifiers of its own ignores the global ones
+        let options = SortOptions { keys: vec!["2n".parse().unwrap()], reverse: true, numeric: false, ..SortOptions::default() };
+        assert_eq!(sorted("x 10
+y 9
+", &options), "y 9
+x 10
+");
+        let options = SortOptions { keys: vec!["2".parse().unwrap()], numeric: true, ..SortOptions::default() };
+        assert_eq!(sorted("x 10
+y 9
+", &options), "y 9
+x 10
+");
+    }
+
+    #[test]
+    fn check_external_merge_matches_in_memory_sort() {
+        let input: String = (0..500).map(|i| format!("{}\n", (i * 7919) % 1000)).collect();
+        let in_memory = sorted(&input, &SortOptions::default());
+
+        let temp_dir = std::env::temp_dir().join(format!("ccsort-test-{}", std::process::id()));
+        let options = SortOptions { buffer_size: 256, temp_dir: temp_dir.clone(), ..SortOptions::default() };
+        assert_eq!(sorted(&input, &options), in_memory);
+
+        // The runs are cleaned up afterwards
+        assert_eq!(std::fs::read_dir(&temp_dir).unwrap().count(), 0);
+        std::fs::remove_dir(&temp_dir).unwrap();
+    }
+
+    #[test]
+    fn check_external_merge_in_several_passes() {
+        let input: String = (0..2000).rev().map(|i| format!("{:04}\n", i)).collect();
+        let options = SortOptions { buffer_size: 64, ..SortOptions::default() };
+        let expected: String = (0..2000).map(|i| format!("{:04}\n", i)).collect();
+        assert_eq!(sorted(&input, &options), expected);
+    }
+
+    #[test]
+    fn check_external_merge_unique() {
+        let input: String = (0..300).map(|i| format!("{}\n", i % 10)).collect();
+        let options = SortOptions { buffer_size: 128, unique: true, numeric: true, ..SortOptions::default() };
+        assert_eq!(sorted(&input, &options), "0\n1\n2\n3\n4\n5\n6\n7\n8\n9\n");
+    }
+}
@@ -0,0 +1,51 @@
+use assert_cmd::Command;
+
+#[test]
+fn run_sort_stdin() {
+    Command::cargo_bin("ccsort").unwrap()
+        .write_stdin("pear\napple\nfig\n")
+        .assert().success().stdout("apple\nfig\npear\n");
+}
+
+#[test]
+fn run_sort_numeric_unique_reverse() {
+    Command::cargo_bin("ccsort").unwrap()
+        .args(["-nur"]).write_stdin("3\n10\n3\n2\n")
+        .assert().success().stdout("10\n3\n2\n");
+}
+
+#[test]
+fn run_sort_by_key() {
+    Command::cargo_bin("ccsort").unwrap()
+        .args(["-t", ",", "-k", "2", "-n"]).write_stdin("a,3\nb,1\nc,2\n")
+        .assert().success().stdout("b,1\nc,2\na,3\n");
+}
+
+#[test]
+fn run_sort_by_key_with_modifiers() {
+    Command::cargo_bin("ccsort").unwrap()
+        .args(["-k2n"]).write_stdin("a 10\nb 9\n")
+        .assert().success().stdout("b 9\na 10\n");
+    Command::cargo_bin("ccsort").unwrap()
+        .args(["-k", "2,2nr"]).write_stdin("b 9\na 10\n")
+        .assert().success().stdout("a 10\nb 9\n");
+}
+
+#[test]
+fn run_sort_in_place_with_small_buffer() {
+    let dir = std::env::temp_dir().join(format!("ccsort-cli-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let file = dir.join("words.txt");
+    let words: String = (0..1000).rev().map(|i| format!("word{:03}\n", i)).collect();
+    std::fs::write(&file, words).unwrap();
+
+    Command::cargo_bin("ccsort").unwrap()
+        .args(["-S", "1K", "-T"]).arg(&dir).arg("-o").arg(&file).arg(&file)
+        .assert().success().stdout("");
+
+    let sorted: String = (0..1000).map(|i| format!("word{:03}\n", i)).collect();
+    assert_eq!(std::fs::read_to_string(&file).unwrap(), sorted);
+    // Only the sorted file is left behind
+    assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,79 @@
+use crate::{uniq, UniqOptions};
+use anyhow::{Context, Result};
+use cc_cli::{is_broken_pipe, BROKEN_PIPE_EXIT_CODE};
+use cc_io::Source;
+use clap::Parser;
+use std::ffi::OsString;
+use std::fs::File;
+use std::io::{stdout, BufWriter};
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+/// uniq - report or omit repeated lines
+#[derive(Parser)]
+struct Cli {
+    /// Print how many times each line occurred before it
+    #[arg(short, long)]
+    count: bool,
+
+    /// Print only lines that occurred more than once, once each
+    #[arg(short = 'd', long)]
+    repeated: bool,
+
+    /// Print only lines that occurred once
+    #[arg(short, long)]
+    unique: bool,
+
+    /// Ignore case when comparing lines
+    #[arg(short, long)]
+    ignore_case: bool,
+
+    /// Ignore the first N fields of each line when comparing
+    #[arg(short = 'f', long, value_name = "N", default_value_t = 0)]
+    skip_fields: usize,
+
+    /// The file to read, or - for stdin
+    input: Option<PathBuf>,
+
+    /// The file to write, instead of stdout
+    output: Option<PathBuf>,
+}
+
+/// Run ccuniq with the given command line, `args[0]` being the program name
+pub fn main<I, T>(args: I) -> ExitCode
+where
+    I: IntoIterator<Item = T>,
+    T: Into<OsString> + Clone,
+{
+    let args: Cli = cc_cli::parse_from(args, env!("CARGO_PKG_VERSION"));
+
+    match run(args) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) if is_broken_pipe(&e) => ExitCode::from(BROKEN_PIPE_EXIT_CODE),
+        Err(e) => {
+            eprintln!("Error: {:?}", e);
+            ExitCode::FAILURE
+        },
+    }
+}
+
+fn run(args: Cli) -> Result<()> {
+    let options = UniqOptions {
+        count: args.count,
+        repeated: args.repeated,
+        unique: args.unique,
+        ignore_case: args.ignore_case,
+        skip_fields: args.skip_fields,
+    };
+    let input = cc_io::open(&Source::from_arg(args.input.as_deref()))?;
+    let label = input.label();
+
+    let result = match &args.output {
+        Some(path) => {
+            let file = File::create(path).with_context(|| format!("could not create `{}`", path.display()))?;
+            uniq(input.reader, &options, BufWriter::new(file))
+        },
+        None => uniq(input.reader, &options, BufWriter::new(stdout().lock())),
+    };
+    result.with_context(|| format!("could not filter `{}`", label))
+}
@@ -0,0 +1,149 @@
+// Duplicate line filtering core of ccuniq, usable without the command line interface.
+// Like uniq, only adjacent lines are compared, so the input is read as a stream and only the
+// current run of equal lines is held in memory.
+use std::io::{self, BufRead, Write};
+
+mod cli;
+
+pub use cli::main;
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct UniqOptions {
+    /// Print how many times each line occurred before it
+    pub count: bool,
+    /// Print only lines that occurred more than once
+    pub repeated: bool,
+    /// Print only lines that occurred once
+    pub unique: bool,
+    /// Compare lines ignoring ASCII case
+    pub ignore_case: bool,
+    /// Ignore this many fields at the start of each line when comparing. A field is a run of
+    /// blanks followed by a run of other characters.
+    pub skip_fields: usize,
+}
+
+impl UniqOptions {
+    /// Whether two lines, without their terminators, belong in the same run
+    pub fn same(&self, a: &[u8], b: &[u8]) -> bool {
+        let (a, b) = (skip_fields(a, self.skip_fields), skip_fields(b, self.skip_fields));
+        match self.ignore_case {
+            true => a.eq_ignore_ascii_case(b),
+            false => a == b,
+        }
+    }
+
+    fn prints(&self, occurrences: usize) -> bool {
+        match occurrences {
+            1 => !self.repeated,
+            _ => !self.unique,
+        }
+    }
+}
+
+fn skip_fields(line: &[u8], fields: usize) -> &[u8] {
+    let is_blank = |b: &u8| *b == b' ' || *b == b'\t';
+    let mut rest = line;
+    for _ in 0..fields {
+        let start = rest.iter().position(|b| !is_blank(b)).unwrap_or(rest.len());
+        let end = rest[start..].iter().position(is_blank).map_or(rest.len(), |i| start + i);
+        rest = &rest[end..];
+    }
+    rest
+}
+
+/// Copy `reader` to `out`, collapsing each run of equal lines into its first line
+pub fn uniq(mut reader: impl BufRead, options: &UniqOptions, mut out: impl Write) -> io::Result<()> {
+    let mut first = Vec::new();
+    let mut occurrences = 0;
+    let mut line = Vec::new();
+
+    loop {
+        line.clear();
+        let done = reader.read_until(b'\n', &mut line)? == 0;
+        if line.last() == Some(&b'\n') {
+            line.pop();
+        }
+
+        if !done && occurrences > 0 && options.same(&first, &line) {
+            occurrences += 1;
+            continue;
+        }
+
+        // The run has ended, so now it's known how many times its line occurred
+        if occurrences > 0 && options.prints(occurrences) {
+            if options.count {
+                write!(out, "{:>7} ", occurrences)?;
+            }
+            out.write_all(&first)?;
+            out.write_all(b"\n")?;
+        }
+        if done {
+            return out.flush();
+        }
+
+        std::mem::swap(&mut first, &mut line);
+        occurrences = 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Inputs and outputs below are checked against coreutils uniq
+    const SAMPLE: &str = "a\na\nb\nA\na\nx 1\ny 1\nz 2\n";
+
+    fn uniq_str(input: &str, options: UniqOptions) -> String {
+        let mut out = Vec::new();
+        uniq(input.as_bytes(), &options, &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn check_uniq() {
+        assert_eq!(uniq_str(SAMPLE, UniqOptions::default()), "a\nb\nA\na\nx 1\ny 1\nz 2\n");
+    }
+
+    #[test]
+    fn check_uniq_count() {
+        let options = UniqOptions { count: true, ..UniqOptions::default() };
+        assert_eq!(uniq_str("a\na\nb\n", options), "      2 a\n      1 b\n");
+    }
+
+    #[test]
+    fn check_uniq_ignore_case_keeps_first_line() {
+        let options = UniqOptions { count: true, ignore_case: true, ..UniqOptions::default() };
+        assert_eq!(uniq_str("b\nA\na\n", options), "      1 b\n      2 A\n");
+    }
+
+    #[test]
+    fn check_uniq_repeated_and_unique() {
+        let repeated = UniqOptions { repeated: true, ..UniqOptions::default() };
+        assert_eq!(uniq_str(SAMPLE, repeated), "a\n");
+
+        let unique = UniqOptions { unique: true, ..UniqOptions::default() };
+        assert_eq!(uniq_str(SAMPLE, unique), "b\nA\na\nx 1\ny 1\nz 2\n");
+
+        let both = UniqOptions { repeated: true, unique: true, ..UniqOptions::default() };
+        assert_eq!(uniq_str(SAMPLE, both), "");
+    }
+
+    #[test]
+    fn check_uniq_skip_fields() {
+        let options = UniqOptions { repeated: true, skip_fields: 1, ..UniqOptions::default() };
+        assert_eq!(uniq_str(SAMPLE, options), "a\nx 1\n");
+    }
+
+    #[test]
+    fn check_uniq_last_line_without_newline() {
+        let options = UniqOptions { count: true, ..UniqOptions::default() };
+        assert_eq!(uniq_str("a\na", options), "      2 a\n");
+        assert_eq!(uniq_str("", options), "");
+    }
+
+    #[test]
+    fn check_skip_fields() {
+        assert_eq!(skip_fields(b"  one two  three", 2), b"  three");
+        assert_eq!(skip_fields(b"one", 3), b"");
+    }
+}
@@ -0,0 +1,30 @@
+use assert_cmd::Command;
+
+fn ccuniq() -> Command {
+    let mut cmd = Command::cargo_bin("ccuniq").unwrap();
+    cmd.current_dir(env!("CARGO_MANIFEST_DIR"));
+    cmd
+}
+
+#[test]
+fn run_uniq_file() {
+    ccuniq().arg("testinputs/sample.txt").assert().success().stdout("a\nb\nA\na\nx 1\ny 1\nz 2\n");
+}
+
+#[test]
+fn run_uniq_stdin_counting() {
+    ccuniq().args(["-c", "-"]).write_stdin("a\na\nb\n").assert().success().stdout("      2 a\n      1 b\n");
+}
+
+#[test]
+fn run_uniq_repeated_ignoring_case() {
+    ccuniq().args(["-di", "testinputs/sample.txt"]).assert().success().stdout("a\nA\n");
+}
+
+#[test]
+fn run_uniq_into_output_file() {
+    let output = std::env::temp_dir().join(format!("ccuniq-out-{}", std::process::id()));
+    ccuniq().args(["-u", "-f", "1", "testinputs/sample.txt"]).arg(&output).assert().success().stdout("");
+    assert_eq!(std::fs::read_to_string(&output).unwrap(), "z 2\n");
+    std::fs::remove_file(&output).unwrap();
+}
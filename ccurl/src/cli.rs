@@ -0,0 +1,142 @@
+use crate::{copy_body, read_response_head, Request, Url, UrlError};
+use anyhow::{anyhow, Context, Result};
+use cc_cli::{is_broken_pipe, BROKEN_PIPE_EXIT_CODE};
+use clap::Parser;
+use std::ffi::OsString;
+use std::fs::File;
+use std::io::{stderr, stdout, BufReader, BufWriter, Write};
+use std::net::TcpStream;
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+/// Like curl, tell apart a URL that can't be fetched from a server that can't be reached
+const UNSUPPORTED_PROTOCOL_EXIT_CODE: u8 = 1;
+const MALFORMED_URL_EXIT_CODE: u8 = 3;
+const CONNECT_EXIT_CODE: u8 = 7;
+
+/// curl - transfer a URL over HTTP
+#[derive(Parser)]
+struct Cli {
+    /// The request method, GET by default or POST when sending data
+    #[arg(short = 'X', long = "request", value_name = "METHOD")]
+    method: Option<String>,
+
+    /// Add a header, e.g. -H 'Accept: application/json', replacing any default with that name
+    #[arg(short = 'H', long = "header", value_name = "HEADER", value_parser = parse_header)]
+    headers: Vec<(String, String)>,
+
+    /// Send this as the request body, or the contents of FILE for @FILE
+    #[arg(short = 'd', long = "data", value_name = "DATA")]
+    data: Option<String>,
+
+    /// Write the body to this file instead of stdout
+    #[arg(short, long, value_name = "FILE")]
+    output: Option<PathBuf>,
+
+    /// Include the response status line and headers in the output
+    #[arg(short, long)]
+    include: bool,
+
+    /// Trace the request and response headers on stderr
+    #[arg(short, long)]
+    verbose: bool,
+
+    url: String,
+}
+
+fn parse_header(s: &str) -> Result<(String, String), String> {
+    match s.split_once(':') {
+        Some((name, value)) if !name.trim().is_empty() => Ok((name.trim().to_string(), value.trim().to_string())),
+        _ => Err(format!("invalid header `{}`, expected `Name: value`", s)),
+    }
+}
+
+/// Run ccurl with the given command line, `args[0]` being the program name
+pub fn main<I, T>(args: I) -> ExitCode
+where
+    I: IntoIterator<Item = T>,
+    T: Into<OsString> + Clone,
+{
+    let args: Cli = cc_cli::parse_from(args, env!("CARGO_PKG_VERSION"));
+
+    match run(args) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) if is_broken_pipe(&e) => ExitCode::from(BROKEN_PIPE_EXIT_CODE),
+        Err(e) => {
+            eprintln!("Error: {:?}", e);
+            match e.downcast_ref::<UrlError>() {
+                Some(UrlError::UnsupportedScheme(_)) => ExitCode::from(UNSUPPORTED_PROTOCOL_EXIT_CODE),
+                Some(UrlError::Malformed(_)) => ExitCode::from(MALFORMED_URL_EXIT_CODE),
+                None if e.downcast_ref::<ConnectError>().is_some() => ExitCode::from(CONNECT_EXIT_CODE),
+                None => ExitCode::FAILURE,
+            }
+        },
+    }
+}
+
+/// Marks a failure to reach the server, which gets its own exit code
+#[derive(Debug)]
+struct ConnectError(String);
+
+impl std::fmt::Display for ConnectError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "could not connect to {}", self.0)
+    }
+}
+
+impl std::error::Error for ConnectError {}
+
+fn run(args: Cli) -> Result<()> {
+    let url: Url = args.url.parse()?;
+    let body = match args.data {
+        Some(data) => match data.strip_prefix('@') {
+            Some(path) => Some(std::fs::read(path).with_context(|| format!("could not read `{}`", path))?),
+            None => Some(data.into_bytes()),
+        },
+        None => None,
+    };
+    let method = args.method.unwrap_or_else(|| if body.is_some() { "POST" } else { "GET" }.to_string());
+    let request = Request::new(&method, url, &args.headers, body);
+
+    let address = (request.url.host.as_str(), request.url.port);
+    let stream = TcpStream::connect(address)
+        .map_err(|e| anyhow!(e).context(ConnectError(format!("{}:{}", request.url.host, request.url.port))))?;
+
+    let mut trace = stderr().lock();
+    if args.verbose {
+        writeln!(trace, "* Connected to {} ({}) port {}", request.url.host, stream.peer_addr()?.ip(), request.url.port)?;
+        for line in request.head().lines() {
+            writeln!(trace, "> {}", line)?;
+        }
+    }
+    request.write(&mut &stream).context("could not send the request")?;
+
+    let mut reader = BufReader::new(&stream);
+    let response = read_response_head(&mut reader).context("could not read the response")?;
+    let status_line = format!("{} {} {}", response.version, response.status, response.reason);
+    if args.verbose {
+        writeln!(trace, "< {}", status_line)?;
+        for (name, value) in &response.headers {
+            writeln!(trace, "< {}: {}", name, value)?;
+        }
+        writeln!(trace, "<")?;
+    }
+
+    let mut out: Box<dyn Write> = match &args.output {
+        Some(path) => {
+            let file = File::create(path).with_context(|| format!("could not create `{}`", path.display()))?;
+            Box::new(BufWriter::new(file))
+        },
+        None => Box::new(BufWriter::new(stdout().lock())),
+    };
+    if args.include {
+        write!(out, "{}\r\n", status_line)?;
+        for (name, value) in &response.headers {
+            write!(out, "{}: {}\r\n", name, value)?;
+        }
+        write!(out, "\r\n")?;
+    }
+    copy_body(&mut reader, &response, &method, &mut out).context("could not read the response body")?;
+    out.flush()?;
+    Ok(())
+}
@@ -0,0 +1,355 @@
+// HTTP client
+// Sends one HTTP/1.1 request and reads the response, whose body can come with a length, in
+// chunks, or run until the server closes the connection.
+// Reference:  https://www.rfc-editor.org/rfc/rfc9112
+use std::fmt;
+use std::io::{self, BufRead, ErrorKind, Read, Write};
+use std::str::FromStr;
+
+mod cli;
+
+pub use cli::main;
+
+pub const USER_AGENT: &str = concat!("ccurl/", env!("CARGO_PKG_VERSION"));
+
+/// Why a URL can't be fetched
+#[derive(Debug, PartialEq)]
+pub enum UrlError {
+    /// A scheme other than http, like https
+    UnsupportedScheme(String),
+    Malformed(String),
+}
+
+impl fmt::Display for UrlError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            UrlError::UnsupportedScheme(scheme) => write!(f, "protocol `{}` is not supported", scheme),
+            UrlError::Malformed(reason) => write!(f, "malformed URL: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for UrlError {}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Url {
+    pub host: String,
+    pub port: u16,
+    /// The path and query, always starting with `/`
+    pub target: String,
+}
+
+impl Url {
+    /// The Host header value, which leaves out the default port
+    pub fn authority(&self) -> String {
+        let host = match self.host.contains(':') {
+            true => format!("[{}]", self.host),
+            false => self.host.clone(),
+        };
+        match self.port {
+            80 => host,
+            port => format!("{}:{}", host, port),
+        }
+    }
+}
+
+impl FromStr for Url {
+    type Err = UrlError;
+
+    /// Parse `[http://]HOST[:PORT][/PATH][?QUERY]`, taking a URL without a scheme to be http like
+    /// curl does. IPv6 hosts go in brackets, e.g. `http://[::1]:8080/`.
+    fn from_str(s: &str) -> Result<Url, UrlError> {
+        let rest = match s.split_once("://") {
+            Some((scheme, rest)) if scheme.eq_ignore_ascii_case("http") => rest,
+            Some((scheme, _)) => return Err(UrlError::UnsupportedScheme(scheme.to_string())),
+            None => s,
+        };
+
+        // A fragment is only for the client, so it's never sent
+        let rest = rest.split('#').next().unwrap_or_default();
+        let (authority, target) = match rest.find(['/', '?']) {
+            Some(i) if rest[i..].starts_with('?') => (&rest[..i], format!("/{}", &rest[i..])),
+            Some(i) => (&rest[..i], rest[i..].to_string()),
+            None => (rest, "/".to_string()),
+        };
+        if authority.contains('@') {
+            return Err(UrlError::Malformed("user names in URLs are not supported".to_string()));
+        }
+
+        let (host, port) = match authority.strip_prefix('[') {
+            Some(bracketed) => match bracketed.split_once(']') {
+                Some((host, "")) => (host, None),
+                Some((host, port)) => match port.strip_prefix(':') {
+                    Some(port) => (host, Some(port)),
+                    None => return Err(UrlError::Malformed(format!("unexpected `{}` after host", port))),
+                },
+                None => return Err(UrlError::Malformed("unclosed `[` in host".to_string())),
+            },
+            None => match authority.split_once(':') {
+                Some((host, port)) => (host, Some(port)),
+                None => (authority, None),
+            },
+        };
+        if host.is_empty() {
+            return Err(UrlError::Malformed("no host".to_string()));
+        }
+        let port = match port {
+            None => 80,
+            Some(port) => port.parse().map_err(|_| UrlError::Malformed(format!("invalid port `{}`", port)))?,
+        };
+
+        Ok(Url { host: host.to_string(), port, target })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Request {
+    pub method: String,
+    pub url: Url,
+    pub headers: Vec<(String, String)>,
+    pub body: Option<Vec<u8>>,
+}
+
+impl Request {
+    /// A request with the headers curl sends by default. Headers given in `extra` replace the
+    /// defaults with the same name, and come after the rest.
+    pub fn new(method: &str, url: Url, extra: &[(String, String)], body: Option<Vec<u8>>) -> Request {
+        let mut headers = vec![
+            ("Host".to_string(), url.authority()),
+            ("User-Agent".to_string(), USER_AGENT.to_string()),
+            ("Accept".to_string(), "*/*".to_string()),
+        ];
+        if let Some(body) = &body {
+            headers.push(("Content-Type".to_string(), "application/x-www-form-urlencoded".to_string()));
+            headers.push(("Content-Length".to_string(), body.len().to_string()));
+        }
+        // Reading the response to the end of the connection is simplest when there's only one
+        headers.push(("Connection".to_string(), "close".to_string()));
+
+        for (name, value) in extra {
+            headers.retain(|(existing, _)| !existing.eq_ignore_ascii_case(name));
+            headers.push((name.clone(), value.clone()));
+        }
+        Request { method: method.to_string(), url, headers, body }
+    }
+
+    /// The request line and headers, each with its CRLF, ending with the blank line
+    pub fn head(&self) -> String {
+        let mut head = format!("{} {} HTTP/1.1\r\n", self.method, self.url.target);
+        for (name, value) in &self.headers {
+            head.push_str(&format!("{}: {}\r\n", name, value));
+        }
+        head.push_str("\r\n");
+        head
+    }
+
+    pub fn write(&self, out: &mut impl Write) -> io::Result<()> {
+        out.write_all(self.head().as_bytes())?;
+        if let Some(body) = &self.body {
+            out.write_all(body)?;
+        }
+        out.flush()
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Response {
+    pub version: String,
+    pub status: u16,
+    pub reason: String,
+    pub headers: Vec<(String, String)>,
+}
+
+impl Response {
+    /// The first header called `name`, ignoring case
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.iter().find(|(n, _)| n.eq_ignore_ascii_case(name)).map(|(_, value)| value.as_str())
+    }
+
+    /// Whether the status and request method mean there's no body, whatever the headers say
+    fn has_no_body(&self, method: &str) -> bool {
+        method == "HEAD" || (100..200).contains(&self.status) || self.status == 204 || self.status == 304
+    }
+}
+
+fn invalid(message: impl Into<String>) -> io::Error {
+    io::Error::new(ErrorKind::InvalidData, message.into())
+}
+
+/// Read a line, without its CRLF or bare LF
+fn read_line(reader: &mut impl BufRead) -> io::Result<String> {
+    let mut line = String::new();
+    if reader.read_line(&mut line)? == 0 {
+        return Err(ErrorKind::UnexpectedEof.into());
+    }
+    let trimmed = line.trim_end_matches('\n').trim_end_matches('\r');
+    Ok(trimmed.to_string())
+}
+
+/// Read the status line and headers of a response. Interim 1xx responses are skipped, since
+/// the real one follows them.
+pub fn read_response_head(reader: &mut impl BufRead) -> io::Result<Response> {
+    loop {
+        let line = read_line(reader)?;
+        let mut parts = line.splitn(3, ' ');
+        let (Some(version), Some(status)) = (parts.next(), parts.next()) else {
+            return Err(invalid(format!("malformed status line `{}`", line)));
+        };
+        if !version.starts_with("HTTP/") {
+            return Err(invalid(format!("malformed status line `{}`", line)));
+        }
+        let status: u16 = status.parse().map_err(|_| invalid(format!("invalid status `{}`", status)))?;
+
+        let mut headers = Vec::new();
+        loop {
+            let line = read_line(reader)?;
+            if line.is_empty() {
+                break;
+            }
+            let (name, value) = line.split_once(':').ok_or_else(|| invalid(format!("malformed header `{}`", line)))?;
+            headers.push((name.to_string(), value.trim().to_string()));
+        }
+
+        if (100..200).contains(&status) && status != 101 {
+            continue;
+        }
+        return Ok(Response {
+            version: version.to_string(),
+            status,
+            reason: parts.next().unwrap_or_default().to_string(),
+            headers,
+        });
+    }
+}
+
+/// Copy a body sent with chunked transfer coding, returning its decoded length
+fn copy_chunked(reader: &mut impl BufRead, out: &mut impl Write) -> io::Result<u64> {
+    let mut total = 0;
+    loop {
+        let line = read_line(reader)?;
+        // Chunk extensions after a `;` carry nothing needed here
+        let size = line.split(';').next().unwrap_or_default().trim();
+        let size = u64::from_str_radix(size, 16).map_err(|_| invalid(format!("invalid chunk size `{}`", size)))?;
+        if size == 0 {
+            // Skip any trailer fields up to the blank line ending the body
+            while !read_line(reader)?.is_empty() {}
+            return Ok(total);
+        }
+
+        let copied = io::copy(&mut reader.take(size), out)?;
+        if copied < size {
+            return Err(ErrorKind::UnexpectedEof.into());
+        }
+        total += copied;
+        if !read_line(reader)?.is_empty() {
+            return Err(invalid("chunk not followed by CRLF"));
+        }
+    }
+}
+
+/// Copy the body of `response` to `out`, returning its length
+pub fn copy_body(reader: &mut impl BufRead, response: &Response, method: &str, out: &mut impl Write) -> io::Result<u64> {
+    if response.has_no_body(method) {
+        return Ok(0);
+    }
+    let chunked = response
+        .header("transfer-encoding")
+        .is_some_and(|coding| coding.rsplit(',').next().is_some_and(|last| last.trim().eq_ignore_ascii_case("chunked")));
+    if chunked {
+        return copy_chunked(reader, out);
+    }
+
+    match response.header("content-length") {
+        Some(length) => {
+            let length: u64 = length.parse().map_err(|_| invalid(format!("invalid Content-Length `{}`", length)))?;
+            let copied = io::copy(&mut reader.take(length), out)?;
+            match copied == length {
+                true => Ok(copied),
+                false => Err(ErrorKind::UnexpectedEof.into()),
+            }
+        },
+        None => io::copy(reader, out),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn url(s: &str) -> Url {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn check_parse_url() {
+        assert_eq!(url("http://example.com"), Url { host: "example.com".to_string(), port: 80, target: "/".to_string() });
+        assert_eq!(url("HTTP://localhost:8080/a/b?c=d#top"), Url {
+            host: "localhost".to_string(),
+            port: 8080,
+            target: "/a/b?c=d".to_string(),
+        });
+        assert_eq!(url("example.com?q=1").target, "/?q=1");
+        assert_eq!(url("http://[::1]:81/").host, "::1");
+        assert_eq!(url("http://[::1]:81/").authority(), "[::1]:81");
+    }
+
+    #[test]
+    fn check_parse_url_errors() {
+        assert_eq!("https://example.com".parse::<Url>(), Err(UrlError::UnsupportedScheme("https".to_string())));
+        assert!(matches!("http:///path".parse::<Url>(), Err(UrlError::Malformed(_))));
+        assert!(matches!("http://host:http/".parse::<Url>(), Err(UrlError::Malformed(_))));
+        assert!(matches!("http://[::1/".parse::<Url>(), Err(UrlError::Malformed(_))));
+    }
+
+    #[test]
+    fn check_request_head() {
+        let extra = [("accept".to_string(), "application/json".to_string()), ("X-Trace".to_string(), "1".to_string())];
+        let request = Request::new("POST", url("localhost:8080/items"), &extra, Some(b"a=1".to_vec()));
+        assert_eq!(request.head(), concat!(
+            "POST /items HTTP/1.1\r\n",
+            "Host: localhost:8080\r\n",
+            "User-Agent: ccurl/0.1.0\r\n",
+            "Content-Type: application/x-www-form-urlencoded\r\n",
+            "Content-Length: 3\r\n",
+            "Connection: close\r\n",
+            "accept: application/json\r\n",
+            "X-Trace: 1\r\n",
+            "\r\n",
+        ));
+    }
+
+    fn fetch(response: &str, method: &str) -> io::Result<(Response, String)> {
+        let mut reader = response.as_bytes();
+        let head = read_response_head(&mut reader)?;
+        let mut body = Vec::new();
+        copy_body(&mut reader, &head, method, &mut body)?;
+        Ok((head, String::from_utf8(body).unwrap()))
+    }
+
+    #[test]
+    fn check_read_response() {
+        let (head, body) = fetch("HTTP/1.1 200 OK\r\nContent-Length: 5\r\n\r\nhello and more", "GET").unwrap();
+        assert_eq!((head.status, head.reason.as_str(), body.as_str()), (200, "OK", "hello"));
+        assert_eq!(head.header("content-LENGTH"), Some("5"));
+
+        let (_, body) = fetch("HTTP/1.0 200 OK\r\n\r\nuntil close", "GET").unwrap();
+        assert_eq!(body, "until close");
+        let (_, body) = fetch("HTTP/1.1 200 OK\r\nContent-Length: 5\r\n\r\n", "HEAD").unwrap();
+        assert_eq!(body, "");
+    }
+
+    #[test]
+    fn check_read_chunked_response() {
+        let response = "HTTP/1.1 100 Continue\r\n\r\nHTTP/1.1 201 Created\r\nTransfer-Encoding: chunked\r\n\r\n\
+            5;ext=1\r\nhello\r\n7\r\n, world\r\n0\r\nTrailer: x\r\n\r\n";
+        let (head, body) = fetch(response, "POST").unwrap();
+        assert_eq!((head.status, body.as_str()), (201, "hello, world"));
+    }
+
+    #[test]
+    fn check_read_response_errors() {
+        assert!(fetch("SMTP ready\r\n\r\n", "GET").is_err());
+        assert!(fetch("HTTP/1.1 200 OK\r\nContent-Length: 10\r\n\r\nshort", "GET").is_err());
+        assert!(fetch("HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\nzz\r\n", "GET").is_err());
+    }
+}
@@ -0,0 +1,101 @@
+use assert_cmd::Command;
+use predicates::prelude::*;
+use predicates::str::contains;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpListener;
+use std::thread::{self, JoinHandle};
+
+fn ccurl() -> Command {
+    Command::cargo_bin("ccurl").unwrap()
+}
+
+/// Serve one connection with a canned response, returning the URL to fetch and a handle giving
+/// the request that was received
+fn serve_once(response: &'static str) -> (String, JoinHandle<String>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/path?q=1", listener.local_addr().unwrap());
+    let handle = thread::spawn(move || {
+        let (stream, _) = listener.accept().unwrap();
+        let mut reader = BufReader::new(&stream);
+        let mut request = String::new();
+        let mut length = 0;
+        loop {
+            let mut line = String::new();
+            reader.read_line(&mut line).unwrap();
+            if let Some(value) = line.to_ascii_lowercase().strip_prefix("content-length:") {
+                length = value.trim().parse().unwrap();
+            }
+            request.push_str(&line);
+            if line == "\r\n" {
+                break;
+            }
+        }
+        let mut body = vec![0; length];
+        reader.read_exact(&mut body).unwrap();
+        request.push_str(&String::from_utf8(body).unwrap());
+        (&stream).write_all(response.as_bytes()).unwrap();
+        request
+    });
+    (url, handle)
+}
+
+#[test]
+fn run_curl_get() {
+    let (url, server) = serve_once("HTTP/1.1 200 OK\r\nContent-Length: 6\r\n\r\nhello\n");
+    ccurl().arg(&url).assert().success().stdout("hello\n");
+
+    let request = server.join().unwrap();
+    let host = url.trim_start_matches("http://").split('/').next().unwrap();
+    assert_eq!(request, format!(
+        "GET /path?q=1 HTTP/1.1\r\nHost: {}\r\nUser-Agent: ccurl/0.1.0\r\nAccept: */*\r\nConnection: close\r\n\r\n",
+        host,
+    ));
+}
+
+#[test]
+fn run_curl_post_with_headers() {
+    let (url, server) = serve_once("HTTP/1.1 201 Created\r\nTransfer-Encoding: chunked\r\n\r\n3\r\nok\n\r\n0\r\n\r\n");
+    ccurl()
+        .args(["-d", r#"{"name":"cc"}"#, "-H", "Content-Type: application/json", &url])
+        .assert()
+        .success()
+        .stdout("ok\n");
+
+    let request = server.join().unwrap();
+    assert!(request.starts_with("POST /path?q=1 HTTP/1.1\r\n"), "{}", request);
+    assert!(request.contains("\r\nContent-Length: 13\r\n"), "{}", request);
+    assert!(request.ends_with("\r\nContent-Type: application/json\r\n\r\n{\"name\":\"cc\"}"), "{}", request);
+    assert!(!request.contains("x-www-form-urlencoded"), "{}", request);
+}
+
+#[test]
+fn run_curl_delete_verbose_include() {
+    let (url, server) = serve_once("HTTP/1.1 204 No Content\r\nServer: test\r\n\r\n");
+    ccurl()
+        .args(["-X", "DELETE", "-v", "-i", &url])
+        .assert()
+        .success()
+        .stdout("HTTP/1.1 204 No Content\r\nServer: test\r\n\r\n")
+        .stderr(contains("> DELETE /path?q=1 HTTP/1.1\n").and(contains("< HTTP/1.1 204 No Content\n< Server: test\n<\n")));
+    assert!(server.join().unwrap().starts_with("DELETE "));
+}
+
+#[test]
+fn run_curl_put_output_file() {
+    let (url, server) = serve_once("HTTP/1.1 200 OK\r\n\r\nsaved body");
+    let output = std::env::temp_dir().join(format!("ccurl-out-{}", std::process::id()));
+    ccurl().args(["-X", "PUT", "-d", "x", "-o"]).arg(&output).arg(&url).assert().success().stdout("");
+    assert_eq!(std::fs::read_to_string(&output).unwrap(), "saved body");
+    assert!(server.join().unwrap().starts_with("PUT "));
+    std::fs::remove_file(&output).unwrap();
+}
+
+#[test]
+fn run_curl_errors() {
+    ccurl().arg("https://example.com/").assert().code(1).stderr(contains("protocol `https` is not supported"));
+    ccurl().arg("http://localhost:port/").assert().code(3);
+
+    // Nothing listens on a port just given back by the OS
+    let port = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+    ccurl().arg(format!("http://127.0.0.1:{}/", port)).assert().code(7);
+}
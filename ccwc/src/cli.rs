@@ -0,0 +1,637 @@
+use anyhow::{bail, Context, Result};
+use crate::{
+    count, count_occurrences, count_with_progress, is_binary, line_length, CountOptions, Counts, LineLimit,
+    LineMeasure, LineReader, WordRule,
+};
+use cc_cli::{is_broken_pipe, FormatArgs, Report, ReportError, BROKEN_PIPE_EXIT_CODE};
+use cc_io::Source;
+use cc_walk::WalkArgs;
+use clap::Parser;
+use encoding_rs::Encoding;
+use encoding_rs_io::DecodeReaderBytesBuilder;
+use regex::Regex;
+use output::Output;
+use serde::Serialize;
+use state::{FileId, FileState, State};
+use std::ffi::OsString;
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::time::Duration;
+
+mod output;
+mod state;
+
+/// wc - word, line, character, and byte count
+#[derive(Parser)]
+struct Cli {
+    /// The number of bytes in each input file
+    #[arg(short = 'c')]
+    bytes: bool,
+
+    /// The number of lines in each input file
+    #[arg(short)]
+    lines: bool,
+
+    /// The number of words in each input file
+    #[arg(short)]
+    words: bool,
+
+    /// The number of characters in each input file
+    #[arg(short = 'm')]
+    chars: bool,
+
+    /// The display width of the longest line in each input file
+    #[arg(short = 'L')]
+    max_line_length: bool,
+
+    /// With -L, also print the line number of the longest line
+    #[arg(long, requires = "max_line_length")]
+    show_longest: bool,
+
+    /// With --show-longest, also print the start of the longest line
+    #[arg(long, requires = "show_longest")]
+    preview: bool,
+
+    /// Estimate line, word, and character counts by sampling blocks of the file. Files small
+    /// enough to count cheaply are counted exactly instead
+    #[arg(long)]
+    estimate: bool,
+
+    /// Print the number and length of each line instead of totals. Lengths are in bytes with -c,
+    /// characters with -m, and display columns otherwise
+    #[arg(long)]
+    per_line: bool,
+
+    /// Count the occurrences of this literal string instead
+    #[arg(long, value_name = "PATTERN")]
+    count_string: Option<String>,
+
+    /// Let occurrences counted by --count-string overlap, e.g. "aa" occurs twice in "aaa"
+    #[arg(long, requires = "count_string")]
+    overlapping: bool,
+
+    /// Write the results to this file instead of stdout. The file is replaced in one step once
+    /// counting has finished
+    #[arg(long, value_name = "FILE")]
+    output: Option<PathBuf>,
+
+    /// Add the results to the end of the --output file instead of replacing it
+    #[arg(long, requires = "output")]
+    append: bool,
+
+    /// Fail if any line, not counting its newline, is longer than this many bytes
+    #[arg(long, value_name = "BYTES")]
+    max_line_bytes: Option<usize>,
+
+    /// Warn about each line over --max-line-bytes and keep counting, instead of failing
+    #[arg(long, requires = "max_line_bytes")]
+    report_long_lines: bool,
+
+    /// Count each Chinese, Japanese, or Korean character as a word
+    #[arg(long)]
+    cjk: bool,
+
+    /// Break the line count down into blank, whitespace-only, and content lines
+    #[arg(long)]
+    classify_lines: bool,
+
+    /// Print the counts so far to stderr this often while counting, e.g. 5s, 500ms, or 1m
+    #[arg(long, value_name = "DURATION", value_parser = parse_duration)]
+    interval: Option<Duration>,
+
+    /// Count each match of this regex as a word instead of splitting on whitespace
+    #[arg(long, value_name = "REGEX", value_parser = Regex::new, conflicts_with = "cjk")]
+    words_match: Option<Regex>,
+
+    /// Decode the input from this encoding before counting, e.g. latin1, utf-16le, or shift_jis.
+    /// Byte counts are then of the input converted to UTF-8
+    #[arg(long, value_name = "ENCODING", value_parser = parse_encoding, conflicts_with = "estimate")]
+    from_encoding: Option<&'static Encoding>,
+
+    /// Remember how far each file has been counted in this file, and on later runs only count
+    /// the lines added since. The counts printed are always for the whole file, a last line
+    /// without a newline included, but that line is counted again next run in case it grows
+    #[arg(
+        long,
+        value_name = "FILE",
+        conflicts_with_all = ["estimate", "count_string", "per_line", "offset", "length", "from_encoding"],
+    )]
+    state: Option<PathBuf>,
+
+    /// Skip this many bytes of the input before counting
+    #[arg(long, default_value_t = 0)]
+    offset: u64,
+
+    /// Count at most this many bytes of the input
+    #[arg(long)]
+    length: Option<u64>,
+
+    /// Count every file under the given directories, or under the current directory if none are
+    /// given
+    #[arg(short, long)]
+    recursive: bool,
+
+    #[command(flatten)]
+    walk: WalkArgs,
+
+    /// Leave files that look binary out of the counts, listing them separately instead
+    #[arg(long)]
+    skip_binary: bool,
+
+    #[command(flatten)]
+    format: FormatArgs,
+
+    /// The paths to the files to read, or stdin if there are none
+    paths: Vec<PathBuf>,
+}
+
+impl Cli {
+    fn word_rule(&self) -> WordRule {
+        if let Some(regex) = &self.words_match {
+            WordRule::Regex(regex.clone())
+        } else if self.cjk {
+            WordRule::Cjk
+        } else {
+            WordRule::Whitespace
+        }
+    }
+}
+
+/// Parse a duration like `5s`, `500ms`, or `2m`. A bare number is taken as seconds.
+fn parse_duration(value: &str) -> Result<Duration> {
+    let split = value.find(|c: char| !c.is_ascii_digit() && c != '.').unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+    let number: f64 = number.parse().with_context(|| format!("invalid duration `{}`", value))?;
+
+    let seconds = match unit {
+        "ms" => number / 1000.0,
+        "" | "s" => number,
+        "m" => number * 60.0,
+        "h" => number * 3600.0,
+        _ => bail!("unknown unit `{}` in duration `{}`, expected ms, s, m, or h", unit, value),
+    };
+
+    Ok(Duration::from_secs_f64(seconds))
+}
+
+struct Input {
+    path: String,
+    reader: Box<dyn BufRead>,
+}
+
+/// The slice of the input to count, set via `--offset` and `--length`
+#[derive(Clone, Copy)]
+struct ByteRange {
+    offset: u64,
+    length: Option<u64>,
+}
+
+fn get_input(option_path: Option<PathBuf>, range: &ByteRange) -> Result<Input> {
+    let source = Source::from_arg(option_path.as_deref());
+    let input = cc_io::open_at(&source, range.offset)?;
+    let limit = range.length.unwrap_or(u64::MAX);
+
+    Ok(
+        Input {
+            // Like wc, leave the name off when counting stdin
+            path: if source.is_stdin() { String::new() } else { source.label() },
+            reader: Box::new(input.reader.take(limit)),
+        }
+    )
+}
+
+/// Decode the input from a legacy encoding into UTF-8 as it's read. A byte order mark, if there is
+/// one, takes precedence over the given encoding.
+fn transcode(reader: Box<dyn BufRead>, encoding: &'static Encoding) -> Box<dyn BufRead> {
+    let decoder = DecodeReaderBytesBuilder::new().encoding(Some(encoding)).build(reader);
+    Box::new(BufReader::new(decoder))
+}
+
+fn parse_encoding(label: &str) -> Result<&'static Encoding> {
+    match Encoding::for_label(label.as_bytes()) {
+        Some(encoding) => Ok(encoding),
+        None => bail!("unknown encoding `{}`, try e.g. latin1, utf-16le, or shift_jis", label),
+    }
+}
+
+/// Run ccwc with the given command line, `args[0]` being the program name
+pub fn main<I, T>(args: I) -> ExitCode
+where
+    I: IntoIterator<Item = T>,
+    T: Into<OsString> + Clone,
+{
+    let args: Cli = cc_cli::parse_from(args, env!("CARGO_PKG_VERSION"));
+
+    let format = args.format.format;
+    let mut report = args.format.is_report().then(|| Report::start("ccwc", env!("CARGO_PKG_VERSION")));
+    let result = match &args.output {
+        Some(path) => Output::file(path, args.append),
+        None => Ok(Output::stdout()),
+    };
+    let result = result.and_then(|mut out| {
+        let counted = run(args, &mut out, report.as_mut());
+        let Some(mut report) = report else {
+            return match counted {
+                Ok(()) => out.finish().map(|()| ExitCode::SUCCESS),
+                Err(e) => {
+                    out.discard();
+                    Err(e)
+                },
+            };
+        };
+        // The report says what went wrong, along with whatever was counted before it did
+        if let Err(e) = counted {
+            report.errors.push(ReportError::from_error(None, &e));
+        }
+        let code = if report.errors.is_empty() { ExitCode::SUCCESS } else { ExitCode::FAILURE };
+        report.write_as(format, &mut out)?;
+        out.finish()?;
+        Ok(code)
+    });
+
+    match result {
+        Ok(code) => code,
+        Err(e) if is_broken_pipe(&e) => ExitCode::from(BROKEN_PIPE_EXIT_CODE),
+        Err(e) => {
+            eprintln!("Error: {:?}", e);
+            ExitCode::FAILURE
+        },
+    }
+}
+
+/// One input's counts in a --format json report
+#[derive(Serialize)]
+struct InputCounts {
+    input: String,
+    #[serde(flatten)]
+    counts: Counts,
+}
+
+/// Count the inputs, writing the counts to `out`, or adding them to `report` if there is one
+fn run(args: Cli, out: &mut impl Write, mut report: Option<&mut Report<InputCounts>>) -> Result<()> {
+    if report.is_some() && (args.estimate || args.count_string.is_some() || args.per_line) {
+        bail!("--format json and sarif only report counts, they cannot be used with --estimate, --count-string, or --per-line");
+    }
+    let paths = input_paths(&args)?;
+
+    if args.estimate {
+        if paths.is_empty() {
+            bail!("--estimate needs a file to sample, it cannot read from stdin");
+        }
+        for path in &paths {
+            print_estimate(&args, path, out)?;
+        }
+        return Ok(());
+    }
+
+    let range = ByteRange { offset: args.offset, length: args.length };
+    let limit = args.max_line_bytes.map(|max_bytes| LineLimit {
+        max_bytes,
+        report_only: args.report_long_lines,
+    });
+    let options = CountOptions { limit, words: args.word_rule() };
+
+    // Without any paths, count stdin
+    let inputs: Vec<Option<PathBuf>> = if paths.is_empty() {
+        vec![None]
+    } else {
+        paths.into_iter().map(Some).collect()
+    };
+    let mut total = Counts::default();
+    let mut total_occurrences = 0;
+    let mut binary_paths = Vec::new();
+    if let Some(report) = &mut report {
+        report.inputs = inputs.iter().map(|path| Source::from_arg(path.as_deref()).label()).collect();
+    }
+
+    let mut state = match &args.state {
+        Some(_) if inputs.contains(&None) => bail!("--state needs files to count, it cannot read from stdin"),
+        Some(state_path) => Some(State::load(state_path)?),
+        None => None,
+    };
+
+    for path in &inputs {
+        // With a state file, only count the complete lines added since the last run
+        let mut resumed = None;
+        let range = match (&state, path) {
+            (Some(state), Some(path)) => {
+                let mut file = std::fs::File::open(path)
+                    .with_context(|| format!("could not read file `{}`", path.display()))?;
+                let metadata = file.metadata()?;
+                let (size, id) = (metadata.len(), FileId::of(&metadata));
+                let previous = state.resume(&path.display().to_string(), size, id);
+                let end = state::last_line_end(&mut file, previous.offset, size)?;
+                let range = ByteRange { offset: previous.offset, length: Some(end - previous.offset) };
+                resumed = Some((previous, end, size, id));
+                range
+            },
+            _ => range,
+        };
+
+        let mut input = get_input(path.clone(), &range)?;
+        if let Some(encoding) = args.from_encoding {
+            input.reader = transcode(input.reader, encoding);
+        }
+
+        if args.skip_binary && is_binary(&mut input.reader)? {
+            binary_paths.push(input.path);
+            continue;
+        }
+
+        if let Some(pattern) = &args.count_string {
+            let occurrences = count_occurrences(input.reader, pattern.as_bytes(), args.overlapping)?;
+            writeln!(out, "    {} {}", occurrences, input.path)?;
+            total_occurrences += occurrences;
+        } else if args.per_line {
+            let measure = if args.bytes {
+                LineMeasure::Bytes
+            } else if args.chars {
+                LineMeasure::Chars
+            } else {
+                LineMeasure::Width
+            };
+            print_per_line(input.reader, limit, measure, out)?;
+        } else {
+            let mut counts = count_with_progress(input.reader, &options, args.interval, |counts| {
+                eprintln!("ccwc: {} {} {} so far {}", counts.lines, counts.words, counts.chars, input.path);
+            })?;
+            if let (Some(state), Some((previous, end, size, id))) = (&mut state, resumed) {
+                counts = state::merge(&previous.counts, counts);
+                let checkpoint = FileState { offset: end, counts: counts.clone(), id };
+                state.files.insert(input.path.clone(), checkpoint);
+                // The line still being written is left out of the checkpoint but not the counts
+                if size > end {
+                    let tail = get_input(path.clone(), &ByteRange { offset: end, length: Some(size - end) })?;
+                    counts = state::merge(&counts, count(tail.reader, &options)?);
+                }
+            }
+            match &mut report {
+                Some(report) => {
+                    let input = Source::from_arg(path.as_deref()).label();
+                    report.results.push(InputCounts { input, counts: counts.clone() });
+                },
+                None => print_counts(&args, &counts, &input.path, out)?,
+            }
+            total += counts;
+        }
+    }
+
+    if inputs.len() > 1 && report.is_none() {
+        if args.count_string.is_some() {
+            writeln!(out, "    {} total", total_occurrences)?;
+        } else if !args.per_line {
+            print_counts(&args, &total, "total", out)?;
+        }
+    }
+
+    if let (Some(state), Some(state_path)) = (&state, &args.state) {
+        state.save(state_path)?;
+    }
+
+    // Binary files are left out of the counts above, so list them on their own
+    for path in binary_paths {
+        match &mut report {
+            Some(report) => report.errors.push(ReportError::new(Some(&path), "skipped binary file")),
+            None => eprintln!("ccwc: skipped binary file {}", path),
+        }
+    }
+
+    Ok(())
+}
+
+/// The files to count. In recursive mode, directories are replaced by every file beneath them.
+fn input_paths(args: &Cli) -> Result<Vec<PathBuf>> {
+    if !args.recursive {
+        return Ok(args.paths.clone());
+    }
+
+    let roots = if args.paths.is_empty() {
+        vec![PathBuf::from(".")]
+    } else {
+        args.paths.clone()
+    };
+    Ok(cc_walk::walk(&roots, &args.walk.options())?)
+}
+
+fn print_counts(args: &Cli, counts: &Counts, display_path: &str, out: &mut impl Write) -> Result<()> {
+    if args.classify_lines {
+        writeln!(
+            out,
+            "    {} lines: {} blank, {} whitespace-only, {} content {}",
+            counts.lines, counts.blank_lines, counts.whitespace_lines, counts.content_lines, display_path,
+        )?;
+    } else if args.max_line_length {
+        match (&counts.longest_line, args.show_longest) {
+            (Some(longest), true) if args.preview => writeln!(
+                out,
+                "    {} {} (line {}: {:?})",
+                longest.width, display_path, longest.number, longest.preview,
+            )?,
+            (Some(longest), true) => {
+                writeln!(out, "    {} {} (line {})", longest.width, display_path, longest.number)?
+            },
+            _ => writeln!(out, "    {} {}", counts.max_line_length(), display_path)?,
+        }
+    } else if args.bytes {
+        writeln!(out, "    {} {}", counts.bytes, display_path)?;
+    }  else if args.lines {
+        writeln!(out, "    {} {}", counts.lines, display_path)?;
+    } else if args.words {
+        writeln!(out, "    {} {}", counts.words, display_path)?;
+    } else if args.chars {
+        writeln!(out, "    {} {}", counts.chars, display_path)?;
+    } else {
+        writeln!(out, "    {} {} {} {}", counts.lines, counts.words, counts.chars, display_path)?;
+    }
+
+    Ok(())
+}
+
+fn print_per_line(
+    reader: impl BufRead,
+    limit: Option<LineLimit>,
+    measure: LineMeasure,
+    out: &mut impl Write,
+) -> Result<()> {
+    let mut reader = LineReader::new(reader, limit);
+    let mut line = Vec::new();
+
+    while reader.read_line(&mut line)? {
+        writeln!(out, "{}\t{}", reader.line_number(), line_length(&line, measure))?;
+    }
+
+    Ok(())
+}
+
+/// Size of each block read when estimating counts
+const SAMPLE_BLOCK_SIZE: u64 = 64 * 1024;
+
+/// Number of blocks sampled when estimating counts
+const SAMPLE_BLOCK_COUNT: u64 = 32;
+
+/// An extrapolated count with the half-width of its 95% confidence interval, or None if the whole
+/// file was counted
+#[derive(Debug, PartialEq)]
+struct Estimate {
+    value: f64,
+    margin: Option<f64>,
+}
+
+impl std::fmt::Display for Estimate {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self.margin {
+            Some(margin) => write!(f, "~{:.0} (±{:.0})", self.value, margin),
+            None => write!(f, "{:.0}", self.value),
+        }
+    }
+}
+
+fn print_estimate(args: &Cli, path: &Path, out: &mut impl Write) -> Result<()> {
+    let mut file = std::fs::File::open(path)
+        .with_context(|| format!("could not read file `{}`", path.display()))?;
+    let size = file.metadata()?.len();
+    let options = CountOptions {
+        words: args.word_rule(),
+        ..CountOptions::default()
+    };
+
+    // Small files are cheaper to count exactly than to sample, and an empty one has nothing to sample
+    let (lines, words, chars) = if size <= SAMPLE_BLOCK_SIZE * SAMPLE_BLOCK_COUNT {
+        let counts = count(BufReader::new(&mut file), &options)?;
+        let exact = |count: usize| Estimate { value: count as f64, margin: None };
+        (exact(counts.lines), exact(counts.words), exact(counts.chars))
+    } else {
+        let samples = sample_blocks(&mut file, size)?;
+        let counts = samples.iter().map(|b| count(&b[..], &options)).collect::<Result<Vec<_>>>()?;
+        let lines: Vec<f64> = counts.iter().map(|c| c.lines as f64).collect();
+        let words: Vec<f64> = counts.iter().map(|c| c.words as f64).collect();
+        let chars: Vec<f64> = counts.iter().map(|c| c.chars as f64).collect();
+        let sampled = samples.iter().map(|b| b.len() as f64).sum::<f64>() / samples.len() as f64;
+        (extrapolate(&lines, sampled, size), extrapolate(&words, sampled, size), extrapolate(&chars, sampled, size))
+    };
+    let display_path = path.display();
+
+    if args.bytes {
+        writeln!(out, "    {} {}", size, display_path)?;
+    } else if args.lines {
+        writeln!(out, "    {} {}", lines, display_path)?;
+    } else if args.words {
+        writeln!(out, "    {} {}", words, display_path)?;
+    } else if args.chars {
+        writeln!(out, "    {} {}", chars, display_path)?;
+    } else {
+        writeln!(out, "    {} {} {} {}", lines, words, chars, display_path)?;
+    }
+
+    Ok(())
+}
+
+/// Read `SAMPLE_BLOCK_COUNT` blocks spread evenly from the start to the end of the file
+fn sample_blocks(file: &mut std::fs::File, size: u64) -> Result<Vec<Vec<u8>>> {
+    let stride = (size - SAMPLE_BLOCK_SIZE) / (SAMPLE_BLOCK_COUNT - 1);
+    let mut blocks = Vec::new();
+
+    for i in 0..SAMPLE_BLOCK_COUNT {
+        let mut block = vec![0; SAMPLE_BLOCK_SIZE as usize];
+        file.seek(SeekFrom::Start(i * stride))?;
+        file.read_exact(&mut block)?;
+        blocks.push(block);
+    }
+
+    Ok(blocks)
+}
+
+/// Scale the per-block counts up to the size of the whole file.
+///
+/// The margin is derived from the spread of the per-block counts, so a file with a uniform shape
+/// gets a tight interval while a file mixing e.g. long and short lines gets a wide one.
+fn extrapolate(counts: &[f64], block_size: f64, total_size: u64) -> Estimate {
+    // Nothing was sampled, so there's nothing to scale up
+    if counts.is_empty() || block_size == 0.0 {
+        return Estimate { value: 0.0, margin: Some(0.0) };
+    }
+
+    let n = counts.len() as f64;
+    let scale = total_size as f64 / block_size;
+    let mean = counts.iter().sum::<f64>() / n;
+
+    if counts.len() < 2 {
+        return Estimate { value: mean * scale, margin: Some(0.0) };
+    }
+
+    let variance = counts.iter().map(|c| (c - mean).powi(2)).sum::<f64>() / (n - 1.0);
+    Estimate {
+        value: mean * scale,
+        margin: Some(1.96 * (variance / n).sqrt() * scale),
+    }
+}
+
+#[test]
+fn test_parse_duration() {
+    assert_eq!(parse_duration("5s").unwrap(), Duration::from_secs(5));
+    assert_eq!(parse_duration("5").unwrap(), Duration::from_secs(5));
+    assert_eq!(parse_duration("250ms").unwrap(), Duration::from_millis(250));
+    assert_eq!(parse_duration("1.5m").unwrap(), Duration::from_secs(90));
+    assert_eq!(parse_duration("2h").unwrap(), Duration::from_secs(7200));
+}
+
+#[test]
+fn test_parse_duration_invalid() {
+    assert!(parse_duration("").is_err());
+    assert!(parse_duration("5 weeks").is_err());
+    assert!(parse_duration("s").is_err());
+}
+
+#[test]
+fn test_transcode_utf16() {
+    let bytes: Vec<u8> = "caf\u{e9}\n".encode_utf16().flat_map(u16::to_le_bytes).collect();
+    let reader = transcode(Box::new(std::io::Cursor::new(bytes)), encoding_rs::UTF_16LE);
+    let counts = count(reader, &CountOptions::default()).unwrap();
+    assert_eq!(counts.chars, 5);
+    assert_eq!(counts.words, 1);
+}
+
+#[test]
+fn test_transcode_latin1() {
+    let reader = transcode(Box::new(&b"na\xefve caf\xe9\n"[..]), parse_encoding("latin1").unwrap());
+    let counts = count(reader, &CountOptions::default()).unwrap();
+    assert_eq!(counts.chars, 11);
+    assert_eq!(counts.bytes, 13);
+}
+
+#[test]
+fn test_parse_encoding_unknown() {
+    assert!(parse_encoding("klingon").is_err());
+}
+
+#[test]
+fn test_extrapolate_single_sample_is_exact() {
+    let result = extrapolate(&[42.0], 100.0, 100);
+    assert_eq!(result, Estimate { value: 42.0, margin: Some(0.0) });
+}
+
+#[test]
+fn test_extrapolate_uniform_samples() {
+    let result = extrapolate(&[10.0, 10.0, 10.0], 100.0, 1000);
+    assert_eq!(result, Estimate { value: 100.0, margin: Some(0.0) });
+}
+
+#[test]
+fn test_extrapolate_varied_samples_have_margin() {
+    let result = extrapolate(&[5.0, 15.0], 100.0, 1000);
+    assert_eq!(result.value, 100.0);
+    assert!(result.margin.unwrap() > 0.0);
+}
+
+#[test]
+fn test_extrapolate_nothing_sampled() {
+    let result = extrapolate(&[0.0], 0.0, 0);
+    assert_eq!(result.to_string(), "~0 (±0)");
+    assert_eq!(extrapolate(&[], 100.0, 1000), Estimate { value: 0.0, margin: Some(0.0) });
+}
+
+#[test]
+fn test_estimate_counted_exactly() {
+    assert_eq!(Estimate { value: 3.0, margin: None }.to_string(), "3");
+}
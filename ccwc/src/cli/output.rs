@@ -0,0 +1,92 @@
+// Destination for the counts: stdout by default, or a file given with --output
+use anyhow::{Context, Result};
+use std::{
+    fs::{File, OpenOptions},
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+pub enum Output {
+    Stdout(std::io::Stdout),
+    /// Results are written to a temporary file beside `dest` and renamed over it once complete,
+    /// so readers of `dest` never see a half-written report
+    File {
+        file: BufWriter<File>,
+        temp: PathBuf,
+        dest: PathBuf,
+    },
+}
+
+impl Output {
+    pub fn stdout() -> Output {
+        Output::Stdout(std::io::stdout())
+    }
+
+    /// Start writing to `dest`, keeping its current contents when `append` is set
+    pub fn file(dest: &Path, append: bool) -> Result<Output> {
+        let temp = temp_path(dest);
+
+        if append && dest.exists() {
+            std::fs::copy(dest, &temp)
+                .with_context(|| format!("could not copy `{}` to append to it", dest.display()))?;
+        }
+
+        let file = OpenOptions::new()
+            .write(true)
+            .create(true)
+            .append(append)
+            .truncate(!append)
+            .open(&temp)
+            .with_context(|| format!("could not create output file `{}`", temp.display()))?;
+
+        Ok(Output::File {
+            file: BufWriter::new(file),
+            temp,
+            dest: dest.to_path_buf(),
+        })
+    }
+
+    /// Flush everything written, and move a file output into place
+    pub fn finish(self) -> Result<()> {
+        match self {
+            Output::Stdout(mut stdout) => Ok(stdout.flush()?),
+            Output::File { mut file, temp, dest } => {
+                file.flush()?;
+                file.get_ref().sync_all()?;
+                std::fs::rename(&temp, &dest)
+                    .with_context(|| format!("could not write output file `{}`", dest.display()))
+            },
+        }
+    }
+
+    /// Give up on the output after an error, leaving any existing file untouched
+    pub fn discard(self) {
+        if let Output::File { file, temp, .. } = self {
+            drop(file);
+            let _ = std::fs::remove_file(temp);
+        }
+    }
+}
+
+impl Write for Output {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match self {
+            Output::Stdout(stdout) => stdout.write(buf),
+            Output::File { file, .. } => file.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        match self {
+            Output::Stdout(stdout) => stdout.flush(),
+            Output::File { file, .. } => file.flush(),
+        }
+    }
+}
+
+/// A hidden sibling of `dest`, so the final rename stays on the same filesystem
+pub fn temp_path(dest: &Path) -> PathBuf {
+    let name = dest.file_name().map(|n| n.to_string_lossy()).unwrap_or_default();
+    dest.with_file_name(format!(".{}.{}.tmp", name, std::process::id()))
+}
+
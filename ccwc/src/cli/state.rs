@@ -0,0 +1,157 @@
+// Checkpoint file for --state, so a growing file only has its new lines counted on each run
+use super::output::temp_path;
+use anyhow::{Context, Result};
+use crate::Counts;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fs::{File, Metadata},
+    io::{Read, Seek, SeekFrom},
+    path::Path,
+};
+
+/// Size of the blocks read backwards when looking for the end of the last complete line
+const SCAN_BLOCK_SIZE: u64 = 8 * 1024;
+
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct State {
+    /// Keyed by the path as given on the command line
+    pub files: BTreeMap<String, FileState>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct FileState {
+    /// How far into the file has been counted, always just after a newline
+    pub offset: u64,
+    /// The counts for everything before `offset`
+    pub counts: Counts,
+    /// Which file it was, where that can be told, so one put in its place under the same name is
+    /// counted from the start even if it's as big
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<FileId>,
+}
+
+/// The device and inode a file is on, which stay the same however it's written to and change when
+/// it's replaced by another, as log rotation does
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct FileId {
+    pub device: u64,
+    pub inode: u64,
+}
+
+impl FileId {
+    #[cfg(unix)]
+    pub fn of(metadata: &Metadata) -> Option<FileId> {
+        use std::os::unix::fs::MetadataExt;
+        Some(FileId { device: metadata.dev(), inode: metadata.ino() })
+    }
+
+    /// Elsewhere a rotated file is only noticed by its having shrunk
+    #[cfg(not(unix))]
+    pub fn of(_metadata: &Metadata) -> Option<FileId> {
+        None
+    }
+}
+
+impl State {
+    /// Load the state left by the last run, or start afresh if there wasn't one
+    pub fn load(path: &Path) -> Result<State> {
+        if !path.exists() {
+            return Ok(State::default());
+        }
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("could not read state file `{}`", path.display()))?;
+        serde_json::from_str(&content)
+            .with_context(|| format!("could not parse state file `{}`", path.display()))
+    }
+
+    /// Replace the state file in one step, so an interrupted run can't leave it half-written
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let temp = temp_path(path);
+        std::fs::write(&temp, serde_json::to_string_pretty(self)?)
+            .with_context(|| format!("could not write state file `{}`", temp.display()))?;
+        std::fs::rename(&temp, path)
+            .with_context(|| format!("could not write state file `{}`", path.display()))
+    }
+
+    /// Where to resume counting `file`. A file that's shrunk since the last run, or is another file
+    /// now, has been rotated or truncated, so it's counted again from the start.
+    pub fn resume(&self, name: &str, size: u64, id: Option<FileId>) -> FileState {
+        match self.files.get(name) {
+            Some(previous) if previous.offset <= size && (previous.id.is_none() || previous.id == id) => previous.clone(),
+            _ => FileState::default(),
+        }
+    }
+}
+
+/// Add the counts of the newly counted lines onto those from earlier runs
+pub fn merge(previous: &Counts, mut new: Counts) -> Counts {
+    // Line numbers in the new counts start over from the resume offset
+    if let Some(longest) = &mut new.longest_line {
+        longest.number += previous.lines;
+    }
+    previous.clone() + new
+}
+
+/// The offset just after the last newline between `from` and `to`, or `from` if there isn't one.
+/// A line still being written is left for the next run, so it isn't counted twice.
+pub fn last_line_end(file: &mut File, from: u64, to: u64) -> Result<u64> {
+    let mut end = to;
+    let mut block = vec![0; SCAN_BLOCK_SIZE as usize];
+
+    while end > from {
+        let start = end.saturating_sub(SCAN_BLOCK_SIZE).max(from);
+        let block = &mut block[..(end - start) as usize];
+        file.seek(SeekFrom::Start(start))?;
+        file.read_exact(block)?;
+        if let Some(i) = block.iter().rposition(|&b| b == b'\n') {
+            return Ok(start + i as u64 + 1);
+        }
+        end = start;
+    }
+
+    Ok(from)
+}
+
+#[test]
+fn test_resume_new_file() {
+    let state = State::default();
+    assert_eq!(state.resume("log", 100, None), FileState::default());
+}
+
+#[test]
+fn test_resume_grown_file() {
+    let mut state = State::default();
+    let id = Some(FileId { device: 1, inode: 2 });
+    let previous = FileState { offset: 50, counts: Counts { lines: 5, ..Counts::default() }, id };
+    state.files.insert("log".to_string(), previous.clone());
+    assert_eq!(state.resume("log", 100, id), previous);
+}
+
+#[test]
+fn test_resume_rotated_file() {
+    let mut state = State::default();
+    state.files.insert("log".to_string(), FileState { offset: 50, ..FileState::default() });
+    assert_eq!(state.resume("log", 10, None), FileState::default());
+}
+
+#[test]
+fn test_resume_replaced_file() {
+    let mut state = State::default();
+    let id = FileId { device: 1, inode: 2 };
+    state.files.insert("log".to_string(), FileState { offset: 50, id: Some(id), ..FileState::default() });
+    assert_eq!(state.resume("log", 100, Some(FileId { inode: 3, ..id })), FileState::default());
+}
+
+#[test]
+fn test_merge_offsets_longest_line() {
+    let previous = Counts { lines: 10, ..Counts::default() };
+    let new = Counts {
+        lines: 2,
+        longest_line: Some(crate::LongestLine { number: 1, width: 5, preview: "lorem".to_string() }),
+        ..Counts::default()
+    };
+    let result = merge(&previous, new);
+    assert_eq!(result.lines, 12);
+    assert_eq!(result.longest_line.unwrap().number, 11);
+}
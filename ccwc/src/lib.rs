@@ -0,0 +1,569 @@
+// Counting core of ccwc, usable without the command line interface.
+// The command line interface itself is exposed as `main` so ccbox can run it too.
+// My answer to:  https://codingchallenges.substack.com/p/coding-challenge-1
+use anyhow::{bail, Result};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::io::{BufRead, Read};
+use std::ops::{Add, AddAssign};
+use std::time::{Duration, Instant};
+use unicode_width::UnicodeWidthChar;
+
+mod cli;
+
+pub use cc_io::is_binary;
+pub use cli::main;
+
+/// The counts for one input, or the total over several inputs
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Counts {
+    pub lines: usize,
+    pub words: usize,
+    pub chars: usize,
+    pub bytes: usize,
+    /// Lines with nothing but a line terminator
+    pub blank_lines: usize,
+    /// Lines with only spaces, tabs, etc.
+    pub whitespace_lines: usize,
+    /// Lines with at least one word
+    pub content_lines: usize,
+    /// The widest line, or the first of them if there's a tie
+    pub longest_line: Option<LongestLine>,
+}
+
+/// Number of characters of the longest line kept as a preview
+pub const PREVIEW_CHARS: usize = 60;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct LongestLine {
+    /// Line number, starting from 1
+    pub number: usize,
+    /// Display width, as measured by `wc -L`
+    pub width: usize,
+    /// The start of the line, at most `PREVIEW_CHARS` characters
+    pub preview: String,
+}
+
+impl Counts {
+    /// The display width of the widest line, or 0 for empty input
+    pub fn max_line_length(&self) -> usize {
+        self.longest_line.as_ref().map_or(0, |l| l.width)
+    }
+}
+
+impl Add for Counts {
+    type Output = Counts;
+
+    fn add(mut self, other: Counts) -> Counts {
+        self += other;
+        self
+    }
+}
+
+impl AddAssign for Counts {
+    fn add_assign(&mut self, other: Counts) {
+        self.lines += other.lines;
+        self.words += other.words;
+        self.chars += other.chars;
+        self.bytes += other.bytes;
+        self.blank_lines += other.blank_lines;
+        self.whitespace_lines += other.whitespace_lines;
+        self.content_lines += other.content_lines;
+        if other.max_line_length() > self.max_line_length() {
+            self.longest_line = other.longest_line;
+        }
+    }
+}
+
+impl std::iter::Sum for Counts {
+    fn sum<I: Iterator<Item = Counts>>(iter: I) -> Counts {
+        iter.fold(Counts::default(), Add::add)
+    }
+}
+
+impl<'a> std::iter::Sum<&'a Counts> for Counts {
+    fn sum<I: Iterator<Item = &'a Counts>>(iter: I) -> Counts {
+        iter.cloned().sum()
+    }
+}
+
+/// The longest line allowed in the input, set via `--max-line-bytes`
+#[derive(Clone, Copy)]
+pub struct LineLimit {
+    pub max_bytes: usize,
+    /// Warn about each line over the limit instead of giving up at the first one
+    pub report_only: bool,
+}
+
+/// Reads the input a line at a time, enforcing the line limit if there is one
+pub struct LineReader<R> {
+    reader: R,
+    limit: Option<LineLimit>,
+    line_number: usize,
+}
+
+impl<R: BufRead> LineReader<R> {
+    pub fn new(reader: R, limit: Option<LineLimit>) -> LineReader<R> {
+        LineReader {
+            reader,
+            limit,
+            line_number: 0,
+        }
+    }
+
+    /// Replace `line` with the next line of input, including its newline.
+    /// Returns false once the input is exhausted.
+    pub fn read_line(&mut self, line: &mut Vec<u8>) -> Result<bool> {
+        line.clear();
+
+        let read = match self.limit {
+            // Stop buffering just past the limit, so a file missing its newlines can't exhaust
+            // memory
+            Some(limit) if !limit.report_only => {
+                (&mut self.reader).take(limit.max_bytes as u64 + 1).read_until(b'\n', line)?
+            },
+            _ => self.reader.read_until(b'\n', line)?,
+        };
+        if read == 0 {
+            return Ok(false);
+        }
+        self.line_number += 1;
+
+        if let Some(limit) = self.limit {
+            let length = line.strip_suffix(b"\n").unwrap_or(line).len();
+            if length > limit.max_bytes {
+                if !limit.report_only {
+                    bail!("line {} is longer than the limit of {} bytes", self.line_number, limit.max_bytes);
+                }
+                eprintln!(
+                    "ccwc: line {} is {} bytes, longer than the limit of {} bytes",
+                    self.line_number, length, limit.max_bytes,
+                );
+            }
+        }
+
+        Ok(true)
+    }
+
+    /// The number of the line last read, starting from 1
+    pub fn line_number(&self) -> usize {
+        self.line_number
+    }
+}
+
+/// What counts as a word
+#[derive(Clone, Default)]
+pub enum WordRule {
+    /// Runs of non-whitespace, like wc
+    #[default]
+    Whitespace,
+    /// Runs of non-whitespace, except that each Han, Kana, or Hangul character is a word by itself
+    Cjk,
+    /// Each match of the regex, e.g. `[A-Za-z0-9_]+` for identifiers. Matches can't span lines.
+    Regex(Regex),
+}
+
+#[derive(Default)]
+pub struct CountOptions {
+    pub limit: Option<LineLimit>,
+    pub words: WordRule,
+}
+
+/// Count the input a line at a time, so only the longest line needs to fit in memory
+pub fn count(reader: impl BufRead, options: &CountOptions) -> Result<Counts> {
+    count_with_progress(reader, options, None, |_| {})
+}
+
+/// Like `count`, but calls `report` with the counts so far every `interval` while counting.
+///
+/// The clock is only checked between lines, so a stalled input delays the next report until more
+/// data arrives.
+pub fn count_with_progress(
+    reader: impl BufRead,
+    options: &CountOptions,
+    interval: Option<Duration>,
+    mut report: impl FnMut(&Counts),
+) -> Result<Counts> {
+    let mut counts = Counts::default();
+    let mut reader = LineReader::new(reader, options.limit);
+    let mut line = Vec::new();
+    let mut last_report = Instant::now();
+
+    while reader.read_line(&mut line)? {
+        if let Some(interval) = interval {
+            if last_report.elapsed() >= interval {
+                report(&counts);
+                last_report = Instant::now();
+            }
+        }
+
+        let text = String::from_utf8_lossy(&line);
+        counts.lines += 1;
+        counts.words += match &options.words {
+            WordRule::Whitespace => count_words(&text),
+            WordRule::Cjk => count_words_cjk(&text),
+            WordRule::Regex(regex) => regex.find_iter(&text).count(),
+        };
+        counts.chars += text.chars().count();
+        counts.bytes += line.len();
+
+        let content = line.strip_suffix(b"\n").unwrap_or(&line);
+        let content = content.strip_suffix(b"\r").unwrap_or(content);
+        if content.is_empty() {
+            counts.blank_lines += 1;
+        } else if content.iter().all(u8::is_ascii_whitespace) {
+            counts.whitespace_lines += 1;
+        } else {
+            counts.content_lines += 1;
+        }
+
+        let width = display_width(&String::from_utf8_lossy(content));
+        if width > counts.max_line_length() {
+            counts.longest_line = Some(LongestLine {
+                number: reader.line_number(),
+                width,
+                preview: String::from_utf8_lossy(content).chars().take(PREVIEW_CHARS).collect(),
+            });
+        }
+    }
+
+    Ok(counts)
+}
+
+pub fn count_words(content: &str) -> usize {
+    let mut total_word_count = 0;
+    let mut word_char_count = 0;
+
+    // Iterate over string char by char.
+    // If we encounter a word char, mark that we're in a word.
+    // If we encounter a non-word char, and we are in a word, then we've encountered the end of the
+    // word.
+    for ch in content.chars() {
+        if ch.is_ascii_whitespace() {
+            if word_char_count > 0 {
+                total_word_count += 1;
+                word_char_count = 0;
+            }
+        } else {
+            word_char_count += 1;
+        }
+    }
+
+    if word_char_count > 0 {
+        total_word_count += 1;
+    }
+
+    total_word_count
+}
+
+/// Like `count_words`, but every CJK character is a word by itself since those scripts aren't
+/// written with spaces between words
+pub fn count_words_cjk(content: &str) -> usize {
+    let mut total_word_count = 0;
+    let mut word_char_count = 0;
+
+    for ch in content.chars() {
+        if ch.is_ascii_whitespace() || is_cjk(ch) {
+            if word_char_count > 0 {
+                total_word_count += 1;
+                word_char_count = 0;
+            }
+            if is_cjk(ch) {
+                total_word_count += 1;
+            }
+        } else {
+            word_char_count += 1;
+        }
+    }
+
+    if word_char_count > 0 {
+        total_word_count += 1;
+    }
+
+    total_word_count
+}
+
+/// Whether a character is a Han ideograph, Hiragana, Katakana, or Hangul
+pub fn is_cjk(ch: char) -> bool {
+    matches!(ch,
+        '\u{1100}'..='\u{11FF}'       // Hangul Jamo
+        | '\u{3040}'..='\u{309F}'     // Hiragana
+        | '\u{30A0}'..='\u{30FF}'     // Katakana
+        | '\u{3130}'..='\u{318F}'     // Hangul Compatibility Jamo
+        | '\u{31F0}'..='\u{31FF}'     // Katakana Phonetic Extensions
+        | '\u{3400}'..='\u{4DBF}'     // CJK Unified Ideographs Extension A
+        | '\u{4E00}'..='\u{9FFF}'     // CJK Unified Ideographs
+        | '\u{AC00}'..='\u{D7AF}'     // Hangul Syllables
+        | '\u{F900}'..='\u{FAFF}'     // CJK Compatibility Ideographs
+        | '\u{FF66}'..='\u{FF9F}'     // Halfwidth Katakana
+        | '\u{20000}'..='\u{2FA1F}'   // CJK Unified Ideographs Extensions B onwards
+    )
+}
+
+/// Count the occurrences of `pattern` in the input.
+///
+/// The input is searched a buffer at a time. The tail of each buffer is carried over into the next
+/// search so that occurrences straddling a buffer boundary are still found.
+pub fn count_occurrences(mut reader: impl BufRead, pattern: &[u8], overlapping: bool) -> Result<usize> {
+    if pattern.is_empty() {
+        bail!("the pattern to count must not be empty");
+    }
+
+    let finder = memchr::memmem::Finder::new(pattern);
+    let step = if overlapping { 1 } else { pattern.len() };
+    let mut occurrences = 0;
+    let mut window: Vec<u8> = Vec::new();
+    // Where the next occurrence may start, relative to the start of the window
+    let mut next = 0;
+
+    loop {
+        let chunk = reader.fill_buf()?;
+        if chunk.is_empty() {
+            break;
+        }
+        window.extend_from_slice(chunk);
+        let consumed = chunk.len();
+        reader.consume(consumed);
+
+        while let Some(found) = finder.find(&window[next..]) {
+            occurrences += 1;
+            next += found + step;
+            if next > window.len() {
+                break;
+            }
+        }
+
+        // Anything before the last `pattern.len() - 1` bytes can't start an occurrence any more
+        let keep_from = window.len().saturating_sub(pattern.len() - 1);
+        window.drain(..keep_from);
+        next = next.saturating_sub(keep_from);
+    }
+
+    Ok(occurrences)
+}
+
+/// How `--per-line` measures the length of a line
+#[derive(Clone, Copy)]
+pub enum LineMeasure {
+    Bytes,
+    Chars,
+    Width,
+}
+
+/// The length of a line, not counting its line terminator
+pub fn line_length(line: &[u8], measure: LineMeasure) -> usize {
+    let line = line.strip_suffix(b"\n").unwrap_or(line);
+    let line = line.strip_suffix(b"\r").unwrap_or(line);
+
+    match measure {
+        LineMeasure::Bytes => line.len(),
+        LineMeasure::Chars => String::from_utf8_lossy(line).chars().count(),
+        LineMeasure::Width => display_width(&String::from_utf8_lossy(line)),
+    }
+}
+
+/// The number of terminal columns a line takes up, with tabs stopping every 8 columns like `wc -L`
+pub fn display_width(line: &str) -> usize {
+    line.chars().fold(0, |width, ch| match ch {
+        '\t' => width + 8 - width % 8,
+        _ => width + ch.width().unwrap_or(0),
+    })
+}
+
+#[test]
+fn test_count_words_empty_string() {
+    let result = count_words("");
+    assert_eq!(result, 0);
+}
+
+#[test]
+fn test_count_words() {
+    let result = count_words("lorem ipsum dolor sit amet");
+    assert_eq!(result, 5);
+}
+
+#[test]
+fn test_count_words_cjk() {
+    assert_eq!(count_words_cjk("lorem ipsum"), 2);
+    assert_eq!(count_words_cjk("\u{6211}\u{7231}\u{4f60}"), 3);
+    assert_eq!(count_words_cjk("\u{3053}\u{308c}\u{306f}Rust\u{3067}\u{3059}"), 6);
+    assert_eq!(count_words_cjk("\u{d55c}\u{ad6d}\u{c5b4} text"), 4);
+}
+
+#[test]
+fn test_count_words_regex() {
+    let options = CountOptions {
+        words: WordRule::Regex(Regex::new("[A-Za-z0-9_]+").unwrap()),
+        ..CountOptions::default()
+    };
+    let result = count(&b"let x_1 = foo(bar, 42);\n"[..], &options).unwrap();
+    assert_eq!(result.words, 5);
+}
+
+#[test]
+fn test_count() {
+    let result = count(&b"lorem ipsum\ndolor sit amet\n"[..], &CountOptions::default()).unwrap();
+    assert_eq!(result, Counts {
+        lines: 2,
+        words: 5,
+        chars: 27,
+        bytes: 27,
+        blank_lines: 0,
+        whitespace_lines: 0,
+        content_lines: 2,
+        longest_line: Some(LongestLine { number: 2, width: 14, preview: "dolor sit amet".to_string() }),
+    });
+}
+
+#[test]
+fn test_count_last_line_without_newline() {
+    let result = count(&b"lorem\nipsum"[..], &CountOptions::default()).unwrap();
+    assert_eq!(result, Counts {
+        lines: 2,
+        words: 2,
+        chars: 11,
+        bytes: 11,
+        blank_lines: 0,
+        whitespace_lines: 0,
+        content_lines: 2,
+        longest_line: Some(LongestLine { number: 1, width: 5, preview: "lorem".to_string() }),
+    });
+}
+
+#[test]
+fn test_count_occurrences() {
+    let result = count_occurrences(&b"lorem ipsum dolor sit amet"[..], b"or", false).unwrap();
+    assert_eq!(result, 2);
+}
+
+#[test]
+fn test_count_occurrences_overlapping() {
+    assert_eq!(count_occurrences(&b"aaaa"[..], b"aa", false).unwrap(), 2);
+    assert_eq!(count_occurrences(&b"aaaa"[..], b"aa", true).unwrap(), 3);
+}
+
+#[test]
+fn test_count_occurrences_across_buffers() {
+    // A tiny buffer forces occurrences to straddle reads
+    let reader = std::io::BufReader::with_capacity(3, &b"abcabcabc"[..]);
+    assert_eq!(count_occurrences(reader, b"cab", false).unwrap(), 2);
+    let reader = std::io::BufReader::with_capacity(2, &b"aaaaa"[..]);
+    assert_eq!(count_occurrences(reader, b"aaa", false).unwrap(), 1);
+    let reader = std::io::BufReader::with_capacity(2, &b"aaaaa"[..]);
+    assert_eq!(count_occurrences(reader, b"aaa", true).unwrap(), 3);
+}
+
+#[test]
+fn test_counts_add() {
+    let a = Counts { lines: 1, words: 2, chars: 3, bytes: 4, ..Counts::default() };
+    let b = Counts { lines: 10, words: 20, chars: 30, bytes: 40, content_lines: 1, ..Counts::default() };
+    let mut c = a.clone() + b.clone();
+    assert_eq!(c, Counts { lines: 11, words: 22, chars: 33, bytes: 44, content_lines: 1, ..Counts::default() });
+    c += a.clone();
+    assert_eq!(c.lines, 12);
+    assert_eq!([a.clone(), b.clone()].iter().sum::<Counts>(), a + b);
+}
+
+#[test]
+fn test_counts_add_keeps_longest_line() {
+    let short = count(&b"lorem\n"[..], &CountOptions::default()).unwrap();
+    let long = count(&b"a\nlorem ipsum\n"[..], &CountOptions::default()).unwrap();
+    let total = short.clone() + long.clone();
+    assert_eq!(total.longest_line, long.longest_line);
+    let total = long.clone() + short;
+    assert_eq!(total.longest_line, long.longest_line);
+}
+
+#[test]
+fn test_count_longest_line_preview_is_truncated() {
+    let line = "x".repeat(PREVIEW_CHARS * 2);
+    let result = count(line.as_bytes(), &CountOptions::default()).unwrap();
+    assert_eq!(result.max_line_length(), PREVIEW_CHARS * 2);
+    assert_eq!(result.longest_line.unwrap().preview.len(), PREVIEW_CHARS);
+}
+
+#[test]
+fn test_counts_sum_empty() {
+    assert_eq!(Vec::<Counts>::new().into_iter().sum::<Counts>(), Counts::default());
+}
+
+#[test]
+fn test_counts_serde_round_trip() {
+    let counts = Counts { lines: 1, words: 2, chars: 3, bytes: 4, ..Counts::default() };
+    let json = serde_json::to_string(&counts).unwrap();
+    assert_eq!(serde_json::from_str::<Counts>(&json).unwrap(), counts);
+}
+
+#[test]
+fn test_count_with_progress_reports() {
+    let mut reports = Vec::new();
+    let options = CountOptions::default();
+    let interval = Some(Duration::ZERO);
+    let result = count_with_progress(&b"lorem\nipsum\n"[..], &options, interval, |c| reports.push(c.lines)).unwrap();
+    assert_eq!(result.lines, 2);
+    assert_eq!(reports, [0, 1]);
+}
+
+#[test]
+fn test_count_classifies_lines() {
+    let result = count(&b"lorem\n\n  \t\r\n\r\nipsum\n \n"[..], &CountOptions::default()).unwrap();
+    assert_eq!(result.lines, 6);
+    assert_eq!(result.blank_lines, 2);
+    assert_eq!(result.whitespace_lines, 2);
+    assert_eq!(result.content_lines, 2);
+}
+
+#[test]
+fn test_count_within_line_limit() {
+    let limit = LineLimit { max_bytes: 5, report_only: false };
+    let result = count(&b"lorem\nipsum\n"[..], &CountOptions { limit: Some(limit), ..CountOptions::default() }).unwrap();
+    assert_eq!(result.lines, 2);
+}
+
+#[test]
+fn test_count_over_line_limit() {
+    let limit = LineLimit { max_bytes: 4, report_only: false };
+    let result = count(&b"lorem\nipsum\n"[..], &CountOptions { limit: Some(limit), ..CountOptions::default() });
+    assert!(result.is_err());
+}
+
+#[test]
+fn test_count_reports_long_lines() {
+    let limit = LineLimit { max_bytes: 4, report_only: true };
+    let result = count(&b"lorem\nipsum\n"[..], &CountOptions { limit: Some(limit), ..CountOptions::default() }).unwrap();
+    assert_eq!(result.lines, 2);
+}
+
+#[test]
+fn test_is_binary() {
+    assert!(!is_binary(&mut &b"lorem ipsum\n"[..]).unwrap());
+    assert!(is_binary(&mut &b"\x7fELF\x02\x01\x00\x00"[..]).unwrap());
+}
+
+#[test]
+fn test_is_binary_does_not_consume() {
+    let mut reader = &b"lorem\x00"[..];
+    is_binary(&mut reader).unwrap();
+    assert_eq!(count(reader, &CountOptions::default()).unwrap().bytes, 6);
+}
+
+#[test]
+fn test_line_length_ignores_terminator() {
+    assert_eq!(line_length(b"lorem\r\n", LineMeasure::Bytes), 5);
+    assert_eq!(line_length(b"lorem\n", LineMeasure::Bytes), 5);
+    assert_eq!(line_length(b"lorem", LineMeasure::Bytes), 5);
+}
+
+#[test]
+fn test_line_length_measures() {
+    let line = "caf\u{e9} \u{4e16}\u{754c}\n".as_bytes();
+    assert_eq!(line_length(line, LineMeasure::Bytes), 12);
+    assert_eq!(line_length(line, LineMeasure::Chars), 7);
+    assert_eq!(line_length(line, LineMeasure::Width), 9);
+}
+
+#[test]
+fn test_display_width_expands_tabs() {
+    assert_eq!(display_width("a\tb"), 9);
+    assert_eq!(display_width("\t"), 8);
+}
@@ -0,0 +1,53 @@
+use crate::{serve, DEFAULT_PORT};
+use anyhow::{Context, Result};
+use clap::Parser;
+use std::ffi::OsString;
+use std::net::TcpListener;
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+/// webserver - serve static files over HTTP
+#[derive(Parser)]
+struct Cli {
+    /// The address to listen on
+    #[arg(long, default_value = "127.0.0.1")]
+    bind: String,
+
+    /// The port to listen on, or 0 to pick a free one
+    #[arg(short, long, default_value_t = DEFAULT_PORT)]
+    port: u16,
+
+    /// The directory to serve files from
+    #[arg(default_value = ".")]
+    root: PathBuf,
+}
+
+/// Run ccwebserver with the given command line, `args[0]` being the program name
+pub fn main<I, T>(args: I) -> ExitCode
+where
+    I: IntoIterator<Item = T>,
+    T: Into<OsString> + Clone,
+{
+    let args: Cli = cc_cli::parse_from(args, env!("CARGO_PKG_VERSION"));
+
+    match run(args) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("Error: {:?}", e);
+            ExitCode::FAILURE
+        },
+    }
+}
+
+fn run(args: Cli) -> Result<()> {
+    if !args.root.is_dir() {
+        anyhow::bail!("`{}` is not a directory", args.root.display());
+    }
+    let address = (args.bind.as_str(), args.port);
+    let listener = TcpListener::bind(address).with_context(|| format!("could not listen on {}:{}", args.bind, args.port))?;
+    // Give the real address, which is how to find the port when asking for any free one
+    eprintln!("Serving {} on http://{}/", args.root.display(), listener.local_addr()?);
+
+    serve(listener, &args.root)?;
+    Ok(())
+}
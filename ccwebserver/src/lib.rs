@@ -0,0 +1,406 @@
+// Static file web server
+// Serves GET and HEAD requests for files under a document root over HTTP/1.1, one thread per
+// connection, keeping connections open between requests unless the client asks otherwise.
+// Reference:  https://www.rfc-editor.org/rfc/rfc9112
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, ErrorKind, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+mod cli;
+
+pub use cli::main;
+
+pub const DEFAULT_PORT: u16 = 8080;
+
+/// Longest request line or header accepted, and the most headers
+const MAX_LINE_LENGTH: usize = 8 * 1024;
+const MAX_HEADERS: usize = 100;
+
+/// How long an idle connection is kept open waiting for another request
+pub const KEEP_ALIVE_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Request {
+    pub method: String,
+    pub target: String,
+    pub version: String,
+    /// Names are lowercased
+    pub headers: Vec<(String, String)>,
+}
+
+impl Request {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.iter().find(|(n, _)| n == name).map(|(_, value)| value.as_str())
+    }
+
+    /// Whether the client wants the connection kept open after this request
+    pub fn keep_alive(&self) -> bool {
+        let connection = self.header("connection").map(str::to_ascii_lowercase);
+        match self.version.as_str() {
+            "HTTP/1.0" => connection.as_deref() == Some("keep-alive"),
+            _ => connection.as_deref() != Some("close"),
+        }
+    }
+}
+
+/// A response status, with the reason phrase sent beside it
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Status(pub u16, pub &'static str);
+
+impl Status {
+    pub const OK: Status = Status(200, "OK");
+    pub const BAD_REQUEST: Status = Status(400, "Bad Request");
+    pub const FORBIDDEN: Status = Status(403, "Forbidden");
+    pub const NOT_FOUND: Status = Status(404, "Not Found");
+    pub const METHOD_NOT_ALLOWED: Status = Status(405, "Method Not Allowed");
+    pub const HEADERS_TOO_LARGE: Status = Status(431, "Request Header Fields Too Large");
+    pub const VERSION_NOT_SUPPORTED: Status = Status(505, "HTTP Version Not Supported");
+}
+
+/// Why a request couldn't be read
+#[derive(Debug)]
+pub enum RequestError {
+    Io(io::Error),
+    Malformed(&'static str),
+    /// A line or the headers went over the limits
+    TooLarge,
+}
+
+impl From<io::Error> for RequestError {
+    fn from(e: io::Error) -> RequestError {
+        RequestError::Io(e)
+    }
+}
+
+impl std::fmt::Display for RequestError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            RequestError::Io(e) => write!(f, "{}", e),
+            RequestError::Malformed(reason) => write!(f, "malformed request: {}", reason),
+            RequestError::TooLarge => f.write_str("request headers too large"),
+        }
+    }
+}
+
+impl std::error::Error for RequestError {}
+
+/// Read a line ending in CRLF, or a bare LF as RFC 9112 allows, without the line ending
+fn read_line(reader: &mut impl BufRead) -> Result<Option<String>, RequestError> {
+    let mut line = Vec::new();
+    if reader.take(MAX_LINE_LENGTH as u64 + 1).read_until(b'\n', &mut line)? == 0 {
+        return Ok(None);
+    }
+    if !line.ends_with(b"\n") {
+        return Err(match line.len() > MAX_LINE_LENGTH {
+            true => RequestError::TooLarge,
+            false => RequestError::Io(ErrorKind::UnexpectedEof.into()),
+        });
+    }
+    line.pop();
+    if line.ends_with(b"\r") {
+        line.pop();
+    }
+    String::from_utf8(line).map(Some).map_err(|_| RequestError::Malformed("line is not UTF-8"))
+}
+
+/// Read a request's line and headers, or None if the client closed the connection before
+/// starting one. Bodies are never needed to serve files, so requests with one are refused.
+pub fn read_request(reader: &mut impl BufRead) -> Result<Option<Request>, RequestError> {
+    // Clients may send blank lines between requests
+    let line = loop {
+        match read_line(reader)? {
+            None => return Ok(None),
+            Some(line) if line.is_empty() => continue,
+            Some(line) => break line,
+        }
+    };
+
+    let mut parts = line.split(' ');
+    let (Some(method), Some(target), Some(version), None) = (parts.next(), parts.next(), parts.next(), parts.next()) else {
+        return Err(RequestError::Malformed("request line"));
+    };
+
+    let mut headers = Vec::new();
+    loop {
+        let Some(line) = read_line(reader)? else {
+            return Err(RequestError::Io(ErrorKind::UnexpectedEof.into()));
+        };
+        if line.is_empty() {
+            break;
+        }
+        if headers.len() == MAX_HEADERS {
+            return Err(RequestError::TooLarge);
+        }
+        let (name, value) = line.split_once(':').ok_or(RequestError::Malformed("header"))?;
+        headers.push((name.to_ascii_lowercase(), value.trim().to_string()));
+    }
+
+    Ok(Some(Request {
+        method: method.to_string(),
+        target: target.to_string(),
+        version: version.to_string(),
+        headers,
+    }))
+}
+
+/// The media type to send for a file, from its extension
+pub fn content_type(path: &Path) -> &'static str {
+    let extension = path.extension().map(|e| e.to_string_lossy().to_ascii_lowercase());
+    match extension.as_deref() {
+        Some("html" | "htm") => "text/html; charset=utf-8",
+        Some("css") => "text/css; charset=utf-8",
+        Some("js" | "mjs") => "text/javascript; charset=utf-8",
+        Some("json") => "application/json",
+        Some("txt") => "text/plain; charset=utf-8",
+        Some("xml") => "application/xml",
+        Some("svg") => "image/svg+xml",
+        Some("png") => "image/png",
+        Some("jpg" | "jpeg") => "image/jpeg",
+        Some("gif") => "image/gif",
+        Some("webp") => "image/webp",
+        Some("ico") => "image/x-icon",
+        Some("wasm") => "application/wasm",
+        Some("pdf") => "application/pdf",
+        _ => "application/octet-stream",
+    }
+}
+
+/// Undo %XX escapes in a URL path
+fn percent_decode(path: &str) -> Option<String> {
+    let bytes = path.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let hex = std::str::from_utf8(bytes.get(i + 1..i + 3)?).ok()?;
+            decoded.push(u8::from_str_radix(hex, 16).ok()?);
+            i += 3;
+        } else {
+            decoded.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8(decoded).ok()
+}
+
+/// Find the file a request target names under `root`, which must be canonical. Paths that climb
+/// out of the root, even through a symlink, are forbidden, and a directory means the
+/// `index.html` in it.
+pub fn resolve(root: &Path, target: &str) -> Result<PathBuf, Status> {
+    let path = target.split(['?', '#']).next().unwrap_or_default();
+    if !path.starts_with('/') {
+        return Err(Status::BAD_REQUEST);
+    }
+    let path = percent_decode(path).ok_or(Status::BAD_REQUEST)?;
+
+    let mut file = root.to_path_buf();
+    for component in Path::new(&path).components() {
+        match component {
+            Component::Normal(part) => file.push(part),
+            Component::RootDir | Component::CurDir => {},
+            Component::ParentDir | Component::Prefix(_) => return Err(Status::FORBIDDEN),
+        }
+    }
+
+    let file = match file.canonicalize() {
+        Ok(file) => file,
+        Err(e) if e.kind() == ErrorKind::PermissionDenied => return Err(Status::FORBIDDEN),
+        Err(_) => return Err(Status::NOT_FOUND),
+    };
+    if !file.starts_with(root) {
+        return Err(Status::FORBIDDEN);
+    }
+    match file.is_dir() {
+        true if file.join("index.html").is_file() => Ok(file.join("index.html")),
+        true => Err(Status::FORBIDDEN),
+        false => Ok(file),
+    }
+}
+
+/// Write a status line and headers, then the body unless it's the answer to a HEAD request
+pub fn write_response(
+    out: &mut impl Write,
+    status: Status,
+    headers: &[(&str, &str)],
+    body: &mut impl Read,
+    length: u64,
+    head: bool,
+) -> io::Result<()> {
+    write!(out, "HTTP/1.1 {} {}\r\n", status.0, status.1)?;
+    for (name, value) in headers {
+        write!(out, "{}: {}\r\n", name, value)?;
+    }
+    write!(out, "Content-Length: {}\r\n\r\n", length)?;
+    if !head {
+        io::copy(&mut body.take(length), out)?;
+    }
+    out.flush()
+}
+
+/// Answer with a short HTML page explaining the status
+fn write_error(out: &mut impl Write, status: Status, head: bool, close: bool) -> io::Result<()> {
+    let body = format!("<html><body><h1>{} {}</h1></body></html>\n", status.0, status.1);
+    let mut headers = vec![("Content-Type", "text/html; charset=utf-8")];
+    if status == Status::METHOD_NOT_ALLOWED {
+        headers.push(("Allow", "GET, HEAD"));
+    }
+    if close {
+        headers.push(("Connection", "close"));
+    }
+    write_response(out, status, &headers, &mut body.as_bytes(), body.len() as u64, head)
+}
+
+/// Answer one request, returning whether the connection can stay open
+fn respond(out: &mut impl Write, root: &Path, request: &Request) -> io::Result<bool> {
+    let keep_alive = request.keep_alive();
+    let head = request.method == "HEAD";
+
+    if request.version != "HTTP/1.1" && request.version != "HTTP/1.0" {
+        write_error(out, Status::VERSION_NOT_SUPPORTED, head, true)?;
+        return Ok(false);
+    }
+    // Bodies aren't read, so the next request can't be found after one and the connection has to
+    // close
+    let has_body =
+        request.header("content-length").is_some_and(|n| n != "0") || request.header("transfer-encoding").is_some();
+    if request.method != "GET" && !head {
+        let reuse = keep_alive && !has_body;
+        write_error(out, Status::METHOD_NOT_ALLOWED, false, !reuse)?;
+        return Ok(reuse);
+    }
+    if has_body {
+        write_error(out, Status::BAD_REQUEST, head, true)?;
+        return Ok(false);
+    }
+
+    let opened = resolve(root, &request.target).and_then(|path| match File::open(&path) {
+        Ok(file) => Ok((path, file)),
+        Err(e) if e.kind() == ErrorKind::PermissionDenied => Err(Status::FORBIDDEN),
+        Err(_) => Err(Status::NOT_FOUND),
+    });
+    let (path, file) = match opened {
+        Ok(opened) => opened,
+        Err(status) => {
+            write_error(out, status, head, !keep_alive)?;
+            return Ok(keep_alive);
+        },
+    };
+
+    let length = file.metadata()?.len();
+    let mut headers = vec![("Content-Type", content_type(&path))];
+    if !keep_alive {
+        headers.push(("Connection", "close"));
+    }
+    write_response(out, Status::OK, &headers, &mut BufReader::new(file), length, head)?;
+    Ok(keep_alive)
+}
+
+/// Answer requests from one client until it disconnects or wants the connection closed
+pub fn handle_connection(stream: TcpStream, root: &Path) -> io::Result<()> {
+    stream.set_read_timeout(Some(KEEP_ALIVE_TIMEOUT))?;
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut writer = BufWriter::new(stream);
+
+    loop {
+        let request = match read_request(&mut reader) {
+            Ok(Some(request)) => request,
+            Ok(None) => return Ok(()),
+            Err(RequestError::Io(e)) if e.kind() == ErrorKind::WouldBlock || e.kind() == ErrorKind::TimedOut => {
+                return Ok(());
+            },
+            Err(RequestError::Io(e)) => return Err(e),
+            Err(RequestError::Malformed(_)) => return write_error(&mut writer, Status::BAD_REQUEST, false, true),
+            Err(RequestError::TooLarge) => return write_error(&mut writer, Status::HEADERS_TOO_LARGE, false, true),
+        };
+        if !respond(&mut writer, root, &request)? {
+            return Ok(());
+        }
+    }
+}
+
+/// Accept connections forever, serving files under `root` to each on its own thread
+pub fn serve(listener: TcpListener, root: &Path) -> io::Result<()> {
+    let root: Arc<Path> = root.canonicalize()?.into();
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            // The client gave up before we got to it
+            Err(e) if e.kind() == ErrorKind::ConnectionAborted => continue,
+            Err(e) => return Err(e),
+        };
+        let root = Arc::clone(&root);
+        thread::spawn(move || {
+            if let Err(e) = handle_connection(stream, &root) {
+                if e.kind() != ErrorKind::ConnectionReset && e.kind() != ErrorKind::BrokenPipe {
+                    eprintln!("Error: {}", e);
+                }
+            }
+        });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn root() -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("testinputs/www").canonicalize().unwrap()
+    }
+
+    #[test]
+    fn check_read_request() {
+        let mut input = &b"GET /a%20b.txt?x=1 HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n"[..];
+        let request = read_request(&mut input).unwrap().unwrap();
+        assert_eq!(request.method, "GET");
+        assert_eq!(request.target, "/a%20b.txt?x=1");
+        assert_eq!(request.header("host"), Some("localhost"));
+        assert!(!request.keep_alive());
+        assert_eq!(read_request(&mut input).unwrap(), None);
+    }
+
+    #[test]
+    fn check_read_request_malformed() {
+        assert!(read_request(&mut &b"GET /\r\n\r\n"[..]).is_err());
+        assert!(read_request(&mut &b"GET / HTTP/1.1\r\nno colon\r\n\r\n"[..]).is_err());
+        assert!(read_request(&mut &b"GET / HTTP/1.1\r\nHost: x\r\n"[..]).is_err());
+    }
+
+    #[test]
+    fn check_keep_alive() {
+        let request = |version: &str, connection: Option<&str>| Request {
+            method: "GET".to_string(),
+            target: "/".to_string(),
+            version: version.to_string(),
+            headers: connection.map(|c| ("connection".to_string(), c.to_string())).into_iter().collect(),
+        };
+        assert!(request("HTTP/1.1", None).keep_alive());
+        assert!(!request("HTTP/1.1", Some("Close")).keep_alive());
+        assert!(!request("HTTP/1.0", None).keep_alive());
+        assert!(request("HTTP/1.0", Some("keep-alive")).keep_alive());
+    }
+
+    #[test]
+    fn check_content_type() {
+        assert_eq!(content_type(Path::new("index.HTML")), "text/html; charset=utf-8");
+        assert_eq!(content_type(Path::new("logo.png")), "image/png");
+        assert_eq!(content_type(Path::new("Makefile")), "application/octet-stream");
+    }
+
+    #[test]
+    fn check_resolve() {
+        let root = root();
+        assert_eq!(resolve(&root, "/"), Ok(root.join("index.html")));
+        assert_eq!(resolve(&root, "/notes/hello%20world.txt?v=2"), Ok(root.join("notes/hello world.txt")));
+        assert_eq!(resolve(&root, "/missing.html"), Err(Status::NOT_FOUND));
+        assert_eq!(resolve(&root, "/../Cargo.toml"), Err(Status::FORBIDDEN));
+        assert_eq!(resolve(&root, "/%2e%2e/Cargo.toml"), Err(Status::FORBIDDEN));
+        assert_eq!(resolve(&root, "/notes/"), Err(Status::FORBIDDEN));
+        assert_eq!(resolve(&root, "/bad%zz"), Err(Status::BAD_REQUEST));
+        assert_eq!(resolve(&root, "index.html"), Err(Status::BAD_REQUEST));
+    }
+}
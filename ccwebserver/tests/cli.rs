@@ -0,0 +1,81 @@
+use cc_tests::Server;
+use std::io::Write;
+use std::net::TcpStream;
+use std::process::Command;
+use std::thread;
+
+/// A ccwebserver process serving testinputs/www on a free port
+fn start() -> Server {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_ccwebserver"));
+    command.current_dir(env!("CARGO_MANIFEST_DIR")).arg("testinputs/www");
+    Server::start(command)
+}
+
+fn get(server: &Server, path: &str) -> String {
+    server.send(&format!("GET {} HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n", path))
+}
+
+#[test]
+fn run_webserver_index() {
+    let server = start();
+    let response = get(&server, "/");
+    let index = std::fs::read_to_string(concat!(env!("CARGO_MANIFEST_DIR"), "/testinputs/www/index.html")).unwrap();
+    assert_eq!(response, format!(
+        "HTTP/1.1 200 OK\r\nContent-Type: text/html; charset=utf-8\r\nConnection: close\r\nContent-Length: {}\r\n\r\n{}",
+        index.len(),
+        index,
+    ));
+}
+
+#[test]
+fn run_webserver_escaped_path() {
+    let server = start();
+    let response = get(&server, "/notes/hello%20world.txt");
+    assert!(response.starts_with("HTTP/1.1 200 OK\r\nContent-Type: text/plain; charset=utf-8\r\n"), "{}", response);
+    assert!(response.ends_with("Content-Length: 14\r\n\r\nHello, world!\n"), "{}", response);
+}
+
+#[test]
+fn run_webserver_not_found_and_forbidden() {
+    let server = start();
+    assert!(get(&server, "/nope.html").starts_with("HTTP/1.1 404 Not Found\r\n"));
+    assert!(get(&server, "/../Cargo.toml").starts_with("HTTP/1.1 403 Forbidden\r\n"));
+    assert!(get(&server, "/notes/").starts_with("HTTP/1.1 403 Forbidden\r\n"));
+}
+
+#[test]
+fn run_webserver_bad_requests() {
+    let server = start();
+    assert!(server.send("nonsense\r\n\r\n").starts_with("HTTP/1.1 400 Bad Request\r\n"));
+    let response = server.send("DELETE / HTTP/1.1\r\nConnection: close\r\n\r\n");
+    assert!(response.starts_with("HTTP/1.1 405 Method Not Allowed\r\n"), "{}", response);
+    assert!(response.contains("\r\nAllow: GET, HEAD\r\n"), "{}", response);
+}
+
+#[test]
+fn run_webserver_head_and_keep_alive() {
+    let server = start();
+    let response = server.send("HEAD /style.css HTTP/1.1\r\n\r\nGET /style.css HTTP/1.1\r\nConnection: close\r\n\r\n");
+    let head = "HTTP/1.1 200 OK\r\nContent-Type: text/css; charset=utf-8\r\nContent-Length: 22\r\n\r\n";
+    let get = concat!(
+        "HTTP/1.1 200 OK\r\nContent-Type: text/css; charset=utf-8\r\nConnection: close\r\nContent-Length: 22\r\n\r\n",
+        "body { color: #333; }\n",
+    );
+    assert_eq!(response, format!("{}{}", head, get));
+}
+
+#[test]
+fn run_webserver_concurrent_connections() {
+    let server = start();
+
+    // Hold a connection open without finishing its request, which mustn't stop others being served
+    let mut idle = TcpStream::connect(&server.address).unwrap();
+    idle.write_all(b"GET / HTTP/1.1\r\n").unwrap();
+
+    thread::scope(|scope| {
+        let clients: Vec<_> = (0..8).map(|_| scope.spawn(|| get(&server, "/style.css"))).collect();
+        for client in clients {
+            assert!(client.join().unwrap().ends_with("body { color: #333; }\n"));
+        }
+    });
+}
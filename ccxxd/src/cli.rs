@@ -0,0 +1,97 @@
+use crate::{dump, reverse, DumpOptions, MAX_COLUMNS};
+use anyhow::{bail, Context, Result};
+use cc_cli::{is_broken_pipe, BROKEN_PIPE_EXIT_CODE};
+use cc_io::Source;
+use clap::Parser;
+use std::ffi::OsString;
+use std::fs::File;
+use std::io::{stdout, BufWriter, Read, Write};
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+/// xxd - make a hex dump, or turn one back into binary
+#[derive(Parser)]
+struct Cli {
+    /// Bytes per line
+    #[arg(short = 'c', long = "cols", value_name = "COLS", default_value_t = 16)]
+    columns: usize,
+
+    /// Bytes per group of hex digits, 0 for no grouping
+    #[arg(short = 'g', long = "groupsize", value_name = "BYTES", default_value_t = 2)]
+    group_size: usize,
+
+    /// Start this many bytes into the input, which may be given in hex as 0x...
+    #[arg(short = 's', long = "seek", value_name = "OFFSET", value_parser = parse_number, default_value = "0")]
+    seek: u64,
+
+    /// Stop after this many bytes, which may be given in hex as 0x...
+    #[arg(short = 'l', long = "len", value_name = "LEN", value_parser = parse_number)]
+    length: Option<u64>,
+
+    /// Read a hex dump and write the bytes it shows
+    #[arg(short = 'r', long = "revert", conflicts_with_all = ["columns", "group_size", "seek", "length"])]
+    reverse: bool,
+
+    /// The file to read, or - for stdin
+    input: Option<PathBuf>,
+
+    /// The file to write, instead of stdout
+    output: Option<PathBuf>,
+}
+
+fn parse_number(s: &str) -> Result<u64, String> {
+    let parsed = match s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+        Some(hex) => u64::from_str_radix(hex, 16),
+        None => s.parse(),
+    };
+    parsed.map_err(|_| format!("invalid number `{}`", s))
+}
+
+/// Run ccxxd with the given command line, `args[0]` being the program name
+pub fn main<I, T>(args: I) -> ExitCode
+where
+    I: IntoIterator<Item = T>,
+    T: Into<OsString> + Clone,
+{
+    let args: Cli = cc_cli::parse_from(args, env!("CARGO_PKG_VERSION"));
+
+    match run(args) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) if is_broken_pipe(&e) => ExitCode::from(BROKEN_PIPE_EXIT_CODE),
+        Err(e) => {
+            eprintln!("Error: {:?}", e);
+            ExitCode::FAILURE
+        },
+    }
+}
+
+fn run(args: Cli) -> Result<()> {
+    if args.columns == 0 || args.columns > MAX_COLUMNS {
+        bail!("columns must be between 1 and {}", MAX_COLUMNS);
+    }
+    let source = Source::from_arg(args.input.as_deref());
+    let input = cc_io::open_at(&source, if args.reverse { 0 } else { args.seek })?;
+
+    let mut out: Box<dyn Write> = match &args.output {
+        Some(path) => {
+            let file = File::create(path).with_context(|| format!("could not create `{}`", path.display()))?;
+            Box::new(BufWriter::new(file))
+        },
+        None => Box::new(BufWriter::new(stdout().lock())),
+    };
+
+    if args.reverse {
+        return reverse(input.reader, &mut out).with_context(|| format!("could not revert `{}`", source.label()));
+    }
+
+    let options = DumpOptions {
+        columns: args.columns,
+        group_size: args.group_size,
+        offset: args.seek,
+    };
+    let reader: Box<dyn Read> = match args.length {
+        Some(length) => Box::new(input.reader.take(length)),
+        None => input.reader,
+    };
+    dump(reader, &mut out, &options).with_context(|| format!("could not dump `{}`", source.label()))
+}
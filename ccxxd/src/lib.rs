@@ -0,0 +1,194 @@
+// Hex dump in the format of xxd, and back again. Each line gives the offset, the bytes in hex in
+// groups, and the bytes as text with anything unprintable shown as a dot, e.g.
+//
+//   00000000: 4865 6c6c 6f2c 2077 6f72 6c64 210a       Hello, world!.
+use std::io::{self, BufRead, ErrorKind, Read, Write};
+
+mod cli;
+
+pub use cli::main;
+
+/// Most bytes a line can show, the same limit as xxd
+pub const MAX_COLUMNS: usize = 256;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct DumpOptions {
+    /// Bytes per line
+    pub columns: usize,
+    /// Bytes per group of hex digits, or 0 for no grouping
+    pub group_size: usize,
+    /// Offset of the first byte, as shown at the start of the first line
+    pub offset: u64,
+}
+
+impl Default for DumpOptions {
+    fn default() -> DumpOptions {
+        DumpOptions { columns: 16, group_size: 2, offset: 0 }
+    }
+}
+
+/// Read up to `buf.len()` bytes, stopping short only at the end of the input
+fn read_full(reader: &mut impl Read, buf: &mut [u8]) -> io::Result<usize> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match reader.read(&mut buf[filled..]) {
+            Ok(0) => break,
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == ErrorKind::Interrupted => {},
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(filled)
+}
+
+/// Write one line of the dump for `bytes`, which is a full line's worth except at the end
+pub fn write_line(out: &mut impl Write, bytes: &[u8], offset: u64, options: &DumpOptions) -> io::Result<()> {
+    let group_size = match options.group_size {
+        0 => options.columns,
+        n => n,
+    };
+    // The width of a full line's hex, so the text lines up on a short last line
+    let width = options.columns * 2 + (options.columns - 1) / group_size;
+
+    let mut hex = String::with_capacity(width);
+    for (i, byte) in bytes.iter().enumerate() {
+        if i > 0 && i.is_multiple_of(group_size) {
+            hex.push(' ');
+        }
+        hex.push_str(&format!("{:02x}", byte));
+    }
+    let text: String = bytes
+        .iter()
+        .map(|&b| if b == b' ' || b.is_ascii_graphic() { b as char } else { '.' })
+        .collect();
+
+    writeln!(out, "{:08x}: {:<width$}  {}", offset, hex, text, width = width)
+}
+
+/// Dump everything in `reader`
+pub fn dump(mut reader: impl Read, out: &mut impl Write, options: &DumpOptions) -> io::Result<()> {
+    let mut buf = vec![0; options.columns];
+    let mut offset = options.offset;
+    loop {
+        let n = read_full(&mut reader, &mut buf)?;
+        if n == 0 {
+            break;
+        }
+        write_line(out, &buf[..n], offset, options)?;
+        offset += n as u64;
+        if n < buf.len() {
+            break;
+        }
+    }
+    out.flush()
+}
+
+fn invalid(line_number: usize, message: &str) -> io::Error {
+    io::Error::new(ErrorKind::InvalidData, format!("line {}: {}", line_number, message))
+}
+
+/// Turn a dump back into the bytes it shows, putting each line's bytes at its offset. Gaps
+/// between lines, as left by a dump that skipped some input, are filled with zeros.
+pub fn reverse(reader: impl BufRead, out: &mut impl Write) -> io::Result<()> {
+    let mut position = 0;
+    for (i, line) in reader.split(b'\n').enumerate() {
+        let line = line?;
+        let line_number = i + 1;
+        let Some(colon) = line.iter().position(|&b| b == b':') else {
+            // Blank lines are fine, but anything else without an offset isn't part of a dump
+            match line.iter().all(u8::is_ascii_whitespace) {
+                true => continue,
+                false => return Err(invalid(line_number, "expected `OFFSET: HEX`")),
+            }
+        };
+
+        let offset = std::str::from_utf8(&line[..colon])
+            .ok()
+            .and_then(|offset| u64::from_str_radix(offset.trim(), 16).ok())
+            .ok_or_else(|| invalid(line_number, "invalid offset"))?;
+        if offset < position {
+            return Err(invalid(line_number, "offset goes backwards"));
+        }
+        io::copy(&mut io::repeat(0).take(offset - position), out)?;
+        position = offset;
+
+        // The hex ends where the text starts, after two spaces, since groups only have one
+        // between them
+        let rest = &line[colon + 1..];
+        let hex_end = rest.windows(2).position(|pair| pair == b"  ").unwrap_or(rest.len());
+        let digits: Vec<u8> = rest[..hex_end].iter().copied().filter(|b| !b.is_ascii_whitespace()).collect();
+        if !digits.len().is_multiple_of(2) {
+            return Err(invalid(line_number, "odd number of hex digits"));
+        }
+        let mut bytes = Vec::with_capacity(digits.len() / 2);
+        for pair in digits.chunks(2) {
+            let byte = std::str::from_utf8(pair).ok().and_then(|pair| u8::from_str_radix(pair, 16).ok());
+            bytes.push(byte.ok_or_else(|| invalid(line_number, "invalid hex digit"))?);
+        }
+        out.write_all(&bytes)?;
+        position += bytes.len() as u64;
+    }
+    out.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hexdump(input: &[u8], options: &DumpOptions) -> String {
+        let mut out = Vec::new();
+        dump(input, &mut out, options).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    fn unhex(dump: &str) -> Vec<u8> {
+        let mut out = Vec::new();
+        reverse(dump.as_bytes(), &mut out).unwrap();
+        out
+    }
+
+    #[test]
+    fn check_dump() {
+        assert_eq!(
+            hexdump(b"Hello, world!\nHe", &DumpOptions::default()),
+            "00000000: 4865 6c6c 6f2c 2077 6f72 6c64 210a 4865  Hello, world!.He\n",
+        );
+        assert_eq!(hexdump(b"", &DumpOptions::default()), "");
+    }
+
+    #[test]
+    fn check_dump_short_last_line() {
+        let options = DumpOptions { columns: 3, offset: 2, ..DumpOptions::default() };
+        assert_eq!(hexdump(b"cdef", &options), "00000002: 6364 65  cde\n00000005: 66       f\n");
+    }
+
+    #[test]
+    fn check_dump_groups() {
+        let options = DumpOptions { group_size: 3, ..DumpOptions::default() };
+        assert_eq!(hexdump(b"abcdefg", &options), format!("00000000: 616263 646566 67{}  abcdefg\n", " ".repeat(21)));
+        let options = DumpOptions { columns: 4, group_size: 0, ..DumpOptions::default() };
+        assert_eq!(hexdump(b"\x00\x7f ~", &options), "00000000: 007f207e  .. ~\n");
+    }
+
+    #[test]
+    fn check_reverse_round_trip() {
+        let input: Vec<u8> = (0..=255).chain(b"  two spaces  ".iter().copied()).collect();
+        for options in [DumpOptions::default(), DumpOptions { columns: 7, group_size: 4, offset: 0 }] {
+            assert_eq!(unhex(&hexdump(&input, &options)), input);
+        }
+    }
+
+    #[test]
+    fn check_reverse_fills_gaps() {
+        assert_eq!(unhex("00000002: 6162  ab\n\n00000006: 63  c\n"), b"\0\0ab\0\0c");
+    }
+
+    #[test]
+    fn check_reverse_invalid() {
+        let mut out = Vec::new();
+        let err = reverse(&b"00000000: 6162  ab\nnonsense\n"[..], &mut out).unwrap_err();
+        assert_eq!(err.to_string(), "line 2: expected `OFFSET: HEX`");
+        assert!(reverse(&b"00000000: 616  a\n"[..], &mut out).is_err());
+        assert!(reverse(&b"00000004: 61  a\n00000000: 61  a\n"[..], &mut out).is_err());
+    }
+}
@@ -0,0 +1,43 @@
+use assert_cmd::Command;
+
+fn ccxxd() -> Command {
+    let mut cmd = Command::cargo_bin("ccxxd").unwrap();
+    cmd.current_dir(env!("CARGO_MANIFEST_DIR"));
+    cmd
+}
+
+const FOX_DUMP: &str = concat!(
+    "00000000: 5468 6520 7175 6963 6b20 6272 6f77 6e20  The quick brown \n",
+    "00000010: 666f 780a 6a75 6d70 7320 6f76 6572 0974  fox.jumps over.t\n",
+    "00000020: 6865 206c 617a 7920 646f 672e 0a         he lazy dog..\n",
+);
+
+#[test]
+fn run_xxd_file() {
+    ccxxd().arg("testinputs/fox.txt").assert().success().stdout(FOX_DUMP);
+}
+
+#[test]
+fn run_xxd_columns_seek_length() {
+    ccxxd()
+        .args(["-c", "8", "-s", "0x4", "-l", "13", "testinputs/fox.txt"])
+        .assert()
+        .success()
+        .stdout("00000004: 7175 6963 6b20 6272  quick br\n0000000c: 6f77 6e20 66         own f\n");
+}
+
+#[test]
+fn run_xxd_stdin_seek() {
+    ccxxd().args(["-s", "2"]).write_stdin("abcd").assert().success().stdout("00000002: 6364                                     cd\n");
+}
+
+#[test]
+fn run_xxd_reverse() {
+    let expected = std::fs::read(concat!(env!("CARGO_MANIFEST_DIR"), "/testinputs/fox.txt")).unwrap();
+    ccxxd().arg("-r").write_stdin(FOX_DUMP).assert().success().stdout(expected);
+}
+
+#[test]
+fn run_xxd_rejects_bad_columns() {
+    ccxxd().args(["-c", "0", "testinputs/fox.txt"]).assert().failure();
+}
@@ -0,0 +1,119 @@
+// Integration tests across the tools.
+// Owns the challenge test corpora under testinputs, the JSON_checker suite and the wc test file,
+// and runs the tools' binaries against them on their own and piped into one another, the way
+// they'd be used from a shell. The servers' own tests start them through Server.
+// Reference:  http://www.json.org/JSON_checker/
+use assert_cmd::assert::Assert;
+use assert_cmd::Command;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{Shutdown, TcpStream};
+use std::process::{Child, Stdio};
+use std::sync::Once;
+
+/// The packages with a binary of the workspace's, each binary named after its package
+pub const TOOLS: &[&str] = &[
+    "cc2jsonparser",
+    "ccbase64",
+    "ccbox",
+    "cccalc",
+    "cccat",
+    "cccut",
+    "ccdiff",
+    "ccdns",
+    "ccgrep",
+    "cchuffman",
+    "ccirc",
+    "ccjq",
+    "ccmemcached",
+    "ccratelimiter",
+    "ccredis",
+    "ccshell",
+    "ccsort",
+    "ccuniq",
+    "ccurl",
+    "ccwc",
+    "ccwebserver",
+    "ccxxd",
+];
+
+/// A command running one of the tools from the testinputs directory
+pub fn tool(name: &str) -> Command {
+    build_tools();
+    let mut cmd = Command::cargo_bin(name).unwrap();
+    cmd.current_dir(concat!(env!("CARGO_MANIFEST_DIR"), "/testinputs"));
+    cmd
+}
+
+/// Run each command line with its output as the next one's input, the first reading `input`,
+/// and give the last one's result. Every command before the last has to succeed.
+pub fn pipeline(input: &[u8], commands: &[&[&str]]) -> Assert {
+    let (last, rest) = commands.split_last().expect("a pipeline needs a command");
+    let mut input = input.to_vec();
+    for words in rest {
+        let output = command_line(words).write_stdin(input).output().unwrap();
+        assert!(output.status.success(), "{} failed: {}", words[0], String::from_utf8_lossy(&output.stderr));
+        input = output.stdout;
+    }
+    command_line(last).write_stdin(input).assert()
+}
+
+/// A command for the words of a command line, the first being the tool's name
+fn command_line(words: &[&str]) -> Command {
+    let mut cmd = tool(words[0]);
+    cmd.args(&words[1..]);
+    cmd
+}
+
+/// Build the tools, which cargo only does by itself when testing the whole workspace
+fn build_tools() {
+    static BUILD: Once = Once::new();
+    BUILD.call_once(|| {
+        let mut cargo = std::process::Command::new(env!("CARGO"));
+        cargo.args(["build", "--quiet"]);
+        for package in TOOLS {
+            cargo.args(["--package", package]);
+        }
+        if !cfg!(debug_assertions) {
+            cargo.arg("--release");
+        }
+        assert!(cargo.status().unwrap().success(), "could not build the tools");
+    });
+}
+
+/// A server process listening on a free port, killed when dropped
+pub struct Server {
+    child: Child,
+    /// Where it's listening, e.g. `127.0.0.1:37019`
+    pub address: String,
+}
+
+impl Server {
+    /// Run the command with `--port 0` added, and wait for it to say on stderr where it's
+    /// listening, the address or a URL of it being the last word of the first line
+    pub fn start(mut command: std::process::Command) -> Server {
+        let mut child = command.args(["--port", "0"]).stderr(Stdio::piped()).spawn().unwrap();
+
+        let mut line = String::new();
+        BufReader::new(child.stderr.as_mut().unwrap()).read_line(&mut line).unwrap();
+        let url = line.trim().rsplit(' ').next().unwrap();
+        let address = url.trim_start_matches("http://").trim_end_matches('/').to_string();
+        Server { child, address }
+    }
+
+    /// Send raw request bytes, close our side, and return everything the server sent back
+    pub fn send(&self, request: &str) -> String {
+        let mut stream = TcpStream::connect(&self.address).unwrap();
+        stream.write_all(request.as_bytes()).unwrap();
+        stream.shutdown(Shutdown::Write).unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        response
+    }
+}
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
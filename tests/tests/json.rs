@@ -0,0 +1,619 @@
+use cc_tests::{pipeline, tool};
+use predicates::prelude::*;
+use std::time::Duration;
+
+#[test]
+fn run_json_steps_valid() {
+    for file in ["step1/valid.json", "step2/valid.json", "step2/valid2.json", "step3/valid.json", "step4/valid.json", "step4/valid2.json"] {
+        tool("cc2jsonparser").arg(format!("json/{}", file)).assert().success().code(0);
+    }
+}
+
+#[test]
+fn run_json_steps_invalid() {
+    for file in ["step1/invalid.json", "step2/invalid.json", "step2/invalid2.json", "step3/invalid.json", "step4/invalid.json"] {
+        tool("cc2jsonparser").arg(format!("json/{}", file)).assert().failure().code(1);
+    }
+}
+
+#[test]
+fn run_json_reports_error_position() {
+    tool("cc2jsonparser").arg("json/step2/invalid.json").assert().failure().code(1)
+        .stderr(predicate::str::contains("expected a string for the object key, found `}`").and(predicate::str::contains("json/step2/invalid.json:1:17")));
+}
+
+#[test]
+fn run_json_checker_pass() {
+    for n in 1..=3 {
+        tool("cc2jsonparser").arg(format!("json/checker/pass{}.json", n)).assert().success().stdout("Parse successful\n");
+    }
+}
+
+#[test]
+fn run_json_checker_fail() {
+    // JSON_checker's own limits rather than the RFC's, which allows any value at the top level
+    // and leaves nesting depth up to the parser
+    let accepted = [1, 18];
+    for n in (1..=33).filter(|n| !accepted.contains(n)) {
+        tool("cc2jsonparser").arg(format!("json/checker/fail{}.json", n)).assert().failure().code(1);
+    }
+}
+
+#[test]
+fn run_json_max_depth() {
+    // fail18.json nests 20 deep
+    tool("cc2jsonparser").args(["--max-depth", "20", "json/checker/fail18.json"]).assert().success();
+    tool("cc2jsonparser").args(["--max-depth", "19", "json/checker/fail18.json"]).assert().failure().code(1)
+        .stderr(predicate::str::contains("maximum depth exceeded, `[` nests deeper than 19 levels"));
+    let deep = format!("{}{}", "[".repeat(100_000), "]".repeat(100_000));
+    tool("cc2jsonparser").arg("-").write_stdin(deep.clone()).assert().failure().code(1)
+        .stderr(predicate::str::contains("maximum depth exceeded, `[` nests deeper than 512 levels"));
+    tool("cc2jsonparser").args(["--max-depth", "0", "-"]).write_stdin(deep).assert().success()
+        .stdout("Parse successful\n");
+    // Sorting goes as deep too
+    let deep = format!("{}0{}", "{\"b\":[],\"a\":".repeat(100_000), "}".repeat(100_000));
+    let sorted = format!("{}0{}\n", "{\"a\":".repeat(100_000), ",\"b\":[]}".repeat(100_000));
+    tool("cc2jsonparser").args(["--max-depth", "0", "--compact", "--sort-keys", "-"]).write_stdin(deep).assert().success()
+        .stdout(sorted);
+}
+
+#[test]
+fn run_json_whitespace_and_encoding() {
+    tool("cc2jsonparser").arg("-").write_stdin("{\r\n\t\"a\": [1,\t2]\r\n}\r\n").assert().success();
+    tool("cc2jsonparser").arg("-").write_stdin("[\"a\nb\"]").assert().failure().code(1)
+        .stderr(predicate::str::contains("unterminated string").and(predicate::str::contains("<stdin>:1:2")));
+    tool("cc2jsonparser").arg("-").write_stdin(b"[\"caf\xe9\"]".to_vec()).assert().failure().code(1)
+        .stderr(predicate::str::contains("invalid UTF-8, found byte 0xE9").and(predicate::str::contains("<stdin>:1:6")));
+}
+
+#[test]
+fn run_json_bom() {
+    tool("cc2jsonparser").arg("-").write_stdin("\u{feff}{\"a\": 1}").assert().success()
+        .stdout("Parse successful\n")
+        .stderr(predicate::str::contains("warning: skipped the byte order mark at the start of the input"));
+    tool("cc2jsonparser").args(["--strict", "-"]).write_stdin("\u{feff}{\"a\": 1}").assert().failure().code(1)
+        .stderr(predicate::str::contains("error: byte order mark at the start of the input\n --> <stdin>:1:1"));
+    tool("cc2jsonparser").args(["--strict", "json/step2/valid.json"]).assert().success().stderr("");
+}
+
+#[test]
+fn run_json_utf16_and_utf32() {
+    let utf16le = |text: &str| text.encode_utf16().flat_map(u16::to_le_bytes).collect::<Vec<u8>>();
+    tool("cc2jsonparser").args(["--compact", "-"]).write_stdin(utf16le("{\"caf\u{e9}\": [1, \"\u{1f600}\"]}")).assert().success()
+        .stdout("{\"caf\u{e9}\":[1,\"\u{1f600}\"]}\n").stderr("");
+    tool("cc2jsonparser").arg("-").write_stdin(utf16le("\u{feff}[true]")).assert().success()
+        .stderr(predicate::str::contains("warning: skipped the byte order mark at the start of the input"));
+    let utf32be: Vec<u8> = "[null]".chars().flat_map(|c| (c as u32).to_be_bytes()).collect();
+    tool("cc2jsonparser").args(["--compact", "-"]).write_stdin(utf32be).assert().success().stdout("[null]\n");
+    // An explicit encoding isn't second-guessed
+    tool("cc2jsonparser").args(["--encoding", "utf-8", "-"]).write_stdin(utf16le("[1]")).assert().failure().code(1);
+    tool("cc2jsonparser").args(["--encoding", "utf-16be", "-"]).write_stdin(utf16le("[1]")).assert().failure().code(1)
+        .stderr(predicate::str::contains("error: illegal character `\u{5b00}`\n --> <stdin>:1:1"));
+    tool("cc2jsonparser").args(["--encoding", "utf-16le", "-"]).write_stdin(b"[\x00\x00\xd8]\x00".to_vec()).assert().failure().code(1)
+        .stderr(predicate::str::contains("invalid UTF-16, unpaired surrogate 0xD800"));
+    tool("cc2jsonparser").args(["--encoding", "latin1", "-"]).assert().failure().code(2)
+        .stderr(predicate::str::contains("unknown encoding `latin1`"));
+}
+
+#[test]
+fn run_json_several_files() {
+    tool("cc2jsonparser").args(["json/step1/valid.json", "json/step2/invalid.json", "json/missing.json", "-"]).write_stdin("").assert().failure().code(1)
+        .stdout("json/step1/valid.json: OK\n\
+            json/step2/invalid.json: error at 1:17: expected a string for the object key, found `}`\n\
+            json/missing.json: error: could not read `json/missing.json`: No such file or directory (os error 2)\n\
+            <stdin>: error: did not find anything to parse\n");
+    tool("cc2jsonparser").args(["json/step1/valid.json", "json/step2/valid.json"]).assert().success()
+        .stdout("json/step1/valid.json: OK\njson/step2/valid.json: OK\n").stderr("");
+    tool("cc2jsonparser").args(["--all-errors", "json/step4/invalid.json", "json/step1/valid.json"]).assert().failure().code(1)
+        .stdout(predicate::str::contains("json/step4/invalid.json: error at 7:13: illegal character `'`\n"))
+        .stdout(predicate::str::contains("json/step4/invalid.json: error at 7:19: illegal character `v`\n"))
+        .stdout(predicate::str::ends_with("json/step1/valid.json: OK\n"));
+    // A repeated key is only an error if it's denied
+    tool("cc2jsonparser").args(["-", "json/step1/valid.json"]).write_stdin("{\"a\": 1, \"a\": 2}").assert().success()
+        .stdout("<stdin>: OK\njson/step1/valid.json: OK\n")
+        .stderr(predicate::str::contains("warning: duplicate key `a`"));
+    tool("cc2jsonparser").args(["--deny-duplicate-keys", "-", "json/step1/valid.json"]).write_stdin("{\"a\": 1, \"a\": 2}").assert().failure().code(1)
+        .stdout("<stdin>: error at 1:10: duplicate key `a`\njson/step1/valid.json: OK\n");
+    tool("cc2jsonparser").args(["--pretty", "json/step1/valid.json", "json/step2/valid.json"]).assert().failure().code(2)
+        .stderr(predicate::str::contains("cannot be used with more than one file"));
+}
+
+#[test]
+fn run_json_recursive() {
+    tool("cc2jsonparser").args(["-r", "json/step1", "--recursive", "json/step3"]).assert().failure().code(1)
+        .stdout("json/step1/invalid.json: error: did not find anything to parse\n\
+            json/step3/invalid.json: error at 3:11: illegal character `F`\n\
+            2 valid, 2 invalid\n\
+            Invalid:\n  json/step1/invalid.json\n  json/step3/invalid.json\n");
+    tool("cc2jsonparser").args(["-r", "json", "--include", "valid*.json"]).assert().success()
+        .stdout(predicate::str::ends_with("6 valid, 0 invalid\n"));
+    tool("cc2jsonparser").args(["-r", "json/missing"]).assert().failure().code(1)
+        .stderr(predicate::str::contains("could not read `json/missing`"));
+    tool("cc2jsonparser").args(["--pretty", "-r", "json/step1"]).assert().failure().code(2);
+}
+
+#[test]
+fn run_json_jobs() {
+    // The files are printed about in the order given however many are checked at once
+    let files: Vec<String> = (1..=33).map(|n| format!("json/checker/fail{}.json", n)).chain((1..=3).map(|n| format!("json/checker/pass{}.json", n))).collect();
+    let output = tool("cc2jsonparser").args(["--jobs", "1"]).args(&files).output().unwrap();
+    let lines = String::from_utf8(output.stdout).unwrap();
+    let names: Vec<&str> = lines.lines().map(|line| line.split(": ").next().unwrap()).collect();
+    assert_eq!(names, files);
+    for jobs in ["0", "4", "64"] {
+        tool("cc2jsonparser").args(["-j", jobs]).args(&files).assert().failure().code(1).stdout(lines.clone());
+    }
+    tool("cc2jsonparser").args(["-j", "3", "-r", "json/step2"]).assert().failure().code(1)
+        .stdout(predicate::str::starts_with("json/step2/invalid.json: error"));
+}
+
+#[test]
+fn run_json_glob() {
+    tool("cc2jsonparser").arg("json/step[12]/*.json").assert().failure().code(1)
+        .stdout("json/step1/invalid.json: error: did not find anything to parse\n\
+            json/step1/valid.json: OK\n\
+            json/step2/invalid.json: error at 1:17: expected a string for the object key, found `}`\n\
+            json/step2/invalid2.json: error at 3:3: illegal character `k`\n\
+            json/step2/valid.json: OK\n\
+            json/step2/valid2.json: OK\n");
+    tool("cc2jsonparser").args(["json/**/valid*.json", "json/checker/pass1.json"]).assert().success()
+        .stdout(predicate::str::starts_with("json/step1/valid.json: OK\n").and(predicate::str::ends_with("json/checker/pass1.json: OK\n")));
+    // A pattern matching a single file is checked like that file on its own
+    tool("cc2jsonparser").arg("json/step1/v*.json").assert().success().stdout("Parse successful\n");
+    tool("cc2jsonparser").arg("json/*.yaml").assert().failure().code(1)
+        .stderr(predicate::str::contains("could not read `json/*.yaml`"));
+}
+
+#[test]
+fn run_json_stdin_by_default() {
+    tool("cc2jsonparser").write_stdin("{\"a\": [1, 2]}").assert().success().stdout("Parse successful\n");
+    tool("cc2jsonparser").arg("--compact").write_stdin("{\"a\": [1, 2]}").assert().success().stdout("{\"a\":[1,2]}\n");
+    tool("cc2jsonparser").write_stdin("[1,]").assert().failure().code(1)
+        .stdout("Parse failed\n").stderr(predicate::str::contains("<stdin>:1:4"));
+    // Only when there's nothing else to read
+    tool("cc2jsonparser").args(["-r", "json/step1"]).write_stdin("[1]").assert().failure().code(1)
+        .stdout(predicate::str::contains("<stdin>").not());
+}
+
+#[test]
+fn run_json_quiet() {
+    tool("cc2jsonparser").args(["-q", "json/step1/valid.json"]).assert().success().stdout("").stderr("");
+    tool("cc2jsonparser").arg("--quiet").write_stdin("\u{feff}{\"a\": 1, \"a\": 2}").assert().success().stdout("").stderr("");
+    // Errors are still said
+    tool("cc2jsonparser").args(["-q", "json/step2/invalid.json"]).assert().failure().code(1)
+        .stdout("").stderr(predicate::str::contains("error: expected a string for the object key"));
+    tool("cc2jsonparser").args(["-q", "--deny-duplicate-keys"]).write_stdin("{\"a\": 1, \"a\": 2}").assert().failure().code(1)
+        .stdout("").stderr(predicate::str::contains("error: duplicate key `a`"));
+    tool("cc2jsonparser").args(["-q", "-r", "json/step2"]).assert().failure().code(1)
+        .stdout("json/step2/invalid.json: error at 1:17: expected a string for the object key, found `}`\n\
+            json/step2/invalid2.json: error at 3:3: illegal character `k`\n");
+    tool("cc2jsonparser").args(["-q", "json/step1/valid.json", "json/step2/valid.json"]).assert().success().stdout("");
+    tool("cc2jsonparser").args(["-q", "--pretty", "json/step1/valid.json"]).assert().failure().code(2);
+}
+
+#[test]
+fn run_json_ndjson() {
+    let records = "{\"level\": \"info\", \"n\": 1}\n{\"level\": \"warn\", \"n\": 2}\n\n[3]\n";
+    tool("cc2jsonparser").arg("--ndjson").write_stdin(records).assert().success().stdout("Parse successful\n");
+    tool("cc2jsonparser").write_stdin(records).assert().failure().code(1)
+        .stderr(predicate::str::contains("error: expected the end of input after the value, found `{`\n --> <stdin>:2:1"));
+    tool("cc2jsonparser").arg("--ndjson").write_stdin("{\"n\": 1}\n{\"n\": }\n{\"n\": 3}\n{\"n\" 4}\n").assert().failure().code(1)
+        .stdout("Parse failed\n")
+        .stderr(predicate::str::contains("error: expected a value, found `}`\n --> <stdin>:2:7"))
+        .stderr(predicate::str::contains("error: expected `:` after the object key, found number `4`\n --> <stdin>:4:6"));
+    tool("cc2jsonparser").args(["--ndjson", "--format", "json"]).write_stdin("[1]\n[2,]\n").assert().failure().code(1)
+        .stdout(predicate::str::contains(r#""line":2,"column":4"#));
+    tool("cc2jsonparser").args(["--ndjson", "--pretty"]).write_stdin("[1]\n").assert().failure().code(2);
+}
+
+#[test]
+fn run_json_comments() {
+    let settings = "// editor settings\n{\n  \"tabSize\": 2, // spaces\n  /* \"wrap\": true, */\n  \"files\": [\"a\"]\n}\n";
+    tool("cc2jsonparser").arg("--allow-comments").write_stdin(settings).assert().success().stdout("Parse successful\n");
+    tool("cc2jsonparser").write_stdin(settings).assert().failure().code(1)
+        .stderr(predicate::str::contains("error: illegal character `/`\n --> <stdin>:1:1"));
+    tool("cc2jsonparser").args(["--allow-comments", "--all-errors"]).write_stdin("[1, /* two\n").assert().failure().code(1)
+        .stderr(predicate::str::contains("error: unterminated comment\n --> <stdin>:1:5"));
+    // Written without them unless they're kept
+    tool("cc2jsonparser").args(["--allow-comments", "--pretty"]).write_stdin(settings).assert().success()
+        .stdout("{\n  \"tabSize\": 2,\n  \"files\": [\n    \"a\"\n  ]\n}\n");
+    tool("cc2jsonparser").args(["--allow-comments", "--pretty", "--keep-comments", "--indent", "4"]).write_stdin(settings).assert().success()
+        .stdout("// editor settings\n{\n    \"tabSize\": 2, // spaces\n    /* \"wrap\": true, */\n    \"files\": [\n        \"a\"\n    ]\n}\n");
+    tool("cc2jsonparser").args(["--pretty", "--keep-comments"]).write_stdin(settings).assert().failure().code(2);
+}
+
+#[test]
+fn run_json_trailing_commas() {
+    let config = "{\n  \"name\": \"app\",\n  \"ports\": [80, 443,],\n}\n";
+    tool("cc2jsonparser").write_stdin(config).assert().failure().code(1)
+        .stderr(predicate::str::contains("error: expected a value, found `]`\n --> <stdin>:3:21"));
+    tool("cc2jsonparser").arg("--allow-trailing-commas").write_stdin(config).assert().success()
+        .stdout("Parse successful\n")
+        .stderr(predicate::str::contains("warning: trailing comma after the last array element\n --> <stdin>:3:20"))
+        .stderr(predicate::str::contains("warning: trailing comma after the last object member\n --> <stdin>:3:22"));
+    tool("cc2jsonparser").args(["--lenient", "--compact"]).write_stdin(config).assert().success()
+        .stdout("{\"name\":\"app\",\"ports\":[80,443]}\n");
+    tool("cc2jsonparser").args(["--lenient", "-q"]).write_stdin(config).assert().success().stdout("").stderr("");
+    // Not an error in a report either
+    tool("cc2jsonparser").args(["--lenient", "--format", "json"]).write_stdin(config).assert().success()
+        .stdout(predicate::str::contains(r#""valid":true"#));
+}
+
+#[test]
+fn run_json_json5() {
+    let config = "// JSON5\n{\n  name: 'app',\n  port: 0x1F90,\n  ratio: +.5,\n  limit: Infinity,\n  motd: 'one \\\ntwo',\n}\n";
+    tool("cc2jsonparser").write_stdin(config).assert().failure().code(1);
+    tool("cc2jsonparser").arg("--json5").write_stdin(config).assert().success()
+        .stdout("Parse successful\n")
+        .stderr("");
+    tool("cc2jsonparser").args(["--json5", "--compact"]).write_stdin(config).assert().success()
+        .stdout("{\"name\":\"app\",\"port\":8080,\"ratio\":0.5,\"limit\":null,\"motd\":\"one two\"}\n");
+    tool("cc2jsonparser").arg("--json5").write_stdin("{a: 0x}").assert().failure().code(1)
+        .stderr(predicate::str::contains("error: invalid number `0x`: expected a hex digit after `0x`\n --> <stdin>:1:5"));
+}
+
+#[test]
+fn run_json_to_yaml() {
+    let config = r#"{"name": "app", "replicas": 2, "ports": [80, 443], "env": {"DEBUG": "false"}}"#;
+    tool("cc2jsonparser").args(["--to", "yaml"]).write_stdin(config).assert().success()
+        .stdout("name: app\nreplicas: 2\nports:\n  - 80\n  - 443\nenv:\n  DEBUG: \"false\"\n");
+    tool("cc2jsonparser").args(["--to", "yaml", "--path", "$.ports[*]"]).write_stdin(config).assert().success()
+        .stdout("80\n---\n443\n");
+    tool("cc2jsonparser").args(["--to", "yml"]).write_stdin(config).assert().failure().code(2)
+        .stderr(predicate::str::contains("unknown format `yml`, expected yaml"));
+    tool("cc2jsonparser").args(["--to", "yaml"]).write_stdin("[1,]").assert().failure().code(1);
+}
+
+#[test]
+fn run_json_to_toml() {
+    let config = r#"{"name": "app", "ports": [80, 443], "db": {"host": "localhost", "pool": 5}}"#;
+    tool("cc2jsonparser").args(["--to", "toml"]).write_stdin(config).assert().success()
+        .stdout("name = \"app\"\nports = [80, 443]\n\n[db]\nhost = \"localhost\"\npool = 5\n");
+    tool("cc2jsonparser").args(["--to", "toml", "--pointer", "/db"]).write_stdin(config).assert().success()
+        .stdout("host = \"localhost\"\npool = 5\n");
+    tool("cc2jsonparser").args(["--to", "toml"]).write_stdin(r#"[{"a": 1}, "b"]"#).assert().failure().code(1)
+        .stdout("")
+        .stderr(predicate::str::contains("error: a TOML document is a table, so only an object can be written as one, not an array"));
+    tool("cc2jsonparser").args(["--to", "toml"]).write_stdin(r#"{"db": {"password": null}}"#).assert().failure().code(1)
+        .stderr(predicate::str::contains("error: `db.password` is null, which TOML has no way to write"));
+}
+
+#[test]
+fn run_json_to_csv() {
+    let dump = r#"{"users": [{"id": 1, "name": "Ann"}, {"id": 2, "name": "Bo, Jr", "email": "bo@example.com"}]}"#;
+    tool("cc2jsonparser").args(["--to", "csv", "--pointer", "/users"]).write_stdin(dump).assert().success()
+        .stdout("id,name,email\r\n1,Ann,\r\n2,\"Bo, Jr\",bo@example.com\r\n");
+    tool("cc2jsonparser").args(["--to", "csv"]).write_stdin(dump).assert().failure().code(1)
+        .stderr(predicate::str::contains("error: CSV is a table of rows, so only an array of objects can be written as it, not an object"));
+    tool("cc2jsonparser").args(["--to", "csv", "--path", "$.users[*]"]).write_stdin(dump).assert().failure().code(1)
+        .stderr(predicate::str::contains("error: a CSV file has one document in it, but 2 values were picked out"));
+}
+
+#[test]
+fn run_json_msgpack() {
+    let payload: &[u8] = b"\x82\xa4name\xa3app\xa5ports\x92\x50\xcd\x01\xbb";
+    tool("cc2jsonparser").args(["--from", "msgpack"]).write_stdin(payload).assert().success()
+        .stdout("Parse successful\n");
+    tool("cc2jsonparser").args(["--from", "msgpack", "--compact"]).write_stdin(payload).assert().success()
+        .stdout("{\"name\":\"app\",\"ports\":[80,443]}\n");
+    tool("cc2jsonparser").args(["--to", "msgpack"]).write_stdin(r#"{"name": "app", "ports": [80, 443]}"#).assert().success()
+        .stdout(payload);
+    tool("cc2jsonparser").args(["--from", "msgpack"]).write_stdin(&payload[..9]).assert().failure().code(1)
+        .stderr(predicate::str::contains("could not decode `<stdin>`: the input ends partway through a string at byte 6"));
+}
+
+#[test]
+fn run_json_cbor() {
+    let payload: &[u8] = b"\xa2\x64name\x63app\x65ports\x82\x18\x50\x19\x01\xbb";
+    tool("cc2jsonparser").args(["--from", "cbor"]).write_stdin(payload).assert().success()
+        .stdout("Parse successful\n");
+    tool("cc2jsonparser").args(["--to", "cbor"]).write_stdin(r#"{"name": "app", "ports": [80, 443]}"#).assert().success()
+        .stdout(payload);
+    // A COSE header, with integer keys and a byte string
+    tool("cc2jsonparser").args(["--from", "cbor", "--compact"]).write_stdin(&b"\xa2\x01\x26\x04\x42\x11\x22"[..]).assert().success()
+        .stdout("{\"1\":-7,\"4\":\"ESI\"}\n");
+    tool("cc2jsonparser").args(["--from", "cbor"]).write_stdin(&payload[..4]).assert().failure().code(1)
+        .stderr(predicate::str::contains("could not decode `<stdin>`: the input ends partway through a value at byte 1"));
+}
+
+#[test]
+fn run_json_canonicalize() {
+    tool("cc2jsonparser").arg("--canonicalize").write_stdin("{\"b\": [1.50, 1E30, \"\\u00e9\"],\n \"a\": {\"z\": null, \"\\ud83d\\ude00\": true, \"\\uffff\": false}}").assert().success()
+        .stdout("{\"a\":{\"z\":null,\"😀\":true,\"\u{ffff}\":false},\"b\":[1.5,1e+30,\"é\"]}");
+    tool("cc2jsonparser").args(["--canonicalize", "--path", "$[*]"]).write_stdin(r#"[{"y": 1, "x": 2}, 0.10]"#).assert().success()
+        .stdout("{\"x\":2,\"y\":1}\n0.1");
+    tool("cc2jsonparser").arg("--canonicalize").write_stdin(r#"{"a": 1, "a": 2}"#).assert().failure().code(1)
+        .stderr(predicate::str::contains("error: the document has the key `a` more than once, which canonical JSON doesn't allow"));
+    tool("cc2jsonparser").args(["--canonicalize", "--pretty"]).write_stdin("{}").assert().failure().code(2);
+}
+
+#[test]
+fn run_json_diff() {
+    let changed = r#"{"key5": 101.0, "key4": "other", "key2": false, "key1": true, "key6": [1]}"#;
+    tool("cc2jsonparser").args(["diff", "json/step3/valid.json", "-"]).write_stdin(changed).assert().failure().code(1)
+        .stdout("- /key3: null\n~ /key4: \"value\" -> \"other\"\n+ /key6: [1]\n");
+    tool("cc2jsonparser").args(["diff", "json/step3/valid.json", "-", "--json-patch"]).write_stdin(changed).assert().failure().code(1)
+        .stdout(predicate::str::starts_with("[\n  {\n    \"op\": \"remove\",\n    \"path\": \"/key3\"\n  },\n"));
+    let reordered = "{\"key5\": 1.01e2, \"key4\": \"value\",\n\"key3\": null, \"key2\": false, \"key1\": true}";
+    tool("cc2jsonparser").args(["diff", "-", "json/step3/valid.json"]).write_stdin(reordered).assert().success().stdout("");
+    tool("cc2jsonparser").args(["diff", "json/step2/invalid.json", "-"]).write_stdin("{}").assert().failure().code(2)
+        .stderr(predicate::str::contains("json/step2/invalid.json:"));
+}
+
+#[test]
+fn run_json_merge_patch() {
+    let patch = r#"{"key2": null, "key4": {"nested": "value"}, "key6": [1]}"#;
+    tool("cc2jsonparser").args(["merge-patch", "json/step3/valid.json", "-", "--compact"]).write_stdin(patch).assert().success()
+        .stdout("{\"key1\":true,\"key3\":null,\"key4\":{\"nested\":\"value\"},\"key5\":101,\"key6\":[1]}\n");
+    tool("cc2jsonparser").args(["merge-patch", "-", "json/step2/valid2.json"]).write_stdin("[1, 2]").assert().success()
+        .stdout("{\n  \"key\": \"value\",\n  \"key2\": \"value\"\n}\n");
+    tool("cc2jsonparser").args(["merge-patch", "json/step3/valid.json", "-"]).write_stdin("{").assert().failure().code(2)
+        .stdout("")
+        .stderr(predicate::str::contains("<stdin>:1:2"));
+}
+
+#[test]
+fn run_json_patch() {
+    let patch = r#"[{"op": "test", "path": "/key5", "value": 101}, {"op": "move", "from": "/key4", "path": "/key6"}, {"op": "remove", "path": "/key3"}]"#;
+    tool("cc2jsonparser").args(["patch", "json/step3/valid.json", "-", "--compact"]).write_stdin(patch).assert().success()
+        .stdout("{\"key1\":true,\"key2\":false,\"key5\":101,\"key6\":\"value\"}\n");
+    tool("cc2jsonparser").args(["patch", "json/step3/valid.json", "-"]).write_stdin(r#"[{"op": "test", "path": "/key5", "value": 100}]"#).assert().failure().code(1)
+        .stdout("")
+        .stderr(predicate::str::contains("error: the test failed, `/key5` isn't the value it expects in operation 0\n --> <stdin>"));
+    // What diff --json-patch writes turns the one document into the other
+    let patched = tool("cc2jsonparser").args(["diff", "json/step3/valid.json", "json/step2/valid2.json", "--json-patch"]).output().unwrap().stdout;
+    tool("cc2jsonparser").args(["patch", "json/step3/valid.json", "-"]).write_stdin(patched).assert().success()
+        .stdout("{\n  \"key2\": \"value\",\n  \"key\": \"value\"\n}\n");
+}
+
+#[test]
+fn run_json_flatten() {
+    tool("cc2jsonparser").args(["--flatten", "json/step3/valid.json"]).assert().success()
+        .stdout("key1 = true\nkey2 = false\nkey3 = null\nkey4 = \"value\"\nkey5 = 101\n");
+    tool("cc2jsonparser").args(["--flatten", "--filter", ".user"]).write_stdin(r#"{"user": {"addresses": [{"city": "Oslo"}], "first name": "Ann"}}"#).assert().success()
+        .stdout("addresses[0].city = \"Oslo\"\n[\"first name\"] = \"Ann\"\n");
+    tool("cc2jsonparser").args(["--flatten", "--compact"]).write_stdin("{}").assert().failure().code(2);
+}
+
+#[test]
+fn run_json_stats() {
+    tool("cc2jsonparser").args(["--stats", "json/step3/valid.json"]).assert().success()
+        .stdout("objects: 1\narrays: 0\nstrings: 1\nnumbers: 1\nbooleans: 2\nnulls: 1\nkeys: 5\nmax depth: 1\nlongest string: 5 characters, `/key4`\nlongest array: none\n");
+    tool("cc2jsonparser").arg("--stats").write_stdin(r#"{"items": [[1, 2], [3]], "id": "abc"}"#).assert().success()
+        .stdout(predicate::str::contains("max depth: 3\nlongest string: 3 characters, `/id`\nlongest array: 2 elements, `/items/0`\n"));
+    tool("cc2jsonparser").arg("--stats").write_stdin("[1, 2").assert().failure().code(1)
+        .stdout("Parse failed\n");
+}
+
+#[test]
+fn run_json_repair() {
+    tool("cc2jsonparser").args(["--repair", "--compact", "--color", "never"]).write_stdin("{name: 'app', tags: ['a',],\n  deps: [1").assert().success()
+        .stdout("{\"name\":\"app\",\"tags\":[\"a\"],\"deps\":[1]}\n")
+        .stderr(predicate::str::contains("note: removed the trailing comma\n --> <stdin>:1:25\n"))
+        .stderr(predicate::str::contains("note: closed the array left open at the end\n --> <stdin>:2:9\n"));
+    tool("cc2jsonparser").args(["--repair", "json/step3/valid.json"]).assert().success()
+        .stdout(predicate::str::starts_with("{\n  \"key1\": true,\n")).stderr("");
+    tool("cc2jsonparser").arg("--repair").write_stdin("[1 2]").assert().failure().code(1)
+        .stdout("Parse failed\n");
+}
+
+#[test]
+fn run_json_watch() {
+    let path = std::env::temp_dir().join(format!("cc2jsonparser-watch-{}.json", std::process::id()));
+    std::fs::write(&path, r#"{"a": 1}"#).unwrap();
+    // Built before the file's changed, so the first check is of it as it was
+    let mut watch = tool("cc2jsonparser");
+    let changer = {
+        let path = path.clone();
+        std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(700));
+            std::fs::write(path, r#"{"a": }"#).unwrap();
+        })
+    };
+    let watched = watch.arg("--watch").arg(&path).timeout(Duration::from_millis(1800)).assert();
+    changer.join().unwrap();
+    std::fs::remove_file(&path).unwrap();
+    watched.stdout(predicate::str::is_match(r"^\[\d\d:\d\d:\d\d\] \S+: OK\n\[\d\d:\d\d:\d\d\] \S+: error at 1:7: expected a value, found `\}`\n$").unwrap());
+    tool("cc2jsonparser").args(["--watch", "-"]).write_stdin("{}").assert().failure().code(2);
+}
+
+#[test]
+fn run_json_watch_sees_a_change_keeping_the_size_and_time() {
+    let path = std::env::temp_dir().join(format!("cc2jsonparser-watch-same-{}.json", std::process::id()));
+    std::fs::write(&path, r#"{"a": 1}"#).unwrap();
+    let modified = std::fs::metadata(&path).unwrap().modified().unwrap();
+    let mut watch = tool("cc2jsonparser");
+    let changer = {
+        let path = path.clone();
+        std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(700));
+            std::fs::write(&path, r#"{"a": } "#).unwrap();
+            std::fs::File::options().write(true).open(&path).unwrap().set_modified(modified).unwrap();
+        })
+    };
+    let watched = watch.arg("--watch").arg(&path).timeout(Duration::from_millis(1800)).assert();
+    changer.join().unwrap();
+    std::fs::remove_file(&path).unwrap();
+    watched.stdout(predicate::str::is_match(r"^\[\d\d:\d\d:\d\d\] \S+: OK\n\[\d\d:\d\d:\d\d\] \S+: error at 1:7: expected a value, found `\}`\n$").unwrap());
+}
+
+#[test]
+fn run_json_repl() {
+    tool("cc2jsonparser").args(["--repl", "--color", "never"]).write_stdin("{\"a\":\n [1, 2]}\n:filter .a[]\n{\"a\": [3]}\n[1 2]\n").assert().success()
+        .stdout("{\n  \"a\": [\n    1,\n    2\n  ]\n}\n3\nerror: expected `,` or `]` after the array element, found number `2`\n --> snippet:1:4\n  |\n1 | [1 2]\n  |    ^\n");
+    tool("cc2jsonparser").args(["--repl", "json/step3/valid.json"]).assert().failure().code(2);
+}
+
+#[test]
+fn run_json_bignum() {
+    let input = r#"{"id": 340282366920938463463374607431768211455, "pi": 3.14159265358979323846}"#;
+    tool("cc2jsonparser").args(["--compact", "--color", "never"]).write_stdin(input).assert().success()
+        .stdout("{\"id\":340282366920938463463374607431768211455,\"pi\":3.14159265358979323846}\n")
+        .stderr(predicate::str::contains("warning: integer `340282366920938463463374607431768211455` is too big for 64 bits, so it's read as the float `3.402823669209385e38`\n --> <stdin>:1:8\n"))
+        .stderr(predicate::str::contains("warning: number `3.14159265358979323846` has more digits than a float keeps, so it's read as `3.141592653589793`\n --> <stdin>:1:55\n"));
+    tool("cc2jsonparser").args(["--bignum", "--color", "never"]).write_stdin(input).assert().success()
+        .stdout("Parse successful\n")
+        .stderr(predicate::str::contains("integer").not().and(predicate::str::contains("3.14159265358979323846")));
+}
+
+#[test]
+fn run_json_deep_output() {
+    // Writing nests as deep as parsing does, without running out of stack
+    let deep = format!("{}{}", "[".repeat(100_000), "]".repeat(100_000));
+    for args in [&["--compact"][..], &["--canonicalize"], &["--flatten"], &["--to", "cbor"], &["--to", "msgpack"]] {
+        tool("cc2jsonparser").args(["--max-depth", "0"]).args(args).write_stdin(deep.as_str()).assert().success();
+    }
+    let deep = format!("{}{}", "[".repeat(10_000), "]".repeat(10_000));
+    for args in [&["--pretty"][..], &["--to", "yaml"]] {
+        tool("cc2jsonparser").args(["--max-depth", "0"]).args(args).write_stdin(deep.as_str()).assert().success();
+    }
+}
+
+#[test]
+fn run_json_error_quotes_the_file() {
+    tool("cc2jsonparser").arg("json/step4/invalid.json").assert().failure().code(1)
+        .stderr(predicate::str::contains(" --> json/step4/invalid.json:7:13\n  |\n7 |   \"key-l\": ['list value']\n  |             ^^^^^\n"));
+    tool("cc2jsonparser").args(["--all-errors", "json/step4/invalid.json"]).assert().failure()
+        .stderr(predicate::str::contains("7 |   \"key-l\": ['list value']\n"));
+}
+
+#[test]
+fn run_json_from_cat() {
+    pipeline(b"", &[&["cccat", "json/checker/pass1.json"], &["cc2jsonparser", "-"]]).success()
+        .stdout(predicate::str::ends_with("Parse successful\n"));
+    pipeline(b"[1, 2,]", &[&["cccat"], &["cc2jsonparser", "-"]]).failure().code(1);
+}
+
+#[test]
+fn run_json_into_jq() {
+    pipeline(b"", &[&["cccat", "json/checker/pass1.json"], &["ccjq", "-r", ".[8].jsontext"]]).success()
+        .stdout("{\"object with 1 member\":[\"array with 1 element\"]}\n");
+    pipeline(b"[{\"a\": 1}, {\"a\": [2]}]", &[&["ccjq", "-c", ".[]"], &["ccjq", "--ndjson", "-c", ".a"]]).success().stdout("1\n[2]\n");
+}
+
+#[test]
+fn run_json_all_errors() {
+    tool("cc2jsonparser").args(["--all-errors", "json/step4/invalid.json"]).assert().failure().code(1)
+        .stderr(predicate::str::contains("json/step4/invalid.json:7:13"));
+    tool("cc2jsonparser").args(["--all-errors", "-"]).write_stdin("[1,,\n{\"a\" 2}, tru]").assert().failure().code(1)
+        .stdout(predicate::str::contains("Parse failed"))
+        .stderr(predicate::str::contains("error: expected a value, found `,`\n --> <stdin>:1:4"))
+        .stderr(predicate::str::contains("error: expected `:` after the object key, found number `2`\n --> <stdin>:2:6"))
+        .stderr(predicate::str::contains("error: expected `true`, found `tru`\n --> <stdin>:2:10"));
+    tool("cc2jsonparser").args(["--all-errors", "json/checker/pass1.json"]).assert().success().stdout("Parse successful\n");
+}
+
+#[test]
+fn run_json_pretty() {
+    tool("cc2jsonparser").args(["--pretty", "json/step3/valid.json"]).assert().success()
+        .stdout(predicate::str::starts_with("{\n  \"key1\": true,\n"));
+    tool("cc2jsonparser").args(["--pretty", "--indent", "4", "-"]).write_stdin("{\"a\":[1,{}],\"b\":\"x\\ty\"}").assert().success()
+        .stdout("{\n    \"a\": [\n        1,\n        {}\n    ],\n    \"b\": \"x\\ty\"\n}\n");
+    tool("cc2jsonparser").args(["--pretty", "--indent-tab", "-"]).write_stdin("[[]]").assert().success().stdout("[\n\t[]\n]\n");
+    tool("cc2jsonparser").args(["--pretty", "-"]).write_stdin("[1,]").assert().failure().code(1).stdout("Parse failed\n");
+    tool("cc2jsonparser").args(["--indent", "4", "json/step1/valid.json"]).assert().failure().code(2);
+}
+
+#[test]
+fn run_json_compact() {
+    tool("cc2jsonparser").args(["--compact", "json/step3/valid.json"]).assert().success()
+        .stdout("{\"key1\":true,\"key2\":false,\"key3\":null,\"key4\":\"value\",\"key5\":101}\n");
+    tool("cc2jsonparser").args(["--compact", "-"]).write_stdin("[ 1,\n  { \"a b\" : [ ] } ]").assert().success()
+        .stdout("[1,{\"a b\":[]}]\n");
+    tool("cc2jsonparser").args(["--compact", "--pretty", "json/step1/valid.json"]).assert().failure().code(2);
+}
+
+#[test]
+fn run_json_sort_keys() {
+    tool("cc2jsonparser").args(["--compact", "--sort-keys", "-"]).write_stdin(r#"{"b": [{"y": 1, "x": 2}], "a": null}"#)
+        .assert().success().stdout("{\"a\":null,\"b\":[{\"x\":2,\"y\":1}]}\n");
+    tool("cc2jsonparser").args(["--pretty", "--sort-keys", "-"]).write_stdin(r#"{"b": 1, "a": 2}"#)
+        .assert().success().stdout("{\n  \"a\": 2,\n  \"b\": 1\n}\n");
+    tool("cc2jsonparser").args(["--sort-keys", "json/step1/valid.json"]).assert().failure().code(2);
+}
+
+#[test]
+fn run_json_color() {
+    tool("cc2jsonparser").args(["--compact", "--color", "always", "-"]).write_stdin(r#"{"a": [1, "x", null]}"#)
+        .assert().success()
+        .stdout("{\x1b[1;34m\"a\"\x1b[0m:[\x1b[36m1\x1b[0m,\x1b[32m\"x\"\x1b[0m,\x1b[1;33mnull\x1b[0m]}\n");
+    // Piped, so auto leaves it plain
+    tool("cc2jsonparser").args(["--compact", "-"]).write_stdin("[true]").assert().success().stdout("[true]\n");
+    tool("cc2jsonparser").args(["--color", "always", "-"]).write_stdin("[1,]").assert().failure()
+        .stderr(predicate::str::contains("\x1b[1;31merror\x1b[0m"));
+}
+
+#[test]
+fn run_json_filter() {
+    let input = r#"{"items": [{"id": 1, "tags": ["a"]}, {"id": 2, "tags": []}], "name": "list"}"#;
+    tool("cc2jsonparser").args(["--filter", ".items[] | .id, .tags[0]", "-"]).write_stdin(input).assert().success()
+        .stdout("1\n\"a\"\n2\nnull\n");
+    tool("cc2jsonparser").args(["--filter", ".items[0]", "-"]).write_stdin(input).assert().success()
+        .stdout("{\n  \"id\": 1,\n  \"tags\": [\n    \"a\"\n  ]\n}\n");
+    tool("cc2jsonparser").args(["--filter", ".items", "--compact", "-"]).write_stdin(input).assert().success()
+        .stdout("[{\"id\":1,\"tags\":[\"a\"]},{\"id\":2,\"tags\":[]}]\n");
+    tool("cc2jsonparser").args(["--filter", ".name.first", "-"]).write_stdin(input).assert().failure().code(1)
+        .stderr(predicate::str::contains("cannot index a string with \"first\""));
+    tool("cc2jsonparser").args(["--filter", ".items[", "-"]).write_stdin(input).assert().failure().code(2)
+        .stderr(predicate::str::contains("expected an index, a key, or `]` after `[` at column 8 of the filter"));
+}
+
+#[test]
+fn run_json_path() {
+    let input = r#"{"store": {"book": [{"author": "Rees", "price": 8.95}, {"author": "Waugh", "price": 12.99}], "bicycle": {"price": 399}}}"#;
+    tool("cc2jsonparser").args(["--path", "$.store.book[*].author", "-"]).write_stdin(input).assert().success()
+        .stdout("\"Rees\"\n\"Waugh\"\n");
+    tool("cc2jsonparser").args(["--path", "$..price", "-"]).write_stdin(input).assert().success()
+        .stdout("8.95\n12.99\n399\n");
+    tool("cc2jsonparser").args(["--path", "$.store.bicycle", "--pretty", "-"]).write_stdin(input).assert().success()
+        .stdout("{\n  \"price\": 399\n}\n");
+    tool("cc2jsonparser").args(["--path", "$.store.car", "-"]).write_stdin(input).assert().success().stdout("");
+    tool("cc2jsonparser").args(["--path", "store", "-"]).write_stdin(input).assert().failure().code(2)
+        .stderr(predicate::str::contains("expected the query to start with `$` at column 1 of the query"));
+}
+
+#[test]
+fn run_json_pointer() {
+    let input = r#"{"items": [{"name": "a/b", "tags": ["x"]}], "m~n": 1}"#;
+    tool("cc2jsonparser").args(["--pointer", "/items/0", "-"]).write_stdin(input).assert().success()
+        .stdout("{\n  \"name\": \"a/b\",\n  \"tags\": [\n    \"x\"\n  ]\n}\n");
+    tool("cc2jsonparser").args(["--pointer", "/items/0/tags", "--compact", "-"]).write_stdin(input).assert().success()
+        .stdout("[\"x\"]\n");
+    tool("cc2jsonparser").args(["--pointer", "/m~0n", "-"]).write_stdin(input).assert().success().stdout("1\n");
+    tool("cc2jsonparser").args(["--pointer", "/items/1", "-"]).write_stdin(input).assert().failure().code(3)
+        .stdout("").stderr(predicate::str::contains("nothing at pointer `/items/1`"));
+    tool("cc2jsonparser").args(["--pointer", "items", "-"]).write_stdin(input).assert().failure().code(2)
+        .stderr(predicate::str::contains("pointer `items` must be empty or start with `/`"));
+}
+
+#[test]
+fn run_json_schema() {
+    let schema = "json/schema/person.schema.json";
+    tool("cc2jsonparser").args(["--schema", schema, "-"]).write_stdin(r#"{"name": "Ann", "age": 30}"#).assert().success()
+        .stdout("Parse successful\nMatches the schema\n");
+    tool("cc2jsonparser").args(["--schema", schema, "--color", "never", "-"]).write_stdin(r#"{"age": -1}"#).assert().failure().code(1)
+        .stdout("Parse successful\nDoes not match the schema\n")
+        .stderr(predicate::str::contains("error: missing required property `name`\n --> <stdin>\n  = at the root of the document\n  = at `/required` in the schema\n"))
+        .stderr(predicate::str::contains("error: expected at least 0, found -1\n --> <stdin>\n  = at `/age` in the document\n  = at `/properties/age/minimum` in the schema\n"));
+    tool("cc2jsonparser").args(["--schema", schema, "--format", "json", "-"]).write_stdin(r#"{"name": 1}"#).assert().failure().code(1)
+        .stdout(predicate::str::contains(r#""results":[{"input":"<stdin>","valid":false}]"#))
+        .stdout(predicate::str::contains(r#""message":"expected string, found number at `/name` in the document, at `/properties/name/type` in the schema"}"#));
+    tool("cc2jsonparser").args(["--schema", "json/schema/unsupported.schema.json", "-"]).write_stdin("{}").assert().failure().code(2)
+        .stderr(predicate::str::contains("not a schema this can check with: `anyOf` isn't supported at `/anyOf` in the schema"));
+}
+
+#[test]
+fn run_json_duplicate_keys() {
+    let input = "{\"a\": 1,\n \"a\": 2}";
+    let diagnostic = " --> <stdin>:2:2\n  = `a` is first written at line 1, column 2\n";
+    tool("cc2jsonparser").args(["--color", "never", "-"]).write_stdin(input).assert().success()
+        .stdout("Parse successful\n").stderr(format!("warning: duplicate key `a`\n{}", diagnostic));
+    tool("cc2jsonparser").args(["--deny-duplicate-keys", "--color", "never", "-"]).write_stdin(input).assert().failure().code(1)
+        .stdout("Parse failed\n").stderr(format!("error: duplicate key `a`\n{}", diagnostic));
+    tool("cc2jsonparser").args(["--deny-duplicate-keys", "--compact", "-"]).write_stdin(input).assert().failure().code(1).stdout("");
+    tool("cc2jsonparser").args(["--deny-duplicate-keys", "--all-errors", "-"]).write_stdin(input).assert().failure().code(1)
+        .stderr(predicate::str::contains("error: duplicate key `a`"));
+    tool("cc2jsonparser").args(["--deny-duplicate-keys", "--format", "json", "-"]).write_stdin(input).assert().failure().code(1)
+        .stdout(predicate::str::contains(r#""errors":[{"input":"<stdin>","message":"duplicate key `a`","line":2,"column":2}]"#));
+    tool("cc2jsonparser").args(["--deny-duplicate-keys", "-"]).write_stdin(r#"{"a": {"a": 1}}"#).assert().success();
+}
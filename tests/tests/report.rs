@@ -0,0 +1,95 @@
+use cc_tests::tool;
+use serde_json::{json, Value};
+
+/// The --format json report a tool writes for these arguments, and whether it succeeded
+fn run_report(name: &str, args: &[&str]) -> (Value, bool) {
+    let output = tool(name).args(["--format", "json"]).args(args).output().unwrap();
+    let mut report: Value = serde_json::from_slice(&output.stdout).unwrap();
+    // What every tool's report has, whatever the tool
+    assert_eq!(report["tool"], name);
+    assert!(report["version"].is_string());
+    assert!(report["timing"]["elapsed_ms"].is_number());
+    report.as_object_mut().unwrap().retain(|key, _| ["inputs", "results", "errors"].contains(&key.as_str()));
+    (report, output.status.success())
+}
+
+#[test]
+fn run_report_wc() {
+    let (report, success) = run_report("ccwc", &["-l", "wc/test.txt"]);
+    assert!(success);
+    assert_eq!(report["inputs"], json!(["wc/test.txt"]));
+    assert_eq!(report["results"][0]["input"], "wc/test.txt");
+    assert_eq!(report["results"][0]["lines"], 7145);
+    assert_eq!(report["results"][0]["bytes"], 342190);
+    assert_eq!(report["errors"], json!([]));
+
+    let (report, success) = run_report("ccwc", &["wc/test.txt", "wc/missing.txt"]);
+    assert!(!success);
+    assert_eq!(report["results"].as_array().unwrap().len(), 1);
+    assert_eq!(report["errors"][0]["message"], "could not read `wc/missing.txt`: No such file or directory (os error 2)");
+}
+
+#[test]
+fn run_report_json() {
+    let (report, success) = run_report("cc2jsonparser", &["json/step2/invalid.json"]);
+    assert!(!success);
+    assert_eq!(report, json!({
+        "inputs": ["json/step2/invalid.json"],
+        "results": [{"input": "json/step2/invalid.json", "valid": false}],
+        "errors": [{
+            "input": "json/step2/invalid.json",
+            "message": "expected a string for the object key, found `}`",
+            "line": 1,
+            "column": 17,
+        }],
+    }));
+
+    let (report, success) = run_report("cc2jsonparser", &["json/checker/pass1.json"]);
+    assert!(success);
+    assert_eq!(report["results"], json!([{"input": "json/checker/pass1.json", "valid": true}]));
+    assert_eq!(report["errors"], json!([]));
+
+    let (report, success) = run_report("cc2jsonparser", &["json/checker/pass1.json", "json/step2/invalid.json", "json/missing.json"]);
+    assert!(!success);
+    assert_eq!(report["inputs"], json!(["json/checker/pass1.json", "json/step2/invalid.json", "json/missing.json"]));
+    assert_eq!(report["results"], json!([
+        {"input": "json/checker/pass1.json", "valid": true},
+        {"input": "json/step2/invalid.json", "valid": false},
+    ]));
+    assert_eq!(report["errors"][1]["message"], "could not read `json/missing.json`: No such file or directory (os error 2)");
+
+    // --output is the same as --format
+    let output = tool("cc2jsonparser").args(["--output", "json", "--all-errors", "json/step4/invalid.json"]).output().unwrap();
+    assert!(!output.status.success());
+    let report: Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(report["results"], json!([{"input": "json/step4/invalid.json", "valid": false}]));
+    assert_eq!(report["errors"][1], json!({"input": "json/step4/invalid.json", "message": "illegal character `v`", "line": 7, "column": 19}));
+}
+
+#[test]
+fn run_report_sarif() {
+    let output = tool("cc2jsonparser").args(["--output", "sarif", "json/step2/invalid.json", "json/step1/valid.json"]).output().unwrap();
+    assert!(!output.status.success());
+    let log: Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(log["version"], "2.1.0");
+    assert_eq!(log["runs"][0]["tool"]["driver"]["name"], "cc2jsonparser");
+    assert_eq!(log["runs"][0]["results"], json!([{
+        "ruleId": "syntax",
+        "level": "error",
+        "message": {"text": "expected a string for the object key, found `}`"},
+        "locations": [{"physicalLocation": {
+            "artifactLocation": {"uri": "json/step2/invalid.json"},
+            "region": {"startLine": 1, "startColumn": 17, "byteOffset": 16, "byteLength": 1},
+        }}],
+    }]));
+
+    let output = tool("cc2jsonparser").args(["--format", "sarif", "--deny-duplicate-keys", "-"]).write_stdin("{\"a\": 1, \"a\": 2}").output().unwrap();
+    let log: Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(log["runs"][0]["results"][0]["ruleId"], "duplicate-key");
+    assert!(log["runs"][0]["results"][0].get("locations").is_none());
+
+    let output = tool("cc2jsonparser").args(["--format", "sarif", "json/checker/pass1.json"]).output().unwrap();
+    assert!(output.status.success());
+    let log: Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(log["runs"][0]["results"], json!([]));
+}
@@ -0,0 +1,13 @@
+use cc_tests::{tool, TOOLS};
+use predicates::prelude::*;
+
+#[test]
+fn run_every_tool_version_and_man_page() {
+    // ccbox hands its arguments to the tool it's asked for, so it's only run through one
+    for name in TOOLS.iter().filter(|name| **name != "ccbox") {
+        tool(name).arg("--version").assert().success().stdout(predicate::str::starts_with(format!("{} 0.1.0 (", name)));
+        tool(name).arg("man").assert().success()
+            .stdout(predicate::str::starts_with(format!(".TH {} 1 ", name.to_uppercase())));
+    }
+    tool("ccbox").args(["ccwc", "--version"]).assert().success().stdout(predicate::str::starts_with("ccwc 0.1.0 ("));
+}
@@ -0,0 +1,38 @@
+use cc_tests::{pipeline, tool};
+
+#[test]
+fn run_wc_counts() {
+    tool("ccwc").arg("wc/test.txt").assert().success().stdout("    7145 58164 339292 wc/test.txt\n");
+    tool("ccwc").args(["-c", "wc/test.txt"]).assert().success().stdout("    342190 wc/test.txt\n");
+    tool("ccwc").args(["-l", "wc/test.txt"]).assert().success().stdout("    7145 wc/test.txt\n");
+    tool("ccwc").args(["-w", "wc/test.txt"]).assert().success().stdout("    58164 wc/test.txt\n");
+    tool("ccwc").args(["-m", "wc/test.txt"]).assert().success().stdout("    339292 wc/test.txt\n");
+}
+
+#[test]
+fn run_wc_from_cat() {
+    pipeline(b"", &[&["cccat", "wc/test.txt"], &["ccwc", "-l"]]).success().stdout("    7145 \n");
+}
+
+#[test]
+fn run_wc_on_jsonparser_output() {
+    pipeline(b"", &[&["cc2jsonparser", "json/checker/pass1.json"], &["ccwc", "-l"]]).success().stdout("    1 \n");
+}
+
+#[test]
+fn run_wc_state_counts_a_last_line_still_being_written() {
+    let dir = std::env::temp_dir().join(format!("cc-tests-wc-state-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let (log, state) = (dir.join("log.txt"), dir.join("state.json"));
+    let run = || {
+        let output = tool("ccwc").arg("--state").arg(&state).arg(&log).output().unwrap();
+        assert!(output.status.success());
+        String::from_utf8(output.stdout).unwrap().split_whitespace().take(3).collect::<Vec<_>>().join(" ")
+    };
+    std::fs::write(&log, "a b\nc").unwrap();
+    assert_eq!(run(), "2 3 5");
+    assert_eq!(run(), "2 3 5");
+    std::fs::write(&log, "a b\nc d\ne\n").unwrap();
+    assert_eq!(run(), "3 5 10");
+    std::fs::remove_dir_all(&dir).unwrap();
+}